@@ -3,15 +3,20 @@ use crate::common::CliError;
 use anyhow::anyhow;
 use capstone::{
     arch::arm::ArchMode as armArchMode, arch::arm64::ArchMode as aarch64ArchMode,
+    arch::mips::ArchMode as mipsArchMode, arch::riscv::ArchExtraMode as riscvArchExtraMode,
     arch::riscv::ArchMode as riscvArchMode, prelude::*, Capstone, Endian,
 };
 use num_traits::Num;
 use probe_rs::{
     architecture::arm::Dump,
-    debug::{debug_info::DebugInfo, registers::Registers, stack_frame::StackFrame, VariableName},
-    Core, CoreType, InstructionSet, MemoryInterface, RegisterDescription, RegisterId,
+    debug::{
+        debug_info::DebugInfo, registers::Registers, stack_frame::StackFrame, ExpressionValue,
+        VariableName,
+    },
+    Core, CoreDump, CoreType, InstructionSet, MemoryInterface, RegisterDescription, RegisterId,
 };
 use std::fs::File;
+use std::path::Path;
 use std::{io::prelude::*, time::Duration};
 
 use parse_int::parse;
@@ -91,6 +96,17 @@ impl DebugCli {
                         .mode(riscvArchMode::RiscV32)
                         .endian(Endian::Little)
                         .build(),
+                    InstructionSet::RV32C => Capstone::new()
+                        .riscv()
+                        .mode(riscvArchMode::RiscV32)
+                        .extra_mode(std::iter::once(riscvArchExtraMode::RiscVC))
+                        .endian(Endian::Little)
+                        .build(),
+                    InstructionSet::Mips32 => Capstone::new()
+                        .mips()
+                        .mode(mipsArchMode::Mips32)
+                        .endian(Endian::Little)
+                        .build(),
                 }
                 .map_err(|err| anyhow!("Error creating capstone: {:?}", err))?;
 
@@ -139,7 +155,7 @@ impl DebugCli {
 
                     if cli_data.core.architecture() == probe_rs::Architecture::Arm {
                         match cli_data.core.core_type() {
-                            CoreType::Armv6m | CoreType::Armv7em | CoreType::Armv7m | CoreType::Armv8m | CoreType::Armv7a | CoreType::Armv8a => {
+                            CoreType::Armv6m | CoreType::Armv7em | CoreType::Armv7m | CoreType::Armv8m | CoreType::Armv7a | CoreType::Armv7r | CoreType::Armv8a => {
                                 // Unwrap is safe here because ARM always defines this register
                                 let psr_desc = cli_data.core.registers().psr().unwrap();
 
@@ -561,6 +577,59 @@ impl DebugCli {
             },
         });
 
+        cli.add_command(Command {
+            name: "print",
+            help_text: "Evaluate an expression, e.g. `config.flags & 0x4`, `buf[i].len` or `*ptr`",
+
+            function: |cli_data, args| {
+                if args.is_empty() {
+                    return Err(CliError::MissingArgument);
+                }
+
+                match cli_data.state {
+                    DebugState::Halted(ref mut halted_state) => {
+                        let current_frame =
+                            if let Some(current_frame) = halted_state.get_current_frame_mut() {
+                                current_frame
+                            } else {
+                                println!("StackFrame not found.");
+                                return Ok(CliState::Continue);
+                            };
+
+                        let debug_info = if let Some(debug_info) = &cli_data.debug_info {
+                            debug_info
+                        } else {
+                            println!("No debug information available.");
+                            return Ok(CliState::Continue);
+                        };
+
+                        match debug_info.evaluate_expression(
+                            &mut cli_data.core,
+                            current_frame,
+                            &args.join(" "),
+                        ) {
+                            Ok(value) => match &value {
+                                ExpressionValue::Variable(variable) => println!(
+                                    "{}: {} = {}",
+                                    variable.name,
+                                    variable.type_name,
+                                    value.value(current_frame)
+                                ),
+                                ExpressionValue::Integer(integer) if *integer >= 0 => {
+                                    println!("{} ({:#x})", integer, integer)
+                                }
+                                ExpressionValue::Integer(integer) => println!("{}", integer),
+                            },
+                            Err(error) => println!("Failed to evaluate expression: {}", error),
+                        }
+                    }
+                    DebugState::Running => println!("Core must be halted for this command."),
+                }
+
+                Ok(CliState::Continue)
+            },
+        });
+
         cli.add_command(Command {
             name: "up",
             help_text: "Move up a frame",
@@ -644,6 +713,46 @@ impl DebugCli {
             },
         });
 
+        cli.add_command(Command {
+            name: "coredump",
+            help_text: "Store the registers and memory as an ELF core file (coredump <file> [<address> <size>]...)",
+
+            function: |cli_data, args| {
+                let path = match args.first() {
+                    Some(path) => Path::new(path),
+                    None => return Err(CliError::MissingArgument),
+                };
+
+                let mut ranges = Vec::new();
+                for index in (1..args.len()).step_by(2) {
+                    let address: u64 = get_int_argument(args, index)?;
+                    let size: u64 = get_int_argument(args, index + 1)?;
+                    ranges.push(address..address + size);
+                }
+
+                // Without ranges, dump the stack up to the end of the memory region which contains it.
+                if ranges.is_empty() {
+                    let stack_pointer = cli_data.core.registers().stack_pointer();
+                    let sp: u64 = cli_data.core.read_core_reg(stack_pointer)?;
+
+                    match cli_data.core.memory_region(sp) {
+                        Some(region) => ranges.push(sp..region.range.end),
+                        None => println!(
+                            "The stack pointer {:#010x} is not in a known memory region.",
+                            sp
+                        ),
+                    }
+                }
+
+                let core_dump = CoreDump::capture(&mut cli_data.core, &ranges)?;
+                core_dump.store(path)?;
+
+                println!("Stored the core dump in {}", path.display());
+
+                Ok(CliState::Continue)
+            },
+        });
+
         cli.add_command(Command {
             name: "reset",
 
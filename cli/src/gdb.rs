@@ -1,3 +1,4 @@
+use std::path::Path;
 use std::time::Duration;
 
 use probe_rs_cli_util::common_options::ProbeOptions;
@@ -6,6 +7,7 @@ pub fn run_gdb_server(
     common: ProbeOptions,
     connection_string: Option<&str>,
     reset_halt: bool,
+    elf: Option<&Path>,
 ) -> anyhow::Result<()> {
     let mut session = common.simple_attach()?;
 
@@ -17,11 +19,15 @@ pub fn run_gdb_server(
 
     let gdb_connection_string = connection_string.unwrap_or("localhost:1337");
 
-    let instances = probe_rs_gdb_server::GdbInstanceConfiguration::from_session(
+    let mut instances = probe_rs_gdb_server::GdbInstanceConfiguration::from_session(
         &session,
         Some(gdb_connection_string.to_owned()),
     );
 
+    for instance in instances.iter_mut() {
+        instance.elf = elf.map(Path::to_path_buf);
+    }
+
     for instance in instances.iter() {
         println!(
             "Firing up GDB stub for {:?} cores at {:?}",
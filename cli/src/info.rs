@@ -20,6 +20,16 @@ use probe_rs_cli_util::common_options::ProbeOptions;
 pub(crate) fn show_info_of_device(common: &ProbeOptions) -> Result<()> {
     let mut probe = common.attach_probe()?;
 
+    if let Some(firmware_version) = probe.firmware_version() {
+        println!("Probe firmware version: {}", firmware_version);
+    }
+
+    match probe.get_target_voltage() {
+        Ok(Some(voltage)) => println!("Target voltage: {:.2} V", voltage),
+        Ok(None) => (),
+        Err(e) => log::warn!("Error reading the target voltage: {}", e),
+    }
+
     let protocols = if let Some(protocol) = common.protocol {
         vec![protocol]
     } else {
@@ -2,13 +2,19 @@ mod common;
 mod debugger;
 mod gdb;
 mod info;
+mod rtt_server;
 mod run;
+mod symbolicate;
 
 use debugger::CliState;
 
 use probe_rs::{
+    config::TargetSelector,
     debug::debug_info::DebugInfo,
-    flashing::{erase_all, BinOptions, FileDownloadError, Format},
+    flashing::{
+        download_files_with_esp_rom_loader, erase_all, erase_all_with_sector_cache, BinOptions,
+        DownloadOptions, FileDownloadError, Format, SectorCache,
+    },
     MemoryInterface, Probe,
 };
 
@@ -16,7 +22,7 @@ use probe_rs_cli_util::{
     clap,
     clap::Parser,
     common_options::{print_chip_info, print_families, CargoOptions, FlashOptions, ProbeOptions},
-    flash::run_flash_download,
+    flash::{run_flash_download, sector_cache_key},
 };
 
 use rustyline::Editor;
@@ -66,8 +72,29 @@ enum Cli {
             help = "Use this flag to reset and halt (instead of just a halt) the attached core after attaching to the target."
         )]
         reset_halt: bool,
+
+        #[structopt(long, parse(from_os_str))]
+        /// The ELF file of the program, to report the threads of its RTOS to GDB
+        elf: Option<PathBuf>,
+
+        #[clap(flatten)]
+        common: ProbeOptions,
+    },
+    /// Expose RTT channels as TCP sockets, like SEGGER's RTT server
+    RttServer {
+        #[clap(flatten)]
+        shared: CoreOptions,
+
         #[clap(flatten)]
         common: ProbeOptions,
+
+        /// The address of channel 0. Channel n is available on the port of this address plus n.
+        #[clap(long, default_value = "localhost:19021")]
+        bind: String,
+
+        /// The numbers of the channels to expose. All channels are exposed if none are given.
+        #[clap(long = "channel")]
+        channels: Vec<usize>,
     },
     /// Basic command line debugger
     Debug {
@@ -126,11 +153,24 @@ enum Cli {
         /// Disable double-buffering when downloading flash.  If downloading times out, try this option.
         #[structopt(long = "disable-double-buffering")]
         disable_double_buffering: bool,
+
+        /// Skip data which is outside of the memory map of the target, instead of aborting the download.
+        #[structopt(long)]
+        skip_invalid_data: bool,
+
+        /// Program an Espressif chip through the serial bootloader in its ROM, reached through
+        /// this serial port, instead of a debug probe. Requires `--chip`.
+        #[structopt(long)]
+        esp_rom_port: Option<String>,
     },
     /// Erase all nonvolatile memory of attached target
     Erase {
         #[structopt(flatten)]
         common: ProbeOptions,
+
+        /// The sector cache of the flashing commands, which is discarded for the erased target
+        #[structopt(long = "sector-cache", parse(from_os_str))]
+        sector_cache: Option<PathBuf>,
     },
     /// Flash and run an ELF program
     #[structopt(name = "run")]
@@ -148,6 +188,10 @@ enum Cli {
         /// Disable double-buffering when downloading flash.  If downloading times out, try this option.
         #[structopt(long = "disable-double-buffering")]
         disable_double_buffering: bool,
+
+        /// Also show the output of the probe's virtual COM port, opened with the given baud rate
+        #[structopt(long = "vcom-baud")]
+        vcom_baud: Option<u32>,
     },
     /// Trace a memory location on the target
     #[structopt(name = "trace")]
@@ -162,6 +206,15 @@ enum Cli {
         #[structopt(parse(try_from_str = parse_u64))]
         loc: u64,
     },
+    /// Print the backtrace of a dump created by the `dump` or `coredump` command of the debugger
+    Symbolicate {
+        /// The path to the dump
+        #[structopt(parse(from_os_str))]
+        dump: PathBuf,
+        /// The path to the ELF file of the program which was running when the dump was created
+        #[structopt(parse(from_os_str))]
+        elf: PathBuf,
+    },
     #[clap(subcommand)]
     Chip(Chip),
 }
@@ -200,7 +253,19 @@ fn main() -> Result<()> {
             gdb_connection_string,
             common,
             reset_halt,
-        } => gdb::run_gdb_server(common, gdb_connection_string.as_deref(), reset_halt),
+            elf,
+        } => gdb::run_gdb_server(
+            common,
+            gdb_connection_string.as_deref(),
+            reset_halt,
+            elf.as_deref(),
+        ),
+        Cli::RttServer {
+            shared,
+            common,
+            bind,
+            channels,
+        } => rtt_server::run_rtt_server(common, shared.core, &bind, &channels),
         Cli::Reset {
             shared,
             common,
@@ -217,6 +282,22 @@ fn main() -> Result<()> {
             loc,
             words,
         } => dump_memory(&shared, &common, loc, words),
+        Cli::Download {
+            common,
+            format,
+            base_address,
+            skip_bytes,
+            path,
+            skip_invalid_data,
+            esp_rom_port: Some(port),
+            ..
+        } => download_program_esp_rom(
+            common,
+            format.into(base_address, skip_bytes),
+            &path,
+            &port,
+            skip_invalid_data,
+        ),
         Cli::Download {
             common,
             format,
@@ -226,6 +307,8 @@ fn main() -> Result<()> {
             chip_erase,
             disable_progressbars,
             disable_double_buffering,
+            skip_invalid_data,
+            esp_rom_port: None,
         } => download_program_fast(
             common,
             format.into(base_address, skip_bytes),
@@ -233,19 +316,31 @@ fn main() -> Result<()> {
             chip_erase,
             disable_progressbars,
             disable_double_buffering,
+            skip_invalid_data,
         ),
         Cli::Run {
             common,
             path,
             chip_erase,
             disable_double_buffering,
-        } => run::run(common, &path, chip_erase, disable_double_buffering),
-        Cli::Erase { common } => erase(&common),
+            vcom_baud,
+        } => run::run(
+            common,
+            &path,
+            chip_erase,
+            disable_double_buffering,
+            vcom_baud,
+        ),
+        Cli::Erase {
+            common,
+            sector_cache,
+        } => erase(common, sector_cache.as_deref()),
         Cli::Trace {
             shared,
             common,
             loc,
         } => trace_u32_on_target(&shared, &common, loc),
+        Cli::Symbolicate { dump, elf } => symbolicate::symbolicate(&dump, &elf),
         Cli::Chip(Chip::List) => print_families(io::stdout()).map_err(Into::into),
         Cli::Chip(Chip::Info { name }) => print_chip_info(name, io::stdout()),
     }
@@ -309,6 +404,7 @@ fn download_program_fast(
     do_chip_erase: bool,
     disable_progressbars: bool,
     disable_double_buffering: bool,
+    skip_invalid_data: bool,
 ) -> Result<()> {
     let mut session = common.simple_attach()?;
 
@@ -318,6 +414,7 @@ fn download_program_fast(
     };
 
     let mut loader = session.target().flash_loader();
+    loader.set_skip_invalid_data(skip_invalid_data);
 
     match format {
         Format::Bin(options) => loader.load_bin_data(&mut file, options),
@@ -337,6 +434,8 @@ fn download_program_fast(
             reset_halt: false,
             log: None,
             restore_unwritten: false,
+            skip_unchanged: false,
+            sector_cache: None,
             flash_layout_output_path: None,
             elf: None,
             work_dir: None,
@@ -350,10 +449,47 @@ fn download_program_fast(
     Ok(())
 }
 
-fn erase(common: &ProbeOptions) -> Result<()> {
+fn download_program_esp_rom(
+    common: ProbeOptions,
+    format: Format,
+    path: &str,
+    port: &str,
+    skip_invalid_data: bool,
+) -> Result<()> {
+    let target = match common.get_target_selector()? {
+        TargetSelector::Specified(target) => target,
+        _ => anyhow::bail!("The chip has to be specified with `--chip` to use the ROM bootloader."),
+    };
+
+    let mut options = DownloadOptions::default();
+    options.skip_invalid_data = skip_invalid_data;
+
+    let instant = Instant::now();
+    download_files_with_esp_rom_loader(&target, port, &[(path, format)], options)?;
+    println!(
+        "Programmed {} through the ROM bootloader in {:.2}s",
+        path,
+        instant.elapsed().as_secs_f64()
+    );
+
+    Ok(())
+}
+
+fn erase(mut common: ProbeOptions, sector_cache: Option<&Path>) -> Result<()> {
+    // Erasing all memory is the purpose of this command, so it doesn't need to be allowed explicitly.
+    common.allow_erase_all = true;
     let mut session = common.simple_attach()?;
 
-    erase_all(&mut session)?;
+    match (sector_cache, sector_cache_key(&session)) {
+        (Some(path), Some(key)) => {
+            erase_all_with_sector_cache(&mut session, &mut SectorCache::load(path, key))?
+        }
+        (Some(_), None) => {
+            log::warn!("The sector cache is not updated, because the serial number of the probe is not known.");
+            erase_all(&mut session)?
+        }
+        (None, _) => erase_all(&mut session)?,
+    }
 
     Ok(())
 }
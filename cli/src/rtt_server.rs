@@ -0,0 +1,235 @@
+//! Exposes RTT channels as TCP sockets, like SEGGER's RTT server.
+//!
+//! Each channel gets its own port: the data of the up channel is sent to all connected clients,
+//! and the data received from the clients is written to the down channel with the same number.
+
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+
+use anyhow::{anyhow, Context, Result};
+use probe_rs_cli_util::common_options::ProbeOptions;
+use probe_rs_rtt::{read_up_channels, DownChannel, PollInterval, Rtt, UpChannel};
+
+/// A channel which is exposed on a TCP port.
+struct ChannelBridge {
+    number: usize,
+    up: Option<UpChannel>,
+    down: Option<DownChannel>,
+    listener: TcpListener,
+    clients: Vec<TcpStream>,
+    /// Data from the clients which didn't fit into the down channel yet.
+    pending_down: Vec<u8>,
+}
+
+impl ChannelBridge {
+    /// Accepts new clients, and returns `true` if there were any.
+    fn accept(&mut self) -> Result<bool> {
+        let mut accepted = false;
+
+        loop {
+            match self.listener.accept() {
+                Ok((client, address)) => {
+                    client.set_nonblocking(true)?;
+                    client.set_nodelay(true)?;
+                    println!("Channel {}: {} connected", self.number, address);
+
+                    self.clients.push(client);
+                    accepted = true;
+                }
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => return Ok(accepted),
+                Err(error) => return Err(error.into()),
+            }
+        }
+    }
+
+    /// Sends data of the up channel to all clients.
+    ///
+    /// The data is dropped for clients which don't keep up with it, so one slow client doesn't
+    /// block the others.
+    fn send(&mut self, data: &[u8]) {
+        let mut dropped = 0;
+
+        self.clients = std::mem::take(&mut self.clients)
+            .into_iter()
+            .filter_map(|mut client| match write_some(&mut client, data) {
+                Ok(written) => {
+                    dropped += data.len() - written;
+                    Some(client)
+                }
+                Err(error) => {
+                    log::debug!("Disconnecting RTT client: {}", error);
+                    None
+                }
+            })
+            .collect();
+
+        if let Some(up) = &self.up {
+            if dropped > 0 {
+                up.report_dropped(dropped);
+            }
+        }
+    }
+
+    /// Receives data for the down channel from all clients, and returns `true` if there was any.
+    fn receive(&mut self) -> bool {
+        let mut buffer = [0u8; 1024];
+        let mut received = false;
+
+        let pending_down = &mut self.pending_down;
+        self.clients = std::mem::take(&mut self.clients)
+            .into_iter()
+            .filter_map(|mut client| loop {
+                match client.read(&mut buffer) {
+                    // The client closed the connection.
+                    Ok(0) => return None,
+                    Ok(count) => {
+                        pending_down.extend_from_slice(&buffer[..count]);
+                        received = true;
+                    }
+                    Err(error) if error.kind() == io::ErrorKind::WouldBlock => return Some(client),
+                    Err(error) => {
+                        log::debug!("Disconnecting RTT client: {}", error);
+                        return None;
+                    }
+                }
+            })
+            .collect();
+
+        if self.down.is_none() {
+            // The channel has no down channel to receive the data.
+            self.pending_down.clear();
+        }
+
+        received
+    }
+}
+
+/// Writes as much of `data` as the client accepts without blocking, and returns how much that was.
+fn write_some(client: &mut TcpStream, mut data: &[u8]) -> io::Result<usize> {
+    let mut written = 0;
+
+    while !data.is_empty() {
+        match client.write(data) {
+            Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+            Ok(count) => {
+                written += count;
+                data = &data[count..];
+            }
+            Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+            Err(error) => return Err(error),
+        }
+    }
+
+    Ok(written)
+}
+
+pub fn run_rtt_server(
+    common: ProbeOptions,
+    core_index: usize,
+    bind: &str,
+    channels: &[usize],
+) -> Result<()> {
+    let mut session = common.simple_attach()?;
+    let memory_map = session.target().memory_map.clone();
+    let mut core = session.core(core_index)?;
+
+    let mut rtt = Rtt::attach(&mut core, &memory_map)?;
+    println!("Found the RTT control block at {:#010x}", rtt.ptr());
+
+    let base_address: SocketAddr = bind
+        .to_socket_addrs()
+        .with_context(|| format!("Invalid address {}", bind))?
+        .next()
+        .ok_or_else(|| anyhow!("The address {} can't be resolved", bind))?;
+
+    let numbers: Vec<usize> = if channels.is_empty() {
+        let mut numbers: Vec<usize> = rtt
+            .up_channels()
+            .iter()
+            .map(|channel| channel.number())
+            .collect();
+        numbers.extend(rtt.down_channels().iter().map(|channel| channel.number()));
+        numbers
+    } else {
+        channels.to_vec()
+    };
+
+    let mut bridges: Vec<ChannelBridge> = Vec::new();
+    for number in numbers {
+        if bridges.iter().any(|bridge| bridge.number == number) {
+            continue;
+        }
+
+        let up = rtt.up_channels().take(number);
+        let down = rtt.down_channels().take(number);
+        if up.is_none() && down.is_none() {
+            eprintln!("RTT channel {} does not exist, skipping it.", number);
+            continue;
+        }
+
+        let mut address = base_address;
+        address.set_port(base_address.port() + number as u16);
+
+        let listener = TcpListener::bind(address)
+            .with_context(|| format!("Failed to listen on {}", address))?;
+        listener.set_nonblocking(true)?;
+
+        println!(
+            "Channel {} ({}) is available on {}",
+            number,
+            up.as_ref()
+                .and_then(|channel| channel.name())
+                .or_else(|| down.as_ref().and_then(|channel| channel.name()))
+                .unwrap_or("no name"),
+            address
+        );
+
+        bridges.push(ChannelBridge {
+            number,
+            up,
+            down,
+            listener,
+            clients: Vec::new(),
+            pending_down: Vec::new(),
+        });
+    }
+
+    if bridges.is_empty() {
+        return Err(anyhow!("There are no RTT channels to serve."));
+    }
+
+    let mut poll_interval = PollInterval::default();
+
+    loop {
+        let mut active = false;
+
+        for bridge in &mut bridges {
+            active |= bridge.accept()?;
+            active |= bridge.receive();
+        }
+
+        // Channels without clients aren't read, so their data stays on the target until a
+        // client connects.
+        let up_channels = bridges
+            .iter()
+            .filter(|bridge| !bridge.clients.is_empty())
+            .filter_map(|bridge| bridge.up.as_ref());
+        let mut data = read_up_channels(&mut core, up_channels)?;
+
+        for bridge in &mut bridges {
+            if let Some(data) = data.remove(&bridge.number) {
+                bridge.send(&data);
+                active = true;
+            }
+
+            if let Some(down) = &bridge.down {
+                if !bridge.pending_down.is_empty() {
+                    let count = down.write(&mut core, &bridge.pending_down)?;
+                    bridge.pending_down.drain(..count);
+                }
+            }
+        }
+
+        poll_interval.wait(active);
+    }
+}
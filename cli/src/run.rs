@@ -3,8 +3,9 @@ use probe_rs::flashing::FileDownloadError;
 use probe_rs_cli_util::common_options::{CargoOptions, FlashOptions, ProbeOptions};
 use probe_rs_cli_util::flash::run_flash_download;
 use probe_rs_cli_util::rtt;
+use probe_rs_rtt::PollInterval;
 use std::fs::File;
-use std::io::Write;
+use std::io::{self, Read, Write};
 use std::path::Path;
 
 pub fn run(
@@ -12,7 +13,13 @@ pub fn run(
     path: &str,
     chip_erase: bool,
     disable_double_buffering: bool,
+    vcom_baud: Option<u32>,
 ) -> Result<()> {
+    let mut vcom = vcom_baud.map(|baud| common.open_vcom(baud)).transpose()?;
+    if let Some(vcom) = &vcom {
+        log::info!("Showing the output of the virtual COM port {}", vcom.name());
+    }
+
     let mut session = common.simple_attach()?;
 
     let mut file = match File::open(path) {
@@ -35,6 +42,8 @@ pub fn run(
             reset_halt: false,
             log: None,
             restore_unwritten: false,
+            skip_unchanged: false,
+            sector_cache: None,
             flash_layout_output_path: None,
             elf: None,
             work_dir: None,
@@ -60,14 +69,41 @@ pub fn run(
         }
     };
 
-    if let Some(rtta) = &mut rtta {
-        let mut stdout = std::io::stdout();
-        loop {
-            for (_ch, data) in rtta.poll_rtt(&mut core) {
-                stdout.write_all(data.as_bytes()).unwrap();
+    if rtta.is_none() && vcom.is_none() {
+        return Ok(());
+    }
+
+    let mut stdout = std::io::stdout();
+    let mut vcom_buf = [0u8; 1024];
+    let mut poll_interval = PollInterval::default();
+    loop {
+        if let Some(rtta) = &mut rtta {
+            let output = rtta.poll_rtt(&mut core);
+            for data in output.values() {
+                stdout.write_all(data.as_bytes())?;
+            }
+            if !output.is_empty() {
+                stdout.flush()?;
+            }
+
+            // Reading the virtual COM port already waits for data.
+            if vcom.is_none() {
+                poll_interval.wait(!output.is_empty());
             }
         }
-    }
 
-    Ok(())
+        if let Some(vcom) = &mut vcom {
+            match vcom.read(&mut vcom_buf) {
+                Ok(0) => {}
+                Ok(count) => {
+                    stdout.write_all(&vcom_buf[..count])?;
+                    stdout.flush()?;
+                }
+                Err(error) if error.kind() == io::ErrorKind::TimedOut => {}
+                Err(error) => {
+                    return Err(error).context("Failed to read from the virtual COM port.")
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,75 @@
+use anyhow::{Context, Result};
+use probe_rs::architecture::arm::Dump;
+use probe_rs::debug::{debug_info::DebugInfo, ColumnType, VariableName};
+use probe_rs::CoreDump;
+use std::path::Path;
+
+/// Prints the backtrace of a dump created with the `dump` or `coredump` command of the debugger,
+/// with the local variables of each frame, using the debug information of the program which was
+/// running.
+pub(crate) fn symbolicate(dump_path: &Path, elf_path: &Path) -> Result<()> {
+    let dump_data = std::fs::read(dump_path)
+        .with_context(|| format!("Failed to read the dump {}", dump_path.display()))?;
+
+    // ELF core files are written by the `coredump` command, RON files by the `dump` command.
+    let dump = if dump_data.starts_with(b"\x7fELF") {
+        CoreDump::from_elf(&dump_data)?
+    } else {
+        let dump: Dump = ron::de::from_bytes(&dump_data).context("Failed to parse the dump")?;
+        CoreDump::from(&dump)
+    };
+
+    let debug_info = DebugInfo::from_file(elf_path).with_context(|| {
+        format!(
+            "Failed to read the debug information of {}",
+            elf_path.display()
+        )
+    })?;
+
+    let stack_frames = dump.symbolicate(&debug_info)?;
+
+    for (i, frame) in stack_frames.iter().enumerate() {
+        print!("Frame {}: {} @ {:#010x}", i, frame.function_name, frame.pc);
+
+        if frame.is_inlined {
+            print!(" inline");
+        }
+        println!();
+
+        if let Some(location) = &frame.source_location {
+            if let (Some(directory), Some(file)) = (&location.directory, &location.file) {
+                print!("       {}/{}", directory.display(), file);
+
+                if let Some(line) = location.line {
+                    print!(":{}", line);
+
+                    if let Some(ColumnType::Column(column)) = location.column {
+                        print!(":{}", column);
+                    }
+                }
+
+                println!();
+            }
+        }
+
+        let local_variables = match &frame.local_variables {
+            Some(local_variables) => local_variables,
+            None => continue,
+        };
+
+        if let Some(locals) =
+            local_variables.get_variable_by_name_and_parent(&VariableName::LocalScopeRoot, None)
+        {
+            for variable in local_variables.get_children(Some(locals.variable_key))? {
+                println!(
+                    "         {}: {} = {}",
+                    variable.name,
+                    variable.type_name,
+                    variable.get_value(local_variables)
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1,11 +1,17 @@
 use crate::{
     debug_adapter::{dap_types, protocol::ProtocolAdapter},
-    debugger::{configuration::ConsoleLog, core_data::CoreHandle, session_data::BreakpointType},
+    debugger::{
+        configuration::ConsoleLog,
+        core_data::CoreHandle,
+        live_watch::{self, LiveWatch},
+        session_data::BreakpointType,
+    },
     DebuggerError,
 };
 use anyhow::{anyhow, Result};
 use capstone::{
     arch::arm::ArchMode as armArchMode, arch::arm64::ArchMode as aarch64ArchMode,
+    arch::mips::ArchMode as mipsArchMode, arch::riscv::ArchExtraMode as riscvArchExtraMode,
     arch::riscv::ArchMode as riscvArchMode, prelude::*, Capstone, Endian,
 };
 use dap_types::*;
@@ -13,10 +19,11 @@ use num_traits::Zero;
 use parse_int::parse;
 use probe_rs::{
     debug::{
-        registers::Registers, stepping_mode::SteppingMode, ColumnType, SourceLocation,
-        VariableName, VariableNodeType,
+        registers::Registers, stepping_mode::SteppingMode, ColumnType, ExpressionValue,
+        SourceLocation, VariableLocation, VariableName, VariableNodeType,
     },
-    CoreStatus, HaltReason, InstructionSet, MemoryInterface,
+    CoreStatus, FaultReport, HaltReason, InstructionSet, MemoryInterface, VectorCatchCondition,
+    Watchpoint, WatchpointKind,
 };
 use probe_rs_cli_util::rtt;
 use serde::{de::DeserializeOwned, Serialize};
@@ -145,48 +152,30 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
             Err(error) => return self.send_response::<()>(request, Err(error)),
         };
         let memory_offset = arguments.offset.unwrap_or(0);
-        let mut address: u64 =
-            if let Ok(address) = parse::<u64>(arguments.memory_reference.as_ref()) {
-                address + memory_offset as u64
-            } else {
-                return self.send_response::<()>(
-                    request,
-                    Err(DebuggerError::Other(anyhow!(
-                        "Could not read any data at address {:?}",
-                        arguments.memory_reference
-                    ))),
-                );
-            };
-        let mut num_bytes_unread = arguments.count as usize;
-        let mut buff = vec![];
-        while num_bytes_unread > 0 {
-            if let Ok(good_byte) = target_core.core.read_word_8(address) {
-                buff.push(good_byte);
-                address += 1;
-                num_bytes_unread -= 1;
-            } else {
-                break;
-            }
-        }
-        if !buff.is_empty() || num_bytes_unread == 0 {
-            let response = base64::encode(&buff);
-            self.send_response(
-                request,
-                Ok(Some(ReadMemoryResponseBody {
-                    address: format!("{:#010x}", address),
-                    data: Some(response),
-                    unreadable_bytes: None,
-                })),
-            )
+        let address: u64 = if let Ok(address) = parse::<u64>(arguments.memory_reference.as_ref()) {
+            // The offset can be negative.
+            address.wrapping_add(memory_offset as u64)
         } else {
-            self.send_response::<()>(
+            return self.send_response::<()>(
                 request,
                 Err(DebuggerError::Other(anyhow!(
-                    "Could not read any data at address {:#010x}",
-                    address
+                    "Could not read any data at address {:?}",
+                    arguments.memory_reference
                 ))),
-            )
-        }
+            );
+        };
+        let count = arguments.count.max(0) as u64;
+        let buff = read_readable_memory(&mut target_core.core, address, count);
+        // The memory after the first unreadable byte is reported as unreadable, so that the client can show it as such, instead of failing the whole request.
+        let unreadable_bytes = count - buff.len() as u64;
+        self.send_response(
+            request,
+            Ok(Some(ReadMemoryResponseBody {
+                address: format!("{:#010x}", address),
+                data: Some(base64::encode(&buff)),
+                unreadable_bytes: (unreadable_bytes > 0).then(|| unreadable_bytes as i64),
+            })),
+        )
     }
 
     pub(crate) fn write_memory(
@@ -232,16 +221,34 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                 );
             }
         };
-        match target_core
-            .core
-            .write_8(address, &data_bytes)
-            .map_err(DebuggerError::ProbeRs)
-        {
-            Ok(_) => {
+        let write_result = match target_core.core.write_8(address, &data_bytes) {
+            Ok(_) => Ok(data_bytes.len()),
+            Err(error) => {
+                if arguments.allow_partial.unwrap_or(false) {
+                    // Write as many bytes as possible, up to the first byte which can't be written.
+                    let bytes_written = data_bytes
+                        .iter()
+                        .zip(address..)
+                        .take_while(|(byte, byte_address)| {
+                            target_core.core.write_word_8(*byte_address, **byte).is_ok()
+                        })
+                        .count();
+                    if bytes_written > 0 {
+                        Ok(bytes_written)
+                    } else {
+                        Err(DebuggerError::ProbeRs(error))
+                    }
+                } else {
+                    Err(DebuggerError::ProbeRs(error))
+                }
+            }
+        };
+        match write_result {
+            Ok(bytes_written) => {
                 self.send_response(
                     request,
                     Ok(Some(WriteMemoryResponseBody {
-                        bytes_written: Some(data_bytes.len() as i64),
+                        bytes_written: Some(bytes_written as i64),
                         offset: None,
                     })),
                 )?;
@@ -249,7 +256,7 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                 self.send_event(
                     "memory",
                     Some(MemoryEventBody {
-                        count: data_bytes.len() as i64,
+                        count: bytes_written as i64,
                         memory_reference: format!("{:#010x}", address),
                         offset: 0,
                     }),
@@ -287,6 +294,16 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
         // The Variables request sometimes returns the variable name, and other times the variable id, so this expression will be tested to determine if it is an id or not.
         let expression = arguments.expression.clone();
 
+        // The stack frames are out of date while the core runs, so only static variables are evaluated, with their current value in memory.
+        if matches!(
+            self.last_known_status,
+            CoreStatus::Running | CoreStatus::Sleeping
+        ) && target_core.core.supports_background_memory_access()
+        {
+            response_body.result = live_watch::evaluate_static_variable(target_core, &expression);
+            return self.send_response(request, Ok(Some(response_body)));
+        }
+
         // Make sure we have a valid StackFrame
         if let Some(stack_frame) = match arguments.frame_id {
             Some(frame_id) => target_core
@@ -361,7 +378,45 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                     response_body.type_ = Some(format!("{:?}", variable.type_name));
                     response_body.variables_reference = variables_reference;
                 } else {
-                    // If we made it to here, no register or variable matched the expression.
+                    // If we made it to here, no register or variable matched the expression, so evaluate it.
+                    match target_core.core_data.debug_info.evaluate_expression(
+                        &mut target_core.core,
+                        stack_frame,
+                        &expression,
+                    ) {
+                        Ok(ExpressionValue::Variable(variable)) => {
+                            if let Some(variable_cache) = [
+                                stack_frame.local_variables.as_mut(),
+                                stack_frame.static_variables.as_mut(),
+                            ]
+                            .into_iter()
+                            .flatten()
+                            .find(|variable_cache| {
+                                variable_cache
+                                    .get_variable_by_key(variable.variable_key)
+                                    .is_some()
+                            }) {
+                                let (
+                                    variables_reference,
+                                    named_child_variables_cnt,
+                                    indexed_child_variables_cnt,
+                                ) = self.get_variable_reference(&variable, variable_cache);
+                                response_body.indexed_variables = Some(indexed_child_variables_cnt);
+                                response_body.memory_reference =
+                                    Some(format!("{}", variable.memory_location));
+                                response_body.named_variables = Some(named_child_variables_cnt);
+                                response_body.result = variable.get_value(variable_cache);
+                                response_body.type_ = Some(format!("{:?}", variable.type_name));
+                                response_body.variables_reference = variables_reference;
+                            }
+                        }
+                        Ok(ExpressionValue::Integer(value)) => {
+                            response_body.result = value.to_string();
+                        }
+                        Err(error) => {
+                            response_body.result = format!("<{}>", error);
+                        }
+                    }
                 }
             }
         }
@@ -369,6 +424,35 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
         self.send_response(request, Ok(Some(response_body)))
     }
 
+    /// Replace the expressions which are watched while the core runs, with the custom `liveWatch` request.
+    pub(crate) fn live_watch(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: Request,
+    ) -> Result<()> {
+        let arguments: LiveWatchArguments = match get_arguments(&request) {
+            Ok(arguments) => arguments,
+            Err(error) => return self.send_response::<()>(request, Err(error)),
+        };
+
+        if !target_core.core.supports_background_memory_access() {
+            return self.send_response::<()>(
+                request,
+                Err(DebuggerError::Other(anyhow!(
+                    "Live watch is not supported, because the memory of this core can't be read while it runs."
+                ))),
+            );
+        }
+
+        target_core.core_data.live_watches = arguments
+            .expressions
+            .into_iter()
+            .map(|live_watch| LiveWatch::new(live_watch.expression, live_watch.refresh_interval))
+            .collect();
+
+        self.send_response::<()>(request, Ok(None))
+    }
+
     /// Set the variable with the given name in the variable container to a new value.
     pub(crate) fn set_variable(
         &mut self,
@@ -395,35 +479,107 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
         let parent_key = arguments.variables_reference;
         let new_value = arguments.value.clone();
 
-        //TODO: Check for, and prevent SVD Peripheral/Register/Field values from being updated, until such time as we can do it safely.
+        // The parent_key can refer to an SVD peripheral or register, whose registers or fields are written to the target directly.
+        if let Some(core_peripherals) = &target_core.core_data.core_peripherals {
+            if let Some(svd_variable) = core_peripherals
+                .svd_variable_cache
+                .get_variable_by_key(parent_key)
+                .and_then(|parent_variable| {
+                    // The SVD variables use fully qualified names, but the client only knows the last part.
+                    core_peripherals
+                        .svd_variable_cache
+                        .get_variable_by_name_and_parent(
+                            &VariableName::Named(format!(
+                                "{}.{}",
+                                parent_variable.name, arguments.name
+                            )),
+                            Some(parent_key),
+                        )
+                })
+            {
+                return match core_peripherals.write_variable(
+                    &mut target_core.core,
+                    &svd_variable,
+                    &new_value,
+                ) {
+                    Ok(updated_value) => {
+                        response_body.type_ = Some(svd_variable.type_name.to_string());
+                        response_body.value = updated_value;
+                        self.send_response(request, Ok(Some(response_body)))
+                    }
+                    Err(error) => {
+                        self.send_response::<SetVariableResponseBody>(request, Err(error))
+                    }
+                };
+            }
+        }
 
         match target_core
             .core_data
             .stack_frames
             .iter_mut()
-            .find(|stack_frame| stack_frame.id == parent_key)
+            .enumerate()
+            .find(|(_, stack_frame)| stack_frame.id == parent_key)
         {
-            Some(stack_frame) => {
+            Some((frame_index, stack_frame)) => {
                 // The variable is a register value in this StackFrame
-                if let Some((_register_number, _register_value)) =
-                    stack_frame.registers.registers().into_iter().find(
-                        |(register_number, _register_value)| {
-                            let register_number = **register_number;
-                            let register_name = stack_frame
-                                .registers
-                                .get_name_by_dwarf_register_number(register_number)
-                                .unwrap_or_else(|| format!("r{:#}", register_number));
-                            register_name == arguments.name
-                        },
-                    )
+                if let Some(register_number) = stack_frame
+                    .registers
+                    .registers()
+                    .map(|(register_number, _register_value)| *register_number)
+                    .find(|register_number| {
+                        let register_name = stack_frame
+                            .registers
+                            .get_name_by_dwarf_register_number(*register_number)
+                            .unwrap_or_else(|| format!("r{:#}", register_number));
+                        register_name == arguments.name
+                    })
                 {
-                    // TODO: Does it make sense for us to consider implementing an update of platform registers?
-                    return self.send_response::<SetVariableResponseBody>(
-                        request,
-                        Err(DebuggerError::Other(anyhow!(
-                            "Set Register values is not yet supported."
-                        ))),
-                    );
+                    // The registers of the other frames are reconstructed by unwinding, and can't be written.
+                    if frame_index != 0 {
+                        return self.send_response::<SetVariableResponseBody>(
+                            request,
+                            Err(DebuggerError::Other(anyhow!(
+                                "Registers can only be updated in the frame which is currently executing."
+                            ))),
+                        );
+                    }
+
+                    let register_value: u64 = match parse(&new_value) {
+                        Ok(register_value) => register_value,
+                        Err(error) => {
+                            return self.send_response::<SetVariableResponseBody>(
+                                request,
+                                Err(DebuggerError::Other(anyhow!(
+                                    "Invalid register value {:?}: {:?}",
+                                    new_value,
+                                    error
+                                ))),
+                            )
+                        }
+                    };
+
+                    match stack_frame.registers.write_register(
+                        &mut target_core.core,
+                        register_number,
+                        register_value,
+                    ) {
+                        Ok(()) => {
+                            response_body.type_ = Some(format!("{}", VariableName::RegistersRoot));
+                            response_body.value = format!("{:#010x}", register_value);
+                        }
+                        Err(error) => {
+                            return self.send_response::<SetVariableResponseBody>(
+                                request,
+                                Err(DebuggerError::Other(anyhow!(
+                                    "Failed to update register {}, with new value {:?} : {:?}",
+                                    arguments.name,
+                                    new_value,
+                                    error
+                                ))),
+                            )
+                        }
+                    }
                 }
             }
             None => {
@@ -432,7 +588,11 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                 // The parent_key refers to a local or static variable in one of the in-scope StackFrames.
                 let mut cache_variable: Option<probe_rs::debug::Variable> = None;
                 let mut variable_cache: Option<&mut probe_rs::debug::VariableCache> = None;
-                for search_frame in target_core.core_data.stack_frames.iter_mut() {
+                let mut variable_frame_index = 0;
+                for (frame_index, search_frame) in
+                    target_core.core_data.stack_frames.iter_mut().enumerate()
+                {
+                    variable_frame_index = frame_index;
                     if let Some(search_cache) = &mut search_frame.local_variables {
                         if let Some(search_variable) = search_cache
                             .get_variable_by_name_and_parent(&variable_name, Some(parent_key))
@@ -456,6 +616,21 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                 if let (Some(cache_variable), Some(variable_cache)) =
                     (cache_variable, variable_cache)
                 {
+                    // Variables in registers are written to the registers of the core, which only belong to the first frame.
+                    if variable_frame_index != 0
+                        && matches!(
+                            cache_variable.memory_location,
+                            VariableLocation::Register(_)
+                        )
+                    {
+                        return self.send_response::<SetVariableResponseBody>(
+                            request,
+                            Err(DebuggerError::Other(anyhow!(
+                                "Variables stored in registers can only be updated in the frame which is currently executing."
+                            ))),
+                        );
+                    }
+
                     // We have found the variable that needs to be updated.
                     match cache_variable.update_value(
                         &mut target_core.core,
@@ -505,86 +680,35 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
         }
     }
 
-    pub(crate) fn restart(
-        &mut self,
-        target_core: &mut CoreHandle,
-        request: Option<Request>,
-    ) -> Result<()> {
-        match target_core.core.halt(Duration::from_millis(500)) {
-            Ok(_) => {}
-            Err(error) => {
-                if let Some(request) = request {
-                    return self.send_response::<()>(
-                        request,
-                        Err(DebuggerError::Other(anyhow!("{}", error))),
-                    );
-                } else {
-                    return self.send_error_response(&DebuggerError::Other(anyhow!("{}", error)));
-                }
-            }
-        }
-
-        // Different code paths if we invoke this from a request, versus an internal function.
-        if let Some(request) = request {
-            match target_core.core.reset() {
-                Ok(_) => {
-                    self.last_known_status = CoreStatus::Running;
-                    let event_body = Some(ContinuedEventBody {
-                        all_threads_continued: Some(false), // TODO: Implement multi-core logic here
-                        thread_id: target_core.core.id() as i64,
-                    });
+    /// Reset and halt the core.
+    /// The DAP Client will always do a `reset_and_halt`, and then will consider `halt_after_reset` value after the `configuration_done` request.
+    /// Otherwise the probe will run past the `main()` before the DAP Client has had a chance to set breakpoints in `main()`.
+    pub(crate) fn restart(&mut self, target_core: &mut CoreHandle) -> Result<()> {
+        target_core.core.halt(Duration::from_millis(500))?;
+        target_core
+            .core
+            .reset_and_halt(Duration::from_millis(500))?;
 
-                    self.send_event("continued", event_body)
-                }
-                Err(error) => {
-                    return self.send_response::<()>(
-                        request,
-                        Err(DebuggerError::Other(anyhow!("{}", error))),
-                    );
-                }
-            }
-        } else {
-            // The DAP Client will always do a `reset_and_halt`, and then will consider `halt_after_reset` value after the `configuration_done` request.
-            // Otherwise the probe will run past the `main()` before the DAP Client has had a chance to set breakpoints in `main()`.
-            match target_core.core.reset_and_halt(Duration::from_millis(500)) {
-                Ok(_) => {
-                    if let Some(request) = request {
-                        return self.send_response::<()>(request, Ok(None));
-                    }
-                    // Only notify the DAP client if we are NOT in initialization stage (`CoreStatus::Unknown`).
-                    if self.last_known_status != CoreStatus::Unknown {
-                        let event_body = Some(StoppedEventBody {
-                            reason: "reset".to_owned(),
-                            description: Some(
-                                CoreStatus::Halted(HaltReason::External)
-                                    .short_long_status()
-                                    .1
-                                    .to_string(),
-                            ),
-                            thread_id: Some(target_core.core.id() as i64),
-                            preserve_focus_hint: None,
-                            text: None,
-                            all_threads_stopped: Some(false), // TODO: Implement multi-core logic here
-                            hit_breakpoint_ids: None,
-                        });
-                        self.send_event("stopped", event_body)?;
-                        self.last_known_status = CoreStatus::Halted(HaltReason::External);
-                    }
-                    Ok(())
-                }
-                Err(error) => {
-                    if let Some(request) = request {
-                        return self.send_response::<()>(
-                            request,
-                            Err(DebuggerError::Other(anyhow!("{}", error))),
-                        );
-                    } else {
-                        return self
-                            .send_error_response(&DebuggerError::Other(anyhow!("{}", error)));
-                    }
-                }
-            }
+        // Only notify the DAP client if we are NOT in initialization stage (`CoreStatus::Unknown`).
+        if self.last_known_status != CoreStatus::Unknown {
+            let event_body = Some(StoppedEventBody {
+                reason: "reset".to_owned(),
+                description: Some(
+                    CoreStatus::Halted(HaltReason::External)
+                        .short_long_status()
+                        .1
+                        .to_string(),
+                ),
+                thread_id: Some(target_core.core.id() as i64),
+                preserve_focus_hint: None,
+                text: None,
+                all_threads_stopped: Some(false),
+                hit_breakpoint_ids: None,
+            });
+            self.send_event("stopped", event_body)?;
+            self.last_known_status = CoreStatus::Halted(HaltReason::External);
         }
+        Ok(())
     }
 
     /// NOTE: VSCode sends a 'threads' request when it receives the response from this request, irrespective of target state.
@@ -859,7 +983,274 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
         self.send_response(request, Ok(Some(instruction_breakpoint_body)))
     }
 
-    pub(crate) fn threads(&mut self, target_core: &mut CoreHandle, request: Request) -> Result<()> {
+    /// Resolve the variable of a `dataBreakpointInfo` request to its memory location, which is encoded in the `data_id` as `<address>/<size>`.
+    pub(crate) fn data_breakpoint_info(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: Request,
+    ) -> Result<()> {
+        let arguments: DataBreakpointInfoArguments = match get_arguments(&request) {
+            Ok(arguments) => arguments,
+            Err(error) => return self.send_response::<()>(request, Err(error)),
+        };
+
+        let variable_name = VariableName::Named(arguments.name.clone());
+
+        // The `variablesReference` is the key of the parent variable, which is unique across all caches.
+        let mut variable = None;
+        for stack_frame in target_core.core_data.stack_frames.iter() {
+            variable = [
+                stack_frame.local_variables.as_ref(),
+                stack_frame.static_variables.as_ref(),
+            ]
+            .into_iter()
+            .flatten()
+            .find_map(|variable_cache| match arguments.variables_reference {
+                Some(parent_key) => {
+                    variable_cache.get_variable_by_name_and_parent(&variable_name, Some(parent_key))
+                }
+                None => variable_cache.get_variable_by_name(&variable_name),
+            });
+            if variable.is_some() {
+                break;
+            }
+        }
+
+        // Without a parent, the name can also be an expression.
+        if variable.is_none() && arguments.variables_reference.is_none() {
+            if let Some(stack_frame) = target_core.core_data.stack_frames.first_mut() {
+                if let Ok(ExpressionValue::Variable(evaluated)) = target_core
+                    .core_data
+                    .debug_info
+                    .evaluate_expression(&mut target_core.core, stack_frame, &arguments.name)
+                {
+                    variable = Some(evaluated);
+                }
+            }
+        }
+
+        let response_body = match variable {
+            Some(variable) => match variable.memory_location {
+                VariableLocation::Address(address) if variable.byte_size > 0 => {
+                    DataBreakpointInfoResponseBody {
+                        data_id: Some(format!("{:#010x}/{}", address, variable.byte_size)),
+                        description: format!(
+                            "{} ({} bytes at {:#010x})",
+                            arguments.name, variable.byte_size, address
+                        ),
+                        access_types: Some(vec![
+                            DataBreakpointAccessType::Read,
+                            DataBreakpointAccessType::Write,
+                            DataBreakpointAccessType::ReadWrite,
+                        ]),
+                        can_persist: Some(false),
+                    }
+                }
+                _ => DataBreakpointInfoResponseBody {
+                    data_id: None,
+                    description: format!(
+                        "Data breakpoints are only supported for variables in memory, but {} is not.",
+                        arguments.name
+                    ),
+                    access_types: None,
+                    can_persist: None,
+                },
+            },
+            None => DataBreakpointInfoResponseBody {
+                data_id: None,
+                description: format!("Could not find the variable {}", arguments.name),
+                access_types: None,
+                can_persist: None,
+            },
+        };
+
+        self.send_response(request, Ok(Some(response_body)))
+    }
+
+    pub(crate) fn set_data_breakpoints(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: Request,
+    ) -> Result<()> {
+        let arguments: SetDataBreakpointsArguments = match get_arguments(&request) {
+            Ok(arguments) => arguments,
+            Err(error) => return self.send_response::<()>(request, Err(error)),
+        };
+
+        // Always clear existing data breakpoints before setting new ones.
+        if let Err(error) = target_core.clear_data_breakpoints() {
+            log::warn!("Failed to clear data breakpoints. {}", error);
+        }
+
+        let mut created_breakpoints: Vec<Breakpoint> = Vec::new();
+
+        for requested_breakpoint in arguments.breakpoints {
+            let mut breakpoint_response = Breakpoint {
+                column: None,
+                end_column: None,
+                end_line: None,
+                id: None,
+                instruction_reference: None,
+                line: None,
+                message: None,
+                offset: None,
+                source: None,
+                verified: false,
+            };
+
+            let kind = match requested_breakpoint.access_type {
+                Some(DataBreakpointAccessType::Read) => WatchpointKind::Read,
+                Some(DataBreakpointAccessType::ReadWrite) => WatchpointKind::Access,
+                Some(DataBreakpointAccessType::Write) | None => WatchpointKind::Write,
+            };
+
+            match parse_data_id(&requested_breakpoint.data_id) {
+                Some((address, length)) => {
+                    breakpoint_response.instruction_reference = Some(format!("{:#010x}", address));
+
+                    match target_core.set_data_breakpoint(Watchpoint {
+                        address,
+                        length,
+                        kind,
+                    }) {
+                        Ok(()) => {
+                            breakpoint_response.verified = true;
+                            breakpoint_response.message = Some(format!(
+                                "Data breakpoint on {} bytes at {:#010x}",
+                                length, address
+                            ));
+                        }
+                        Err(error) => {
+                            let message = format!(
+                                "Could not set data breakpoint on {} bytes at {:#010x}: {}",
+                                length, address, error
+                            );
+                            self.log_to_console(format!("Warning: {}", message));
+                            self.show_message(MessageSeverity::Warning, message.clone());
+                            breakpoint_response.message = Some(message);
+                        }
+                    }
+                }
+                None => {
+                    breakpoint_response.message = Some(format!(
+                        "Invalid data breakpoint id: {:?}",
+                        requested_breakpoint.data_id
+                    ));
+                }
+            }
+
+            created_breakpoints.push(breakpoint_response);
+        }
+
+        let data_breakpoint_body = SetDataBreakpointsResponseBody {
+            breakpoints: created_breakpoints,
+        };
+        self.send_response(request, Ok(Some(data_breakpoint_body)))
+    }
+
+    /// Set the exception breakpoints, which halt the core when it takes one of the exceptions of [EXCEPTION_BREAKPOINT_FILTERS].
+    pub(crate) fn set_exception_breakpoints(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: Request,
+    ) -> Result<()> {
+        let arguments: SetExceptionBreakpointsArguments = match get_arguments(&request) {
+            Ok(arguments) => arguments,
+            Err(error) => return self.send_response::<()>(request, Err(error)),
+        };
+
+        let breakpoints = target_core.set_exception_breakpoints(&arguments.filters);
+        for message in breakpoints
+            .iter()
+            .filter(|breakpoint| !breakpoint.verified)
+            .filter_map(|breakpoint| breakpoint.message.as_ref())
+        {
+            self.log_to_console(format!("Warning: {}", message));
+        }
+
+        self.send_response(
+            request,
+            Ok(Some(SetExceptionBreakpointsResponseBody {
+                breakpoints: Some(breakpoints),
+            })),
+        )
+    }
+
+    /// Describe the exception which halted the core, with the decoded fault status registers of Cortex-M cores.
+    pub(crate) fn exception_info(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: Request,
+    ) -> Result<()> {
+        let regs = target_core.core.registers();
+        let pc: u64 = match target_core.core.read_core_reg(regs.program_counter()) {
+            Ok(pc) => pc,
+            Err(error) => {
+                return self.send_response::<()>(request, Err(DebuggerError::ProbeRs(error)))
+            }
+        };
+        // The exception number is in the IPSR bits of the xPSR on Cortex-M cores.
+        let exception_number = match regs.psr() {
+            Some(psr) => target_core.core.read_core_reg::<u32>(psr).ok(),
+            None => None,
+        }
+        .map(|xpsr| xpsr & 0x1ff);
+        let exception_id = match exception_number {
+            Some(0) => "Reset",
+            Some(2) => "NMI",
+            Some(3) => "HardFault",
+            Some(4) => "MemManage",
+            Some(5) => "BusFault",
+            Some(6) => "UsageFault",
+            Some(7) => "SecureFault",
+            _ => "Exception",
+        }
+        .to_string();
+
+        let fault_report = match FaultReport::read(&mut target_core.core, pc) {
+            Ok(fault_report) => fault_report,
+            Err(error) => {
+                return self.send_response::<()>(request, Err(DebuggerError::ProbeRs(error)))
+            }
+        };
+        let mut description = vec![format!("{} at {:#010x}", exception_id, pc)];
+        if let Some(faulting_pc) = fault_report.faulting_pc {
+            description.push(format!("Faulting instruction at {:#010x}", faulting_pc));
+        }
+        description.extend(fault_report.causes().into_iter().map(String::from));
+        if let Some(mmfar) = fault_report.mmfar {
+            description.push(format!("MemManage fault address: {:#010x}", mmfar));
+        }
+        if let Some(bfar) = fault_report.bfar {
+            description.push(format!("BusFault address: {:#010x}", bfar));
+        }
+        let description = description.join("\n");
+
+        self.send_response(
+            request,
+            Ok(Some(ExceptionInfoResponseBody {
+                exception_id: exception_id.clone(),
+                description: Some(description.clone()),
+                break_mode: ExceptionBreakMode::Always,
+                details: Some(ExceptionDetails {
+                    message: Some(description),
+                    type_name: Some(exception_id),
+                    full_type_name: None,
+                    evaluate_name: None,
+                    stack_trace: None,
+                    inner_exception: None,
+                }),
+            })),
+        )
+    }
+
+    /// The `other_threads` are the threads of the other configured cores, which are included in the response.
+    pub(crate) fn threads(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: Request,
+        other_threads: Vec<Thread>,
+    ) -> Result<()> {
         // TODO: Implement actual thread resolution. For now, we just use the core id as the thread id.
         let mut threads: Vec<Thread> = vec![];
         match self.last_known_status {
@@ -891,6 +1282,7 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                                     name: target_core.core_data.target_name.clone(),
                                 };
                                 threads.push(single_thread);
+                                threads.extend(other_threads);
                                 self.send_response(
                                     request.clone(),
                                     Ok(Some(ThreadsResponseBody { threads })),
@@ -916,6 +1308,7 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                     name: target_core.core_data.target_name.clone(),
                 };
                 threads.push(single_thread);
+                threads.extend(other_threads);
                 // We do the actual stack trace here, because VSCode sometimes sends multiple StackTrace requests, which lead to unnecessary unwind processing.
                 // By doing it here, we do it once, and serve up the results when we get the StackTrace requests.
                 let regs = target_core.core.registers();
@@ -949,6 +1342,60 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
         self.send_response(request, Ok(Some(ThreadsResponseBody { threads })))
     }
 
+    /// Synchronize the status of a core, which is not the target of the `threads` request, with the DAP client, and return its thread.
+    /// This follows the same logic as [DebugAdapter::threads], except that a core which doesn't halt after reset is resumed directly.
+    pub(crate) fn synchronize_thread(
+        &mut self,
+        target_core: &mut CoreHandle,
+    ) -> Result<Thread, DebuggerError> {
+        if self.last_known_status == CoreStatus::Unknown {
+            let core_status = target_core.core.status()?;
+            self.last_known_status = core_status;
+            if core_status.is_halted() {
+                if self.halt_after_reset
+                    || core_status == CoreStatus::Halted(HaltReason::Breakpoint)
+                {
+                    let event_body = Some(StoppedEventBody {
+                        reason: core_status.short_long_status().0.to_owned(),
+                        description: Some(core_status.short_long_status().1.to_string()),
+                        thread_id: Some(target_core.core.id() as i64),
+                        preserve_focus_hint: None,
+                        text: None,
+                        all_threads_stopped: Some(false),
+                        hit_breakpoint_ids: None,
+                    });
+                    self.send_event("stopped", event_body)?;
+                } else {
+                    target_core.core.run()?;
+                    self.last_known_status = CoreStatus::Running;
+                    let event_body = Some(ContinuedEventBody {
+                        all_threads_continued: Some(false),
+                        thread_id: target_core.core.id() as i64,
+                    });
+                    self.send_event("continued", event_body)?;
+                }
+            }
+        }
+
+        if self.last_known_status.is_halted() {
+            let regs = target_core.core.registers();
+            let pc: u64 = target_core.core.read_core_reg(regs.program_counter())?;
+            log::debug!(
+                "Updating the stack frame data for core #{}",
+                target_core.core.id()
+            );
+            target_core.core_data.stack_frames = target_core
+                .core_data
+                .debug_info
+                .unwind(&mut target_core.core, pc)?;
+        }
+
+        Ok(Thread {
+            id: target_core.core.id() as i64,
+            name: target_core.core_data.target_name.clone(),
+        })
+    }
+
     pub(crate) fn stack_trace(
         &mut self,
         target_core: &mut CoreHandle,
@@ -1365,6 +1812,17 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                     .mode(riscvArchMode::RiscV32)
                     .endian(Endian::Little)
                     .build(),
+                InstructionSet::RV32C => Capstone::new()
+                    .riscv()
+                    .mode(riscvArchMode::RiscV32)
+                    .extra_mode(std::iter::once(riscvArchExtraMode::RiscVC))
+                    .endian(Endian::Little)
+                    .build(),
+                InstructionSet::Mips32 => Capstone::new()
+                    .mips()
+                    .mode(mipsArchMode::Mips32)
+                    .endian(Endian::Little)
+                    .build(),
             }
             .map_err(|err| anyhow!("Error creating capstone: {:?}", err))?;
 
@@ -1392,6 +1850,7 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                             let mut location = None;
                             let mut line = None;
                             let mut column = None;
+                            let mut symbol = None;
                             if let Some(current_source_location) = target_core
                                 .core_data
                                 .debug_info
@@ -1405,6 +1864,12 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                                             ColumnType::Column(c) => c as i64,
                                         });
                                         stored_source_location = Some(current_source_location);
+                                        symbol = target_core
+                                            .core_data
+                                            .debug_info
+                                            .function_name(instruction.address(), false)
+                                            .ok()
+                                            .flatten();
                                     }
                                 } else {
                                         stored_source_location = Some(current_source_location);
@@ -1425,10 +1890,17 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                                     instruction.mnemonic().unwrap_or("<unknown>"),
                                     instruction.op_str().unwrap_or("")
                                 ),
-                                instruction_bytes: None,
+                                instruction_bytes: Some(
+                                    instruction
+                                        .bytes()
+                                        .iter()
+                                        .map(|byte| format!("{:02X}", byte))
+                                        .collect::<Vec<String>>()
+                                        .join(" "),
+                                ),
                                 line,
                                 location,
-                                symbol: None,
+                                symbol,
                             }
                         })
                         .collect::<Vec<DisassembledInstruction>>();
@@ -2025,6 +2497,31 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
             .is_ok()
     }
 
+    /// Send a custom `probe-rs-live-watch` event to the MS DAP Client, with the new value of a live watch expression.
+    pub fn live_watch_output(&mut self, expression: String, value: String) -> bool {
+        self.send_event(
+            "probe-rs-live-watch",
+            Some(LiveWatchEventBody { expression, value }),
+        )
+        .is_ok()
+    }
+
+    /// Send the data of an RTT channel to the debug console of the MS DAP Client, with an `output` event.
+    /// Each channel uses its own category, e.g. `rtt0`, so the client can tell the channels apart.
+    pub fn rtt_console_output(&mut self, channel_number: usize, rtt_data: String) -> bool {
+        let event_body = OutputEventBody {
+            output: rtt_data,
+            category: Some(format!("rtt{}", channel_number)),
+            variables_reference: None,
+            source: None,
+            line: None,
+            column: None,
+            data: None,
+            group: None,
+        };
+        self.send_event("output", Some(event_body)).is_ok()
+    }
+
     fn new_progress_id(&mut self) -> ProgressId {
         let id = self.progress_id;
 
@@ -2101,6 +2598,98 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
     }
 }
 
+/// The exception breakpoint filters which are offered to the DAP client, with their label, description, and the vector catch condition which implements them.
+pub(crate) const EXCEPTION_BREAKPOINT_FILTERS: &[(&str, &str, &str, VectorCatchCondition)] = &[
+    (
+        "hardFault",
+        "HardFault",
+        "Halt when the core takes a HardFault exception",
+        VectorCatchCondition::HardFault,
+    ),
+    (
+        "memManage",
+        "MemManage",
+        "Halt when the core takes a MemManage exception",
+        VectorCatchCondition::MemManage,
+    ),
+    (
+        "busFault",
+        "BusFault",
+        "Halt when the core takes a BusFault exception",
+        VectorCatchCondition::BusFault,
+    ),
+    (
+        "usageFault",
+        "UsageFault",
+        "Halt when the core takes a UsageFault exception",
+        VectorCatchCondition::UsageFault,
+    ),
+    (
+        "secureFault",
+        "SecureFault",
+        "Halt when the core takes a SecureFault exception",
+        VectorCatchCondition::SecureFault,
+    ),
+    (
+        "reset",
+        "Reset",
+        "Halt when the core is reset, e.g. by a watchdog",
+        VectorCatchCondition::CoreReset,
+    ),
+];
+
+/// The exception breakpoint filters for the `Capabilities` of the debug adapter.
+pub(crate) fn exception_breakpoint_filters() -> Vec<ExceptionBreakpointsFilter> {
+    EXCEPTION_BREAKPOINT_FILTERS
+        .iter()
+        .map(
+            |(filter, label, description, _)| ExceptionBreakpointsFilter {
+                filter: filter.to_string(),
+                label: label.to_string(),
+                description: Some(description.to_string()),
+                default: Some(false),
+                supports_condition: Some(false),
+                condition_description: None,
+            },
+        )
+        .collect()
+}
+
+/// The size of the blocks in which memory is read for the `readMemory` request.
+const READ_MEMORY_BLOCK_SIZE: u64 = 1024;
+
+/// Read up to `count` bytes at `address`, until the first byte which can't be read.
+/// The memory is read with 8-bit accesses, in blocks which are aligned to [READ_MEMORY_BLOCK_SIZE]. Only a block which can't be read completely is read byte by byte, to find the first unreadable byte.
+fn read_readable_memory(core: &mut probe_rs::Core, address: u64, count: u64) -> Vec<u8> {
+    let mut buff = Vec::new();
+    let mut block_address = address;
+    let end_address = address.saturating_add(count);
+    while block_address < end_address {
+        let block_size = (READ_MEMORY_BLOCK_SIZE - block_address % READ_MEMORY_BLOCK_SIZE)
+            .min(end_address - block_address);
+        let mut block = vec![0u8; block_size as usize];
+        if core.read_8(block_address, &mut block).is_ok() {
+            buff.extend_from_slice(&block);
+            block_address += block_size;
+        } else {
+            for byte_address in block_address..block_address + block_size {
+                match core.read_word_8(byte_address) {
+                    Ok(byte) => buff.push(byte),
+                    Err(_) => return buff,
+                }
+            }
+            block_address += block_size;
+        }
+    }
+    buff
+}
+
+/// Parse the `data_id` of a data breakpoint, as created by [`DebugAdapter::data_breakpoint_info`], into the address and size of the data.
+fn parse_data_id(data_id: &str) -> Option<(u64, u64)> {
+    let (address, size) = data_id.split_once('/')?;
+    Some((parse(address).ok()?, size.parse().ok()?))
+}
+
 /// A helper function to greate a [`dap_types::Source`] struct from a [`SourceLocation`]
 fn get_dap_source(source_location: &SourceLocation) -> Option<Source> {
     // Attempt to construct the path for the source code
@@ -51,6 +51,46 @@ pub struct RttWindowOpenedArguments {
     pub window_is_open: bool,
 }
 
+/// Custom [`LiveWatchRequest`] request, so that VSCode can watch expressions while the core runs.
+/// The values are refreshed at the interval of each expression, and are sent with `probe-rs-live-watch` events when they change.
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct LiveWatchRequest {
+    /// Object containing arguments for the command.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<LiveWatchArguments>,
+    /// The command to execute.
+    pub command: String,
+    /// Sequence number (also known as message ID). For protocol messages of type `request` this ID
+    /// can be used to cancel the request.
+    pub seq: i64,
+    /// Message type.
+    #[serde(rename = "type")]
+    pub type_: String,
+}
+///  Arguments for [`LiveWatchRequest`] request.
+#[derive(Clone, PartialEq, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LiveWatchArguments {
+    /// The expressions to watch, which replace the ones of earlier requests.
+    pub expressions: Vec<LiveWatchExpression>,
+}
+
+#[derive(Clone, PartialEq, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LiveWatchExpression {
+    /// The name of a static variable, or an expression which refers to one.
+    pub expression: String,
+    /// The refresh interval in milliseconds.
+    pub refresh_interval: Option<u64>,
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LiveWatchEventBody {
+    pub expression: String,
+    pub value: String,
+}
+
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RttChannelEventBody {
@@ -45,7 +45,7 @@ pub struct SessionConfig {
 
     /// Every core on the target has certain configuration.
     ///
-    /// NOTE: Although we allow specifying multiple core configurations, this is a work in progress, and probe-rs-debugger currently only supports debugging a single core.
+    /// Each configured core is exposed to the DAP client as a thread, with the core index as thread id.
     pub(crate) core_configs: Vec<CoreConfig>,
 }
 
@@ -122,6 +122,15 @@ impl SessionConfig {
             }
         })
     }
+
+    /// Whether the core should halt after reset. The `CoreConfig::halt_after_reset` of the core overrides `FlashingConfig::halt_after_reset`.
+    pub(crate) fn halt_after_reset(&self, core_index: usize) -> bool {
+        self.core_configs
+            .iter()
+            .find(|core_config| core_config.core_index == core_index)
+            .and_then(|core_config| core_config.halt_after_reset)
+            .unwrap_or(self.flashing_config.halt_after_reset)
+    }
 }
 
 /// If the path to the program to be debugged is relative, we join if with the cwd.
@@ -172,6 +181,10 @@ pub struct FlashingConfig {
     /// Restore erased bytes that will not be rewritten from ELF
     #[serde(default)]
     pub(crate) restore_unwritten_bytes: bool,
+
+    /// Read back the flashed data to verify it has been written correctly
+    #[serde(default)]
+    pub(crate) verify_after_flashing: bool,
 }
 
 /// Configuration options for all core level configuration.
@@ -188,6 +201,13 @@ pub struct CoreConfig {
     /// CMSIS-SVD file for the target. Relative to `cwd`, or fully qualified.
     pub(crate) svd_file: Option<PathBuf>,
 
+    /// Flash the `program_binary` of this core, if flashing is enabled. Cores which share a binary only flash it once.
+    #[serde(default = "default_flash_program")]
+    pub(crate) flash_program: bool,
+
+    /// Halt this core after reset, instead of using `FlashingConfig::halt_after_reset`.
+    pub(crate) halt_after_reset: Option<bool>,
+
     #[serde(flatten)]
     pub(crate) rtt_config: rtt::RttConfig,
 }
@@ -196,6 +216,11 @@ fn default_console_log() -> Option<ConsoleLog> {
     Some(ConsoleLog::Error)
 }
 
+/// Used by serde to provide defaults for `CoreConfig::flash_program`
+fn default_flash_program() -> bool {
+    true
+}
+
 /// The level of information to be logged to the debugger console. The DAP Client will set appropriate RUST_LOG env for 'launch' configurations,  and will pass the rust log output to the client debug console.
 #[derive(Copy, Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
 pub enum ConsoleLog {
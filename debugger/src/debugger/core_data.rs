@@ -1,12 +1,16 @@
 use super::session_data;
 use crate::{
-    debug_adapter::{dap_adapter::DebugAdapter, protocol::ProtocolAdapter},
-    debugger::debug_rtt,
+    debug_adapter::{
+        dap_adapter::{DebugAdapter, EXCEPTION_BREAKPOINT_FILTERS},
+        dap_types::Breakpoint,
+        protocol::ProtocolAdapter,
+    },
+    debugger::{debug_rtt, live_watch::LiveWatch},
     peripherals::svd_variables::SvdCache,
     DebuggerError,
 };
 use anyhow::Result;
-use probe_rs::{debug::debug_info::DebugInfo, Core};
+use probe_rs::{debug::debug_info::DebugInfo, Core, CoreStatus, Watchpoint};
 use probe_rs_cli_util::rtt;
 
 /// [CoreData] is used to cache data needed by the debugger, on a per-core basis.
@@ -17,7 +21,15 @@ pub struct CoreData {
     pub(crate) core_peripherals: Option<SvdCache>,
     pub(crate) stack_frames: Vec<probe_rs::debug::stack_frame::StackFrame>,
     pub(crate) breakpoints: Vec<session_data::ActiveBreakpoint>,
+    /// The data breakpoints, which are set as hardware watchpoints.
+    pub(crate) data_breakpoints: Vec<Watchpoint>,
+    /// The filters of the exception breakpoints, which are set as vector catch conditions.
+    pub(crate) exception_breakpoints: Vec<String>,
     pub(crate) rtt_connection: Option<debug_rtt::RttConnection>,
+    /// The expressions which are refreshed while the core runs.
+    pub(crate) live_watches: Vec<LiveWatch>,
+    /// Track the last known status of the core, which is loaded into [DebugAdapter::last_known_status] while a request for this core is processed.
+    pub(crate) last_known_status: CoreStatus,
 }
 
 /// [CoreHandle] provides handles to various data structures required to debug a single instance of a core. The actual state is stored in [SessionData].
@@ -58,16 +70,24 @@ impl<'p> CoreHandle<'p> {
             Ok(target_rtt) => {
                 for any_channel in target_rtt.active_channels.iter() {
                     if let Some(up_channel) = &any_channel.up_channel {
+                        let debug_console = rtt_config.channels.iter().any(|channel_config| {
+                            channel_config.channel_number == Some(up_channel.number())
+                                && channel_config.debug_console
+                        });
                         debugger_rtt_channels.push(debug_rtt::DebuggerRttChannel {
                             channel_number: up_channel.number(),
                             // This value will eventually be set to true by a VSCode client request "rttWindowOpened"
                             has_client_window: false,
+                            debug_console,
                         });
-                        debug_adapter.rtt_window(
-                            up_channel.number(),
-                            any_channel.channel_name.clone(),
-                            any_channel.data_format,
-                        );
+                        // Channels shown in the debug console don't need a window.
+                        if !debug_console {
+                            debug_adapter.rtt_window(
+                                up_channel.number(),
+                                any_channel.channel_name.clone(),
+                                any_channel.data_format,
+                            );
+                        }
                     }
                 }
                 self.core_data.rtt_connection = Some(debug_rtt::RttConnection {
@@ -82,6 +102,32 @@ impl<'p> CoreHandle<'p> {
         Ok(())
     }
 
+    /// Refresh the live watch expressions which are due, using memory reads which don't halt the core, and send the values which changed to the client.
+    /// Returns `true` if at least one value was sent.
+    pub(crate) fn poll_live_watches<P: ProtocolAdapter>(
+        &mut self,
+        debug_adapter: &mut DebugAdapter<P>,
+    ) -> bool {
+        if self.core_data.live_watches.is_empty() || !self.core.supports_background_memory_access()
+        {
+            return false;
+        }
+
+        // The live watches need the stack frames of the core data, so they are taken out while they are refreshed.
+        let mut live_watches = std::mem::take(&mut self.core_data.live_watches);
+        let now = std::time::Instant::now();
+        let mut sent_values = false;
+        for live_watch in live_watches.iter_mut() {
+            if let Some(value) = live_watch.refresh(self, now) {
+                sent_values |=
+                    debug_adapter.live_watch_output(live_watch.expression.clone(), value);
+            }
+        }
+        self.core_data.live_watches = live_watches;
+
+        sent_values
+    }
+
     /// Set a single breakpoint in target configuration as well as [`CoreHandle::breakpoints`]
     pub(crate) fn set_breakpoint(
         &mut self,
@@ -137,4 +183,74 @@ impl<'p> CoreHandle<'p> {
         }
         Ok(())
     }
+
+    /// Set a data breakpoint in target configuration as well as [`CoreData::data_breakpoints`]
+    pub(crate) fn set_data_breakpoint(
+        &mut self,
+        watchpoint: Watchpoint,
+    ) -> Result<(), DebuggerError> {
+        self.core
+            .set_hw_watchpoint(watchpoint)
+            .map_err(DebuggerError::ProbeRs)?;
+        self.core_data.data_breakpoints.push(watchpoint);
+        Ok(())
+    }
+
+    /// Clear all data breakpoints. Affects target configuration as well as [`CoreData::data_breakpoints`]
+    pub(crate) fn clear_data_breakpoints(&mut self) -> Result<()> {
+        for watchpoint in std::mem::take(&mut self.core_data.data_breakpoints) {
+            self.core
+                .clear_hw_watchpoint(watchpoint)
+                .map_err(DebuggerError::ProbeRs)?;
+        }
+        Ok(())
+    }
+
+    /// Enable the vector catch of each of the exception `filters`, disable all others, and store the `filters` in [`CoreData::exception_breakpoints`].
+    /// Returns a breakpoint for each of the `filters`, which is not verified if the core doesn't support it.
+    pub(crate) fn set_exception_breakpoints(&mut self, filters: &[String]) -> Vec<Breakpoint> {
+        let mut breakpoints = Vec::new();
+        for filter in filters {
+            let mut breakpoint = Breakpoint {
+                column: None,
+                end_column: None,
+                end_line: None,
+                id: None,
+                instruction_reference: None,
+                line: None,
+                message: None,
+                offset: None,
+                source: None,
+                verified: false,
+            };
+            match EXCEPTION_BREAKPOINT_FILTERS
+                .iter()
+                .find(|(known_filter, ..)| known_filter == filter)
+            {
+                Some((_, label, _, condition)) => {
+                    match self.core.set_vector_catch(*condition, true) {
+                        Ok(()) => breakpoint.verified = true,
+                        Err(error) => {
+                            breakpoint.message = Some(format!(
+                                "Could not set {} exception breakpoint: {}",
+                                label, error
+                            ))
+                        }
+                    }
+                }
+                None => breakpoint.message = Some(format!("Unknown exception filter {:?}", filter)),
+            }
+            breakpoints.push(breakpoint);
+        }
+
+        for (known_filter, _, _, condition) in EXCEPTION_BREAKPOINT_FILTERS {
+            if !filters.iter().any(|filter| filter == known_filter) {
+                // Conditions which the core doesn't support can't be enabled either.
+                self.core.set_vector_catch(*condition, false).ok();
+            }
+        }
+        self.core_data.exception_breakpoints = filters.to_vec();
+
+        breakpoints
+    }
 }
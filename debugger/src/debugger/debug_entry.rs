@@ -19,6 +19,7 @@ use std::{
     cell::RefCell,
     net::{Ipv4Addr, TcpListener},
     ops::Mul,
+    path::PathBuf,
     rc::Rc,
     thread,
     time::Duration,
@@ -50,6 +51,8 @@ impl std::str::FromStr for TargetSessionType {
 pub(crate) enum DebuggerStatus {
     ContinueSession,
     TerminateSession,
+    /// Flash the program binaries again, and restart the cores, without closing the probe or the connection to the DAP Client.
+    RestartSession(Request),
 }
 
 /// #Debugger Overview
@@ -59,6 +62,8 @@ pub(crate) enum DebuggerStatus {
 ///   - `probe-rs-debug --debug --port <IP port number> <other options>` : Uses TCP Sockets to the defined IP port number to service DAP requests.
 pub struct Debugger {
     config: configuration::SessionConfig,
+    /// The core which receives the requests without a `threadId`. This is the core which halted last.
+    current_core_index: usize,
 }
 
 impl Debugger {
@@ -68,6 +73,7 @@ impl Debugger {
                 port,
                 ..Default::default()
             },
+            current_core_index: 0,
         }
     }
 
@@ -87,154 +93,99 @@ impl Debugger {
         match request {
             None => {
                 // If there are no requests, we poll target cores for status, which includes handling RTT data.
-                match debug_adapter.last_known_status {
-                    CoreStatus::Unknown => {
-                        // Don't do anything until we know VSCode's startup sequence is complete, and changes this to either Halted or Running.
-                        Ok(DebuggerStatus::ContinueSession)
-                    }
-                    CoreStatus::Halted(_) => {
-                        // We check for RTT data the first time we have entered halted status, to ensure the buffers are drained.
-                        // After that, for as long as we remain in halted state, we don't need to check RTT again.
-                        // TODO: This only works for a single core, so until it can be redesigned, will use the first one configured.
-                        let check_rtt = if let Some(target_core_config) =
-                            self.config.core_configs.first_mut()
-                        {
-                            if let Ok(mut core_handle) =
-                                session_data.attach_core(target_core_config.core_index)
-                            {
-                                match core_handle.core.status() {
-                                    Ok(new_status) => new_status != debug_adapter.last_known_status,
-                                    Err(error) => {
-                                        let error = DebuggerError::ProbeRs(error);
-                                        let _ = debug_adapter.send_error_response(&error);
-                                        return Err(error);
-                                    }
-                                }
-                            } else {
-                                return Err(DebuggerError::Other(anyhow!(
-                                    "Unable to connect to target core"
-                                )));
-                            }
-                        } else {
-                            return Err(DebuggerError::Other(anyhow!(
-                                "Cannot continue unless one target core configuration is defined."
-                            )));
-                        };
-                        if check_rtt {
-                            session_data.poll_rtt(&self.config, debug_adapter);
-                        } else {
-                            // No need to poll the target status if we know it is halted and waiting for us to do something.
-                            thread::sleep(Duration::from_millis(50)); // Small delay to reduce fast looping costs on the client
-                        }
-                        Ok(DebuggerStatus::ContinueSession)
-                    }
-                    _other => {
-                        let received_rtt_data = session_data.poll_rtt(&self.config, debug_adapter);
+                let core_indexes: Vec<usize> = self
+                    .config
+                    .core_configs
+                    .iter()
+                    .map(|core_config| core_config.core_index)
+                    .collect();
+                if core_indexes.is_empty() {
+                    return Err(DebuggerError::Other(anyhow!(
+                        "Cannot continue unless one target core configuration is defined."
+                    )));
+                }
+                let mut status_changed = false;
+                for core_index in core_indexes {
+                    status_changed |= self.poll_core(session_data, debug_adapter, core_index)?;
+                }
 
-                        // Check and update the core status.
-                        // TODO: This only works for a single core, so until it can be redesigned, will use the first one configured.
-                        let mut target_core = if let Some(target_core_config) =
-                            self.config.core_configs.first_mut()
-                        {
-                            if let Ok(core_handle) =
-                                session_data.attach_core(target_core_config.core_index)
-                            {
-                                core_handle
-                            } else {
-                                return Err(DebuggerError::Other(anyhow!(
-                                    "Unable to connect to target core"
-                                )));
-                            }
-                        } else {
-                            return Err(DebuggerError::Other(anyhow!(
-                                "Cannot continue unless one target core configuration is defined."
-                            )));
-                        };
+                // We check for RTT data while a core runs, and the first time it has entered halted status, to ensure the buffers are drained.
+                // After that, for as long as all cores remain in halted state, we don't need to check RTT again.
+                let check_rtt = status_changed
+                    || session_data.core_data.iter().any(|core_data| {
+                        matches!(
+                            core_data.last_known_status,
+                            CoreStatus::Running | CoreStatus::Sleeping
+                        )
+                    });
+                let received_rtt_data =
+                    check_rtt && session_data.poll_rtt(&self.config, debug_adapter);
 
-                        let new_status = match target_core.core.status() {
-                            Ok(new_status) => new_status,
-                            Err(error) => {
-                                let error = DebuggerError::ProbeRs(error);
-                                let _ = debug_adapter.send_error_response(&error);
-                                return Err(error);
-                            }
-                        };
+                // Only sleep (nap for a short duration) IF the status of the cores hasn't changed AND there was no RTT data in the last poll.
+                // Otherwise loop again to keep things flowing as fast as possible.
+                // The justification is that any client side CPU used to keep polling is a small price to pay for maximum throughput of debug requests and RTT from the probe.
+                if !status_changed && !received_rtt_data {
+                    thread::sleep(Duration::from_millis(50)); // Small delay to reduce fast looping costs.
+                }
+                Ok(DebuggerStatus::ContinueSession)
+            }
+            Some(request) => {
+                if self.config.core_configs.is_empty() {
+                    return Err(DebuggerError::Other(anyhow!(
+                        "Cannot continue unless one target core configuration is defined."
+                    )));
+                }
+                if request.command == "restart" {
+                    if self.config.flashing_config.flashing_enabled {
+                        // The `debug_session` owns the `debug_adapter`, which is needed to report the flashing progress.
+                        return Ok(DebuggerStatus::RestartSession(request));
+                    }
+                    let result = self.restart_cores(session_data, debug_adapter);
+                    debug_adapter.send_response::<()>(request, result.map(|()| None))?;
+                    return Ok(DebuggerStatus::ContinueSession);
+                }
 
-                        // Only sleep (nap for a short duration) IF the probe's status hasn't changed AND there was no RTT data in the last poll.
-                        // Otherwise loop again to keep things flowing as fast as possible.
-                        // The justification is that any client side CPU used to keep polling is a small price to pay for maximum throughput of debug requests and RTT from the probe.
-                        if received_rtt_data && new_status == debug_adapter.last_known_status {
-                            return Ok(DebuggerStatus::ContinueSession);
-                        } else if new_status == debug_adapter.last_known_status {
-                            thread::sleep(Duration::from_millis(50)); // Small delay to reduce fast looping costs.
+                let core_index = self.core_index_for_request(&request);
+                let other_threads = if request.command == "threads" {
+                    match self.other_core_threads(session_data, debug_adapter, core_index) {
+                        Ok(other_threads) => other_threads,
+                        Err(error) => {
+                            debug_adapter.send_response::<()>(request, Err(error))?;
                             return Ok(DebuggerStatus::ContinueSession);
-                        } else {
-                            debug_adapter.last_known_status = new_status;
-                        };
-
-                        match new_status {
-                            CoreStatus::Running | CoreStatus::Sleeping => {
-                                let event_body = Some(ContinuedEventBody {
-                                    all_threads_continued: Some(true),
-                                    thread_id: target_core.core.id() as i64,
-                                });
-                                debug_adapter.send_event("continued", event_body)?;
-                            }
-                            CoreStatus::Halted(_) => {
-                                let event_body = Some(StoppedEventBody {
-                                    reason: new_status.short_long_status().0.to_owned(),
-                                    description: Some(new_status.short_long_status().1.to_owned()),
-                                    thread_id: Some(target_core.core.id() as i64),
-                                    preserve_focus_hint: Some(false),
-                                    text: None,
-                                    all_threads_stopped: Some(true),
-                                    hit_breakpoint_ids: None,
-                                });
-                                debug_adapter.send_event("stopped", event_body)?;
-                            }
-                            CoreStatus::LockedUp => {
-                                debug_adapter.show_message(
-                                    MessageSeverity::Error,
-                                    new_status.short_long_status().1.to_owned(),
-                                );
-                                return Err(DebuggerError::Other(anyhow!(new_status
-                                    .short_long_status()
-                                    .1
-                                    .to_owned())));
-                            }
-                            CoreStatus::Unknown => {
-                                debug_adapter.send_error_response(&DebuggerError::Other(
-                                    anyhow!("Unknown Device status reveived from Probe-rs"),
-                                ))?;
-
-                                return Err(DebuggerError::Other(anyhow!(
-                                    "Unknown Device status reveived from Probe-rs"
-                                )));
+                        }
+                    }
+                } else {
+                    vec![]
+                };
+                if request.command == "setExceptionBreakpoints" {
+                    // The exception breakpoints apply to all cores, and the response is sent for the current core.
+                    if let Ok(arguments) =
+                        get_arguments::<SetExceptionBreakpointsArguments>(&request)
+                    {
+                        for core_config in self.config.core_configs.iter() {
+                            if core_config.core_index != core_index {
+                                if let Ok(mut other_core) =
+                                    session_data.attach_core(core_config.core_index)
+                                {
+                                    other_core.set_exception_breakpoints(&arguments.filters);
+                                }
                             }
-                        };
-                        Ok(DebuggerStatus::ContinueSession)
+                        }
                     }
                 }
-            }
-            Some(request) => {
+
                 // First, attach to the core.
-                // TODO: This only works for a single core, so until it can be redesigned, will use the first one configured.
-                let mut target_core = if let Some(target_core_config) =
-                    self.config.core_configs.first_mut()
+                let mut target_core = if let Ok(core_handle) = session_data.attach_core(core_index)
                 {
-                    if let Ok(core_handle) = session_data.attach_core(target_core_config.core_index)
-                    {
-                        core_handle
-                    } else {
-                        return Err(DebuggerError::Other(anyhow!(
-                            "Unable to connect to target core"
-                        )));
-                    }
+                    core_handle
                 } else {
                     return Err(DebuggerError::Other(anyhow!(
-                        "Cannot continue unless one target core configuration is defined."
+                        "Unable to connect to target core"
                     )));
                 };
+                // The [DebugAdapter] methods work with the status of the core they are called for.
+                debug_adapter.last_known_status = target_core.core_data.last_known_status;
+                debug_adapter.halt_after_reset = self.config.halt_after_reset(core_index);
 
                 // For some operations, we need to make sure the core isn't sleeping, by calling `Core::halt()`.
                 // When we do this, we need to flag it (`unhalt_me = true`), and later call `Core::run()` again.
@@ -245,6 +196,8 @@ impl Debugger {
                     | "setBreakpoint"
                     | "setBreakpoints"
                     | "setInstructionBreakpoints"
+                    | "dataBreakpointInfo"
+                    | "setDataBreakpoints"
                     | "clearBreakpoint"
                     | "stackTrace"
                     | "threads"
@@ -357,21 +310,26 @@ impl Debugger {
                         .configuration_done(&mut target_core, request)
                         .and(Ok(DebuggerStatus::ContinueSession)),
                     "threads" => debug_adapter
-                        .threads(&mut target_core, request)
+                        .threads(&mut target_core, request, other_threads)
                         .and(Ok(DebuggerStatus::ContinueSession)),
-                    "restart" => {
-                        // Reset RTT so that the link can be re-established
-                        target_core.core_data.rtt_connection = None;
-                        debug_adapter
-                            .restart(&mut target_core, Some(request))
-                            .and(Ok(DebuggerStatus::ContinueSession))
-                    }
                     "setBreakpoints" => debug_adapter
                         .set_breakpoints(&mut target_core, request)
                         .and(Ok(DebuggerStatus::ContinueSession)),
                     "setInstructionBreakpoints" => debug_adapter
                         .set_instruction_breakpoints(&mut target_core, request)
                         .and(Ok(DebuggerStatus::ContinueSession)),
+                    "dataBreakpointInfo" => debug_adapter
+                        .data_breakpoint_info(&mut target_core, request)
+                        .and(Ok(DebuggerStatus::ContinueSession)),
+                    "setDataBreakpoints" => debug_adapter
+                        .set_data_breakpoints(&mut target_core, request)
+                        .and(Ok(DebuggerStatus::ContinueSession)),
+                    "setExceptionBreakpoints" => debug_adapter
+                        .set_exception_breakpoints(&mut target_core, request)
+                        .and(Ok(DebuggerStatus::ContinueSession)),
+                    "exceptionInfo" => debug_adapter
+                        .exception_info(&mut target_core, request)
+                        .and(Ok(DebuggerStatus::ContinueSession)),
                     "stackTrace" => debug_adapter
                         .stack_trace(&mut target_core, request)
                         .and(Ok(DebuggerStatus::ContinueSession)),
@@ -390,6 +348,9 @@ impl Debugger {
                     "evaluate" => debug_adapter
                         .evaluate(&mut target_core, request)
                         .and(Ok(DebuggerStatus::ContinueSession)),
+                    "liveWatch" => debug_adapter
+                        .live_watch(&mut target_core, request)
+                        .and(Ok(DebuggerStatus::ContinueSession)),
                     other_command => {
                         // Unimplemented command.
                         debug_adapter.send_response::<()>(
@@ -410,6 +371,7 @@ impl Debugger {
                                 }
                             }
                         }
+                        target_core.core_data.last_known_status = debug_adapter.last_known_status;
                         Ok(debugger_status)
                     }
                     Err(e) => Err(DebuggerError::Other(e.context("Error executing request."))),
@@ -418,6 +380,481 @@ impl Debugger {
         }
     }
 
+    /// Poll the status of a single core, and tell the DAP-Client about any changes. Returns whether the status of the core changed.
+    /// - While the last known status of the core is `Unknown`, we do nothing. Wait until the `threads` request sets it to something known.
+    /// - If the last known status is `Halted`, then we only report a change, and leave it to the next DAP-Client request to act on it.
+    /// - If the last known status is `Running`, then we refresh the live watches, and report when the core stops for good reasons like breakpoints, or bad reasons like panics.
+    fn poll_core<P: ProtocolAdapter>(
+        &mut self,
+        session_data: &mut session_data::SessionData,
+        debug_adapter: &mut DebugAdapter<P>,
+        core_index: usize,
+    ) -> Result<bool, DebuggerError> {
+        let mut target_core = if let Ok(core_handle) = session_data.attach_core(core_index) {
+            core_handle
+        } else {
+            return Err(DebuggerError::Other(anyhow!(
+                "Unable to connect to target core"
+            )));
+        };
+        let last_known_status = target_core.core_data.last_known_status;
+
+        match last_known_status {
+            CoreStatus::Unknown => return Ok(false),
+            CoreStatus::Halted(_) => {}
+            _other => {
+                target_core.poll_live_watches(debug_adapter);
+            }
+        }
+
+        let new_status = match target_core.core.status() {
+            Ok(new_status) => new_status,
+            Err(error) => {
+                let error = DebuggerError::ProbeRs(error);
+                let _ = debug_adapter.send_error_response(&error);
+                return Err(error);
+            }
+        };
+
+        if new_status == last_known_status {
+            return Ok(false);
+        } else if last_known_status.is_halted() {
+            // No need to update the status, because the next request will synchronize it.
+            return Ok(true);
+        }
+        target_core.core_data.last_known_status = new_status;
+
+        match new_status {
+            CoreStatus::Running | CoreStatus::Sleeping => {
+                let event_body = Some(ContinuedEventBody {
+                    all_threads_continued: Some(false),
+                    thread_id: target_core.core.id() as i64,
+                });
+                debug_adapter.send_event("continued", event_body)?;
+            }
+            CoreStatus::Halted(_) => {
+                // Requests without a `threadId` will go to the core which halted last.
+                self.current_core_index = core_index;
+                let event_body = Some(StoppedEventBody {
+                    reason: new_status.short_long_status().0.to_owned(),
+                    description: Some(new_status.short_long_status().1.to_owned()),
+                    thread_id: Some(target_core.core.id() as i64),
+                    preserve_focus_hint: Some(false),
+                    text: None,
+                    all_threads_stopped: Some(false),
+                    hit_breakpoint_ids: None,
+                });
+                debug_adapter.send_event("stopped", event_body)?;
+            }
+            CoreStatus::LockedUp => {
+                debug_adapter.show_message(
+                    MessageSeverity::Error,
+                    new_status.short_long_status().1.to_owned(),
+                );
+                return Err(DebuggerError::Other(anyhow!(new_status
+                    .short_long_status()
+                    .1
+                    .to_owned())));
+            }
+            CoreStatus::Unknown => {
+                debug_adapter.send_error_response(&DebuggerError::Other(anyhow!(
+                    "Unknown Device status reveived from Probe-rs"
+                )))?;
+
+                return Err(DebuggerError::Other(anyhow!(
+                    "Unknown Device status reveived from Probe-rs"
+                )));
+            }
+        };
+        Ok(true)
+    }
+
+    /// Each core is exposed to the DAP-Client as a thread, with the core index as the thread id.
+    /// Requests with a `threadId` go to that core, and all other requests go to the current core.
+    fn core_index_for_request(&mut self, request: &Request) -> usize {
+        let thread_id = request
+            .arguments
+            .as_ref()
+            .and_then(|arguments| arguments.get("threadId"))
+            .and_then(|thread_id| thread_id.as_u64());
+        if let Some(core_config) = thread_id.and_then(|thread_id| {
+            self.config
+                .core_configs
+                .iter()
+                .find(|core_config| core_config.core_index as u64 == thread_id)
+        }) {
+            self.current_core_index = core_config.core_index;
+        }
+        self.current_core_index
+    }
+
+    /// The `threads` request lists all cores, so synchronize the status of the cores other than `core_index` with the DAP-Client, and return their threads.
+    fn other_core_threads<P: ProtocolAdapter>(
+        &mut self,
+        session_data: &mut session_data::SessionData,
+        debug_adapter: &mut DebugAdapter<P>,
+        core_index: usize,
+    ) -> Result<Vec<Thread>, DebuggerError> {
+        let mut other_threads = vec![];
+        let other_core_indexes: Vec<usize> = self
+            .config
+            .core_configs
+            .iter()
+            .map(|core_config| core_config.core_index)
+            .filter(|other_core_index| *other_core_index != core_index)
+            .collect();
+        for other_core_index in other_core_indexes {
+            let mut target_core = session_data.attach_core(other_core_index)?;
+            // The [DebugAdapter] methods work with the status of the core they are called for.
+            debug_adapter.last_known_status = target_core.core_data.last_known_status;
+            debug_adapter.halt_after_reset = self.config.halt_after_reset(other_core_index);
+            let result = debug_adapter.synchronize_thread(&mut target_core);
+            target_core.core_data.last_known_status = debug_adapter.last_known_status;
+            other_threads.push(result?);
+        }
+        Ok(other_threads)
+    }
+
+    /// Restart all cores for the `restart` request, while the probe and the DAP-Client stay connected.
+    /// All cores are reset and halted first, and then the cores which don't halt after reset continue.
+    fn restart_cores<P: ProtocolAdapter>(
+        &self,
+        session_data: &mut session_data::SessionData,
+        debug_adapter: &mut DebugAdapter<P>,
+    ) -> Result<(), DebuggerError> {
+        let core_indexes: Vec<usize> = self
+            .config
+            .core_configs
+            .iter()
+            .map(|core_config| core_config.core_index)
+            .collect();
+        for core_index in core_indexes {
+            let mut target_core = session_data.attach_core(core_index)?;
+            // Reset RTT so that the link can be re-established
+            target_core.core_data.rtt_connection = None;
+            // The [DebugAdapter] methods work with the status of the core they are called for.
+            debug_adapter.last_known_status = target_core.core_data.last_known_status;
+            let result = debug_adapter
+                .restart(&mut target_core)
+                .map_err(DebuggerError::Other)
+                .and_then(|()| {
+                    // The reset may have cleared the vector catch of the exception breakpoints.
+                    let exception_breakpoints = target_core.core_data.exception_breakpoints.clone();
+                    target_core.set_exception_breakpoints(&exception_breakpoints);
+                    if !self.config.halt_after_reset(core_index) {
+                        target_core.core.run()?;
+                        debug_adapter.last_known_status = CoreStatus::Running;
+                        let event_body = Some(ContinuedEventBody {
+                            all_threads_continued: Some(false),
+                            thread_id: target_core.core.id() as i64,
+                        });
+                        debug_adapter.send_event("continued", event_body)?;
+                    }
+                    Ok(())
+                });
+            target_core.core_data.last_known_status = debug_adapter.last_known_status;
+            result?;
+        }
+        Ok(())
+    }
+
+    /// Flash the `program_binary` of each core which has `flash_program` set.
+    /// The flashing progress is reported to the DAP client from a callback, which needs to own the `debug_adapter` while flashing, so it is returned again afterwards, together with the result of the flashing.
+    /// An error is only returned directly if the `debug_adapter` could not be returned.
+    fn flash_program_binaries<P: ProtocolAdapter + 'static>(
+        &self,
+        session_data: &mut session_data::SessionData,
+        mut debug_adapter: DebugAdapter<P>,
+        request_seq: i64,
+    ) -> Result<(DebugAdapter<P>, Result<(), DebuggerError>), DebuggerError> {
+        // Cores which share a `program_binary` only flash it once.
+        let mut program_binaries: Vec<PathBuf> = vec![];
+        for target_core_config in self
+            .config
+            .core_configs
+            .iter()
+            .filter(|target_core_config| target_core_config.flash_program)
+        {
+            match &target_core_config.program_binary {
+                Some(program_binary) => {
+                    if !program_binaries.contains(program_binary) {
+                        program_binaries.push(program_binary.clone());
+                    }
+                }
+                None => {
+                    let err = DebuggerError::Other(anyhow!(
+                        "Please use the --program-binary option to specify an executable"
+                    ));
+                    return Ok((debug_adapter, Err(err)));
+                }
+            };
+        }
+
+        for path_to_elf in program_binaries {
+            debug_adapter.log_to_console(format!(
+                "INFO: FLASHING: Starting write of {:?} to device memory",
+                &path_to_elf
+            ));
+
+            let progress_id = debug_adapter
+                .start_progress(&format!("Flashing {:?}", &path_to_elf), Some(request_seq))
+                .ok();
+
+            let mut download_options = DownloadOptions::default();
+            download_options.keep_unwritten_bytes =
+                self.config.flashing_config.restore_unwritten_bytes;
+            download_options.do_chip_erase = self.config.flashing_config.full_chip_erase;
+            download_options.verify = self.config.flashing_config.verify_after_flashing;
+            let flash_result = {
+                let rc_debug_adapter = Rc::new(RefCell::new(debug_adapter));
+                let rc_debug_adapter_clone = rc_debug_adapter.clone();
+                let flash_result = {
+                    struct ProgressState {
+                        total_page_size: usize,
+                        total_sector_size: usize,
+                        total_fill_size: usize,
+                        page_size_done: usize,
+                        sector_size_done: usize,
+                        fill_size_done: usize,
+                    }
+
+                    let flash_progress = Rc::new(RefCell::new(ProgressState {
+                        total_page_size: 0,
+                        total_sector_size: 0,
+                        total_fill_size: 0,
+                        page_size_done: 0,
+                        sector_size_done: 0,
+                        fill_size_done: 0,
+                    }));
+
+                    let flash_progress = if let Some(id) = progress_id {
+                        FlashProgress::new(move |event| {
+                            let mut flash_progress = flash_progress.borrow_mut();
+                            let mut debug_adapter = rc_debug_adapter_clone.borrow_mut();
+                            match event {
+                                probe_rs::flashing::ProgressEvent::Initialized { flash_layout } => {
+                                    flash_progress.total_page_size = flash_layout
+                                        .pages()
+                                        .iter()
+                                        .map(|s| s.size() as usize)
+                                        .sum();
+
+                                    flash_progress.total_sector_size = flash_layout
+                                        .sectors()
+                                        .iter()
+                                        .map(|s| s.size() as usize)
+                                        .sum();
+
+                                    flash_progress.total_fill_size = flash_layout
+                                        .fills()
+                                        .iter()
+                                        .map(|s| s.size() as usize)
+                                        .sum();
+                                }
+                                probe_rs::flashing::ProgressEvent::StartedFilling => {
+                                    debug_adapter
+                                        .update_progress(
+                                            Some(0.0),
+                                            Some("Reading Old Pages ..."),
+                                            id,
+                                        )
+                                        .ok();
+                                }
+                                probe_rs::flashing::ProgressEvent::PageFilled { size, .. } => {
+                                    flash_progress.fill_size_done += size as usize;
+                                    let progress = flash_progress.fill_size_done as f64
+                                        / flash_progress.total_fill_size as f64;
+                                    debug_adapter
+                                        .update_progress(
+                                            Some(progress),
+                                            Some(format!(
+                                                "Reading Old Pages ({:02.0}%)",
+                                                progress.mul(100_f64)
+                                            )),
+                                            id,
+                                        )
+                                        .ok();
+                                }
+                                probe_rs::flashing::ProgressEvent::FailedFilling => {
+                                    debug_adapter
+                                        .update_progress(
+                                            Some(1.0),
+                                            Some("Reading Old Pages Failed!"),
+                                            id,
+                                        )
+                                        .ok();
+                                }
+                                probe_rs::flashing::ProgressEvent::FinishedFilling => {
+                                    debug_adapter
+                                        .update_progress(
+                                            Some(1.0),
+                                            Some("Reading Old Pages Complete!"),
+                                            id,
+                                        )
+                                        .ok();
+                                }
+                                probe_rs::flashing::ProgressEvent::StartedErasing => {
+                                    debug_adapter
+                                        .update_progress(Some(0.0), Some("Erasing Sectors ..."), id)
+                                        .ok();
+                                }
+                                probe_rs::flashing::ProgressEvent::SectorErased {
+                                    size, ..
+                                } => {
+                                    flash_progress.sector_size_done += size as usize;
+                                    let progress = flash_progress.sector_size_done as f64
+                                        / flash_progress.total_sector_size as f64;
+                                    debug_adapter
+                                        .update_progress(
+                                            Some(progress),
+                                            Some(format!(
+                                                "Erasing Sectors ({:02.0}%)",
+                                                progress.mul(100_f64)
+                                            )),
+                                            id,
+                                        )
+                                        .ok();
+                                }
+                                probe_rs::flashing::ProgressEvent::FailedErasing => {
+                                    debug_adapter
+                                        .update_progress(
+                                            Some(1.0),
+                                            Some("Erasing Sectors Failed!"),
+                                            id,
+                                        )
+                                        .ok();
+                                }
+                                probe_rs::flashing::ProgressEvent::FinishedErasing => {
+                                    debug_adapter
+                                        .update_progress(
+                                            Some(1.0),
+                                            Some("Erasing Sectors Complete!"),
+                                            id,
+                                        )
+                                        .ok();
+                                }
+                                probe_rs::flashing::ProgressEvent::StartedProgramming => {
+                                    debug_adapter
+                                        .update_progress(
+                                            Some(0.0),
+                                            Some("Programming Pages ..."),
+                                            id,
+                                        )
+                                        .ok();
+                                }
+                                probe_rs::flashing::ProgressEvent::PageProgrammed {
+                                    size, ..
+                                } => {
+                                    flash_progress.page_size_done += size as usize;
+                                    let progress = flash_progress.page_size_done as f64
+                                        / flash_progress.total_page_size as f64;
+                                    debug_adapter
+                                        .update_progress(
+                                            Some(progress),
+                                            Some(format!(
+                                                "Programming Pages ({:02.0}%)",
+                                                progress.mul(100_f64)
+                                            )),
+                                            id,
+                                        )
+                                        .ok();
+                                }
+                                probe_rs::flashing::ProgressEvent::FailedProgramming => {
+                                    debug_adapter
+                                        .update_progress(
+                                            Some(1.0),
+                                            Some("Flashing Pages Failed!"),
+                                            id,
+                                        )
+                                        .ok();
+                                }
+                                probe_rs::flashing::ProgressEvent::FinishedProgramming => {
+                                    debug_adapter
+                                        .update_progress(
+                                            Some(1.0),
+                                            Some("Flashing Pages Complete!"),
+                                            id,
+                                        )
+                                        .ok();
+                                }
+                                probe_rs::flashing::ProgressEvent::SectorsSkipped {
+                                    count, ..
+                                } => {
+                                    debug_adapter
+                                        .update_progress(
+                                            None,
+                                            Some(format!("Skipped {} unchanged sectors", count)),
+                                            id,
+                                        )
+                                        .ok();
+                                }
+                                probe_rs::flashing::ProgressEvent::StartedVerifying => {
+                                    debug_adapter
+                                        .update_progress(Some(0.0), Some("Verifying Flash ..."), id)
+                                        .ok();
+                                }
+                                probe_rs::flashing::ProgressEvent::FailedVerifying => {
+                                    debug_adapter
+                                        .update_progress(
+                                            Some(1.0),
+                                            Some("Verifying Flash Failed!"),
+                                            id,
+                                        )
+                                        .ok();
+                                }
+                                probe_rs::flashing::ProgressEvent::FinishedVerifying => {
+                                    debug_adapter
+                                        .update_progress(
+                                            Some(1.0),
+                                            Some("Verifying Flash Complete!"),
+                                            id,
+                                        )
+                                        .ok();
+                                }
+                                probe_rs::flashing::ProgressEvent::Finished { .. } => {}
+                                _ => {}
+                            }
+                        })
+                    } else {
+                        FlashProgress::new(|_event| {})
+                    };
+                    download_options.progress = Some(&flash_progress);
+                    download_file_with_options(
+                        &mut session_data.session,
+                        &path_to_elf,
+                        Format::Elf,
+                        download_options,
+                    )
+                };
+                debug_adapter = match Rc::try_unwrap(rc_debug_adapter) {
+                    Ok(debug_adapter) => debug_adapter.into_inner(),
+                    Err(too_many_strong_references) => {
+                        let other_error = DebuggerError::Other(anyhow!("Unexpected error while dereferencing the `debug_adapter` (It has {} strong references). Please report this as a bug.", Rc::strong_count(&too_many_strong_references)));
+                        return Err(other_error);
+                    }
+                };
+
+                if let Some(id) = progress_id {
+                    let _ = debug_adapter.end_progress(id);
+                }
+                flash_result
+            };
+
+            match flash_result {
+                Ok(_) => {
+                    debug_adapter.log_to_console(format!(
+                        "INFO: FLASHING: Completed write of {:?} to device memory",
+                        &path_to_elf
+                    ));
+                }
+                Err(error) => {
+                    return Ok((debug_adapter, Err(DebuggerError::FileDownload(error))));
+                }
+            }
+        }
+        Ok((debug_adapter, Ok(())))
+    }
+
     /// `debug_session` is where the primary _debug processing_ for the DAP (Debug Adapter Protocol) adapter happens.
     /// All requests are interpreted, actions taken, and responses formulated here. This function is self contained and returns nothing.
     /// The [`DebugAdapter`] takes care of _implementing the DAP Base Protocol_ and _communicating with the DAP client_ and _probe_.
@@ -503,12 +940,12 @@ impl Debugger {
             supports_clipboard_context: Some(true),
             supports_disassemble_request: Some(true),
             supports_instruction_breakpoints: Some(true),
+            supports_data_breakpoints: Some(true),
             supports_stepping_granularity: Some(true),
+            exception_breakpoint_filters: Some(exception_breakpoint_filters()),
+            supports_exception_info_request: Some(true),
             // supports_value_formatting_options: Some(true),
             // supports_function_breakpoints: Some(true),
-            // TODO: Use DEMCR register to implement exception breakpoints
-            // supports_exception_options: Some(true),
-            // supports_exception_filter_options: Some (true),
             ..Default::default()
         };
         debug_adapter.send_response(initialize_request, Ok(Some(capabilities)))?;
@@ -546,7 +983,6 @@ impl Debugger {
             };
         };
 
-        // TODO: Multi-core: This currently only supports the first `SessionConfig::core_configs`
         match get_arguments(&launch_attach_request) {
             Ok(arguments) => {
                 if requested_target_session_type.is_some() {
@@ -561,15 +997,16 @@ impl Debugger {
                             || self.config.flashing_config.halt_after_reset
                             || self.config.flashing_config.full_chip_erase
                             || self.config.flashing_config.restore_unwritten_bytes
+                            || self.config.flashing_config.verify_after_flashing
                         {
                             debug_adapter.send_response::<()>(
                                         launch_attach_request,
                                         Err(DebuggerError::Other(anyhow!(
-                                            "Please do not use any of the `flashing_enabled`, `reset_after_flashing`, halt_after_reset`, `full_chip_erase`, or `restore_unwritten_bytes` options when using `attach` request type."))),
+                                            "Please do not use any of the `flashing_enabled`, `reset_after_flashing`, halt_after_reset`, `full_chip_erase`, `restore_unwritten_bytes`, or `verify_after_flashing` options when using `attach` request type."))),
                                     )?;
 
                             return Err(DebuggerError::Other(anyhow!(
-                                            "Please do not use any of the `flashing_enabled`, `reset_after_flashing`, halt_after_reset`, `full_chip_erase`, or `restore_unwritten_bytes` options when using `attach` request type.")));
+                                            "Please do not use any of the `flashing_enabled`, `reset_after_flashing`, halt_after_reset`, `full_chip_erase`, `restore_unwritten_bytes`, or `verify_after_flashing` options when using `attach` request type.")));
                         }
                     }
                 }
@@ -607,266 +1044,32 @@ impl Debugger {
             }
         };
 
-        // TODO: Currently the logic of processing MS DAP requests and executing them, is based on having a single core. It needs to be re-thought for multiple cores. Not all DAP requests require access to the core. One possible is to do the core attach inside each of the request implementations for those that need it, because the applicable core_index can be read from the request arguments.
-        // TODO: Until we refactor this, we only support a single core (always the first one specified in `SessionConfig::core_configs`)
-        let target_core_config =
-            if let Some(target_core_config) = self.config.core_configs.first_mut() {
-                target_core_config
-            } else {
-                return Err(DebuggerError::Other(anyhow!(
-                    "Cannot continue unless one target core configuration is defined."
-                )));
-            };
-
-        debug_adapter.halt_after_reset = self.config.flashing_config.halt_after_reset;
-        // Do the flashing.
-        // TODO: Multi-core ... needs to flash multiple binaries
+        // Requests without a `threadId` go to the first configured core, until another core halts.
+        self.current_core_index = if let Some(target_core_config) = self.config.core_configs.first()
         {
-            if self.config.flashing_config.flashing_enabled {
-                let path_to_elf = match &target_core_config.program_binary {
-                    Some(program_binary) => program_binary,
-                    None => {
-                        let err = DebuggerError::Other(anyhow!(
-                            "Please use the --program-binary option to specify an executable"
-                        ));
-                        debug_adapter.send_error_response(&err)?;
-                        return Err(err);
-                    }
-                };
-                debug_adapter.log_to_console(format!(
-                    "INFO: FLASHING: Starting write of {:?} to device memory",
-                    &path_to_elf
-                ));
-
-                let progress_id = debug_adapter
-                    .start_progress("Flashing device", Some(launch_attach_request.seq))
-                    .ok();
-
-                let mut download_options = DownloadOptions::default();
-                download_options.keep_unwritten_bytes =
-                    self.config.flashing_config.restore_unwritten_bytes;
-                download_options.do_chip_erase = self.config.flashing_config.full_chip_erase;
-                let flash_result = {
-                    let rc_debug_adapter = Rc::new(RefCell::new(debug_adapter));
-                    let rc_debug_adapter_clone = rc_debug_adapter.clone();
-                    let flash_result = {
-                        struct ProgressState {
-                            total_page_size: usize,
-                            total_sector_size: usize,
-                            total_fill_size: usize,
-                            page_size_done: usize,
-                            sector_size_done: usize,
-                            fill_size_done: usize,
-                        }
-
-                        let flash_progress = Rc::new(RefCell::new(ProgressState {
-                            total_page_size: 0,
-                            total_sector_size: 0,
-                            total_fill_size: 0,
-                            page_size_done: 0,
-                            sector_size_done: 0,
-                            fill_size_done: 0,
-                        }));
-
-                        let flash_progress = if let Some(id) = progress_id {
-                            FlashProgress::new(move |event| {
-                                let mut flash_progress = flash_progress.borrow_mut();
-                                let mut debug_adapter = rc_debug_adapter_clone.borrow_mut();
-                                match event {
-                                    probe_rs::flashing::ProgressEvent::Initialized {
-                                        flash_layout,
-                                    } => {
-                                        flash_progress.total_page_size = flash_layout
-                                            .pages()
-                                            .iter()
-                                            .map(|s| s.size() as usize)
-                                            .sum();
-
-                                        flash_progress.total_sector_size = flash_layout
-                                            .sectors()
-                                            .iter()
-                                            .map(|s| s.size() as usize)
-                                            .sum();
-
-                                        flash_progress.total_fill_size = flash_layout
-                                            .fills()
-                                            .iter()
-                                            .map(|s| s.size() as usize)
-                                            .sum();
-                                    }
-                                    probe_rs::flashing::ProgressEvent::StartedFilling => {
-                                        debug_adapter
-                                            .update_progress(
-                                                Some(0.0),
-                                                Some("Reading Old Pages ..."),
-                                                id,
-                                            )
-                                            .ok();
-                                    }
-                                    probe_rs::flashing::ProgressEvent::PageFilled {
-                                        size, ..
-                                    } => {
-                                        flash_progress.fill_size_done += size as usize;
-                                        let progress = flash_progress.fill_size_done as f64
-                                            / flash_progress.total_fill_size as f64;
-                                        debug_adapter
-                                            .update_progress(
-                                                Some(progress),
-                                                Some(format!("Reading Old Pages ({})", progress)),
-                                                id,
-                                            )
-                                            .ok();
-                                    }
-                                    probe_rs::flashing::ProgressEvent::FailedFilling => {
-                                        debug_adapter
-                                            .update_progress(
-                                                Some(1.0),
-                                                Some("Reading Old Pages Failed!"),
-                                                id,
-                                            )
-                                            .ok();
-                                    }
-                                    probe_rs::flashing::ProgressEvent::FinishedFilling => {
-                                        debug_adapter
-                                            .update_progress(
-                                                Some(1.0),
-                                                Some("Reading Old Pages Complete!"),
-                                                id,
-                                            )
-                                            .ok();
-                                    }
-                                    probe_rs::flashing::ProgressEvent::StartedErasing => {
-                                        debug_adapter
-                                            .update_progress(
-                                                Some(0.0),
-                                                Some("Erasing Sectors ..."),
-                                                id,
-                                            )
-                                            .ok();
-                                    }
-                                    probe_rs::flashing::ProgressEvent::SectorErased {
-                                        size,
-                                        ..
-                                    } => {
-                                        flash_progress.sector_size_done += size as usize;
-                                        let progress = flash_progress.sector_size_done as f64
-                                            / flash_progress.total_sector_size as f64;
-                                        debug_adapter
-                                            .update_progress(
-                                                Some(progress),
-                                                Some(format!("Erasing Sectors ({})", progress)),
-                                                id,
-                                            )
-                                            .ok();
-                                    }
-                                    probe_rs::flashing::ProgressEvent::FailedErasing => {
-                                        debug_adapter
-                                            .update_progress(
-                                                Some(1.0),
-                                                Some("Erasing Sectors Failed!"),
-                                                id,
-                                            )
-                                            .ok();
-                                    }
-                                    probe_rs::flashing::ProgressEvent::FinishedErasing => {
-                                        debug_adapter
-                                            .update_progress(
-                                                Some(1.0),
-                                                Some("Erasing Sectors Complete!"),
-                                                id,
-                                            )
-                                            .ok();
-                                    }
-                                    probe_rs::flashing::ProgressEvent::StartedProgramming => {
-                                        debug_adapter
-                                            .update_progress(
-                                                Some(0.0),
-                                                Some("Programming Pages ..."),
-                                                id,
-                                            )
-                                            .ok();
-                                    }
-                                    probe_rs::flashing::ProgressEvent::PageProgrammed {
-                                        size,
-                                        ..
-                                    } => {
-                                        flash_progress.page_size_done += size as usize;
-                                        let progress = flash_progress.page_size_done as f64
-                                            / flash_progress.total_page_size as f64;
-                                        debug_adapter
-                                            .update_progress(
-                                                Some(progress),
-                                                Some(format!(
-                                                    "Programming Pages ({:02.0}%)",
-                                                    progress.mul(100_f64)
-                                                )),
-                                                id,
-                                            )
-                                            .ok();
-                                    }
-                                    probe_rs::flashing::ProgressEvent::FailedProgramming => {
-                                        debug_adapter
-                                            .update_progress(
-                                                Some(1.0),
-                                                Some("Flashing Pages Failed!"),
-                                                id,
-                                            )
-                                            .ok();
-                                    }
-                                    probe_rs::flashing::ProgressEvent::FinishedProgramming => {
-                                        debug_adapter
-                                            .update_progress(
-                                                Some(1.0),
-                                                Some("Flashing Pages Complete!"),
-                                                id,
-                                            )
-                                            .ok();
-                                    }
-                                }
-                            })
-                        } else {
-                            FlashProgress::new(|_event| {})
-                        };
-                        download_options.progress = Some(&flash_progress);
-                        download_file_with_options(
-                            &mut session_data.session,
-                            &path_to_elf,
-                            Format::Elf,
-                            download_options,
-                        )
-                    };
-                    debug_adapter = match Rc::try_unwrap(rc_debug_adapter) {
-                        Ok(debug_adapter) => debug_adapter.into_inner(),
-                        Err(too_many_strong_references) => {
-                            let other_error = DebuggerError::Other(anyhow!("Unexpected error while dereferencing the `debug_adapter` (It has {} strong references). Please report this as a bug.", Rc::strong_count(&too_many_strong_references)));
-                            return Err(other_error);
-                        }
-                    };
-
-                    if let Some(id) = progress_id {
-                        let _ = debug_adapter.end_progress(id);
-                    }
-                    flash_result
-                };
+            target_core_config.core_index
+        } else {
+            return Err(DebuggerError::Other(anyhow!(
+                "Cannot continue unless one target core configuration is defined."
+            )));
+        };
 
-                match flash_result {
-                    Ok(_) => {
-                        debug_adapter.log_to_console(format!(
-                            "INFO: FLASHING: Completed write of {:?} to device memory",
-                            &path_to_elf
-                        ));
-                    }
-                    Err(error) => {
-                        let error = DebuggerError::FileDownload(error);
-                        debug_adapter.send_error_response(&error)?;
-                        return Err(error);
-                    }
-                }
+        // Do the flashing.
+        if self.config.flashing_config.flashing_enabled {
+            let (returned_debug_adapter, flash_result) = self.flash_program_binaries(
+                &mut session_data,
+                debug_adapter,
+                launch_attach_request.seq,
+            )?;
+            debug_adapter = returned_debug_adapter;
+            if let Err(error) = flash_result {
+                debug_adapter.send_error_response(&error)?;
+                return Err(error);
             }
         }
 
-        // This is the first attach to the requested core. If this one works, all subsequent ones will be no-op requests for a Core reference. Do NOT hold onto this reference for the duration of the session ... that is why this code is in a block of its own.
-        {
+        // This is the first attach to each of the configured cores. If this one works, all subsequent ones will be no-op requests for a Core reference. Do NOT hold onto this reference for the duration of the session ... that is why this code is in a block of its own.
+        for target_core_config in self.config.core_configs.iter() {
             // First, attach to the core
             let mut target_core = match session_data.attach_core(target_core_config.core_index) {
                 Ok(mut target_core) => {
@@ -907,7 +1110,7 @@ impl Debugger {
                 && self.config.flashing_config.reset_after_flashing
             {
                 debug_adapter
-                    .restart(&mut target_core, None)
+                    .restart(&mut target_core)
                     .context("Failed to restart core")?;
             }
         }
@@ -936,6 +1139,18 @@ impl Debugger {
                 Ok(DebuggerStatus::TerminateSession) => {
                     return Ok(DebuggerStatus::TerminateSession);
                 }
+                Ok(DebuggerStatus::RestartSession(request)) => {
+                    let (returned_debug_adapter, flash_result) =
+                        self.flash_program_binaries(&mut session_data, debug_adapter, request.seq)?;
+                    debug_adapter = returned_debug_adapter;
+                    // A failed flashing only fails the `restart` request, the session continues.
+                    // The program binaries may have been rebuilt, so reload their debug information.
+                    // Breakpoints remain set at their addresses.
+                    let result = flash_result
+                        .and_then(|()| session_data.reload_debug_info(&self.config))
+                        .and_then(|()| self.restart_cores(&mut session_data, &mut debug_adapter));
+                    debug_adapter.send_response::<()>(request, result.map(|()| None))?;
+                }
                 Err(e) => {
                     debug_adapter.show_message(
                         MessageSeverity::Error,
@@ -1050,7 +1265,8 @@ pub fn debug(port: Option<u16>, vscode: bool) -> Result<()> {
                                     &program_name, addr
                                 );
                             }
-                            Ok(DebuggerStatus::ContinueSession) => {
+                            Ok(DebuggerStatus::ContinueSession)
+                            | Ok(DebuggerStatus::RestartSession(_)) => {
                                 log::error!("probe-rs-debugger enountered unexpected `DebuggerStatus` in debug() execution. Please report this as a bug.");
                             }
                         }
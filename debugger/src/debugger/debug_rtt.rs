@@ -31,6 +31,8 @@ pub(crate) struct DebuggerRttChannel {
     pub(crate) channel_number: usize,
     // We will not poll target RTT channels until we have confirmation from the client that the output window has been opened.
     pub(crate) has_client_window: bool,
+    // The data is sent to the debug console as `output` events, instead of to a window, which is available immediately.
+    pub(crate) debug_console: bool,
 }
 
 impl DebuggerRttChannel {
@@ -43,7 +45,7 @@ impl DebuggerRttChannel {
         debug_adapter: &mut DebugAdapter<P>,
         rtt_target: &mut rtt::RttActiveTarget,
     ) -> bool {
-        if self.has_client_window {
+        if self.has_client_window || self.debug_console {
             rtt_target
                 .active_channels
                 .iter_mut()
@@ -54,13 +56,15 @@ impl DebuggerRttChannel {
                         false
                     }
                 })
-                .and_then(|rtt_channel| {
-                    rtt_channel.get_rtt_data(core, rtt_target.defmt_state.as_ref())
-                })
+                .and_then(|rtt_channel| rtt_channel.get_rtt_data(core))
                 .and_then(|(channel_number, channel_data)| {
-                    if debug_adapter
-                        .rtt_output(channel_number.parse::<usize>().unwrap_or(0), channel_data)
-                    {
+                    let channel_number = channel_number.parse::<usize>().unwrap_or(0);
+                    let sent = if self.debug_console {
+                        debug_adapter.rtt_console_output(channel_number, channel_data)
+                    } else {
+                        debug_adapter.rtt_output(channel_number, channel_data)
+                    };
+                    if sent {
                         Some(true)
                     } else {
                         None
@@ -0,0 +1,134 @@
+use super::core_data::CoreHandle;
+use anyhow::{anyhow, Result};
+use probe_rs::{
+    debug::{ExpressionValue, Variable, VariableCache, VariableName, VariableType, VariableValue},
+    Core,
+};
+use std::time::{Duration, Instant};
+
+/// The refresh interval of expressions which don't specify one.
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_millis(1000);
+/// The shortest refresh interval, which limits the load on the probe.
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_millis(50);
+
+/// An expression which is watched while the core runs, and refreshed at its own interval.
+/// Only static variables with a base type can be watched, because their memory location is fixed, and their value can be read without halting the core.
+pub(crate) struct LiveWatch {
+    pub(crate) expression: String,
+    refresh_interval: Duration,
+    /// The variable of the expression, which is resolved with the stack frames of the first halt.
+    variable: Option<Variable>,
+    next_refresh: Instant,
+    /// The last value sent to the client, so only changes are sent.
+    last_value: Option<String>,
+}
+
+impl LiveWatch {
+    /// Create a live watch, with the `refresh_interval` in milliseconds.
+    pub(crate) fn new(expression: String, refresh_interval: Option<u64>) -> Self {
+        Self {
+            expression,
+            refresh_interval: refresh_interval
+                .map(Duration::from_millis)
+                .unwrap_or(DEFAULT_REFRESH_INTERVAL)
+                .max(MIN_REFRESH_INTERVAL),
+            variable: None,
+            next_refresh: Instant::now(),
+            last_value: None,
+        }
+    }
+
+    /// Refresh the value if it is due, and return it if it changed since the last refresh.
+    pub(crate) fn refresh(&mut self, target_core: &mut CoreHandle, now: Instant) -> Option<String> {
+        if now < self.next_refresh {
+            return None;
+        }
+        self.next_refresh = now + self.refresh_interval;
+
+        let value = match &self.variable {
+            Some(variable) => read_value(&mut target_core.core, variable),
+            None => match resolve_static_variable(target_core, &self.expression) {
+                Ok(variable) => {
+                    let value = read_value(&mut target_core.core, &variable);
+                    self.variable = Some(variable);
+                    value
+                }
+                // Try again at the next refresh, e.g. after the core halted for the first time.
+                Err(error) => format!("<{}>", error),
+            },
+        };
+
+        if self.last_value.as_ref() == Some(&value) {
+            None
+        } else {
+            self.last_value = Some(value.clone());
+            Some(value)
+        }
+    }
+}
+
+/// Evaluate `expression` while the core runs, with the current value of the static variable in memory.
+pub(crate) fn evaluate_static_variable(target_core: &mut CoreHandle, expression: &str) -> String {
+    match resolve_static_variable(target_core, expression) {
+        Ok(variable) => read_value(&mut target_core.core, &variable),
+        Err(error) => format!("<{}>", error),
+    }
+}
+
+/// Find the static variable of `expression`, using the stack frames of the last halt.
+fn resolve_static_variable(target_core: &mut CoreHandle, expression: &str) -> Result<Variable> {
+    let stack_frame = target_core
+        .core_data
+        .stack_frames
+        .first_mut()
+        .ok_or_else(|| anyhow!("The variables are available once the core has halted."))?;
+
+    let variable = match stack_frame
+        .static_variables
+        .as_ref()
+        .and_then(|static_variables| {
+            static_variables.get_variable_by_name(&VariableName::Named(expression.to_string()))
+        }) {
+        Some(variable) => variable,
+        None => match target_core.core_data.debug_info.evaluate_expression(
+            &mut target_core.core,
+            stack_frame,
+            expression,
+        )? {
+            ExpressionValue::Variable(variable) => variable,
+            ExpressionValue::Integer(_) => {
+                return Err(anyhow!("`{}` is not a variable.", expression))
+            }
+        },
+    };
+
+    // Local variables move with the stack frame, so their memory can't be read while the core runs.
+    if stack_frame
+        .static_variables
+        .as_ref()
+        .and_then(|static_variables| static_variables.get_variable_by_key(variable.variable_key))
+        .is_none()
+    {
+        return Err(anyhow!(
+            "Only static variables can be watched while the core runs."
+        ));
+    }
+
+    if !matches!(variable.type_name, VariableType::Base(_)) {
+        return Err(anyhow!(
+            "Only variables with a base type can be watched while the core runs."
+        ));
+    }
+
+    Ok(variable)
+}
+
+/// Read the current value of a variable with a base type from memory.
+fn read_value(core: &mut Core, variable: &Variable) -> String {
+    let mut variable = variable.clone();
+    // Base types don't have children, so they don't need the cache.
+    let variable_cache = VariableCache::new();
+    variable.set_value(VariableValue::Empty);
+    variable.extract_value(core, &variable_cache);
+    variable.get_value(&variable_cache)
+}
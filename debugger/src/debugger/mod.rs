@@ -6,5 +6,7 @@ pub(crate) mod core_data;
 pub(crate) mod debug_entry;
 /// The debugger support for rtt.
 pub(crate) mod debug_rtt;
+/// Watch expressions which are refreshed while the core runs.
+pub(crate) mod live_watch;
 /// The data structures needed to keep track of a [`SessionData`].
 pub(crate) mod session_data;
@@ -174,7 +174,11 @@ impl SessionData {
                 core_peripherals: None,
                 stack_frames: Vec::<probe_rs::debug::stack_frame::StackFrame>::new(),
                 breakpoints: Vec::<ActiveBreakpoint>::new(),
+                data_breakpoints: Vec::new(),
+                exception_breakpoints: Vec::new(),
                 rtt_connection: None,
+                live_watches: Vec::new(),
+                last_known_status: CoreStatus::Unknown,
             })
         }
 
@@ -203,6 +207,26 @@ impl SessionData {
         }
     }
 
+    /// Reload the [DebugInfo] of each core from its `program_binary`, e.g. after the binary was flashed again.
+    pub(crate) fn reload_debug_info(
+        &mut self,
+        config: &SessionConfig,
+    ) -> Result<(), DebuggerError> {
+        for core_config in config.core_configs.iter() {
+            if let (Some(core_data), Some(program_binary)) = (
+                self.core_data
+                    .iter_mut()
+                    .find(|core_data| core_data.core_index == core_config.core_index),
+                &core_config.program_binary,
+            ) {
+                core_data.debug_info = DebugInfo::from_file(program_binary)
+                    .map_err(|error| DebuggerError::Other(anyhow!(error)))?;
+                core_data.stack_frames.clear();
+            }
+        }
+        Ok(())
+    }
+
     /// Check all target cores to ensure they have a configured and initialized RTT connections and if they do, process the RTT data.
     /// Return true if at least one channel on one core had data in the buffer.
     pub(crate) fn poll_rtt<P: ProtocolAdapter>(
@@ -221,7 +245,7 @@ impl SessionData {
                             core_rtt.process_rtt_data(debug_adapter, &mut target_core.core);
                     } else {
                         // We have not yet reached the point in the target application where the RTT buffers are initialized, so let's check again.
-                        if debug_adapter.last_known_status != CoreStatus::Unknown
+                        if target_core.core_data.last_known_status != CoreStatus::Unknown
                         // Do not attempt this until we have processed the MSDAP request for "configurationDone" ...
                         {
                             #[allow(clippy::unwrap_used)]
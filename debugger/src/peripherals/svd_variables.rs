@@ -6,9 +6,9 @@ use probe_rs::{
     debug::{
         Variable, VariableCache, VariableLocation, VariableName, VariableNodeType, VariableType,
     },
-    Core,
+    Core, MemoryInterface,
 };
-use std::{fmt::Debug, fs::File, io::Read, path::Path};
+use std::{collections::HashSet, fmt::Debug, fs::File, io::Read, path::Path};
 use svd_parser::{
     self as svd,
     svd::{Access, Device},
@@ -22,6 +22,8 @@ pub(crate) struct SvdCache {
     /// Unlike other VariableCache instances, it will only be built once per DebugSession.
     /// After that, only the SVD fields values change values, and the data for these will be re-read everytime they are queried by the debugger.
     pub(crate) svd_variable_cache: VariableCache,
+    /// The keys of the registers and fields whose access doesn't allow writing.
+    read_only_variables: HashSet<i64>,
 }
 
 impl SvdCache {
@@ -53,13 +55,16 @@ impl SvdCache {
                             )
                             .ok();
 
+                        let mut read_only_variables = HashSet::new();
                         Ok(SvdCache {
                             svd_variable_cache: variable_cache_from_svd(
                                 peripheral_device,
                                 core,
                                 debug_adapter,
                                 progress_id,
+                                &mut read_only_variables,
                             )?,
+                            read_only_variables,
                         })
                     }
                     Err(error) => Err(DebuggerError::Other(anyhow::anyhow!(
@@ -74,14 +79,91 @@ impl SvdCache {
             Err(error) => Err(DebuggerError::Other(anyhow::anyhow!("{}", error))),
         }
     }
+
+    /// Write `new_value` to the SVD register or field `variable`, and return the value read back from the target.
+    /// Fields are written with a read-modify-write of their register, so the other fields of the register keep their values.
+    pub(crate) fn write_variable(
+        &self,
+        core: &mut Core,
+        variable: &Variable,
+        new_value: &str,
+    ) -> Result<String, DebuggerError> {
+        if self.read_only_variables.contains(&variable.variable_key) {
+            return Err(DebuggerError::Other(anyhow::anyhow!(
+                "{} is read-only.",
+                variable.name
+            )));
+        }
+
+        let value: u32 = parse_int::parse(new_value).map_err(|error| {
+            DebuggerError::Other(anyhow::anyhow!(
+                "Invalid value {:?} for {}: {:?}",
+                new_value,
+                variable.name,
+                error
+            ))
+        })?;
+        let address = variable
+            .memory_location
+            .memory_address()
+            .map_err(|error| DebuggerError::Other(anyhow::anyhow!(error)))?;
+
+        match variable.variable_node_type {
+            VariableNodeType::SvdRegister => core.write_word_32(address, value)?,
+            VariableNodeType::SvdField => {
+                // The register is read first, so its fields have to be readable without side effects.
+                if !variable
+                    .parent_key
+                    .and_then(|parent_key| self.svd_variable_cache.get_variable_by_key(parent_key))
+                    .map_or(false, |register| register.is_valid())
+                {
+                    return Err(DebuggerError::Other(anyhow::anyhow!(
+                        "The register of {} can't be read, so the field can't be written without changing the other fields.",
+                        variable.name
+                    )));
+                }
+
+                // For SVD fields, the range_lower_bound and range_upper_bound are the bit range LSB and MSB.
+                let bit_offset = variable.range_lower_bound as u32;
+                let bit_width = (variable.range_upper_bound - variable.range_lower_bound) as u32;
+                let field_mask = u32::MAX.checked_shr(32 - bit_width).unwrap_or(0);
+                if value & !field_mask != 0 {
+                    return Err(DebuggerError::Other(anyhow::anyhow!(
+                        "The value {:#x} doesn't fit in the {} bits of {}.",
+                        value,
+                        bit_width,
+                        variable.name
+                    )));
+                }
+
+                let register_value = core.read_word_32(address)?;
+                core.write_word_32(
+                    address,
+                    (register_value & !(field_mask << bit_offset)) | (value << bit_offset),
+                )?;
+            }
+            _ => {
+                return Err(DebuggerError::Other(anyhow::anyhow!(
+                    "Only peripheral registers and fields can be written, not {}.",
+                    variable.name
+                )))
+            }
+        }
+
+        let mut updated_variable = variable.clone();
+        updated_variable.extract_value(core, &self.svd_variable_cache);
+        Ok(updated_variable.get_value(&self.svd_variable_cache))
+    }
 }
 
 /// Create a [`probe_rs::debug::VariableCache`] from a Device that was parsed from a CMSIS-SVD file.
+/// The keys of the registers and fields which can't be written are added to `read_only_variables`.
 pub(crate) fn variable_cache_from_svd<P: ProtocolAdapter>(
     peripheral_device: Device,
     core: &mut Core,
     debug_adapter: &mut DebugAdapter<P>,
     progress_id: i64,
+    read_only_variables: &mut HashSet<i64>,
 ) -> Result<probe_rs::debug::VariableCache, DebuggerError> {
     let mut svd_cache = probe_rs::debug::VariableCache::new();
     let mut device_root_variable = Variable::new(None, None);
@@ -192,6 +274,10 @@ pub(crate) fn variable_cache_from_svd<P: ProtocolAdapter>(
                 register_variable,
                 core,
             )?;
+            let register_is_read_only = register.properties.access == Some(Access::ReadOnly);
+            if register_is_read_only {
+                read_only_variables.insert(register_variable.variable_key);
+            }
             for field in register.fields() {
                 let mut field_variable = Variable::new(None, None);
                 field_variable.name = VariableName::Named(format!(
@@ -239,11 +325,14 @@ pub(crate) fn variable_cache_from_svd<P: ProtocolAdapter>(
                     )?;
                 }
                 // TODO: Extend the Variable definition, so that we can resolve the EnumeratedValues for fields.
-                svd_cache.cache_variable(
+                let field_variable = svd_cache.cache_variable(
                     Some(register_variable.variable_key),
                     field_variable,
                     core,
                 )?;
+                if register_is_read_only || field.access == Some(Access::ReadOnly) {
+                    read_only_variables.insert(field_variable.variable_key);
+                }
             }
         }
     }
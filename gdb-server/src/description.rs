@@ -0,0 +1,454 @@
+//! The target description, which tells gdb which registers the core has.
+//!
+//! The description is built from the register file of the core, and served with
+//! `qXfer:features:read`. It uses the standard gdb features for each architecture, so gdb knows
+//! the meaning of the registers, e.g. the FPU and the special registers of ARM M-profile cores.
+//!
+//! The registers are numbered in the order in which they are described, which is also their
+//! order in the `g` packet. [`TargetDescription::read_register`] and
+//! [`TargetDescription::write_register`] access a register by that number.
+
+use probe_rs::{Core, CoreType, Error, RegisterDescription, RegisterFile, RegisterId};
+use std::fmt::Write;
+use std::ops::Range;
+
+/// Where the value of a register in the target description comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RegisterSource {
+    /// A core register.
+    Core(RegisterId),
+    /// A field at the given bit offset of a core register, for registers which are packed into
+    /// one core register, like the special registers of ARM M-profile cores.
+    Field(RegisterId, u32),
+    /// Two 32 bit core registers, the low half first, like the double precision registers of an
+    /// ARM FPU, which are read as two single precision registers.
+    Pair(RegisterId, RegisterId),
+}
+
+/// A register in the target description.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct GdbRegister {
+    pub(crate) name: String,
+    pub(crate) bitsize: u32,
+    /// The gdb type of the register, e.g. `int` or `ieee_double`.
+    pub(crate) gdb_type: &'static str,
+    pub(crate) source: RegisterSource,
+}
+
+/// A feature of the target description, with the registers which belong to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Feature {
+    name: &'static str,
+    /// Type definitions which are used by the registers of the feature.
+    types: &'static str,
+    registers: Range<usize>,
+}
+
+/// The flags of the XPSR register of ARM M-profile cores.
+const XPSR_FLAGS: &str = r#"<flags id="xpsr_flags" size="4">
+      <field name="N" start="31" end="31"/>
+      <field name="Z" start="30" end="30"/>
+      <field name="C" start="29" end="29"/>
+      <field name="V" start="28" end="28"/>
+      <field name="Q" start="27" end="27"/>
+      <field name="T" start="24" end="24"/>
+      <field name="GE" start="16" end="19"/>
+      <field name="EXCEPTION" start="0" end="8"/>
+    </flags>"#;
+
+/// The target description of a core.
+#[derive(Debug, Default)]
+pub(crate) struct TargetDescription {
+    architecture: &'static str,
+    features: Vec<Feature>,
+    registers: Vec<GdbRegister>,
+}
+
+impl TargetDescription {
+    /// Builds the target description of `core`.
+    pub(crate) fn new(core: &mut Core) -> Result<Self, Error> {
+        let core_type = core.core_type();
+
+        // The FPU registers are only described if the core has an FPU. Cores which can't detect
+        // their FPU yet are described without it.
+        let fpu = match core.fpu_support() {
+            Ok(fpu) => fpu,
+            Err(Error::Other(error)) => {
+                log::debug!("Describing the core without an FPU: {}", error);
+                false
+            }
+            Err(error) => return Err(error),
+        };
+
+        Ok(Self::build(core_type, core.registers(), fpu))
+    }
+
+    fn build(core_type: CoreType, registers: &RegisterFile, fpu: bool) -> Self {
+        let mut description = Self::default();
+
+        match core_type {
+            CoreType::Armv6m | CoreType::Armv7m | CoreType::Armv7em | CoreType::Armv8m => {
+                description.cortex_m(core_type, registers, fpu)
+            }
+            CoreType::Armv8a if registers.program_counter().size_in_bits() == 64 => {
+                description.aarch64(registers)
+            }
+            CoreType::Armv7a | CoreType::Armv7r | CoreType::Armv8a => {
+                description.arm(registers, fpu)
+            }
+            CoreType::Riscv => description.riscv(registers),
+            CoreType::Mips32 => description.mips(registers),
+        }
+
+        description
+    }
+
+    fn cortex_m(&mut self, core_type: CoreType, registers: &RegisterFile, fpu: bool) {
+        self.architecture = "arm";
+
+        self.feature("org.gnu.gdb.arm.m-profile", XPSR_FLAGS, |description| {
+            description.arm_core_registers(registers);
+            if let Some(psr) = registers.psr() {
+                description.register("xpsr", psr, "xpsr_flags");
+            }
+        });
+
+        if fpu {
+            self.vfp(registers);
+        }
+
+        self.feature("org.gnu.gdb.arm.m-system", "", |description| {
+            if let Some(msp) = registers.msp() {
+                description.register("msp", msp, "data_ptr");
+            }
+            if let Some(psp) = registers.psp() {
+                description.register("psp", psp, "data_ptr");
+            }
+
+            if let Some(extra) = registers.extra() {
+                let extra = RegisterId::from(extra);
+                let mut special = vec![("primask", 0)];
+                // ARMv6-M has no BASEPRI and FAULTMASK.
+                if core_type != CoreType::Armv6m {
+                    special.push(("basepri", 8));
+                    special.push(("faultmask", 16));
+                }
+                special.push(("control", 24));
+
+                for (name, offset) in special {
+                    description.registers.push(GdbRegister {
+                        name: name.to_owned(),
+                        bitsize: 8,
+                        gdb_type: "int",
+                        source: RegisterSource::Field(extra, offset),
+                    });
+                }
+            }
+        });
+    }
+
+    fn arm(&mut self, registers: &RegisterFile, fpu: bool) {
+        self.architecture = "arm";
+
+        self.feature("org.gnu.gdb.arm.core", "", |description| {
+            description.arm_core_registers(registers);
+            if let Some(psr) = registers.psr() {
+                description.register("cpsr", psr, "int");
+            }
+        });
+
+        if fpu {
+            self.vfp(registers);
+        }
+    }
+
+    /// Describes R0 to R12, SP, LR and PC.
+    fn arm_core_registers(&mut self, registers: &RegisterFile) {
+        for register in registers.registers().take(13) {
+            self.register(&register.name().to_lowercase(), register, "int");
+        }
+        self.register("sp", registers.stack_pointer(), "data_ptr");
+        self.register("lr", registers.return_address(), "int");
+        self.register("pc", registers.program_counter(), "code_ptr");
+    }
+
+    /// Describes the double precision registers of the FPU, which consist of two single precision
+    /// registers each.
+    fn vfp(&mut self, registers: &RegisterFile) {
+        let single: Vec<&RegisterDescription> = match registers.fpu_registers() {
+            Some(single) => single.collect(),
+            None => return,
+        };
+
+        self.feature("org.gnu.gdb.arm.vfp", "", |description| {
+            for (index, pair) in single.chunks_exact(2).enumerate() {
+                description.registers.push(GdbRegister {
+                    name: format!("d{}", index),
+                    bitsize: 64,
+                    gdb_type: "ieee_double",
+                    source: RegisterSource::Pair(pair[0].into(), pair[1].into()),
+                });
+            }
+
+            if let Some(fpscr) = registers.fpscr() {
+                description.register("fpscr", fpscr, "int");
+            }
+        });
+    }
+
+    fn aarch64(&mut self, registers: &RegisterFile) {
+        self.architecture = "aarch64";
+
+        self.feature("org.gnu.gdb.aarch64.core", "", |description| {
+            // X0 to X30, SP and PC.
+            for register in registers.registers() {
+                let gdb_type = match register.name() {
+                    "SP" => "data_ptr",
+                    "PC" => "code_ptr",
+                    _ => "int",
+                };
+                description.register(&register.name().to_lowercase(), register, gdb_type);
+            }
+
+            if let Some(psr) = registers.psr() {
+                description.register("cpsr", psr, "int");
+            }
+        });
+    }
+
+    fn riscv(&mut self, registers: &RegisterFile) {
+        self.architecture = "riscv:rv32";
+
+        self.feature("org.gnu.gdb.riscv.cpu", "", |description| {
+            for register in registers.registers() {
+                description.register(register.name(), register, "int");
+            }
+            description.register("pc", registers.program_counter(), "code_ptr");
+        });
+    }
+
+    fn mips(&mut self, registers: &RegisterFile) {
+        self.architecture = "mips";
+
+        let by_name = |name: &str| registers.registers().find(|r| r.name() == name);
+
+        self.feature("org.gnu.gdb.mips.cpu", "", |description| {
+            // gdb expects the general purpose registers by number, not by their ABI names.
+            for (index, register) in registers.registers().take(32).enumerate() {
+                description.register(&format!("r{}", index), register, "int");
+            }
+            for name in ["lo", "hi"] {
+                if let Some(register) = by_name(name) {
+                    description.register(name, register, "int");
+                }
+            }
+            description.register("pc", registers.program_counter(), "code_ptr");
+        });
+
+        self.feature("org.gnu.gdb.mips.cp0", "", |description| {
+            for (name, register) in [
+                ("status", "sr"),
+                ("badvaddr", "badvaddr"),
+                ("cause", "cause"),
+            ] {
+                if let Some(register) = by_name(register) {
+                    description.register(name, register, "int");
+                }
+            }
+        });
+    }
+
+    fn feature(
+        &mut self,
+        name: &'static str,
+        types: &'static str,
+        registers: impl FnOnce(&mut Self),
+    ) {
+        let start = self.registers.len();
+        registers(self);
+
+        self.features.push(Feature {
+            name,
+            types,
+            registers: start..self.registers.len(),
+        });
+    }
+
+    fn register(&mut self, name: &str, register: &RegisterDescription, gdb_type: &'static str) {
+        self.registers.push(GdbRegister {
+            name: name.to_owned(),
+            bitsize: register.size_in_bits() as u32,
+            gdb_type,
+            source: RegisterSource::Core(register.into()),
+        });
+    }
+
+    /// Returns the registers of the description, indexed by their gdb register number.
+    pub(crate) fn registers(&self) -> &[GdbRegister] {
+        &self.registers
+    }
+
+    /// Returns the target description document for `qXfer:features:read`.
+    pub(crate) fn xml(&self) -> String {
+        let mut xml = String::from(
+            r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+"#,
+        );
+
+        // Writing to a String can't fail.
+        writeln!(xml, "  <architecture>{}</architecture>", self.architecture).unwrap();
+
+        for feature in &self.features {
+            writeln!(xml, r#"  <feature name="{}">"#, feature.name).unwrap();
+            if !feature.types.is_empty() {
+                writeln!(xml, "    {}", feature.types).unwrap();
+            }
+
+            for regnum in feature.registers.clone() {
+                let register = &self.registers[regnum];
+                writeln!(
+                    xml,
+                    r#"    <reg name="{}" bitsize="{}" regnum="{}" type="{}"/>"#,
+                    register.name, register.bitsize, regnum, register.gdb_type
+                )
+                .unwrap();
+            }
+
+            xml.push_str("  </feature>\n");
+        }
+
+        xml.push_str("</target>\n");
+        xml
+    }
+
+    /// Reads register `regnum`, and returns its value in little endian byte order, or `None` if
+    /// there is no such register.
+    pub(crate) fn read_register(
+        &self,
+        core: &mut Core,
+        regnum: usize,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let register = match self.registers.get(regnum) {
+            Some(register) => register,
+            None => return Ok(None),
+        };
+
+        let value: u64 = match register.source {
+            RegisterSource::Core(id) => core.read_core_reg(id)?,
+            RegisterSource::Field(id, offset) => {
+                let value: u64 = core.read_core_reg(id)?;
+                (value >> offset) & ((1 << register.bitsize) - 1)
+            }
+            RegisterSource::Pair(low, high) => {
+                let low: u32 = core.read_core_reg(low)?;
+                let high: u32 = core.read_core_reg(high)?;
+                (high as u64) << 32 | low as u64
+            }
+        };
+
+        let bytes = (register.bitsize as usize + 7) / 8;
+        Ok(Some(value.to_le_bytes()[..bytes].to_vec()))
+    }
+
+    /// Writes register `regnum` with a value in little endian byte order, and returns `false` if
+    /// there is no such register.
+    pub(crate) fn write_register(
+        &self,
+        core: &mut Core,
+        regnum: usize,
+        bytes: &[u8],
+    ) -> Result<bool, Error> {
+        let register = match self.registers.get(regnum) {
+            Some(register) => register,
+            None => return Ok(false),
+        };
+
+        let mut buffer = [0u8; 8];
+        let length = bytes.len().min(buffer.len());
+        buffer[..length].copy_from_slice(&bytes[..length]);
+        let value = u64::from_le_bytes(buffer);
+
+        match register.source {
+            RegisterSource::Core(id) if register.bitsize <= 32 => {
+                core.write_core_reg(id, value as u32)?
+            }
+            RegisterSource::Core(id) => core.write_core_reg(id, value)?,
+            RegisterSource::Field(id, offset) => {
+                let mask = ((1u32 << register.bitsize) - 1) << offset;
+                let current: u32 = core.read_core_reg(id)?;
+                let field = ((value as u32) << offset) & mask;
+                core.write_core_reg(id, (current & !mask) | field)?
+            }
+            RegisterSource::Pair(low, high) => {
+                core.write_core_reg(low, value as u32)?;
+                core.write_core_reg(high, (value >> 32) as u32)?;
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Feature, GdbRegister, RegisterSource, TargetDescription, XPSR_FLAGS};
+    use probe_rs::RegisterId;
+
+    #[test]
+    fn target_description_xml() {
+        let register = |name: &str, bitsize, gdb_type, source| GdbRegister {
+            name: name.to_owned(),
+            bitsize,
+            gdb_type,
+            source,
+        };
+
+        let description = TargetDescription {
+            architecture: "arm",
+            features: vec![
+                Feature {
+                    name: "org.gnu.gdb.arm.m-profile",
+                    types: XPSR_FLAGS,
+                    registers: 0..2,
+                },
+                Feature {
+                    name: "org.gnu.gdb.arm.vfp",
+                    types: "",
+                    registers: 2..3,
+                },
+            ],
+            registers: vec![
+                register("pc", 32, "code_ptr", RegisterSource::Core(RegisterId(15))),
+                register(
+                    "xpsr",
+                    32,
+                    "xpsr_flags",
+                    RegisterSource::Core(RegisterId(16)),
+                ),
+                register(
+                    "d0",
+                    64,
+                    "ieee_double",
+                    RegisterSource::Pair(RegisterId(64), RegisterId(65)),
+                ),
+            ],
+        };
+
+        let xml = description.xml();
+
+        assert!(xml.starts_with("<?xml version=\"1.0\"?>"));
+        assert!(xml.contains("  <architecture>arm</architecture>\n"));
+        assert!(xml.contains(
+            r#"    <reg name="xpsr" bitsize="32" regnum="1" type="xpsr_flags"/>
+  </feature>
+  <feature name="org.gnu.gdb.arm.vfp">
+    <reg name="d0" bitsize="64" regnum="2" type="ieee_double"/>
+  </feature>
+</target>
+"#
+        ));
+        assert!(xml.contains(r#"<flags id="xpsr_flags" size="4">"#));
+    }
+}
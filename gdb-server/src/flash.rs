@@ -0,0 +1,347 @@
+//! Flash programming with gdb's `load` command.
+//!
+//! gdb only writes to flash if the memory map of the target, which is read with
+//! `qXfer:memory-map:read`, marks the memory as flash. It then erases the blocks of the memory map
+//! with `vFlashErase`, writes the data with `vFlashWrite`, and finishes with `vFlashDone`, which
+//! is when the data is actually programmed, with the flash loader of probe-rs.
+//!
+//! `compare-sections` verifies the programmed sections with `qCRC`.
+
+use probe_rs::config::{MemoryRegion, NvmRegion, RawFlashAlgorithm};
+use probe_rs::flashing::{DownloadOptions, FlashError};
+use probe_rs::{Error, Session};
+use std::fmt::Write;
+use std::ops::Range;
+
+/// The end of the memory map, unless the target has memory above it.
+const ADDRESS_SPACE_END: u64 = 0x1_0000_0000;
+
+/// The kind of a block in the memory map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockKind {
+    Ram,
+    /// Non-volatile memory which can't be programmed, because there is no flash algorithm for it.
+    Rom,
+    /// Flash with the given erase block size.
+    Flash(u64),
+}
+
+/// Returns the blocks of a non-volatile memory region, one for each group of sectors with the
+/// same size.
+fn flash_blocks(
+    region: &NvmRegion,
+    flash_algorithms: &[RawFlashAlgorithm],
+) -> Vec<(Range<u64>, BlockKind)> {
+    let algorithm = flash_algorithms
+        .iter()
+        .filter(|algorithm| {
+            algorithm
+                .flash_properties
+                .address_range
+                .contains(&region.range.start)
+        })
+        .max_by_key(|algorithm| algorithm.default);
+
+    let properties = match algorithm {
+        Some(algorithm) => &algorithm.flash_properties,
+        None => return vec![(region.range.clone(), BlockKind::Rom)],
+    };
+
+    let flash = &properties.address_range;
+    let sectors = &properties.sectors;
+
+    let mut blocks = Vec::new();
+    for (index, sector) in sectors.iter().enumerate() {
+        let start = flash.start + sector.address;
+        let end = sectors
+            .get(index + 1)
+            .map(|next| flash.start + next.address)
+            .unwrap_or(flash.end);
+
+        let start = start.max(region.range.start);
+        let end = end.min(region.range.end);
+        if start < end {
+            blocks.push((start..end, BlockKind::Flash(sector.size)));
+        }
+    }
+
+    blocks
+}
+
+/// Builds the memory map document of the target for `qXfer:memory-map:read`.
+///
+/// gdb doesn't access memory outside of the memory map, so the gaps between the regions, which
+/// contain the peripherals, are marked as RAM.
+pub(crate) fn memory_map_xml(
+    memory_map: &[MemoryRegion],
+    flash_algorithms: &[RawFlashAlgorithm],
+) -> String {
+    let mut blocks = Vec::new();
+    for region in memory_map {
+        match region {
+            MemoryRegion::Ram(region) => blocks.push((region.range.clone(), BlockKind::Ram)),
+            MemoryRegion::Generic(region) => blocks.push((region.range.clone(), BlockKind::Ram)),
+            MemoryRegion::Nvm(region) => blocks.extend(flash_blocks(region, flash_algorithms)),
+        }
+    }
+    blocks.sort_by_key(|(range, _)| range.start);
+
+    let mut xml = String::from(
+        r#"<?xml version="1.0"?>
+<!DOCTYPE memory-map PUBLIC "+//IDN gnu.org//DTD GDB Memory Map V1.0//EN" "http://sourceware.org/gdb/gdb-memory-map.dtd">
+<memory-map>
+"#,
+    );
+
+    // gdb ignores the whole memory map if regions overlap, so aliases of memory which is already
+    // in the map are left out.
+    let mut end = 0;
+    for (range, kind) in blocks {
+        if range.end <= end {
+            continue;
+        }
+
+        if range.start > end {
+            write_block(&mut xml, end..range.start, BlockKind::Ram);
+        }
+
+        write_block(&mut xml, range.start.max(end)..range.end, kind);
+        end = range.end;
+    }
+
+    if end < ADDRESS_SPACE_END {
+        write_block(&mut xml, end..ADDRESS_SPACE_END, BlockKind::Ram);
+    }
+
+    xml.push_str("</memory-map>\n");
+    xml
+}
+
+fn write_block(xml: &mut String, range: Range<u64>, kind: BlockKind) {
+    let length = range.end - range.start;
+
+    // Writing to a String can't fail.
+    match kind {
+        BlockKind::Ram => writeln!(
+            xml,
+            r#"  <memory type="ram" start="{:#x}" length="{:#x}"/>"#,
+            range.start, length
+        ),
+        BlockKind::Rom => writeln!(
+            xml,
+            r#"  <memory type="rom" start="{:#x}" length="{:#x}"/>"#,
+            range.start, length
+        ),
+        BlockKind::Flash(block_size) => writeln!(
+            xml,
+            r#"  <memory type="flash" start="{:#x}" length="{:#x}">
+    <property name="blocksize">{:#x}</property>
+  </memory>"#,
+            range.start, length, block_size
+        ),
+    }
+    .unwrap();
+}
+
+/// Collects the data of the `vFlashErase` and `vFlashWrite` packets, and programs it on
+/// `vFlashDone`.
+///
+/// The erased ranges are filled with the erased value of the flash, so the flash loader erases
+/// the same sectors as gdb requested, and the parts which gdb doesn't write stay erased.
+#[derive(Debug, Default)]
+pub(crate) struct FlashWriter {
+    chunks: Vec<(u64, Vec<u8>)>,
+}
+
+impl FlashWriter {
+    /// Handles `vFlashErase`.
+    pub(crate) fn erase(
+        &mut self,
+        flash_algorithms: &[RawFlashAlgorithm],
+        address: u64,
+        length: u64,
+    ) {
+        let erased_byte_value = flash_algorithms
+            .iter()
+            .find(|algorithm| algorithm.flash_properties.address_range.contains(&address))
+            .map(|algorithm| algorithm.flash_properties.erased_byte_value)
+            .unwrap_or(0xff);
+
+        self.chunks
+            .push((address, vec![erased_byte_value; length as usize]));
+    }
+
+    /// Handles `vFlashWrite`.
+    pub(crate) fn write(&mut self, address: u64, data: &[u8]) {
+        let end = address + data.len() as u64;
+
+        let erased = self
+            .chunks
+            .iter_mut()
+            .find(|(start, chunk)| *start <= address && end <= *start + chunk.len() as u64);
+
+        match erased {
+            Some((start, chunk)) => {
+                let offset = (address - *start) as usize;
+                chunk[offset..offset + data.len()].copy_from_slice(data);
+            }
+            // gdb always erases before writing, but the flash loader erases as needed anyway.
+            None => self.chunks.push((address, data.to_vec())),
+        }
+    }
+
+    /// Handles `vFlashDone`, and programs the collected data.
+    pub(crate) fn done(&mut self, session: &mut Session) -> Result<(), FlashError> {
+        let chunks = std::mem::take(&mut self.chunks);
+        if chunks.is_empty() {
+            return Ok(());
+        }
+
+        let mut loader = session.target().flash_loader();
+        for (address, data) in &chunks {
+            loader.add_data(*address, data)?;
+        }
+
+        // gdb programs the whole image on every `load`, even if little of it changed.
+        let mut download_options = DownloadOptions::default();
+        download_options.skip_unchanged_sectors = true;
+
+        loader.commit(session, download_options)
+    }
+}
+
+/// The initial value of the CRC which gdb uses for `qCRC`.
+const GDB_CRC_INITIAL: u32 = 0xffff_ffff;
+
+/// Handles `qCRC:<address>,<length>`, which gdb sends for every section with `compare-sections`,
+/// and returns the reply, `C` followed by the CRC, or an error reply.
+///
+/// The CRC is calculated on the target if possible, which is much faster than reading the
+/// memory back.
+pub(crate) fn crc_reply(session: &mut Session, core_index: usize, arguments: &str) -> String {
+    crc_reply_with(arguments, |range, initial| {
+        session.memory_crc32(core_index, range, initial)
+    })
+}
+
+fn crc_reply_with(
+    arguments: &str,
+    crc32: impl FnOnce(Range<u64>, u32) -> Result<u32, Error>,
+) -> String {
+    let range = match parse_crc_arguments(arguments) {
+        Some(range) => range,
+        None => return "E01".to_string(),
+    };
+
+    match crc32(range, GDB_CRC_INITIAL) {
+        Ok(crc) => format!("C{:x}", crc),
+        Err(error) => {
+            log::warn!("Failed to calculate the CRC for qCRC: {}", error);
+            "E02".to_string()
+        }
+    }
+}
+
+/// Parses the `<address>,<length>` of `qCRC`, both in hex.
+fn parse_crc_arguments(arguments: &str) -> Option<Range<u64>> {
+    let (address, length) = arguments.split_once(',')?;
+    let address = u64::from_str_radix(address, 16).ok()?;
+    let length = u64::from_str_radix(length, 16).ok()?;
+
+    Some(address..address.checked_add(length)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{crc_reply_with, memory_map_xml, FlashWriter, GDB_CRC_INITIAL};
+    use probe_rs::config::{
+        FlashProperties, MemoryRegion, NvmRegion, RamRegion, RawFlashAlgorithm, SectorDescription,
+    };
+
+    #[test]
+    fn memory_map() {
+        let memory_map = vec![
+            MemoryRegion::Nvm(NvmRegion {
+                name: None,
+                range: 0x0800_0000..0x0804_0000,
+                is_boot_memory: true,
+                cores: vec![],
+            }),
+            MemoryRegion::Ram(RamRegion {
+                name: None,
+                range: 0x2000_0000..0x2002_0000,
+                is_boot_memory: false,
+                cores: vec![],
+            }),
+        ];
+
+        let algorithm = RawFlashAlgorithm {
+            flash_properties: FlashProperties {
+                address_range: 0x0800_0000..0x0804_0000,
+                sectors: vec![
+                    SectorDescription {
+                        size: 0x4000,
+                        address: 0,
+                    },
+                    SectorDescription {
+                        size: 0x1_0000,
+                        address: 0x1_0000,
+                    },
+                ],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let xml = memory_map_xml(&memory_map, &[algorithm]);
+
+        assert_eq!(
+            xml,
+            r#"<?xml version="1.0"?>
+<!DOCTYPE memory-map PUBLIC "+//IDN gnu.org//DTD GDB Memory Map V1.0//EN" "http://sourceware.org/gdb/gdb-memory-map.dtd">
+<memory-map>
+  <memory type="ram" start="0x0" length="0x8000000"/>
+  <memory type="flash" start="0x8000000" length="0x10000">
+    <property name="blocksize">0x4000</property>
+  </memory>
+  <memory type="flash" start="0x8010000" length="0x30000">
+    <property name="blocksize">0x10000</property>
+  </memory>
+  <memory type="ram" start="0x8040000" length="0x17fc0000"/>
+  <memory type="ram" start="0x20000000" length="0x20000"/>
+  <memory type="ram" start="0x20020000" length="0xdffe0000"/>
+</memory-map>
+"#
+        );
+    }
+
+    #[test]
+    fn fill_erased_blocks() {
+        let mut writer = FlashWriter::default();
+
+        writer.erase(&[], 0x1000, 8);
+        writer.write(0x1002, &[1, 2]);
+
+        assert_eq!(
+            writer.chunks,
+            vec![(0x1000, vec![0xff, 0xff, 1, 2, 0xff, 0xff, 0xff, 0xff])]
+        );
+    }
+
+    #[test]
+    fn crc_reply() {
+        // The CRC-32/MPEG-2 of "123456789", which gdb calculates for the same bytes.
+        let reply = crc_reply_with("20000000,9", |range, initial| {
+            assert_eq!(range, 0x2000_0000..0x2000_0009);
+            assert_eq!(initial, GDB_CRC_INITIAL);
+            Ok(0x0376_e6e7)
+        });
+        assert_eq!(reply, "C376e6e7");
+
+        assert_eq!(crc_reply_with("20000000", |_, _| unreachable!()), "E01");
+        assert_eq!(
+            crc_reply_with("ffffffffffffffff,2", |_, _| unreachable!()),
+            "E01"
+        );
+    }
+}
@@ -0,0 +1,33 @@
+//! Interrupts from gdb, which sends the break character when the user presses Ctrl-C.
+//!
+//! While the target runs, the stub checks for the break character before it checks the cores, so
+//! a Ctrl-C is handled promptly, even if a core halts at the same time.
+//!
+//! Flash programming is never interrupted, as it would leave the flash partially programmed.
+
+use std::io::{self, ErrorKind};
+use std::net::TcpStream;
+
+/// The break character gdb sends to interrupt the target.
+const BREAK_CHARACTER: u8 = 0x03;
+
+/// Returns `true` if gdb sent the break character.
+///
+/// This doesn't block, and doesn't consume any data, so the packets of gdb are still read by the
+/// stub, which also handles the break character itself.
+pub(crate) fn interrupt_pending(stream: &TcpStream) -> io::Result<bool> {
+    stream.set_nonblocking(true)?;
+
+    let mut byte = [0u8];
+    let result = stream.peek(&mut byte);
+
+    stream.set_nonblocking(false)?;
+
+    match result {
+        Ok(1) => Ok(byte[0] == BREAK_CHARACTER),
+        // The connection was closed, which is handled when the stub reads from it.
+        Ok(_) => Ok(false),
+        Err(error) if error.kind() == ErrorKind::WouldBlock => Ok(false),
+        Err(error) => Err(error),
+    }
+}
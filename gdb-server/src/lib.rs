@@ -1,5 +1,14 @@
 mod arch;
+mod description;
+mod flash;
+mod interrupt;
+mod monitor;
+mod packet;
+mod rtos;
+mod semihosting;
 mod stub;
 mod target;
+mod threads;
+mod watchpoint;
 
 pub use stub::{run, GdbInstanceConfiguration};
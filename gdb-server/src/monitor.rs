@@ -0,0 +1,279 @@
+//! The `monitor` commands of gdb, which are sent with the `qRcmd` packet.
+//!
+//! The commands follow the names of the OpenOCD commands where possible, so existing gdb scripts,
+//! e.g. with `monitor reset halt`, keep working.
+
+use probe_rs::architecture::arm::SwoConfig;
+use probe_rs::flashing::erase_all;
+use probe_rs::{Permissions, Session};
+use probe_rs_rtt::Rtt;
+use std::fmt::Write;
+use std::time::Duration;
+
+const HELP: &str = "\
+Commands:
+  help                   Show this help.
+  reset [halt|run]       Reset the current core, and halt it or let it run (default: run).
+                         gdb is not notified of the reset, use `reset halt` and `continue`
+                         to keep gdb in sync with the core.
+  erase                  Erase all flash memory of the target.
+  rtt start              Attach to the RTT control block of the target.
+  rtt stop               Detach from the RTT control block.
+  rtt                    List the RTT channels.
+  swo <clock> [<baud>]   Enable SWO, with the TPIU clock and the baud rate in Hz.
+  swo off                Disable SWO.
+  info                   Show information about the target and the probe.
+";
+
+const ERASE_NOT_ALLOWED: &str = "\
+Erasing all flash memory is not permitted for this session.
+Restart the gdb server with `--allow-erase-all` to allow it.
+";
+
+/// A monitor command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Help,
+    Reset { halt: bool },
+    Erase,
+    RttStart,
+    RttStop,
+    RttChannels,
+    Swo { clock: u32, baud: Option<u32> },
+    SwoOff,
+    Info,
+}
+
+impl Command {
+    fn parse(command: &str) -> Result<Self, String> {
+        let words: Vec<&str> = command.split_whitespace().collect();
+
+        let parse_frequency = |value: &str| {
+            value
+                .parse::<u32>()
+                .map_err(|_| format!("Invalid frequency '{}'", value))
+        };
+
+        let command = match words[..] {
+            [] | ["help"] => Command::Help,
+            ["reset"] | ["reset", "run"] => Command::Reset { halt: false },
+            // `reset init` halts the core in OpenOCD, before the init scripts are run.
+            ["reset", "halt"] | ["reset", "init"] => Command::Reset { halt: true },
+            ["erase"] => Command::Erase,
+            ["rtt"] => Command::RttChannels,
+            ["rtt", "start"] => Command::RttStart,
+            ["rtt", "stop"] => Command::RttStop,
+            ["swo", "off"] => Command::SwoOff,
+            ["swo", clock] => Command::Swo {
+                clock: parse_frequency(clock)?,
+                baud: None,
+            },
+            ["swo", clock, baud] => Command::Swo {
+                clock: parse_frequency(clock)?,
+                baud: Some(parse_frequency(baud)?),
+            },
+            ["info"] => Command::Info,
+            _ => {
+                return Err(format!(
+                    "Unknown command '{}', see 'monitor help'",
+                    command.trim()
+                ))
+            }
+        };
+
+        Ok(command)
+    }
+}
+
+/// The state of the monitor commands of a gdb instance.
+#[derive(Default)]
+pub(crate) struct Monitor {
+    rtt: Option<Rtt>,
+}
+
+impl Monitor {
+    /// Runs a monitor command on core `core` of the session, which is the core of the thread
+    /// selected in gdb, and returns the output for the gdb console.
+    ///
+    /// Errors are reported in the output, because gdb only shows a generic error message
+    /// otherwise.
+    pub(crate) fn run(&mut self, session: &mut Session, core: usize, command: &str) -> String {
+        let command = match Command::parse(command) {
+            Ok(command) => command,
+            Err(error) => return format!("{}\n", error),
+        };
+
+        let mut output = String::new();
+        if let Err(error) = self.execute(session, core, command, &mut output) {
+            writeln!(output, "Error: {:#}", error).unwrap();
+        }
+
+        output
+    }
+
+    fn execute(
+        &mut self,
+        session: &mut Session,
+        core: usize,
+        command: Command,
+        output: &mut String,
+    ) -> anyhow::Result<()> {
+        match command {
+            Command::Help => output.push_str(HELP),
+            Command::Reset { halt: true } => {
+                let information = session
+                    .core(core)?
+                    .reset_and_halt(Duration::from_millis(500))?;
+                writeln!(output, "Core halted at {:#010x}", information.pc)?;
+            }
+            Command::Reset { halt: false } => {
+                session.core(core)?.reset()?;
+                writeln!(output, "Core reset")?;
+            }
+            Command::Erase => {
+                if let Some(message) = erase_denied(session.permissions()) {
+                    output.push_str(message);
+                    return Ok(());
+                }
+
+                // The flash algorithms overwrite the RAM, so the control block is gone.
+                self.rtt = None;
+
+                erase_all(session)?;
+                writeln!(output, "Erased all flash memory")?;
+            }
+            Command::RttStart => {
+                let memory_map = session.target().memory_map.clone();
+                let rtt = Rtt::attach(&mut session.core(core)?, &memory_map)?;
+                writeln!(
+                    output,
+                    "Attached to RTT control block at {:#010x}",
+                    rtt.ptr()
+                )?;
+                self.rtt = Some(rtt);
+                self.write_channels(output)?;
+            }
+            Command::RttStop => {
+                self.rtt = None;
+                writeln!(output, "Detached from RTT control block")?;
+            }
+            Command::RttChannels => self.write_channels(output)?,
+            Command::Swo { clock, baud } => {
+                let mut config = SwoConfig::new(clock);
+                if let Some(baud) = baud {
+                    config = config.set_baud(baud);
+                }
+
+                session.setup_swv(core, &config)?;
+                writeln!(
+                    output,
+                    "SWO enabled with {} Bd, TPIU clock {} Hz",
+                    config.baud(),
+                    config.tpiu_clk()
+                )?;
+            }
+            Command::SwoOff => {
+                session.disable_swv(core)?;
+                writeln!(output, "SWO disabled")?;
+            }
+            Command::Info => {
+                writeln!(output, "Target: {}", session.target().name)?;
+                for (index, core_type) in session.list_cores() {
+                    writeln!(output, "Core {}: {:?}", index, core_type)?;
+                }
+
+                if let Some(variant) = session.variant_id()? {
+                    writeln!(output, "Variant: {}", variant.name_prefix)?;
+                    if let Some(flash_size) = variant.flash_size {
+                        writeln!(output, "Flash size: {} KiB", flash_size / 1024)?;
+                    }
+                }
+
+                match session.get_target_voltage()? {
+                    Some(voltage) => writeln!(output, "Target voltage: {:.2} V", voltage)?,
+                    None => writeln!(output, "Target voltage: unknown")?,
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_channels(&mut self, output: &mut String) -> anyhow::Result<()> {
+        let rtt = match &mut self.rtt {
+            Some(rtt) => rtt,
+            None => {
+                writeln!(output, "RTT is not attached, use 'monitor rtt start'")?;
+                return Ok(());
+            }
+        };
+
+        for channel in rtt.up_channels().iter() {
+            writeln!(
+                output,
+                "Up {}: {} ({} bytes)",
+                channel.number(),
+                channel.name().unwrap_or("(unnamed)"),
+                channel.buffer_size()
+            )?;
+        }
+
+        for channel in rtt.down_channels().iter() {
+            writeln!(
+                output,
+                "Down {}: {} ({} bytes)",
+                channel.number(),
+                channel.name().unwrap_or("(unnamed)"),
+                channel.buffer_size()
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The reply to `monitor erase` if the session may not erase the chip.
+///
+/// This is checked before the flash algorithms run, so the RTT control block survives a denied erase.
+fn erase_denied(permissions: &Permissions) -> Option<&'static str> {
+    if permissions.is_erase_all_allowed() {
+        None
+    } else {
+        Some(ERASE_NOT_ALLOWED)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{erase_denied, Command, ERASE_NOT_ALLOWED};
+    use probe_rs::Permissions;
+
+    #[test]
+    fn parse_commands() {
+        assert_eq!(Command::parse(""), Ok(Command::Help));
+        assert_eq!(Command::parse("reset"), Ok(Command::Reset { halt: false }));
+        assert_eq!(
+            Command::parse(" reset  halt "),
+            Ok(Command::Reset { halt: true })
+        );
+        assert_eq!(Command::parse("rtt start"), Ok(Command::RttStart));
+        assert_eq!(
+            Command::parse("swo 72000000 2000000"),
+            Ok(Command::Swo {
+                clock: 72_000_000,
+                baud: Some(2_000_000)
+            })
+        );
+
+        assert!(Command::parse("swo fast").is_err());
+        assert!(Command::parse("reset now").is_err());
+    }
+
+    #[test]
+    fn erase_denied_without_permission() {
+        assert_eq!(erase_denied(&Permissions::new()), Some(ERASE_NOT_ALLOWED));
+        assert!(ERASE_NOT_ALLOWED.contains("--allow-erase-all"));
+
+        assert_eq!(erase_denied(&Permissions::new().allow_erase_all()), None);
+    }
+}
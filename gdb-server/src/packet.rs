@@ -0,0 +1,228 @@
+//! Packets which gdbstub doesn't support, and which are handled by the target before gdbstub sees
+//! them: the flash programming packets, `qCRC`, and the `F` replies of the File-I/O protocol.
+//!
+//! The other bytes from gdb, including all other packets, are passed on to gdbstub unchanged.
+
+use std::collections::VecDeque;
+use std::io::{self, Write};
+
+/// A packet which is handled by the target.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Packet {
+    /// `vFlashErase:<address>,<length>`
+    FlashErase { address: u64, length: u64 },
+    /// `vFlashWrite:<address>:<data>`
+    FlashWrite { address: u64, data: Vec<u8> },
+    /// `vFlashDone`
+    FlashDone,
+    /// `qCRC:<address>,<length>`, with the arguments.
+    Crc(String),
+    /// `F<result>[,<errno>[,C]]`, the reply to a File-I/O request, without the attachment.
+    FileIoReply(String),
+}
+
+impl Packet {
+    /// Parses the payload of a packet, and returns `None` if gdbstub handles the packet.
+    fn parse(payload: &[u8]) -> Option<Self> {
+        if let Some(arguments) = payload.strip_prefix(b"vFlashErase:") {
+            let (address, length) = std::str::from_utf8(arguments).ok()?.split_once(',')?;
+
+            Some(Packet::FlashErase {
+                address: parse_hex(address)?,
+                length: parse_hex(length)?,
+            })
+        } else if let Some(arguments) = payload.strip_prefix(b"vFlashWrite:") {
+            let separator = arguments.iter().position(|&byte| byte == b':')?;
+            let address = std::str::from_utf8(&arguments[..separator]).ok()?;
+
+            Some(Packet::FlashWrite {
+                address: parse_hex(address)?,
+                data: unescape(&arguments[separator + 1..]),
+            })
+        } else if payload == b"vFlashDone" {
+            Some(Packet::FlashDone)
+        } else if let Some(arguments) = payload.strip_prefix(b"qCRC:") {
+            let arguments = std::str::from_utf8(arguments).ok()?;
+
+            Some(Packet::Crc(arguments.to_string()))
+        } else if payload.starts_with(b"F") {
+            let reply = std::str::from_utf8(payload).ok()?;
+            let reply = reply.split(';').next().unwrap_or(reply);
+
+            Some(Packet::FileIoReply(reply.to_string()))
+        } else {
+            None
+        }
+    }
+}
+
+/// Separates the packets which are handled by the target from the data for gdbstub.
+#[derive(Debug, Default)]
+pub(crate) struct PacketFilter {
+    /// The bytes of the packet which is being received, starting with `$`.
+    packet: Vec<u8>,
+    /// The position of the `#` which ends the data of the packet, once it was received.
+    end: Option<usize>,
+    /// The bytes which are passed on to gdbstub.
+    forward: VecDeque<u8>,
+    /// gdb doesn't expect acknowledgments anymore.
+    no_ack: bool,
+}
+
+impl PacketFilter {
+    /// Passes a byte from gdb through the filter, and returns the packet which it completed, if
+    /// the packet is handled by the target. The packet has to be answered with
+    /// [`PacketFilter::reply`].
+    pub(crate) fn push(&mut self, byte: u8) -> Option<Packet> {
+        if self.packet.is_empty() && byte != b'$' {
+            self.forward.push_back(byte);
+            return None;
+        }
+
+        self.packet.push(byte);
+
+        // The data is followed by `#` and two digits of checksum. A `#` in the data is always
+        // escaped, so the first `#` ends the data.
+        let end = match self.end {
+            Some(end) => end,
+            None if byte == b'#' => {
+                self.end = Some(self.packet.len() - 1);
+                return None;
+            }
+            None => return None,
+        };
+
+        if self.packet.len() < end + 3 {
+            return None;
+        }
+
+        let packet = std::mem::take(&mut self.packet);
+        self.end = None;
+
+        let payload = &packet[1..end];
+        let valid = std::str::from_utf8(&packet[end + 1..])
+            .ok()
+            .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+            == Some(checksum(payload));
+
+        // Packets with a bad checksum are left to gdbstub, which asks gdb to send them again.
+        if valid {
+            if let Some(packet) = Packet::parse(payload) {
+                return Some(packet);
+            }
+
+            if payload == b"QStartNoAckMode" {
+                self.no_ack = true;
+            }
+        }
+
+        self.forward.extend(packet);
+        None
+    }
+
+    /// Returns the next byte for gdbstub.
+    pub(crate) fn pop(&mut self) -> Option<u8> {
+        self.forward.pop_front()
+    }
+
+    /// Acknowledges a packet which was returned by [`PacketFilter::push`], and sends the reply, if
+    /// there is one.
+    pub(crate) fn reply(&self, conn: &mut impl Write, reply: Option<&str>) -> io::Result<()> {
+        if !self.no_ack {
+            conn.write_all(b"+")?;
+        }
+
+        match reply {
+            Some(reply) => self.send(conn, reply),
+            None => conn.flush(),
+        }
+    }
+
+    /// Sends a packet to gdb.
+    pub(crate) fn send(&self, conn: &mut impl Write, packet: &str) -> io::Result<()> {
+        write!(conn, "${}#{:02x}", packet, checksum(packet.as_bytes()))?;
+        conn.flush()
+    }
+}
+
+fn checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0, |sum, &byte| sum.wrapping_add(byte))
+}
+
+fn parse_hex(value: &str) -> Option<u64> {
+    u64::from_str_radix(value, 16).ok()
+}
+
+/// Removes the escaping of binary data. An escaped byte is sent as `}` followed by the byte
+/// XOR 0x20.
+fn unescape(data: &[u8]) -> Vec<u8> {
+    let mut unescaped = Vec::with_capacity(data.len());
+
+    let mut bytes = data.iter();
+    while let Some(&byte) = bytes.next() {
+        match byte {
+            b'}' => {
+                if let Some(&escaped) = bytes.next() {
+                    unescaped.push(escaped ^ 0x20);
+                }
+            }
+            byte => unescaped.push(byte),
+        }
+    }
+
+    unescaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Packet, PacketFilter};
+
+    fn push_all(filter: &mut PacketFilter, bytes: &[u8]) -> Vec<Packet> {
+        bytes.iter().filter_map(|&byte| filter.push(byte)).collect()
+    }
+
+    fn forwarded(filter: &mut PacketFilter) -> Vec<u8> {
+        std::iter::from_fn(|| filter.pop()).collect()
+    }
+
+    #[test]
+    fn filter_packets() {
+        let mut filter = PacketFilter::default();
+
+        let packets = push_all(&mut filter, b"+$qCRC:20000000,9#6a+$g#67");
+        assert_eq!(packets, [Packet::Crc("20000000,9".to_string())]);
+        assert_eq!(forwarded(&mut filter), b"++$g#67");
+
+        let packets = push_all(&mut filter, b"$vFlashWrite:8000000:\x01}]}\x03#96");
+        assert_eq!(
+            packets,
+            [Packet::FlashWrite {
+                address: 0x0800_0000,
+                data: vec![0x01, b'}', b'#'],
+            }]
+        );
+
+        // A packet with a bad checksum is passed on.
+        let packets = push_all(&mut filter, b"$qCRC:20000000,9#00");
+        assert!(packets.is_empty());
+        assert_eq!(forwarded(&mut filter), b"$qCRC:20000000,9#00");
+
+        let mut reply = Vec::new();
+        filter.reply(&mut reply, Some("OK")).unwrap();
+        assert_eq!(reply, b"+$OK#9a");
+
+        let packets = push_all(&mut filter, b"$F-1,2,C;X#04");
+        assert_eq!(packets, [Packet::FileIoReply("F-1,2,C".to_string())]);
+
+        let mut reply = Vec::new();
+        filter.reply(&mut reply, None).unwrap();
+        assert_eq!(reply, b"+");
+
+        push_all(&mut filter, b"$QStartNoAckMode#b0");
+        assert_eq!(forwarded(&mut filter), b"$QStartNoAckMode#b0");
+
+        let mut reply = Vec::new();
+        filter.reply(&mut reply, Some("OK")).unwrap();
+        assert_eq!(reply, b"$OK#9a");
+    }
+}
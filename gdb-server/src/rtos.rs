@@ -0,0 +1,183 @@
+//! The threads of an RTOS, which are exposed to gdb as threads.
+//!
+//! If the program, which is passed with `--elf`, uses a supported RTOS, its threads are read from
+//! the target whenever the core halts, and are reported to gdb instead of the core. This is only
+//! done for instances with a single core. The id of a thread is the id the RTOS awareness reports,
+//! usually the address of its control block, so it stays the same while the thread exists.
+//!
+//! The registers of the running thread are the registers of the core. The registers of the other
+//! threads are the ones saved by their last context switch, and can't be written.
+
+use crate::description::{RegisterSource, TargetDescription};
+use gdbstub::common::Tid;
+use probe_rs::debug::rtos::{self, Rtos, RtosThread, ThreadState};
+use probe_rs::debug::DebugInfo;
+use probe_rs::{Core, RegisterId};
+
+/// Where gdb reads a register of a thread from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ThreadRegister {
+    /// The thread is running, so the register is read from the core.
+    Core,
+    /// The value saved by the last context switch, in little endian byte order.
+    Saved(Vec<u8>),
+    /// The value was not saved, and is reported as unavailable.
+    Unavailable,
+}
+
+/// The threads of the RTOS of the program.
+pub(crate) struct RtosThreads {
+    rtos: Box<dyn Rtos>,
+    debug_info: DebugInfo,
+    threads: Vec<RtosThread>,
+}
+
+impl RtosThreads {
+    /// Detects the RTOS of the program, and returns `None` if it uses none of the supported ones.
+    pub(crate) fn new(debug_info: DebugInfo) -> Option<Self> {
+        let rtos = rtos::detect(&debug_info)?;
+        log::info!("Reporting the threads of {} to gdb", rtos.name());
+
+        Some(Self {
+            rtos,
+            debug_info,
+            threads: Vec::new(),
+        })
+    }
+
+    /// Reads the threads from the halted core, which has to be done whenever the core halts.
+    ///
+    /// If the threads can't be read, e.g. because the kernel hasn't been started yet, no threads
+    /// are reported, and gdb shows the core as the only thread.
+    pub(crate) fn refresh(&mut self, core: &mut Core) {
+        self.threads = match self.rtos.threads(core, &self.debug_info) {
+            Ok(threads) => threads,
+            Err(error) => {
+                log::debug!(
+                    "Could not read the threads of {}: {}",
+                    self.rtos.name(),
+                    error
+                );
+                Vec::new()
+            }
+        };
+    }
+
+    /// Returns `true` if there are threads to report instead of the core.
+    pub(crate) fn is_active(&self) -> bool {
+        !self.threads.is_empty()
+    }
+
+    /// Returns the ids of all threads.
+    pub(crate) fn tids(&self) -> impl Iterator<Item = Tid> + '_ {
+        self.threads.iter().filter_map(tid)
+    }
+
+    /// Returns the id of the running thread, which is reported in the stop reply.
+    pub(crate) fn current(&self) -> Option<Tid> {
+        self.threads
+            .iter()
+            .find(|thread| thread.is_running())
+            .and_then(tid)
+    }
+
+    fn thread(&self, tid: Tid) -> Option<&RtosThread> {
+        self.threads
+            .iter()
+            .find(|thread| thread.id == tid.get() as u64)
+    }
+
+    /// Returns `true` if `tid` is a thread of the RTOS.
+    pub(crate) fn contains(&self, tid: Tid) -> bool {
+        self.thread(tid).is_some()
+    }
+
+    /// Returns `true` if `tid` is a thread of the RTOS which isn't running, so its registers
+    /// aren't the registers of the core.
+    pub(crate) fn is_suspended(&self, tid: Tid) -> bool {
+        matches!(self.thread(tid), Some(thread) if !thread.is_running())
+    }
+
+    /// Returns the description of a thread for `qThreadExtraInfo`, e.g. `idle, Ready, priority 0`.
+    pub(crate) fn extra_info(&self, tid: Tid) -> Option<String> {
+        self.thread(tid).map(extra_info)
+    }
+
+    /// Returns register `regnum` of the target description for thread `tid`.
+    pub(crate) fn register(
+        &self,
+        tid: Tid,
+        description: &TargetDescription,
+        regnum: usize,
+    ) -> ThreadRegister {
+        let thread = match self.thread(tid) {
+            Some(thread) if !thread.is_running() => thread,
+            _ => return ThreadRegister::Core,
+        };
+
+        let (registers, register) = match (&thread.registers, description.registers().get(regnum)) {
+            (Some(registers), Some(register)) => (registers, register),
+            _ => return ThreadRegister::Unavailable,
+        };
+
+        // Only the core registers are saved by a context switch.
+        let id = match register.source {
+            RegisterSource::Core(id) => id,
+            _ => return ThreadRegister::Unavailable,
+        };
+
+        let value = registers.registers().find_map(|(&number, &value)| {
+            registers
+                .get_description_by_dwarf_register_number(number)
+                .filter(|description| RegisterId::from(*description) == id)
+                .map(|_| value)
+        });
+
+        match value {
+            Some(value) => {
+                let bytes = (register.bitsize as usize + 7) / 8;
+                ThreadRegister::Saved(value.to_le_bytes()[..bytes].to_vec())
+            }
+            None => ThreadRegister::Unavailable,
+        }
+    }
+}
+
+fn tid(thread: &RtosThread) -> Option<Tid> {
+    Tid::new(thread.id as usize)
+}
+
+fn extra_info(thread: &RtosThread) -> String {
+    let state = match thread.state {
+        ThreadState::Running => "Running",
+        ThreadState::Ready => "Ready",
+        ThreadState::Blocked => "Blocked",
+        ThreadState::Suspended => "Suspended",
+        ThreadState::Inactive => "Inactive",
+        ThreadState::Unknown => "Unknown",
+    };
+
+    match thread.priority {
+        Some(priority) => format!("{}, {}, priority {}", thread.name, state, priority),
+        None => format!("{}, {}", thread.name, state),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::extra_info;
+    use probe_rs::debug::rtos::{RtosThread, ThreadState};
+
+    #[test]
+    fn thread_extra_info() {
+        let thread = RtosThread {
+            id: 0x2000_0100,
+            name: "idle".to_string(),
+            state: ThreadState::Ready,
+            priority: Some(0),
+            registers: None,
+        };
+
+        assert_eq!(extra_info(&thread), "idle, Ready, priority 0");
+    }
+}
@@ -0,0 +1,357 @@
+//! Semihosting with the File-I/O remote protocol of gdb.
+//!
+//! The console and file operations of the target are forwarded to gdb with `F` request packets, so they use the console and the filesystem of the debugger. gdb accesses
+//! the buffers in the memory of the target itself, and answers with an `F` reply packet, which
+//! completes the semihosting call.
+//!
+//! All other operations, e.g. the exit of the target, are serviced on the host by a
+//! [`SemihostingHost`].
+
+use probe_rs::architecture::arm::semihosting::{
+    ExitStatus, SemihostingCall, SemihostingEvent, SemihostingHost, SemihostingPolicy,
+};
+use probe_rs::{Core, Error};
+
+const SYS_OPEN: u32 = 0x01;
+const SYS_CLOSE: u32 = 0x02;
+const SYS_WRITEC: u32 = 0x03;
+const SYS_WRITE0: u32 = 0x04;
+const SYS_WRITE: u32 = 0x05;
+const SYS_READ: u32 = 0x06;
+const SYS_ISTTY: u32 = 0x09;
+const SYS_SEEK: u32 = 0x0a;
+const SYS_REMOVE: u32 = 0x0e;
+const SYS_RENAME: u32 = 0x0f;
+const SYS_ERRNO: u32 = 0x13;
+
+// The flags of `Fopen`, as defined by the File-I/O protocol.
+const O_RDONLY: u32 = 0x0;
+const O_WRONLY: u32 = 0x1;
+const O_RDWR: u32 = 0x2;
+const O_APPEND: u32 = 0x8;
+const O_CREAT: u32 = 0x200;
+const O_TRUNC: u32 = 0x400;
+
+/// The maximum length of a NUL terminated string written with `SYS_WRITE0`.
+const MAX_STRING_LEN: u64 = 4096;
+
+/// How the result of gdb is converted to the result of the semihosting call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Conversion {
+    /// The result is passed on.
+    None,
+    /// The result is the number of bytes which were transferred, and the semihosting call returns
+    /// the number of bytes which were not.
+    Remaining(u32),
+    /// The result is the new position, and the semihosting call returns 0.
+    Success,
+}
+
+/// What the gdb server has to do after a semihosting call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum SemihostingAction {
+    /// The core is not halted on a semihosting call.
+    None,
+    /// The call was serviced on the host, and the core can be resumed.
+    Resume,
+    /// The request has to be sent to gdb, whose reply is passed to [`SemihostingBridge::reply`].
+    Forward(String),
+    /// The target exited.
+    Exit(ExitStatus),
+}
+
+/// Services the semihosting calls of a core, with gdb or on the host.
+#[derive(Debug)]
+pub(crate) struct SemihostingBridge {
+    host: SemihostingHost,
+    /// The call which was forwarded to gdb, and is waiting for the reply.
+    pending: Option<(SemihostingCall, Conversion)>,
+    /// The errno of the last operation which failed in gdb, for `SYS_ERRNO`.
+    errno: Option<i32>,
+}
+
+impl SemihostingBridge {
+    /// Creates a bridge which services the calls on the host with the `policy`.
+    pub(crate) fn new(policy: SemihostingPolicy) -> Self {
+        Self {
+            host: SemihostingHost::new(policy),
+            pending: None,
+            errno: None,
+        }
+    }
+
+    /// Returns `true` if a request was forwarded to gdb, and the reply is still missing.
+    pub(crate) fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Services the semihosting call of the halted core, which is forwarded to gdb if gdb can
+    /// service it.
+    pub(crate) fn handle(&mut self, core: &mut Core) -> Result<SemihostingAction, Error> {
+        let call = match SemihostingCall::detect(core)? {
+            Some(call) => call,
+            None => return Ok(SemihostingAction::None),
+        };
+
+        if call.operation == SYS_ERRNO {
+            if let Some(errno) = self.errno {
+                call.complete(core, errno)?;
+                return Ok(SemihostingAction::Resume);
+            }
+        }
+
+        match request(core, &call)? {
+            Some(Request::Console(fd)) => {
+                call.complete(core, fd)?;
+                return Ok(SemihostingAction::Resume);
+            }
+            Some(Request::Forward(packet, conversion)) => {
+                log::debug!("Forwarding semihosting request to gdb: {}", packet);
+
+                self.pending = Some((call, conversion));
+                return Ok(SemihostingAction::Forward(packet));
+            }
+            None => {}
+        }
+
+        self.errno = None;
+
+        Ok(match self.host.handle(core)? {
+            Some(SemihostingEvent::Exit(status)) => SemihostingAction::Exit(status),
+            Some(_) => SemihostingAction::Resume,
+            None => SemihostingAction::None,
+        })
+    }
+
+    /// Completes the forwarded call with the `F` reply packet of gdb.
+    ///
+    /// Returns `true` if the user interrupted the operation with Ctrl-C, in which case the core
+    /// should stay halted and be reported as stopped by `SIGINT`.
+    pub(crate) fn reply(&mut self, core: &mut Core, reply: &str) -> Result<bool, Error> {
+        let (call, conversion) = match self.pending.take() {
+            Some(pending) => pending,
+            None => return Ok(false),
+        };
+
+        let (result, errno, interrupted) = parse_reply(reply)
+            .ok_or_else(|| Error::Other(anyhow::anyhow!("Invalid File-I/O reply '{}'", reply)))?;
+
+        let result = if result < 0 {
+            self.errno = errno;
+            -1
+        } else {
+            match conversion {
+                Conversion::None => result,
+                Conversion::Remaining(requested) => requested.saturating_sub(result as u32) as i32,
+                Conversion::Success => 0,
+            }
+        };
+
+        call.complete(core, result)?;
+
+        Ok(interrupted)
+    }
+}
+
+/// How a semihosting call is serviced with gdb.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Request {
+    /// The console was opened, with the file descriptor of the console stream in gdb.
+    Console(i32),
+    /// The `F` request packet for gdb.
+    Forward(String, Conversion),
+}
+
+/// Returns how a semihosting call is serviced with gdb, or `None` if the operation is serviced
+/// on the host.
+fn request(core: &mut Core, call: &SemihostingCall) -> Result<Option<Request>, Error> {
+    let (packet, conversion) = match call.operation {
+        SYS_OPEN => {
+            let [path, mode, len] = parameters::<3>(core, call)?;
+
+            // The console is already open in gdb, as stdin, stdout and stderr.
+            if read_path_is_console(core, path, len)? {
+                let fd = match mode {
+                    0..=3 => 0,
+                    4..=7 => 1,
+                    _ => 2,
+                };
+                return Ok(Some(Request::Console(fd)));
+            }
+
+            let flags = match open_flags(mode) {
+                Some(flags) => flags,
+                None => return Ok(None),
+            };
+
+            // The length of paths includes the terminating NUL.
+            (
+                format!("Fopen,{:x}/{:x},{:x},1b6", path, len + 1, flags),
+                Conversion::None,
+            )
+        }
+        SYS_CLOSE => {
+            let [fd] = parameters::<1>(core, call)?;
+            (format!("Fclose,{:x}", fd), Conversion::None)
+        }
+        SYS_WRITEC => (format!("Fwrite,1,{:x},1", call.parameter), Conversion::None),
+        SYS_WRITE0 => {
+            let len = c_string_len(core, call.parameter.into())?;
+            (
+                format!("Fwrite,1,{:x},{:x}", call.parameter, len),
+                Conversion::None,
+            )
+        }
+        SYS_WRITE => {
+            let [fd, buffer, len] = parameters::<3>(core, call)?;
+            (
+                format!("Fwrite,{:x},{:x},{:x}", fd, buffer, len),
+                Conversion::Remaining(len),
+            )
+        }
+        SYS_READ => {
+            let [fd, buffer, len] = parameters::<3>(core, call)?;
+            (
+                format!("Fread,{:x},{:x},{:x}", fd, buffer, len),
+                Conversion::Remaining(len),
+            )
+        }
+        SYS_ISTTY => {
+            let [fd] = parameters::<1>(core, call)?;
+            (format!("Fisatty,{:x}", fd), Conversion::None)
+        }
+        SYS_SEEK => {
+            let [fd, position] = parameters::<2>(core, call)?;
+            // SEEK_SET
+            (
+                format!("Flseek,{:x},{:x},0", fd, position),
+                Conversion::Success,
+            )
+        }
+        SYS_REMOVE => {
+            let [path, len] = parameters::<2>(core, call)?;
+            (
+                format!("Funlink,{:x}/{:x}", path, len + 1),
+                Conversion::None,
+            )
+        }
+        SYS_RENAME => {
+            let [from, from_len, to, to_len] = parameters::<4>(core, call)?;
+            (
+                format!(
+                    "Frename,{:x}/{:x},{:x}/{:x}",
+                    from,
+                    from_len + 1,
+                    to,
+                    to_len + 1
+                ),
+                Conversion::None,
+            )
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(Request::Forward(packet, conversion)))
+}
+
+fn parameters<const N: usize>(core: &mut Core, call: &SemihostingCall) -> Result<[u32; N], Error> {
+    let mut parameters = [0u32; N];
+    parameters.copy_from_slice(&call.read_parameters(core, N)?);
+    Ok(parameters)
+}
+
+/// Returns the flags of `Fopen` for the mode of `SYS_OPEN`, which are the modes of `fopen`, with
+/// and without the `b` flag.
+fn open_flags(mode: u32) -> Option<u32> {
+    let flags = match mode / 2 {
+        0 => O_RDONLY,
+        1 => O_RDWR,
+        2 => O_WRONLY | O_CREAT | O_TRUNC,
+        3 => O_RDWR | O_CREAT | O_TRUNC,
+        4 => O_WRONLY | O_CREAT | O_APPEND,
+        5 => O_RDWR | O_CREAT | O_APPEND,
+        _ => return None,
+    };
+
+    Some(flags)
+}
+
+fn read_path_is_console(core: &mut Core, path: u32, len: u32) -> Result<bool, Error> {
+    if len != 3 {
+        return Ok(false);
+    }
+
+    let mut name = [0u8; 3];
+    core.read_8(path.into(), &mut name)?;
+
+    Ok(&name == b":tt")
+}
+
+/// Returns the length of the NUL terminated string at `address`.
+fn c_string_len(core: &mut Core, address: u64) -> Result<u64, Error> {
+    let mut len = 0;
+
+    while len < MAX_STRING_LEN {
+        let mut chunk = [0u8; 32];
+        core.read_8(address + len, &mut chunk)?;
+
+        match chunk.iter().position(|&b| b == 0) {
+            Some(end) => return Ok(len + end as u64),
+            None => len += chunk.len() as u64,
+        }
+    }
+
+    Ok(len)
+}
+
+/// Parses an `F` reply packet, `Fretcode[,errno[,C]]`, with or without the leading `F`.
+///
+/// The return code and the errno are hexadecimal, and the return code can be negative.
+fn parse_reply(reply: &str) -> Option<(i32, Option<i32>, bool)> {
+    let reply = reply.strip_prefix('F').unwrap_or(reply);
+    let mut fields = reply.split(',');
+
+    let parse = |field: &str| match field.strip_prefix('-') {
+        Some(value) => i64::from_str_radix(value, 16).ok().map(|value| -value),
+        None => i64::from_str_radix(field, 16).ok(),
+    };
+
+    let result = parse(fields.next()?)? as i32;
+    let errno = match fields.next() {
+        Some(errno) => Some(parse(errno)? as i32),
+        None => None,
+    };
+    let interrupted = match fields.next() {
+        Some("C") => true,
+        Some(_) => return None,
+        None => false,
+    };
+
+    Some((result, errno, interrupted))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{open_flags, parse_reply};
+
+    #[test]
+    fn parse_replies() {
+        assert_eq!(parse_reply("F10"), Some((0x10, None, false)));
+        assert_eq!(parse_reply("F-1,9"), Some((-1, Some(9), false)));
+        assert_eq!(parse_reply("-1,4,C"), Some((-1, Some(4), true)));
+
+        assert_eq!(parse_reply("F"), None);
+        assert_eq!(parse_reply("F0,0,X"), None);
+    }
+
+    #[test]
+    fn fopen_modes() {
+        // "rb"
+        assert_eq!(open_flags(1), Some(0x0));
+        // "w"
+        assert_eq!(open_flags(4), Some(0x601));
+        // "a+b"
+        assert_eq!(open_flags(11), Some(0x20a));
+        assert_eq!(open_flags(12), None);
+    }
+}
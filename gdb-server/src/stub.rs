@@ -3,6 +3,7 @@ use probe_rs::{CoreType, Error, Session};
 
 use std::cell::RefCell;
 use std::net::{SocketAddr, ToSocketAddrs};
+use std::path::PathBuf;
 use std::rc::Rc;
 use std::time::Duration;
 
@@ -12,6 +13,10 @@ use super::target;
 
 const CONNECTION_STRING: &str = "127.0.0.1:1337";
 
+/// The longest time between two checks of the targets, so a Ctrl-C from gdb is handled promptly
+/// while a target runs.
+const MAX_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
 /// Configuration for a single GDB endpoint
 pub struct GdbInstanceConfiguration {
     /// The core type that will be sent to GDB
@@ -20,6 +25,9 @@ pub struct GdbInstanceConfiguration {
     pub cores: Vec<usize>,
     /// The list of [SocketAddr] addresses to bind to
     pub socket_addrs: Vec<SocketAddr>,
+    /// The ELF file of the program, whose debug information is used to report the threads of
+    /// its RTOS to GDB, if the instance has a single core.
+    pub elf: Option<PathBuf>,
 }
 
 impl GdbInstanceConfiguration {
@@ -66,6 +74,7 @@ impl GdbInstanceConfiguration {
                 core_type: *core_type,
                 cores: cores.to_vec(),
                 socket_addrs: adjust_addrs(&addrs, i),
+                elf: None,
             })
             .collect();
 
@@ -99,20 +108,23 @@ pub fn run<'a>(
                 session.clone(),
                 instance.cores.to_vec(),
                 &instance.socket_addrs[..],
+                instance.elf.as_deref(),
             )
         })
         .collect::<Result<Vec<target::RuntimeTarget>, Error>>()?;
 
     // Process every target in a loop
     loop {
-        let mut wait_time = Duration::ZERO;
+        let mut wait_time = MAX_POLL_INTERVAL;
 
         for target in targets.iter_mut() {
             wait_time = wait_time.min(target.process()?);
         }
 
         // Wait until we were asked to check again
-        std::thread::sleep(wait_time);
+        if !wait_time.is_zero() {
+            std::thread::sleep(wait_time);
+        }
     }
 }
 
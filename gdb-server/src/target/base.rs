@@ -0,0 +1,266 @@
+//! Register and memory access, and run control of the cores.
+
+use super::{non_fatal, RuntimeTarget};
+use crate::arch::{RuntimeRegId, RuntimeRegisters};
+use crate::rtos::ThreadRegister;
+use crate::threads::ResumeAction;
+use gdbstub::common::{Signal, Tid};
+use gdbstub::target::ext::base::multithread::{
+    MultiThreadBase, MultiThreadResume, MultiThreadResumeOps, MultiThreadSingleStep,
+    MultiThreadSingleStepOps,
+};
+use gdbstub::target::ext::base::single_register_access::{
+    SingleRegisterAccess, SingleRegisterAccessOps,
+};
+use gdbstub::target::ext::thread_extra_info::{ThreadExtraInfo, ThreadExtraInfoOps};
+use gdbstub::target::{TargetError, TargetResult};
+use probe_rs::{Core, Error, MemoryInterface};
+
+impl MultiThreadBase for RuntimeTarget {
+    fn read_registers(&mut self, regs: &mut RuntimeRegisters, tid: Tid) -> TargetResult<(), Self> {
+        let core = self.select(tid)?;
+        let mut session = self.session.borrow_mut();
+        let mut core = session.core(core).map_err(non_fatal)?;
+
+        regs.regs.clear();
+        for regnum in 0..self.description.registers().len() {
+            if let Some(value) = self
+                .read_thread_register(&mut core, tid, regnum)
+                .map_err(non_fatal)?
+            {
+                regs.regs.extend(value);
+            }
+        }
+
+        let pc = core.registers().program_counter();
+        regs.pc = core.read_core_reg(pc).map_err(non_fatal)?;
+
+        Ok(())
+    }
+
+    fn write_registers(&mut self, regs: &RuntimeRegisters, tid: Tid) -> TargetResult<(), Self> {
+        let core = self.select(tid)?;
+        if self.is_suspended(tid) {
+            return Err(TargetError::NonFatal);
+        }
+
+        let mut session = self.session.borrow_mut();
+        let mut core = session.core(core).map_err(non_fatal)?;
+
+        let mut bytes = &regs.regs[..];
+        for (regnum, register) in self.description.registers().iter().enumerate() {
+            let size = (register.bitsize as usize + 7) / 8;
+            if bytes.len() < size {
+                break;
+            }
+
+            self.description
+                .write_register(&mut core, regnum, &bytes[..size])
+                .map_err(non_fatal)?;
+            bytes = &bytes[size..];
+        }
+
+        Ok(())
+    }
+
+    fn read_addrs(&mut self, start_addr: u64, data: &mut [u8], tid: Tid) -> TargetResult<(), Self> {
+        let core = self.select(tid)?;
+        let mut session = self.session.borrow_mut();
+
+        session
+            .core(core)
+            .and_then(|mut core| core.read(start_addr, data))
+            .map_err(non_fatal)
+    }
+
+    fn write_addrs(&mut self, start_addr: u64, data: &[u8], tid: Tid) -> TargetResult<(), Self> {
+        let core = self.select(tid)?;
+        let mut session = self.session.borrow_mut();
+
+        session
+            .core(core)
+            .and_then(|mut core| core.write_8(start_addr, data))
+            .map_err(non_fatal)
+    }
+
+    fn list_active_threads(&mut self, thread_is_active: &mut dyn FnMut(Tid)) -> Result<(), Error> {
+        match &self.rtos {
+            Some(rtos) if rtos.is_active() => rtos.tids().for_each(thread_is_active),
+            _ => self.threads.tids().for_each(thread_is_active),
+        }
+
+        Ok(())
+    }
+
+    fn support_single_register_access(&mut self) -> Option<SingleRegisterAccessOps<'_, Tid, Self>> {
+        Some(self)
+    }
+
+    fn support_resume(&mut self) -> Option<MultiThreadResumeOps<'_, Self>> {
+        Some(self)
+    }
+
+    fn support_thread_extra_info(&mut self) -> Option<ThreadExtraInfoOps<'_, Self>> {
+        Some(self)
+    }
+}
+
+impl SingleRegisterAccess<Tid> for RuntimeTarget {
+    fn read_register(
+        &mut self,
+        tid: Tid,
+        reg_id: RuntimeRegId,
+        buf: &mut [u8],
+    ) -> TargetResult<usize, Self> {
+        let core = self.select(tid)?;
+        let mut session = self.session.borrow_mut();
+        let mut core = session.core(core).map_err(non_fatal)?;
+
+        let regnum = u32::from(reg_id) as usize;
+        match self
+            .read_thread_register(&mut core, tid, regnum)
+            .map_err(non_fatal)?
+        {
+            Some(value) => Ok(copy_to_buf(&value, buf)),
+            None => Err(TargetError::NonFatal),
+        }
+    }
+
+    fn write_register(
+        &mut self,
+        tid: Tid,
+        reg_id: RuntimeRegId,
+        val: &[u8],
+    ) -> TargetResult<(), Self> {
+        let core = self.select(tid)?;
+        if self.is_suspended(tid) {
+            return Err(TargetError::NonFatal);
+        }
+
+        let mut session = self.session.borrow_mut();
+        let mut core = session.core(core).map_err(non_fatal)?;
+
+        let regnum = u32::from(reg_id) as usize;
+        if self
+            .description
+            .write_register(&mut core, regnum, val)
+            .map_err(non_fatal)?
+        {
+            Ok(())
+        } else {
+            Err(TargetError::NonFatal)
+        }
+    }
+}
+
+impl RuntimeTarget {
+    /// Reads register `regnum` of thread `tid`, which runs on `core`. The registers of a
+    /// suspended thread of the RTOS are the ones saved by its last context switch.
+    fn read_thread_register(
+        &self,
+        core: &mut Core,
+        tid: Tid,
+        regnum: usize,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let register = self
+            .rtos
+            .as_ref()
+            .map(|rtos| rtos.register(tid, &self.description, regnum));
+
+        match register {
+            Some(ThreadRegister::Saved(value)) => Ok(Some(value)),
+            // gdb expects a value for every register, so registers which weren't saved read as
+            // zero.
+            Some(ThreadRegister::Unavailable) => Ok(self
+                .description
+                .registers()
+                .get(regnum)
+                .map(|register| vec![0; (register.bitsize as usize + 7) / 8])),
+            Some(ThreadRegister::Core) | None => self.description.read_register(core, regnum),
+        }
+    }
+
+    /// Returns `true` if `tid` is a suspended thread of the RTOS, whose registers can't be
+    /// written.
+    fn is_suspended(&self, tid: Tid) -> bool {
+        matches!(&self.rtos, Some(rtos) if rtos.is_suspended(tid))
+    }
+}
+
+impl MultiThreadResume for RuntimeTarget {
+    fn resume(&mut self) -> Result<(), Error> {
+        self.threads.resume(&mut self.session.borrow_mut())
+    }
+
+    fn clear_resume_actions(&mut self) -> Result<(), Error> {
+        self.threads.clear_resume_actions();
+
+        Ok(())
+    }
+
+    fn set_resume_action_continue(
+        &mut self,
+        tid: Tid,
+        _signal: Option<Signal>,
+    ) -> Result<(), Error> {
+        set_resume_action(self, tid, ResumeAction::Continue)
+    }
+
+    fn support_single_step(&mut self) -> Option<MultiThreadSingleStepOps<'_, Self>> {
+        Some(self)
+    }
+}
+
+impl MultiThreadSingleStep for RuntimeTarget {
+    fn set_resume_action_step(&mut self, tid: Tid, _signal: Option<Signal>) -> Result<(), Error> {
+        set_resume_action(self, tid, ResumeAction::Step)
+    }
+}
+
+fn set_resume_action(
+    target: &mut RuntimeTarget,
+    tid: Tid,
+    action: ResumeAction,
+) -> Result<(), Error> {
+    let tid = target.core_thread(tid);
+    if target.threads.set_resume_action(tid, action) {
+        Ok(())
+    } else {
+        Err(Error::Other(anyhow::anyhow!(
+            "gdb resumed thread {}, which doesn't exist",
+            tid
+        )))
+    }
+}
+
+impl ThreadExtraInfo for RuntimeTarget {
+    fn thread_extra_info(&self, tid: Tid, buf: &mut [u8]) -> Result<usize, Error> {
+        let info = match self.rtos.as_ref().and_then(|rtos| rtos.extra_info(tid)) {
+            Some(info) => info,
+            None => match self.threads.core(tid) {
+                Some(core) => format!("Core {}", core),
+                None => return Ok(0),
+            },
+        };
+
+        Ok(copy_to_buf(info.as_bytes(), buf))
+    }
+}
+
+/// Copies as much of `data` into `buf` as fits, and returns the number of bytes copied.
+pub(super) fn copy_to_buf(data: &[u8], buf: &mut [u8]) -> usize {
+    let length = data.len().min(buf.len());
+    buf[..length].copy_from_slice(&data[..length]);
+    length
+}
+
+/// Copies the part of `data` which gdb requested with `offset` and `length` into `buf`, and returns
+/// the number of bytes copied.
+pub(super) fn copy_range_to_buf(data: &[u8], offset: u64, length: usize, buf: &mut [u8]) -> usize {
+    let start = usize::try_from(offset)
+        .unwrap_or(usize::MAX)
+        .min(data.len());
+    let end = start.saturating_add(length).min(data.len());
+
+    copy_to_buf(&data[start..end], buf)
+}
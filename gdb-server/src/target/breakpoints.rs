@@ -0,0 +1,101 @@
+//! Hardware breakpoints and watchpoints, for the `Z1` to `Z4` packets.
+//!
+//! gdb sets breakpoints and watchpoints on all threads, so they are set on every core of the
+//! instance.
+
+use super::{non_fatal, RuntimeTarget};
+use crate::watchpoint::{add_hw_watchpoint, remove_hw_watchpoint};
+use gdbstub::target::ext::breakpoints::{
+    Breakpoints, HwBreakpoint, HwBreakpointOps, HwWatchpoint, HwWatchpointOps, WatchKind,
+};
+use gdbstub::target::TargetResult;
+use probe_rs::Error;
+
+impl Breakpoints for RuntimeTarget {
+    fn support_hw_breakpoint(&mut self) -> Option<HwBreakpointOps<'_, Self>> {
+        Some(self)
+    }
+
+    fn support_hw_watchpoint(&mut self) -> Option<HwWatchpointOps<'_, Self>> {
+        Some(self)
+    }
+}
+
+impl HwBreakpoint for RuntimeTarget {
+    fn add_hw_breakpoint(&mut self, addr: u64, _kind: usize) -> TargetResult<bool, Self> {
+        let mut session = self.session.borrow_mut();
+
+        for tid in self.threads.tids() {
+            let mut core = session.core(self.core(tid)?).map_err(non_fatal)?;
+
+            match core.set_hw_breakpoint(addr) {
+                Ok(()) => {}
+                // All breakpoint units are in use.
+                Err(Error::Other(error)) => {
+                    log::warn!("Could not set breakpoint: {}", error);
+                    return Ok(false);
+                }
+                Err(error) => return Err(non_fatal(error)),
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn remove_hw_breakpoint(&mut self, addr: u64, _kind: usize) -> TargetResult<bool, Self> {
+        let mut session = self.session.borrow_mut();
+        let mut removed = false;
+
+        for tid in self.threads.tids() {
+            let mut core = session.core(self.core(tid)?).map_err(non_fatal)?;
+
+            match core.clear_hw_breakpoint(addr) {
+                Ok(()) => removed = true,
+                // There is no breakpoint at the address.
+                Err(Error::Other(_)) => {}
+                Err(error) => return Err(non_fatal(error)),
+            }
+        }
+
+        Ok(removed)
+    }
+}
+
+impl HwWatchpoint for RuntimeTarget {
+    fn add_hw_watchpoint(
+        &mut self,
+        addr: u64,
+        len: u64,
+        kind: WatchKind,
+    ) -> TargetResult<bool, Self> {
+        let mut session = self.session.borrow_mut();
+
+        for tid in self.threads.tids() {
+            let mut core = session.core(self.core(tid)?).map_err(non_fatal)?;
+
+            if !add_hw_watchpoint(&mut core, addr, len, kind).map_err(non_fatal)? {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn remove_hw_watchpoint(
+        &mut self,
+        addr: u64,
+        len: u64,
+        kind: WatchKind,
+    ) -> TargetResult<bool, Self> {
+        let mut session = self.session.borrow_mut();
+        let mut removed = false;
+
+        for tid in self.threads.tids() {
+            let mut core = session.core(self.core(tid)?).map_err(non_fatal)?;
+
+            removed |= remove_hw_watchpoint(&mut core, addr, len, kind).map_err(non_fatal)?;
+        }
+
+        Ok(removed)
+    }
+}
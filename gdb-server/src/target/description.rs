@@ -0,0 +1,29 @@
+//! The target description, for `qXfer:features:read`, which tells gdb which registers the core
+//! has.
+
+use super::base::copy_range_to_buf;
+use super::RuntimeTarget;
+use gdbstub::target::ext::target_description_xml_override::TargetDescriptionXmlOverride;
+use gdbstub::target::{TargetError, TargetResult};
+
+impl TargetDescriptionXmlOverride for RuntimeTarget {
+    fn target_description_xml(
+        &self,
+        annex: &[u8],
+        offset: u64,
+        length: usize,
+        buf: &mut [u8],
+    ) -> TargetResult<usize, Self> {
+        // The description is a single document, without includes.
+        if annex != b"target.xml" {
+            return Err(TargetError::NonFatal);
+        }
+
+        Ok(copy_range_to_buf(
+            self.description_xml.as_bytes(),
+            offset,
+            length,
+            buf,
+        ))
+    }
+}
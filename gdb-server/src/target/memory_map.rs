@@ -0,0 +1,22 @@
+//! The memory map, for `qXfer:memory-map:read`, which tells gdb where the flash is.
+
+use super::base::copy_range_to_buf;
+use super::RuntimeTarget;
+use gdbstub::target::ext::memory_map::MemoryMap;
+use gdbstub::target::TargetResult;
+
+impl MemoryMap for RuntimeTarget {
+    fn memory_map_xml(
+        &self,
+        offset: u64,
+        length: usize,
+        buf: &mut [u8],
+    ) -> TargetResult<usize, Self> {
+        Ok(copy_range_to_buf(
+            self.memory_map.as_bytes(),
+            offset,
+            length,
+            buf,
+        ))
+    }
+}
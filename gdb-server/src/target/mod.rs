@@ -0,0 +1,491 @@
+//! The gdb target of a gdb instance, which connects gdbstub to the cores of the session.
+//!
+//! Each instance listens on its own socket. While no gdb is connected, the cores are left alone.
+//! When gdb connects, the cores of the instance are halted, and the packets of gdb are passed to
+//! gdbstub one byte at a time, so the instances never block each other. The packets which
+//! gdbstub doesn't support are handled here instead.
+
+mod base;
+mod breakpoints;
+mod description;
+mod memory_map;
+mod monitor;
+
+use crate::arch::RuntimeArch;
+use crate::description::TargetDescription;
+use crate::flash::{crc_reply, memory_map_xml, FlashWriter};
+use crate::interrupt::interrupt_pending;
+use crate::monitor::Monitor;
+use crate::packet::{Packet, PacketFilter};
+use crate::rtos::RtosThreads;
+use crate::semihosting::{SemihostingAction, SemihostingBridge};
+use crate::threads::{CoreThreads, HaltAction};
+use gdbstub::common::Tid;
+use gdbstub::conn::ConnectionExt;
+use gdbstub::stub::state_machine::GdbStubStateMachine;
+use gdbstub::stub::{DisconnectReason, GdbStub, GdbStubError, MultiThreadStopReason};
+use gdbstub::target::ext::base::BaseOps;
+use gdbstub::target::ext::breakpoints::BreakpointsOps;
+use gdbstub::target::ext::memory_map::MemoryMapOps;
+use gdbstub::target::ext::monitor_cmd::MonitorCmdOps;
+use gdbstub::target::ext::target_description_xml_override::TargetDescriptionXmlOverrideOps;
+use gdbstub::target::{Target, TargetError};
+use probe_rs::architecture::arm::semihosting::SemihostingPolicy;
+use probe_rs::debug::DebugInfo;
+use probe_rs::{Architecture, Error, Session};
+use std::cell::RefCell;
+use std::io::ErrorKind;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// How long to wait before checking again, if nothing happened.
+const IDLE_WAIT: Duration = Duration::from_millis(100);
+
+type GdbStateMachine = GdbStubStateMachine<'static, RuntimeTarget, TcpStream>;
+type GdbError = GdbStubError<Error, std::io::Error>;
+
+/// The gdb target of a gdb instance.
+pub(crate) struct RuntimeTarget {
+    /// The state of the gdb connection, if gdb is connected.
+    gdb: Option<GdbStateMachine>,
+    packets: PacketFilter,
+    session: Rc<RefCell<Session>>,
+    listener: TcpListener,
+    threads: CoreThreads,
+    /// The threads of the RTOS of the program, which are reported instead of the core.
+    rtos: Option<RtosThreads>,
+    /// The core of the thread which gdb accessed last, for the monitor commands.
+    selected_core: usize,
+    /// The cores of an instance have the same type, so they share the description.
+    description: TargetDescription,
+    description_xml: String,
+    memory_map: String,
+    flash: FlashWriter,
+    monitor: Monitor,
+    semihosting: SemihostingBridge,
+    /// The core whose semihosting call was forwarded to gdb.
+    semihosting_core: usize,
+    /// gdb interrupted the forwarded semihosting call, which has to be reported as a stop.
+    semihosting_interrupted: bool,
+}
+
+impl RuntimeTarget {
+    /// Creates the target for `cores` of the session, and listens for gdb on `addrs`.
+    ///
+    /// If the `elf` file of the program is given, the threads of its RTOS are reported.
+    pub(crate) fn new(
+        session: Rc<RefCell<Session>>,
+        cores: Vec<usize>,
+        addrs: &[SocketAddr],
+        elf: Option<&Path>,
+    ) -> Result<Self, Error> {
+        let listener = TcpListener::bind(addrs).map_err(|error| Error::Other(error.into()))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|error| Error::Other(error.into()))?;
+
+        let (description, memory_map) = {
+            let mut session = session.borrow_mut();
+            let target = session.target();
+            let memory_map = memory_map_xml(&target.memory_map, &target.flash_algorithms);
+
+            let mut core = session.core(cores[0])?;
+            (TargetDescription::new(&mut core)?, memory_map)
+        };
+
+        // gdb can't tell on which core a thread of the RTOS runs, so they are only reported for
+        // a single core.
+        let rtos = match elf {
+            Some(elf) if cores.len() == 1 => {
+                let debug_info = DebugInfo::from_file(elf).map_err(|error| {
+                    Error::Other(anyhow::anyhow!(
+                        "Failed to read {}: {}",
+                        elf.display(),
+                        error
+                    ))
+                })?;
+                RtosThreads::new(debug_info)
+            }
+            Some(_) => {
+                log::warn!("The threads of an RTOS are only reported for a single core");
+                None
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            gdb: None,
+            packets: PacketFilter::default(),
+            session,
+            listener,
+            selected_core: cores[0],
+            semihosting_core: cores[0],
+            threads: CoreThreads::new(cores),
+            rtos,
+            description_xml: description.xml(),
+            description,
+            memory_map,
+            flash: FlashWriter::default(),
+            monitor: Monitor::default(),
+            semihosting: SemihostingBridge::new(SemihostingPolicy::default()),
+            semihosting_interrupted: false,
+        })
+    }
+
+    /// Processes the pending events of the instance, and returns how long to wait until it has
+    /// to be checked again.
+    pub(crate) fn process(&mut self) -> Result<Duration, Error> {
+        let gdb = match self.gdb.take() {
+            Some(gdb) => gdb,
+            None => match self.accept()? {
+                Some(gdb) => gdb,
+                None => return Ok(IDLE_WAIT),
+            },
+        };
+
+        let mut wait_time = Duration::ZERO;
+
+        let result = match gdb {
+            GdbStubStateMachine::Idle(mut state) => match self.next_byte(state.borrow_conn()) {
+                Ok(Some(byte)) => state.incoming_data(self, byte),
+                Ok(None) => {
+                    wait_time = IDLE_WAIT;
+                    Ok(state.into())
+                }
+                Err(error) => Err(error),
+            },
+            GdbStubStateMachine::Running(mut state) => match self.poll(state.borrow_conn()) {
+                Ok(Some(stop_reason)) => state.report_stop(self, stop_reason),
+                Ok(None) => match self.next_byte(state.borrow_conn()) {
+                    Ok(Some(byte)) => state.incoming_data(self, byte),
+                    Ok(None) => {
+                        wait_time = IDLE_WAIT;
+                        Ok(state.into())
+                    }
+                    Err(error) => Err(error),
+                },
+                Err(error) => Err(error),
+            },
+            GdbStubStateMachine::CtrlCInterrupt(state) => {
+                let stop_reason = self.threads.interrupt(&mut self.session.borrow_mut())?;
+                let stop_reason = self.stopped(stop_reason)?;
+                state.interrupt_handled(self, stop_reason)
+            }
+            GdbStubStateMachine::Disconnected(state) => {
+                self.disconnect(state.get_reason())?;
+                return Ok(wait_time);
+            }
+        };
+
+        match result {
+            Ok(gdb) => self.gdb = Some(gdb),
+            Err(GdbStubError::TargetError(error)) => return Err(error),
+            Err(error) => {
+                log::error!("Closing the gdb connection: {}", error);
+                self.disconnect(DisconnectReason::Disconnect)?;
+            }
+        }
+
+        Ok(wait_time)
+    }
+
+    /// Accepts a connection from gdb, and halts the cores, which gdb expects to be halted.
+    fn accept(&mut self) -> Result<Option<GdbStateMachine>, Error> {
+        let (stream, addr) = match self.listener.accept() {
+            Ok(connection) => connection,
+            Err(error) if error.kind() == ErrorKind::WouldBlock => return Ok(None),
+            Err(error) => return Err(Error::Other(error.into())),
+        };
+
+        log::info!("Accepted a gdb connection from {}", addr);
+
+        self.threads.halt_all(&mut self.session.borrow_mut())?;
+        self.refresh_rtos()?;
+        self.packets = PacketFilter::default();
+        self.flash = FlashWriter::default();
+        self.semihosting_interrupted = false;
+
+        match GdbStub::<RuntimeTarget, TcpStream>::new(stream).run_state_machine(self) {
+            Ok(gdb) => Ok(Some(gdb)),
+            Err(error) => Err(gdb_error(error)),
+        }
+    }
+
+    /// Returns the next byte from gdb for gdbstub, if there is one. The packets which gdbstub
+    /// doesn't support are answered on the way.
+    fn next_byte(&mut self, conn: &mut TcpStream) -> Result<Option<u8>, GdbError> {
+        loop {
+            if let Some(byte) = self.packets.pop() {
+                return Ok(Some(byte));
+            }
+
+            let byte = match read_byte(conn).map_err(GdbStubError::ConnectionRead)? {
+                Some(byte) => byte,
+                None => return Ok(None),
+            };
+
+            if let Some(packet) = self.packets.push(byte) {
+                let reply = self.handle_packet(packet);
+                self.packets
+                    .reply(conn, reply.as_deref())
+                    .map_err(GdbStubError::ConnectionWrite)?;
+            }
+        }
+    }
+
+    /// Handles a packet which gdbstub doesn't support, and returns the reply, if there is one.
+    fn handle_packet(&mut self, packet: Packet) -> Option<String> {
+        let mut session = self.session.borrow_mut();
+
+        let reply = match packet {
+            Packet::FlashErase { address, length } => {
+                let flash_algorithms = &session.target().flash_algorithms;
+                self.flash.erase(flash_algorithms, address, length);
+                "OK".to_string()
+            }
+            Packet::FlashWrite { address, data } => {
+                self.flash.write(address, &data);
+                "OK".to_string()
+            }
+            Packet::FlashDone => match self.flash.done(&mut session) {
+                Ok(()) => "OK".to_string(),
+                Err(error) => {
+                    log::error!("Failed to program the flash: {}", error);
+                    "E01".to_string()
+                }
+            },
+            // The cores of an instance share their memory, so the first core calculates the CRC.
+            Packet::Crc(arguments) => {
+                crc_reply(&mut session, self.threads.first_core(), &arguments)
+            }
+            // The reply completes the semihosting call, and the core continues. The stop is
+            // reported later, so there is no reply to the reply.
+            Packet::FileIoReply(reply) => {
+                let result = session
+                    .core(self.semihosting_core)
+                    .and_then(|mut core| self.semihosting.reply(&mut core, &reply))
+                    .and_then(|interrupted| {
+                        if !interrupted {
+                            self.threads.resume_waiting(&mut session)?;
+                        }
+                        Ok(interrupted)
+                    });
+
+                // If the call can't be completed, the core is reported as interrupted, so gdb
+                // can take over.
+                self.semihosting_interrupted = result.unwrap_or_else(|error| {
+                    log::error!("Failed to complete the semihosting call: {}", error);
+                    true
+                });
+
+                return None;
+            }
+        };
+
+        Some(reply)
+    }
+
+    /// Checks if a running core halted, and returns the stop reason for gdb if one did.
+    ///
+    /// If gdb sent the break character, the cores aren't checked, so the Ctrl-C is read and
+    /// handled first.
+    fn poll(
+        &mut self,
+        conn: &mut TcpStream,
+    ) -> Result<Option<MultiThreadStopReason<u64>>, GdbError> {
+        if interrupt_pending(conn).map_err(GdbStubError::ConnectionRead)? {
+            return Ok(None);
+        }
+
+        let stop_reason = if self.semihosting_interrupted {
+            self.semihosting_interrupted = false;
+            self.threads
+                .interrupt(&mut self.session.borrow_mut())
+                .map_err(GdbStubError::TargetError)?
+        } else if self.semihosting.is_pending() {
+            return Ok(None);
+        } else {
+            self.poll_cores(conn)?
+        };
+
+        self.stopped(stop_reason).map_err(GdbStubError::TargetError)
+    }
+
+    /// Checks if a running core halted, and returns the stop reason if one did.
+    ///
+    /// A core which halted on a semihosting call isn't reported. The call is serviced on the
+    /// host, or forwarded to gdb, and the cores aren't checked until gdb replied.
+    fn poll_cores(
+        &mut self,
+        conn: &mut TcpStream,
+    ) -> Result<Option<MultiThreadStopReason<u64>>, GdbError> {
+        let mut session = self.session.borrow_mut();
+
+        let bridge = &mut self.semihosting;
+        let mut forwarded = None;
+        let stop_reason = self
+            .threads
+            .poll(&mut session, |session, core_number| {
+                let mut core = session.core(core_number)?;
+                if core.architecture() != Architecture::Arm {
+                    return Ok(HaltAction::Report);
+                }
+
+                let action = match bridge.handle(&mut core)? {
+                    SemihostingAction::None => HaltAction::Report,
+                    SemihostingAction::Resume => HaltAction::Resume,
+                    SemihostingAction::Forward(packet) => {
+                        forwarded = Some((core_number, packet));
+                        HaltAction::Wait
+                    }
+                    SemihostingAction::Exit(status) => {
+                        HaltAction::Stop(MultiThreadStopReason::Exited(status.code() as u8))
+                    }
+                };
+
+                Ok(action)
+            })
+            .map_err(GdbStubError::TargetError)?;
+
+        if let Some((core, packet)) = forwarded {
+            self.semihosting_core = core;
+            self.packets
+                .send(conn, &packet)
+                .map_err(GdbStubError::ConnectionWrite)?;
+        }
+
+        Ok(stop_reason)
+    }
+
+    /// Reads the threads of the RTOS from the halted core.
+    fn refresh_rtos(&mut self) -> Result<(), Error> {
+        if let Some(rtos) = &mut self.rtos {
+            let mut session = self.session.borrow_mut();
+            rtos.refresh(&mut session.core(self.threads.first_core())?);
+        }
+
+        Ok(())
+    }
+
+    /// Reads the threads of the RTOS after the cores stopped, and reports the stop for the
+    /// running thread of the RTOS.
+    fn stopped(
+        &mut self,
+        stop_reason: Option<MultiThreadStopReason<u64>>,
+    ) -> Result<Option<MultiThreadStopReason<u64>>, Error> {
+        let stop_reason = match stop_reason {
+            Some(stop_reason) => stop_reason,
+            None => return Ok(None),
+        };
+
+        self.refresh_rtos()?;
+
+        let tid = match self.rtos.as_ref().and_then(RtosThreads::current) {
+            Some(tid) => tid,
+            None => return Ok(Some(stop_reason)),
+        };
+
+        let stop_reason = match stop_reason {
+            MultiThreadStopReason::SignalWithThread { signal, .. } => {
+                MultiThreadStopReason::SignalWithThread { tid, signal }
+            }
+            MultiThreadStopReason::Watch { kind, addr, .. } => {
+                MultiThreadStopReason::Watch { tid, kind, addr }
+            }
+            stop_reason => stop_reason,
+        };
+
+        Ok(Some(stop_reason))
+    }
+
+    /// Cleans up after gdb disconnected. If gdb detached, the cores are resumed.
+    fn disconnect(&mut self, reason: DisconnectReason) -> Result<(), Error> {
+        log::info!("gdb disconnected: {:?}", reason);
+
+        if reason == DisconnectReason::Disconnect {
+            self.threads.resume_all(&mut self.session.borrow_mut())?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the thread of the core which runs thread `tid`, which can be a thread of the RTOS.
+    fn core_thread(&self, tid: Tid) -> Tid {
+        match &self.rtos {
+            // The threads of the RTOS run on the only core of the instance.
+            Some(rtos) if rtos.contains(tid) => self.threads.tids().next().unwrap_or(tid),
+            _ => tid,
+        }
+    }
+
+    /// Returns the number of the core in the session which runs thread `tid`.
+    fn core(&self, tid: Tid) -> Result<usize, TargetError<Error>> {
+        self.threads
+            .core(self.core_thread(tid))
+            .ok_or(TargetError::NonFatal)
+    }
+
+    /// Returns the number of the core in the session which runs thread `tid`, whose registers or
+    /// memory gdb accesses, and selects the core for the monitor commands.
+    fn select(&mut self, tid: Tid) -> Result<usize, TargetError<Error>> {
+        self.selected_core = self.core(tid)?;
+        Ok(self.selected_core)
+    }
+}
+
+impl Target for RuntimeTarget {
+    type Arch = RuntimeArch;
+    type Error = Error;
+
+    fn base_ops(&mut self) -> BaseOps<'_, Self::Arch, Self::Error> {
+        BaseOps::MultiThread(self)
+    }
+
+    // Breakpoints are set with the breakpoint units of the cores, so gdb must not write
+    // breakpoint instructions into memory, which may be flash.
+    fn guard_rail_implicit_sw_breakpoints(&self) -> bool {
+        true
+    }
+
+    fn support_breakpoints(&mut self) -> Option<BreakpointsOps<'_, Self>> {
+        Some(self)
+    }
+
+    fn support_memory_map(&mut self) -> Option<MemoryMapOps<'_, Self>> {
+        Some(self)
+    }
+
+    fn support_monitor_cmd(&mut self) -> Option<MonitorCmdOps<'_, Self>> {
+        Some(self)
+    }
+
+    fn support_target_description_xml_override(
+        &mut self,
+    ) -> Option<TargetDescriptionXmlOverrideOps<'_, Self>> {
+        Some(self)
+    }
+}
+
+/// Reads a byte from gdb, if there is one.
+fn read_byte(stream: &mut TcpStream) -> std::io::Result<Option<u8>> {
+    match ConnectionExt::peek(stream)? {
+        Some(_) => ConnectionExt::read(stream).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Reports a probe error, which happened while handling a packet, to gdb as an error reply, so
+/// gdb can continue, e.g. after it accessed memory which doesn't exist.
+fn non_fatal(error: Error) -> TargetError<Error> {
+    log::warn!("Error while handling a gdb request: {}", error);
+    TargetError::NonFatal
+}
+
+fn gdb_error(error: GdbError) -> Error {
+    match error {
+        GdbStubError::TargetError(error) => error,
+        error => Error::Other(anyhow::anyhow!("{}", error)),
+    }
+}
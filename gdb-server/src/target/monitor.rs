@@ -0,0 +1,18 @@
+//! The `monitor` commands, for `qRcmd`.
+
+use super::RuntimeTarget;
+use gdbstub::target::ext::monitor_cmd::{ConsoleOutput, MonitorCmd};
+use probe_rs::Error;
+
+impl MonitorCmd for RuntimeTarget {
+    fn handle_monitor_cmd(&mut self, cmd: &[u8], mut out: ConsoleOutput<'_>) -> Result<(), Error> {
+        let command = String::from_utf8_lossy(cmd);
+
+        let output = self
+            .monitor
+            .run(&mut self.session.borrow_mut(), self.selected_core, &command);
+        out.write_raw(output.as_bytes());
+
+        Ok(())
+    }
+}
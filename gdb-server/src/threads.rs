@@ -0,0 +1,311 @@
+//! The cores of a gdb instance, which are exposed to gdb as threads.
+//!
+//! The `n`th core of an instance is the thread with the id `n + 1`. gdb selects the thread for
+//! register and memory accesses with `Hg`, and the threads to resume with `Hc` or `vCont`.
+//!
+//! gdb runs in all-stop mode, so as soon as one core halts, the other running cores of the
+//! instance are halted as well, and the core which halted first is reported in the stop reply.
+//! Cores of other instances, e.g. of a different architecture, are never touched.
+//!
+//! A core can also halt on a request which is serviced before it continues, e.g. a semihosting
+//! call. Such a halt is not reported to gdb.
+
+use crate::watchpoint::watchpoint_stop_reason;
+use gdbstub::common::{Signal, Tid};
+use gdbstub::stub::MultiThreadStopReason;
+use probe_rs::{CoreStatus, Error, HaltReason, Session};
+use std::num::NonZeroUsize;
+use std::time::Duration;
+
+/// How a thread is resumed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ResumeAction {
+    Continue,
+    Step,
+}
+
+/// The run state of a core, as far as gdb knows it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ThreadState {
+    Halted,
+    Running,
+    /// The core did a single step, which hasn't been reported yet.
+    Stepped,
+    /// The core halted on a request, which is serviced before it continues. gdb still considers
+    /// it running.
+    Waiting,
+}
+
+/// What happens with a core which halted while it was running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum HaltAction {
+    /// The halt is reported to gdb.
+    Report,
+    /// The halt was handled, and the core continues.
+    Resume,
+    /// The core waits until [`CoreThreads::resume_waiting`] is called.
+    Wait,
+    /// The halt is reported to gdb with the given stop reason.
+    Stop(MultiThreadStopReason<u64>),
+}
+
+/// The cores of a gdb instance, with their resume actions and run state.
+#[derive(Debug)]
+pub(crate) struct CoreThreads {
+    /// The numbers of the cores in the session.
+    cores: Vec<usize>,
+    actions: Vec<Option<ResumeAction>>,
+    states: Vec<ThreadState>,
+}
+
+impl CoreThreads {
+    /// Creates the threads for `cores`, the core numbers in the session, which are all halted.
+    pub(crate) fn new(cores: Vec<usize>) -> Self {
+        let count = cores.len();
+
+        Self {
+            cores,
+            actions: vec![None; count],
+            states: vec![ThreadState::Halted; count],
+        }
+    }
+
+    /// Returns the ids of all threads.
+    pub(crate) fn tids(&self) -> impl Iterator<Item = Tid> {
+        (1..=self.cores.len()).filter_map(NonZeroUsize::new)
+    }
+
+    /// Returns the number of the first core of the instance in the session.
+    pub(crate) fn first_core(&self) -> usize {
+        self.cores[0]
+    }
+
+    /// Returns the number of the core of thread `tid` in the session.
+    pub(crate) fn core(&self, tid: Tid) -> Option<usize> {
+        self.cores.get(tid.get() - 1).copied()
+    }
+
+    /// Clears the resume actions of all threads, before the actions of a `vCont` packet are set.
+    pub(crate) fn clear_resume_actions(&mut self) {
+        self.actions.iter_mut().for_each(|action| *action = None);
+    }
+
+    /// Sets the resume action of thread `tid`, and returns `false` if there is no such thread.
+    pub(crate) fn set_resume_action(&mut self, tid: Tid, action: ResumeAction) -> bool {
+        match self.actions.get_mut(tid.get() - 1) {
+            Some(slot) => {
+                *slot = Some(action);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Resumes the threads with their resume actions. Threads without a resume action are
+    /// continued, because gdb sends `vCont;c` without a thread id to continue all threads.
+    ///
+    /// If any thread is stepped, the other threads are not resumed, because the step ends right
+    /// away, and they would have to be halted again immediately.
+    pub(crate) fn resume(&mut self, session: &mut Session) -> Result<(), Error> {
+        let step = self.actions.contains(&Some(ResumeAction::Step));
+
+        for index in 0..self.cores.len() {
+            let mut core = session.core(self.cores[index])?;
+
+            match self.actions[index] {
+                Some(ResumeAction::Step) => {
+                    core.step()?;
+                    self.states[index] = ThreadState::Stepped;
+                }
+                Some(ResumeAction::Continue) | None if !step => {
+                    core.run()?;
+                    self.states[index] = ThreadState::Running;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Halts all cores, when gdb connects.
+    pub(crate) fn halt_all(&mut self, session: &mut Session) -> Result<(), Error> {
+        for index in 0..self.cores.len() {
+            let mut core = session.core(self.cores[index])?;
+            if !core.core_halted()? {
+                core.halt(Duration::from_millis(100))?;
+            }
+            self.states[index] = ThreadState::Halted;
+        }
+
+        Ok(())
+    }
+
+    /// Resumes all cores, when gdb detaches.
+    pub(crate) fn resume_all(&mut self, session: &mut Session) -> Result<(), Error> {
+        for index in 0..self.cores.len() {
+            let mut core = session.core(self.cores[index])?;
+            if core.core_halted()? {
+                core.run()?;
+            }
+            self.states[index] = ThreadState::Running;
+        }
+
+        Ok(())
+    }
+
+    /// Checks if a running core halted, and returns the stop reason if one did.
+    ///
+    /// `on_halt` is called with the number of the core in the session, and decides what happens
+    /// with the halted core. The other running cores are halted before the stop is reported.
+    pub(crate) fn poll(
+        &mut self,
+        session: &mut Session,
+        mut on_halt: impl FnMut(&mut Session, usize) -> Result<HaltAction, Error>,
+    ) -> Result<Option<MultiThreadStopReason<u64>>, Error> {
+        if let Some(index) = self
+            .states
+            .iter()
+            .position(|&state| state == ThreadState::Stepped)
+        {
+            self.states[index] = ThreadState::Halted;
+            return Ok(Some(MultiThreadStopReason::DoneStep));
+        }
+
+        for index in 0..self.cores.len() {
+            if self.states[index] != ThreadState::Running {
+                continue;
+            }
+
+            let status = session.core(self.cores[index])?.status()?;
+            if let CoreStatus::Halted(reason) = status {
+                let stop_reason = match on_halt(session, self.cores[index])? {
+                    // The watchpoint which triggered has to be read before the other cores are
+                    // halted.
+                    HaltAction::Report => self.stop_reason(session, index, reason)?,
+                    HaltAction::Resume => {
+                        session.core(self.cores[index])?.run()?;
+                        continue;
+                    }
+                    HaltAction::Wait => {
+                        self.states[index] = ThreadState::Waiting;
+                        return Ok(None);
+                    }
+                    HaltAction::Stop(stop_reason) => stop_reason,
+                };
+
+                self.states[index] = ThreadState::Halted;
+                self.halt_running(session)?;
+
+                return Ok(Some(stop_reason));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Resumes the cores which wait for a request to be serviced.
+    pub(crate) fn resume_waiting(&mut self, session: &mut Session) -> Result<(), Error> {
+        for index in 0..self.cores.len() {
+            if self.states[index] == ThreadState::Waiting {
+                session.core(self.cores[index])?.run()?;
+                self.states[index] = ThreadState::Running;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Halts all running cores, when gdb interrupts the target, and returns the stop reason.
+    pub(crate) fn interrupt(
+        &mut self,
+        session: &mut Session,
+    ) -> Result<Option<MultiThreadStopReason<u64>>, Error> {
+        let first = match self
+            .states
+            .iter()
+            .position(|&state| state == ThreadState::Running || state == ThreadState::Waiting)
+        {
+            Some(first) => first,
+            None => return Ok(None),
+        };
+
+        self.halt_running(session)?;
+
+        Ok(Some(MultiThreadStopReason::SignalWithThread {
+            tid: self.tid_of(first),
+            signal: Signal::SIGINT,
+        }))
+    }
+
+    /// Halts the running cores. The cores which wait for a request are already halted.
+    fn halt_running(&mut self, session: &mut Session) -> Result<(), Error> {
+        for index in 0..self.cores.len() {
+            match self.states[index] {
+                ThreadState::Running => {
+                    session
+                        .core(self.cores[index])?
+                        .halt(Duration::from_millis(100))?;
+                    self.states[index] = ThreadState::Halted;
+                }
+                ThreadState::Waiting => self.states[index] = ThreadState::Halted,
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn stop_reason(
+        &self,
+        session: &mut Session,
+        index: usize,
+        reason: HaltReason,
+    ) -> Result<MultiThreadStopReason<u64>, Error> {
+        let tid = self.tid_of(index);
+
+        let signal = match reason {
+            HaltReason::Step => return Ok(MultiThreadStopReason::DoneStep),
+            HaltReason::Watchpoint => {
+                let mut core = session.core(self.cores[index])?;
+                if let Some(stop_reason) = watchpoint_stop_reason(&mut core, tid)? {
+                    return Ok(stop_reason);
+                }
+                Signal::SIGTRAP
+            }
+            HaltReason::Request | HaltReason::External => Signal::SIGINT,
+            _ => Signal::SIGTRAP,
+        };
+
+        Ok(MultiThreadStopReason::SignalWithThread { tid, signal })
+    }
+
+    fn tid_of(&self, index: usize) -> Tid {
+        // Thread ids start at one, so they are never zero.
+        NonZeroUsize::new(index + 1).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CoreThreads, ResumeAction};
+    use std::num::NonZeroUsize;
+
+    #[test]
+    fn threads_of_cores() {
+        let mut threads = CoreThreads::new(vec![2, 3]);
+
+        let tids: Vec<usize> = threads.tids().map(|tid| tid.get()).collect();
+        assert_eq!(tids, [1, 2]);
+
+        assert_eq!(threads.core(NonZeroUsize::new(1).unwrap()), Some(2));
+        assert_eq!(threads.core(NonZeroUsize::new(3).unwrap()), None);
+
+        assert!(threads.set_resume_action(NonZeroUsize::new(2).unwrap(), ResumeAction::Step));
+        assert!(!threads.set_resume_action(NonZeroUsize::new(3).unwrap(), ResumeAction::Step));
+        assert_eq!(threads.actions, [None, Some(ResumeAction::Step)]);
+
+        threads.clear_resume_actions();
+        assert_eq!(threads.actions, [None, None]);
+    }
+}
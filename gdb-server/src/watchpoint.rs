@@ -0,0 +1,88 @@
+//! Hardware watchpoints, for the `Z2`, `Z3` and `Z4` packets.
+//!
+//! The watchpoints are set with the watchpoint API of the core. When the core halts because of a
+//! watchpoint, the watched address is reported in the stop reply, so gdb can tell which of its
+//! watchpoints triggered.
+
+use gdbstub::common::Tid;
+use gdbstub::stub::MultiThreadStopReason;
+use gdbstub::target::ext::breakpoints::WatchKind;
+use probe_rs::{Core, Error, Watchpoint, WatchpointKind};
+
+fn watchpoint(addr: u64, len: u64, kind: WatchKind) -> Watchpoint {
+    let kind = match kind {
+        WatchKind::Write => WatchpointKind::Write,
+        WatchKind::Read => WatchpointKind::Read,
+        WatchKind::ReadWrite => WatchpointKind::Access,
+    };
+
+    Watchpoint {
+        address: addr,
+        length: len,
+        kind,
+    }
+}
+
+/// Sets a watchpoint, and returns `false` if the core can't watch the range.
+///
+/// gdb tells the user that the watchpoint can't be set if `false` is returned, so only errors of
+/// the probe are returned as errors.
+pub(crate) fn add_hw_watchpoint(
+    core: &mut Core,
+    addr: u64,
+    len: u64,
+    kind: WatchKind,
+) -> Result<bool, Error> {
+    match core.set_hw_watchpoint(watchpoint(addr, len, kind)) {
+        Ok(()) => Ok(true),
+        Err(Error::Other(error)) => {
+            log::warn!("Could not set watchpoint: {}", error);
+            Ok(false)
+        }
+        Err(error) => Err(error),
+    }
+}
+
+/// Clears a watchpoint, and returns `false` if it wasn't set.
+pub(crate) fn remove_hw_watchpoint(
+    core: &mut Core,
+    addr: u64,
+    len: u64,
+    kind: WatchKind,
+) -> Result<bool, Error> {
+    match core.clear_hw_watchpoint(watchpoint(addr, len, kind)) {
+        Ok(()) => Ok(true),
+        Err(Error::Other(error)) => {
+            log::debug!("Could not clear watchpoint: {}", error);
+            Ok(false)
+        }
+        Err(error) => Err(error),
+    }
+}
+
+/// Returns the stop reason for thread `tid`, whose core halted because of a watchpoint.
+///
+/// The core reports the triggered watchpoint only once, so this has to be called right after the
+/// core halted. `None` is returned if the core doesn't report which watchpoint triggered, and the
+/// stop has to be reported without an address.
+pub(crate) fn watchpoint_stop_reason(
+    core: &mut Core,
+    tid: Tid,
+) -> Result<Option<MultiThreadStopReason<u64>>, Error> {
+    let watchpoint = match core.triggered_watchpoint()? {
+        Some(watchpoint) => watchpoint,
+        None => return Ok(None),
+    };
+
+    let kind = match watchpoint.kind {
+        WatchpointKind::Write => WatchKind::Write,
+        WatchpointKind::Read => WatchKind::Read,
+        WatchpointKind::Access => WatchKind::ReadWrite,
+    };
+
+    Ok(Some(MultiThreadStopReason::Watch {
+        tid,
+        kind,
+        addr: watchpoint.address,
+    }))
+}
@@ -33,16 +33,63 @@
 //! ```
 use crate::ArtifactError;
 
-use std::{fs::File, io::Write, path::Path, path::PathBuf};
+use std::{error::Error, fs::File, io::Write, path::Path, path::PathBuf};
 
 use byte_unit::Byte;
 use clap;
 use probe_rs::{
-    config::{RegistryError, TargetSelector},
+    config::{ChipFamily, DebugSequenceScript, RegistryError, TargetSelector},
     flashing::{FileDownloadError, FlashError, FlashLoader},
-    DebugProbeError, DebugProbeSelector, FakeProbe, Permissions, Probe, Session, Target,
-    WireProtocol,
+    AttachOptions, DebugProbeError, DebugProbeSelector, FakeProbe, Permissions, Probe,
+    ProbeSelectionError, Session, Target, WireProtocol,
 };
+#[cfg(feature = "serial")]
+use probe_rs::{VcomError, VirtualComPort};
+
+/// Whether `path` is a CMSIS-Pack or a `.pdsc` file, instead of a target description.
+fn is_cmsis_pack(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|extension| extension.to_str()),
+        Some("pack") | Some("pdsc")
+    )
+}
+
+/// Generate the target descriptions of a CMSIS-Pack.
+#[cfg(feature = "cmsis-pack")]
+fn families_from_pack(path: &Path) -> Result<Vec<ChipFamily>, Box<dyn Error + Send + Sync>> {
+    Ok(target_gen::families_from_pack(path)?)
+}
+
+#[cfg(not(feature = "cmsis-pack"))]
+fn families_from_pack(_path: &Path) -> Result<Vec<ChipFamily>, Box<dyn Error + Send + Sync>> {
+    Err("CMSIS-Packs are only supported with the `cmsis-pack` feature".into())
+}
+
+/// The names of the chips which are most similar to `name`, to be shown when `name` was not found.
+fn chip_suggestions(name: &str) -> Vec<String> {
+    probe_rs::config::search(name)
+        .into_iter()
+        .take(5)
+        .map(|chip| chip.chip)
+        .collect()
+}
+
+fn format_suggestions(suggestions: &[String]) -> String {
+    if suggestions.is_empty() {
+        String::new()
+    } else {
+        format!(" Did you mean one of: {}?", suggestions.join(", "))
+    }
+}
+
+fn load_sequence_script(path: &Path) -> Result<DebugSequenceScript, Box<dyn Error + Send + Sync>> {
+    let file = File::open(path)?;
+    Ok(serde_yaml::from_reader(file)?)
+}
+
+/// The environment variable which can contain the path of additional target descriptions,
+/// either a single file or a directory.
+pub const CHIP_DESCRIPTION_PATH_ENV: &str = "PROBE_RS_CHIP_DESCRIPTION_PATH";
 
 /// Common options when flashing a target device.
 #[derive(Debug, clap::StructOpt)]
@@ -85,6 +132,18 @@ pub struct FlashOptions {
         help = "Enable this flag to restore all bytes erased in the sector erase but not overwritten by any page."
     )]
     pub restore_unwritten: bool,
+    #[structopt(
+        name = "skip-unchanged",
+        long = "skip-unchanged",
+        help = "Enable this flag to read back the flash first and skip all sectors which already contain the new data."
+    )]
+    pub skip_unchanged: bool,
+    #[structopt(
+        name = "cache file",
+        long = "sector-cache",
+        help = "Keep track of the programmed sectors in the given file, and skip all sectors which are unchanged since the last download to the same target."
+    )]
+    pub sector_cache: Option<PathBuf>,
     #[structopt(
         name = "filename",
         long = "flash-layout",
@@ -138,8 +197,22 @@ impl FlashOptions {
 pub struct ProbeOptions {
     #[structopt(long)]
     pub chip: Option<String>,
+    /// A target description file, or a directory of target description files, which are added to the built-in targets.
+    ///
+    /// If built with the `cmsis-pack` feature, this can also be a CMSIS-Pack (`.pack`) or a `.pdsc` file,
+    /// from which the target descriptions are generated.
+    ///
+    /// Additional target descriptions can also be given with the `PROBE_RS_CHIP_DESCRIPTION_PATH` environment variable.
+    /// Targets given with this option take precedence over the ones from the environment variable.
     #[structopt(name = "chip description file path", long = "chip-description-path")]
     pub chip_description_path: Option<PathBuf>,
+    /// A YAML file with debug sequences, which replace the sequences of the chip.
+    ///
+    /// The sequences are written in the language of the CMSIS-Pack debug description,
+    /// e.g. to write a few registers of the board when connecting, before flashing or when resetting.
+    /// Requires the chip to be given with `--chip`.
+    #[structopt(long = "sequence-script")]
+    pub sequence_script: Option<PathBuf>,
 
     /// Protocol used to connect to chip. Possible options: [swd, jtag]
     #[structopt(long, help_heading = "PROBE CONFIGURATION")]
@@ -147,7 +220,8 @@ pub struct ProbeOptions {
 
     /// Use this flag to select a specific probe in the list.
     ///
-    /// Use '--probe VID:PID' or '--probe VID:PID:Serial' if you have more than one probe with the same VID:PID.",
+    /// Use '--probe VID:PID' or '--probe VID:PID:Serial' if you have more than one probe with the same VID:PID.
+    /// The serial number may contain '*' and '?' wildcards.",
     #[structopt(long = "probe", help_heading = "PROBE CONFIGURATION")]
     pub probe_selector: Option<DebugProbeSelector>,
     #[clap(
@@ -169,37 +243,89 @@ pub struct ProbeOptions {
         even when it has read-only protection."
     )]
     pub allow_erase_all: bool,
+    /// Don't suspend the watchdogs of the chip while it is halted, e.g. to test the watchdog itself.
+    #[structopt(long = "keep-watchdogs-running")]
+    pub keep_watchdogs_running: bool,
+    /// Attach to the running chip without resetting or halting it, or changing its debug configuration.
+    #[structopt(long = "preserve-state", conflicts_with = "connect-under-reset")]
+    pub preserve_state: bool,
+    /// Release the debug power domains of the chip when detaching, so it can enter its low power modes.
+    #[structopt(long = "power-down-on-detach")]
+    pub power_down_on_detach: bool,
 }
 
 impl ProbeOptions {
-    /// Add targets contained in file given by --chip-description-path
-    /// to probe-rs registery.
+    /// Add targets contained in the file or directory given by the `PROBE_RS_CHIP_DESCRIPTION_PATH`
+    /// environment variable and by --chip-description-path to probe-rs registery.
     ///
     /// Note: should be called before [FlashOptions::early_exit] and any other functions in [ProbeOptions].
     pub fn maybe_load_chip_desc(&self) -> Result<(), OperationError> {
-        if let Some(ref cdp) = self.chip_description_path {
-            probe_rs::config::add_target_from_yaml(Path::new(cdp)).map_err(|error| {
-                OperationError::FailedChipDescriptionParsing {
-                    source: error,
-                    path: cdp.clone(),
+        let env_path = std::env::var_os(CHIP_DESCRIPTION_PATH_ENV).map(PathBuf::from);
+
+        for cdp in env_path.iter().chain(&self.chip_description_path) {
+            if is_cmsis_pack(cdp) {
+                let families =
+                    families_from_pack(cdp).map_err(|error| OperationError::FailedPackParsing {
+                        source: error,
+                        path: cdp.clone(),
+                    })?;
+
+                for family in families {
+                    log::debug!("Adding chip family {} from {}", family.name, cdp.display());
+                    probe_rs::config::add_target_family(family).map_err(|error| {
+                        OperationError::FailedChipDescriptionParsing {
+                            source: error,
+                            path: cdp.clone(),
+                        }
+                    })?;
                 }
-            })
-        } else {
-            Ok(())
+                continue;
+            }
+
+            let result = if cdp.is_dir() {
+                probe_rs::config::add_targets_from_dir(cdp)
+            } else {
+                probe_rs::config::add_target_from_yaml(cdp)
+            };
+
+            result.map_err(|error| OperationError::FailedChipDescriptionParsing {
+                source: error,
+                path: cdp.clone(),
+            })?;
         }
+
+        Ok(())
     }
 
     /// Resolves a resultant target selector from passed [ProbeOptions].
     pub fn get_target_selector(&self) -> Result<TargetSelector, OperationError> {
         let target = if let Some(chip_name) = &self.chip {
-            let target = probe_rs::config::get_target_by_name(chip_name).map_err(|error| {
+            let mut target = probe_rs::config::get_target_by_name(chip_name).map_err(|error| {
                 OperationError::ChipNotFound {
                     source: error,
                     name: chip_name.clone(),
+                    suggestions: chip_suggestions(chip_name),
                 }
             })?;
 
+            if let Some(path) = &self.sequence_script {
+                let script = load_sequence_script(path).map_err(|error| {
+                    OperationError::FailedSequenceScriptLoading {
+                        source: error,
+                        path: path.clone(),
+                    }
+                })?;
+                target.add_sequence_script(&script).map_err(|error| {
+                    OperationError::FailedSequenceScriptLoading {
+                        source: Box::new(error),
+                        path: path.clone(),
+                    }
+                })?;
+            }
+
             TargetSelector::Specified(target)
+        } else if self.sequence_script.is_some() {
+            return Err(OperationError::SequenceScriptWithoutChip);
         } else {
             TargetSelector::Auto
         };
@@ -207,6 +333,33 @@ impl ProbeOptions {
         Ok(target)
     }
 
+    /// Opens the virtual COM port of the selected probe.
+    ///
+    /// The probe is selected the same way as in [`ProbeOptions::attach_probe`].
+    #[cfg(feature = "serial")]
+    pub fn open_vcom(&self, baud_rate: u32) -> Result<VirtualComPort, OperationError> {
+        let list = Probe::list_all();
+
+        let info = match &self.probe_selector {
+            Some(selector) => selector.resolve(&list).map_err(|error| match error {
+                ProbeSelectionError::NoMatch(_) => OperationError::NoProbesFound,
+                error => OperationError::AmbiguousProbeSelector(error),
+            })?,
+            None => {
+                if list.len() > 1 {
+                    return Err(OperationError::MultipleProbesFound { number: list.len() });
+                }
+
+                list.into_iter()
+                    .next()
+                    .ok_or(OperationError::NoProbesFound)?
+            }
+        };
+
+        info.open_vcom(baud_rate)
+            .map_err(OperationError::FailedToOpenVcom)
+    }
+
     /// Attaches to specified probe and configures it.
     pub fn attach_probe(&self) -> Result<Probe, OperationError> {
         let mut probe = {
@@ -217,9 +370,17 @@ impl ProbeOptions {
             // If we got a probe selector as an argument, open the probe
             // matching the selector if possible.
             match &self.probe_selector {
-                Some(selector) => {
-                    Probe::open(selector.clone()).map_err(OperationError::FailedToOpenProbe)
-                }
+                Some(selector) => match selector.resolve(&Probe::list_all()) {
+                    Ok(info) => Probe::open(info).map_err(OperationError::FailedToOpenProbe),
+                    Err(error @ ProbeSelectionError::Ambiguous { .. }) => {
+                        Err(OperationError::AmbiguousProbeSelector(error))
+                    }
+                    // The probe might not be listed, e.g. due to missing permissions,
+                    // so try to open it directly anyway.
+                    Err(ProbeSelectionError::NoMatch(_)) => {
+                        Probe::open(selector.clone()).map_err(OperationError::FailedToOpenProbe)
+                    }
+                },
                 None => {
                     // Only automatically select a probe if there is
                     // only a single probe detected.
@@ -271,15 +432,26 @@ impl ProbeOptions {
             permissions = permissions.allow_erase_all();
         }
 
-        let session = if self.connect_under_reset {
-            probe.attach_under_reset(target, permissions)
-        } else {
-            probe.attach(target, permissions)
+        let mut options = AttachOptions::new();
+        if self.connect_under_reset {
+            options = options.under_reset();
         }
-        .map_err(|error| OperationError::AttachingFailed {
-            source: error,
-            connect_under_reset: self.connect_under_reset,
-        })?;
+        if self.keep_watchdogs_running {
+            options = options.keep_watchdogs_running();
+        }
+        if self.preserve_state {
+            options = options.preserve_state();
+        }
+        if self.power_down_on_detach {
+            options = options.power_down_on_detach();
+        }
+
+        let session = probe
+            .attach_with_options(target, permissions, options)
+            .map_err(|error| OperationError::AttachingFailed {
+                source: error,
+                connect_under_reset: self.connect_under_reset,
+            })?;
 
         Ok(session)
     }
@@ -453,6 +625,11 @@ pub enum OperationError {
     FailedToOpenProbe(#[source] DebugProbeError),
     #[error("{number} probes were found.")]
     MultipleProbesFound { number: usize },
+    #[error("The probe selector is ambiguous.")]
+    AmbiguousProbeSelector(#[source] ProbeSelectionError),
+    #[cfg(feature = "serial")]
+    #[error("Failed to open the virtual COM port of the debug probe.")]
+    FailedToOpenVcom(#[source] VcomError),
     #[error("The flashing procedure failed for '{path}'.")]
     FlashingFailed {
         #[source]
@@ -467,6 +644,20 @@ pub enum OperationError {
         source: RegistryError,
         path: PathBuf,
     },
+    #[error("Failed to generate the chip descriptions of the CMSIS-Pack '{path}'.")]
+    FailedPackParsing {
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+        path: PathBuf,
+    },
+    #[error("Failed to load the debug sequences of '{path}'.")]
+    FailedSequenceScriptLoading {
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+        path: PathBuf,
+    },
+    #[error("Debug sequences can only be given together with a chip.")]
+    SequenceScriptWithoutChip,
     #[error("Failed to change the working directory to '{path}'.")]
     FailedToChangeWorkingDirectory {
         #[source]
@@ -481,11 +672,12 @@ pub enum OperationError {
     },
     #[error("Failed to build the cargo project.")]
     FailedToBuildCargoProject(#[source] ArtifactError),
-    #[error("The chip '{name}' was not found in the database.")]
+    #[error("The chip '{name}' was not found in the database.{}", format_suggestions(.suggestions))]
     ChipNotFound {
         #[source]
         source: RegistryError,
         name: String,
+        suggestions: Vec<String>,
     },
     #[error("The protocol '{protocol}' could not be selected.")]
     FailedToSelectProtocol {
@@ -545,10 +737,10 @@ pub fn list_connected_probes(mut f: impl Write) -> Result<(), std::io::Error> {
 /// commandline.
 pub fn print_families(mut f: impl Write) -> Result<(), OperationError> {
     writeln!(f, "Available chips:")?;
-    for family in probe_rs::config::families().map_err(OperationError::FailedToReadFamilies)? {
+    for family in probe_rs::config::list_families() {
         writeln!(f, "{}", &family.name)?;
         writeln!(f, "    Variants:")?;
-        for variant in family.variants() {
+        for variant in &family.variants {
             writeln!(f, "        {}", variant.name)?;
         }
     }
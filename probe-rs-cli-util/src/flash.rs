@@ -8,7 +8,10 @@ use std::{path::Path, sync::Arc, time::Instant};
 
 use colored::Colorize;
 use probe_rs::{
-    flashing::{DownloadOptions, FlashLoader, FlashProgress, ProgressEvent},
+    flashing::{
+        DownloadOptions, FlashError, FlashLoader, FlashProgress, PhaseStatistics, ProgressEvent,
+        SectorCache,
+    },
     Session,
 };
 
@@ -29,6 +32,15 @@ pub fn run_flash_download(
     download_option.dry_run = opt.probe_options.dry_run;
     download_option.do_chip_erase = do_chip_erase;
     download_option.disable_double_buffering = opt.disable_double_buffering;
+    download_option.skip_unchanged_sectors = opt.skip_unchanged;
+    download_option.sector_cache = match (&opt.sector_cache, sector_cache_key(session)) {
+        (Some(path), Some(key)) => Some(SectorCache::load(path, key)),
+        (Some(_), None) => {
+            log::warn!("The sector cache is not used, because the serial number of the probe is not known.");
+            None
+        }
+        (None, _) => None,
+    };
 
     if !opt.disable_progressbars {
         // Create progress bars.
@@ -134,6 +146,21 @@ pub fn run_flash_download(
                         fp.finish()
                     };
                 }
+                SectorsSkipped { .. } | StartedVerifying | FailedVerifying | FinishedVerifying => {}
+                Finished { summary } => {
+                    log_phase("Filled", "fills", &summary.filling);
+                    log_phase("Erased", "sectors", &summary.erasing);
+                    log_phase("Programmed", "pages", &summary.programming);
+                    log_phase("Verified", "blocks", &summary.verifying);
+                    if summary.skipped_sectors > 0 {
+                        log::info!(
+                            "Skipped {} unchanged sectors ({} bytes)",
+                            summary.skipped_sectors,
+                            summary.skipped_bytes
+                        );
+                    }
+                }
+                _ => {}
             }
         });
 
@@ -146,26 +173,16 @@ pub fn run_flash_download(
 
         download_option.progress = Some(&progress);
 
-        loader.commit(session, download_option).map_err(|error| {
-            OperationError::FlashingFailed {
-                source: error,
-                target: session.target().clone(),
-                target_spec: opt.probe_options.chip.clone(),
-                path: path.to_path_buf(),
-            }
-        })?;
+        loader
+            .commit(session, download_option)
+            .map_err(|error| flashing_failed(error, session, opt, path))?;
 
         // We don't care if we cannot join this thread.
         let _ = progress_thread_handle.join();
     } else {
-        loader.commit(session, download_option).map_err(|error| {
-            OperationError::FlashingFailed {
-                source: error,
-                target: session.target().clone(),
-                target_spec: opt.probe_options.chip.clone(),
-                path: path.to_path_buf(),
-            }
-        })?;
+        loader
+            .commit(session, download_option)
+            .map_err(|error| flashing_failed(error, session, opt, path))?;
     }
 
     // Stop timer.
@@ -178,3 +195,55 @@ pub fn run_flash_download(
 
     Ok(())
 }
+
+/// Wraps an error of the download into an [OperationError].
+///
+/// If the target has no flash algorithm, a hint on loading one is printed.
+fn flashing_failed(
+    error: FlashError,
+    session: &Session,
+    opt: &FlashOptions,
+    path: &Path,
+) -> OperationError {
+    if let FlashError::NoFlashLoaderAlgorithmAttached { .. } = error {
+        logging::eprintln(format!(
+            "        {} A target description with flash algorithms, e.g. generated from the CMSIS-Pack of the chip with target-gen, can be loaded with `--chip-description-path`.",
+            "Hint".blue().bold()
+        ));
+    }
+
+    OperationError::FlashingFailed {
+        source: error,
+        target: session.target().clone(),
+        target_spec: opt.probe_options.chip.clone(),
+        path: path.to_path_buf(),
+    }
+}
+
+/// The key which identifies the target in the sector cache.
+///
+/// The target is identified by the chip and the serial number of the probe it is connected to.
+/// Without the serial number, different probes would share the cached sectors, so `None` is
+/// returned if it is not known.
+pub fn sector_cache_key(session: &Session) -> Option<String> {
+    let serial_number = session.probe_serial_number()?;
+
+    Some(format!("{}:{}", session.target().name, serial_number))
+}
+
+/// Log the statistics of a finished flashing phase, if anything was done in it.
+fn log_phase(action: &str, unit: &str, statistics: &PhaseStatistics) {
+    if statistics.units == 0 {
+        return;
+    }
+
+    log::info!(
+        "{} {} {} ({} bytes) in {:.2}s ({:.1} KiB/s)",
+        action,
+        statistics.units,
+        unit,
+        statistics.bytes,
+        statistics.duration.as_secs_f64(),
+        statistics.kibibytes_per_second().unwrap_or_default()
+    );
+}
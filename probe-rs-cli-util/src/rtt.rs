@@ -4,7 +4,7 @@ use chrono::Local;
 use num_traits::Zero;
 use probe_rs::config::MemoryRegion;
 use probe_rs::Core;
-use probe_rs_rtt::{DownChannel, Rtt, ScanRegion, UpChannel};
+use probe_rs_rtt::{ChannelMode, DefmtDecoder, DownChannel, Rtt, ScanRegion, UpChannel};
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::fs::File;
@@ -34,8 +34,24 @@ pub fn attach_to_rtt(
     };
 
     match Rtt::attach_region(core, memory_map, &rtt_header_address) {
-        Ok(rtt) => {
+        Ok(mut rtt) => {
             log::info!("RTT initialized.");
+            for channel_config in &rtt_config.channels {
+                if let (Some(number), Some(mode)) =
+                    (channel_config.channel_number, channel_config.mode)
+                {
+                    match rtt.up_channels().get(number) {
+                        Some(up_channel) => {
+                            up_channel.set_mode(core, mode)?;
+                            log::info!("Set the mode of RTT up channel {} to {:?}", number, mode);
+                        }
+                        None => log::warn!(
+                            "RTT up channel {} does not exist, its mode is not set.",
+                            number
+                        ),
+                    }
+                }
+            }
             let app = RttActiveTarget::new(rtt, elf_file, rtt_config)?;
             Ok(app)
         }
@@ -108,6 +124,18 @@ pub struct RttChannelConfig {
     #[serde(default = "default_include_location")]
     // Control the inclusion of source location information for DataFormat::Defmt.
     pub show_location: bool,
+    #[structopt(skip)]
+    #[serde(default)]
+    // Change the mode of the up channel on the target, e.g. to block the target instead of losing data when the buffer is full.
+    pub mode: Option<ChannelMode>,
+    #[structopt(skip)]
+    #[serde(default)]
+    // Only show messages of at least this level for DataFormat::Defmt.
+    pub log_level: Option<log::LevelFilter>,
+    #[structopt(skip)]
+    #[serde(default)]
+    // Show the data in the debug console of a DAP client, instead of a separate RTT window.
+    pub debug_console: bool,
 }
 
 /// This is the primary interface through which RTT channel data is read and written. Every actual RTT channel has a configuration and buffer that is used for this purpose.
@@ -122,6 +150,10 @@ pub struct RttActiveChannel {
     rtt_buffer: RttBuffer,
     show_timestamps: bool,
     show_location: bool,
+    log_level: Option<log::LevelFilter>,
+    defmt_decoder: Option<DefmtDecoder>,
+    /// The number of reads which found the buffer full, which were already reported.
+    buffer_full_reported: u64,
 }
 
 /// A fully configured RttActiveChannel. The configuration will always try to 'default' based on information read from the RTT control block in the binary. Where insufficient information is available, it will use the supplied configuration, with final hardcoded defaults where no other information was available.
@@ -180,6 +212,9 @@ impl RttActiveChannel {
             rtt_buffer: RttBuffer::new(buffer_size),
             show_timestamps: full_config.show_timestamps,
             show_location,
+            log_level: full_config.log_level,
+            defmt_decoder: None,
+            buffer_full_reported: 0,
         }
     }
 
@@ -217,77 +252,107 @@ impl RttActiveChannel {
     }
 
     /// Retrieves available data from the channel and if available, returns `Some(channel_number:String, formatted_data:String)`.
-    pub fn get_rtt_data(
-        &mut self,
-        core: &mut Core,
-        defmt_state: Option<&(defmt_decoder::Table, Option<defmt_decoder::Locations>)>,
-    ) -> Option<(String, String)> {
-        self
-            .poll_rtt(core)
-            .map(|bytes_read| {
-                (
-                    self.number().unwrap_or(0).to_string(), // If the Channel doesn't have a number, then send the output to channel 0
-                    {
-                        let mut formatted_data = String::new();
-                        match self.data_format {
-                            DataFormat::String => {
-                                let incoming = String::from_utf8_lossy(&self.rtt_buffer.0[..bytes_read]).to_string();
-                                for (_i, line) in incoming.split_terminator('\n').enumerate() {
-                                    if self.show_timestamps {
-                                        write!(formatted_data, "{} :", Local::now())
-                                            .map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
-                                    }
-                                    writeln!(formatted_data, "{}", line).map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
-                                }
-                            }
-                            DataFormat::BinaryLE => {
-                                for element in &self.rtt_buffer.0[..bytes_read] {
-                                    // Width of 4 allows 0xFF to be printed.
-                                    write!(formatted_data, "{:#04x}", element).map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
-                                }
-                            }
-                            DataFormat::Defmt => {
-                                match defmt_state {
-                                    Some((table, locs)) => {
+    pub fn get_rtt_data(&mut self, core: &mut Core) -> Option<(String, String)> {
+        self.poll_rtt(core)
+            .map(|bytes_read| self.format_rtt_data(bytes_read))
+    }
 
-                                        let mut stream_decoder = table.new_stream_decoder();
-                                        stream_decoder.received(&self.rtt_buffer.0[..bytes_read]);
-                                        while let Ok(frame) = stream_decoder.decode()
+    /// Formats the first `bytes_read` bytes in the channel buffer, and returns `(channel_number:String, formatted_data:String)`.
+    fn format_rtt_data(&mut self, bytes_read: usize) -> (String, String) {
+        (
+            self.number().unwrap_or(0).to_string(), // If the Channel doesn't have a number, then send the output to channel 0
+            {
+                let mut formatted_data = String::new();
+                match self.data_format {
+                    DataFormat::String => {
+                        let incoming =
+                            String::from_utf8_lossy(&self.rtt_buffer.0[..bytes_read]).to_string();
+                        for (_i, line) in incoming.split_terminator('\n').enumerate() {
+                            if self.show_timestamps {
+                                write!(formatted_data, "{} :", Local::now()).map_or_else(
+                                    |err| log::error!("Failed to format RTT data - {:?}", err),
+                                    |r| r,
+                                );
+                            }
+                            writeln!(formatted_data, "{}", line).map_or_else(
+                                |err| log::error!("Failed to format RTT data - {:?}", err),
+                                |r| r,
+                            );
+                        }
+                    }
+                    DataFormat::BinaryLE => {
+                        for element in &self.rtt_buffer.0[..bytes_read] {
+                            // Width of 4 allows 0xFF to be printed.
+                            write!(formatted_data, "{:#04x}", element).map_or_else(
+                                |err| log::error!("Failed to format RTT data - {:?}", err),
+                                |r| r,
+                            );
+                        }
+                    }
+                    DataFormat::Defmt => {
+                        match self.defmt_decoder.as_mut() {
+                            Some(decoder) => {
+                                for record in decoder.push(&self.rtt_buffer.0[..bytes_read]) {
+                                    writeln!(formatted_data, "{}", record).map_or_else(
+                                        |err| log::error!("Failed to format RTT data - {:?}", err),
+                                        |r| r,
+                                    );
+                                    if self.show_location {
+                                        if let (Some(file), Some(line)) =
+                                            (&record.file, record.line)
                                         {
-                                            // NOTE(`[]` indexing) all indices in `table` have already been
-                                            // verified to exist in the `locs` map.
-                                            let loc = locs.as_ref().map(|locs| &locs[&frame.index()]);
-                                            writeln!(formatted_data, "{}", frame.display(false)).map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
-                                            if self.show_location {
-                                                if let Some(loc) = loc {
-                                                    let relpath = if let Ok(relpath) =
-                                                        loc.file.strip_prefix(&std::env::current_dir().unwrap())
-                                                    {
-                                                        relpath
-                                                    } else {
-                                                        // not relative; use full path
-                                                        &loc.file
-                                                    };
-                                                    writeln!(formatted_data,
-                                                        "└─ {}:{}",
-                                                        relpath.display(),
-                                                        loc.line
-                                                    ).map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
-                                                }
-                                            }
+                                            let relpath = if let Ok(relpath) =
+                                                file.strip_prefix(&std::env::current_dir().unwrap())
+                                            {
+                                                relpath
+                                            } else {
+                                                // not relative; use full path
+                                                file
+                                            };
+                                            writeln!(
+                                                formatted_data,
+                                                "└─ {}:{}",
+                                                relpath.display(),
+                                                line
+                                            )
+                                            .map_or_else(
+                                                |err| {
+                                                    log::error!(
+                                                        "Failed to format RTT data - {:?}",
+                                                        err
+                                                    )
+                                                },
+                                                |r| r,
+                                            );
                                         }
                                     }
-                                    None => {
-                                        write!(formatted_data, "Running rtt in defmt mode but table or locations could not be loaded.")
-                                            .map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
-                                    }
                                 }
                             }
-                        };
-                        formatted_data
+                            None => {
+                                write!(formatted_data, "Running rtt in defmt mode but table or locations could not be loaded.")
+                                            .map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
+                            }
+                        }
                     }
-                )
-            })
+                };
+                formatted_data
+            },
+        )
+    }
+
+    /// Warns if the buffer of the up channel was found full since the last warning, because the target may have dropped data then.
+    fn report_buffer_full(&mut self) {
+        if let Some(up_channel) = &self.up_channel {
+            let buffer_full = up_channel.statistics().buffer_full;
+            if buffer_full > self.buffer_full_reported {
+                log::warn!(
+                    "The buffer of RTT channel {} ({}) was full, data may have been lost.",
+                    up_channel.number(),
+                    self.channel_name
+                );
+                self.buffer_full_reported = buffer_full;
+            }
+        }
     }
 
     pub fn _push_rtt(&mut self, core: &mut Core) {
@@ -305,7 +370,6 @@ impl RttActiveChannel {
 #[derive(Debug)]
 pub struct RttActiveTarget {
     pub active_channels: Vec<RttActiveChannel>,
-    pub defmt_state: Option<(defmt_decoder::Table, Option<defmt_decoder::Locations>)>,
 }
 
 impl RttActiveTarget {
@@ -349,42 +413,34 @@ impl RttActiveTarget {
         let defmt_enabled = active_channels
             .iter()
             .any(|elem| elem.data_format == DataFormat::Defmt);
-        let defmt_state = if defmt_enabled {
+        if defmt_enabled {
             let elf = fs::read(elf_file).map_err(|err| {
                 anyhow!(
                     "Error reading program binary while initalizing RTT: {}",
                     err
                 )
             })?;
-            if let Some(table) = defmt_decoder::Table::parse(&elf)? {
-                let locs = {
-                    let locs = table.get_locations(&elf)?;
-
-                    if !table.is_empty() && locs.is_empty() {
-                        log::warn!("Insufficient DWARF info; compile your program with `debug = 2` to enable location info.");
-                        None
-                    } else if table.indices().all(|idx| locs.contains_key(&(idx as u64))) {
-                        Some(locs)
-                    } else {
-                        log::warn!(
-                            "Location info is incomplete; it will be omitted from the output."
-                        );
-                        None
+            for active_channel in active_channels
+                .iter_mut()
+                .filter(|elem| elem.data_format == DataFormat::Defmt)
+            {
+                // Each channel needs its own decoder, because messages may be split across reads.
+                match DefmtDecoder::from_elf(&elf)? {
+                    Some(mut decoder) => {
+                        if let Some(level) = active_channel.log_level {
+                            decoder.set_level(level);
+                        }
+                        active_channel.defmt_decoder = Some(decoder);
                     }
-                };
-                Some((table, locs))
-            } else {
-                log::warn!("No `Table` definition in DWARF info; compile your program with `debug = 2` to enable location info.");
-                None
+                    None => {
+                        log::warn!("No `Table` definition in DWARF info; compile your program with `debug = 2` to enable location info.");
+                        break;
+                    }
+                }
             }
-        } else {
-            None
-        };
+        }
 
-        Ok(Self {
-            active_channels,
-            defmt_state,
-        })
+        Ok(Self { active_channels })
     }
 
     pub fn get_rtt_symbol<T: Read + Seek>(file: &mut T) -> Option<u64> {
@@ -406,11 +462,43 @@ impl RttActiveTarget {
     }
 
     /// Polls the RTT target on all channels and returns available data.
+    ///
+    /// The pointers of all up channels are read at once, and only the buffers of channels which
+    /// have data are read, to keep the traffic to the probe low when polling often.
     pub fn poll_rtt(&mut self, core: &mut Core) -> HashMap<String, String> {
-        let defmt_state = self.defmt_state.as_ref();
+        let up_channels = self
+            .active_channels
+            .iter()
+            .filter_map(|active_channel| active_channel.up_channel.as_ref());
+
+        let mut data = match probe_rs_rtt::read_up_channels(core, up_channels) {
+            Ok(data) => data,
+            // The probe may be temporarily unavailable, e.g. because the user pressed the `reset` button, so the channels are polled again next time.
+            Err(probe_rs_rtt::Error::Probe(err)) => {
+                log::debug!("Error reading from RTT: {}", err);
+                return HashMap::new();
+            }
+            // The channels can be read again once the core is halted.
+            Err(err @ probe_rs_rtt::Error::CoreRunning(_)) => {
+                log::debug!("{}", err);
+                return HashMap::new();
+            }
+            Err(err) => {
+                log::error!("\nError reading from RTT: {}", err);
+                return HashMap::new();
+            }
+        };
+
         self.active_channels
             .iter_mut()
-            .filter_map(|active_channel| active_channel.get_rtt_data(core, defmt_state))
+            .filter_map(|active_channel| {
+                active_channel.report_buffer_full();
+
+                let bytes = data.remove(&active_channel.number()?)?;
+                active_channel.rtt_buffer.0[..bytes.len()].copy_from_slice(&bytes);
+
+                Some(active_channel.format_rtt_data(bytes.len()))
+            })
             .collect::<HashMap<_, _>>()
     }
 
@@ -1,6 +1,7 @@
 use super::memory::MemoryRegion;
 use crate::CoreType;
 use serde::{Deserialize, Serialize};
+use std::ops::Range;
 
 /// A single chip variant.
 ///
@@ -30,6 +31,22 @@ pub struct Chip {
     ///
     /// [`ChipFamily::flash_algorithms`]: crate::ChipFamily::flash_algorithms
     pub flash_algorithms: Vec<String>,
+    /// The timing used when attaching to the chip while it is held in reset.
+    ///
+    /// If this is not set, [`ConnectUnderReset::default`] is used.
+    #[serde(default)]
+    #[cfg_attr(
+        not(feature = "bincode"),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub connect_under_reset: Option<ConnectUnderReset>,
+    /// The address ranges in which the RTT control block is searched, instead of all RAM.
+    ///
+    /// This speeds up attaching to RTT on chips with a lot of RAM, when the control block is
+    /// known to be in a part of it.
+    #[serde(default)]
+    #[cfg_attr(not(feature = "bincode"), serde(skip_serializing_if = "Vec::is_empty"))]
+    pub rtt_scan_ranges: Vec<Range<u64>>,
 }
 
 impl Chip {
@@ -47,6 +64,37 @@ impl Chip {
             }],
             memory_map: vec![],
             flash_algorithms: vec![],
+            connect_under_reset: None,
+            rtt_scan_ranges: vec![],
+        }
+    }
+}
+
+/// The steps taken when attaching to a chip while it is held in reset.
+///
+/// An attach under reset asserts nRESET, waits for `assert_delay_ms`, connects to the
+/// debug port (sending the SWJ sequence and reading the IDCODE), optionally arms a
+/// reset catch to halt the core, and then releases nRESET again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConnectUnderReset {
+    /// The time in milliseconds nRESET is held before connecting to the debug port.
+    pub assert_delay_ms: u64,
+    /// The time in milliseconds to wait after releasing nRESET, before the chip is accessed again.
+    pub release_delay_ms: u64,
+    /// Halt the core on the first instruction after nRESET is released.
+    pub halt: bool,
+    /// The time in milliseconds to wait for the core to halt after nRESET is released.
+    pub halt_timeout_ms: u64,
+}
+
+impl Default for ConnectUnderReset {
+    fn default() -> Self {
+        Self {
+            assert_delay_ms: 0,
+            release_delay_ms: 0,
+            halt: true,
+            halt_timeout_ms: 100,
         }
     }
 }
@@ -73,6 +121,8 @@ pub enum CoreAccessOptions {
     Arm(ArmCoreAccessOptions),
     /// Riscv specific options
     Riscv(RiscvCoreAccessOptions),
+    /// Mips specific options
+    Mips(MipsCoreAccessOptions),
 }
 
 /// The data required to access an ARM core
@@ -91,5 +141,13 @@ pub struct ArmCoreAccessOptions {
 }
 
 /// The data required to access a Risc-V core
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RiscvCoreAccessOptions {}
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct RiscvCoreAccessOptions {
+    /// The index of the hart in the debug module, which is selected with `hartsel`.
+    #[serde(default)]
+    pub hart_id: u32,
+}
+
+/// The data required to access a MIPS core
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct MipsCoreAccessOptions {}
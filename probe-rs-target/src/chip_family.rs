@@ -1,6 +1,7 @@
 use crate::CoreAccessOptions;
 
 use super::chip::Chip;
+use super::debug_sequence::DebugSequenceDescription;
 use super::flash_algorithm::RawFlashAlgorithm;
 use jep106::JEP106Code;
 
@@ -32,6 +33,8 @@ pub enum CoreType {
     Armv6m,
     /// ARMv7-A: Cortex A7, A9, A15
     Armv7a,
+    /// ARMv7-R: Cortex R4, R5
+    Armv7r,
     /// ARMv7-M: Cortex M3
     Armv7m,
     /// ARMv7e-M: Cortex M4, M7
@@ -42,6 +45,8 @@ pub enum CoreType {
     Armv8m,
     /// RISC-V
     Riscv,
+    /// MIPS32 with EJTAG, e.g. M4K, 24K
+    Mips32,
 }
 
 impl CoreType {
@@ -61,6 +66,8 @@ pub enum Architecture {
     Arm,
     /// A RISC-V core.
     Riscv,
+    /// A MIPS core.
+    Mips,
 }
 
 impl CoreType {
@@ -68,6 +75,7 @@ impl CoreType {
     pub fn architecture(&self) -> Architecture {
         match self {
             CoreType::Riscv => Architecture::Riscv,
+            CoreType::Mips32 => Architecture::Mips,
             _ => Architecture::Arm,
         }
     }
@@ -84,6 +92,10 @@ pub enum InstructionSet {
     A64,
     /// RISC-V 32-bit instruction set
     RV32,
+    /// RISC-V 32-bit instruction set with the compressed (C) extension
+    RV32C,
+    /// MIPS32 instruction set
+    Mips32,
 }
 
 /// This describes a chip family with all its variants.
@@ -105,6 +117,18 @@ pub struct ChipFamily {
     pub variants: Vec<Chip>,
     /// This vector holds all available algorithms.
     pub flash_algorithms: Vec<RawFlashAlgorithm>,
+    /// The declarations of the global variables of the debug sequences, like the `<debugvars>` element
+    /// of a CMSIS-Pack.
+    #[serde(default)]
+    #[cfg_attr(
+        not(feature = "bincode"),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub debug_vars: Option<String>,
+    /// Debug sequences which replace the built-in sequences of probe-rs for this family.
+    #[serde(default)]
+    #[cfg_attr(not(feature = "bincode"), serde(skip_serializing_if = "Vec::is_empty"))]
+    pub debug_sequences: Vec<DebugSequenceDescription>,
 
     #[serde(skip, default = "default_source")]
     /// Source of the target description, used for diagnostics
@@ -167,6 +191,7 @@ impl ChipFamily {
                             core.core_type,
                             CoreType::Armv6m
                                 | CoreType::Armv7a
+                                | CoreType::Armv7r
                                 | CoreType::Armv7em
                                 | CoreType::Armv7m
                                 | CoreType::Armv8a
@@ -178,8 +203,10 @@ impl ChipFamily {
                             ));
                         }
 
-                        if matches!(core.core_type, CoreType::Armv7a | CoreType::Armv8a)
-                            && options.debug_base.is_none()
+                        if matches!(
+                            core.core_type,
+                            CoreType::Armv7a | CoreType::Armv7r | CoreType::Armv8a
+                        ) && options.debug_base.is_none()
                         {
                             return Err(format!("Core {} requires setting debug_base", core.name));
                         }
@@ -196,6 +223,14 @@ impl ChipFamily {
                             ));
                         }
                     }
+                    CoreAccessOptions::Mips(_) => {
+                        if core.core_type != CoreType::Mips32 {
+                            return Err(format!(
+                                "Mips options don't match core type {:?} on core {}",
+                                core.core_type, core.name
+                            ));
+                        }
+                    }
                 }
             }
         }
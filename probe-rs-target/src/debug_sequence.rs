@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+
+/// A debug sequence from the debug description of a CMSIS-Pack.
+///
+/// Debug sequences customize the steps which are executed to connect to, reset, and debug a chip,
+/// e.g. `DebugPortSetup` or `ResetSystem`. Their code is written in the expression language
+/// of the [CMSIS debug description].
+///
+/// [CMSIS debug description]: https://open-cmsis-pack.github.io/Open-CMSIS-Pack-Spec/main/html/debug_description.html
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DebugSequenceDescription {
+    /// The name of the sequence, e.g. `ResetSystem`.
+    pub name: String,
+    /// The blocks and control elements of the sequence, which are executed in order.
+    pub body: Vec<SequenceElement>,
+}
+
+/// Debug sequences supplied by the user, e.g. to run board specific code when connecting to a target.
+///
+/// The sequences replace the sequences of the target description and the built-in sequences of probe-rs.
+/// A script uses the same keys as the debug sequences of a target description.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DebugSequenceScript {
+    /// The declarations of the global variables of the sequences.
+    #[serde(default)]
+    pub debug_vars: Option<String>,
+    /// The sequences.
+    #[serde(default)]
+    pub debug_sequences: Vec<DebugSequenceDescription>,
+}
+
+/// An element of a [`DebugSequenceDescription`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SequenceElement {
+    /// A block of statements, which are executed in order.
+    Block(SequenceBlock),
+    /// A control element, which executes its body conditionally or in a loop.
+    Control(SequenceControl),
+}
+
+/// A block of statements, corresponding to the `<block>` element of a debug sequence.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SequenceBlock {
+    /// If set, the statements should be executed without interruption by other probe accesses.
+    #[serde(default)]
+    pub atomic: bool,
+    /// The statements of the block.
+    pub code: String,
+}
+
+/// A control element, corresponding to the `<control>` element of a debug sequence.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SequenceControl {
+    /// The body is only executed if this expression evaluates to a non-zero value.
+    #[serde(rename = "if", default)]
+    pub condition: Option<String>,
+    /// The body is executed repeatedly as long as this expression evaluates to a non-zero value.
+    #[serde(rename = "while", default)]
+    pub repeat_while: Option<String>,
+    /// The maximum duration of the `while` loop in microseconds.
+    /// The loop is ended when it expires, without an error.
+    #[serde(default)]
+    pub timeout: Option<u64>,
+    /// The nested elements.
+    #[serde(default)]
+    pub body: Vec<SequenceElement>,
+}
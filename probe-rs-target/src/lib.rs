@@ -13,16 +13,24 @@
 
 mod chip;
 mod chip_family;
+mod debug_sequence;
 mod flash_algorithm;
 mod flash_properties;
 mod memory;
 
-pub use chip::{ArmCoreAccessOptions, Chip, Core, CoreAccessOptions, RiscvCoreAccessOptions};
+pub use chip::{
+    ArmCoreAccessOptions, Chip, ConnectUnderReset, Core, CoreAccessOptions, MipsCoreAccessOptions,
+    RiscvCoreAccessOptions,
+};
 pub use chip_family::{
     Architecture, ChipFamily, CoreType, InstructionSet, TargetDescriptionSource,
 };
+pub use debug_sequence::{
+    DebugSequenceDescription, DebugSequenceScript, SequenceBlock, SequenceControl, SequenceElement,
+};
 pub use flash_algorithm::RawFlashAlgorithm;
 pub use flash_properties::FlashProperties;
 pub use memory::{
-    MemoryRange, MemoryRegion, NvmRegion, PageInfo, RamRegion, SectorDescription, SectorInfo,
+    GenericRegion, MemoryRange, MemoryRegion, NvmRegion, PageInfo, RamRegion, SectorDescription,
+    SectorInfo,
 };
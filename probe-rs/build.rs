@@ -10,17 +10,53 @@ fn main() {
     // cargo reruns build.rs if any file under the crate root has changed)
     // This improves build times and IDE responsivity when not editing targets.
     println!("cargo:rerun-if-changed=targets");
+    println!("cargo:rerun-if-changed=Cargo.toml");
 
-    // Test if we have to generate built-in targets
-    if env::var("CARGO_FEATURE_BUILTIN_TARGETS").is_err() {
-        return;
-    }
+    // All targets are included with the `builtin-targets` feature, otherwise only the
+    // families whose feature is enabled.
+    let all_targets = env::var_os("CARGO_FEATURE_BUILTIN_TARGETS").is_some();
+    let enabled_features: Vec<String> = env::vars_os()
+        .filter_map(|(name, _)| {
+            name.to_str()?
+                .strip_prefix("CARGO_FEATURE_")
+                .map(ToOwned::to_owned)
+        })
+        .collect();
+
+    let manifest_path = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("Cargo.toml");
+    let manifest = read_to_string(&manifest_path).expect("Failed to read Cargo.toml");
+    let declared_features = declared_features(&manifest);
 
     let mut families: Vec<ChipFamily> = Vec::new();
 
     let mut files = vec![];
     visit_dirs(Path::new("targets"), &mut files).unwrap();
+    files.sort();
+
+    // Every target file needs a feature, otherwise it could only be used with `builtin-targets`.
+    let missing_features: Vec<String> = files
+        .iter()
+        .map(|file| target_feature(file))
+        .filter(|feature| !declared_features.contains(feature))
+        .collect();
+    if !missing_features.is_empty() {
+        panic!(
+            "The target files in {:?} have no feature, please add these features to {:?}:\n{}",
+            Path::new("targets"),
+            manifest_path,
+            missing_features
+                .iter()
+                .map(|feature| format!("{} = []", feature))
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+    }
+
     for file in files {
+        if !all_targets && !enabled_features.contains(&feature_env_name(&target_feature(&file))) {
+            continue;
+        }
+
         let string = read_to_string(&file).expect(
             "Algorithm definition file could not be read. This is a bug. Please report it.",
         );
@@ -43,6 +79,38 @@ fn main() {
     let _: Vec<ChipFamily> = bincode::deserialize(&families_bin).unwrap();
 }
 
+/// The name of the feature which includes the target file, e.g. `target-stm32f4` for
+/// `STM32F4_Series.yaml`.
+fn target_feature(file: &Path) -> String {
+    let stem = file
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .expect("Invalid name of a target file")
+        .to_ascii_lowercase();
+    let family = stem.strip_suffix("_series").unwrap_or(&stem);
+
+    format!("target-{}", family.replace('_', "-"))
+}
+
+/// The name of a feature as it appears in the `CARGO_FEATURE_*` environment variables, e.g.
+/// `TARGET_STM32F4` for `target-stm32f4`.
+fn feature_env_name(feature: &str) -> String {
+    feature.to_ascii_uppercase().replace('-', "_")
+}
+
+/// The names of the features in the `[features]` table of the manifest.
+fn declared_features(manifest: &str) -> Vec<String> {
+    manifest
+        .lines()
+        .map(str::trim)
+        .skip_while(|line| *line != "[features]")
+        .skip(1)
+        .take_while(|line| !line.starts_with('['))
+        .filter_map(|line| line.split_once('=').map(|(name, _)| name.trim().to_owned()))
+        .filter(|name| !name.is_empty() && !name.starts_with('#'))
+        .collect()
+}
+
 /// One possible implementation of walking a directory only visiting files.
 fn visit_dirs(dir: &Path, targets: &mut Vec<PathBuf>) -> io::Result<()> {
     if dir.is_dir() {
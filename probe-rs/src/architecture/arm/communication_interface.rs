@@ -76,6 +76,9 @@ pub trait ArmProbeInterface: DapAccess + SwdSequence + SwoAccess + Send {
         dp: DpAddress,
     ) -> Result<Option<ArmChipInfo>, ProbeRsError>;
 
+    /// Get a reference to the underlying debug probe.
+    fn probe_mut(&mut self) -> &mut dyn DebugProbe;
+
     /// Closes the interface and returns back the generic probe it consumed.
     fn close(self: Box<Self>) -> Probe;
 }
@@ -312,6 +315,10 @@ impl ArmProbeInterface for ArmCommunicationInterface<Initialized> {
         ArmCommunicationInterface::num_access_ports(self, dp)
     }
 
+    fn probe_mut(&mut self) -> &mut dyn DebugProbe {
+        self.probe.probe_mut()
+    }
+
     fn close(self: Box<Self>) -> Probe {
         Probe::from_attached_probe(RawDapAccess::into_probe(self.probe))
     }
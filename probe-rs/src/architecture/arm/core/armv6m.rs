@@ -1,6 +1,6 @@
 //! Register types and the core interface for armv6-M
 
-use super::{CortexMState, Dfsr, ARM_REGISTER_FILE};
+use super::{cortex_m::DwtVersion, CortexMState, Dfsr, ARM_REGISTER_FILE};
 
 use crate::architecture::arm::sequences::ArmDebugSequence;
 use crate::core::{
@@ -11,6 +11,7 @@ use crate::memory::{valid_32_address, Memory};
 use crate::{
     Architecture, CoreInformation, CoreInterface, CoreStatus, CoreType, DebugProbeError,
     HaltReason, InstructionSet, MemoryInterface, MemoryMappedRegister, RegisterId,
+    VectorCatchCondition, Watchpoint,
 };
 use anyhow::Result;
 use bitfield::bitfield;
@@ -812,6 +813,52 @@ impl<'probe> CoreInterface for Armv6m<'probe> {
         Ok(breakpoints)
     }
 
+    fn available_watchpoint_units(&mut self) -> Result<u32, Error> {
+        super::cortex_m::available_watchpoint_units(&mut self.memory)
+    }
+
+    fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, Error> {
+        super::cortex_m::hw_watchpoints(&mut self.memory, DwtVersion::Armv7m)
+    }
+
+    fn set_hw_watchpoint(
+        &mut self,
+        unit_index: usize,
+        watchpoint: Watchpoint,
+    ) -> Result<(), Error> {
+        super::cortex_m::set_hw_watchpoint(
+            &mut self.memory,
+            DwtVersion::Armv7m,
+            unit_index,
+            watchpoint,
+        )
+    }
+
+    fn clear_hw_watchpoint(&mut self, unit_index: usize) -> Result<(), Error> {
+        super::cortex_m::clear_hw_watchpoint(&mut self.memory, unit_index)
+    }
+
+    fn triggered_watchpoint(&mut self) -> Result<Option<Watchpoint>, Error> {
+        super::cortex_m::triggered_watchpoint(&mut self.memory, DwtVersion::Armv7m)
+    }
+
+    fn set_vector_catch(
+        &mut self,
+        condition: VectorCatchCondition,
+        enabled: bool,
+    ) -> Result<(), Error> {
+        // ARMv6-M has no configurable faults, so all faults escalate to a HardFault.
+        match condition {
+            VectorCatchCondition::HardFault | VectorCatchCondition::CoreReset => {
+                super::cortex_m::set_vector_catch(&mut self.memory, condition, enabled)
+            }
+            _ => Err(Error::Other(anyhow::anyhow!(
+                "Vector catch for {:?} is not supported on ARMv6-M",
+                condition
+            ))),
+        }
+    }
+
     fn fpu_support(&mut self) -> Result<bool, crate::error::Error> {
         Ok(false)
     }
@@ -1,4 +1,7 @@
 //! Register types and the core interface for armv7-a
+//!
+//! ARMv7-R cores (Cortex-R4, Cortex-R5) implement the same v7 debug architecture,
+//! and use this interface as well.
 
 use crate::architecture::arm::core::armv7a_debug_regs::*;
 use crate::architecture::arm::core::register;
@@ -41,10 +44,12 @@ pub enum Armv7aError {
     DataAbort,
 }
 
-/// Interface for interacting with an ARMv7-A core
+/// Interface for interacting with an ARMv7-A or ARMv7-R core
 pub struct Armv7a<'probe> {
     memory: Memory<'probe>,
 
+    core_type: CoreType,
+
     state: &'probe mut CortexAState,
 
     base_address: u64,
@@ -58,10 +63,34 @@ pub struct Armv7a<'probe> {
 
 impl<'probe> Armv7a<'probe> {
     pub(crate) fn new(
+        memory: Memory<'probe>,
+        state: &'probe mut CortexAState,
+        base_address: u64,
+        sequence: Arc<dyn ArmDebugSequence>,
+    ) -> Result<Self, Error> {
+        Self::with_core_type(memory, state, base_address, sequence, CoreType::Armv7a)
+    }
+
+    /// Create an interface for an ARMv7-R core.
+    ///
+    /// ARMv7-R cores have no MMU, so all addresses are physical. Memory is accessed through
+    /// the core, which makes the tightly coupled memories (TCMs) accessible at the addresses
+    /// the core sees them. Cores running in lockstep are debugged through the primary core only.
+    pub(crate) fn new_armv7r(
+        memory: Memory<'probe>,
+        state: &'probe mut CortexAState,
+        base_address: u64,
+        sequence: Arc<dyn ArmDebugSequence>,
+    ) -> Result<Self, Error> {
+        Self::with_core_type(memory, state, base_address, sequence, CoreType::Armv7r)
+    }
+
+    fn with_core_type(
         mut memory: Memory<'probe>,
         state: &'probe mut CortexAState,
         base_address: u64,
         sequence: Arc<dyn ArmDebugSequence>,
+        core_type: CoreType,
     ) -> Result<Self, Error> {
         if !state.initialized() {
             // determine current state
@@ -87,6 +116,7 @@ impl<'probe> Armv7a<'probe> {
 
         Ok(Self {
             memory,
+            core_type,
             state,
             base_address,
             sequence,
@@ -318,11 +348,8 @@ impl<'probe> CoreInterface for Armv7a<'probe> {
     }
 
     fn reset(&mut self) -> Result<(), Error> {
-        self.sequence.reset_system(
-            &mut self.memory,
-            crate::CoreType::Armv7a,
-            Some(self.base_address),
-        )?;
+        self.sequence
+            .reset_system(&mut self.memory, self.core_type, Some(self.base_address))?;
 
         // Reset our cached values
         self.reset_register_cache();
@@ -331,16 +358,10 @@ impl<'probe> CoreInterface for Armv7a<'probe> {
     }
 
     fn reset_and_halt(&mut self, timeout: Duration) -> Result<CoreInformation, Error> {
-        self.sequence.reset_catch_set(
-            &mut self.memory,
-            crate::CoreType::Armv7a,
-            Some(self.base_address),
-        )?;
-        self.sequence.reset_system(
-            &mut self.memory,
-            crate::CoreType::Armv7a,
-            Some(self.base_address),
-        )?;
+        self.sequence
+            .reset_catch_set(&mut self.memory, self.core_type, Some(self.base_address))?;
+        self.sequence
+            .reset_system(&mut self.memory, self.core_type, Some(self.base_address))?;
 
         // Request halt
         let address = Dbgdrcr::get_mmio_address(self.base_address);
@@ -352,7 +373,7 @@ impl<'probe> CoreInterface for Armv7a<'probe> {
         // Release from reset
         self.sequence.reset_catch_clear(
             &mut self.memory,
-            crate::CoreType::Armv7a,
+            self.core_type,
             Some(self.base_address),
         )?;
 
@@ -564,7 +585,7 @@ impl<'probe> CoreInterface for Armv7a<'probe> {
     }
 
     fn core_type(&self) -> CoreType {
-        CoreType::Armv7a
+        self.core_type
     }
 
     fn instruction_set(&mut self) -> Result<InstructionSet, Error> {
@@ -637,6 +658,11 @@ impl<'probe> CoreInterface for Armv7a<'probe> {
             Ok(())
         }
     }
+
+    fn supports_background_memory_access(&mut self) -> bool {
+        // Memory is accessed by executing load and store instructions on the core.
+        false
+    }
 }
 
 impl<'probe> MemoryInterface for Armv7a<'probe> {
@@ -1038,6 +1064,33 @@ mod test {
         .unwrap();
     }
 
+    #[test]
+    fn armv7r_new() {
+        let mut probe = MockProbe::new();
+
+        // Add expectations
+        add_status_expectations(&mut probe, true);
+
+        let mock_mem = Memory::new(
+            probe,
+            MemoryAp::new(ApAddress {
+                ap: 0,
+                dp: DpAddress::Default,
+            }),
+        );
+
+        let mut state = CortexAState::new();
+        let armv7r = Armv7a::new_armv7r(
+            mock_mem,
+            &mut state,
+            TEST_BASE_ADDRESS,
+            DefaultArmSequence::create(),
+        )
+        .unwrap();
+
+        assert_eq!(armv7r.core_type(), CoreType::Armv7r);
+    }
+
     #[test]
     fn armv7a_core_halted() {
         let mut probe = MockProbe::new();
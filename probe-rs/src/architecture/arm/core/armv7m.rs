@@ -6,9 +6,9 @@ use crate::core::{
 };
 use crate::error::Error;
 use crate::memory::{valid_32_address, Memory};
-use crate::{CoreType, DebugProbeError, InstructionSet};
+use crate::{CoreType, DebugProbeError, InstructionSet, VectorCatchCondition, Watchpoint};
 
-use super::cortex_m::Cpacr;
+use super::cortex_m::{Cpacr, DwtVersion};
 use super::{register, CortexMState, Dfsr, ARM_REGISTER_FILE};
 use crate::{
     core::{Architecture, CoreStatus, HaltReason},
@@ -999,6 +999,49 @@ impl<'probe> CoreInterface for Armv7m<'probe> {
         Ok(breakpoints)
     }
 
+    fn available_watchpoint_units(&mut self) -> Result<u32, Error> {
+        super::cortex_m::available_watchpoint_units(&mut self.memory)
+    }
+
+    fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, Error> {
+        super::cortex_m::hw_watchpoints(&mut self.memory, DwtVersion::Armv7m)
+    }
+
+    fn set_hw_watchpoint(
+        &mut self,
+        unit_index: usize,
+        watchpoint: Watchpoint,
+    ) -> Result<(), Error> {
+        super::cortex_m::set_hw_watchpoint(
+            &mut self.memory,
+            DwtVersion::Armv7m,
+            unit_index,
+            watchpoint,
+        )
+    }
+
+    fn clear_hw_watchpoint(&mut self, unit_index: usize) -> Result<(), Error> {
+        super::cortex_m::clear_hw_watchpoint(&mut self.memory, unit_index)
+    }
+
+    fn triggered_watchpoint(&mut self) -> Result<Option<Watchpoint>, Error> {
+        super::cortex_m::triggered_watchpoint(&mut self.memory, DwtVersion::Armv7m)
+    }
+
+    fn set_vector_catch(
+        &mut self,
+        condition: VectorCatchCondition,
+        enabled: bool,
+    ) -> Result<(), Error> {
+        if condition == VectorCatchCondition::SecureFault {
+            return Err(Error::Other(anyhow!(
+                "Vector catch for {:?} is not supported on ARMv7-M",
+                condition
+            )));
+        }
+        super::cortex_m::set_vector_catch(&mut self.memory, condition, enabled)
+    }
+
     fn fpu_support(&mut self) -> Result<bool, crate::error::Error> {
         Ok(Cpacr(self.memory.read_word_32(Cpacr::ADDRESS)?).fpu_present())
     }
@@ -378,6 +378,38 @@ impl<'probe> Armv8a<'probe> {
         Ok(())
     }
 
+    /// Opens the gate of the given CTI channel, so that events on it are passed between
+    /// this core and the cross trigger matrix. The gates of all other channels are closed.
+    pub(crate) fn open_cti_gate(&mut self, channel: usize) -> Result<(), Error> {
+        let mut cti_gate = CtiGate(0);
+        cti_gate.set_en(channel, 1);
+
+        let address = CtiGate::get_mmio_address(self.cti_address);
+        self.memory.write_word_32(address, cti_gate.into())?;
+
+        Ok(())
+    }
+
+    /// Closes the gates of all CTI channels.
+    pub(crate) fn close_cti_gates(&mut self) -> Result<(), Error> {
+        let address = CtiGate::get_mmio_address(self.cti_address);
+        self.memory.write_word_32(address, CtiGate(0).into())?;
+
+        Ok(())
+    }
+
+    /// Writes back all modified registers and acknowledges the pending halt trigger,
+    /// so that the core restarts as soon as a restart event arrives on its CTI.
+    pub(crate) fn prepare_for_restart(&mut self) -> Result<(), Error> {
+        self.writeback_registers()?;
+        self.ack_cti_halt()
+    }
+
+    /// Returns `true` if the core is halted, according to the cached core state.
+    pub(crate) fn is_halted(&self) -> bool {
+        matches!(self.state.current_state, CoreStatus::Halted(_))
+    }
+
     fn read_core_reg_32(&mut self, reg_num: u16) -> Result<RegisterValue, Error> {
         // Generate instruction to extract register
         match reg_num {
@@ -692,10 +724,7 @@ impl<'probe> CoreInterface for Armv8a<'probe> {
             return Ok(());
         }
 
-        // set writeback values
-        self.writeback_registers()?;
-
-        self.ack_cti_halt()?;
+        self.prepare_for_restart()?;
 
         // Ungate restart CTI channel
         let mut cti_gate = CtiGate(0);
@@ -1005,6 +1034,11 @@ impl<'probe> CoreInterface for Armv8a<'probe> {
             Ok(())
         }
     }
+
+    fn supports_background_memory_access(&mut self) -> bool {
+        // Memory is accessed by executing load and store instructions on the core.
+        false
+    }
 }
 
 impl<'probe> MemoryInterface for Armv8a<'probe> {
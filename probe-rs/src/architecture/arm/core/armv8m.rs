@@ -9,13 +9,14 @@ use crate::{
 };
 use crate::{Architecture, CoreInformation};
 use crate::{CoreInterface, CoreType, InstructionSet, MemoryMappedRegister};
-use crate::{RegisterId, RegisterValue};
+use crate::{RegisterId, RegisterValue, VectorCatchCondition, Watchpoint};
 use anyhow::Result;
 
 use bitfield::bitfield;
 
-use super::cortex_m::Cpacr;
+use super::cortex_m::{Cpacr, DwtVersion};
 use super::{CortexMState, Dfsr, ARM_REGISTER_FILE};
+use std::ops::Range;
 use std::sync::Arc;
 use std::{
     mem::size_of,
@@ -381,6 +382,43 @@ impl<'probe> CoreInterface for Armv8m<'probe> {
         Ok(breakpoints)
     }
 
+    fn available_watchpoint_units(&mut self) -> Result<u32, Error> {
+        super::cortex_m::available_watchpoint_units(&mut self.memory)
+    }
+
+    fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, Error> {
+        super::cortex_m::hw_watchpoints(&mut self.memory, DwtVersion::Armv8m)
+    }
+
+    fn set_hw_watchpoint(
+        &mut self,
+        unit_index: usize,
+        watchpoint: Watchpoint,
+    ) -> Result<(), Error> {
+        super::cortex_m::set_hw_watchpoint(
+            &mut self.memory,
+            DwtVersion::Armv8m,
+            unit_index,
+            watchpoint,
+        )
+    }
+
+    fn clear_hw_watchpoint(&mut self, unit_index: usize) -> Result<(), Error> {
+        super::cortex_m::clear_hw_watchpoint(&mut self.memory, unit_index)
+    }
+
+    fn triggered_watchpoint(&mut self) -> Result<Option<Watchpoint>, Error> {
+        super::cortex_m::triggered_watchpoint(&mut self.memory, DwtVersion::Armv8m)
+    }
+
+    fn set_vector_catch(
+        &mut self,
+        condition: VectorCatchCondition,
+        enabled: bool,
+    ) -> Result<(), Error> {
+        super::cortex_m::set_vector_catch(&mut self.memory, condition, enabled)
+    }
+
     fn fpu_support(&mut self) -> Result<bool, crate::error::Error> {
         Ok(Cpacr(self.memory.read_word_32(Cpacr::ADDRESS)?).fpu_present())
     }
@@ -972,3 +1010,211 @@ impl From<FpCompN> for u32 {
         value.0
     }
 }
+
+bitfield! {
+    /// SAU Control Register, SAU_CTRL (see armv8-M Architecture Reference Manual D1.2.205)
+    #[derive(Copy, Clone)]
+    pub struct SauCtrl(u32);
+    impl Debug;
+    /// All Non-secure. When SAU_CTRL.ENABLE is 0, this bit selects if all memory is
+    /// Non-secure (1) or Secure (0).
+    pub allns, set_allns: 1;
+    /// Enable. Enables the SAU.
+    pub enable, set_enable: 0;
+}
+
+impl MemoryMappedRegister for SauCtrl {
+    const ADDRESS: u64 = 0xE000_EDD0;
+    const NAME: &'static str = "SAU_CTRL";
+}
+
+impl From<u32> for SauCtrl {
+    fn from(value: u32) -> Self {
+        SauCtrl(value)
+    }
+}
+
+impl From<SauCtrl> for u32 {
+    fn from(value: SauCtrl) -> Self {
+        value.0
+    }
+}
+
+bitfield! {
+    /// SAU Type Register, SAU_TYPE (see armv8-M Architecture Reference Manual D1.2.209)
+    #[derive(Copy, Clone)]
+    pub struct SauType(u32);
+    impl Debug;
+    /// SAU regions. The number of implemented SAU regions.
+    pub sregion, _: 7, 0;
+}
+
+impl MemoryMappedRegister for SauType {
+    const ADDRESS: u64 = 0xE000_EDD4;
+    const NAME: &'static str = "SAU_TYPE";
+}
+
+impl From<u32> for SauType {
+    fn from(value: u32) -> Self {
+        SauType(value)
+    }
+}
+
+impl From<SauType> for u32 {
+    fn from(value: SauType) -> Self {
+        value.0
+    }
+}
+
+/// SAU Region Number Register, SAU_RNR (see armv8-M Architecture Reference Manual D1.2.208)
+///
+/// Selects the region which is accessed through SAU_RBAR and SAU_RLAR.
+#[derive(Debug, Copy, Clone)]
+pub struct SauRnr(u32);
+
+impl From<u32> for SauRnr {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<SauRnr> for u32 {
+    fn from(value: SauRnr) -> Self {
+        value.0
+    }
+}
+
+impl MemoryMappedRegister for SauRnr {
+    const ADDRESS: u64 = 0xE000_EDD8;
+    const NAME: &'static str = "SAU_RNR";
+}
+
+/// SAU Region Base Address Register, SAU_RBAR (see armv8-M Architecture Reference Manual D1.2.206)
+///
+/// Bits[31:5] hold the base address of the region, the address of its first byte.
+#[derive(Debug, Copy, Clone)]
+pub struct SauRbar(u32);
+
+impl From<u32> for SauRbar {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<SauRbar> for u32 {
+    fn from(value: SauRbar) -> Self {
+        value.0
+    }
+}
+
+impl MemoryMappedRegister for SauRbar {
+    const ADDRESS: u64 = 0xE000_EDDC;
+    const NAME: &'static str = "SAU_RBAR";
+}
+
+bitfield! {
+    /// SAU Region Limit Address Register, SAU_RLAR (see armv8-M Architecture Reference Manual D1.2.207)
+    #[derive(Copy, Clone)]
+    pub struct SauRlar(u32);
+    impl Debug;
+    /// Limit address. Bits[31:5] of the address of the last 32 byte block of the region.
+    pub laddr, set_laddr: 31, 5;
+    /// Non-secure callable. Marks the region as Non-secure callable instead of Non-secure.
+    pub nsc, set_nsc: 1;
+    /// Enable. Enables the region.
+    pub enable, set_enable: 0;
+}
+
+impl MemoryMappedRegister for SauRlar {
+    const ADDRESS: u64 = 0xE000_EDE0;
+    const NAME: &'static str = "SAU_RLAR";
+}
+
+impl From<u32> for SauRlar {
+    fn from(value: u32) -> Self {
+        SauRlar(value)
+    }
+}
+
+impl From<SauRlar> for u32 {
+    fn from(value: SauRlar) -> Self {
+        value.0
+    }
+}
+
+/// A region of the Security Attribution Unit (SAU).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SauRegion {
+    /// The memory of the region. The start and the end have to be aligned to 32 bytes.
+    pub range: Range<u64>,
+    /// The region is Non-secure callable, instead of Non-secure.
+    pub non_secure_callable: bool,
+}
+
+/// Configure and enable the Security Attribution Unit (SAU) of an Armv8-M core with the
+/// Security Extension.
+///
+/// The memory in the `regions` becomes Non-secure or Non-secure callable, all other memory is
+/// Secure, unless the implementation defined attribution unit of the chip marks it otherwise.
+/// All other regions of the SAU are disabled.
+///
+/// The SAU is usually configured by the secure firmware. Configuring it from the debugger allows
+/// running a Non-secure image without it. The configuration is lost when the core is reset, and
+/// the SAU registers can only be written with a secure debug access.
+pub fn configure_sau(
+    memory: &mut impl MemoryInterface,
+    regions: &[SauRegion],
+) -> Result<(), Error> {
+    let sau_type = SauType(memory.read_word_32(SauType::ADDRESS)?);
+    let region_count = sau_type.sregion();
+
+    if regions.len() > region_count as usize {
+        return Err(Error::Other(anyhow::anyhow!(
+            "The SAU has {} regions, but {} regions were given.",
+            region_count,
+            regions.len()
+        )));
+    }
+
+    let mut limits = Vec::with_capacity(regions.len());
+    for region in regions {
+        let range = &region.range;
+        if range.start >= range.end
+            || range.start % 32 != 0
+            || range.end % 32 != 0
+            || range.end > 1 << 32
+        {
+            return Err(Error::Other(anyhow::anyhow!(
+                "The SAU region {:#010x}..{:#010x} is empty or not aligned to 32 bytes.",
+                range.start,
+                range.end
+            )));
+        }
+
+        let mut rlar = SauRlar(0);
+        rlar.set_laddr(((range.end - 32) >> 5) as u32);
+        rlar.set_nsc(region.non_secure_callable);
+        rlar.set_enable(true);
+        limits.push((range.start as u32, rlar));
+    }
+
+    // Disable the SAU while its regions are changed.
+    memory.write_word_32(SauCtrl::ADDRESS, SauCtrl(0).into())?;
+
+    for index in 0..region_count {
+        let (base, rlar) = limits
+            .get(index as usize)
+            .copied()
+            .unwrap_or((0, SauRlar(0)));
+
+        memory.write_word_32(SauRnr::ADDRESS, index)?;
+        memory.write_word_32(SauRbar::ADDRESS, base)?;
+        memory.write_word_32(SauRlar::ADDRESS, rlar.into())?;
+    }
+
+    let mut ctrl = SauCtrl(0);
+    ctrl.set_enable(true);
+    memory.write_word_32(SauCtrl::ADDRESS, ctrl.into())?;
+
+    Ok(())
+}
@@ -1,7 +1,12 @@
 //! Common functions and data types for Cortex-M core variants
 
-use crate::{DebugProbeError, Error, Memory, MemoryMappedRegister, RegisterId};
+use super::armv8m::Demcr;
+use crate::{
+    DebugProbeError, Error, Memory, MemoryMappedRegister, RegisterId, VectorCatchCondition,
+    Watchpoint, WatchpointKind,
+};
 
+use anyhow::anyhow;
 use bitfield::bitfield;
 use std::time::{Duration, Instant};
 
@@ -128,6 +133,39 @@ impl MemoryMappedRegister for Cpacr {
     const NAME: &'static str = "CPACR";
 }
 
+bitfield! {
+    /// The `EXC_RETURN` value, which is in LR while an exception handler is executed, and which
+    /// describes the exception frame the core stacked on exception entry.
+    #[derive(Copy, Clone)]
+    pub struct ExcReturn(u32);
+    impl Debug;
+    /// Whether the frame is on the secure stack (ARMv8-M with the Security Extension).
+    pub s, _: 6;
+    /// Whether the callee saved registers are stacked with the default rules, i.e. not stacked (ARMv8-M).
+    pub dcrs, _: 5;
+    /// Whether the frame is a basic frame, without the floating point registers.
+    pub ftype, _: 4;
+    /// Whether the core returns to thread mode.
+    pub mode, _: 3;
+    /// Whether the frame is on the process stack.
+    pub spsel, _: 2;
+    /// Whether the exception is taken to the secure state (ARMv8-M with the Security Extension).
+    pub es, _: 0;
+}
+
+impl ExcReturn {
+    /// Returns the `EXC_RETURN` value, if `return_address` is one.
+    ///
+    /// `0xFFFF_FFFF` is the value of LR after reset, and not an `EXC_RETURN` value.
+    pub fn from_return_address(return_address: u64) -> Option<ExcReturn> {
+        if (0xFFFF_FF00..0xFFFF_FFFF).contains(&return_address) {
+            Some(ExcReturn(return_address as u32))
+        } else {
+            None
+        }
+    }
+}
+
 pub(crate) fn read_core_reg(memory: &mut Memory, addr: RegisterId) -> Result<u32, Error> {
     // Write the DCRSR value to select the register we want to read.
     let mut dcrsr_val = Dcrsr(0);
@@ -176,3 +214,306 @@ fn wait_for_core_register_transfer(memory: &mut Memory, timeout: Duration) -> Re
     }
     Err(Error::Probe(DebugProbeError::Timeout))
 }
+
+bitfield! {
+    /// DWT Control Register, DWT_CTRL (see armv7-M Architecture Reference Manual C1.8.7)
+    #[derive(Copy, Clone)]
+    pub struct DwtCtrl(u32);
+    impl Debug;
+    /// The number of comparators implemented.
+    pub numcomp, _: 31, 28;
+}
+
+impl From<u32> for DwtCtrl {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<DwtCtrl> for u32 {
+    fn from(value: DwtCtrl) -> Self {
+        value.0
+    }
+}
+
+impl MemoryMappedRegister for DwtCtrl {
+    const ADDRESS: u64 = 0xE000_1000;
+    const NAME: &'static str = "DWT_CTRL";
+}
+
+/// The address of the DWT_COMP register of the first comparator. The registers of each
+/// comparator are 16 bytes apart.
+const DWT_COMP0: u64 = 0xE000_1020;
+/// The offset of DWT_MASK from DWT_COMP. ARMv8-M has no mask register.
+const DWT_MASK_OFFSET: u64 = 0x4;
+/// The offset of DWT_FUNCTION from DWT_COMP.
+const DWT_FUNCTION_OFFSET: u64 = 0x8;
+
+/// DEMCR.TRCENA, which enables the DWT. It is named DWTENA on ARMv6-M.
+const DEMCR_TRCENA: u32 = 1 << 24;
+/// The address of DEMCR, which is the same on all Cortex-M variants.
+const DEMCR: u64 = 0xE000_EDFC;
+
+/// DWT_FUNCTION.MATCHED, which is set when the comparator matched, and cleared when the register
+/// is read.
+const DWT_FUNCTION_MATCHED: u32 = 1 << 24;
+
+/// The layout of the DWT_FUNCTION register, which differs between the architecture versions.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum DwtVersion {
+    /// The layout of ARMv6-M and ARMv7-M, where the watched range is set with DWT_MASK.
+    Armv7m,
+    /// The layout of ARMv8-M, where the watched range is set with DWT_FUNCTION.DATAVSIZE.
+    Armv8m,
+}
+
+impl DwtVersion {
+    /// Returns the DWT_FUNCTION value which makes a comparator halt the core on `kind` accesses,
+    /// and the DWT_MASK value for a range of `2^size_log2` bytes.
+    fn configuration(self, kind: WatchpointKind, size_log2: u32) -> (u32, u32) {
+        match self {
+            DwtVersion::Armv7m => {
+                let function = match kind {
+                    WatchpointKind::Read => 0b0101,
+                    WatchpointKind::Write => 0b0110,
+                    WatchpointKind::Access => 0b0111,
+                };
+                (function, size_log2)
+            }
+            DwtVersion::Armv8m => {
+                let match_type = match kind {
+                    WatchpointKind::Access => 0b0100,
+                    WatchpointKind::Write => 0b0101,
+                    WatchpointKind::Read => 0b0110,
+                };
+                // ACTION = 0b01 generates a debug event, DATAVSIZE is the size of the range.
+                (size_log2 << 10 | 0b01 << 4 | match_type, 0)
+            }
+        }
+    }
+
+    /// Decodes the watchpoint which a comparator is configured with, if it is one.
+    fn watchpoint(self, comp: u32, mask: u32, function: u32) -> Option<Watchpoint> {
+        let (kind, size_log2) = match self {
+            DwtVersion::Armv7m => {
+                let kind = match function & 0xf {
+                    0b0101 => WatchpointKind::Read,
+                    0b0110 => WatchpointKind::Write,
+                    0b0111 => WatchpointKind::Access,
+                    _ => return None,
+                };
+                (kind, mask & 0x1f)
+            }
+            DwtVersion::Armv8m => {
+                if (function >> 4) & 0b11 != 0b01 {
+                    return None;
+                }
+
+                let kind = match function & 0xf {
+                    0b0100 => WatchpointKind::Access,
+                    0b0101 => WatchpointKind::Write,
+                    0b0110 => WatchpointKind::Read,
+                    _ => return None,
+                };
+                (kind, (function >> 10) & 0b11)
+            }
+        };
+
+        Some(Watchpoint {
+            address: comp as u64,
+            length: 1 << size_log2,
+            kind,
+        })
+    }
+}
+
+/// Enables the DWT, whose registers may not be accessible otherwise.
+fn enable_dwt(memory: &mut Memory) -> Result<(), Error> {
+    let demcr = memory.read_word_32(DEMCR)?;
+    if demcr & DEMCR_TRCENA == 0 {
+        memory.write_word_32(DEMCR, demcr | DEMCR_TRCENA)?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn available_watchpoint_units(memory: &mut Memory) -> Result<u32, Error> {
+    enable_dwt(memory)?;
+
+    Ok(DwtCtrl(memory.read_word_32(DwtCtrl::ADDRESS)?).numcomp())
+}
+
+fn read_watchpoint(
+    memory: &mut Memory,
+    version: DwtVersion,
+    unit_index: usize,
+) -> Result<(Option<Watchpoint>, bool), Error> {
+    let comp_address = DWT_COMP0 + 16 * unit_index as u64;
+
+    let comp = memory.read_word_32(comp_address)?;
+    let mask = match version {
+        DwtVersion::Armv7m => memory.read_word_32(comp_address + DWT_MASK_OFFSET)?,
+        DwtVersion::Armv8m => 0,
+    };
+    let function = memory.read_word_32(comp_address + DWT_FUNCTION_OFFSET)?;
+
+    Ok((
+        version.watchpoint(comp, mask, function),
+        function & DWT_FUNCTION_MATCHED != 0,
+    ))
+}
+
+pub(crate) fn hw_watchpoints(
+    memory: &mut Memory,
+    version: DwtVersion,
+) -> Result<Vec<Option<Watchpoint>>, Error> {
+    let units = available_watchpoint_units(memory)? as usize;
+
+    (0..units)
+        .map(|unit_index| Ok(read_watchpoint(memory, version, unit_index)?.0))
+        .collect()
+}
+
+pub(crate) fn set_hw_watchpoint(
+    memory: &mut Memory,
+    version: DwtVersion,
+    unit_index: usize,
+    watchpoint: Watchpoint,
+) -> Result<(), Error> {
+    let length = watchpoint.length;
+    if !length.is_power_of_two() || watchpoint.address % length != 0 {
+        return Err(Error::Other(anyhow!(
+            "Unsupported watchpoint at {:#010x} with a length of {} bytes. The length must be a power of two, and the address must be aligned to it.",
+            watchpoint.address,
+            length
+        )));
+    }
+
+    let address = u32::try_from(watchpoint.address).map_err(|_| {
+        Error::Other(anyhow!(
+            "Unsupported watchpoint address {:#010x}",
+            watchpoint.address
+        ))
+    })?;
+
+    let size_log2 = length.trailing_zeros();
+    if version == DwtVersion::Armv8m && size_log2 > 2 {
+        return Err(Error::Other(anyhow!(
+            "Unsupported watchpoint length of {} bytes. At most 4 bytes can be watched.",
+            length
+        )));
+    }
+
+    enable_dwt(memory)?;
+
+    let comp_address = DWT_COMP0 + 16 * unit_index as u64;
+    let (function, mask) = version.configuration(watchpoint.kind, size_log2);
+
+    // Disable the comparator while it is reconfigured.
+    memory.write_word_32(comp_address + DWT_FUNCTION_OFFSET, 0)?;
+    memory.write_word_32(comp_address, address)?;
+
+    if version == DwtVersion::Armv7m {
+        memory.write_word_32(comp_address + DWT_MASK_OFFSET, mask)?;
+
+        // The number of bits which can be masked is implementation defined, and larger values
+        // read back as the maximum.
+        if memory.read_word_32(comp_address + DWT_MASK_OFFSET)? != mask {
+            return Err(Error::Other(anyhow!(
+                "Unsupported watchpoint length of {} bytes",
+                length
+            )));
+        }
+    }
+
+    memory.write_word_32(comp_address + DWT_FUNCTION_OFFSET, function)?;
+
+    Ok(())
+}
+
+pub(crate) fn clear_hw_watchpoint(memory: &mut Memory, unit_index: usize) -> Result<(), Error> {
+    let comp_address = DWT_COMP0 + 16 * unit_index as u64;
+
+    memory.write_word_32(comp_address + DWT_FUNCTION_OFFSET, 0)?;
+
+    Ok(())
+}
+
+pub(crate) fn triggered_watchpoint(
+    memory: &mut Memory,
+    version: DwtVersion,
+) -> Result<Option<Watchpoint>, Error> {
+    let units = available_watchpoint_units(memory)? as usize;
+
+    for unit_index in 0..units {
+        if let (Some(watchpoint), true) = read_watchpoint(memory, version, unit_index)? {
+            return Ok(Some(watchpoint));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Enables or disables the vector catch `condition` in DEMCR.
+///
+/// The vector catch bits are at the same position in DEMCR for all Cortex-M variants, so the
+/// ARMv8-M register type is used for all of them. The caller has to check that the core
+/// supports the `condition`.
+pub(crate) fn set_vector_catch(
+    memory: &mut Memory,
+    condition: VectorCatchCondition,
+    enabled: bool,
+) -> Result<(), Error> {
+    let mut demcr = Demcr::from(memory.read_word_32(Demcr::ADDRESS)?);
+
+    match condition {
+        VectorCatchCondition::HardFault => demcr.set_vc_harderr(enabled),
+        VectorCatchCondition::MemManage => demcr.set_vc_mmerr(enabled),
+        VectorCatchCondition::BusFault => demcr.set_vc_buserr(enabled),
+        VectorCatchCondition::UsageFault => {
+            demcr.set_vc_staterr(enabled);
+            demcr.set_vc_chkerr(enabled);
+            demcr.set_vc_nocperr(enabled);
+        }
+        VectorCatchCondition::SecureFault => demcr.set_vc_sferr(enabled),
+        VectorCatchCondition::CoreReset => demcr.set_vc_corereset(enabled),
+    }
+
+    memory.write_word_32(Demcr::ADDRESS, demcr.into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::DwtVersion;
+    use crate::{Watchpoint, WatchpointKind};
+
+    #[test]
+    fn watchpoint_configuration() {
+        let watchpoint = Watchpoint {
+            address: 0x2000_0100,
+            length: 4,
+            kind: WatchpointKind::Write,
+        };
+
+        let (function, mask) = DwtVersion::Armv7m.configuration(watchpoint.kind, 2);
+        assert_eq!((function, mask), (0b0110, 2));
+        assert_eq!(
+            DwtVersion::Armv7m.watchpoint(0x2000_0100, mask, function),
+            Some(watchpoint)
+        );
+
+        let (function, _) = DwtVersion::Armv8m.configuration(watchpoint.kind, 2);
+        assert_eq!(function, 0b1000_0001_0101);
+        assert_eq!(
+            DwtVersion::Armv8m.watchpoint(0x2000_0100, 0, function),
+            Some(watchpoint)
+        );
+
+        // Comparators which are used for tracing aren't watchpoints.
+        assert_eq!(DwtVersion::Armv7m.watchpoint(0x2000_0100, 0, 0b0011), None);
+        assert_eq!(
+            DwtVersion::Armv8m.watchpoint(0x2000_0100, 0, 0b10_0101),
+            None
+        );
+    }
+}
@@ -0,0 +1,51 @@
+use super::Dump;
+use crate::debug::{DebugInfo, StackFrame};
+use crate::{CoreDump, CoreType, Error, InstructionSet, RegisterId};
+
+impl Dump {
+    /// Symbolicates the dump: unwinds the dumped stack with the debug information of the program
+    /// which was running on the core, and resolves the local variables of each frame.
+    ///
+    /// Only the registers and the stack are in the dump, so variables in other memory, e.g. static
+    /// variables, show an error instead of their value. See [`CoreDump::symbolicate`].
+    pub fn symbolicate(&self, debug_info: &DebugInfo) -> Result<Vec<StackFrame>, Error> {
+        CoreDump::from(self).symbolicate(debug_info)
+    }
+}
+
+impl From<&Dump> for CoreDump {
+    fn from(dump: &Dump) -> Self {
+        CoreDump {
+            core_type: CoreType::Armv7m,
+            instruction_set: InstructionSet::Thumb2,
+            registers: dump
+                .regs
+                .iter()
+                .enumerate()
+                .map(|(id, &value)| (RegisterId(id as u16), value))
+                .collect(),
+            memory: vec![(dump.stack_addr.into(), dump.stack.clone())],
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Dump;
+    use crate::CoreDump;
+
+    #[test]
+    fn convert_dump() {
+        let dump = Dump::new(0x2000_0000, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+        let core_dump = CoreDump::from(&dump);
+
+        let mut data = [0u8; 4];
+        core_dump.read(0x2000_0004, &mut data).unwrap();
+        assert_eq!(data, [5, 6, 7, 8]);
+        assert!(core_dump.read(0x2000_0006, &mut data).is_err());
+        assert_eq!(
+            core_dump.memory_ranges().next(),
+            Some(0x2000_0000..0x2000_0008)
+        );
+    }
+}
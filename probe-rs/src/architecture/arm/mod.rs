@@ -6,6 +6,7 @@ pub mod component;
 pub(crate) mod core;
 pub mod dp;
 pub mod memory;
+pub mod semihosting;
 pub mod sequences;
 pub mod swo;
 mod traits;
@@ -0,0 +1,838 @@
+//! ARM semihosting, which lets the target use services of the host, e.g. for console output,
+//! file I/O or to report its exit code.
+//!
+//! The target requests a service by executing `BKPT 0xAB` (Thumb) or `HLT 0xF000` (A32), with
+//! the operation in R0 and its parameter in R1. This halts the core, and [`SemihostingHost::handle`]
+//! decodes the request, services it and writes the result back, after which the core can be resumed.
+//! Which operations are serviced is configured with a [`SemihostingPolicy`].
+//!
+//! See the [semihosting specification](https://github.com/ARM-software/abi-aa/blob/main/semihosting/semihosting.rst)
+//! for the details of each operation.
+
+use crate::{core::RegisterId, Core, CoreStatus, Error, InstructionSet, MemoryInterface};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::{Instant, SystemTime};
+
+const SYS_OPEN: u32 = 0x01;
+const SYS_CLOSE: u32 = 0x02;
+const SYS_WRITEC: u32 = 0x03;
+const SYS_WRITE0: u32 = 0x04;
+const SYS_WRITE: u32 = 0x05;
+const SYS_READ: u32 = 0x06;
+const SYS_READC: u32 = 0x07;
+const SYS_ISERROR: u32 = 0x08;
+const SYS_ISTTY: u32 = 0x09;
+const SYS_SEEK: u32 = 0x0a;
+const SYS_FLEN: u32 = 0x0c;
+const SYS_REMOVE: u32 = 0x0e;
+const SYS_RENAME: u32 = 0x0f;
+const SYS_CLOCK: u32 = 0x10;
+const SYS_TIME: u32 = 0x11;
+const SYS_ERRNO: u32 = 0x13;
+const SYS_GET_CMDLINE: u32 = 0x15;
+const SYS_HEAPINFO: u32 = 0x16;
+const SYS_EXIT: u32 = 0x18;
+const SYS_EXIT_EXTENDED: u32 = 0x20;
+const SYS_ELAPSED: u32 = 0x30;
+const SYS_TICKFREQ: u32 = 0x31;
+
+/// `ADP_Stopped_ApplicationExit`, the exit reason of a regular exit.
+pub const ADP_STOPPED_APPLICATION_EXIT: u32 = 0x20026;
+
+/// The maximum length of strings read from the target, in case a string isn't terminated.
+const MAX_STRING_LEN: usize = 4096;
+
+/// `BKPT 0xAB` in the Thumb instruction set.
+const THUMB_BKPT: u16 = 0xbeab;
+/// `HLT 0x3C` in the Thumb instruction set.
+const THUMB_HLT: u16 = 0xbabc;
+/// `HLT 0xF000` in the A32 instruction set.
+const ARM_HLT: u32 = 0xe10f_0070;
+
+// The errno values reported to the target.
+const EPERM: i32 = 1;
+const EIO: i32 = 5;
+const EBADF: i32 = 9;
+const EINVAL: i32 = 22;
+
+/// A semihosting operation requested by the target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SemihostingCommand {
+    /// `SYS_OPEN`: Opens a file, or the console if the path is `:tt`.
+    Open {
+        /// The path of the file.
+        path: String,
+        /// The mode, from 0 (`r`) to 11 (`a+b`), as for `fopen`.
+        mode: u32,
+    },
+    /// `SYS_CLOSE`: Closes a file.
+    Close {
+        /// The handle of the file.
+        handle: u32,
+    },
+    /// `SYS_WRITEC`: Writes a character to the console.
+    WriteC {
+        /// The character.
+        character: u8,
+    },
+    /// `SYS_WRITE0`: Writes a NUL terminated string to the console.
+    Write0 {
+        /// The string, without the terminating NUL.
+        text: Vec<u8>,
+    },
+    /// `SYS_WRITE`: Writes to a file.
+    Write {
+        /// The handle of the file.
+        handle: u32,
+        /// The data to write.
+        data: Vec<u8>,
+    },
+    /// `SYS_READ`: Reads from a file into a buffer of the target.
+    Read {
+        /// The handle of the file.
+        handle: u32,
+        /// The address of the buffer.
+        buffer: u32,
+        /// The number of bytes to read.
+        len: u32,
+    },
+    /// `SYS_READC`: Reads a character from the console.
+    ReadC,
+    /// `SYS_ISERROR`: Checks if a return value is an error.
+    IsError {
+        /// The return value.
+        status: i32,
+    },
+    /// `SYS_ISTTY`: Checks if a file is the console.
+    IsTty {
+        /// The handle of the file.
+        handle: u32,
+    },
+    /// `SYS_SEEK`: Seeks to an absolute position in a file.
+    Seek {
+        /// The handle of the file.
+        handle: u32,
+        /// The position, in bytes from the start of the file.
+        position: u32,
+    },
+    /// `SYS_FLEN`: Returns the length of a file.
+    FileLength {
+        /// The handle of the file.
+        handle: u32,
+    },
+    /// `SYS_REMOVE`: Deletes a file.
+    Remove {
+        /// The path of the file.
+        path: String,
+    },
+    /// `SYS_RENAME`: Renames a file.
+    Rename {
+        /// The current path of the file.
+        from: String,
+        /// The new path of the file.
+        to: String,
+    },
+    /// `SYS_CLOCK`: Returns the time since the start of the session in centiseconds.
+    Clock,
+    /// `SYS_TIME`: Returns the number of seconds since the Unix epoch.
+    Time,
+    /// `SYS_ERRNO`: Returns the errno value of the last failed operation.
+    Errno,
+    /// `SYS_GET_CMDLINE`: Writes the command line to a buffer of the target.
+    GetCommandLine {
+        /// The address of the parameter block, which contains the address and the size of the buffer.
+        block: u32,
+    },
+    /// `SYS_HEAPINFO`: Writes the location of the heap and the stack to a block of the target.
+    HeapInfo {
+        /// The address of the block.
+        block: u32,
+    },
+    /// `SYS_EXIT` and `SYS_EXIT_EXTENDED`: The target exits.
+    Exit(ExitStatus),
+    /// `SYS_ELAPSED`: Writes the number of ticks since the start of the session to a block of the target.
+    Elapsed {
+        /// The address of the block.
+        block: u32,
+    },
+    /// `SYS_TICKFREQ`: Returns the frequency of the ticks of `SYS_ELAPSED`.
+    TickFrequency,
+    /// An operation which is not supported.
+    Unknown {
+        /// The operation number, from R0.
+        operation: u32,
+        /// The parameter, from R1.
+        parameter: u32,
+    },
+}
+
+impl SemihostingCommand {
+    /// Decodes the semihosting request of the core, if it is halted on a semihosting call.
+    ///
+    /// Returns `None` if the core isn't halted, or if the instruction at the program counter
+    /// isn't a semihosting call.
+    pub fn decode(core: &mut Core<'_>) -> Result<Option<Self>, Error> {
+        if semihosting_call_size(core)?.is_none() {
+            return Ok(None);
+        }
+
+        let operation: u32 = core.read_core_reg(RegisterId(0))?;
+        let parameter: u32 = core.read_core_reg(RegisterId(1))?;
+
+        Self::decode_operation(core, operation, parameter).map(Some)
+    }
+
+    fn decode_operation(
+        core: &mut Core<'_>,
+        operation: u32,
+        parameter: u32,
+    ) -> Result<Self, Error> {
+        let mut read_parameters = |count: usize| -> Result<Vec<u32>, Error> {
+            let mut parameters = vec![0u32; count];
+            core.read_32(parameter.into(), &mut parameters)?;
+            Ok(parameters)
+        };
+
+        Ok(match operation {
+            SYS_OPEN => {
+                let parameters = read_parameters(3)?;
+                SemihostingCommand::Open {
+                    path: read_string(core, parameters[0], parameters[2] as usize)?,
+                    mode: parameters[1],
+                }
+            }
+            SYS_CLOSE => SemihostingCommand::Close {
+                handle: read_parameters(1)?[0],
+            },
+            SYS_WRITEC => SemihostingCommand::WriteC {
+                character: core.read_word_8(parameter.into())?,
+            },
+            SYS_WRITE0 => SemihostingCommand::Write0 {
+                text: read_c_string(core, parameter)?,
+            },
+            SYS_WRITE => {
+                let parameters = read_parameters(3)?;
+                let mut data = vec![0u8; parameters[2] as usize];
+                core.read_8(parameters[1].into(), &mut data)?;
+
+                SemihostingCommand::Write {
+                    handle: parameters[0],
+                    data,
+                }
+            }
+            SYS_READ => {
+                let parameters = read_parameters(3)?;
+                SemihostingCommand::Read {
+                    handle: parameters[0],
+                    buffer: parameters[1],
+                    len: parameters[2],
+                }
+            }
+            SYS_READC => SemihostingCommand::ReadC,
+            SYS_ISERROR => SemihostingCommand::IsError {
+                status: read_parameters(1)?[0] as i32,
+            },
+            SYS_ISTTY => SemihostingCommand::IsTty {
+                handle: read_parameters(1)?[0],
+            },
+            SYS_SEEK => {
+                let parameters = read_parameters(2)?;
+                SemihostingCommand::Seek {
+                    handle: parameters[0],
+                    position: parameters[1],
+                }
+            }
+            SYS_FLEN => SemihostingCommand::FileLength {
+                handle: read_parameters(1)?[0],
+            },
+            SYS_REMOVE => {
+                let parameters = read_parameters(2)?;
+                SemihostingCommand::Remove {
+                    path: read_string(core, parameters[0], parameters[1] as usize)?,
+                }
+            }
+            SYS_RENAME => {
+                let parameters = read_parameters(4)?;
+                SemihostingCommand::Rename {
+                    from: read_string(core, parameters[0], parameters[1] as usize)?,
+                    to: read_string(core, parameters[2], parameters[3] as usize)?,
+                }
+            }
+            SYS_CLOCK => SemihostingCommand::Clock,
+            SYS_TIME => SemihostingCommand::Time,
+            SYS_ERRNO => SemihostingCommand::Errno,
+            SYS_GET_CMDLINE => SemihostingCommand::GetCommandLine { block: parameter },
+            SYS_HEAPINFO => SemihostingCommand::HeapInfo { block: parameter },
+            // On 32-bit targets, the parameter of SYS_EXIT is the reason itself.
+            SYS_EXIT => SemihostingCommand::Exit(ExitStatus {
+                reason: parameter,
+                subcode: None,
+            }),
+            SYS_EXIT_EXTENDED => {
+                let parameters = read_parameters(2)?;
+                SemihostingCommand::Exit(ExitStatus {
+                    reason: parameters[0],
+                    subcode: Some(parameters[1]),
+                })
+            }
+            SYS_ELAPSED => SemihostingCommand::Elapsed { block: parameter },
+            SYS_TICKFREQ => SemihostingCommand::TickFrequency,
+            operation => SemihostingCommand::Unknown {
+                operation,
+                parameter,
+            },
+        })
+    }
+}
+
+/// The exit status reported by the target with `SYS_EXIT` or `SYS_EXIT_EXTENDED`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExitStatus {
+    /// The reason of the exit, e.g. [`ADP_STOPPED_APPLICATION_EXIT`].
+    pub reason: u32,
+    /// The exit code, which is only reported with `SYS_EXIT_EXTENDED`.
+    pub subcode: Option<u32>,
+}
+
+impl ExitStatus {
+    /// Returns `true` if the target exited regularly with exit code 0.
+    pub fn success(&self) -> bool {
+        self.code() == 0
+    }
+
+    /// Returns the exit code of the target.
+    ///
+    /// Exits for other reasons than [`ADP_STOPPED_APPLICATION_EXIT`], e.g. after a runtime error,
+    /// are reported with exit code 1.
+    pub fn code(&self) -> i32 {
+        if self.reason == ADP_STOPPED_APPLICATION_EXIT {
+            self.subcode.unwrap_or(0) as i32
+        } else {
+            1
+        }
+    }
+}
+
+/// A semihosting call the core is halted on.
+///
+/// This is for hosts which service some operations themselves, e.g. a gdb server which forwards
+/// them to gdb, and use a [`SemihostingHost`] for the others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SemihostingCall {
+    /// The operation number, from R0.
+    pub operation: u32,
+    /// The parameter, from R1, which is the address of the parameter block for most operations.
+    pub parameter: u32,
+    /// The size of the semihosting call instruction.
+    size: u32,
+}
+
+impl SemihostingCall {
+    /// Returns the semihosting call of the core, if it is halted on one.
+    pub fn detect(core: &mut Core<'_>) -> Result<Option<Self>, Error> {
+        let size = match semihosting_call_size(core)? {
+            Some(size) => size,
+            None => return Ok(None),
+        };
+
+        Ok(Some(Self {
+            operation: core.read_core_reg(RegisterId(0))?,
+            parameter: core.read_core_reg(RegisterId(1))?,
+            size,
+        }))
+    }
+
+    /// Reads `count` words of the parameter block.
+    pub fn read_parameters(&self, core: &mut Core<'_>, count: usize) -> Result<Vec<u32>, Error> {
+        let mut parameters = vec![0u32; count];
+        core.read_32(self.parameter.into(), &mut parameters)?;
+        Ok(parameters)
+    }
+
+    /// Finishes the call with `result`, which is written to R0, and moves the program counter past
+    /// the semihosting call, so the core can be resumed.
+    pub fn complete(&self, core: &mut Core<'_>, result: i32) -> Result<(), Error> {
+        core.write_core_reg(RegisterId(0), result as u32)?;
+
+        let pc_id = core.registers().program_counter().id;
+        let pc: u32 = core.read_core_reg(pc_id)?;
+        core.write_core_reg(pc_id, pc + self.size)
+    }
+}
+
+/// Configures which semihosting operations the [`SemihostingHost`] services.
+///
+/// Operations which are not allowed fail on the target, with `EPERM` as errno.
+///
+/// # Example
+///
+/// ```
+/// use probe_rs::architecture::arm::semihosting::SemihostingPolicy;
+///
+/// let policy = SemihostingPolicy::new()
+///     .allow_file_io("target/semihosting")
+///     .command_line("firmware --selftest");
+/// ```
+#[derive(Debug, Clone)]
+pub struct SemihostingPolicy {
+    console: bool,
+    file_root: Option<PathBuf>,
+    exit: bool,
+    command_line: String,
+}
+
+impl Default for SemihostingPolicy {
+    fn default() -> Self {
+        Self {
+            console: true,
+            file_root: None,
+            exit: true,
+            command_line: String::new(),
+        }
+    }
+}
+
+impl SemihostingPolicy {
+    /// Creates the default policy, which allows console I/O, the time and exit operations,
+    /// but no access to the files of the host.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow or deny reading from and writing to the console of the host.
+    #[must_use]
+    pub fn allow_console(self, allow: bool) -> Self {
+        Self {
+            console: allow,
+            ..self
+        }
+    }
+
+    /// Allow access to the files in the directory `root`.
+    ///
+    /// The paths used by the target are relative to `root`, and can't leave it.
+    #[must_use]
+    pub fn allow_file_io(self, root: impl Into<PathBuf>) -> Self {
+        Self {
+            file_root: Some(root.into()),
+            ..self
+        }
+    }
+
+    /// Allow or deny the target to report its exit.
+    ///
+    /// If exits are denied, `SYS_EXIT` fails on the target and it keeps running.
+    #[must_use]
+    pub fn allow_exit(self, allow: bool) -> Self {
+        Self {
+            exit: allow,
+            ..self
+        }
+    }
+
+    /// The command line returned by `SYS_GET_CMDLINE`, which is empty by default.
+    #[must_use]
+    pub fn command_line(self, command_line: impl Into<String>) -> Self {
+        Self {
+            command_line: command_line.into(),
+            ..self
+        }
+    }
+
+    /// Returns `true` if the policy allows the operation.
+    pub fn allows(&self, command: &SemihostingCommand) -> bool {
+        match command {
+            SemihostingCommand::Open { path, .. } if path == ":tt" => self.console,
+            SemihostingCommand::WriteC { .. }
+            | SemihostingCommand::Write0 { .. }
+            | SemihostingCommand::ReadC => self.console,
+            SemihostingCommand::Open { .. }
+            | SemihostingCommand::Remove { .. }
+            | SemihostingCommand::Rename { .. } => self.file_root.is_some(),
+            SemihostingCommand::Exit(_) => self.exit,
+            _ => true,
+        }
+    }
+
+    /// Resolves a path of the target inside of the root directory for file I/O.
+    fn resolve(&self, path: &str) -> Option<PathBuf> {
+        let root = self.file_root.as_ref()?;
+        let path = Path::new(path);
+
+        let is_contained = path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+
+        is_contained.then(|| root.join(path))
+    }
+}
+
+/// What [`SemihostingHost::handle`] did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SemihostingEvent {
+    /// The operation was serviced, or denied, and the core can be resumed.
+    Serviced(SemihostingCommand),
+    /// The target exited. The core stays halted on the semihosting call.
+    Exit(ExitStatus),
+}
+
+/// A file opened by the target.
+#[derive(Debug)]
+enum HostFile {
+    Stdin,
+    Stdout,
+    Stderr,
+    File(File),
+}
+
+/// Services the semihosting requests of a target.
+#[derive(Debug)]
+pub struct SemihostingHost {
+    policy: SemihostingPolicy,
+    files: HashMap<u32, HostFile>,
+    next_handle: u32,
+    errno: i32,
+    start: Instant,
+}
+
+impl SemihostingHost {
+    /// Creates a host which services the operations allowed by the `policy`.
+    pub fn new(policy: SemihostingPolicy) -> Self {
+        Self {
+            policy,
+            files: HashMap::new(),
+            // Handle 0 is not used, as some C libraries treat it as an error.
+            next_handle: 1,
+            errno: 0,
+            start: Instant::now(),
+        }
+    }
+
+    /// Services the semihosting request of the core, if it is halted on a semihosting call.
+    ///
+    /// After the operation is serviced, the result is written to R0 and the program counter is
+    /// moved past the semihosting call, so the core can be resumed. Returns `None` if the core
+    /// is not halted on a semihosting call, e.g. because it hit a regular breakpoint.
+    pub fn handle(&mut self, core: &mut Core<'_>) -> Result<Option<SemihostingEvent>, Error> {
+        let call = match SemihostingCall::detect(core)? {
+            Some(call) => call,
+            None => return Ok(None),
+        };
+
+        let command = SemihostingCommand::decode_operation(core, call.operation, call.parameter)?;
+
+        log::debug!("Semihosting request: {:?}", command);
+
+        let result = if self.policy.allows(&command) {
+            if let SemihostingCommand::Exit(status) = command {
+                return Ok(Some(SemihostingEvent::Exit(status)));
+            }
+
+            self.service(core, &command)?
+        } else {
+            log::warn!("Semihosting operation denied by the policy: {:?}", command);
+            self.errno = EPERM;
+            -1
+        };
+
+        call.complete(core, result)?;
+
+        Ok(Some(SemihostingEvent::Serviced(command)))
+    }
+
+    /// Services an allowed operation, and returns the value for R0.
+    fn service(&mut self, core: &mut Core<'_>, command: &SemihostingCommand) -> Result<i32, Error> {
+        let result = match command {
+            SemihostingCommand::Open { path, mode } => self.open(path, *mode),
+            SemihostingCommand::Close { handle } => match self.files.remove(handle) {
+                Some(_) => Ok(0),
+                None => Err(EBADF),
+            },
+            SemihostingCommand::WriteC { character } => {
+                write_console(&mut io::stdout(), &[*character]).map(|_| 0)
+            }
+            SemihostingCommand::Write0 { text } => {
+                write_console(&mut io::stdout(), text).map(|_| 0)
+            }
+            SemihostingCommand::Write { handle, data } => self.write(*handle, data),
+            SemihostingCommand::Read {
+                handle,
+                buffer,
+                len,
+            } => {
+                let mut data = vec![0u8; *len as usize];
+                match self.read(*handle, &mut data) {
+                    Ok(count) => {
+                        core.write_8((*buffer).into(), &data[..count])?;
+                        // The number of bytes which were not read.
+                        Ok((*len as usize - count) as i32)
+                    }
+                    Err(errno) => Err(errno),
+                }
+            }
+            SemihostingCommand::ReadC => {
+                let mut character = [0u8];
+                io::stdin()
+                    .read_exact(&mut character)
+                    .map(|_| character[0].into())
+                    .map_err(|error| errno_of(&error))
+            }
+            SemihostingCommand::IsError { status } => Ok((*status < 0).into()),
+            SemihostingCommand::IsTty { handle } => match self.files.get(handle) {
+                Some(HostFile::File(_)) => Ok(0),
+                Some(_) => Ok(1),
+                None => Err(EBADF),
+            },
+            SemihostingCommand::Seek { handle, position } => match self.files.get_mut(handle) {
+                Some(HostFile::File(file)) => file
+                    .seek(SeekFrom::Start((*position).into()))
+                    .map(|_| 0)
+                    .map_err(|error| errno_of(&error)),
+                Some(_) => Err(EINVAL),
+                None => Err(EBADF),
+            },
+            SemihostingCommand::FileLength { handle } => match self.files.get(handle) {
+                Some(HostFile::File(file)) => file
+                    .metadata()
+                    .map(|metadata| metadata.len() as i32)
+                    .map_err(|error| errno_of(&error)),
+                Some(_) => Err(EINVAL),
+                None => Err(EBADF),
+            },
+            SemihostingCommand::Remove { path } => match self.policy.resolve(path) {
+                Some(path) => std::fs::remove_file(path)
+                    .map(|_| 0)
+                    .map_err(|error| errno_of(&error)),
+                None => Err(EPERM),
+            },
+            SemihostingCommand::Rename { from, to } => {
+                match (self.policy.resolve(from), self.policy.resolve(to)) {
+                    (Some(from), Some(to)) => std::fs::rename(from, to)
+                        .map(|_| 0)
+                        .map_err(|error| errno_of(&error)),
+                    _ => Err(EPERM),
+                }
+            }
+            SemihostingCommand::Clock => Ok((self.start.elapsed().as_millis() / 10) as i32),
+            SemihostingCommand::Time => Ok(SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map(|time| time.as_secs() as i32)
+                .unwrap_or(0)),
+            SemihostingCommand::Errno => Ok(self.errno),
+            SemihostingCommand::GetCommandLine { block } => {
+                let mut parameters = [0u32; 2];
+                core.read_32((*block).into(), &mut parameters)?;
+                let [buffer, size] = parameters;
+
+                let command_line = self.policy.command_line.as_bytes();
+                if command_line.len() < size as usize {
+                    core.write_8(buffer.into(), command_line)?;
+                    core.write_word_8(buffer as u64 + command_line.len() as u64, 0)?;
+                    core.write_word_32(*block as u64 + 4, command_line.len() as u32)?;
+                    Ok(0)
+                } else {
+                    Err(EINVAL)
+                }
+            }
+            SemihostingCommand::HeapInfo { block } => {
+                // Zeros let the C library of the target use its defaults.
+                let pointer = core.read_word_32((*block).into())?;
+                core.write_32(pointer.into(), &[0; 4])?;
+                Ok(0)
+            }
+            SemihostingCommand::Elapsed { block } => {
+                let ticks = self.start.elapsed().as_micros() as u64;
+                core.write_32((*block).into(), &[ticks as u32, (ticks >> 32) as u32])?;
+                Ok(0)
+            }
+            SemihostingCommand::TickFrequency => Ok(1_000_000),
+            SemihostingCommand::Exit(_) => Ok(0),
+            SemihostingCommand::Unknown { operation, .. } => {
+                log::warn!("Unsupported semihosting operation {:#x}", operation);
+                Err(EINVAL)
+            }
+        };
+
+        Ok(match result {
+            Ok(value) => value,
+            Err(errno) => {
+                self.errno = errno;
+                -1
+            }
+        })
+    }
+
+    fn open(&mut self, path: &str, mode: u32) -> Result<i32, i32> {
+        let file = if path == ":tt" {
+            match mode {
+                0..=3 => HostFile::Stdin,
+                4..=7 => HostFile::Stdout,
+                _ => HostFile::Stderr,
+            }
+        } else {
+            let resolved = self.policy.resolve(path).ok_or(EPERM)?;
+
+            let mut options = OpenOptions::new();
+            // The modes of fopen, with and without the `b` flag.
+            match mode / 2 {
+                0 => options.read(true),
+                1 => options.read(true).write(true),
+                2 => options.write(true).create(true).truncate(true),
+                3 => options.read(true).write(true).create(true).truncate(true),
+                4 => options.append(true).create(true),
+                5 => options.read(true).append(true).create(true),
+                _ => return Err(EINVAL),
+            };
+
+            HostFile::File(options.open(resolved).map_err(|error| errno_of(&error))?)
+        };
+
+        let handle = self.next_handle;
+        self.next_handle += 1;
+        self.files.insert(handle, file);
+
+        Ok(handle as i32)
+    }
+
+    /// Returns the number of bytes which were not written.
+    fn write(&mut self, handle: u32, data: &[u8]) -> Result<i32, i32> {
+        let result = match self.files.get_mut(&handle) {
+            Some(HostFile::Stdout) if self.policy.console => write_console(&mut io::stdout(), data),
+            Some(HostFile::Stderr) if self.policy.console => write_console(&mut io::stderr(), data),
+            Some(HostFile::File(file)) => file.write_all(data).map_err(|error| errno_of(&error)),
+            Some(HostFile::Stdin) => Err(EBADF),
+            Some(_) => Err(EPERM),
+            None => Err(EBADF),
+        };
+
+        result.map(|_| 0)
+    }
+
+    /// Returns the number of bytes which were read.
+    fn read(&mut self, handle: u32, data: &mut [u8]) -> Result<usize, i32> {
+        let result = match self.files.get_mut(&handle) {
+            Some(HostFile::Stdin) if self.policy.console => io::stdin().read(data),
+            Some(HostFile::File(file)) => file.read(data),
+            Some(HostFile::Stdout | HostFile::Stderr) => return Err(EBADF),
+            Some(_) => return Err(EPERM),
+            None => return Err(EBADF),
+        };
+
+        result.map_err(|error| errno_of(&error))
+    }
+}
+
+/// Returns the size of the semihosting call instruction at the program counter,
+/// or `None` if the core is not halted on a semihosting call.
+fn semihosting_call_size(core: &mut Core<'_>) -> Result<Option<u32>, Error> {
+    if !matches!(core.status()?, CoreStatus::Halted(_)) {
+        return Ok(None);
+    }
+
+    let pc: u64 = core.read_core_reg(core.registers().program_counter().id)?;
+
+    match core.instruction_set()? {
+        InstructionSet::Thumb2 => {
+            let mut instruction = [0u8; 2];
+            core.read_8(pc, &mut instruction)?;
+
+            let instruction = u16::from_le_bytes(instruction);
+            Ok((instruction == THUMB_BKPT || instruction == THUMB_HLT).then(|| 2))
+        }
+        InstructionSet::A32 => {
+            let instruction = core.read_word_32(pc)?;
+            Ok((instruction == ARM_HLT).then(|| 4))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn write_console(console: &mut impl Write, data: &[u8]) -> Result<(), i32> {
+    console
+        .write_all(data)
+        .and_then(|_| console.flush())
+        .map_err(|error| errno_of(&error))
+}
+
+fn errno_of(error: &io::Error) -> i32 {
+    error.raw_os_error().unwrap_or(EIO)
+}
+
+/// Reads a string of the given length from the target.
+fn read_string(core: &mut Core<'_>, address: u32, len: usize) -> Result<String, Error> {
+    let mut data = vec![0u8; len.min(MAX_STRING_LEN)];
+    core.read_8(address.into(), &mut data)?;
+
+    Ok(String::from_utf8_lossy(&data).into_owned())
+}
+
+/// Reads a NUL terminated string from the target.
+fn read_c_string(core: &mut Core<'_>, address: u32) -> Result<Vec<u8>, Error> {
+    let mut text = Vec::new();
+    let mut address = address as u64;
+
+    while text.len() < MAX_STRING_LEN {
+        let mut chunk = [0u8; 32];
+        core.read_8(address, &mut chunk)?;
+
+        match chunk.iter().position(|&b| b == 0) {
+            Some(end) => {
+                text.extend_from_slice(&chunk[..end]);
+                return Ok(text);
+            }
+            None => text.extend_from_slice(&chunk),
+        }
+
+        address += chunk.len() as u64;
+    }
+
+    Ok(text)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn exit_status_codes() {
+        let exit = |reason, subcode| ExitStatus { reason, subcode };
+
+        assert!(exit(ADP_STOPPED_APPLICATION_EXIT, None).success());
+        assert_eq!(exit(ADP_STOPPED_APPLICATION_EXIT, Some(3)).code(), 3);
+        // ADP_Stopped_RunTimeErrorUnknown
+        assert_eq!(exit(0x20023, None).code(), 1);
+    }
+
+    #[test]
+    fn policy_keeps_paths_inside_the_root() {
+        let policy = SemihostingPolicy::new().allow_file_io("/tmp/root");
+
+        assert_eq!(
+            policy.resolve("log/output.txt"),
+            Some(PathBuf::from("/tmp/root/log/output.txt"))
+        );
+        assert_eq!(policy.resolve("../secret"), None);
+        assert_eq!(policy.resolve("/etc/passwd"), None);
+
+        assert_eq!(SemihostingPolicy::new().resolve("output.txt"), None);
+    }
+
+    #[test]
+    fn policy_denies_file_io_by_default() {
+        let policy = SemihostingPolicy::new();
+
+        assert!(policy.allows(&SemihostingCommand::WriteC { character: b'a' }));
+        assert!(policy.allows(&SemihostingCommand::Open {
+            path: ":tt".to_string(),
+            mode: 4
+        }));
+        assert!(!policy.allows(&SemihostingCommand::Open {
+            path: "output.txt".to_string(),
+            mode: 4
+        }));
+    }
+}
@@ -0,0 +1,180 @@
+//! Sequences for the Microchip SAM D and SAM E series.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::{reset_hardware_deassert, ArmDebugSequence};
+use crate::architecture::arm::{
+    ap::MemoryAp, communication_interface::DapProbe, ArmProbeInterface, Pins,
+};
+use crate::Memory;
+
+/// An error which occurred while handling the Device Service Unit (DSU) of a SAM chip.
+#[derive(thiserror::Error, Debug)]
+pub enum AtSamError {
+    /// The chip is protected by its security bit and may not be erased.
+    #[error("The chip is protected by its security bit. It has to be erased to remove the protection, which requires the erase_all permission.")]
+    Protected,
+    /// The chip erase did not finish in time.
+    #[error("Timeout while waiting for the chip erase")]
+    Timeout,
+    /// The DSU reported an error during the chip erase.
+    #[error("The chip erase failed, the DSU reported the status {0:#04x}")]
+    ChipEraseFailed(u8),
+    /// The chip is still protected after the chip erase.
+    #[error("The chip is still protected after the chip erase")]
+    StillProtected,
+}
+
+/// The sequence handle for the SAM D and SAM E series, which have a Device Service Unit (DSU).
+///
+/// A chip which is protected by its security bit is recovered by the chip erase of the DSU,
+/// which requires the `erase_all` permission. The CPU reset extension of the DSU is used to
+/// keep the CPU in reset after a hardware reset, until the debugger is ready.
+pub struct AtSAM(());
+
+impl AtSAM {
+    const DSU_CTRL: u64 = 0x4100_2000;
+    const DSU_STATUSA: u64 = 0x4100_2001;
+    const DSU_STATUSB: u64 = 0x4100_2002;
+
+    const CTRL_CE: u8 = 1 << 4;
+
+    const STATUSA_DONE: u8 = 1 << 0;
+    const STATUSA_CRSTEXT: u8 = 1 << 1;
+    const STATUSA_BERR: u8 = 1 << 2;
+    const STATUSA_FAIL: u8 = 1 << 3;
+    const STATUSA_PERR: u8 = 1 << 4;
+
+    const STATUSB_PROT: u8 = 1 << 0;
+
+    /// The time it takes at most to erase the whole chip.
+    const CHIP_ERASE_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Create a new sequence handle for the SAM D and SAM E series.
+    pub fn create() -> Arc<dyn ArmDebugSequence> {
+        Arc::new(Self(()))
+    }
+
+    fn is_protected(&self, memory: &mut Memory) -> Result<bool, crate::Error> {
+        Ok(memory.read_word_8(Self::DSU_STATUSB)? & Self::STATUSB_PROT != 0)
+    }
+
+    /// Erase the flash and the RAM with the DSU, which also clears the security bit.
+    fn chip_erase(&self, memory: &mut Memory) -> Result<(), crate::Error> {
+        // Clear the previous status, but keep the CPU reset extension.
+        memory.write_word_8(
+            Self::DSU_STATUSA,
+            Self::STATUSA_DONE | Self::STATUSA_BERR | Self::STATUSA_FAIL | Self::STATUSA_PERR,
+        )?;
+        memory.write_word_8(Self::DSU_CTRL, Self::CTRL_CE)?;
+
+        let start = Instant::now();
+        let status = loop {
+            let status = memory.read_word_8(Self::DSU_STATUSA)?;
+            if status & Self::STATUSA_DONE != 0 {
+                break status;
+            }
+            if start.elapsed() > Self::CHIP_ERASE_TIMEOUT {
+                return Err(crate::Error::architecture_specific(AtSamError::Timeout));
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        };
+
+        if status & (Self::STATUSA_FAIL | Self::STATUSA_PERR) != 0 {
+            return Err(crate::Error::architecture_specific(
+                AtSamError::ChipEraseFailed(status),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Reset the chip with the CPU reset extension, which applies the new security bit.
+    fn reset_with_extension(&self, memory: &mut Memory) -> Result<(), crate::Error> {
+        let mut pins = Pins(0);
+        pins.set_nreset(true);
+        pins.set_swclk_tck(true);
+
+        memory.get_arm_probe().swj_pins(0, pins.0 as u32, 0)?;
+        std::thread::sleep(Duration::from_millis(10));
+        self.reset_hardware_deassert(memory)
+    }
+}
+
+impl ArmDebugSequence for AtSAM {
+    fn reset_hardware_assert(&self, interface: &mut dyn DapProbe) -> Result<(), crate::Error> {
+        // Keeping SWCLK low while the reset is released enables the CPU reset extension.
+        let mut pins = Pins(0);
+        pins.set_nreset(true);
+        pins.set_swclk_tck(true);
+
+        let _ = interface.swj_pins(0, pins.0 as u32, 0)?;
+
+        Ok(())
+    }
+
+    fn reset_hardware_deassert(&self, memory: &mut Memory) -> Result<(), crate::Error> {
+        let mut n_reset = Pins(0);
+        n_reset.set_nreset(true);
+        let mut select = n_reset;
+        select.set_swclk_tck(true);
+
+        memory
+            .get_arm_probe()
+            .swj_pins(n_reset.0 as u32, select.0 as u32, 0)?;
+        reset_hardware_deassert(memory)?;
+
+        // Release the CPU from the reset extension.
+        if memory.read_word_8(Self::DSU_STATUSA)? & Self::STATUSA_CRSTEXT != 0 {
+            memory.write_word_8(Self::DSU_STATUSA, Self::STATUSA_CRSTEXT)?;
+        }
+
+        Ok(())
+    }
+
+    fn debug_device_unlock(
+        &self,
+        interface: &mut Box<dyn ArmProbeInterface>,
+        default_ap: MemoryAp,
+        permissions: &crate::Permissions,
+    ) -> Result<(), crate::Error> {
+        let mut memory = interface.memory_interface(default_ap)?;
+
+        if !self.is_protected(&mut memory)? {
+            return Ok(());
+        }
+
+        log::warn!("The chip is protected by its security bit. It has to be erased to unlock it.");
+        if permissions.erase_all().is_err() {
+            return Err(crate::Error::architecture_specific(AtSamError::Protected));
+        }
+
+        self.chip_erase(&mut memory)?;
+        self.reset_with_extension(&mut memory)?;
+
+        if self.is_protected(&mut memory)? {
+            return Err(crate::Error::architecture_specific(
+                AtSamError::StillProtected,
+            ));
+        }
+
+        log::info!("Erased the chip to remove the protection");
+
+        Ok(())
+    }
+
+    fn debug_erase_all(
+        &self,
+        interface: &mut Box<dyn ArmProbeInterface>,
+        default_ap: MemoryAp,
+    ) -> Result<bool, crate::Error> {
+        let mut memory = interface.memory_interface(default_ap)?;
+
+        log::info!("Erasing all memory via the DSU");
+        self.chip_erase(&mut memory)?;
+        self.reset_with_extension(&mut memory)?;
+
+        Ok(true)
+    }
+}
@@ -0,0 +1,217 @@
+//! Sequences for the NXP Kinetis series.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::ArmDebugSequence;
+use crate::architecture::arm::ap::MemoryAp;
+use crate::architecture::arm::{
+    communication_interface::Initialized, ApAddress, ArmCommunicationInterface, ArmProbeInterface,
+    DapAccess,
+};
+
+/// An error which occurred while unsecuring a Kinetis chip.
+#[derive(thiserror::Error, Debug)]
+pub enum KinetisError {
+    /// The chip is secured and may not be erased.
+    #[error("The chip is secured. It has to be mass erased to unsecure it, which requires the erase_all permission.")]
+    Secured,
+    /// The chip is secured and the mass erase is disabled in the flash configuration field.
+    #[error("The chip is secured and its mass erase is disabled, so it can not be unsecured.")]
+    MassEraseDisabled,
+    /// The mass erase did not finish in time.
+    #[error("Timeout while waiting for the {0}")]
+    Timeout(&'static str),
+    /// The chip is still secured after the mass erase.
+    #[error("The chip is still secured after the mass erase")]
+    StillSecured,
+}
+
+/// The sequence handle for the Kinetis series.
+///
+/// A secured chip is unsecured by a mass erase through the MDM-AP, which requires the
+/// `erase_all` permission.
+pub struct Kinetis(());
+
+impl Kinetis {
+    /// The access port number of the MDM-AP.
+    const MDM_AP: u8 = 1;
+    /// The IDRs of the MDM-AP.
+    const MDM_AP_IDR: [u32; 2] = [0x001C_0000, 0x001C_0020];
+
+    const STATUS: u8 = 0x00;
+    const CONTROL: u8 = 0x04;
+    const IDR: u8 = 0xFC;
+
+    const STATUS_MASS_ERASE_ACK: u32 = 1 << 0;
+    const STATUS_FLASH_READY: u32 = 1 << 1;
+    const STATUS_SYSTEM_SECURITY: u32 = 1 << 2;
+    const STATUS_MASS_ERASE_ENABLE: u32 = 1 << 5;
+
+    const CONTROL_MASS_ERASE_IN_PROGRESS: u32 = 1 << 0;
+    const CONTROL_SYSTEM_RESET_REQUEST: u32 = 1 << 3;
+
+    /// The time it takes at most to erase the whole chip.
+    const MASS_ERASE_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Create a new sequence handle for the Kinetis series.
+    pub fn create() -> Arc<dyn ArmDebugSequence> {
+        Arc::new(Self(()))
+    }
+
+    /// Returns the address of the MDM-AP, or `None` if the chip does not have one.
+    fn mdm_ap(
+        &self,
+        interface: &mut ArmCommunicationInterface<Initialized>,
+        default_ap: ApAddress,
+    ) -> Result<Option<ApAddress>, crate::Error> {
+        let mdm_ap = ApAddress {
+            ap: Self::MDM_AP,
+            ..default_ap
+        };
+        let idr = interface.read_raw_ap_register(mdm_ap, Self::IDR)?;
+
+        if Self::MDM_AP_IDR.contains(&idr) {
+            Ok(Some(mdm_ap))
+        } else {
+            log::debug!(
+                "AP {} is not an MDM-AP, its IDR is {:#010x}",
+                Self::MDM_AP,
+                idr
+            );
+            Ok(None)
+        }
+    }
+
+    fn wait_for(
+        &self,
+        interface: &mut ArmCommunicationInterface<Initialized>,
+        mdm_ap: ApAddress,
+        description: &'static str,
+        mut condition: impl FnMut(u32, u32) -> bool,
+    ) -> Result<(), crate::Error> {
+        let start = Instant::now();
+        loop {
+            let status = interface.read_raw_ap_register(mdm_ap, Self::STATUS)?;
+            let control = interface.read_raw_ap_register(mdm_ap, Self::CONTROL)?;
+            if condition(status, control) {
+                return Ok(());
+            }
+            if start.elapsed() > Self::MASS_ERASE_TIMEOUT {
+                return Err(crate::Error::architecture_specific(KinetisError::Timeout(
+                    description,
+                )));
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// Erase the whole flash through the MDM-AP, while the system is held in reset.
+    fn mass_erase(
+        &self,
+        interface: &mut ArmCommunicationInterface<Initialized>,
+        mdm_ap: ApAddress,
+    ) -> Result<(), crate::Error> {
+        self.wait_for(interface, mdm_ap, "flash to be ready", |status, _| {
+            status & Self::STATUS_FLASH_READY != 0
+        })?;
+
+        let status = interface.read_raw_ap_register(mdm_ap, Self::STATUS)?;
+        if status & Self::STATUS_MASS_ERASE_ENABLE == 0 {
+            return Err(crate::Error::architecture_specific(
+                KinetisError::MassEraseDisabled,
+            ));
+        }
+
+        // The core must not run while the flash is erased.
+        interface.write_raw_ap_register(
+            mdm_ap,
+            Self::CONTROL,
+            Self::CONTROL_SYSTEM_RESET_REQUEST,
+        )?;
+        interface.write_raw_ap_register(
+            mdm_ap,
+            Self::CONTROL,
+            Self::CONTROL_SYSTEM_RESET_REQUEST | Self::CONTROL_MASS_ERASE_IN_PROGRESS,
+        )?;
+
+        self.wait_for(interface, mdm_ap, "mass erase to start", |status, _| {
+            status & Self::STATUS_MASS_ERASE_ACK != 0
+        })?;
+        self.wait_for(interface, mdm_ap, "mass erase to finish", |_, control| {
+            control & Self::CONTROL_MASS_ERASE_IN_PROGRESS == 0
+        })?;
+
+        interface.write_raw_ap_register(mdm_ap, Self::CONTROL, 0)?;
+
+        Ok(())
+    }
+
+    fn is_secured(
+        &self,
+        interface: &mut ArmCommunicationInterface<Initialized>,
+        mdm_ap: ApAddress,
+    ) -> Result<bool, crate::Error> {
+        let status = interface.read_raw_ap_register(mdm_ap, Self::STATUS)?;
+        Ok(status & Self::STATUS_SYSTEM_SECURITY != 0)
+    }
+}
+
+impl ArmDebugSequence for Kinetis {
+    fn debug_device_unlock(
+        &self,
+        interface: &mut Box<dyn ArmProbeInterface>,
+        default_ap: MemoryAp,
+        permissions: &crate::Permissions,
+    ) -> Result<(), crate::Error> {
+        let mut memory = interface.memory_interface(default_ap)?;
+        let default_ap = memory.get_ap();
+        let interface = memory.get_arm_interface()?;
+
+        let mdm_ap = match self.mdm_ap(interface, default_ap)? {
+            Some(mdm_ap) => mdm_ap,
+            None => return Ok(()),
+        };
+
+        if !self.is_secured(interface, mdm_ap)? {
+            return Ok(());
+        }
+
+        log::warn!("The chip is secured. It has to be mass erased to unsecure it.");
+        if permissions.erase_all().is_err() {
+            return Err(crate::Error::architecture_specific(KinetisError::Secured));
+        }
+
+        self.mass_erase(interface, mdm_ap)?;
+
+        if self.is_secured(interface, mdm_ap)? {
+            return Err(crate::Error::architecture_specific(
+                KinetisError::StillSecured,
+            ));
+        }
+
+        log::info!("Mass erased the chip to unsecure it");
+
+        Ok(())
+    }
+
+    fn debug_erase_all(
+        &self,
+        interface: &mut Box<dyn ArmProbeInterface>,
+        default_ap: MemoryAp,
+    ) -> Result<bool, crate::Error> {
+        let mut memory = interface.memory_interface(default_ap)?;
+        let default_ap = memory.get_ap();
+        let interface = memory.get_arm_interface()?;
+
+        let mdm_ap = match self.mdm_ap(interface, default_ap)? {
+            Some(mdm_ap) => mdm_ap,
+            None => return Ok(false),
+        };
+
+        log::info!("Erasing all memory via the MDM-AP");
+        self.mass_erase(interface, mdm_ap)?;
+
+        Ok(true)
+    }
+}
@@ -1,8 +1,17 @@
 //! Debug sequences to operate special requirements ARM targets.
 
+pub mod atsam;
+pub mod kinetis;
+mod nordic;
+pub mod nrf52;
 pub mod nrf53;
+pub mod nrf91;
 pub mod nxp;
+pub mod pack;
+pub mod rp2040;
+pub mod silabs;
 pub mod stm32;
+pub mod ti;
 
 use std::{
     sync::Arc,
@@ -14,7 +23,7 @@ use probe_rs_target::CoreType;
 
 use crate::architecture::arm::core::armv7a_debug_regs::Armv7DebugRegister;
 use crate::{
-    architecture::arm::{ArmProbeInterface, DapError},
+    architecture::arm::{ArmProbeInterface, DapAccess, DapError},
     core::MemoryMappedRegister,
     DebugProbeError, Memory,
 };
@@ -346,6 +355,32 @@ fn cortex_m_reset_catch_set(core: &mut Memory) -> Result<(), crate::Error> {
     Ok(())
 }
 
+/// Release the nRST line, and wait until the target has released it as well.
+fn reset_hardware_deassert(memory: &mut Memory) -> Result<(), crate::Error> {
+    let interface = memory.get_arm_probe();
+
+    let mut n_reset = Pins(0);
+    n_reset.set_nreset(true);
+    let n_reset = n_reset.0 as u32;
+
+    let can_read_pins = interface.swj_pins(n_reset, n_reset, 0)? != 0xffff_ffff;
+
+    if can_read_pins {
+        let start = Instant::now();
+
+        while start.elapsed() < Duration::from_secs(1) {
+            if Pins(interface.swj_pins(n_reset, n_reset, 0)? as u8).nreset() {
+                return Ok(());
+            }
+        }
+
+        Err(DebugProbeError::Timeout.into())
+    } else {
+        thread::sleep(Duration::from_millis(100));
+        Ok(())
+    }
+}
+
 /// ResetSystem for Cortex-M devices
 fn cortex_m_reset_system(interface: &mut Memory) -> Result<(), crate::Error> {
     use crate::architecture::arm::core::armv7m::{Aircr, Dhcsr};
@@ -410,28 +445,7 @@ pub trait ArmDebugSequence: Send + Sync {
     /// [ARM SVD Debug Description]: http://www.keil.com/pack/doc/cmsis/Pack/html/debug_description.html#resetHardwareDeassert
     #[doc(alias = "ResetHardwareDeassert")]
     fn reset_hardware_deassert(&self, memory: &mut Memory) -> Result<(), crate::Error> {
-        let interface = memory.get_arm_probe();
-
-        let mut n_reset = Pins(0);
-        n_reset.set_nreset(true);
-        let n_reset = n_reset.0 as u32;
-
-        let can_read_pins = interface.swj_pins(n_reset, n_reset, 0)? != 0xffff_ffff;
-
-        if can_read_pins {
-            let start = Instant::now();
-
-            while start.elapsed() < Duration::from_secs(1) {
-                if Pins(interface.swj_pins(n_reset, n_reset, 0)? as u8).nreset() {
-                    return Ok(());
-                }
-            }
-
-            Err(DebugProbeError::Timeout.into())
-        } else {
-            thread::sleep(Duration::from_millis(100));
-            Ok(())
-        }
+        reset_hardware_deassert(memory)
     }
 
     /// Prepare the target debug port for connection. This is based on the
@@ -564,7 +578,7 @@ pub trait ArmDebugSequence: Send + Sync {
     ) -> Result<(), crate::Error> {
         // Dispatch based on core type (Cortex-A vs M)
         match core_type {
-            CoreType::Armv7a => armv7a_core_start(core, debug_base),
+            CoreType::Armv7a | CoreType::Armv7r => armv7a_core_start(core, debug_base),
             CoreType::Armv8a => armv8a_core_start(core, debug_base, cti_base),
             CoreType::Armv6m | CoreType::Armv7m | CoreType::Armv7em | CoreType::Armv8m => {
                 cortex_m_core_start(core)
@@ -590,7 +604,7 @@ pub trait ArmDebugSequence: Send + Sync {
     ) -> Result<(), crate::Error> {
         // Dispatch based on core type (Cortex-A vs M)
         match core_type {
-            CoreType::Armv7a => armv7a_reset_catch_set(core, debug_base),
+            CoreType::Armv7a | CoreType::Armv7r => armv7a_reset_catch_set(core, debug_base),
             CoreType::Armv8a => armv8a_reset_catch_set(core, debug_base),
             CoreType::Armv6m | CoreType::Armv7m | CoreType::Armv7em | CoreType::Armv8m => {
                 cortex_m_reset_catch_set(core)
@@ -616,7 +630,7 @@ pub trait ArmDebugSequence: Send + Sync {
     ) -> Result<(), crate::Error> {
         // Dispatch based on core type (Cortex-A vs M)
         match core_type {
-            CoreType::Armv7a => armv7a_reset_catch_clear(core, debug_base),
+            CoreType::Armv7a | CoreType::Armv7r => armv7a_reset_catch_clear(core, debug_base),
             CoreType::Armv8a => armv8a_reset_catch_clear(core, debug_base),
             CoreType::Armv6m | CoreType::Armv7m | CoreType::Armv7em | CoreType::Armv8m => {
                 cortex_m_reset_catch_clear(core)
@@ -642,7 +656,7 @@ pub trait ArmDebugSequence: Send + Sync {
     ) -> Result<(), crate::Error> {
         // Dispatch based on core type (Cortex-A vs M)
         match core_type {
-            CoreType::Armv7a => armv7a_reset_system(interface, debug_base),
+            CoreType::Armv7a | CoreType::Armv7r => armv7a_reset_system(interface, debug_base),
             CoreType::Armv8a => armv8a_reset_system(interface, debug_base),
             CoreType::Armv6m | CoreType::Armv7m | CoreType::Armv7em | CoreType::Armv8m => {
                 cortex_m_reset_system(interface)
@@ -671,6 +685,19 @@ pub trait ArmDebugSequence: Send + Sync {
         Ok(())
     }
 
+    /// Erase all nonvolatile memory of the device with a vendor specific procedure,
+    /// e.g. by using a vendor specific access port.
+    ///
+    /// This is used by [`crate::flashing::erase_all`] instead of the chip erase routine of the
+    /// flash algorithms. Returns `Ok(false)` if the device has no such procedure, which is the default.
+    fn debug_erase_all(
+        &self,
+        _interface: &mut Box<dyn ArmProbeInterface>,
+        _default_ap: MemoryAp,
+    ) -> Result<bool, crate::Error> {
+        Ok(false)
+    }
+
     /// Executed before step or run command to support recovery from a lost target connection, e.g. after a low power mode.
     /// This is based on the `RecoverSupportStart` function from the [ARM SVD Debug Description].
     ///
@@ -681,6 +708,63 @@ pub trait ArmDebugSequence: Send + Sync {
         Ok(())
     }
 
+    /// Executed before the nonvolatile memory is erased or programmed, e.g. to disable a watchdog
+    /// or the write protection of the board.
+    ///
+    /// This is not part of the ARM SVD Debug Description, it is called `FlashStart` in the
+    /// debug sequences of a target description.
+    fn flash_start(
+        &self,
+        _interface: &mut Box<dyn ArmProbeInterface>,
+        _default_ap: MemoryAp,
+    ) -> Result<(), crate::Error> {
+        // Empty by default
+        Ok(())
+    }
+
+    /// Executed after attaching, to stop the hardware watchdogs of the target while its cores are
+    /// halted, where the chip supports it. It is skipped if the session was opened with
+    /// [`AttachOptions::keep_watchdogs_running`](crate::AttachOptions::keep_watchdogs_running).
+    ///
+    /// This is not part of the ARM SVD Debug Description, it is called `WatchdogSuspend` in the
+    /// debug sequences of a target description.
+    fn watchdog_suspend(
+        &self,
+        _interface: &mut Box<dyn ArmProbeInterface>,
+        _default_ap: MemoryAp,
+    ) -> Result<(), crate::Error> {
+        // Empty by default
+        Ok(())
+    }
+
+    /// Executed after the target has been configured for tracing, to set up the trace pins and clocks.
+    /// This is based on the `TraceStart` function from the [ARM SVD Debug Description].
+    ///
+    /// [ARM SVD Debug Description]: http://www.keil.com/pack/doc/cmsis/Pack/html/debug_description.html#traceStart
+    #[doc(alias = "TraceStart")]
+    fn trace_start(
+        &self,
+        _interface: &mut Box<dyn ArmProbeInterface>,
+        _default_ap: MemoryAp,
+    ) -> Result<(), crate::Error> {
+        // Empty by default
+        Ok(())
+    }
+
+    /// Executed when tracing is disabled, to release the trace pins.
+    /// This is based on the `TraceStop` function from the [ARM SVD Debug Description].
+    ///
+    /// [ARM SVD Debug Description]: http://www.keil.com/pack/doc/cmsis/Pack/html/debug_description.html#traceStop
+    #[doc(alias = "TraceStop")]
+    fn trace_stop(
+        &self,
+        _interface: &mut Box<dyn ArmProbeInterface>,
+        _default_ap: MemoryAp,
+    ) -> Result<(), crate::Error> {
+        // Empty by default
+        Ok(())
+    }
+
     /// Executed when the debugger session is disconnected from the core.
     ///
     /// This is based on the `DebugCoreStop` function from the [ARM SVD Debug Description].
@@ -694,4 +778,23 @@ pub trait ArmDebugSequence: Send + Sync {
         // Empty by default
         Ok(())
     }
+
+    /// Release the power-up requests of the debug port, so the target can enter its low power
+    /// modes again. This is based on the `DebugPortStop` function from the [ARM SVD Debug Description].
+    ///
+    /// It is executed when a session is closed, if it was opened with
+    /// [`AttachOptions::power_down_on_detach`](crate::AttachOptions::power_down_on_detach).
+    ///
+    /// [ARM SVD Debug Description]: http://www.keil.com/pack/doc/cmsis/Pack/html/debug_description.html#debugPortStop
+    #[doc(alias = "DebugPortStop")]
+    fn debug_port_stop(
+        &self,
+        interface: &mut Box<dyn ArmProbeInterface>,
+        dp: DpAddress,
+    ) -> Result<(), crate::Error> {
+        // Clear CDBGPWRUPREQ and CSYSPWRUPREQ
+        interface.write_raw_dp_register(dp, Ctrl::ADDRESS, 0)?;
+
+        Ok(())
+    }
 }
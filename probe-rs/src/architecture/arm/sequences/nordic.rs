@@ -0,0 +1,91 @@
+//! Helpers for the CTRL-AP of Nordic chips, which is used to erase and reset locked chips,
+//! and for their watchdog.
+
+use std::time::{Duration, Instant};
+
+use crate::architecture::arm::ap::CSW;
+use crate::architecture::arm::{
+    communication_interface::Initialized, ApAddress, ArmCommunicationInterface, DapAccess,
+};
+use crate::Memory;
+
+const RESET: u8 = 0x00;
+const ERASEALL: u8 = 0x04;
+const ERASEALLSTATUS: u8 = 0x08;
+
+/// The time it takes at most to erase the whole chip.
+const ERASEALL_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Returns true when the debug access to the chip is enabled.
+/// The `ap_address` must be of the AHB-AP.
+pub(super) fn is_unlocked(
+    arm_interface: &mut ArmCommunicationInterface<Initialized>,
+    ap_address: ApAddress,
+) -> Result<bool, crate::Error> {
+    let csw: CSW = arm_interface.read_raw_ap_register(ap_address, 0x00)?.into();
+    Ok(csw.DeviceEn != 0)
+}
+
+/// Erases the flash, the UICR and the RAM of the chip, which also disables the access port protection.
+/// The `ap_address` must be of the CTRL-AP.
+pub(super) fn ctrl_ap_erase_all(
+    arm_interface: &mut ArmCommunicationInterface<Initialized>,
+    ap_address: ApAddress,
+) -> Result<(), crate::Error> {
+    arm_interface.write_raw_ap_register(ap_address, ERASEALL, 1)?;
+
+    let start = Instant::now();
+    while arm_interface.read_raw_ap_register(ap_address, ERASEALLSTATUS)? != 0 {
+        if start.elapsed() > ERASEALL_TIMEOUT {
+            return Err(crate::Error::ArchitectureSpecific(
+                "Timeout while erasing the chip via the CTRL-AP".into(),
+            ));
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    arm_interface.write_raw_ap_register(ap_address, ERASEALL, 0)?;
+
+    Ok(())
+}
+
+/// Resets the chip with the soft reset of the CTRL-AP.
+pub(super) fn ctrl_ap_reset(
+    arm_interface: &mut ArmCommunicationInterface<Initialized>,
+    ap_address: ApAddress,
+) -> Result<(), crate::Error> {
+    arm_interface.write_raw_ap_register(ap_address, RESET, 1)?;
+    std::thread::sleep(Duration::from_millis(10));
+    arm_interface.write_raw_ap_register(ap_address, RESET, 0)?;
+    Ok(())
+}
+
+const WDT_RUNSTATUS: u64 = 0x400;
+const WDT_CONFIG: u64 = 0x50C;
+/// Keep the watchdog running while the CPU is halted by the debugger.
+const WDT_CONFIG_HALT_RUN: u32 = 1 << 3;
+
+/// Makes the watchdog at `wdt_base` pause while the CPU is halted.
+///
+/// The configuration of the watchdog can only be changed while it is stopped, so a running
+/// watchdog which keeps running while halted can only be reported.
+pub(super) fn pause_watchdog_when_halted(memory: &mut Memory, wdt_base: u64) {
+    let result = (|| -> Result<(), crate::Error> {
+        let config = memory.read_word_32(wdt_base + WDT_CONFIG)?;
+        if config & WDT_CONFIG_HALT_RUN == 0 {
+            return Ok(());
+        }
+
+        if memory.read_word_32(wdt_base + WDT_RUNSTATUS)? & 1 != 0 {
+            log::warn!("The watchdog is running and does not pause while the core is halted. It will reset the chip during debugging.");
+        } else {
+            memory.write_word_32(wdt_base + WDT_CONFIG, config & !WDT_CONFIG_HALT_RUN)?;
+        }
+
+        Ok(())
+    })();
+
+    if let Err(error) = result {
+        log::warn!("Could not configure the watchdog: {}", error);
+    }
+}
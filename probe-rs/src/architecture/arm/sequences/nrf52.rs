@@ -0,0 +1,145 @@
+//! Sequences for the nRF52.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::nordic::{ctrl_ap_erase_all, ctrl_ap_reset, is_unlocked, pause_watchdog_when_halted};
+use super::ArmDebugSequence;
+use crate::architecture::arm::ap::MemoryAp;
+use crate::architecture::arm::{ApAddress, ArmProbeInterface};
+
+/// The sequence handle for the nRF52 series.
+///
+/// If the access port protection (APPROTECT) of the chip is enabled, the chip is recovered
+/// by erasing it through its CTRL-AP, which requires the `erase_all` permission.
+pub struct Nrf52(());
+
+impl Nrf52 {
+    /// The access port number of the CTRL-AP.
+    const CTRL_AP: u8 = 1;
+
+    /// The base address of the watchdog.
+    const WDT: u64 = 0x4001_0000;
+
+    const UICR_APPROTECT: u64 = 0x1000_1208;
+    /// The value of `UICR.APPROTECT` which keeps the protection disabled after a reset.
+    ///
+    /// Newer revisions enable the protection after each reset, unless this value is written.
+    const APPROTECT_HW_DISABLED: u32 = 0x5A;
+
+    const NVMC_READY: u64 = 0x4001_E400;
+    const NVMC_CONFIG: u64 = 0x4001_E504;
+    const NVMC_CONFIG_WEN: u32 = 1;
+    /// The time it takes at most to write a word to the UICR.
+    const NVMC_WRITE_TIMEOUT: Duration = Duration::from_millis(100);
+
+    /// Create a new sequence handle for the nRF52 series.
+    pub fn create() -> Arc<dyn ArmDebugSequence> {
+        Arc::new(Self(()))
+    }
+
+    /// Keeps the access port protection disabled after the next reset, on the revisions which
+    /// enable it on each reset. The UICR must be erased.
+    fn disable_approtect(&self, memory: &mut crate::Memory) -> Result<(), crate::Error> {
+        if memory.read_word_32(Self::UICR_APPROTECT)? != 0xFFFF_FFFF {
+            return Ok(());
+        }
+
+        memory.write_word_32(Self::NVMC_CONFIG, Self::NVMC_CONFIG_WEN)?;
+        memory.write_word_32(Self::UICR_APPROTECT, Self::APPROTECT_HW_DISABLED)?;
+
+        let start = Instant::now();
+        let result = loop {
+            if memory.read_word_32(Self::NVMC_READY)? != 0 {
+                break Ok(());
+            }
+            if start.elapsed() > Self::NVMC_WRITE_TIMEOUT {
+                break Err(crate::Error::ArchitectureSpecific(
+                    "Timeout while writing UICR.APPROTECT".into(),
+                ));
+            }
+        };
+
+        memory.write_word_32(Self::NVMC_CONFIG, 0)?;
+
+        result
+    }
+}
+
+impl ArmDebugSequence for Nrf52 {
+    fn debug_device_unlock(
+        &self,
+        interface: &mut Box<dyn ArmProbeInterface>,
+        default_ap: MemoryAp,
+        permissions: &crate::Permissions,
+    ) -> Result<(), crate::Error> {
+        let mut interface = interface.memory_interface(default_ap)?;
+
+        let ahb_ap_address = interface.get_ap();
+        let ctrl_ap_address = ApAddress {
+            ap: Self::CTRL_AP,
+            ..ahb_ap_address
+        };
+
+        if is_unlocked(interface.get_arm_interface()?, ahb_ap_address)? {
+            return Ok(());
+        }
+
+        log::warn!(
+            "The access port protection is enabled. The chip has to be erased to unlock it."
+        );
+        permissions.erase_all()?;
+
+        ctrl_ap_erase_all(interface.get_arm_interface()?, ctrl_ap_address)?;
+
+        if is_unlocked(interface.get_arm_interface()?, ahb_ap_address)? {
+            // Newer revisions are unlocked until the next reset.
+            self.disable_approtect(&mut interface)?;
+        } else {
+            // Older revisions only evaluate the protection again after a reset.
+            ctrl_ap_reset(interface.get_arm_interface()?, ctrl_ap_address)?;
+
+            if !is_unlocked(interface.get_arm_interface()?, ahb_ap_address)? {
+                return Err(crate::Error::ArchitectureSpecific(
+                    "Could not unlock the chip".into(),
+                ));
+            }
+        }
+
+        log::info!("Erased the chip to disable the access port protection");
+
+        Ok(())
+    }
+
+    fn debug_erase_all(
+        &self,
+        interface: &mut Box<dyn ArmProbeInterface>,
+        default_ap: MemoryAp,
+    ) -> Result<bool, crate::Error> {
+        let mut interface = interface.memory_interface(default_ap)?;
+        let ahb_ap_address = interface.get_ap();
+        let ctrl_ap_address = ApAddress {
+            ap: Self::CTRL_AP,
+            ..ahb_ap_address
+        };
+
+        log::info!("Erasing all memory via the CTRL-AP");
+        ctrl_ap_erase_all(interface.get_arm_interface()?, ctrl_ap_address)?;
+        if is_unlocked(interface.get_arm_interface()?, ahb_ap_address)? {
+            self.disable_approtect(&mut interface)?;
+        }
+        ctrl_ap_reset(interface.get_arm_interface()?, ctrl_ap_address)?;
+
+        Ok(true)
+    }
+
+    fn watchdog_suspend(
+        &self,
+        interface: &mut Box<dyn ArmProbeInterface>,
+        default_ap: MemoryAp,
+    ) -> Result<(), crate::Error> {
+        let mut memory = interface.memory_interface(default_ap)?;
+        pause_watchdog_when_halted(&mut memory, Self::WDT);
+        Ok(())
+    }
+}
@@ -2,19 +2,23 @@
 
 use std::sync::Arc;
 
-use super::ArmDebugSequence;
-use crate::architecture::arm::ap::{MemoryAp, CSW};
-use crate::architecture::arm::{
-    communication_interface::Initialized, ApAddress, ArmCommunicationInterface, ArmProbeInterface,
-    DapAccess,
-};
+use probe_rs_target::CoreType;
+
+use super::nordic::{ctrl_ap_erase_all, ctrl_ap_reset, is_unlocked, pause_watchdog_when_halted};
+use super::{cortex_m_reset_system, reset_hardware_deassert, ArmDebugSequence};
+use crate::architecture::arm::ap::MemoryAp;
+use crate::architecture::arm::{ApAddress, ArmProbeInterface};
+use crate::Memory;
 
 /// The sequence handle for the nRF5340.
 pub struct Nrf5340(());
 
 impl Nrf5340 {
-    const ERASEALL: u8 = 0x04;
-    const ERASEALLSTATUS: u8 = 0x08;
+    /// The base address of the first watchdog of the application core, in the secure address space.
+    const WDT: u64 = 0x5001_8000;
+
+    /// The access port number of the AHB-AP of the application core.
+    const APPLICATION_AHB_AP: u8 = 0;
 
     const APPLICATION_RESET_S_NETWORK_FORCEOFF_REGISTER: u32 = 0x50005614;
     const RELEASE_FORCEOFF: u32 = 0;
@@ -24,35 +28,9 @@ impl Nrf5340 {
         Arc::new(Self(()))
     }
 
-    /// Returns true when the core is unlocked and false when it is locked.
-    /// The `ap_address` must be of the ahb ap of the core.
-    fn is_core_unlocked(
-        &self,
-        arm_interface: &mut ArmCommunicationInterface<Initialized>,
-        ap_address: ApAddress,
-    ) -> Result<bool, crate::Error> {
-        let csw: CSW = arm_interface.read_raw_ap_register(ap_address, 0x00)?.into();
-        Ok(csw.DeviceEn != 0)
-    }
-
-    /// Unlocks the core by performing an erase all procedure.
-    /// The `ap_address` must be of the ctrl ap of the core.
-    fn unlock_core(
-        &self,
-        arm_interface: &mut ArmCommunicationInterface<Initialized>,
-        ap_address: ApAddress,
-        permissions: &crate::Permissions,
-    ) -> Result<(), crate::Error> {
-        permissions.erase_all()?;
-
-        arm_interface.write_raw_ap_register(ap_address, Self::ERASEALL, 1)?;
-        while arm_interface.read_raw_ap_register(ap_address, Self::ERASEALLSTATUS)? != 0 {}
-        Ok(())
-    }
-
     /// Sets the network core to active running.
     /// The `ap_address` must be of the ahb ap of the application core.
-    fn set_network_core_running(&self, interface: &mut crate::Memory) -> Result<(), crate::Error> {
+    fn set_network_core_running(&self, interface: &mut Memory) -> Result<(), crate::Error> {
         interface.write_32(
             Self::APPLICATION_RESET_S_NETWORK_FORCEOFF_REGISTER as u64,
             &[Self::RELEASE_FORCEOFF],
@@ -89,7 +67,7 @@ impl ArmDebugSequence for Nrf5340 {
             };
 
             log::info!("Checking if core {} is unlocked", core_ahb_ap);
-            if self.is_core_unlocked(interface.get_arm_interface()?, core_ahb_ap_address)? {
+            if is_unlocked(interface.get_arm_interface()?, core_ahb_ap_address)? {
                 log::info!("Core {} is already unlocked", core_ahb_ap);
                 continue;
             }
@@ -98,13 +76,15 @@ impl ArmDebugSequence for Nrf5340 {
                 "Core {} is locked. Erase procedure will be started to unlock it.",
                 core_ahb_ap
             );
-            self.unlock_core(
-                interface.get_arm_interface()?,
-                core_ctrl_ap_address,
-                permissions,
-            )?;
+            permissions.erase_all()?;
 
-            if !self.is_core_unlocked(interface.get_arm_interface()?, core_ahb_ap_address)? {
+            ctrl_ap_erase_all(interface.get_arm_interface()?, core_ctrl_ap_address)?;
+            if !is_unlocked(interface.get_arm_interface()?, core_ahb_ap_address)? {
+                // The protection is only evaluated again after a reset.
+                ctrl_ap_reset(interface.get_arm_interface()?, core_ctrl_ap_address)?;
+            }
+
+            if !is_unlocked(interface.get_arm_interface()?, core_ahb_ap_address)? {
                 return Err(crate::Error::ArchitectureSpecific(
                     format!("Could not unlock core {}", core_ahb_ap).into(),
                 ));
@@ -115,4 +95,69 @@ impl ArmDebugSequence for Nrf5340 {
 
         Ok(())
     }
+
+    fn debug_erase_all(
+        &self,
+        interface: &mut Box<dyn ArmProbeInterface>,
+        default_ap: MemoryAp,
+    ) -> Result<bool, crate::Error> {
+        let mut interface = interface.memory_interface(default_ap)?;
+        let ap_address = interface.get_ap();
+
+        // The CTRL-APs of both cores erase all flash, UICR and RAM of their core,
+        // which also removes any access port protection.
+        for core_ctrl_ap in [2, 3] {
+            let core_ctrl_ap_address = ApAddress {
+                ap: core_ctrl_ap,
+                ..ap_address
+            };
+
+            log::info!("Erasing all memory via CTRL-AP {}", core_ctrl_ap);
+            ctrl_ap_erase_all(interface.get_arm_interface()?, core_ctrl_ap_address)?;
+            ctrl_ap_reset(interface.get_arm_interface()?, core_ctrl_ap_address)?;
+        }
+
+        // The reset forces the network core off again.
+        self.set_network_core_running(&mut interface)?;
+
+        Ok(true)
+    }
+
+    fn reset_system(
+        &self,
+        interface: &mut Memory,
+        _core_type: CoreType,
+        _debug_base: Option<u64>,
+    ) -> Result<(), crate::Error> {
+        // Both cores of the nRF5340 are Cortex-M33 cores.
+        cortex_m_reset_system(interface)?;
+
+        // A reset of the application core resets the whole chip, which forces the network core off again.
+        if interface.get_ap().ap == Self::APPLICATION_AHB_AP {
+            self.set_network_core_running(interface)?;
+        }
+
+        Ok(())
+    }
+
+    fn reset_hardware_deassert(&self, memory: &mut Memory) -> Result<(), crate::Error> {
+        reset_hardware_deassert(memory)?;
+
+        // The network core is held off after each reset of the chip, until the application core releases it.
+        if memory.get_ap().ap == Self::APPLICATION_AHB_AP {
+            self.set_network_core_running(memory)?;
+        }
+
+        Ok(())
+    }
+
+    fn watchdog_suspend(
+        &self,
+        interface: &mut Box<dyn ArmProbeInterface>,
+        default_ap: MemoryAp,
+    ) -> Result<(), crate::Error> {
+        let mut memory = interface.memory_interface(default_ap)?;
+        pause_watchdog_when_halted(&mut memory, Self::WDT);
+        Ok(())
+    }
 }
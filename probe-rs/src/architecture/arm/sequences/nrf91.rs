@@ -0,0 +1,149 @@
+//! Sequences for the nRF91.
+
+use std::sync::Arc;
+
+use super::nordic::{ctrl_ap_erase_all, ctrl_ap_reset, pause_watchdog_when_halted};
+use super::ArmDebugSequence;
+use crate::architecture::arm::ap::{MemoryAp, CSW};
+use crate::architecture::arm::{
+    communication_interface::Initialized, ApAddress, ArmCommunicationInterface, ArmProbeInterface,
+    DapAccess,
+};
+
+/// The sequence handle for the nRF91 series.
+///
+/// The nRF91 has two protection mechanisms: APPROTECT blocks all debug access, and
+/// SECUREAPPROTECT blocks the debug access to the secure domain. Both are removed by erasing
+/// the chip through its CTRL-AP, which requires the `erase_all` permission.
+pub struct Nrf9160(());
+
+/// The protection mechanisms of the nRF91 which are enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Protection {
+    approtect: bool,
+    secure_approtect: bool,
+}
+
+impl Protection {
+    fn is_enabled(&self) -> bool {
+        self.approtect || self.secure_approtect
+    }
+
+    fn describe(&self) -> &'static str {
+        match (self.approtect, self.secure_approtect) {
+            (true, true) => "APPROTECT and SECUREAPPROTECT are",
+            (true, false) => "APPROTECT is",
+            (false, true) => "SECUREAPPROTECT is",
+            (false, false) => "no protection is",
+        }
+    }
+}
+
+impl Nrf9160 {
+    /// The access port number of the CTRL-AP.
+    const CTRL_AP: u8 = 4;
+
+    /// The base address of the watchdog, in the secure address space.
+    const WDT: u64 = 0x5001_8000;
+
+    /// Create a new sequence handle for the nRF91 series.
+    pub fn create() -> Arc<dyn ArmDebugSequence> {
+        Arc::new(Self(()))
+    }
+
+    /// Read which protection mechanisms are enabled from the CSW of the AHB-AP.
+    fn protection(
+        &self,
+        arm_interface: &mut ArmCommunicationInterface<Initialized>,
+        ahb_ap_address: ApAddress,
+    ) -> Result<Protection, crate::Error> {
+        let csw: CSW = arm_interface
+            .read_raw_ap_register(ahb_ap_address, 0x00)?
+            .into();
+
+        Ok(Protection {
+            approtect: csw.DeviceEn == 0,
+            secure_approtect: csw.SPIDEN == 0,
+        })
+    }
+}
+
+impl ArmDebugSequence for Nrf9160 {
+    fn debug_device_unlock(
+        &self,
+        interface: &mut Box<dyn ArmProbeInterface>,
+        default_ap: MemoryAp,
+        permissions: &crate::Permissions,
+    ) -> Result<(), crate::Error> {
+        let mut interface = interface.memory_interface(default_ap)?;
+
+        let ahb_ap_address = interface.get_ap();
+        let ctrl_ap_address = ApAddress {
+            ap: Self::CTRL_AP,
+            ..ahb_ap_address
+        };
+
+        let protection = self.protection(interface.get_arm_interface()?, ahb_ap_address)?;
+        if !protection.is_enabled() {
+            return Ok(());
+        }
+
+        log::warn!(
+            "{} enabled. The chip has to be erased to unlock it.",
+            protection.describe()
+        );
+        if permissions.erase_all().is_err() {
+            return Err(crate::Error::MissingPermissions(format!(
+                "erase_all, because {} enabled on the nRF91 and it can only be disabled by erasing the chip",
+                protection.describe()
+            )));
+        }
+
+        ctrl_ap_erase_all(interface.get_arm_interface()?, ctrl_ap_address)?;
+        // The protection is only evaluated again after a reset.
+        ctrl_ap_reset(interface.get_arm_interface()?, ctrl_ap_address)?;
+
+        let protection = self.protection(interface.get_arm_interface()?, ahb_ap_address)?;
+        if protection.is_enabled() {
+            return Err(crate::Error::ArchitectureSpecific(
+                format!(
+                    "Could not unlock the chip, {} still enabled after erasing it",
+                    protection.describe()
+                )
+                .into(),
+            ));
+        }
+
+        log::info!("Erased the chip to disable the access port protection");
+
+        Ok(())
+    }
+
+    fn debug_erase_all(
+        &self,
+        interface: &mut Box<dyn ArmProbeInterface>,
+        default_ap: MemoryAp,
+    ) -> Result<bool, crate::Error> {
+        let mut interface = interface.memory_interface(default_ap)?;
+        let ctrl_ap_address = ApAddress {
+            ap: Self::CTRL_AP,
+            ..interface.get_ap()
+        };
+
+        log::info!("Erasing all memory via the CTRL-AP");
+        ctrl_ap_erase_all(interface.get_arm_interface()?, ctrl_ap_address)?;
+        ctrl_ap_reset(interface.get_arm_interface()?, ctrl_ap_address)?;
+
+        Ok(true)
+    }
+
+    fn watchdog_suspend(
+        &self,
+        interface: &mut Box<dyn ArmProbeInterface>,
+        default_ap: MemoryAp,
+    ) -> Result<(), crate::Error> {
+        let mut memory = interface.memory_interface(default_ap)?;
+        pause_watchdog_when_halted(&mut memory, Self::WDT);
+        Ok(())
+    }
+}
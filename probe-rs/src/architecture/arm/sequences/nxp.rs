@@ -1,5 +1,7 @@
 //! Sequences for NXP chips.
 
+pub mod debug_mailbox;
+
 use std::{
     sync::Arc,
     thread,
@@ -8,28 +10,95 @@ use std::{
 
 use crate::{
     architecture::arm::{
-        ap::{ApAccess, GenericAp, IDR},
+        ap::{ApAccess, GenericAp, MemoryAp, CSW, IDR},
         communication_interface::Initialized,
         dp::{Abort, Ctrl, DpAccess, Select, DPIDR},
-        ApAddress, ArmCommunicationInterface, DapAccess, DpAddress,
+        ApAddress, ArmCommunicationInterface, ArmProbeInterface, DapAccess, DpAddress,
     },
     core::MemoryMappedRegister,
     CommunicationInterface, DebugProbeError,
 };
 
+use self::debug_mailbox::{DebugAuthenticator, DebugMailbox, DebugMailboxError};
 use super::ArmDebugSequence;
 
 /// The sequence handle for the LPC55S69.
-pub struct LPC55S69(());
+pub struct LPC55S69 {
+    authenticator: Option<Arc<dyn DebugAuthenticator>>,
+}
 
 impl LPC55S69 {
+    /// The access port number of the debug mailbox.
+    const DEBUG_MAILBOX_AP: u8 = 2;
+
     /// Create a sequence handle for the LPC55S69.
     pub fn create() -> Arc<dyn ArmDebugSequence> {
-        Arc::new(Self(()))
+        Arc::new(Self {
+            authenticator: None,
+        })
+    }
+
+    /// Create a sequence handle for the LPC55S69, which uses `authenticator` to enable the
+    /// debug access of chips on which it is disabled.
+    pub fn with_authenticator(
+        authenticator: Arc<dyn DebugAuthenticator>,
+    ) -> Arc<dyn ArmDebugSequence> {
+        Arc::new(Self {
+            authenticator: Some(authenticator),
+        })
+    }
+}
+
+/// Returns true when the debug access through `ap` is enabled.
+fn is_debug_enabled(interface: &mut ArmCommunicationInterface<Initialized>, ap: ApAddress) -> bool {
+    match interface.read_raw_ap_register(ap, 0x00) {
+        Ok(csw) => CSW::from(csw).DeviceEn != 0,
+        Err(e) => {
+            log::debug!("Failed to read the CSW of {:?}: {}", ap, e);
+            false
+        }
     }
 }
 
 impl ArmDebugSequence for LPC55S69 {
+    fn debug_device_unlock(
+        &self,
+        interface: &mut Box<dyn ArmProbeInterface>,
+        default_ap: MemoryAp,
+        _permissions: &crate::Permissions,
+    ) -> Result<(), crate::Error> {
+        let mut memory = interface.memory_interface(default_ap)?;
+        let ap = memory.get_ap();
+        let interface = memory.get_arm_interface()?;
+
+        if is_debug_enabled(interface, ap) {
+            return Ok(());
+        }
+
+        let authenticator = self
+            .authenticator
+            .as_ref()
+            .ok_or_else(|| crate::Error::architecture_specific(DebugMailboxError::Locked))?;
+
+        log::info!("The debug access is disabled, starting the debug authentication");
+        let mailbox = DebugMailbox::new(ApAddress {
+            ap: Self::DEBUG_MAILBOX_AP,
+            ..ap
+        });
+        mailbox.resynchronize(interface)?;
+        mailbox.authenticate(interface, authenticator.as_ref())?;
+
+        if !is_debug_enabled(interface, ap) {
+            return Err(crate::Error::architecture_specific(
+                DebugMailboxError::AuthenticationFailed,
+            ));
+        }
+
+        log::info!("Enabled the debug access by debug authentication");
+
+        Ok(())
+    }
+
     fn debug_port_start(
         &self,
         interface: &mut ArmCommunicationInterface<Initialized>,
@@ -0,0 +1,300 @@
+//! The debug mailbox of the LPC55 series.
+//!
+//! The debug mailbox is an access port which is used to send commands to the boot ROM, e.g. to
+//! start a debug session, to enter the ISP mode or to authenticate the debugger on a chip whose
+//! debug access is disabled.
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    architecture::arm::{
+        communication_interface::Initialized, ApAddress, ArmCommunicationInterface, DapAccess,
+    },
+    CommunicationInterface,
+};
+
+/// The token which acknowledges a word of a command or a response.
+const ACK_TOKEN: u32 = 0xA5A5;
+
+/// The time to wait for the boot ROM to answer a request.
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// An error which occurred while talking to the debug mailbox.
+#[derive(thiserror::Error, Debug)]
+pub enum DebugMailboxError {
+    /// The boot ROM did not answer in time.
+    #[error("Timeout while waiting for the debug mailbox")]
+    Timeout,
+    /// The boot ROM did not acknowledge a parameter of a command.
+    #[error("The debug mailbox did not acknowledge parameter {index} of {command:?}, but returned {value:#010x}")]
+    MissingAck {
+        /// The command which was sent.
+        command: Command,
+        /// The index of the parameter.
+        index: usize,
+        /// The value which was returned instead of the acknowledgement.
+        value: u32,
+    },
+    /// The boot ROM returned an error for a command.
+    #[error("The debug mailbox returned the status {status:#06x} for {command:?}")]
+    Status {
+        /// The command which was sent.
+        command: Command,
+        /// The returned status.
+        status: u16,
+    },
+    /// The debug access of the chip is disabled, and no credentials to authenticate are available.
+    #[error("The debug access of the chip is disabled. It can only be enabled by debug authentication, which requires credentials.")]
+    Locked,
+    /// The response to the debug authentication challenge could not be created.
+    #[error("Failed to create the debug authentication response")]
+    Authenticator(#[source] Box<dyn std::error::Error + Send + Sync>),
+    /// The debug access is still disabled after the authentication.
+    #[error("The debug access of the chip is still disabled after the debug authentication")]
+    AuthenticationFailed,
+}
+
+/// The commands of the debug mailbox.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Start the debug mailbox.
+    Start = 0x01,
+    /// Read the code read protection (CRP) level.
+    GetCrpLevel = 0x02,
+    /// Erase the whole flash.
+    EraseFlash = 0x03,
+    /// Leave the debug mailbox.
+    Exit = 0x04,
+    /// Enter the ISP mode of the boot ROM.
+    EnterIspMode = 0x05,
+    /// Enter the fault analysis mode.
+    SetFaultAnalysisMode = 0x06,
+    /// Start a debug session.
+    StartDebugSession = 0x07,
+    /// Request the debug authentication challenge (DAC).
+    DebugAuthenticationStart = 0x10,
+    /// Send the debug authentication response (DAR).
+    DebugAuthenticationResponse = 0x11,
+}
+
+/// Creates the debug authentication response (DAR) for a debug authentication challenge (DAC).
+///
+/// The response contains the debug credential certificate of the user, signed with the key of
+/// the credential, as described in the application note AN13037. Implement this trait to
+/// provide the credentials for a chip whose debug access is disabled, and pass it to
+/// [`LPC55S69::with_authenticator`](super::LPC55S69::with_authenticator).
+pub trait DebugAuthenticator: Send + Sync {
+    /// Create the response for the `challenge`, as returned by the boot ROM.
+    fn respond(
+        &self,
+        challenge: &[u8],
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>>;
+}
+
+/// A handle to the debug mailbox access port.
+#[derive(Debug, Clone, Copy)]
+pub struct DebugMailbox {
+    ap: ApAddress,
+}
+
+impl DebugMailbox {
+    const CSW: u8 = 0x00;
+    const REQUEST: u8 = 0x04;
+    const RETURN: u8 = 0x08;
+
+    /// The bits of the CSW which resynchronize the mailbox and reset the chip.
+    const CSW_RESYNC_AND_RESET: u32 = 0x21;
+
+    /// Create a handle to the debug mailbox with the given address.
+    pub fn new(ap: ApAddress) -> Self {
+        Self { ap }
+    }
+
+    /// Resynchronize the mailbox with the boot ROM and reset the chip.
+    pub fn resynchronize(
+        &self,
+        interface: &mut ArmCommunicationInterface<Initialized>,
+    ) -> Result<(), crate::Error> {
+        interface.write_raw_ap_register(self.ap, Self::CSW, Self::CSW_RESYNC_AND_RESET)?;
+        interface.flush()?;
+
+        // The boot ROM needs some time to start after the reset.
+        thread::sleep(Duration::from_millis(30));
+        let _ = interface.read_raw_ap_register(self.ap, Self::CSW)?;
+
+        Ok(())
+    }
+
+    /// Start a debug session, which enables the debug access if it is permitted.
+    pub fn start_debug_session(
+        &self,
+        interface: &mut ArmCommunicationInterface<Initialized>,
+    ) -> Result<(), crate::Error> {
+        self.command(interface, Command::StartDebugSession, &[])?;
+        Ok(())
+    }
+
+    /// Enter the ISP mode of the boot ROM, with the ISP interface selected by `mode`
+    /// as described in the user manual.
+    pub fn enter_isp_mode(
+        &self,
+        interface: &mut ArmCommunicationInterface<Initialized>,
+        mode: u32,
+    ) -> Result<(), crate::Error> {
+        self.command(interface, Command::EnterIspMode, &[mode])?;
+        Ok(())
+    }
+
+    /// Erase the whole flash.
+    pub fn erase_flash(
+        &self,
+        interface: &mut ArmCommunicationInterface<Initialized>,
+    ) -> Result<(), crate::Error> {
+        self.command(interface, Command::EraseFlash, &[])?;
+        Ok(())
+    }
+
+    /// Run the debug authentication, with the response created by `authenticator`.
+    pub fn authenticate(
+        &self,
+        interface: &mut ArmCommunicationInterface<Initialized>,
+        authenticator: &dyn DebugAuthenticator,
+    ) -> Result<(), crate::Error> {
+        let challenge = self.command(interface, Command::DebugAuthenticationStart, &[])?;
+        log::debug!(
+            "Received a debug authentication challenge of {} words",
+            challenge.len()
+        );
+
+        let response = authenticator
+            .respond(&words_to_bytes(&challenge))
+            .map_err(|e| {
+                crate::Error::architecture_specific(DebugMailboxError::Authenticator(e))
+            })?;
+
+        self.command(
+            interface,
+            Command::DebugAuthenticationResponse,
+            &bytes_to_words(&response),
+        )?;
+
+        Ok(())
+    }
+
+    /// Send a command with its parameters, and return the response.
+    pub fn command(
+        &self,
+        interface: &mut ArmCommunicationInterface<Initialized>,
+        command: Command,
+        parameters: &[u32],
+    ) -> Result<Vec<u32>, crate::Error> {
+        log::debug!("Sending {:?} to the debug mailbox", command);
+
+        self.write_request(interface, command as u32 | (parameters.len() as u32) << 16)?;
+        for (index, parameter) in parameters.iter().enumerate() {
+            let value = self.read_return(interface)?;
+            let remaining = (parameters.len() - index) as u32;
+            if value != (remaining << 16 | ACK_TOKEN) {
+                return Err(crate::Error::architecture_specific(
+                    DebugMailboxError::MissingAck {
+                        command,
+                        index,
+                        value,
+                    },
+                ));
+            }
+            self.write_request(interface, *parameter)?;
+        }
+
+        let header = self.read_return(interface)?;
+        let status = header as u16;
+        if status != 0 {
+            return Err(crate::Error::architecture_specific(
+                DebugMailboxError::Status { command, status },
+            ));
+        }
+
+        let length = (header >> 16) & 0x7FFF;
+        let mut response = Vec::with_capacity(length as usize);
+        for i in 0..length {
+            response.push(self.read_return(interface)?);
+            // The last word is not acknowledged.
+            if i + 1 < length {
+                self.write_request(interface, (length - i) << 16 | ACK_TOKEN)?;
+            }
+        }
+
+        Ok(response)
+    }
+
+    fn write_request(
+        &self,
+        interface: &mut ArmCommunicationInterface<Initialized>,
+        value: u32,
+    ) -> Result<(), crate::Error> {
+        self.retry(|| {
+            interface.write_raw_ap_register(self.ap, Self::REQUEST, value)?;
+            interface.flush()
+        })
+    }
+
+    fn read_return(
+        &self,
+        interface: &mut ArmCommunicationInterface<Initialized>,
+    ) -> Result<u32, crate::Error> {
+        self.retry(|| interface.read_raw_ap_register(self.ap, Self::RETURN))
+    }
+
+    /// The access port does not answer while the boot ROM is busy, so accesses are retried.
+    fn retry<T>(
+        &self,
+        mut access: impl FnMut() -> Result<T, crate::DebugProbeError>,
+    ) -> Result<T, crate::Error> {
+        let start = Instant::now();
+        loop {
+            match access() {
+                Ok(value) => return Ok(value),
+                Err(e) if start.elapsed() < RESPONSE_TIMEOUT => {
+                    log::trace!("Debug mailbox access failed, retrying: {}", e);
+                    thread::sleep(Duration::from_millis(1));
+                }
+                Err(_) => {
+                    return Err(crate::Error::architecture_specific(
+                        DebugMailboxError::Timeout,
+                    ))
+                }
+            }
+        }
+    }
+}
+
+fn words_to_bytes(words: &[u32]) -> Vec<u8> {
+    words.iter().flat_map(|word| word.to_le_bytes()).collect()
+}
+
+/// Convert bytes to little endian words, padding the last word with zeros.
+fn bytes_to_words(bytes: &[u8]) -> Vec<u32> {
+    bytes
+        .chunks(4)
+        .map(|chunk| {
+            let mut word = [0; 4];
+            word[..chunk.len()].copy_from_slice(chunk);
+            u32::from_le_bytes(word)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn response_is_padded_to_words() {
+        let words = bytes_to_words(&[1, 2, 3, 4, 5, 6]);
+        assert_eq!(words, [0x0403_0201, 0x0000_0605]);
+        assert_eq!(&words_to_bytes(&words)[..6], &[1, 2, 3, 4, 5, 6]);
+    }
+}
@@ -0,0 +1,591 @@
+//! Execution of parsed debug sequences.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use super::parser::{BinaryOp, Expr, Statement, UnaryOp};
+use super::PackSequenceError;
+use crate::{Error, WireProtocol};
+
+/// Sequences can call each other with `Sequence`, this limits the nesting to catch endless recursion.
+const MAX_NESTING: usize = 16;
+
+/// The accesses to the target and the probe a sequence can do.
+///
+/// Which accesses are available depends on the state of the connection,
+/// e.g. the memory can not be accessed before the debug port is powered up.
+pub(super) trait SequenceInterface {
+    /// Read `size` bits from the memory at `address`, through the access port `ap`.
+    fn read_memory(&mut self, ap: u8, address: u64, size: u8) -> Result<u64, Error>;
+
+    /// Write `size` bits to the memory at `address`, through the access port `ap`.
+    fn write_memory(&mut self, ap: u8, address: u64, size: u8, value: u64) -> Result<(), Error>;
+
+    fn read_dp(&mut self, address: u8) -> Result<u32, Error>;
+
+    fn write_dp(&mut self, address: u8, value: u32) -> Result<(), Error>;
+
+    fn read_ap(&mut self, ap: u8, address: u8) -> Result<u32, Error>;
+
+    fn write_ap(&mut self, ap: u8, address: u8, value: u32) -> Result<(), Error>;
+
+    fn swj_pins(&mut self, pin_out: u32, pin_select: u32, pin_wait: u32) -> Result<u32, Error>;
+
+    fn swj_sequence(&mut self, bit_len: u8, bits: u64) -> Result<(), Error>;
+
+    fn protocol(&mut self) -> Option<WireProtocol>;
+}
+
+/// An element of a parsed sequence.
+#[derive(Debug)]
+pub(super) enum Element {
+    Block(Vec<Statement>),
+    Control {
+        condition: Option<Expr>,
+        repeat_while: Option<Expr>,
+        timeout: Option<Duration>,
+        body: Vec<Element>,
+    },
+}
+
+/// The parsed debug sequences of a target.
+#[derive(Debug, Default)]
+pub(super) struct Program {
+    /// The declarations of the global variables.
+    pub(super) debug_vars: Vec<Statement>,
+    /// The sequences by their name.
+    pub(super) sequences: HashMap<String, Vec<Element>>,
+}
+
+impl Program {
+    /// Returns true if the sequence `name` is described.
+    pub(super) fn contains(&self, name: &str) -> bool {
+        self.sequences.contains_key(name)
+    }
+
+    /// Execute the sequence `name`, with `ap` as the initial value of `__ap`.
+    pub(super) fn run(
+        &self,
+        name: &str,
+        interface: &mut dyn SequenceInterface,
+        ap: u8,
+    ) -> Result<(), Error> {
+        let protocol = match interface.protocol() {
+            Some(WireProtocol::Jtag) => 1,
+            Some(WireProtocol::Swd) => 2,
+            None => 0,
+        };
+
+        let mut globals = HashMap::new();
+        // The SWJ-DP switching sequences are always available.
+        globals.insert("__protocol".to_string(), protocol | 1 << 16);
+        globals.insert("__connection".to_string(), 1);
+        globals.insert("__dp".to_string(), 0);
+        globals.insert("__ap".to_string(), ap as u64);
+        for name in [
+            "__traceout",
+            "__errorcontrol",
+            "__FlashOp",
+            "__FlashAddr",
+            "__FlashLen",
+            "__FlashArg",
+            "__Result",
+        ] {
+            globals.insert(name.to_string(), 0);
+        }
+
+        let mut interpreter = Interpreter {
+            program: self,
+            interface,
+            globals,
+            nesting: 0,
+        };
+
+        let mut debug_vars = HashMap::new();
+        for statement in &self.debug_vars {
+            interpreter.statement(statement, &mut debug_vars)?;
+        }
+        interpreter.globals.extend(debug_vars);
+
+        interpreter.sequence(name)
+    }
+}
+
+struct Interpreter<'a> {
+    program: &'a Program,
+    interface: &'a mut dyn SequenceInterface,
+    globals: HashMap<String, u64>,
+    nesting: usize,
+}
+
+impl Interpreter<'_> {
+    fn sequence(&mut self, name: &str) -> Result<(), Error> {
+        let program = self.program;
+        let body = program.sequences.get(name).ok_or_else(|| {
+            Error::architecture_specific(PackSequenceError::UnknownSequence(name.to_string()))
+        })?;
+
+        if self.nesting == MAX_NESTING {
+            return Err(Error::architecture_specific(
+                PackSequenceError::NestingTooDeep,
+            ));
+        }
+
+        log::debug!("Executing debug sequence {}", name);
+        self.nesting += 1;
+        let result = self.elements(body, &mut HashMap::new());
+        self.nesting -= 1;
+
+        result
+    }
+
+    fn elements(
+        &mut self,
+        elements: &[Element],
+        locals: &mut HashMap<String, u64>,
+    ) -> Result<(), Error> {
+        for element in elements {
+            match element {
+                Element::Block(statements) => {
+                    for statement in statements {
+                        self.statement(statement, locals)?;
+                    }
+                }
+                Element::Control {
+                    condition,
+                    repeat_while,
+                    timeout,
+                    body,
+                } => {
+                    if let Some(condition) = condition {
+                        if self.evaluate(condition, locals)? == 0 {
+                            continue;
+                        }
+                    }
+
+                    let repeat_while = match repeat_while {
+                        Some(repeat_while) => repeat_while,
+                        None => {
+                            self.elements(body, locals)?;
+                            continue;
+                        }
+                    };
+
+                    let start = Instant::now();
+                    while self.evaluate(repeat_while, locals)? != 0 {
+                        self.elements(body, locals)?;
+
+                        if let Some(timeout) = timeout {
+                            if start.elapsed() > *timeout {
+                                log::debug!("Timeout in the loop of a debug sequence");
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn statement(
+        &mut self,
+        statement: &Statement,
+        locals: &mut HashMap<String, u64>,
+    ) -> Result<(), Error> {
+        match statement {
+            Statement::Declare(name, value) => {
+                let value = self.evaluate(value, locals)?;
+                locals.insert(name.clone(), value);
+            }
+            Statement::Assign(name, operator, value) => {
+                let value = self.evaluate(value, locals)?;
+                let variable = match locals.get_mut(name) {
+                    Some(variable) => variable,
+                    None => self.globals.get_mut(name).ok_or_else(|| {
+                        Error::architecture_specific(PackSequenceError::UndefinedVariable(
+                            name.clone(),
+                        ))
+                    })?,
+                };
+
+                *variable = match operator {
+                    Some(operator) => binary(*operator, *variable, value)?,
+                    None => value,
+                };
+            }
+            Statement::Expr(expr) => {
+                self.evaluate(expr, locals)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn evaluate(&mut self, expr: &Expr, locals: &mut HashMap<String, u64>) -> Result<u64, Error> {
+        Ok(match expr {
+            Expr::Number(value) => *value,
+            Expr::String(_) => {
+                return Err(Error::architecture_specific(
+                    PackSequenceError::UnexpectedString,
+                ))
+            }
+            Expr::Variable(name) => locals
+                .get(name)
+                .or_else(|| self.globals.get(name))
+                .copied()
+                .ok_or_else(|| {
+                    Error::architecture_specific(PackSequenceError::UndefinedVariable(name.clone()))
+                })?,
+            Expr::Unary(operator, operand) => {
+                let operand = self.evaluate(operand, locals)?;
+                match operator {
+                    UnaryOp::Not => (operand == 0) as u64,
+                    UnaryOp::BitNot => !operand,
+                    UnaryOp::Neg => operand.wrapping_neg(),
+                }
+            }
+            Expr::Binary(BinaryOp::And, lhs, rhs) => {
+                (self.evaluate(lhs, locals)? != 0 && self.evaluate(rhs, locals)? != 0) as u64
+            }
+            Expr::Binary(BinaryOp::Or, lhs, rhs) => {
+                (self.evaluate(lhs, locals)? != 0 || self.evaluate(rhs, locals)? != 0) as u64
+            }
+            Expr::Binary(operator, lhs, rhs) => {
+                let lhs = self.evaluate(lhs, locals)?;
+                let rhs = self.evaluate(rhs, locals)?;
+                binary(*operator, lhs, rhs)?
+            }
+            Expr::Call(function, arguments) => self.call(function, arguments, locals)?,
+        })
+    }
+
+    fn call(
+        &mut self,
+        function: &str,
+        arguments: &[Expr],
+        locals: &mut HashMap<String, u64>,
+    ) -> Result<u64, Error> {
+        let mut values = Vec::with_capacity(arguments.len());
+        for argument in arguments {
+            values.push(match argument {
+                Expr::String(_) => 0,
+                argument => self.evaluate(argument, locals)?,
+            });
+        }
+
+        let ap = self.globals["__ap"] as u8;
+        let result = match function {
+            "Read8" | "Read16" | "Read32" | "Read64" => {
+                let size = function[4..].parse().unwrap();
+                self.interface.read_memory(ap, values[0], size)
+            }
+            "Write8" | "Write16" | "Write32" | "Write64" => {
+                let size = function[5..].parse().unwrap();
+                self.interface
+                    .write_memory(ap, values[0], size, values[1])
+                    .map(|()| 0)
+            }
+            "ReadDP" => self.interface.read_dp(values[0] as u8).map(u64::from),
+            "WriteDP" => self
+                .interface
+                .write_dp(values[0] as u8, values[1] as u32)
+                .map(|()| 0),
+            "ReadAP" => self.interface.read_ap(ap, values[0] as u8).map(u64::from),
+            "WriteAP" => self
+                .interface
+                .write_ap(ap, values[0] as u8, values[1] as u32)
+                .map(|()| 0),
+            "DAP_WriteABORT" => self.interface.write_dp(0x0, values[0] as u32).map(|()| 0),
+            "DAP_SWJ_Pins" => self
+                .interface
+                .swj_pins(values[0] as u32, values[1] as u32, values[2] as u32)
+                .map(u64::from),
+            "DAP_SWJ_Sequence" => self
+                .interface
+                .swj_sequence(values[0] as u8, values[1])
+                .map(|()| 0),
+            "DAP_SWJ_Clock" => {
+                log::debug!(
+                    "Ignoring the clock of {} Hz requested by a debug sequence",
+                    values[0]
+                );
+                Ok(0)
+            }
+            "DAP_JTAG_Sequence" => Err(Error::architecture_specific(
+                PackSequenceError::NotAvailable("DAP_JTAG_Sequence"),
+            )),
+            "DAP_Delay" => {
+                std::thread::sleep(Duration::from_micros(values[0]));
+                return Ok(0);
+            }
+            "Sequence" => {
+                self.sequence(string_argument(function, &arguments[0])?)?;
+                return Ok(0);
+            }
+            "Query" | "QueryValue" => {
+                // There is no user to ask, so the default answer is used.
+                let message = string_argument(function, &arguments[arguments.len() - 2])?;
+                let default = values[values.len() - 1];
+                log::info!("{} (answered with {})", message, default);
+                self.globals.insert("__Result".to_string(), default);
+                return Ok(default);
+            }
+            "LoadDebugInfo" => {
+                log::debug!(
+                    "Ignoring LoadDebugInfo({:?}) in a debug sequence",
+                    string_argument(function, &arguments[0])?
+                );
+                return Ok(0);
+            }
+            "Message" => {
+                let message =
+                    format_message(string_argument(function, &arguments[1])?, &values[2..]);
+                match values[0] {
+                    0 => log::info!("{}", message),
+                    1 => log::warn!("{}", message),
+                    _ => {
+                        return Err(Error::architecture_specific(PackSequenceError::Error(
+                            message,
+                        )))
+                    }
+                }
+                return Ok(0);
+            }
+            _ => unreachable!("Unknown functions are rejected by the parser"),
+        };
+
+        match result {
+            // Bit 0 of `__errorcontrol` makes the debugger ignore errors of the access functions.
+            Err(error) if self.globals["__errorcontrol"] & 1 != 0 => {
+                log::debug!(
+                    "Ignoring error of {} in a debug sequence: {}",
+                    function,
+                    error
+                );
+                Ok(0)
+            }
+            result => result,
+        }
+    }
+}
+
+fn string_argument<'a>(function: &str, argument: &'a Expr) -> Result<&'a str, Error> {
+    match argument {
+        Expr::String(string) => Ok(string),
+        _ => Err(Error::architecture_specific(
+            PackSequenceError::ExpectedString(function.to_string()),
+        )),
+    }
+}
+
+fn binary(operator: BinaryOp, lhs: u64, rhs: u64) -> Result<u64, Error> {
+    Ok(match operator {
+        BinaryOp::Mul => lhs.wrapping_mul(rhs),
+        BinaryOp::Div | BinaryOp::Rem if rhs == 0 => {
+            return Err(Error::architecture_specific(
+                PackSequenceError::DivisionByZero,
+            ))
+        }
+        BinaryOp::Div => lhs / rhs,
+        BinaryOp::Rem => lhs % rhs,
+        BinaryOp::Add => lhs.wrapping_add(rhs),
+        BinaryOp::Sub => lhs.wrapping_sub(rhs),
+        BinaryOp::Shl if rhs >= 64 => 0,
+        BinaryOp::Shl => lhs << rhs,
+        BinaryOp::Shr if rhs >= 64 => 0,
+        BinaryOp::Shr => lhs >> rhs,
+        BinaryOp::Lt => (lhs < rhs) as u64,
+        BinaryOp::Le => (lhs <= rhs) as u64,
+        BinaryOp::Gt => (lhs > rhs) as u64,
+        BinaryOp::Ge => (lhs >= rhs) as u64,
+        BinaryOp::Eq => (lhs == rhs) as u64,
+        BinaryOp::Ne => (lhs != rhs) as u64,
+        BinaryOp::BitAnd => lhs & rhs,
+        BinaryOp::BitXor => lhs ^ rhs,
+        BinaryOp::BitOr => lhs | rhs,
+        BinaryOp::And => (lhs != 0 && rhs != 0) as u64,
+        BinaryOp::Or => (lhs != 0 || rhs != 0) as u64,
+    })
+}
+
+/// Format the message of `Message`, which uses the `printf` conversions `%d`, `%u`, `%x` and `%X`.
+fn format_message(format: &str, values: &[u64]) -> String {
+    let mut message = String::new();
+    let mut values = values.iter();
+    let mut chars = format.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            message.push(c);
+            continue;
+        }
+
+        let zero_padded = chars.next_if_eq(&'0').is_some();
+        let mut width = 0;
+        while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+            width = width * 10 + digit as usize;
+            chars.next();
+        }
+        while chars.next_if_eq(&'l').is_some() {}
+
+        let value = match chars.peek() {
+            Some('%') | None => None,
+            Some(_) => Some(values.next().copied().unwrap_or_default()),
+        };
+        let formatted = match (chars.next(), value) {
+            (Some('d'), Some(value)) | (Some('i'), Some(value)) => (value as i64).to_string(),
+            (Some('u'), Some(value)) => value.to_string(),
+            (Some('x'), Some(value)) => format!("{:x}", value),
+            (Some('X'), Some(value)) => format!("{:X}", value),
+            (Some('%'), _) => {
+                message.push('%');
+                continue;
+            }
+            (Some(other), _) => {
+                message.push('%');
+                message.push(other);
+                continue;
+            }
+            (None, _) => {
+                message.push('%');
+                break;
+            }
+        };
+
+        let padding = if zero_padded { '0' } else { ' ' };
+        for _ in formatted.len()..width {
+            message.push(padding);
+        }
+        message.push_str(&formatted);
+    }
+
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::parser::{parse_block, parse_expression};
+    use super::*;
+
+    /// A target with a few memory words, which records the accesses to the debug port.
+    #[derive(Default)]
+    struct MockInterface {
+        memory: HashMap<u64, u64>,
+        dp_writes: Vec<(u8, u32)>,
+    }
+
+    impl SequenceInterface for MockInterface {
+        fn read_memory(&mut self, _ap: u8, address: u64, _size: u8) -> Result<u64, Error> {
+            Ok(self.memory.get(&address).copied().unwrap_or_default())
+        }
+
+        fn write_memory(
+            &mut self,
+            _ap: u8,
+            address: u64,
+            _size: u8,
+            value: u64,
+        ) -> Result<(), Error> {
+            self.memory.insert(address, value);
+            Ok(())
+        }
+
+        fn read_dp(&mut self, _address: u8) -> Result<u32, Error> {
+            Err(Error::architecture_specific(
+                PackSequenceError::NotAvailable("ReadDP"),
+            ))
+        }
+
+        fn write_dp(&mut self, address: u8, value: u32) -> Result<(), Error> {
+            self.dp_writes.push((address, value));
+            Ok(())
+        }
+
+        fn read_ap(&mut self, _ap: u8, _address: u8) -> Result<u32, Error> {
+            Ok(0)
+        }
+
+        fn write_ap(&mut self, _ap: u8, _address: u8, _value: u32) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn swj_pins(&mut self, _out: u32, _select: u32, _wait: u32) -> Result<u32, Error> {
+            Ok(0)
+        }
+
+        fn swj_sequence(&mut self, _bit_len: u8, _bits: u64) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn protocol(&mut self) -> Option<WireProtocol> {
+            Some(WireProtocol::Swd)
+        }
+    }
+
+    fn block(code: &str) -> Element {
+        Element::Block(parse_block(code).unwrap())
+    }
+
+    #[test]
+    fn run_sequence_with_loop_and_nested_call() {
+        let mut program = Program {
+            debug_vars: parse_block("__var Step = 4;").unwrap(),
+            ..Default::default()
+        };
+        program.sequences.insert(
+            "ResetSystem".to_string(),
+            vec![
+                block("__var count = 0;"),
+                Element::Control {
+                    condition: Some(parse_expression("(__protocol & 0xFFFF) == 2").unwrap()),
+                    repeat_while: Some(parse_expression("count < 3").unwrap()),
+                    timeout: None,
+                    body: vec![block(
+                        "count += 1; Write32(0x20000000 + count * Step, count);",
+                    )],
+                },
+                block("Sequence(\"Abort\");"),
+            ],
+        );
+        program
+            .sequences
+            .insert("Abort".to_string(), vec![block("DAP_WriteABORT(0x1E);")]);
+
+        let mut interface = MockInterface::default();
+        program.run("ResetSystem", &mut interface, 0).unwrap();
+
+        assert_eq!(interface.memory.get(&0x2000_0004), Some(&1));
+        assert_eq!(interface.memory.get(&0x2000_000C), Some(&3));
+        assert_eq!(interface.dp_writes, vec![(0x0, 0x1E)]);
+    }
+
+    #[test]
+    fn errors_are_ignored_with_errorcontrol() {
+        let mut program = Program::default();
+        program.sequences.insert(
+            "DebugPortStart".to_string(),
+            vec![block(
+                "__errorcontrol = 1; __var dpidr = ReadDP(0x0); __errorcontrol = 0; Write8(0x10, dpidr + 1);",
+            )],
+        );
+        program
+            .sequences
+            .insert("Failing".to_string(), vec![block("ReadDP(0x0);")]);
+
+        let mut interface = MockInterface::default();
+        program.run("DebugPortStart", &mut interface, 0).unwrap();
+        assert_eq!(interface.memory.get(&0x10), Some(&1));
+
+        assert!(program.run("Failing", &mut interface, 0).is_err());
+    }
+
+    #[test]
+    fn format_message_conversions() {
+        assert_eq!(
+            format_message("IDR %08X, %d%% done, %u", &[0x2ba0_1477, 50]),
+            "IDR 2BA01477, 50% done, 0"
+        );
+    }
+}
@@ -0,0 +1,598 @@
+//! Debug sequences from the debug description of a CMSIS-Pack.
+//!
+//! Instead of implementing [`ArmDebugSequence`] in Rust, a target description can contain
+//! the sequences of the [CMSIS debug description], which are executed by an interpreter.
+
+mod interpreter;
+mod parser;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use probe_rs_target::{CoreType, DebugSequenceDescription, SequenceElement};
+
+use self::interpreter::{Element, Program, SequenceInterface};
+use super::ArmDebugSequence;
+use crate::architecture::arm::ap::{AccessPort, MemoryAp};
+use crate::architecture::arm::communication_interface::{DapProbe, Initialized};
+use crate::architecture::arm::{
+    ApAddress, ArmCommunicationInterface, ArmProbeInterface, DapAccess, DpAddress, PortType,
+};
+use crate::{DebugProbeError, Error, Memory, WireProtocol};
+
+/// An error in the debug sequences of a target description.
+#[derive(thiserror::Error, Debug)]
+pub enum PackSequenceError {
+    /// The code of a sequence could not be parsed.
+    #[error("The debug sequence {sequence} is invalid: {message}")]
+    InvalidSequence {
+        /// The name of the sequence.
+        sequence: String,
+        /// A description of the syntax error.
+        message: String,
+    },
+    /// A sequence which is not described was called.
+    #[error("The debug sequence {0} does not exist")]
+    UnknownSequence(String),
+    /// A variable was used before it was declared.
+    #[error("The variable {0} is not declared")]
+    UndefinedVariable(String),
+    /// A string was used as a value.
+    #[error("A string can only be used as the argument of a function")]
+    UnexpectedString,
+    /// A function was called without a string argument where it expects one.
+    #[error("The function {0} expects a string argument")]
+    ExpectedString(String),
+    /// A division by zero.
+    #[error("Division by zero in a debug sequence")]
+    DivisionByZero,
+    /// The sequences call each other too deeply, usually because of an endless recursion.
+    #[error("The debug sequences are nested too deeply")]
+    NestingTooDeep,
+    /// A function is not available at the point where the sequence is executed.
+    #[error("{0} is not available in this debug sequence")]
+    NotAvailable(&'static str),
+    /// Debug sequences were given for a target which is not an ARM target.
+    #[error("Debug sequences are only supported for ARM targets")]
+    UnsupportedArchitecture,
+    /// The sequence reported an error with `Message`.
+    #[error("The debug sequence reported an error: {0}")]
+    Error(String),
+}
+
+/// Executes the debug sequences of a target description.
+///
+/// Sequences which are not described fall back to another [`ArmDebugSequence`],
+/// usually the built-in sequence of the chip.
+pub struct PackDebugSequence {
+    program: Program,
+    fallback: Arc<dyn ArmDebugSequence>,
+}
+
+impl PackDebugSequence {
+    /// Parse the `sequences`, with the declarations of their global variables in `debug_vars`.
+    ///
+    /// Sequences which are not described are executed by `fallback`.
+    pub fn create(
+        debug_vars: Option<&str>,
+        sequences: &[DebugSequenceDescription],
+        fallback: Arc<dyn ArmDebugSequence>,
+    ) -> Result<Arc<dyn ArmDebugSequence>, PackSequenceError> {
+        let mut program = Program::default();
+
+        if let Some(debug_vars) = debug_vars {
+            program.debug_vars = parser::parse_block(debug_vars).map_err(|message| {
+                PackSequenceError::InvalidSequence {
+                    sequence: "<debugvars>".to_string(),
+                    message,
+                }
+            })?;
+        }
+
+        for sequence in sequences {
+            let body = parse_elements(&sequence.body).map_err(|message| {
+                PackSequenceError::InvalidSequence {
+                    sequence: sequence.name.clone(),
+                    message,
+                }
+            })?;
+            program.sequences.insert(sequence.name.clone(), body);
+        }
+
+        Ok(Arc::new(Self { program, fallback }))
+    }
+}
+
+fn parse_elements(elements: &[SequenceElement]) -> Result<Vec<Element>, String> {
+    elements
+        .iter()
+        .map(|element| {
+            Ok(match element {
+                SequenceElement::Block(block) => Element::Block(parser::parse_block(&block.code)?),
+                SequenceElement::Control(control) => Element::Control {
+                    condition: control
+                        .condition
+                        .as_deref()
+                        .map(parser::parse_expression)
+                        .transpose()?,
+                    repeat_while: control
+                        .repeat_while
+                        .as_deref()
+                        .map(parser::parse_expression)
+                        .transpose()?,
+                    timeout: control
+                        .timeout
+                        .filter(|&timeout| timeout != 0)
+                        .map(Duration::from_micros),
+                    body: parse_elements(&control.body)?,
+                },
+            })
+        })
+        .collect()
+}
+
+impl ArmDebugSequence for PackDebugSequence {
+    fn reset_hardware_assert(&self, interface: &mut dyn DapProbe) -> Result<(), Error> {
+        if !self.program.contains("ResetHardwareAssert") {
+            return self.fallback.reset_hardware_assert(interface);
+        }
+
+        self.program
+            .run("ResetHardwareAssert", &mut RawInterface(interface), 0)
+    }
+
+    fn reset_hardware_deassert(&self, memory: &mut Memory) -> Result<(), Error> {
+        if !self.program.contains("ResetHardwareDeassert") {
+            return self.fallback.reset_hardware_deassert(memory);
+        }
+
+        let ap = memory.get_ap().ap;
+        self.program.run("ResetHardwareDeassert", memory, ap)
+    }
+
+    fn debug_port_setup(&self, interface: &mut Box<dyn DapProbe>) -> Result<(), Error> {
+        if !self.program.contains("DebugPortSetup") {
+            return self.fallback.debug_port_setup(interface);
+        }
+
+        self.program
+            .run("DebugPortSetup", &mut RawInterface(interface.as_mut()), 0)
+    }
+
+    fn debug_port_start(
+        &self,
+        interface: &mut ArmCommunicationInterface<Initialized>,
+        dp: DpAddress,
+    ) -> Result<(), DebugProbeError> {
+        if !self.program.contains("DebugPortStart") {
+            return self.fallback.debug_port_start(interface, dp);
+        }
+
+        self.program
+            .run("DebugPortStart", &mut ProbeInterface { interface, dp }, 0)
+            .map_err(|error| match error {
+                Error::Probe(error) => error,
+                error => DebugProbeError::ArchitectureSpecific(Box::new(error)),
+            })
+    }
+
+    fn debug_core_start(
+        &self,
+        core: &mut Memory,
+        core_type: CoreType,
+        debug_base: Option<u64>,
+        cti_base: Option<u64>,
+    ) -> Result<(), Error> {
+        if !self.program.contains("DebugCoreStart") {
+            return self
+                .fallback
+                .debug_core_start(core, core_type, debug_base, cti_base);
+        }
+
+        let ap = core.get_ap().ap;
+        self.program.run("DebugCoreStart", core, ap)
+    }
+
+    fn reset_catch_set(
+        &self,
+        core: &mut Memory,
+        core_type: CoreType,
+        debug_base: Option<u64>,
+    ) -> Result<(), Error> {
+        if !self.program.contains("ResetCatchSet") {
+            return self.fallback.reset_catch_set(core, core_type, debug_base);
+        }
+
+        let ap = core.get_ap().ap;
+        self.program.run("ResetCatchSet", core, ap)
+    }
+
+    fn reset_catch_clear(
+        &self,
+        core: &mut Memory,
+        core_type: CoreType,
+        debug_base: Option<u64>,
+    ) -> Result<(), Error> {
+        if !self.program.contains("ResetCatchClear") {
+            return self.fallback.reset_catch_clear(core, core_type, debug_base);
+        }
+
+        let ap = core.get_ap().ap;
+        self.program.run("ResetCatchClear", core, ap)
+    }
+
+    fn reset_system(
+        &self,
+        interface: &mut Memory,
+        core_type: CoreType,
+        debug_base: Option<u64>,
+    ) -> Result<(), Error> {
+        if !self.program.contains("ResetSystem") {
+            return self.fallback.reset_system(interface, core_type, debug_base);
+        }
+
+        let ap = interface.get_ap().ap;
+        self.program.run("ResetSystem", interface, ap)
+    }
+
+    fn debug_device_unlock(
+        &self,
+        interface: &mut Box<dyn ArmProbeInterface>,
+        default_ap: MemoryAp,
+        permissions: &crate::Permissions,
+    ) -> Result<(), Error> {
+        if !self.program.contains("DebugDeviceUnlock") {
+            return self
+                .fallback
+                .debug_device_unlock(interface, default_ap, permissions);
+        }
+
+        let ApAddress { dp, ap } = default_ap.ap_address();
+        self.program.run(
+            "DebugDeviceUnlock",
+            &mut ProbeInterface {
+                interface: interface.as_mut(),
+                dp,
+            },
+            ap,
+        )
+    }
+
+    fn debug_erase_all(
+        &self,
+        interface: &mut Box<dyn ArmProbeInterface>,
+        default_ap: MemoryAp,
+    ) -> Result<bool, Error> {
+        self.fallback.debug_erase_all(interface, default_ap)
+    }
+
+    fn recover_support_start(&self, interface: &mut Memory) -> Result<(), Error> {
+        if !self.program.contains("RecoverSupportStart") {
+            return self.fallback.recover_support_start(interface);
+        }
+
+        let ap = interface.get_ap().ap;
+        self.program.run("RecoverSupportStart", interface, ap)
+    }
+
+    fn debug_core_stop(&self, interface: &mut Box<dyn ArmProbeInterface>) -> Result<(), Error> {
+        if !self.program.contains("DebugCoreStop") {
+            return self.fallback.debug_core_stop(interface);
+        }
+
+        self.program.run(
+            "DebugCoreStop",
+            &mut ProbeInterface {
+                interface: interface.as_mut(),
+                dp: DpAddress::Default,
+            },
+            0,
+        )
+    }
+
+    fn debug_port_stop(
+        &self,
+        interface: &mut Box<dyn ArmProbeInterface>,
+        dp: DpAddress,
+    ) -> Result<(), Error> {
+        if !self.program.contains("DebugPortStop") {
+            return self.fallback.debug_port_stop(interface, dp);
+        }
+
+        self.program.run(
+            "DebugPortStop",
+            &mut ProbeInterface {
+                interface: interface.as_mut(),
+                dp,
+            },
+            0,
+        )
+    }
+
+    fn flash_start(
+        &self,
+        interface: &mut Box<dyn ArmProbeInterface>,
+        default_ap: MemoryAp,
+    ) -> Result<(), Error> {
+        if !self.program.contains("FlashStart") {
+            return self.fallback.flash_start(interface, default_ap);
+        }
+
+        let ApAddress { dp, ap } = default_ap.ap_address();
+        self.program.run(
+            "FlashStart",
+            &mut ProbeInterface {
+                interface: interface.as_mut(),
+                dp,
+            },
+            ap,
+        )
+    }
+
+    fn watchdog_suspend(
+        &self,
+        interface: &mut Box<dyn ArmProbeInterface>,
+        default_ap: MemoryAp,
+    ) -> Result<(), Error> {
+        if !self.program.contains("WatchdogSuspend") {
+            return self.fallback.watchdog_suspend(interface, default_ap);
+        }
+
+        let ApAddress { dp, ap } = default_ap.ap_address();
+        self.program.run(
+            "WatchdogSuspend",
+            &mut ProbeInterface {
+                interface: interface.as_mut(),
+                dp,
+            },
+            ap,
+        )
+    }
+
+    fn trace_start(
+        &self,
+        interface: &mut Box<dyn ArmProbeInterface>,
+        default_ap: MemoryAp,
+    ) -> Result<(), Error> {
+        if !self.program.contains("TraceStart") {
+            return self.fallback.trace_start(interface, default_ap);
+        }
+
+        let ApAddress { dp, ap } = default_ap.ap_address();
+        self.program.run(
+            "TraceStart",
+            &mut ProbeInterface {
+                interface: interface.as_mut(),
+                dp,
+            },
+            ap,
+        )
+    }
+
+    fn trace_stop(
+        &self,
+        interface: &mut Box<dyn ArmProbeInterface>,
+        default_ap: MemoryAp,
+    ) -> Result<(), Error> {
+        if !self.program.contains("TraceStop") {
+            return self.fallback.trace_stop(interface, default_ap);
+        }
+
+        let ApAddress { dp, ap } = default_ap.ap_address();
+        self.program.run(
+            "TraceStop",
+            &mut ProbeInterface {
+                interface: interface.as_mut(),
+                dp,
+            },
+            ap,
+        )
+    }
+}
+
+fn read_memory(memory: &mut Memory, address: u64, size: u8) -> Result<u64, Error> {
+    Ok(match size {
+        8 => memory.read_word_8(address)? as u64,
+        16 => {
+            let mut data = [0; 2];
+            memory.read_8(address, &mut data)?;
+            u16::from_le_bytes(data) as u64
+        }
+        32 => memory.read_word_32(address)? as u64,
+        _ => memory.read_word_64(address)?,
+    })
+}
+
+fn write_memory(memory: &mut Memory, address: u64, size: u8, value: u64) -> Result<(), Error> {
+    match size {
+        8 => memory.write_word_8(address, value as u8),
+        16 => memory.write_8(address, &(value as u16).to_le_bytes()),
+        32 => memory.write_word_32(address, value as u32),
+        _ => memory.write_word_64(address, value),
+    }
+}
+
+/// Sequences executed with access to the memory of a core.
+impl SequenceInterface for Memory<'_> {
+    fn read_memory(&mut self, ap: u8, address: u64, size: u8) -> Result<u64, Error> {
+        let own_ap = self.get_ap();
+        if ap == own_ap.ap {
+            return read_memory(self, address, size);
+        }
+
+        let mut memory = self
+            .get_arm_interface()?
+            .memory_interface(MemoryAp::new(ApAddress { dp: own_ap.dp, ap }))?;
+        read_memory(&mut memory, address, size)
+    }
+
+    fn write_memory(&mut self, ap: u8, address: u64, size: u8, value: u64) -> Result<(), Error> {
+        let own_ap = self.get_ap();
+        if ap == own_ap.ap {
+            return write_memory(self, address, size, value);
+        }
+
+        let mut memory = self
+            .get_arm_interface()?
+            .memory_interface(MemoryAp::new(ApAddress { dp: own_ap.dp, ap }))?;
+        write_memory(&mut memory, address, size, value)
+    }
+
+    fn read_dp(&mut self, address: u8) -> Result<u32, Error> {
+        let dp = self.get_ap().dp;
+        Ok(self
+            .get_arm_interface()?
+            .read_raw_dp_register(dp, address)?)
+    }
+
+    fn write_dp(&mut self, address: u8, value: u32) -> Result<(), Error> {
+        let dp = self.get_ap().dp;
+        Ok(self
+            .get_arm_interface()?
+            .write_raw_dp_register(dp, address, value)?)
+    }
+
+    fn read_ap(&mut self, ap: u8, address: u8) -> Result<u32, Error> {
+        let dp = self.get_ap().dp;
+        Ok(self
+            .get_arm_interface()?
+            .read_raw_ap_register(ApAddress { dp, ap }, address)?)
+    }
+
+    fn write_ap(&mut self, ap: u8, address: u8, value: u32) -> Result<(), Error> {
+        let dp = self.get_ap().dp;
+        Ok(self
+            .get_arm_interface()?
+            .write_raw_ap_register(ApAddress { dp, ap }, address, value)?)
+    }
+
+    fn swj_pins(&mut self, pin_out: u32, pin_select: u32, pin_wait: u32) -> Result<u32, Error> {
+        self.get_arm_probe().swj_pins(pin_out, pin_select, pin_wait)
+    }
+
+    fn swj_sequence(&mut self, bit_len: u8, bits: u64) -> Result<(), Error> {
+        self.get_arm_probe().swj_sequence(bit_len, bits)
+    }
+
+    fn protocol(&mut self) -> Option<WireProtocol> {
+        self.get_arm_interface()
+            .ok()
+            .and_then(|interface| interface.probe_mut().active_protocol())
+    }
+}
+
+/// Sequences executed with an initialized debug port.
+struct ProbeInterface<'a> {
+    interface: &'a mut dyn ArmProbeInterface,
+    dp: DpAddress,
+}
+
+impl SequenceInterface for ProbeInterface<'_> {
+    fn read_memory(&mut self, ap: u8, address: u64, size: u8) -> Result<u64, Error> {
+        let mut memory = self
+            .interface
+            .memory_interface(MemoryAp::new(ApAddress { dp: self.dp, ap }))?;
+        read_memory(&mut memory, address, size)
+    }
+
+    fn write_memory(&mut self, ap: u8, address: u64, size: u8, value: u64) -> Result<(), Error> {
+        let mut memory = self
+            .interface
+            .memory_interface(MemoryAp::new(ApAddress { dp: self.dp, ap }))?;
+        write_memory(&mut memory, address, size, value)
+    }
+
+    fn read_dp(&mut self, address: u8) -> Result<u32, Error> {
+        Ok(self.interface.read_raw_dp_register(self.dp, address)?)
+    }
+
+    fn write_dp(&mut self, address: u8, value: u32) -> Result<(), Error> {
+        Ok(self
+            .interface
+            .write_raw_dp_register(self.dp, address, value)?)
+    }
+
+    fn read_ap(&mut self, ap: u8, address: u8) -> Result<u32, Error> {
+        Ok(self
+            .interface
+            .read_raw_ap_register(ApAddress { dp: self.dp, ap }, address)?)
+    }
+
+    fn write_ap(&mut self, ap: u8, address: u8, value: u32) -> Result<(), Error> {
+        Ok(self
+            .interface
+            .write_raw_ap_register(ApAddress { dp: self.dp, ap }, address, value)?)
+    }
+
+    fn swj_pins(&mut self, pin_out: u32, pin_select: u32, pin_wait: u32) -> Result<u32, Error> {
+        self.interface.swj_pins(pin_out, pin_select, pin_wait)
+    }
+
+    fn swj_sequence(&mut self, bit_len: u8, bits: u64) -> Result<(), Error> {
+        self.interface.swj_sequence(bit_len, bits)
+    }
+
+    fn protocol(&mut self) -> Option<WireProtocol> {
+        self.interface.probe_mut().active_protocol()
+    }
+}
+
+/// Sequences executed before the debug port is initialized, with raw access to the probe.
+struct RawInterface<'a>(&'a mut dyn DapProbe);
+
+/// The address of the SELECT register of the debug port.
+const SELECT: u8 = 0x8;
+
+impl SequenceInterface for RawInterface<'_> {
+    fn read_memory(&mut self, _ap: u8, _address: u64, _size: u8) -> Result<u64, Error> {
+        Err(Error::architecture_specific(
+            PackSequenceError::NotAvailable("Memory access"),
+        ))
+    }
+
+    fn write_memory(
+        &mut self,
+        _ap: u8,
+        _address: u64,
+        _size: u8,
+        _value: u64,
+    ) -> Result<(), Error> {
+        Err(Error::architecture_specific(
+            PackSequenceError::NotAvailable("Memory access"),
+        ))
+    }
+
+    fn read_dp(&mut self, address: u8) -> Result<u32, Error> {
+        Ok(self.0.raw_read_register(PortType::DebugPort, address)?)
+    }
+
+    fn write_dp(&mut self, address: u8, value: u32) -> Result<(), Error> {
+        Ok(self
+            .0
+            .raw_write_register(PortType::DebugPort, address, value)?)
+    }
+
+    fn read_ap(&mut self, ap: u8, address: u8) -> Result<u32, Error> {
+        self.write_dp(SELECT, (ap as u32) << 24 | (address & 0xF0) as u32)?;
+        Ok(self.0.raw_read_register(PortType::AccessPort, address)?)
+    }
+
+    fn write_ap(&mut self, ap: u8, address: u8, value: u32) -> Result<(), Error> {
+        self.write_dp(SELECT, (ap as u32) << 24 | (address & 0xF0) as u32)?;
+        Ok(self
+            .0
+            .raw_write_register(PortType::AccessPort, address, value)?)
+    }
+
+    fn swj_pins(&mut self, pin_out: u32, pin_select: u32, pin_wait: u32) -> Result<u32, Error> {
+        Ok(self.0.swj_pins(pin_out, pin_select, pin_wait)?)
+    }
+
+    fn swj_sequence(&mut self, bit_len: u8, bits: u64) -> Result<(), Error> {
+        Ok(self.0.swj_sequence(bit_len, bits)?)
+    }
+
+    fn protocol(&mut self) -> Option<WireProtocol> {
+        self.0.active_protocol()
+    }
+}
@@ -0,0 +1,450 @@
+//! Parser for the expression language of the CMSIS debug description.
+//!
+//! The language is a small subset of C. All values are unsigned 64-bit integers,
+//! string literals are only allowed as arguments of functions like `Message`.
+
+/// A binary operator.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum BinaryOp {
+    Mul,
+    Div,
+    Rem,
+    Add,
+    Sub,
+    Shl,
+    Shr,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+    BitAnd,
+    BitXor,
+    BitOr,
+    And,
+    Or,
+}
+
+impl BinaryOp {
+    /// The binding strength of the operator, higher binds stronger.
+    fn precedence(self) -> u8 {
+        match self {
+            BinaryOp::Or => 1,
+            BinaryOp::And => 2,
+            BinaryOp::BitOr => 3,
+            BinaryOp::BitXor => 4,
+            BinaryOp::BitAnd => 5,
+            BinaryOp::Eq | BinaryOp::Ne => 6,
+            BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => 7,
+            BinaryOp::Shl | BinaryOp::Shr => 8,
+            BinaryOp::Add | BinaryOp::Sub => 9,
+            BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => 10,
+        }
+    }
+
+    fn from_token(token: &str) -> Option<Self> {
+        Some(match token {
+            "*" => BinaryOp::Mul,
+            "/" => BinaryOp::Div,
+            "%" => BinaryOp::Rem,
+            "+" => BinaryOp::Add,
+            "-" => BinaryOp::Sub,
+            "<<" => BinaryOp::Shl,
+            ">>" => BinaryOp::Shr,
+            "<" => BinaryOp::Lt,
+            "<=" => BinaryOp::Le,
+            ">" => BinaryOp::Gt,
+            ">=" => BinaryOp::Ge,
+            "==" => BinaryOp::Eq,
+            "!=" => BinaryOp::Ne,
+            "&" => BinaryOp::BitAnd,
+            "^" => BinaryOp::BitXor,
+            "|" => BinaryOp::BitOr,
+            "&&" => BinaryOp::And,
+            "||" => BinaryOp::Or,
+            _ => return None,
+        })
+    }
+}
+
+/// A unary operator.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum UnaryOp {
+    Not,
+    BitNot,
+    Neg,
+}
+
+/// An expression.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Expr {
+    Number(u64),
+    String(String),
+    Variable(String),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+/// A statement of a block.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Statement {
+    /// `__var name = value;`
+    Declare(String, Expr),
+    /// `name = value;`, or a compound assignment like `name |= value;`.
+    Assign(String, Option<BinaryOp>, Expr),
+    /// An expression whose value is discarded, usually a function call.
+    Expr(Expr),
+}
+
+/// The functions which can be called from a sequence, with their number of arguments.
+///
+/// `Message` takes a variable number of arguments, at least two.
+const FUNCTIONS: &[(&str, usize)] = &[
+    ("Read8", 1),
+    ("Read16", 1),
+    ("Read32", 1),
+    ("Read64", 1),
+    ("ReadAP", 1),
+    ("ReadDP", 1),
+    ("Write8", 2),
+    ("Write16", 2),
+    ("Write32", 2),
+    ("Write64", 2),
+    ("WriteAP", 2),
+    ("WriteDP", 2),
+    ("DAP_Delay", 1),
+    ("DAP_WriteABORT", 1),
+    ("DAP_SWJ_Pins", 3),
+    ("DAP_SWJ_Clock", 1),
+    ("DAP_SWJ_Sequence", 2),
+    ("DAP_JTAG_Sequence", 3),
+    ("Sequence", 1),
+    ("Query", 3),
+    ("QueryValue", 2),
+    ("LoadDebugInfo", 1),
+    ("Message", 2),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(u64),
+    Ident(String),
+    String(String),
+    Punct(&'static str),
+}
+
+/// All punctuators, longer ones first so they take precedence.
+const PUNCTUATORS: &[&str] = &[
+    "<<=", ">>=", "&&", "||", "==", "!=", "<=", ">=", "<<", ">>", "+=", "-=", "*=", "/=", "%=",
+    "&=", "|=", "^=", "+", "-", "*", "/", "%", "&", "|", "^", "!", "~", "<", ">", "=", "(", ")",
+    ",", ";",
+];
+
+fn tokenize(code: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = code;
+
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+        } else if let Some(comment) = rest.strip_prefix("//") {
+            rest = comment.find('\n').map_or("", |end| &comment[end..]);
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            let end = comment
+                .find("*/")
+                .ok_or_else(|| "unterminated comment".to_string())?;
+            rest = &comment[end + 2..];
+        } else if c.is_ascii_digit() {
+            let end = rest
+                .find(|c: char| !c.is_ascii_alphanumeric())
+                .unwrap_or(rest.len());
+            let literal = &rest[..end];
+            let value = match literal
+                .strip_prefix("0x")
+                .or_else(|| literal.strip_prefix("0X"))
+            {
+                Some(hex) => u64::from_str_radix(hex, 16),
+                None => literal.parse(),
+            }
+            .map_err(|_| format!("invalid number `{}`", literal))?;
+            tokens.push(Token::Number(value));
+            rest = &rest[end..];
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Ident(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else if c == '"' {
+            let mut string = String::new();
+            let mut chars = rest[1..].char_indices();
+            let end = loop {
+                match chars.next() {
+                    Some((i, '"')) => break i + 2,
+                    Some((_, '\\')) => match chars.next() {
+                        Some((_, 'n')) => string.push('\n'),
+                        Some((_, 't')) => string.push('\t'),
+                        Some((_, c)) => string.push(c),
+                        None => return Err("unterminated string".to_string()),
+                    },
+                    Some((_, c)) => string.push(c),
+                    None => return Err("unterminated string".to_string()),
+                }
+            };
+            tokens.push(Token::String(string));
+            rest = &rest[end..];
+        } else {
+            let punctuator = PUNCTUATORS
+                .iter()
+                .find(|p| rest.starts_with(*p))
+                .ok_or_else(|| format!("unexpected character `{}`", c))?;
+            tokens.push(Token::Punct(punctuator));
+            rest = &rest[punctuator.len()..];
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Parse the statements of a `<block>` element.
+pub(super) fn parse_block(code: &str) -> Result<Vec<Statement>, String> {
+    let mut parser = Parser {
+        tokens: tokenize(code)?,
+        position: 0,
+    };
+
+    let mut statements = Vec::new();
+    while parser.peek().is_some() {
+        statements.push(parser.statement()?);
+    }
+
+    Ok(statements)
+}
+
+/// Parse a single expression, like the condition of a `<control>` element.
+pub(super) fn parse_expression(code: &str) -> Result<Expr, String> {
+    let mut parser = Parser {
+        tokens: tokenize(code)?,
+        position: 0,
+    };
+
+    let expr = parser.expression(0)?;
+    match parser.peek() {
+        None => Ok(expr),
+        Some(token) => Err(format!("unexpected {:?} after the expression", token)),
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Result<Token, String> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or_else(|| "unexpected end of the code".to_string())?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, punctuator: &str) -> Result<(), String> {
+        match self.next()? {
+            Token::Punct(p) if p == punctuator => Ok(()),
+            token => Err(format!("expected `{}`, found {:?}", punctuator, token)),
+        }
+    }
+
+    fn statement(&mut self) -> Result<Statement, String> {
+        let statement = match (self.peek(), self.tokens.get(self.position + 1)) {
+            (Some(Token::Ident(keyword)), _) if keyword == "__var" => {
+                self.position += 1;
+                let name = match self.next()? {
+                    Token::Ident(name) => name,
+                    token => return Err(format!("expected a variable name, found {:?}", token)),
+                };
+                self.expect("=")?;
+                Statement::Declare(name, self.expression(0)?)
+            }
+            (Some(Token::Ident(name)), Some(Token::Punct(assignment)))
+                if assignment.ends_with('=')
+                    && !matches!(*assignment, "==" | "!=" | "<=" | ">=") =>
+            {
+                let name = name.clone();
+                let operator = match &assignment[..assignment.len() - 1] {
+                    "" => None,
+                    operator => BinaryOp::from_token(operator),
+                };
+                self.position += 2;
+                Statement::Assign(name, operator, self.expression(0)?)
+            }
+            _ => Statement::Expr(self.expression(0)?),
+        };
+        self.expect(";")?;
+
+        Ok(statement)
+    }
+
+    /// Parse an expression containing only binary operators binding stronger than `min_precedence`.
+    fn expression(&mut self, min_precedence: u8) -> Result<Expr, String> {
+        let mut lhs = self.unary()?;
+
+        while let Some(Token::Punct(token)) = self.peek() {
+            let operator = match BinaryOp::from_token(token) {
+                Some(operator) if operator.precedence() > min_precedence => operator,
+                _ => break,
+            };
+            self.position += 1;
+            let rhs = self.expression(operator.precedence())?;
+            lhs = Expr::Binary(operator, Box::new(lhs), Box::new(rhs));
+        }
+
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        match self.next()? {
+            Token::Number(value) => Ok(Expr::Number(value)),
+            Token::String(string) => Ok(Expr::String(string)),
+            Token::Ident(name) => {
+                if self.peek() != Some(&Token::Punct("(")) {
+                    return Ok(Expr::Variable(name));
+                }
+                self.position += 1;
+
+                let mut arguments = Vec::new();
+                if self.peek() == Some(&Token::Punct(")")) {
+                    self.position += 1;
+                } else {
+                    loop {
+                        arguments.push(self.expression(0)?);
+                        match self.next()? {
+                            Token::Punct(")") => break,
+                            Token::Punct(",") => continue,
+                            token => return Err(format!("expected `,` or `)`, found {:?}", token)),
+                        }
+                    }
+                }
+
+                check_call(&name, arguments.len())?;
+                Ok(Expr::Call(name, arguments))
+            }
+            Token::Punct("(") => {
+                let expr = self.expression(0)?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Token::Punct("!") => Ok(Expr::Unary(UnaryOp::Not, Box::new(self.unary()?))),
+            Token::Punct("~") => Ok(Expr::Unary(UnaryOp::BitNot, Box::new(self.unary()?))),
+            Token::Punct("-") => Ok(Expr::Unary(UnaryOp::Neg, Box::new(self.unary()?))),
+            Token::Punct("+") => self.unary(),
+            token => Err(format!("unexpected {:?}", token)),
+        }
+    }
+}
+
+/// Check that `name` is a known function, and that it is called with the right number of arguments.
+fn check_call(name: &str, arguments: usize) -> Result<(), String> {
+    let expected = FUNCTIONS
+        .iter()
+        .find(|(function, _)| *function == name)
+        .map(|(_, expected)| *expected)
+        .ok_or_else(|| format!("unknown function `{}`", name))?;
+
+    let valid = match name {
+        "Message" => arguments >= expected,
+        _ => arguments == expected,
+    };
+    if !valid {
+        return Err(format!(
+            "`{}` expects {} arguments, but {} were given",
+            name, expected, arguments
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn operators_follow_c_precedence() {
+        assert_eq!(
+            parse_expression("1 + 2 * 3 == 7 && ~0 != 0").unwrap(),
+            Expr::Binary(
+                BinaryOp::And,
+                Box::new(Expr::Binary(
+                    BinaryOp::Eq,
+                    Box::new(Expr::Binary(
+                        BinaryOp::Add,
+                        Box::new(Expr::Number(1)),
+                        Box::new(Expr::Binary(
+                            BinaryOp::Mul,
+                            Box::new(Expr::Number(2)),
+                            Box::new(Expr::Number(3))
+                        ))
+                    )),
+                    Box::new(Expr::Number(7))
+                )),
+                Box::new(Expr::Binary(
+                    BinaryOp::Ne,
+                    Box::new(Expr::Unary(UnaryOp::BitNot, Box::new(Expr::Number(0)))),
+                    Box::new(Expr::Number(0))
+                ))
+            )
+        );
+    }
+
+    #[test]
+    fn parse_statements() {
+        let statements = parse_block(
+            "__var value = Read32(0xE000EDF0); // DHCSR
+             value |= 1 << 2; /* C_HALT */
+             Write32(0xE000EDF0, value);",
+        )
+        .unwrap();
+
+        assert_eq!(
+            statements,
+            vec![
+                Statement::Declare(
+                    "value".to_string(),
+                    Expr::Call("Read32".to_string(), vec![Expr::Number(0xE000_EDF0)])
+                ),
+                Statement::Assign(
+                    "value".to_string(),
+                    Some(BinaryOp::BitOr),
+                    Expr::Binary(
+                        BinaryOp::Shl,
+                        Box::new(Expr::Number(1)),
+                        Box::new(Expr::Number(2))
+                    )
+                ),
+                Statement::Expr(Expr::Call(
+                    "Write32".to_string(),
+                    vec![
+                        Expr::Number(0xE000_EDF0),
+                        Expr::Variable("value".to_string())
+                    ]
+                )),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_functions_are_rejected() {
+        assert!(parse_block("Frobnicate(1);").is_err());
+        assert!(parse_block("Write32(1);").is_err());
+    }
+}
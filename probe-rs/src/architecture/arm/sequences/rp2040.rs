@@ -0,0 +1,79 @@
+//! Sequences for the Raspberry Pi RP2040.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::ArmDebugSequence;
+use crate::architecture::arm::{
+    communication_interface::DapProbe,
+    core::armv7m::{Demcr, Dhcsr},
+    dp::Ctrl,
+    DpAddress, PortType, Register,
+};
+use crate::core::MemoryMappedRegister;
+use crate::Memory;
+
+/// The sequence handle for the RP2040.
+///
+/// The RP2040 has no dedicated reset pin on its debug connector. Instead, a reset is
+/// performed through its Rescue DP, which restarts the whole chip and makes the bootrom
+/// wait for the debugger instead of running the program in flash. This recovers chips
+/// whose program disables the debug access or crashes early, e.g. because of a bad
+/// second stage bootloader.
+pub struct Rp2040(());
+
+impl Rp2040 {
+    /// The TARGETSEL value of the Rescue DP.
+    const RESCUE_DP: DpAddress = DpAddress::Multidrop(0xF100_2927);
+
+    /// Create a new sequence handle for the RP2040.
+    pub fn create() -> Arc<dyn ArmDebugSequence> {
+        Arc::new(Self(()))
+    }
+}
+
+impl ArmDebugSequence for Rp2040 {
+    fn reset_hardware_assert(&self, interface: &mut dyn DapProbe) -> Result<(), crate::Error> {
+        // The Rescue DP is selected with a multidrop SWD sequence, which needs an attached probe.
+        // The probe is attached again when the session is set up. This is safe, as attaching
+        // only configures the SWD port of the probe, and the DP of the core is selected again
+        // afterwards anyway.
+        interface.attach()?;
+        interface.select_dp(Self::RESCUE_DP)?;
+
+        // Requesting a debug power up from the Rescue DP resets the chip, and sets the flag
+        // which stops the bootrom after the reset.
+        let mut ctrl = Ctrl(0);
+        ctrl.set_cdbgpwrupreq(true);
+        ctrl.set_csyspwrupreq(true);
+        interface.raw_write_register(PortType::DebugPort, Ctrl::ADDRESS, ctrl.into())?;
+
+        std::thread::sleep(Duration::from_millis(10));
+
+        // The Rescue DP is not reset with the chip, so the request has to be cleared again.
+        interface.raw_write_register(PortType::DebugPort, Ctrl::ADDRESS, 0)?;
+
+        log::info!("Performed a rescue reset of the RP2040");
+
+        Ok(())
+    }
+
+    fn reset_hardware_deassert(&self, memory: &mut Memory) -> Result<(), crate::Error> {
+        // The reset has already happened, so the reset vector catch which is set if the core
+        // should be halted does not trigger. The bootrom waits for the debugger after a rescue
+        // reset, so the core is halted there instead.
+        let demcr = Demcr(memory.read_word_32(Demcr::ADDRESS)?);
+        if !demcr.vc_corereset() {
+            return Ok(());
+        }
+
+        let mut dhcsr = Dhcsr(0);
+        dhcsr.set_c_halt(true);
+        dhcsr.set_c_debugen(true);
+        dhcsr.enable_write();
+
+        memory.write_word_32(Dhcsr::ADDRESS, dhcsr.into())?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,330 @@
+//! Sequences for the Silicon Labs EFM32 and EFR32 series.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::{ArmDebugSequence, DefaultArmSequence};
+use crate::architecture::arm::{
+    ap::{AccessPort, MemoryAp, CSW},
+    communication_interface::{Initialized, SwdSequence},
+    dp::{DpAccess, DPIDR},
+    ApAddress, ArmCommunicationInterface, ArmProbeInterface, DapAccess, DpAddress, Pins,
+};
+use crate::Memory;
+
+/// An error which occurred while unlocking an EFM32 or EFR32.
+#[derive(thiserror::Error, Debug)]
+pub enum SiliconLabsError {
+    /// The debug access is locked and the chip may not be erased.
+    #[error("The debug access of the chip is locked. It has to be erased to unlock it, which requires the erase_all permission.")]
+    Locked,
+    /// The Authentication Access Port could not be reached after a reset.
+    #[error("The Authentication Access Port (AAP) could not be reached after resetting the chip")]
+    AapNotFound,
+    /// The device erase did not finish in time.
+    #[error("Timeout while waiting for the device erase")]
+    Timeout,
+    /// The Secure Engine rejected the device erase.
+    #[error("The Secure Engine rejected the device erase with the status {0:#010x}. The device erase may be disabled.")]
+    EraseRejected(u32),
+    /// The debug access is still locked after the device erase.
+    #[error("The debug access of the chip is still locked after the device erase")]
+    StillLocked,
+}
+
+/// The series of the EFM32 and EFR32 chips, which are unlocked differently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Series {
+    /// Series 0 and 1, which are unlocked through the Authentication Access Port (AAP).
+    Aap,
+    /// Series 2, which are unlocked by the Secure Engine through the Debug Challenge Interface.
+    SecureEngine,
+}
+
+impl Series {
+    fn from_chip_name(name: &str) -> Option<Self> {
+        // E.g. EFR32MG21A010F1024 or EFM32PG12B500F1024, the series follows the family letters.
+        match name.as_bytes().get(7) {
+            Some(b'1') => Some(Series::Aap),
+            Some(b'2') => Some(Series::SecureEngine),
+            _ => None,
+        }
+    }
+}
+
+/// The sequence handle for the EFM32 and EFR32 series.
+///
+/// A chip whose debug access is locked is recovered by a device erase, which requires the
+/// `erase_all` permission.
+pub struct SiliconLabs {
+    series: Option<Series>,
+}
+
+impl SiliconLabs {
+    /// The IDR of the Authentication Access Port, which replaces the AHB-AP of locked chips.
+    const AAP_IDR: u32 = 0x16E6_0001;
+
+    const AAP_CMD: u8 = 0x00;
+    const AAP_CMDKEY: u8 = 0x04;
+    const AAP_STATUS: u8 = 0x08;
+    const AAP_IDR_ADDRESS: u8 = 0xFC;
+
+    const AAP_CMD_DEVICEERASE: u32 = 1 << 0;
+    const AAP_CMD_SYSRESETREQ: u32 = 1 << 1;
+    const AAP_CMDKEY_WRITEEN: u32 = 0xCFAC_C118;
+    const AAP_STATUS_ERASEBUSY: u32 = 1 << 0;
+
+    /// The access port number of the Secure Engine on Series 2.
+    const SE_AP: u8 = 1;
+    const DCI_WDATA: u64 = 0x1000;
+    const DCI_RDATA: u64 = 0x1004;
+    const DCI_STATUS: u64 = 0x1008;
+    const DCI_STATUS_WPENDING: u32 = 1 << 0;
+    const DCI_STATUS_RDATAVALID: u32 = 1 << 8;
+    /// The Debug Challenge Interface command to erase the device, preceded by its length.
+    const DCI_ERASE_DEVICE: [u32; 2] = [0x0000_0008, 0x430F_0000];
+
+    /// The AAP is only reachable in a short window after a reset, so the connection is retried.
+    const AAP_WINDOW_ATTEMPTS: usize = 10;
+    /// The time it takes at most to erase the whole chip.
+    const ERASE_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Create the sequence for the EFM32 or EFR32 with the given name.
+    pub fn create(chip_name: &str) -> Arc<dyn ArmDebugSequence> {
+        Arc::new(Self {
+            series: Series::from_chip_name(chip_name),
+        })
+    }
+
+    fn is_aap(
+        &self,
+        interface: &mut ArmCommunicationInterface<Initialized>,
+        ap: ApAddress,
+    ) -> bool {
+        matches!(
+            interface.read_raw_ap_register(ap, Self::AAP_IDR_ADDRESS),
+            Ok(Self::AAP_IDR)
+        )
+    }
+
+    fn is_unlocked(
+        &self,
+        interface: &mut ArmCommunicationInterface<Initialized>,
+        ap: ApAddress,
+    ) -> bool {
+        match self.series {
+            Some(Series::Aap) => !self.is_aap(interface, ap),
+            _ => match interface.read_raw_ap_register(ap, 0x00) {
+                Ok(csw) => CSW::from(csw).DeviceEn != 0,
+                Err(e) => {
+                    log::debug!("Failed to read the CSW of {:?}: {}", ap, e);
+                    false
+                }
+            },
+        }
+    }
+
+    /// Reset the chip and connect to it again right away, to reach the AAP in the short window
+    /// after the reset.
+    fn connect_after_reset(
+        &self,
+        interface: &mut ArmCommunicationInterface<Initialized>,
+        ap: ApAddress,
+    ) -> Result<(), crate::Error> {
+        let mut n_reset = Pins(0);
+        n_reset.set_nreset(true);
+        let n_reset = n_reset.0 as u32;
+
+        for _ in 0..Self::AAP_WINDOW_ATTEMPTS {
+            interface.swj_pins(0, n_reset, 0)?;
+            std::thread::sleep(Duration::from_millis(1));
+            interface.swj_pins(n_reset, n_reset, 0)?;
+
+            // Line reset, followed by reading the DPIDR to start the SWD interface.
+            interface.swj_sequence(51, 0x0007_FFFF_FFFF_FFFF)?;
+            interface.swj_sequence(3, 0x00)?;
+            if interface
+                .read_dp_register::<DPIDR>(DpAddress::Default)
+                .is_err()
+            {
+                continue;
+            }
+
+            if DefaultArmSequence(())
+                .debug_port_start(interface, DpAddress::Default)
+                .is_ok()
+                && self.is_aap(interface, ap)
+            {
+                return Ok(());
+            }
+        }
+
+        Err(crate::Error::architecture_specific(
+            SiliconLabsError::AapNotFound,
+        ))
+    }
+
+    /// Erase the device through the AAP of Series 0 and 1.
+    fn aap_device_erase(
+        &self,
+        interface: &mut ArmCommunicationInterface<Initialized>,
+        aap: ApAddress,
+    ) -> Result<(), crate::Error> {
+        if !self.is_aap(interface, aap) {
+            self.connect_after_reset(interface, aap)?;
+        }
+
+        interface.write_raw_ap_register(aap, Self::AAP_CMDKEY, Self::AAP_CMDKEY_WRITEEN)?;
+        interface.write_raw_ap_register(aap, Self::AAP_CMD, Self::AAP_CMD_DEVICEERASE)?;
+
+        let start = Instant::now();
+        while interface.read_raw_ap_register(aap, Self::AAP_STATUS)? & Self::AAP_STATUS_ERASEBUSY
+            != 0
+        {
+            if start.elapsed() > Self::ERASE_TIMEOUT {
+                return Err(crate::Error::architecture_specific(
+                    SiliconLabsError::Timeout,
+                ));
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        // The debug lock is only removed by a reset.
+        interface.write_raw_ap_register(aap, Self::AAP_CMDKEY, Self::AAP_CMDKEY_WRITEEN)?;
+        interface.write_raw_ap_register(aap, Self::AAP_CMD, Self::AAP_CMD_SYSRESETREQ)?;
+        interface.write_raw_ap_register(aap, Self::AAP_CMDKEY, 0)?;
+        std::thread::sleep(Duration::from_millis(10));
+
+        Ok(())
+    }
+
+    /// Erase the device with the Secure Engine of Series 2.
+    fn secure_engine_device_erase(&self, memory: &mut Memory) -> Result<(), crate::Error> {
+        let wait_for = |memory: &mut Memory, ready: &dyn Fn(u32) -> bool| {
+            let start = Instant::now();
+            while !ready(memory.read_word_32(Self::DCI_STATUS)?) {
+                if start.elapsed() > Self::ERASE_TIMEOUT {
+                    return Err(crate::Error::architecture_specific(
+                        SiliconLabsError::Timeout,
+                    ));
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            Ok(())
+        };
+
+        for word in Self::DCI_ERASE_DEVICE {
+            wait_for(memory, &|status| status & Self::DCI_STATUS_WPENDING == 0)?;
+            memory.write_word_32(Self::DCI_WDATA, word)?;
+        }
+
+        // The response consists of its length and the status of the command.
+        let mut response = [0; 2];
+        for word in &mut response {
+            wait_for(memory, &|status| status & Self::DCI_STATUS_RDATAVALID != 0)?;
+            *word = memory.read_word_32(Self::DCI_RDATA)?;
+        }
+
+        if response[1] != 0 {
+            return Err(crate::Error::architecture_specific(
+                SiliconLabsError::EraseRejected(response[1]),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn device_erase(
+        &self,
+        interface: &mut Box<dyn ArmProbeInterface>,
+        default_ap: MemoryAp,
+    ) -> Result<bool, crate::Error> {
+        match self.series {
+            Some(Series::Aap) => {
+                let mut memory = interface.memory_interface(default_ap)?;
+                let aap = memory.get_ap();
+                self.aap_device_erase(memory.get_arm_interface()?, aap)?;
+            }
+            Some(Series::SecureEngine) => {
+                let se_ap = MemoryAp::new(ApAddress {
+                    ap: Self::SE_AP,
+                    ..default_ap.ap_address()
+                });
+                let mut memory = interface.memory_interface(se_ap)?;
+                self.secure_engine_device_erase(&mut memory)?;
+            }
+            None => return Ok(false),
+        }
+
+        Ok(true)
+    }
+}
+
+impl ArmDebugSequence for SiliconLabs {
+    fn debug_device_unlock(
+        &self,
+        interface: &mut Box<dyn ArmProbeInterface>,
+        default_ap: MemoryAp,
+        permissions: &crate::Permissions,
+    ) -> Result<(), crate::Error> {
+        let is_unlocked = |interface: &mut Box<dyn ArmProbeInterface>| {
+            let mut memory = interface.memory_interface(default_ap)?;
+            let ap = memory.get_ap();
+            Ok::<_, crate::Error>(self.is_unlocked(memory.get_arm_interface()?, ap))
+        };
+
+        if self.series.is_none() || is_unlocked(interface)? {
+            return Ok(());
+        }
+
+        log::warn!("The debug access of the chip is locked. It has to be erased to unlock it.");
+        if permissions.erase_all().is_err() {
+            return Err(crate::Error::architecture_specific(
+                SiliconLabsError::Locked,
+            ));
+        }
+
+        self.device_erase(interface, default_ap)?;
+
+        if !is_unlocked(interface)? {
+            return Err(crate::Error::architecture_specific(
+                SiliconLabsError::StillLocked,
+            ));
+        }
+
+        log::info!("Erased the chip to remove the debug lock");
+
+        Ok(())
+    }
+
+    fn debug_erase_all(
+        &self,
+        interface: &mut Box<dyn ArmProbeInterface>,
+        default_ap: MemoryAp,
+    ) -> Result<bool, crate::Error> {
+        log::info!("Erasing the device");
+        self.device_erase(interface, default_ap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn series_is_read_from_chip_name() {
+        assert_eq!(
+            Series::from_chip_name("EFM32PG12B500F1024"),
+            Some(Series::Aap)
+        );
+        assert_eq!(
+            Series::from_chip_name("EFR32BG1P232F256"),
+            Some(Series::Aap)
+        );
+        assert_eq!(
+            Series::from_chip_name("EFR32MG21A010F1024"),
+            Some(Series::SecureEngine)
+        );
+        assert_eq!(Series::from_chip_name("EFR32"), None);
+    }
+}
@@ -1,6 +1,8 @@
 //! Sequences for STM32 devices
 
+use std::ops::Range;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use super::ArmDebugSequence;
 use crate::{
@@ -8,13 +10,771 @@ use crate::{
     Memory,
 };
 
+/// The readout protection (RDP) level of an STM32.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadoutProtection {
+    /// The chip is not protected.
+    Level0,
+    /// Only the secure domain of a chip with TrustZone enabled is protected.
+    Level0_5,
+    /// The flash, the SRAM and the backup registers can not be accessed while a debugger is
+    /// connected. The protection can be removed by a mass erase.
+    Level1,
+    /// The debug access is disabled permanently.
+    Level2,
+}
+
+impl ReadoutProtection {
+    /// Decode the RDP option byte, as used by all series except the F0, F1 and F3.
+    fn from_option_byte(value: u8, has_trustzone: bool) -> Self {
+        match value {
+            0xAA => ReadoutProtection::Level0,
+            0x55 if has_trustzone => ReadoutProtection::Level0_5,
+            0xCC => ReadoutProtection::Level2,
+            _ => ReadoutProtection::Level1,
+        }
+    }
+
+    /// Decode the RDP bits of `FLASH_OBR` of the F0, F1 and F3 series.
+    ///
+    /// The F1 only has the single bit RDPRT for level 1, the bit above it is WDG_SW.
+    fn from_obr(obr: u32, single_bit: bool) -> Self {
+        if single_bit {
+            return match obr & 0b10 {
+                0 => ReadoutProtection::Level0,
+                _ => ReadoutProtection::Level1,
+            };
+        }
+
+        match (obr >> 1) & 0b11 {
+            0b00 => ReadoutProtection::Level0,
+            0b11 => ReadoutProtection::Level2,
+            _ => ReadoutProtection::Level1,
+        }
+    }
+}
+
+impl std::fmt::Display for ReadoutProtection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReadoutProtection::Level0 => write!(f, "RDP level 0"),
+            ReadoutProtection::Level0_5 => write!(f, "RDP level 0.5"),
+            ReadoutProtection::Level1 => write!(f, "RDP level 1"),
+            ReadoutProtection::Level2 => write!(f, "RDP level 2"),
+        }
+    }
+}
+
+/// An error which occurred while handling the readout protection of an STM32.
+#[derive(thiserror::Error, Debug)]
+pub enum Stm32Error {
+    /// The chip is protected and the protection may not be removed.
+    #[error("The chip is readout protected ({0}). It has to be mass erased to remove the protection, which requires the erase_all permission.")]
+    ReadoutProtected(ReadoutProtection),
+    /// The chip is protected permanently.
+    #[error("The chip is readout protected permanently ({0}) and can not be debugged.")]
+    PermanentlyProtected(ReadoutProtection),
+    /// Removing the protection is not implemented for the series.
+    #[error("The chip is readout protected ({0}), and removing the protection is not supported for the {1} series.")]
+    RegressionNotSupported(ReadoutProtection, &'static str),
+    /// The flash interface did not finish in time.
+    #[error("Timeout while waiting for the flash interface to program the option bytes")]
+    Timeout,
+    /// The DBGMCU of the chip is not known.
+    #[error("Configuring the DBGMCU is not supported for the {0}")]
+    DbgmcuNotSupported(String),
+}
+
+/// The time the option bytes programming takes at most, including the mass erase.
+const OPTION_BYTES_TIMEOUT: Duration = Duration::from_secs(40);
+
+const FLASH_KEY1: u32 = 0x4567_0123;
+const FLASH_KEY2: u32 = 0xCDEF_89AB;
+const OPTION_KEY1: u32 = 0x0819_2A3B;
+const OPTION_KEY2: u32 = 0x4C5D_6E7F;
+
+/// How the option bytes which contain the RDP level are accessed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OptionBytes {
+    /// The F0, F1 and F3 series, which have the level in `FLASH_OBR`.
+    Obr {
+        /// The F1 series only has a single bit for level 1.
+        single_bit: bool,
+    },
+    /// The F2, F4 and F7 series, which have the level in `FLASH_OPTCR`.
+    Optcr,
+    /// The series which have the level in the lowest byte of `FLASH_OPTR`.
+    Optr {
+        flash_base: u64,
+        optr_offset: u64,
+        /// True if the sequence to remove the protection is implemented.
+        can_regress: bool,
+        has_trustzone: bool,
+    },
+    /// The H7 series, which has the level in `FLASH_OPTSR`.
+    Optsr,
+}
+
+impl OptionBytes {
+    fn from_chip_name(name: &str) -> Option<Self> {
+        let series = name.get(..7)?.to_ascii_uppercase();
+        let optr = |flash_base, optr_offset, can_regress, has_trustzone| OptionBytes::Optr {
+            flash_base,
+            optr_offset,
+            can_regress,
+            has_trustzone,
+        };
+
+        Some(match series.as_str() {
+            "STM32F1" => OptionBytes::Obr { single_bit: true },
+            "STM32F0" | "STM32F3" => OptionBytes::Obr { single_bit: false },
+            "STM32F2" | "STM32F4" | "STM32F7" => OptionBytes::Optcr,
+            "STM32L0" => optr(0x4002_2000, 0x1C, false, false),
+            "STM32L1" => optr(0x4002_3C00, 0x1C, false, false),
+            "STM32G0" | "STM32G4" | "STM32L4" => optr(0x4002_2000, 0x20, true, false),
+            "STM32WB" | "STM32WL" => optr(0x5800_4000, 0x20, true, false),
+            "STM32L5" | "STM32U5" => optr(0x4002_2000, 0x40, false, true),
+            "STM32H7" => OptionBytes::Optsr,
+            _ => return None,
+        })
+    }
+
+    fn readout_protection(
+        &self,
+        memory: &mut Memory<'_>,
+    ) -> Result<ReadoutProtection, crate::Error> {
+        Ok(match *self {
+            OptionBytes::Obr { single_bit } => {
+                let obr = memory.read_word_32(0x4002_201C)?;
+                ReadoutProtection::from_obr(obr, single_bit)
+            }
+            OptionBytes::Optcr => {
+                let optcr = memory.read_word_32(optcr::BASE + optcr::OPTCR)?;
+                ReadoutProtection::from_option_byte((optcr >> 8) as u8, false)
+            }
+            OptionBytes::Optr {
+                flash_base,
+                optr_offset,
+                has_trustzone,
+                ..
+            } => {
+                let optr = memory.read_word_32(flash_base + optr_offset)?;
+                ReadoutProtection::from_option_byte(optr as u8, has_trustzone)
+            }
+            OptionBytes::Optsr => {
+                let optsr = memory.read_word_32(optsr::BASE + optsr::OPTSR_CUR)?;
+                ReadoutProtection::from_option_byte((optsr >> 8) as u8, false)
+            }
+        })
+    }
+
+    /// Set the RDP level to 0, which mass erases the chip.
+    fn regress(
+        &self,
+        memory: &mut Memory<'_>,
+        level: ReadoutProtection,
+    ) -> Result<(), crate::Error> {
+        match *self {
+            OptionBytes::Optcr => {
+                let optcr_address = optcr::BASE + optcr::OPTCR;
+                memory.write_word_32(optcr::BASE + optcr::OPTKEYR, OPTION_KEY1)?;
+                memory.write_word_32(optcr::BASE + optcr::OPTKEYR, OPTION_KEY2)?;
+
+                let optcr = memory.read_word_32(optcr_address)?;
+                memory.write_word_32(optcr_address, (optcr & !0xFF00) | 0xAA00)?;
+                memory.write_word_32(optcr_address, (optcr & !0xFF00) | 0xAA00 | optcr::OPTSTRT)?;
+                wait_while_set(memory, optcr::BASE + optcr::SR, optcr::SR_BSY)?;
+
+                let optcr = memory.read_word_32(optcr_address)?;
+                memory.write_word_32(optcr_address, optcr | optcr::OPTLOCK)?;
+            }
+            OptionBytes::Optr {
+                flash_base,
+                optr_offset,
+                can_regress: true,
+                ..
+            } => {
+                let cr_address = flash_base + optr::CR;
+                memory.write_word_32(flash_base + optr::KEYR, FLASH_KEY1)?;
+                memory.write_word_32(flash_base + optr::KEYR, FLASH_KEY2)?;
+                memory.write_word_32(flash_base + optr::OPTKEYR, OPTION_KEY1)?;
+                memory.write_word_32(flash_base + optr::OPTKEYR, OPTION_KEY2)?;
+
+                let optr = memory.read_word_32(flash_base + optr_offset)?;
+                memory.write_word_32(flash_base + optr_offset, (optr & !0xFF) | 0xAA)?;
+                let cr = memory.read_word_32(cr_address)?;
+                memory.write_word_32(cr_address, cr | optr::CR_OPTSTRT)?;
+                wait_while_set(memory, flash_base + optr::SR, optr::SR_BSY)?;
+
+                // Reloading the option bytes resets the chip, so the write is not acknowledged.
+                let cr = memory.read_word_32(cr_address)?;
+                if let Err(error) = memory.write_word_32(cr_address, cr | optr::CR_OBL_LAUNCH) {
+                    log::debug!("Error after reloading the option bytes: {}", error);
+                }
+            }
+            OptionBytes::Optsr => {
+                memory.write_word_32(optsr::BASE + optsr::OPTKEYR, OPTION_KEY1)?;
+                memory.write_word_32(optsr::BASE + optsr::OPTKEYR, OPTION_KEY2)?;
+
+                let optsr = memory.read_word_32(optsr::BASE + optsr::OPTSR_PRG)?;
+                memory.write_word_32(optsr::BASE + optsr::OPTSR_PRG, (optsr & !0xFF00) | 0xAA00)?;
+                memory.write_word_32(optsr::BASE + optsr::OPTCR, optsr::OPTSTART)?;
+                wait_while_set(memory, optsr::BASE + optsr::OPTSR_CUR, optsr::OPT_BUSY)?;
+
+                memory.write_word_32(optsr::BASE + optsr::OPTCR, optsr::OPTLOCK)?;
+            }
+            OptionBytes::Obr { .. } | OptionBytes::Optr { .. } => {
+                return Err(crate::Error::architecture_specific(
+                    Stm32Error::RegressionNotSupported(level, self.series_name()),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn series_name(&self) -> &'static str {
+        match self {
+            OptionBytes::Obr { .. } => "STM32F0/F1/F3",
+            OptionBytes::Optcr => "STM32F2/F4/F7",
+            OptionBytes::Optr {
+                has_trustzone: true,
+                ..
+            } => "STM32L5/U5",
+            OptionBytes::Optr { .. } => "STM32L0/L1",
+            OptionBytes::Optsr => "STM32H7",
+        }
+    }
+}
+
+/// The registers of the flash interface of the F2, F4 and F7 series.
+mod optcr {
+    pub const BASE: u64 = 0x4002_3C00;
+    pub const OPTKEYR: u64 = 0x08;
+    pub const SR: u64 = 0x0C;
+    pub const OPTCR: u64 = 0x14;
+
+    pub const SR_BSY: u32 = 1 << 16;
+    pub const OPTLOCK: u32 = 1 << 0;
+    pub const OPTSTRT: u32 = 1 << 1;
+}
+
+/// The registers of the flash interfaces with a `FLASH_OPTR` register, relative to their base.
+mod optr {
+    pub const KEYR: u64 = 0x08;
+    pub const OPTKEYR: u64 = 0x0C;
+    pub const SR: u64 = 0x10;
+    pub const CR: u64 = 0x14;
+
+    pub const SR_BSY: u32 = 1 << 16;
+    pub const CR_OPTSTRT: u32 = 1 << 17;
+    pub const CR_OBL_LAUNCH: u32 = 1 << 27;
+}
+
+/// The registers of the flash interface of the H7 series.
+mod optsr {
+    pub const BASE: u64 = 0x5200_2000;
+    pub const OPTKEYR: u64 = 0x08;
+    pub const OPTCR: u64 = 0x18;
+    pub const OPTSR_CUR: u64 = 0x1C;
+    pub const OPTSR_PRG: u64 = 0x20;
+
+    pub const OPTLOCK: u32 = 1 << 0;
+    pub const OPTSTART: u32 = 1 << 1;
+    pub const OPT_BUSY: u32 = 1 << 0;
+}
+
+fn wait_while_set(memory: &mut Memory<'_>, address: u64, mask: u32) -> Result<(), crate::Error> {
+    let start = Instant::now();
+    while memory.read_word_32(address)? & mask != 0 {
+        if start.elapsed() > OPTION_BYTES_TIMEOUT {
+            return Err(crate::Error::architecture_specific(Stm32Error::Timeout));
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    Ok(())
+}
+
+/// Check the readout protection of the chip, and remove level 1 if `erase_all` is permitted.
+///
+/// Without the permission, the chip stays protected and a warning is logged, as the flash
+/// can not be accessed.
+fn unlock_readout_protection(
+    option_bytes: OptionBytes,
+    memory: &mut Memory<'_>,
+    permissions: &crate::Permissions,
+) -> Result<(), crate::Error> {
+    let level = option_bytes.readout_protection(memory)?;
+    log::debug!("Readout protection: {}", level);
+
+    match level {
+        ReadoutProtection::Level0 | ReadoutProtection::Level0_5 => Ok(()),
+        ReadoutProtection::Level2 => Err(crate::Error::architecture_specific(
+            Stm32Error::PermanentlyProtected(level),
+        )),
+        ReadoutProtection::Level1 => {
+            if permissions.erase_all().is_err() {
+                // The core can still be debugged, only the memories are not accessible.
+                log::warn!("{}", Stm32Error::ReadoutProtected(level));
+                return Ok(());
+            }
+
+            log::warn!("Removing the readout protection, which erases the whole flash");
+            option_bytes.regress(memory, level)?;
+            log::info!(
+                "Removed the readout protection. The chip may have to be power cycled for the change to take effect."
+            );
+
+            Ok(())
+        }
+    }
+}
+
+/// The configuration of the DBGMCU of an STM32, which controls how the chip behaves while
+/// it is being debugged.
+///
+/// Options which are not set leave the current configuration of the chip unchanged.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DbgmcuConfig {
+    /// Keep the debug connection and the clock of the core running in the Sleep, Stop and
+    /// Standby modes, so a program which uses low-power modes can still be debugged.
+    pub debug_low_power: bool,
+    /// Stop the independent and window watchdogs while the core is halted.
+    pub freeze_watchdogs: bool,
+    /// Stop all other peripherals which support it, like the timers, the RTC and the
+    /// timeouts of the I2C and CAN controllers, while the core is halted.
+    pub freeze_peripherals: bool,
+}
+
+/// The location of the DBGMCU registers of an STM32 series, as seen by the core.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DbgmcuLayout {
+    base: u64,
+    /// The RCC register and bit which enable the clock of the DBGMCU, on the series where
+    /// it is an APB peripheral.
+    clock_enable: Option<(u64, u32)>,
+    /// The bits of `DBGMCU_CR` which enable debugging in the low-power modes.
+    low_power: u32,
+    /// The offsets of the registers which freeze peripherals, with the bits of all peripherals
+    /// and the bits of the watchdogs in them.
+    freeze: &'static [FreezeRegister],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FreezeRegister {
+    offset: u64,
+    peripherals: u32,
+    watchdogs: u32,
+}
+
+impl FreezeRegister {
+    /// A register which only contains freeze bits, with the watchdogs at `watchdogs`.
+    const fn new(offset: u64, watchdogs: u32) -> Self {
+        Self {
+            offset,
+            peripherals: 0xFFFF_FFFF,
+            watchdogs,
+        }
+    }
+}
+
+/// `DBG_WWDG_STOP` and `DBG_IWDG_STOP` in the APB1 freeze register of most series.
+const APB1_WATCHDOGS: u32 = 1 << 11 | 1 << 12;
+
+impl DbgmcuLayout {
+    const CR: u64 = 0x04;
+
+    fn from_chip_name(name: &str) -> Option<Self> {
+        let series = name.get(..7)?.to_ascii_uppercase();
+
+        const APB_FZ: &[FreezeRegister] = &[
+            FreezeRegister::new(0x08, APB1_WATCHDOGS),
+            FreezeRegister::new(0x0C, 0),
+        ];
+        const APB_FZR: &[FreezeRegister] = &[
+            FreezeRegister::new(0x08, APB1_WATCHDOGS),
+            FreezeRegister::new(0x0C, 0),
+            FreezeRegister::new(0x10, 0),
+        ];
+        // The F1 has the freeze bits in DBGMCU_CR, above the trace configuration.
+        const F1_CR: &[FreezeRegister] = &[FreezeRegister {
+            offset: DbgmcuLayout::CR,
+            peripherals: 0xFFFF_FF00,
+            watchdogs: 1 << 8 | 1 << 9,
+        }];
+        const WB_APB_FZR: &[FreezeRegister] = &[
+            FreezeRegister::new(0x3C, APB1_WATCHDOGS),
+            FreezeRegister::new(0x44, 0),
+            FreezeRegister::new(0x4C, 0),
+        ];
+        // The D1 domain of the H7, accessed through AP2.
+        const H7_APB_FZ: &[FreezeRegister] = &[
+            FreezeRegister::new(0x34, 1 << 6),
+            FreezeRegister::new(0x3C, 0),
+            FreezeRegister::new(0x4C, 0),
+            FreezeRegister::new(0x54, 1 << 18),
+        ];
+
+        let layout = |base, low_power, freeze| DbgmcuLayout {
+            base,
+            clock_enable: None,
+            low_power,
+            freeze,
+        };
+        let apb_layout = |clock_enable, low_power| DbgmcuLayout {
+            base: 0x4001_5800,
+            clock_enable: Some(clock_enable),
+            low_power,
+            freeze: APB_FZ,
+        };
+
+        Some(match series.as_str() {
+            "STM32F1" => layout(0xE004_2000, 0b111, F1_CR),
+            "STM32F2" | "STM32F3" | "STM32F4" | "STM32F7" | "STM32L1" => {
+                layout(0xE004_2000, 0b111, APB_FZ)
+            }
+            "STM32F0" => apb_layout((0x4002_1018, 1 << 22), 0b110),
+            "STM32G0" => apb_layout((0x4002_103C, 1 << 27), 0b110),
+            "STM32L0" => apb_layout((0x4002_1034, 1 << 22), 0b111),
+            "STM32G4" | "STM32L4" => layout(0xE004_2000, 0b111, APB_FZR),
+            "STM32WB" | "STM32WL" => layout(0xE004_2000, 0b111, WB_APB_FZR),
+            "STM32L5" | "STM32U5" => layout(0xE004_4000, 0b110, APB_FZR),
+            "STM32H7" => layout(0xE00E_1000, 0b111, H7_APB_FZ),
+            _ => return None,
+        })
+    }
+
+    /// The bits which have to be set in the register at `offset` for `config`.
+    fn bits(&self, offset: u64, config: &DbgmcuConfig) -> u32 {
+        let mut bits = 0;
+
+        if offset == Self::CR && config.debug_low_power {
+            bits |= self.low_power;
+        }
+
+        for register in self.freeze.iter().filter(|r| r.offset == offset) {
+            if config.freeze_peripherals {
+                bits |= register.peripherals & !register.watchdogs;
+            }
+            if config.freeze_watchdogs {
+                bits |= register.watchdogs;
+            }
+        }
+
+        bits
+    }
+
+    fn apply(&self, memory: &mut Memory<'_>, config: &DbgmcuConfig) -> Result<(), crate::Error> {
+        if let Some((address, bit)) = self.clock_enable {
+            let value = memory.read_word_32(address)?;
+            memory.write_word_32(address, value | bit)?;
+        }
+
+        let offsets = std::iter::once(Self::CR).chain(self.freeze.iter().map(|r| r.offset));
+
+        for offset in offsets {
+            let bits = self.bits(offset, config);
+            if bits == 0 {
+                continue;
+            }
+
+            let address = self.base + offset;
+            let value = memory.read_word_32(address)?;
+            memory.write_word_32(address, value | bits)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The configuration which only freezes the watchdogs, used to suspend them while the core is halted.
+const FREEZE_WATCHDOGS: DbgmcuConfig = DbgmcuConfig {
+    debug_low_power: false,
+    freeze_watchdogs: true,
+    freeze_peripherals: false,
+};
+
+/// Configure the DBGMCU of the STM32 with the given name.
+///
+/// The registers are accessed through `memory`, which has to be the AP of the core, or AP2
+/// on the STM32H7.
+pub fn configure_dbgmcu(
+    memory: &mut Memory<'_>,
+    chip_name: &str,
+    config: &DbgmcuConfig,
+) -> Result<(), crate::Error> {
+    let layout = DbgmcuLayout::from_chip_name(chip_name).ok_or_else(|| {
+        crate::Error::architecture_specific(Stm32Error::DbgmcuNotSupported(chip_name.to_owned()))
+    })?;
+
+    log::debug!("Configuring the DBGMCU: {:?}", config);
+    layout.apply(memory, config)
+}
+
+/// The TrustZone configuration of an STM32 with TrustZone enabled (`TZEN` set).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrustZone {
+    /// The ranges of the flash which are secure according to the watermarks, as addresses in
+    /// the secure alias of the flash.
+    pub secure_flash: Vec<Range<u64>>,
+    /// The size of the flash.
+    flash_size: u64,
+}
+
+impl TrustZone {
+    /// Returns the first part of `range` which would be programmed through the wrong alias of
+    /// the flash, if any.
+    ///
+    /// The secure pages can only be programmed through the secure alias of the flash, and the
+    /// non-secure pages only through the non-secure alias.
+    pub(crate) fn flash_alias_mismatch(&self, range: &Range<u64>) -> Option<Range<u64>> {
+        let non_secure_alias = FLASH_BASE..FLASH_BASE + self.flash_size;
+        let secure_alias =
+            FLASH_BASE + SECURE_FLASH_OFFSET..FLASH_BASE + SECURE_FLASH_OFFSET + self.flash_size;
+
+        // Secure pages in the non-secure alias.
+        let start = range.start.max(non_secure_alias.start);
+        let end = range.end.min(non_secure_alias.end);
+        if start < end {
+            let secure = start + SECURE_FLASH_OFFSET..end + SECURE_FLASH_OFFSET;
+            for secure_range in &self.secure_flash {
+                let start = secure.start.max(secure_range.start);
+                let end = secure.end.min(secure_range.end);
+                if start < end {
+                    return Some(start - SECURE_FLASH_OFFSET..end - SECURE_FLASH_OFFSET);
+                }
+            }
+        }
+
+        // Non-secure pages in the secure alias.
+        let mut address = range.start.max(secure_alias.start);
+        let end = range.end.min(secure_alias.end);
+        for secure_range in &self.secure_flash {
+            if address >= end {
+                break;
+            }
+            if secure_range.end <= address {
+                continue;
+            }
+            if secure_range.start > address {
+                return Some(address..secure_range.start.min(end));
+            }
+            address = secure_range.end;
+        }
+        if address < end {
+            return Some(address..end);
+        }
+
+        None
+    }
+}
+
+/// The base of the non-secure alias of the flash.
+const FLASH_BASE: u64 = 0x0800_0000;
+/// The offset of the secure aliases of the flash from the non-secure ones.
+pub(crate) const SECURE_FLASH_OFFSET: u64 = 0x0400_0000;
+/// The secure aliases of the SRAM.
+pub(crate) const SECURE_SRAM: Range<u64> = 0x3000_0000..0x4000_0000;
+
+/// The flash layout of the STM32 series with TrustZone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TrustZoneFlash {
+    /// The addresses of the secure watermark registers of each bank.
+    watermarks: [u64; 2],
+    /// The number of banks the flash is split into.
+    banks: u64,
+    page_size: u64,
+}
+
+impl TrustZoneFlash {
+    /// The ranges of the secure flash, from the contents of the watermark registers.
+    fn secure_ranges(&self, watermarks: &[u32], flash_size: u64) -> Vec<Range<u64>> {
+        let bank_size = flash_size / self.banks;
+
+        watermarks
+            .iter()
+            .enumerate()
+            .filter_map(|(bank, watermark)| {
+                let start = (watermark & 0x7F) as u64;
+                let end = ((watermark >> 16) & 0x7F) as u64;
+                if start > end {
+                    // The bank does not contain any secure page.
+                    return None;
+                }
+
+                let bank_base = FLASH_BASE + SECURE_FLASH_OFFSET + bank as u64 * bank_size;
+                Some(bank_base + start * self.page_size..bank_base + (end + 1) * self.page_size)
+            })
+            .collect()
+    }
+}
+
+/// Read the TrustZone configuration of the STM32 with the given name and flash size.
+///
+/// Returns `Ok(None)` if TrustZone is disabled, or not available on the series.
+pub(crate) fn read_trustzone(
+    memory: &mut Memory<'_>,
+    chip_name: &str,
+    flash_size: u64,
+) -> Result<Option<TrustZone>, crate::Error> {
+    let series = chip_name.get(..7).map(|name| name.to_ascii_uppercase());
+
+    let flash = match series.as_deref() {
+        Some("STM32L5") | Some("STM32U5") => {
+            let optr = memory.read_word_32(0x4002_2040)?;
+            if optr & (1 << 31) == 0 {
+                return Ok(None);
+            }
+
+            if series.as_deref() == Some("STM32U5") {
+                TrustZoneFlash {
+                    watermarks: [0x4002_2050, 0x4002_2060],
+                    banks: 2,
+                    page_size: 0x2000,
+                }
+            } else if optr & (1 << 22) != 0 {
+                // The flash of the L5 is split into two banks with smaller pages if DBANK is set.
+                TrustZoneFlash {
+                    watermarks: [0x4002_2050, 0x4002_2060],
+                    banks: 2,
+                    page_size: 0x800,
+                }
+            } else {
+                TrustZoneFlash {
+                    watermarks: [0x4002_2050, 0x4002_2060],
+                    banks: 1,
+                    page_size: 0x1000,
+                }
+            }
+        }
+        _ => return Ok(None),
+    };
+
+    let mut watermarks = Vec::new();
+    for address in &flash.watermarks[..flash.banks as usize] {
+        watermarks.push(memory.read_word_32(*address)?);
+    }
+
+    Ok(Some(TrustZone {
+        secure_flash: flash.secure_ranges(&watermarks, flash_size),
+        flash_size,
+    }))
+}
+
+/// The sequence handle for STM32 devices, which handles their readout protection (RDP).
+///
+/// A chip with RDP level 1 is mass erased to remove the protection if the `erase_all`
+/// permission is given, otherwise a warning is logged, as flashing the chip will fail.
+/// The attach fails with [`Stm32Error::PermanentlyProtected`] for RDP level 2.
+///
+/// The watchdogs are frozen while the core is halted. A sequence created with
+/// [`Stm32::with_dbgmcu`] also configures the rest of the DBGMCU when attaching.
+pub struct Stm32 {
+    option_bytes: Option<OptionBytes>,
+    dbgmcu: Option<DbgmcuLayout>,
+    dbgmcu_config: Option<DbgmcuConfig>,
+}
+
+impl Stm32 {
+    /// Create the sequence for the STM32 with the given name.
+    pub fn create(chip_name: &str) -> Arc<Self> {
+        Arc::new(Self {
+            option_bytes: OptionBytes::from_chip_name(chip_name),
+            dbgmcu: DbgmcuLayout::from_chip_name(chip_name),
+            dbgmcu_config: None,
+        })
+    }
+
+    /// Create the sequence for the STM32 with the given name, which configures the DBGMCU
+    /// with `config` when attaching.
+    ///
+    /// To use it, replace the [`Target::debug_sequence`](crate::Target::debug_sequence)
+    /// before attaching.
+    pub fn with_dbgmcu(chip_name: &str, config: DbgmcuConfig) -> Arc<Self> {
+        let layout = DbgmcuLayout::from_chip_name(chip_name);
+        if layout.is_none() {
+            log::warn!("{}", Stm32Error::DbgmcuNotSupported(chip_name.to_owned()));
+        }
+
+        Arc::new(Self {
+            option_bytes: OptionBytes::from_chip_name(chip_name),
+            dbgmcu: layout,
+            dbgmcu_config: Some(config),
+        })
+    }
+
+    /// Read the readout protection level of the chip.
+    ///
+    /// Returns `Ok(None)` if the option bytes of the series are not known.
+    pub fn readout_protection(
+        &self,
+        memory: &mut Memory<'_>,
+    ) -> Result<Option<ReadoutProtection>, crate::Error> {
+        self.option_bytes
+            .map(|option_bytes| option_bytes.readout_protection(memory))
+            .transpose()
+    }
+}
+
+impl ArmDebugSequence for Stm32 {
+    fn debug_device_unlock(
+        &self,
+        interface: &mut Box<dyn ArmProbeInterface>,
+        default_ap: MemoryAp,
+        permissions: &crate::Permissions,
+    ) -> Result<(), crate::Error> {
+        let mut memory = interface.memory_interface(default_ap)?;
+
+        if let Some(option_bytes) = self.option_bytes {
+            unlock_readout_protection(option_bytes, &mut memory, permissions)?;
+        }
+
+        if let (Some(layout), Some(config)) = (&self.dbgmcu, &self.dbgmcu_config) {
+            layout.apply(&mut memory, config)?;
+        }
+
+        Ok(())
+    }
+
+    fn watchdog_suspend(
+        &self,
+        interface: &mut Box<dyn ArmProbeInterface>,
+        default_ap: MemoryAp,
+    ) -> Result<(), crate::Error> {
+        if let Some(layout) = &self.dbgmcu {
+            let mut memory = interface.memory_interface(default_ap)?;
+            layout.apply(&mut memory, &FREEZE_WATCHDOGS)?;
+        }
+
+        Ok(())
+    }
+}
+
 /// Marker struct indicating initialization sequencing for STM32H7 family parts.
-pub struct Stm32h7 {}
+///
+/// The debug connection is always kept in the low-power modes of the D1 domain, and also of
+/// the D2 domain on dual-core parts, whose Cortex-M4 is accessed through AP3. The
+/// watchdogs are frozen while the core is halted. A sequence created with
+/// [`Stm32h7::with_dbgmcu`] also freezes the configured peripherals.
+pub struct Stm32h7 {
+    dbgmcu: DbgmcuConfig,
+}
 
 impl Stm32h7 {
+    /// The AP of the Cortex-M4 of dual-core parts.
+    const CM4_AP: u8 = 3;
+
     /// Create the sequencer for the H7 family of parts.
     pub fn create() -> Arc<Self> {
-        Arc::new(Self {})
+        Arc::new(Self {
+            dbgmcu: DbgmcuConfig::default(),
+        })
+    }
+
+    /// Create the sequencer for the H7 family of parts, which configures the DBGMCU with
+    /// `config` when attaching.
+    pub fn with_dbgmcu(config: DbgmcuConfig) -> Arc<Self> {
+        Arc::new(Self { dbgmcu: config })
     }
 
     /// Configure all debug components on the chip.
@@ -48,6 +808,29 @@ impl Stm32h7 {
 
         Ok(())
     }
+
+    /// Keep the debug connection to the Cortex-M4 of dual-core parts, which is located
+    /// in the D2 domain, in all power modes.
+    pub fn enable_d2_debug(
+        &self,
+        memory: &mut Memory<'_>,
+        enable: bool,
+    ) -> Result<(), crate::Error> {
+        let mut control = dbgmcu::Control::read(memory)?;
+
+        control.enable_sleep_debug_d2(enable);
+        control.enable_stop_debug_d2(enable);
+        control.enable_standby_debug_d2(enable);
+
+        control.write(memory)?;
+
+        Ok(())
+    }
+
+    /// Dual-core parts have an additional AP for the Cortex-M4.
+    fn is_dual_core(interface: &mut Box<dyn ArmProbeInterface>) -> Result<bool, crate::Error> {
+        Ok(interface.num_access_ports(DpAddress::Default)? > Self::CM4_AP as usize)
+    }
 }
 
 mod dbgmcu {
@@ -66,6 +849,9 @@ mod dbgmcu {
         pub u8, dbgsleep_d1, enable_sleep_debug: 0;
         pub u8, dbgstop_d1, enable_stop_debug: 1;
         pub u8, dbgstby_d1, enable_standby_debug: 2;
+        pub u8, dbgsleep_d2, enable_sleep_debug_d2: 3;
+        pub u8, dbgstop_d2, enable_stop_debug_d2: 4;
+        pub u8, dbgstby_d2, enable_standby_debug_d2: 5;
 
         pub u8, d3dbgcken, enable_d3_clock: 22;
         pub u8, d1dbgcken, enable_d1_clock: 21;
@@ -93,8 +879,8 @@ impl ArmDebugSequence for Stm32h7 {
     fn debug_device_unlock(
         &self,
         interface: &mut Box<dyn ArmProbeInterface>,
-        _default_ap: MemoryAp,
-        _permissions: &crate::Permissions,
+        default_ap: MemoryAp,
+        permissions: &crate::Permissions,
     ) -> Result<(), crate::Error> {
         // Power up the debug components through AP2, which is the defualt AP debug port.
         let ap = MemoryAp::new(ApAddress {
@@ -102,8 +888,19 @@ impl ArmDebugSequence for Stm32h7 {
             ap: 2,
         });
 
-        let mut memory = interface.memory_interface(ap)?;
-        self.enable_debug_components(&mut memory, true)?;
+        let dual_core = Self::is_dual_core(interface)?;
+
+        {
+            let mut memory = interface.memory_interface(ap)?;
+            self.enable_debug_components(&mut memory, true)?;
+            if dual_core {
+                self.enable_d2_debug(&mut memory, true)?;
+            }
+            configure_dbgmcu(&mut memory, "STM32H7", &self.dbgmcu)?;
+        }
+
+        let mut memory = interface.memory_interface(default_ap)?;
+        unlock_readout_protection(OptionBytes::Optsr, &mut memory, permissions)?;
 
         Ok(())
     }
@@ -118,9 +915,182 @@ impl ArmDebugSequence for Stm32h7 {
             ap: 2,
         });
 
+        let dual_core = Self::is_dual_core(interface)?;
+
         let mut memory = interface.memory_interface(ap)?;
         self.enable_debug_components(&mut memory, false)?;
+        if dual_core {
+            self.enable_d2_debug(&mut memory, false)?;
+        }
 
         Ok(())
     }
+
+    fn watchdog_suspend(
+        &self,
+        interface: &mut Box<dyn ArmProbeInterface>,
+        _default_ap: MemoryAp,
+    ) -> Result<(), crate::Error> {
+        let ap = MemoryAp::new(ApAddress {
+            dp: DpAddress::Default,
+            ap: 2,
+        });
+
+        let mut memory = interface.memory_interface(ap)?;
+        configure_dbgmcu(&mut memory, "STM32H7", &FREEZE_WATCHDOGS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rdp_option_byte_is_decoded() {
+        assert_eq!(
+            ReadoutProtection::from_option_byte(0xAA, false),
+            ReadoutProtection::Level0
+        );
+        assert_eq!(
+            ReadoutProtection::from_option_byte(0xCC, false),
+            ReadoutProtection::Level2
+        );
+        assert_eq!(
+            ReadoutProtection::from_option_byte(0x55, false),
+            ReadoutProtection::Level1
+        );
+        assert_eq!(
+            ReadoutProtection::from_option_byte(0x55, true),
+            ReadoutProtection::Level0_5
+        );
+        assert_eq!(
+            ReadoutProtection::from_option_byte(0x00, true),
+            ReadoutProtection::Level1
+        );
+    }
+
+    #[test]
+    fn rdp_is_decoded_from_obr() {
+        // F1: bit 1 is RDPRT, bit 2 is WDG_SW, which is set in the default option bytes.
+        assert_eq!(
+            ReadoutProtection::from_obr(0x03FF_FFFC, true),
+            ReadoutProtection::Level0
+        );
+        assert_eq!(
+            ReadoutProtection::from_obr(0x03FF_FFFE, true),
+            ReadoutProtection::Level1
+        );
+
+        // F0 and F3: bits 1 and 2 are RDPRT.
+        assert_eq!(
+            ReadoutProtection::from_obr(0x00FF_FF00, false),
+            ReadoutProtection::Level0
+        );
+        assert_eq!(
+            ReadoutProtection::from_obr(0x00FF_FF02, false),
+            ReadoutProtection::Level1
+        );
+        assert_eq!(
+            ReadoutProtection::from_obr(0x00FF_FF06, false),
+            ReadoutProtection::Level2
+        );
+    }
+
+    #[test]
+    fn secure_flash_is_read_from_watermarks() {
+        let flash = TrustZoneFlash {
+            watermarks: [0, 0],
+            banks: 2,
+            page_size: 0x800,
+        };
+
+        // Pages 0 to 15 of the first bank are secure, the second bank is non-secure.
+        let ranges = flash.secure_ranges(&[0x000F_0000, 0x0000_007F], 0x8_0000);
+        assert_eq!(ranges, vec![0x0C00_0000..0x0C00_8000]);
+
+        let ranges = flash.secure_ranges(&[0x007F_0000, 0x007F_0000], 0x8_0000);
+        assert_eq!(
+            ranges,
+            vec![0x0C00_0000..0x0C04_0000, 0x0C04_0000..0x0C08_0000]
+        );
+    }
+
+    #[test]
+    fn flash_alias_must_match_the_watermarks() {
+        let trustzone = TrustZone {
+            secure_flash: vec![0x0C00_0000..0x0C00_8000],
+            flash_size: 0x8_0000,
+        };
+
+        // Non-secure data in the non-secure pages, secure data in the secure pages.
+        assert_eq!(
+            trustzone.flash_alias_mismatch(&(0x0800_8000..0x0800_9000)),
+            None
+        );
+        assert_eq!(
+            trustzone.flash_alias_mismatch(&(0x0C00_0000..0x0C00_8000)),
+            None
+        );
+
+        // Non-secure data in the secure pages.
+        assert_eq!(
+            trustzone.flash_alias_mismatch(&(0x0800_7000..0x0800_9000)),
+            Some(0x0800_7000..0x0800_8000)
+        );
+        // Secure data in the non-secure pages.
+        assert_eq!(
+            trustzone.flash_alias_mismatch(&(0x0C00_7000..0x0C00_9000)),
+            Some(0x0C00_8000..0x0C00_9000)
+        );
+
+        // Addresses outside of the flash are not checked.
+        assert_eq!(
+            trustzone.flash_alias_mismatch(&(0x2000_0000..0x2000_1000)),
+            None
+        );
+    }
+
+    #[test]
+    fn option_bytes_are_found_by_series() {
+        assert_eq!(
+            OptionBytes::from_chip_name("STM32F411RETx"),
+            Some(OptionBytes::Optcr)
+        );
+        assert_eq!(
+            OptionBytes::from_chip_name("STM32F103C8"),
+            Some(OptionBytes::Obr { single_bit: true })
+        );
+        assert_eq!(OptionBytes::from_chip_name("STM32MP157"), None);
+        assert_eq!(OptionBytes::from_chip_name("STM32"), None);
+    }
+
+    #[test]
+    fn dbgmcu_bits_depend_on_series() {
+        let watchdogs = DbgmcuConfig {
+            freeze_watchdogs: true,
+            ..Default::default()
+        };
+        let all = DbgmcuConfig {
+            debug_low_power: true,
+            freeze_watchdogs: true,
+            freeze_peripherals: true,
+        };
+
+        let f4 = DbgmcuLayout::from_chip_name("STM32F411RETx").unwrap();
+        assert_eq!(f4.base, 0xE004_2000);
+        assert_eq!(f4.bits(0x04, &watchdogs), 0);
+        assert_eq!(f4.bits(0x08, &watchdogs), 0x1800);
+        assert_eq!(f4.bits(0x04, &all), 0b111);
+
+        // The F1 must keep the trace configuration in DBGMCU_CR.
+        let f1 = DbgmcuLayout::from_chip_name("STM32F103C8").unwrap();
+        assert_eq!(f1.bits(0x04, &watchdogs), 0x300);
+        assert_eq!(f1.bits(0x04, &all), 0xFFFF_FF07);
+
+        let g0 = DbgmcuLayout::from_chip_name("STM32G071RBTx").unwrap();
+        assert_eq!(g0.base, 0x4001_5800);
+        assert_eq!(g0.bits(0x04, &all), 0b110);
+
+        assert_eq!(DbgmcuLayout::from_chip_name("STM32MP157"), None);
+    }
 }
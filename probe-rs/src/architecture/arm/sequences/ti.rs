@@ -0,0 +1,235 @@
+//! Sequences for the TI SimpleLink CC13xx and CC26xx series.
+
+use std::sync::Arc;
+
+use super::ArmDebugSequence;
+use crate::architecture::arm::{communication_interface::DapProbe, dp::DPIDR, PortType, Register};
+use crate::probe::{JTAGAccess, ScanChainPosition};
+use crate::{DebugProbeError, WireProtocol};
+
+/// An error which occurred while connecting to the debug port behind the ICEPICK.
+#[derive(thiserror::Error, Debug)]
+pub enum TiError {
+    /// The chips can only be debugged using JTAG.
+    #[error("The CC13xx and CC26xx can only be debugged using JTAG")]
+    JtagRequired,
+    /// The probe does not offer low-level access to JTAG, which is needed to control the ICEPICK.
+    #[error("The probe does not support low-level JTAG access, which is required to control the ICEPICK")]
+    JtagAccessNotAvailable,
+    /// The first TAP in the scan chain is not an ICEPICK.
+    #[error("Expected the ICEPICK JTAG router, but found a TAP with the IDCODE {0:#010x}")]
+    IcePickNotFound(u32),
+}
+
+/// The sequence handle for the TI SimpleLink CC13xx and CC26xx series.
+///
+/// After a reset, the scan chain of these chips only contains the ICEPICK JTAG router, and
+/// the debug port of the Cortex-M core has to be added to the chain through it. The chips start
+/// in the two-pin cJTAG mode, which is switched to four-pin JTAG first.
+pub struct CC13xxCC26xx(());
+
+impl CC13xxCC26xx {
+    /// The port of the ICEPICK to which the debug port of the Cortex-M core is connected.
+    const DAP_PORT: u32 = 0;
+    /// The IR length of the JTAG-DP.
+    const DAP_IR_LEN: u32 = 4;
+
+    /// Create a new sequence handle for the CC13xx and CC26xx series.
+    pub fn create() -> Arc<dyn ArmDebugSequence> {
+        Arc::new(Self(()))
+    }
+}
+
+impl ArmDebugSequence for CC13xxCC26xx {
+    fn debug_port_setup(&self, interface: &mut Box<dyn DapProbe>) -> Result<(), crate::Error> {
+        if interface.active_protocol() != Some(WireProtocol::Jtag) {
+            return Err(crate::Error::architecture_specific(TiError::JtagRequired));
+        }
+
+        // Reset the scan chain, which also removes the debug port from it.
+        interface.swj_sequence(51, 0x0007_FFFF_FFFF_FFFF)?;
+        interface.swj_sequence(20, 0)?;
+
+        {
+            let jtag = interface.try_as_jtag_probe().ok_or_else(|| {
+                crate::Error::architecture_specific(TiError::JtagAccessNotAvailable)
+            })?;
+            jtag.set_scan_chain_position(ScanChainPosition::default())?;
+            jtag.set_ir_len(IcePick::IR_LEN);
+            jtag.read_register(IcePick::IR_BYPASS, 1)?;
+        }
+
+        // The escape sequence is ignored by chips which already use four-pin JTAG,
+        // because the ICEPICK is in BYPASS.
+        send_tms(interface.as_mut(), &CJTAG_TO_JTAG.concat())?;
+        interface.swj_sequence(20, 0)?;
+
+        let jtag = interface
+            .try_as_jtag_probe()
+            .ok_or_else(|| crate::Error::architecture_specific(TiError::JtagAccessNotAvailable))?;
+
+        let mut icepick = IcePick::new(&mut *jtag)?;
+        icepick.enable_port(Self::DAP_PORT)?;
+
+        // The ICEPICK adds the port between TDI and itself, so the debug port is closest to TDO.
+        jtag.set_scan_chain_position(ScanChainPosition {
+            tdi_side_taps: 1,
+            tdi_side_ir_len: IcePick::IR_LEN as usize,
+            ..Default::default()
+        })?;
+        jtag.set_ir_len(Self::DAP_IR_LEN);
+
+        let dpidr = interface.raw_read_register(PortType::DebugPort, DPIDR::ADDRESS)?;
+        log::debug!(
+            "Connected to the debug port behind the ICEPICK, DPIDR: {:#010x}",
+            dpidr
+        );
+
+        Ok(())
+    }
+}
+
+/// The ICEPICK JTAG router, which controls which TAPs are part of the scan chain.
+struct IcePick<'probe> {
+    jtag: &'probe mut dyn JTAGAccess,
+}
+
+impl<'probe> IcePick<'probe> {
+    const IR_LEN: u32 = 6;
+
+    const IR_ROUTER: u32 = 0x02;
+    const IR_IDCODE: u32 = 0x04;
+    const IR_CONNECT: u32 = 0x07;
+    const IR_BYPASS: u32 = 0x3F;
+
+    /// Writing this value to the CONNECT register enables the access to the router.
+    const CONNECT_KEY: u8 = 0x89;
+
+    /// The router block of the secondary debug TAPs.
+    const BLOCK_SDTAP: u32 = 0x2;
+
+    const SDTAP_INHIBIT_SLEEP: u32 = 1 << 3;
+    const SDTAP_FORCE_ACTIVE: u32 = 1 << 8;
+    const SDTAP_SELECT_TAP: u32 = 1 << 13;
+
+    /// The JEDEC manufacturer ID of Texas Instruments in the IDCODE.
+    const TI_MANUFACTURER: u32 = 0x02F;
+
+    /// Check that the scan chain contains an ICEPICK and connect to its router.
+    fn new(jtag: &'probe mut dyn JTAGAccess) -> Result<Self, crate::Error> {
+        jtag.set_ir_len(Self::IR_LEN);
+
+        let idcode = jtag.read_register(Self::IR_IDCODE, 32)?;
+        let idcode = u32::from_le_bytes([idcode[0], idcode[1], idcode[2], idcode[3]]);
+        if idcode & 0xFFF != Self::TI_MANUFACTURER {
+            return Err(crate::Error::architecture_specific(
+                TiError::IcePickNotFound(idcode),
+            ));
+        }
+        log::debug!("Found ICEPICK with IDCODE {:#010x}", idcode);
+
+        jtag.write_register(Self::IR_CONNECT, &[Self::CONNECT_KEY], 8)?;
+
+        Ok(Self { jtag })
+    }
+
+    /// Write `value` to a register of the router.
+    fn write_router(
+        &mut self,
+        block: u32,
+        register: u32,
+        value: u32,
+    ) -> Result<(), DebugProbeError> {
+        let command = 1 << 31 | block << 28 | register << 24 | value & 0x00FF_FFFF;
+        self.jtag
+            .write_register(Self::IR_ROUTER, &command.to_le_bytes(), 32)?;
+        Ok(())
+    }
+
+    /// Add the TAP on `port` to the scan chain.
+    fn enable_port(&mut self, port: u32) -> Result<(), crate::Error> {
+        self.write_router(
+            Self::BLOCK_SDTAP,
+            port,
+            Self::SDTAP_SELECT_TAP | Self::SDTAP_FORCE_ACTIVE | Self::SDTAP_INHIBIT_SLEEP,
+        )?;
+
+        // The TAP is added to the chain when the ICEPICK passes through Run-Test/Idle.
+        let idle_cycles = self.jtag.get_idle_cycles();
+        self.jtag.set_idle_cycles(10);
+        let result = self.jtag.read_register(Self::IR_BYPASS, 1);
+        self.jtag.set_idle_cycles(idle_cycles);
+        result?;
+
+        Ok(())
+    }
+}
+
+/// TMS sequence of a data scan without any bits in Shift-DR, starting in Run-Test/Idle or
+/// Update-DR and ending in Update-DR.
+const ZERO_BIT_SCAN: &[bool] = &[true, false, true, true];
+
+/// The IEEE 1149.7 escape from two-pin cJTAG to four-pin JTAG, starting and ending in Run-Test/Idle.
+///
+/// Two zero-bit scans lock the control level, and the lengths of the following two scans
+/// form the command which selects the four-pin mode.
+const CJTAG_TO_JTAG: [&[bool]; 4] = [
+    ZERO_BIT_SCAN,
+    ZERO_BIT_SCAN,
+    // A scan with one bit in Shift-DR.
+    &[true, false, false, true, true, false],
+    // A scan with nine bits in Shift-DR.
+    &[
+        true, false, false, false, false, false, false, false, false, false, false, true, true,
+        false,
+    ],
+];
+
+/// Send a TMS sequence with TDI low.
+fn send_tms(interface: &mut dyn DapProbe, tms: &[bool]) -> Result<(), DebugProbeError> {
+    for chunk in tms.chunks(64) {
+        let bits = chunk
+            .iter()
+            .rev()
+            .fold(0u64, |bits, &bit| bits << 1 | bit as u64);
+        interface.swj_sequence(chunk.len() as u8, bits)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cjtag_escape_scan_lengths() {
+        // Count the clocks spent in Shift-DR in each scan, by following the TAP state machine.
+        let tms = CJTAG_TO_JTAG.concat();
+        let mut scans = Vec::new();
+        let mut state = "idle";
+        let mut shift = 0;
+        for bit in tms {
+            state = match (state, bit) {
+                ("idle", false) | ("update", false) => "idle",
+                ("idle", true) | ("update", true) => "select",
+                ("select", false) => "capture",
+                ("capture", false) | ("shift", false) => "shift",
+                ("capture", true) | ("shift", true) => "exit1",
+                ("exit1", true) => "update",
+                (state, bit) => panic!("Unexpected TMS {} in {}", bit, state),
+            };
+            match state {
+                "shift" => shift += 1,
+                "update" => {
+                    scans.push(shift);
+                    shift = 0;
+                }
+                _ => (),
+            }
+        }
+
+        assert_eq!(state, "idle");
+        assert_eq!(scans, [0, 0, 1, 9]);
+    }
+}
@@ -145,6 +145,9 @@ pub trait RawDapAccess {
 
     /// Cast this interface into a generic [`DebugProbe`].
     fn into_probe(self: Box<Self>) -> Box<dyn DebugProbe>;
+
+    /// Get a reference to this interface as a generic [`DebugProbe`].
+    fn probe_mut(&mut self) -> &mut dyn DebugProbe;
 }
 
 /// High-level DAP register access.
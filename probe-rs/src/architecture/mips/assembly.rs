@@ -0,0 +1,108 @@
+//! Assembler for the few MIPS32 instructions which are executed in debug mode.
+
+/// `nop`
+pub const NOP: u32 = 0x0000_0000;
+
+/// `sync`
+pub const SYNC: u32 = 0x0000_000f;
+
+/// `deret`, return from debug mode
+pub const DERET: u32 = 0x4200_001f;
+
+/// `sdbbp`, the software debug breakpoint
+pub const SDBBP: u32 = 0x7000_003f;
+
+/// Assemble an I-type instruction.
+const fn i_type(opcode: u32, rs: u8, rt: u8, immediate: u16) -> u32 {
+    opcode << 26 | (rs as u32) << 21 | (rt as u32) << 16 | immediate as u32
+}
+
+/// Assemble a `lui` instruction.
+pub const fn lui(rt: u8, immediate: u16) -> u32 {
+    i_type(0x0f, 0, rt, immediate)
+}
+
+/// Assemble a `lw` instruction.
+pub const fn lw(rt: u8, offset: i16, base: u8) -> u32 {
+    i_type(0x23, base, rt, offset as u16)
+}
+
+/// Assemble a `sw` instruction.
+pub const fn sw(rt: u8, offset: i16, base: u8) -> u32 {
+    i_type(0x2b, base, rt, offset as u16)
+}
+
+/// Assemble a `lbu` instruction.
+pub const fn lbu(rt: u8, offset: i16, base: u8) -> u32 {
+    i_type(0x24, base, rt, offset as u16)
+}
+
+/// Assemble a `sb` instruction.
+pub const fn sb(rt: u8, offset: i16, base: u8) -> u32 {
+    i_type(0x28, base, rt, offset as u16)
+}
+
+/// Assemble a `b` instruction, which branches `offset` instructions relative to the delay slot.
+pub const fn b(offset: i16) -> u32 {
+    // beq $zero, $zero, offset
+    i_type(0x04, 0, 0, offset as u16)
+}
+
+/// Assemble a `mfc0` instruction, to read the coprocessor 0 register `rd` with the selector `sel`.
+pub const fn mfc0(rt: u8, rd: u8, sel: u8) -> u32 {
+    0x10 << 26 | (rt as u32) << 16 | (rd as u32) << 11 | sel as u32
+}
+
+/// Assemble a `mtc0` instruction, to write the coprocessor 0 register `rd` with the selector `sel`.
+pub const fn mtc0(rt: u8, rd: u8, sel: u8) -> u32 {
+    0x10 << 26 | 0x04 << 21 | (rt as u32) << 16 | (rd as u32) << 11 | sel as u32
+}
+
+/// Assemble a `mfhi` instruction.
+pub const fn mfhi(rd: u8) -> u32 {
+    (rd as u32) << 11 | 0x10
+}
+
+/// Assemble a `mthi` instruction.
+pub const fn mthi(rs: u8) -> u32 {
+    (rs as u32) << 21 | 0x11
+}
+
+/// Assemble a `mflo` instruction.
+pub const fn mflo(rd: u8) -> u32 {
+    (rd as u32) << 11 | 0x12
+}
+
+/// Assemble a `mtlo` instruction.
+pub const fn mtlo(rs: u8) -> u32 {
+    (rs as u32) << 21 | 0x13
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn assemble_loads_and_stores() {
+        // lw $9, 4($8)
+        assert_eq!(lw(9, 4, 8), 0x8d09_0004);
+        // sw $9, 0x2000($15)
+        assert_eq!(sw(9, 0x2000, 15), 0xade9_2000);
+        // lui $15, 0xff20
+        assert_eq!(lui(15, 0xff20), 0x3c0f_ff20);
+    }
+
+    #[test]
+    fn assemble_cp0_access() {
+        // mtc0 $15, $31 (DESAVE)
+        assert_eq!(mtc0(15, 31, 0), 0x408f_f800);
+        // mfc0 $8, $24 (DEPC)
+        assert_eq!(mfc0(8, 24, 0), 0x4008_c000);
+    }
+
+    #[test]
+    fn assemble_branch() {
+        // b .-8, relative to the delay slot
+        assert_eq!(b(-2), 0x1000_fffe);
+    }
+}
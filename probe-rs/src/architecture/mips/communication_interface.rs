@@ -0,0 +1,643 @@
+//! Debug communication with MIPS cores over EJTAG.
+//!
+//! In debug mode, the core fetches instructions and data from the debug memory segment (dmseg).
+//! With processor access (PrAcc), these accesses are served by the debugger through the EJTAG
+//! `ADDRESS`, `DATA` and `CONTROL` registers, so any operation on the core is done by feeding it
+//! a small program.
+
+use super::assembly;
+use crate::memory::valid_32_address;
+use crate::probe::JTAGAccess;
+use crate::{DebugProbe, DebugProbeError, Error as ProbeRsError, MemoryInterface, Probe};
+
+use bitfield::bitfield;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::time::{Duration, Instant};
+
+/// An error which occurred while debugging a MIPS core.
+#[derive(thiserror::Error, Debug)]
+pub enum MipsError {
+    /// An error with operating the debug probe occurred.
+    #[error("Debug Probe Error")]
+    DebugProbe(#[from] DebugProbeError),
+    /// A timeout occurred while waiting for the core.
+    #[error("Timeout while waiting for the core.")]
+    Timeout,
+    /// The operation requires the core to be in debug mode.
+    #[error("The core is not in debug mode.")]
+    NotInDebugMode,
+    /// The core accessed an address outside of the areas used by the debugger while executing
+    /// a program in debug mode.
+    #[error("Unexpected processor access to {0:#010x} in debug mode.")]
+    UnexpectedAccess(u32),
+    /// The TAP does not implement EJTAG for a MIPS32 core.
+    #[error("The TAP does not implement EJTAG for a 32-bit MIPS core (IMPCODE {0:#010x}).")]
+    UnsupportedImplementation(u32),
+}
+
+impl From<MipsError> for ProbeRsError {
+    fn from(err: MipsError) -> Self {
+        match err {
+            MipsError::DebugProbe(e) => e.into(),
+            other => ProbeRsError::ArchitectureSpecific(Box::new(other)),
+        }
+    }
+}
+
+/// EJTAG TAP instructions.
+const IDCODE: u32 = 0x01;
+const IMPCODE: u32 = 0x03;
+const ADDRESS: u32 = 0x08;
+const DATA: u32 = 0x09;
+const CONTROL: u32 = 0x0a;
+const EJTAGBOOT: u32 = 0x0c;
+const NORMALBOOT: u32 = 0x0d;
+
+/// Start of the debug memory segment.
+const DMSEG: u32 = 0xff20_0000;
+
+/// Address of the debug exception vector, where programs are executed from.
+const DMSEG_TEXT: u32 = DMSEG + 0x200;
+
+/// End of the area programs are executed from.
+const DMSEG_TEXT_END: u32 = DMSEG + PARAM_IN as u32;
+
+/// Offset of the input parameters of a program from [`DMSEG`].
+const PARAM_IN: i16 = 0x1000;
+
+/// Offset of the output parameters of a program from [`DMSEG`].
+const PARAM_OUT: i16 = 0x2000;
+
+/// Offset of the area where programs save the registers they use, from [`DMSEG`].
+const SCRATCH: i16 = 0x3000;
+
+/// End of the data areas in the debug memory segment.
+const DMSEG_DATA_END: u32 = DMSEG + 0x4000;
+
+/// Register which holds the address of [`DMSEG`] while a program is executed.
+const BASE: u8 = 15;
+
+/// Registers which programs can use freely.
+const TEMP0: u8 = 8;
+const TEMP1: u8 = 9;
+
+/// The coprocessor 0 register `DESAVE`, which holds the original value of [`BASE`].
+const CP0_DESAVE: u8 = 31;
+
+/// Maximum number of words transferred by a single program.
+const MAX_TRANSFER_WORDS: usize = 128;
+
+bitfield! {
+    /// The EJTAG `CONTROL` register.
+    #[derive(Copy, Clone)]
+    pub struct EjtagControl(u32);
+    impl Debug;
+
+    /// A reset occurred since this bit was cleared.
+    pub rocc, set_rocc: 31;
+    /// Size of the pending processor access.
+    pub psz, _: 30, 29;
+    /// A processor access is pending, write 0 to finish it.
+    pub pracc, set_pracc: 18;
+    /// The pending processor access is a write.
+    pub prnw, _: 19;
+    /// Processor reset.
+    pub prrst, set_prrst: 16;
+    /// Processor accesses to dmseg are served by the probe.
+    pub proben, set_proben: 15;
+    /// The debug exception vector is located in dmseg.
+    pub probtrap, set_probtrap: 14;
+    /// Request a debug interrupt.
+    pub ejtagbrk, set_ejtagbrk: 12;
+    /// The core is in debug mode.
+    pub dm, _: 3;
+}
+
+/// A communication interface to a MIPS32 core with EJTAG.
+#[derive(Debug)]
+pub struct MipsCommunicationInterface {
+    probe: Box<dyn JTAGAccess>,
+}
+
+impl MipsCommunicationInterface {
+    /// Creates a new MIPS communication interface with a given probe driver.
+    pub fn new(
+        mut probe: Box<dyn JTAGAccess>,
+    ) -> Result<Self, (Box<dyn JTAGAccess>, DebugProbeError)> {
+        probe.set_ir_len(5);
+
+        let mut interface = Self { probe };
+
+        if let Err(error) = interface.check_implementation() {
+            let error = match error {
+                MipsError::DebugProbe(error) => error,
+                other => DebugProbeError::ArchitectureSpecific(Box::new(other)),
+            };
+            return Err((interface.probe, error));
+        }
+
+        Ok(interface)
+    }
+
+    fn check_implementation(&mut self) -> Result<(), MipsError> {
+        let impcode = self.read_register(IMPCODE)?;
+
+        log::debug!("EJTAG version {}, IMPCODE {:#010x}", impcode >> 29, impcode);
+
+        // Bit 0 is set for 64-bit cores.
+        if impcode & 1 != 0 {
+            return Err(MipsError::UnsupportedImplementation(impcode));
+        }
+
+        Ok(())
+    }
+
+    /// Read the IDCODE of the TAP.
+    pub fn read_idcode(&mut self) -> Result<u32, DebugProbeError> {
+        self.read_register(IDCODE)
+    }
+
+    fn read_register(&mut self, instruction: u32) -> Result<u32, DebugProbeError> {
+        let value = self.probe.read_register(instruction, 32)?;
+
+        Ok(u32::from_le_bytes((&value[..]).try_into().unwrap()))
+    }
+
+    fn write_register(&mut self, instruction: u32, value: u32) -> Result<u32, DebugProbeError> {
+        let value = self
+            .probe
+            .write_register(instruction, &value.to_le_bytes(), 32)?;
+
+        Ok(u32::from_le_bytes((&value[..]).try_into().unwrap()))
+    }
+
+    /// The value of the `CONTROL` register which keeps processor accesses in dmseg enabled.
+    fn control(&self) -> EjtagControl {
+        let mut control = EjtagControl(0);
+        control.set_proben(true);
+        control.set_probtrap(true);
+        // Writing 0 finishes a pending processor access, so this is only cleared on purpose.
+        control.set_pracc(true);
+        control
+    }
+
+    /// Write `control` to the `CONTROL` register, and return its previous value.
+    fn write_control(&mut self, control: EjtagControl) -> Result<EjtagControl, MipsError> {
+        Ok(EjtagControl(self.write_register(CONTROL, control.0)?))
+    }
+
+    fn read_control(&mut self) -> Result<EjtagControl, MipsError> {
+        let control = self.control();
+        self.write_control(control)
+    }
+
+    /// Returns `true` if the core is in debug mode.
+    pub fn is_in_debug_mode(&mut self) -> Result<bool, MipsError> {
+        Ok(self.read_control()?.dm())
+    }
+
+    /// Request the core to enter debug mode, and wait until it did.
+    pub fn enter_debug_mode(&mut self, timeout: Duration) -> Result<(), MipsError> {
+        let mut control = self.control();
+        control.set_ejtagbrk(true);
+        self.write_control(control)?;
+
+        self.wait_for_debug_mode(timeout)
+    }
+
+    /// Wait until the core enters debug mode.
+    pub fn wait_for_debug_mode(&mut self, timeout: Duration) -> Result<(), MipsError> {
+        let start = Instant::now();
+
+        while !self.is_in_debug_mode()? {
+            if start.elapsed() > timeout {
+                return Err(MipsError::Timeout);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Leave debug mode, the core continues to execute instructions.
+    pub fn leave_debug_mode(&mut self) -> Result<(), MipsError> {
+        self.execute(&[assembly::DERET], &[], false)?;
+
+        Ok(())
+    }
+
+    /// Reset the core with the `PrRst` bit. If `halt` is set, the core enters debug mode
+    /// directly after the reset.
+    pub fn reset(&mut self, halt: bool, timeout: Duration) -> Result<(), MipsError> {
+        if halt {
+            self.probe.write_register(EJTAGBOOT, &[0], 1)?;
+        }
+
+        let mut control = self.control();
+        control.set_prrst(true);
+        self.write_control(control)?;
+
+        // Clear the reset and the sticky reset flag.
+        let mut control = self.control();
+        control.set_rocc(false);
+        self.write_control(control)?;
+
+        if halt {
+            let result = self.wait_for_debug_mode(timeout);
+            self.probe.write_register(NORMALBOOT, &[0], 1)?;
+            result?;
+        }
+
+        Ok(())
+    }
+
+    /// Wait for the next processor access, and return its address.
+    fn wait_for_processor_access(&mut self) -> Result<(u32, EjtagControl), MipsError> {
+        let start = Instant::now();
+
+        loop {
+            let control = self.read_control()?;
+
+            if !control.dm() {
+                return Err(MipsError::NotInDebugMode);
+            }
+
+            if control.pracc() {
+                let address = self.read_register(ADDRESS)?;
+                return Ok((address, control));
+            }
+
+            if start.elapsed() > Duration::from_secs(1) {
+                return Err(MipsError::Timeout);
+            }
+        }
+    }
+
+    /// Finish the pending processor access.
+    fn finish_processor_access(&mut self) -> Result<(), MipsError> {
+        let mut control = self.control();
+        control.set_pracc(false);
+        self.write_control(control)?;
+
+        Ok(())
+    }
+
+    /// Let the core execute `code` from dmseg.
+    ///
+    /// The code can load `input` from [`PARAM_IN`] and store its results at [`PARAM_OUT`],
+    /// relative to [`DMSEG`]. It has to end with a branch back to the start. If `wait` is not set,
+    /// this returns once all instructions have been fetched, e.g. for `deret`.
+    ///
+    /// Returns the words written to the data areas of dmseg, by their address.
+    fn execute(
+        &mut self,
+        code: &[u32],
+        input: &[u32],
+        wait: bool,
+    ) -> Result<HashMap<u32, u32>, MipsError> {
+        let mut memory = HashMap::new();
+        for (index, value) in input.iter().enumerate() {
+            memory.insert(DMSEG + PARAM_IN as u32 + 4 * index as u32, *value);
+        }
+
+        let mut fetched = 0;
+
+        loop {
+            let (address, control) = self.wait_for_processor_access()?;
+
+            if control.prnw() {
+                if !(DMSEG..DMSEG_DATA_END).contains(&address) || address < DMSEG_TEXT_END {
+                    return Err(MipsError::UnexpectedAccess(address));
+                }
+
+                let value = self.read_register(DATA)?;
+                memory.insert(address, value);
+            } else if (DMSEG_TEXT..DMSEG_TEXT_END).contains(&address) {
+                if address == DMSEG_TEXT && fetched > 0 {
+                    // The program branched back to the start, the core waits for the next program.
+                    return Ok(memory);
+                }
+
+                let index = ((address - DMSEG_TEXT) / 4) as usize;
+                let instruction = code.get(index).copied().unwrap_or(assembly::NOP);
+                self.write_register(DATA, instruction)?;
+                fetched += 1;
+
+                if !wait && fetched == code.len() {
+                    self.finish_processor_access()?;
+                    return Ok(memory);
+                }
+            } else if (DMSEG..DMSEG_DATA_END).contains(&address) {
+                let value = memory.get(&address).copied().unwrap_or(0);
+                self.write_register(DATA, value)?;
+            } else {
+                return Err(MipsError::UnexpectedAccess(address));
+            }
+
+            self.finish_processor_access()?;
+        }
+    }
+
+    /// Execute `body` with the base address of dmseg in [`BASE`], and with
+    /// [`TEMP0`] and [`TEMP1`] available as scratch registers.
+    ///
+    /// Returns the `output_len` words the program stored at [`PARAM_OUT`].
+    fn execute_program(
+        &mut self,
+        body: &[u32],
+        input: &[u32],
+        output_len: usize,
+    ) -> Result<Vec<u32>, MipsError> {
+        let mut code = vec![
+            assembly::mtc0(BASE, CP0_DESAVE, 0),
+            assembly::lui(BASE, (DMSEG >> 16) as u16),
+            assembly::sw(TEMP0, SCRATCH, BASE),
+            assembly::sw(TEMP1, SCRATCH + 4, BASE),
+        ];
+        code.extend_from_slice(body);
+        code.extend_from_slice(&[
+            assembly::lw(TEMP1, SCRATCH + 4, BASE),
+            assembly::lw(TEMP0, SCRATCH, BASE),
+        ]);
+        let branch = -(code.len() as i16 + 1);
+        code.extend_from_slice(&[assembly::b(branch), assembly::mfc0(BASE, CP0_DESAVE, 0)]);
+
+        let memory = self.execute(&code, input, true)?;
+
+        Ok((0..output_len)
+            .map(|index| {
+                let address = DMSEG + PARAM_OUT as u32 + 4 * index as u32;
+                memory.get(&address).copied().unwrap_or(0)
+            })
+            .collect())
+    }
+
+    /// Read the general purpose register `register`.
+    pub fn read_gpr(&mut self, register: u8) -> Result<u32, MipsError> {
+        let body = match register {
+            0 => return Ok(0),
+            BASE => vec![
+                assembly::mfc0(TEMP0, CP0_DESAVE, 0),
+                assembly::sw(TEMP0, PARAM_OUT, BASE),
+            ],
+            register => vec![assembly::sw(register, PARAM_OUT, BASE)],
+        };
+
+        Ok(self.execute_program(&body, &[], 1)?[0])
+    }
+
+    /// Write the general purpose register `register`.
+    pub fn write_gpr(&mut self, register: u8, value: u32) -> Result<(), MipsError> {
+        let body = match register {
+            0 => return Ok(()),
+            // The scratch registers are restored at the end of the program.
+            TEMP0 | TEMP1 => vec![
+                assembly::lw(TEMP0, PARAM_IN, BASE),
+                assembly::sw(TEMP0, SCRATCH + 4 * (register - TEMP0) as i16, BASE),
+            ],
+            // The base register is restored from DESAVE at the end of the program.
+            BASE => vec![
+                assembly::lw(TEMP0, PARAM_IN, BASE),
+                assembly::mtc0(TEMP0, CP0_DESAVE, 0),
+            ],
+            register => vec![assembly::lw(register, PARAM_IN, BASE)],
+        };
+
+        self.execute_program(&body, &[value], 0)?;
+
+        Ok(())
+    }
+
+    /// Read the coprocessor 0 register `register` with the selector `sel`.
+    pub fn read_cp0(&mut self, register: u8, sel: u8) -> Result<u32, MipsError> {
+        let body = [
+            assembly::mfc0(TEMP0, register, sel),
+            assembly::sw(TEMP0, PARAM_OUT, BASE),
+        ];
+
+        Ok(self.execute_program(&body, &[], 1)?[0])
+    }
+
+    /// Write the coprocessor 0 register `register` with the selector `sel`.
+    pub fn write_cp0(&mut self, register: u8, sel: u8, value: u32) -> Result<(), MipsError> {
+        let body = [
+            assembly::lw(TEMP0, PARAM_IN, BASE),
+            assembly::mtc0(TEMP0, register, sel),
+        ];
+
+        self.execute_program(&body, &[value], 0)?;
+
+        Ok(())
+    }
+
+    /// Read the `hi` (`true`) or `lo` (`false`) register.
+    pub fn read_hi_lo(&mut self, hi: bool) -> Result<u32, MipsError> {
+        let body = [
+            if hi {
+                assembly::mfhi(TEMP0)
+            } else {
+                assembly::mflo(TEMP0)
+            },
+            assembly::sw(TEMP0, PARAM_OUT, BASE),
+        ];
+
+        Ok(self.execute_program(&body, &[], 1)?[0])
+    }
+
+    /// Write the `hi` (`true`) or `lo` (`false`) register.
+    pub fn write_hi_lo(&mut self, hi: bool, value: u32) -> Result<(), MipsError> {
+        let body = [
+            assembly::lw(TEMP0, PARAM_IN, BASE),
+            if hi {
+                assembly::mthi(TEMP0)
+            } else {
+                assembly::mtlo(TEMP0)
+            },
+        ];
+
+        self.execute_program(&body, &[value], 0)?;
+
+        Ok(())
+    }
+
+    /// Read words from memory, as seen by the core.
+    pub fn read_words(&mut self, address: u32, data: &mut [u32]) -> Result<(), MipsError> {
+        for (index, chunk) in data.chunks_mut(MAX_TRANSFER_WORDS).enumerate() {
+            let chunk_address = address + (index * MAX_TRANSFER_WORDS * 4) as u32;
+
+            let mut body = vec![assembly::lw(TEMP0, PARAM_IN, BASE)];
+            for offset in 0..chunk.len() as i16 {
+                body.push(assembly::lw(TEMP1, 4 * offset, TEMP0));
+                body.push(assembly::sw(TEMP1, PARAM_OUT + 4 * offset, BASE));
+            }
+
+            let values = self.execute_program(&body, &[chunk_address], chunk.len())?;
+            chunk.copy_from_slice(&values);
+        }
+
+        Ok(())
+    }
+
+    /// Read bytes from memory, as seen by the core.
+    pub fn read_bytes(&mut self, address: u32, data: &mut [u8]) -> Result<(), MipsError> {
+        for (index, chunk) in data.chunks_mut(MAX_TRANSFER_WORDS).enumerate() {
+            let chunk_address = address + (index * MAX_TRANSFER_WORDS) as u32;
+
+            let mut body = vec![assembly::lw(TEMP0, PARAM_IN, BASE)];
+            for offset in 0..chunk.len() as i16 {
+                body.push(assembly::lbu(TEMP1, offset, TEMP0));
+                body.push(assembly::sw(TEMP1, PARAM_OUT + 4 * offset, BASE));
+            }
+
+            let values = self.execute_program(&body, &[chunk_address], chunk.len())?;
+            for (byte, value) in chunk.iter_mut().zip(values) {
+                *byte = value as u8;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write words to memory, as seen by the core.
+    pub fn write_words(&mut self, address: u32, data: &[u32]) -> Result<(), MipsError> {
+        for (index, chunk) in data.chunks(MAX_TRANSFER_WORDS).enumerate() {
+            let chunk_address = address + (index * MAX_TRANSFER_WORDS * 4) as u32;
+
+            let mut body = vec![assembly::lw(TEMP0, PARAM_IN, BASE)];
+            for offset in 0..chunk.len() as i16 {
+                body.push(assembly::lw(TEMP1, PARAM_IN + 4 + 4 * offset, BASE));
+                body.push(assembly::sw(TEMP1, 4 * offset, TEMP0));
+            }
+            body.push(assembly::SYNC);
+
+            let mut input = vec![chunk_address];
+            input.extend_from_slice(chunk);
+
+            self.execute_program(&body, &input, 0)?;
+        }
+
+        Ok(())
+    }
+
+    /// Write bytes to memory, as seen by the core.
+    pub fn write_bytes(&mut self, address: u32, data: &[u8]) -> Result<(), MipsError> {
+        for (index, chunk) in data.chunks(MAX_TRANSFER_WORDS).enumerate() {
+            let chunk_address = address + (index * MAX_TRANSFER_WORDS) as u32;
+
+            let mut body = vec![assembly::lw(TEMP0, PARAM_IN, BASE)];
+            for offset in 0..chunk.len() as i16 {
+                body.push(assembly::lw(TEMP1, PARAM_IN + 4 + 4 * offset, BASE));
+                body.push(assembly::sb(TEMP1, offset, TEMP0));
+            }
+            body.push(assembly::SYNC);
+
+            let mut input = vec![chunk_address];
+            input.extend(chunk.iter().map(|byte| *byte as u32));
+
+            self.execute_program(&body, &input, 0)?;
+        }
+
+        Ok(())
+    }
+
+    /// Get the probe driver.
+    pub fn probe_mut(&mut self) -> &mut dyn DebugProbe {
+        self.probe.probe_mut()
+    }
+
+    /// Destruct the interface and return the stored probe driver.
+    pub fn close(self) -> Probe {
+        Probe::from_attached_probe(self.probe.into_probe())
+    }
+}
+
+impl MemoryInterface for MipsCommunicationInterface {
+    fn supports_native_64bit_access(&mut self) -> bool {
+        false
+    }
+
+    fn read_word_64(&mut self, address: u64) -> Result<u64, ProbeRsError> {
+        let mut data = [0; 1];
+        self.read_64(address, &mut data)?;
+
+        Ok(data[0])
+    }
+
+    fn read_word_32(&mut self, address: u64) -> Result<u32, ProbeRsError> {
+        let mut data = [0; 1];
+        self.read_32(address, &mut data)?;
+
+        Ok(data[0])
+    }
+
+    fn read_word_8(&mut self, address: u64) -> Result<u8, ProbeRsError> {
+        let mut data = [0; 1];
+        self.read_8(address, &mut data)?;
+
+        Ok(data[0])
+    }
+
+    fn read_64(&mut self, address: u64, data: &mut [u64]) -> Result<(), ProbeRsError> {
+        let mut words = vec![0; data.len() * 2];
+        self.read_32(address, &mut words)?;
+
+        for (value, words) in data.iter_mut().zip(words.chunks_exact(2)) {
+            *value = u64::from(words[0]) | (u64::from(words[1]) << 32);
+        }
+
+        Ok(())
+    }
+
+    fn read_32(&mut self, address: u64, data: &mut [u32]) -> Result<(), ProbeRsError> {
+        let address = valid_32_address(address)?;
+        log::debug!("read_32 from {:#08x}", address);
+
+        Ok(self.read_words(address, data)?)
+    }
+
+    fn read_8(&mut self, address: u64, data: &mut [u8]) -> Result<(), ProbeRsError> {
+        let address = valid_32_address(address)?;
+        log::debug!("read_8 from {:#08x}", address);
+
+        Ok(self.read_bytes(address, data)?)
+    }
+
+    fn write_word_64(&mut self, address: u64, data: u64) -> Result<(), ProbeRsError> {
+        self.write_64(address, &[data])
+    }
+
+    fn write_word_32(&mut self, address: u64, data: u32) -> Result<(), ProbeRsError> {
+        self.write_32(address, &[data])
+    }
+
+    fn write_word_8(&mut self, address: u64, data: u8) -> Result<(), ProbeRsError> {
+        self.write_8(address, &[data])
+    }
+
+    fn write_64(&mut self, address: u64, data: &[u64]) -> Result<(), ProbeRsError> {
+        let words: Vec<u32> = data
+            .iter()
+            .flat_map(|value| [*value as u32, (*value >> 32) as u32])
+            .collect();
+
+        self.write_32(address, &words)
+    }
+
+    fn write_32(&mut self, address: u64, data: &[u32]) -> Result<(), ProbeRsError> {
+        let address = valid_32_address(address)?;
+        log::debug!("write_32 to {:#08x}", address);
+
+        Ok(self.write_words(address, data)?)
+    }
+
+    fn write_8(&mut self, address: u64, data: &[u8]) -> Result<(), ProbeRsError> {
+        let address = valid_32_address(address)?;
+        log::debug!("write_8 to {:#08x}", address);
+
+        Ok(self.write_bytes(address, data)?)
+    }
+
+    fn flush(&mut self) -> Result<(), ProbeRsError> {
+        Ok(())
+    }
+}
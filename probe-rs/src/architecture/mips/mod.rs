@@ -0,0 +1,395 @@
+//! All the interface bits for MIPS cores with EJTAG.
+
+use crate::core::{Architecture, CoreInformation, RegisterFile, RegisterValue};
+use crate::memory::valid_32_address;
+use crate::{
+    CoreInterface, CoreStatus, CoreType, Error, HaltReason, InstructionSet, MemoryInterface,
+    RegisterId,
+};
+use anyhow::Result;
+use communication_interface::{MipsCommunicationInterface, MipsError};
+
+use bitfield::bitfield;
+use register::MIPS32_REGISTERS;
+use std::time::Duration;
+
+pub(crate) mod assembly;
+mod register;
+
+pub mod communication_interface;
+
+/// The coprocessor 0 register numbers used by the debugger.
+const CP0_BAD_VADDR: u8 = 8;
+const CP0_STATUS: u8 = 12;
+const CP0_CAUSE: u8 = 13;
+const CP0_DEBUG: u8 = 23;
+const CP0_DEPC: u8 = 24;
+
+/// The debug control register, in the debug register segment (drseg).
+const DCR: u32 = 0xff30_0000;
+
+/// The instruction breakpoint status register.
+const IBS: u32 = 0xff30_1000;
+
+/// Address of the registers of instruction breakpoint `n`.
+const fn iba(n: usize) -> u32 {
+    0xff30_1100 + 0x100 * n as u32
+}
+
+const fn ibm(n: usize) -> u32 {
+    iba(n) + 0x08
+}
+
+const fn ibc(n: usize) -> u32 {
+    iba(n) + 0x18
+}
+
+/// Timeout when waiting for the core to enter debug mode.
+const DEBUG_MODE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// A interface to operate MIPS32 cores.
+pub struct Mips32<'probe> {
+    interface: &'probe mut MipsCommunicationInterface,
+}
+
+impl<'probe> Mips32<'probe> {
+    /// Create a new MIPS32 interface.
+    pub fn new(interface: &'probe mut MipsCommunicationInterface) -> Self {
+        Self { interface }
+    }
+
+    fn read_debug(&mut self) -> Result<Cp0Debug, MipsError> {
+        Ok(Cp0Debug(self.interface.read_cp0(CP0_DEBUG, 0)?))
+    }
+
+    fn write_debug(&mut self, debug: Cp0Debug) -> Result<(), MipsError> {
+        self.interface.write_cp0(CP0_DEBUG, 0, debug.0)
+    }
+
+    fn read_pc(&mut self) -> Result<u32, MipsError> {
+        self.interface.read_cp0(CP0_DEPC, 0)
+    }
+
+    /// Check that the core is in debug mode, which is required to access it.
+    fn check_halted(&mut self) -> Result<(), MipsError> {
+        if self.interface.is_in_debug_mode()? {
+            Ok(())
+        } else {
+            Err(MipsError::NotInDebugMode)
+        }
+    }
+}
+
+impl<'probe> CoreInterface for Mips32<'probe> {
+    fn wait_for_core_halted(&mut self, timeout: Duration) -> Result<(), crate::Error> {
+        Ok(self.interface.wait_for_debug_mode(timeout)?)
+    }
+
+    fn core_halted(&mut self) -> Result<bool, crate::Error> {
+        Ok(self.interface.is_in_debug_mode()?)
+    }
+
+    fn status(&mut self) -> Result<CoreStatus, crate::Error> {
+        if !self.interface.is_in_debug_mode()? {
+            return Ok(CoreStatus::Running);
+        }
+
+        let debug = self.read_debug()?;
+
+        let reason = if debug.dss() {
+            HaltReason::Step
+        } else if debug.dbp() || debug.dib() {
+            HaltReason::Breakpoint
+        } else if debug.dint() {
+            HaltReason::Request
+        } else {
+            HaltReason::Unknown
+        };
+
+        Ok(CoreStatus::Halted(reason))
+    }
+
+    fn halt(&mut self, timeout: Duration) -> Result<CoreInformation, crate::Error> {
+        self.interface.enter_debug_mode(timeout)?;
+
+        let pc = self.read_pc()?;
+
+        Ok(CoreInformation { pc: pc.into() })
+    }
+
+    fn run(&mut self) -> Result<(), crate::Error> {
+        self.check_halted()?;
+
+        Ok(self.interface.leave_debug_mode()?)
+    }
+
+    fn reset(&mut self) -> Result<(), crate::Error> {
+        Ok(self.interface.reset(false, DEBUG_MODE_TIMEOUT)?)
+    }
+
+    fn reset_and_halt(&mut self, timeout: Duration) -> Result<CoreInformation, crate::Error> {
+        self.interface.reset(true, timeout)?;
+
+        let pc = self.read_pc()?;
+
+        Ok(CoreInformation { pc: pc.into() })
+    }
+
+    fn step(&mut self) -> Result<CoreInformation, crate::Error> {
+        self.check_halted()?;
+
+        let mut debug = self.read_debug()?;
+
+        // When halted by an sdbbp instruction, DEPC points to the instruction itself,
+        // so stepping would only hit it again. Skip over it instead.
+        if debug.dbp() {
+            let pc = self.read_pc()?;
+
+            if self.interface.read_word_32(pc.into())? == assembly::SDBBP {
+                let next_pc = pc.wrapping_add(4);
+                self.interface.write_cp0(CP0_DEPC, 0, next_pc)?;
+
+                return Ok(CoreInformation { pc: next_pc.into() });
+            }
+        }
+
+        debug.set_sst(true);
+        self.write_debug(debug)?;
+
+        self.interface.leave_debug_mode()?;
+        self.interface.wait_for_debug_mode(DEBUG_MODE_TIMEOUT)?;
+
+        // clear step request
+        let mut debug = self.read_debug()?;
+        debug.set_sst(false);
+        self.write_debug(debug)?;
+
+        let pc = self.read_pc()?;
+
+        Ok(CoreInformation { pc: pc.into() })
+    }
+
+    fn read_core_reg(&mut self, address: RegisterId) -> Result<RegisterValue, crate::Error> {
+        self.check_halted()?;
+
+        let value = match address {
+            RegisterId(register @ 0..=31) => self.interface.read_gpr(register as u8)?,
+            register::STATUS => self.interface.read_cp0(CP0_STATUS, 0)?,
+            register::LO => self.interface.read_hi_lo(false)?,
+            register::HI => self.interface.read_hi_lo(true)?,
+            register::BAD_VADDR => self.interface.read_cp0(CP0_BAD_VADDR, 0)?,
+            register::CAUSE => self.interface.read_cp0(CP0_CAUSE, 0)?,
+            register::PC_ID => self.read_pc()?,
+            RegisterId(other) => {
+                return Err(Error::Other(anyhow::anyhow!(
+                    "Unknown MIPS register {}",
+                    other
+                )))
+            }
+        };
+
+        Ok(value.into())
+    }
+
+    fn write_core_reg(&mut self, address: RegisterId, value: RegisterValue) -> Result<()> {
+        self.check_halted()?;
+
+        let value: u32 = value.try_into()?;
+
+        match address {
+            RegisterId(register @ 0..=31) => self.interface.write_gpr(register as u8, value)?,
+            register::STATUS => self.interface.write_cp0(CP0_STATUS, 0, value)?,
+            register::LO => self.interface.write_hi_lo(false, value)?,
+            register::HI => self.interface.write_hi_lo(true, value)?,
+            register::BAD_VADDR => self.interface.write_cp0(CP0_BAD_VADDR, 0, value)?,
+            register::CAUSE => self.interface.write_cp0(CP0_CAUSE, 0, value)?,
+            register::PC_ID => self.interface.write_cp0(CP0_DEPC, 0, value)?,
+            RegisterId(other) => return Err(anyhow::anyhow!("Unknown MIPS register {}", other)),
+        }
+
+        Ok(())
+    }
+
+    fn available_breakpoint_units(&mut self) -> Result<u32, crate::Error> {
+        self.check_halted()?;
+
+        let dcr = Dcr(self.interface.read_word_32(DCR.into())?);
+
+        if !dcr.inst_brk() {
+            log::debug!("No instruction breakpoints implemented");
+            return Ok(0);
+        }
+
+        let ibs = Ibs(self.interface.read_word_32(IBS.into())?);
+
+        log::debug!("Target supports {} breakpoints.", ibs.bcn());
+
+        Ok(ibs.bcn())
+    }
+
+    /// See docs on the [`CoreInterface::hw_breakpoints`] trait
+    fn hw_breakpoints(&mut self) -> Result<Vec<Option<u64>>, Error> {
+        let num_hw_breakpoints = self.available_breakpoint_units()? as usize;
+
+        let mut breakpoints = Vec::with_capacity(num_hw_breakpoints);
+        for unit in 0..num_hw_breakpoints {
+            let control = self.interface.read_word_32(ibc(unit).into())?;
+
+            if control & 1 != 0 {
+                let address = self.interface.read_word_32(iba(unit).into())?;
+                breakpoints.push(Some(address.into()));
+            } else {
+                breakpoints.push(None);
+            }
+        }
+
+        Ok(breakpoints)
+    }
+
+    fn enable_breakpoints(&mut self, _state: bool) -> Result<(), crate::Error> {
+        // Every instruction breakpoint is enabled on its own.
+        Ok(())
+    }
+
+    fn set_hw_breakpoint(&mut self, unit_index: usize, addr: u64) -> Result<(), crate::Error> {
+        let addr = valid_32_address(addr)?;
+
+        self.check_halted()?;
+
+        // Compare all address bits, and ignore the ASID.
+        self.interface.write_word_32(iba(unit_index).into(), addr)?;
+        self.interface.write_word_32(ibm(unit_index).into(), 0)?;
+        self.interface.write_word_32(ibc(unit_index).into(), 1)?;
+
+        Ok(())
+    }
+
+    fn clear_hw_breakpoint(&mut self, unit_index: usize) -> Result<(), crate::Error> {
+        self.check_halted()?;
+
+        self.interface.write_word_32(ibc(unit_index).into(), 0)?;
+
+        Ok(())
+    }
+
+    fn hw_breakpoints_enabled(&self) -> bool {
+        // No global enable for breakpoints
+        true
+    }
+
+    fn registers(&self) -> &'static RegisterFile {
+        &MIPS32_REGISTERS
+    }
+
+    fn architecture(&self) -> Architecture {
+        Architecture::Mips
+    }
+
+    fn core_type(&self) -> CoreType {
+        CoreType::Mips32
+    }
+
+    fn instruction_set(&mut self) -> Result<InstructionSet, Error> {
+        Ok(InstructionSet::Mips32)
+    }
+
+    fn fpu_support(&mut self) -> Result<bool, crate::error::Error> {
+        Err(crate::error::Error::Other(anyhow::anyhow!(
+            "Fpu detection not yet implemented"
+        )))
+    }
+
+    fn supports_background_memory_access(&mut self) -> bool {
+        // Memory is accessed by executing programs in debug mode.
+        false
+    }
+}
+
+impl<'probe> MemoryInterface for Mips32<'probe> {
+    fn supports_native_64bit_access(&mut self) -> bool {
+        self.interface.supports_native_64bit_access()
+    }
+    fn read_word_64(&mut self, address: u64) -> Result<u64, Error> {
+        self.interface.read_word_64(address)
+    }
+    fn read_word_32(&mut self, address: u64) -> Result<u32, Error> {
+        self.interface.read_word_32(address)
+    }
+    fn read_word_8(&mut self, address: u64) -> Result<u8, Error> {
+        self.interface.read_word_8(address)
+    }
+    fn read_64(&mut self, address: u64, data: &mut [u64]) -> Result<(), Error> {
+        self.interface.read_64(address, data)
+    }
+    fn read_32(&mut self, address: u64, data: &mut [u32]) -> Result<(), Error> {
+        self.interface.read_32(address, data)
+    }
+    fn read_8(&mut self, address: u64, data: &mut [u8]) -> Result<(), Error> {
+        self.interface.read_8(address, data)
+    }
+    fn write_word_64(&mut self, address: u64, data: u64) -> Result<(), Error> {
+        self.interface.write_word_64(address, data)
+    }
+    fn write_word_32(&mut self, address: u64, data: u32) -> Result<(), Error> {
+        self.interface.write_word_32(address, data)
+    }
+    fn write_word_8(&mut self, address: u64, data: u8) -> Result<(), Error> {
+        self.interface.write_word_8(address, data)
+    }
+    fn write_64(&mut self, address: u64, data: &[u64]) -> Result<(), Error> {
+        self.interface.write_64(address, data)
+    }
+    fn write_32(&mut self, address: u64, data: &[u32]) -> Result<(), Error> {
+        self.interface.write_32(address, data)
+    }
+    fn write_8(&mut self, address: u64, data: &[u8]) -> Result<(), Error> {
+        self.interface.write_8(address, data)
+    }
+    fn flush(&mut self) -> Result<(), Error> {
+        self.interface.flush()
+    }
+}
+
+bitfield! {
+    /// The coprocessor 0 `Debug` register.
+    #[derive(Copy, Clone)]
+    pub struct Cp0Debug(u32);
+    impl Debug;
+
+    /// The debug exception happened in a branch delay slot.
+    pub dbd, _: 31;
+    /// The core is in debug mode.
+    pub dm, _: 30;
+    /// Single step is enabled.
+    pub sst, set_sst: 8;
+    /// A debug interrupt caused the debug exception.
+    pub dint, _: 5;
+    /// An instruction breakpoint caused the debug exception.
+    pub dib, _: 4;
+    /// An `sdbbp` instruction caused the debug exception.
+    pub dbp, _: 1;
+    /// A single step caused the debug exception.
+    pub dss, _: 0;
+}
+
+bitfield! {
+    /// The debug control register (`DCR`).
+    #[derive(Copy, Clone)]
+    pub struct Dcr(u32);
+    impl Debug;
+
+    /// Data breakpoints are implemented.
+    pub data_brk, _: 17;
+    /// Instruction breakpoints are implemented.
+    pub inst_brk, _: 16;
+}
+
+bitfield! {
+    /// The instruction breakpoint status register (`IBS`).
+    #[derive(Copy, Clone)]
+    pub struct Ibs(u32);
+    impl Debug;
+
+    /// Number of instruction breakpoints.
+    pub bcn, _: 27, 24;
+}
@@ -0,0 +1,391 @@
+use crate::core::RegisterDescription;
+use crate::{
+    core::{RegisterDataType, RegisterFile, RegisterKind},
+    RegisterId,
+};
+
+// The register numbers follow the numbering used by GDB for MIPS32 targets.
+
+/// The coprocessor 0 `Status` register.
+pub(super) const STATUS: RegisterId = RegisterId(32);
+
+/// The `lo` register.
+pub(super) const LO: RegisterId = RegisterId(33);
+
+/// The `hi` register.
+pub(super) const HI: RegisterId = RegisterId(34);
+
+/// The coprocessor 0 `BadVAddr` register.
+pub(super) const BAD_VADDR: RegisterId = RegisterId(35);
+
+/// The coprocessor 0 `Cause` register.
+pub(super) const CAUSE: RegisterId = RegisterId(36);
+
+/// The program counter, which is held in the coprocessor 0 `DEPC` register in debug mode.
+pub(super) const PC_ID: RegisterId = RegisterId(37);
+
+static PC: RegisterDescription = RegisterDescription {
+    name: "pc",
+    _kind: RegisterKind::PC,
+    id: PC_ID,
+    _type: RegisterDataType::UnsignedInteger,
+    size_in_bits: 32,
+};
+
+static RA: RegisterDescription = RegisterDescription {
+    name: "ra",
+    _kind: RegisterKind::General,
+    id: RegisterId(31),
+    _type: RegisterDataType::UnsignedInteger,
+    size_in_bits: 32,
+};
+
+static SP: RegisterDescription = RegisterDescription {
+    name: "sp",
+    _kind: RegisterKind::General,
+    id: RegisterId(29),
+    _type: RegisterDataType::UnsignedInteger,
+    size_in_bits: 32,
+};
+
+static FP: RegisterDescription = RegisterDescription {
+    name: "fp",
+    _kind: RegisterKind::General,
+    id: RegisterId(30),
+    _type: RegisterDataType::UnsignedInteger,
+    size_in_bits: 32,
+};
+
+pub(super) static MIPS32_REGISTERS: RegisterFile = RegisterFile {
+    platform_registers: &[
+        RegisterDescription {
+            name: "zero",
+            _kind: RegisterKind::General,
+            id: RegisterId(0),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "at",
+            _kind: RegisterKind::General,
+            id: RegisterId(1),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "v0",
+            _kind: RegisterKind::General,
+            id: RegisterId(2),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "v1",
+            _kind: RegisterKind::General,
+            id: RegisterId(3),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "a0",
+            _kind: RegisterKind::General,
+            id: RegisterId(4),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "a1",
+            _kind: RegisterKind::General,
+            id: RegisterId(5),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "a2",
+            _kind: RegisterKind::General,
+            id: RegisterId(6),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "a3",
+            _kind: RegisterKind::General,
+            id: RegisterId(7),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "t0",
+            _kind: RegisterKind::General,
+            id: RegisterId(8),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "t1",
+            _kind: RegisterKind::General,
+            id: RegisterId(9),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "t2",
+            _kind: RegisterKind::General,
+            id: RegisterId(10),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "t3",
+            _kind: RegisterKind::General,
+            id: RegisterId(11),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "t4",
+            _kind: RegisterKind::General,
+            id: RegisterId(12),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "t5",
+            _kind: RegisterKind::General,
+            id: RegisterId(13),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "t6",
+            _kind: RegisterKind::General,
+            id: RegisterId(14),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "t7",
+            _kind: RegisterKind::General,
+            id: RegisterId(15),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "s0",
+            _kind: RegisterKind::General,
+            id: RegisterId(16),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "s1",
+            _kind: RegisterKind::General,
+            id: RegisterId(17),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "s2",
+            _kind: RegisterKind::General,
+            id: RegisterId(18),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "s3",
+            _kind: RegisterKind::General,
+            id: RegisterId(19),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "s4",
+            _kind: RegisterKind::General,
+            id: RegisterId(20),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "s5",
+            _kind: RegisterKind::General,
+            id: RegisterId(21),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "s6",
+            _kind: RegisterKind::General,
+            id: RegisterId(22),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "s7",
+            _kind: RegisterKind::General,
+            id: RegisterId(23),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "t8",
+            _kind: RegisterKind::General,
+            id: RegisterId(24),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "t9",
+            _kind: RegisterKind::General,
+            id: RegisterId(25),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "k0",
+            _kind: RegisterKind::General,
+            id: RegisterId(26),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "k1",
+            _kind: RegisterKind::General,
+            id: RegisterId(27),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "gp",
+            _kind: RegisterKind::General,
+            id: RegisterId(28),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "sp",
+            _kind: RegisterKind::General,
+            id: RegisterId(29),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "fp",
+            _kind: RegisterKind::General,
+            id: RegisterId(30),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ra",
+            _kind: RegisterKind::General,
+            id: RegisterId(31),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "sr",
+            _kind: RegisterKind::General,
+            id: STATUS,
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "lo",
+            _kind: RegisterKind::General,
+            id: LO,
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "hi",
+            _kind: RegisterKind::General,
+            id: HI,
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "badvaddr",
+            _kind: RegisterKind::General,
+            id: BAD_VADDR,
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "cause",
+            _kind: RegisterKind::General,
+            id: CAUSE,
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "pc",
+            _kind: RegisterKind::PC,
+            id: PC_ID,
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+    ],
+
+    program_counter: &PC,
+
+    return_address: &RA,
+
+    stack_pointer: &SP,
+
+    frame_pointer: &FP,
+
+    argument_registers: &[
+        RegisterDescription {
+            name: "a0",
+            _kind: RegisterKind::General,
+            id: RegisterId(4),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "a1",
+            _kind: RegisterKind::General,
+            id: RegisterId(5),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "a2",
+            _kind: RegisterKind::General,
+            id: RegisterId(6),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "a3",
+            _kind: RegisterKind::General,
+            id: RegisterId(7),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+    ],
+
+    result_registers: &[
+        RegisterDescription {
+            name: "v0",
+            _kind: RegisterKind::General,
+            id: RegisterId(2),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "v1",
+            _kind: RegisterKind::General,
+            id: RegisterId(3),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+    ],
+
+    msp: None,
+    psp: None,
+    extra: None,
+    psr: None,
+    fp_status: None,
+    fp_registers: None,
+};
@@ -1,4 +1,5 @@
 #![warn(missing_docs)]
 
 pub mod arm;
+pub mod mips;
 pub mod riscv;
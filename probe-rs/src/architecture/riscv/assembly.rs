@@ -3,6 +3,21 @@
 /// RISCV breakpoint instruction
 pub const EBREAK: u32 = 0b000000000001_00000_000_00000_1110011;
 
+/// RISCV compressed breakpoint instruction, from the C extension
+pub const C_EBREAK: u16 = 0b100_1_00000_00000_10;
+
+/// The length in bytes of the instruction starting with `first_halfword`.
+///
+/// Only 16-bit (compressed) and 32-bit instructions are supported,
+/// every instruction whose two lowest bits are not set is compressed.
+pub fn instruction_length(first_halfword: u16) -> u64 {
+    if first_halfword & 0b11 == 0b11 {
+        4
+    } else {
+        2
+    }
+}
+
 /// Assemble a `lw` instruction.
 pub fn lw(offset: u16, base: u8, width: u8, destination: u8) -> u32 {
     let opcode = 0b000_0011;
@@ -77,7 +92,7 @@ fn i_type_instruction(opcode: u8, rs1: u8, funct3: u8, rd: u8, imm: u16) -> u32
 
 #[cfg(test)]
 mod test {
-    use super::{csrr, csrw, lw, sw};
+    use super::{csrr, csrw, instruction_length, lw, sw, C_EBREAK, EBREAK};
 
     #[test]
     fn assemble_csrr() {
@@ -126,4 +141,12 @@ mod test {
 
         assert_eq!(assembled, expected);
     }
+
+    #[test]
+    fn breakpoint_instruction_lengths() {
+        assert_eq!(C_EBREAK, 0x9002);
+
+        assert_eq!(instruction_length(C_EBREAK), 2);
+        assert_eq!(instruction_length(EBREAK as u16), 4);
+    }
 }
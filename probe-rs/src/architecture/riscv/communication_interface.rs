@@ -13,15 +13,17 @@ use crate::{
     architecture::riscv::*,
     probe::{CommandResult, DeferredResultIndex},
 };
-use crate::{MemoryInterface, Probe};
+use crate::{DebugProbe, MemoryInterface, Probe};
 
 use crate::{probe::JTAGAccess, Error as ProbeRsError, RegisterId};
 
+use crate::core::RegisterDescription;
 use crate::memory::valid_32_address;
 
 use bitfield::bitfield;
 use std::{
     collections::HashMap,
+    sync::Arc,
     time::{Duration, Instant},
 };
 
@@ -61,6 +63,15 @@ pub enum RiscvError {
     /// An error during system bus access occurred.
     #[error("Error using system bus")]
     SystemBusAccess,
+    /// The hart with the given index does not exist.
+    #[error("Hart {0} does not exist.")]
+    HartUnavailable(u32),
+    /// The debug module requires authentication before the target can be debugged.
+    #[error("The debug module requires authentication.")]
+    NotAuthenticated,
+    /// The authentication handshake did not authenticate the debugger.
+    #[error("Authentication with the debug module failed.")]
+    AuthenticationFailed,
     /// The given trigger type is not available for the address breakpoint.
     #[error("Unexpected trigger type {0} for address breakpoint.")]
     UnexpectedTriggerType(u32),
@@ -200,13 +211,56 @@ pub struct RiscvCommunicationInterfaceState {
     /// Number of harts
     num_harts: u32,
 
+    /// The hart which is selected with `hartsel`
+    selected_hart: u32,
+
+    /// The debug module implements the hart array mask, so several harts
+    /// can be halted and resumed at the same time.
+    supports_hart_array: bool,
+
     memory_access_info: HashMap<RiscvBusAccess, MemoryAccessMethod>,
 
+    /// The debugger is authenticated with the debug module.
+    authenticated: bool,
+
     /// describes, if the given register can be read / written with an
     /// abstract command
     abstract_cmd_register_info: HashMap<RegisterId, CoreRegisterAbstractCmdSupport>,
 }
 
+/// The authentication handshake for debug modules which require the debugger to authenticate.
+///
+/// The handshake exchanges data with the debug module using
+/// [`RiscvCommunicationInterface::read_authdata`] and [`RiscvCommunicationInterface::write_authdata`],
+/// e.g. reading a challenge and writing the response computed with a key.
+#[derive(Clone)]
+pub struct RiscvAuthentication {
+    handshake: Arc<
+        dyn Fn(&mut RiscvCommunicationInterface) -> Result<(), RiscvError> + Send + Sync + 'static,
+    >,
+}
+
+impl RiscvAuthentication {
+    /// Create an authentication handshake from a function.
+    pub fn new(
+        handshake: impl Fn(&mut RiscvCommunicationInterface) -> Result<(), RiscvError>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        Self {
+            handshake: Arc::new(handshake),
+        }
+    }
+}
+
+impl std::fmt::Debug for RiscvAuthentication {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RiscvAuthentication")
+            .finish_non_exhaustive()
+    }
+}
+
 /// Timeout for RISCV operations.
 const RISCV_TIMEOUT: Duration = Duration::from_secs(5);
 
@@ -238,19 +292,28 @@ impl RiscvCommunicationInterfaceState {
             // We assume only a singe hart exisits initially
             num_harts: 1,
 
+            selected_hart: 0,
+
+            supports_hart_array: false,
+
             memory_access_info: HashMap::new(),
 
+            authenticated: false,
+
             abstract_cmd_register_info: HashMap::new(),
         }
     }
 
     /// Get the memory access method which should be used for an
     /// access with the specified width.
+    ///
+    /// If the system bus can't be used, the access memory abstract command is tried first,
+    /// and the program buffer is used if the debug module doesn't support it.
     fn memory_access_method(&mut self, access_width: RiscvBusAccess) -> MemoryAccessMethod {
         *self
             .memory_access_info
             .entry(access_width)
-            .or_insert(MemoryAccessMethod::ProgramBuffer)
+            .or_insert(MemoryAccessMethod::AbstractCommand)
     }
 }
 
@@ -300,6 +363,148 @@ impl<'probe> RiscvCommunicationInterface {
         self.dtm.read_idcode()
     }
 
+    /// The number of harts connected to the debug module.
+    pub fn num_harts(&self) -> u32 {
+        self.state.num_harts
+    }
+
+    /// The hart which is currently selected, and which is accessed by all core operations.
+    pub fn selected_hart(&self) -> u32 {
+        self.state.selected_hart
+    }
+
+    /// Select the hart which is accessed by the following core operations.
+    pub fn select_hart(&mut self, hart: u32) -> Result<(), RiscvError> {
+        if hart >= self.state.num_harts {
+            return Err(RiscvError::HartUnavailable(hart));
+        }
+
+        if hart != self.state.selected_hart {
+            log::debug!("Selecting hart {}", hart);
+
+            self.state.selected_hart = hart;
+            self.write_dm_register(self.dmcontrol())?;
+        }
+
+        Ok(())
+    }
+
+    /// A `dmcontrol` value which keeps the debug module active and selects the current hart,
+    /// to be used as the base for all writes to `dmcontrol`.
+    pub(super) fn dmcontrol(&self) -> Dmcontrol {
+        let mut dmcontrol = Dmcontrol(0);
+        dmcontrol.set_hartsel(self.state.selected_hart);
+        dmcontrol.set_dmactive(true);
+        dmcontrol
+    }
+
+    /// Halt all harts of the debug module.
+    ///
+    /// If the debug module implements the hart array mask, all harts are halted at the same
+    /// time, otherwise they are halted one after another.
+    pub fn halt_all_harts(&mut self, timeout: Duration) -> Result<(), RiscvError> {
+        self.request_all_harts(
+            timeout,
+            |dmcontrol| dmcontrol.set_haltreq(true),
+            |status| status.allhalted(),
+        )
+    }
+
+    /// Resume all harts of the debug module.
+    ///
+    /// If the debug module implements the hart array mask, all harts are resumed at the same
+    /// time, otherwise they are resumed one after another.
+    pub fn resume_all_harts(&mut self, timeout: Duration) -> Result<(), RiscvError> {
+        self.request_all_harts(
+            timeout,
+            |dmcontrol| dmcontrol.set_resumereq(true),
+            |status| status.allresumeack(),
+        )
+    }
+
+    /// Send the request set by `request` to all harts, and wait until `done` returns `true`
+    /// for the status of the harts.
+    fn request_all_harts(
+        &mut self,
+        timeout: Duration,
+        request: impl Fn(&mut Dmcontrol),
+        done: impl Fn(&Dmstatus) -> bool,
+    ) -> Result<(), RiscvError> {
+        let selected_hart = self.state.selected_hart;
+
+        let result = if self.state.supports_hart_array && self.state.num_harts > 1 {
+            self.select_hart_array()
+                .and_then(|_| self.request_selected_harts(timeout, true, &request, &done))
+        } else {
+            (0..self.state.num_harts).try_for_each(|hart| {
+                self.state.selected_hart = hart;
+                self.request_selected_harts(timeout, false, &request, &done)
+            })
+        };
+
+        // Select the original hart again, also if the request failed.
+        self.state.selected_hart = selected_hart;
+        self.write_dm_register(self.dmcontrol())?;
+
+        result
+    }
+
+    /// Add all harts to the hart array mask.
+    fn select_hart_array(&mut self) -> Result<(), RiscvError> {
+        for window in 0..(self.state.num_harts + 31) / 32 {
+            let harts_in_window = (self.state.num_harts - window * 32).min(32);
+            let mask = if harts_in_window == 32 {
+                u32::MAX
+            } else {
+                (1 << harts_in_window) - 1
+            };
+
+            self.write_dm_register(Hawindowsel(window))?;
+            self.write_dm_register(Hawindow(mask))?;
+        }
+
+        Ok(())
+    }
+
+    fn request_selected_harts(
+        &mut self,
+        timeout: Duration,
+        hart_array: bool,
+        request: impl Fn(&mut Dmcontrol),
+        done: impl Fn(&Dmstatus) -> bool,
+    ) -> Result<(), RiscvError> {
+        let mut dmcontrol = self.dmcontrol();
+        dmcontrol.set_hasel(hart_array);
+        request(&mut dmcontrol);
+        self.write_dm_register(dmcontrol)?;
+
+        let start = Instant::now();
+        let result = loop {
+            let status: Dmstatus = self.read_dm_register()?;
+            if done(&status) {
+                break Ok(());
+            }
+            if start.elapsed() > timeout {
+                break Err(RiscvError::Timeout);
+            }
+        };
+
+        // Clear the request for all selected harts
+        let mut dmcontrol = self.dmcontrol();
+        dmcontrol.set_hasel(hart_array);
+        self.write_dm_register(dmcontrol)?;
+
+        result
+    }
+
+    /// Deactivate the debug module by clearing `dmactive`, which resets the debug module
+    /// and allows the target to enter its low power modes again.
+    ///
+    /// Halted harts are not resumed by this, they have to be resumed before.
+    pub fn disable_debug_module(&mut self) -> Result<(), RiscvError> {
+        self.write_dm_register(Dmcontrol(0))
+    }
+
     fn enter_debug_mode(&mut self) -> Result<(), RiscvError> {
         // We need a jtag interface
 
@@ -346,6 +551,25 @@ impl<'probe> RiscvCommunicationInterface {
         control.set_dmactive(true);
         self.write_dm_register(control)?;
 
+        // Without authentication, the debug module ignores accesses to most of its registers,
+        // so the discovery of its features is done once the debugger is authenticated.
+        let status: Dmstatus = self.read_dm_register()?;
+
+        self.state.authenticated = status.authenticated();
+
+        if !self.state.authenticated {
+            log::info!("The debug module requires authentication.");
+            return Ok(());
+        }
+
+        self.discover_debug_module()
+    }
+
+    /// Determine the harts and the features of the debug module.
+    fn discover_debug_module(&mut self) -> Result<(), RiscvError> {
+        let mut control = Dmcontrol(0);
+        control.set_dmactive(true);
+
         // Select all harts to determine the width
         // of the hartsel register.
         control.set_hartsel(0xffff_ffff);
@@ -386,13 +610,23 @@ impl<'probe> RiscvCommunicationInterface {
 
         self.state.num_harts = num_harts;
 
-        // Select hart 0 again
+        // Check if the hart array mask is implemented
         let mut control = Dmcontrol(0);
-        control.set_hartsel(0);
+        control.set_hasel(true);
         control.set_dmactive(true);
 
         self.write_dm_register(control)?;
 
+        let control: Dmcontrol = self.read_dm_register()?;
+
+        self.state.supports_hart_array = control.hasel();
+
+        log::debug!("Support for hart array: {}", self.state.supports_hart_array);
+
+        // Select hart 0 again
+        self.state.selected_hart = 0;
+        self.write_dm_register(self.dmcontrol())?;
+
         // determine size of the program buffer, and number of data
         // registers for abstract commands
         let abstractcs: Abstractcs = self.read_dm_register()?;
@@ -476,6 +710,69 @@ impl<'probe> RiscvCommunicationInterface {
         Ok(())
     }
 
+    /// Returns `true` if the debugger is authenticated with the debug module.
+    ///
+    /// Debug modules which don't implement authentication always report the debugger as authenticated.
+    pub fn is_authenticated(&self) -> bool {
+        self.state.authenticated
+    }
+
+    /// Authenticate with the debug module, using `authentication` for the handshake.
+    ///
+    /// The handshake itself is specific to the implementation of the debug module. Once the debugger
+    /// is authenticated, the harts and the features of the debug module are determined.
+    pub fn authenticate(&mut self, authentication: &RiscvAuthentication) -> Result<(), RiscvError> {
+        if self.state.authenticated {
+            return Ok(());
+        }
+
+        (authentication.handshake)(self)?;
+
+        let status = self.wait_while_authbusy()?;
+
+        if !status.authenticated() {
+            return Err(RiscvError::AuthenticationFailed);
+        }
+
+        log::info!("Authenticated with the debug module.");
+        self.state.authenticated = true;
+
+        self.discover_debug_module()
+    }
+
+    /// Read the `authdata` register, to receive data for the authentication handshake.
+    pub fn read_authdata(&mut self) -> Result<u32, RiscvError> {
+        self.wait_while_authbusy()?;
+
+        let authdata: Authdata = self.read_dm_register()?;
+
+        Ok(authdata.0)
+    }
+
+    /// Write the `authdata` register, to send data for the authentication handshake.
+    pub fn write_authdata(&mut self, value: u32) -> Result<(), RiscvError> {
+        self.wait_while_authbusy()?;
+
+        self.write_dm_register(Authdata(value))
+    }
+
+    /// Wait until the authentication module is ready to transfer data.
+    fn wait_for_authentication(&mut self) -> Result<Dmstatus, RiscvError> {
+        let start_time = Instant::now();
+
+        loop {
+            let status: Dmstatus = self.read_dm_register()?;
+
+            if !status.authbusy() {
+                return Ok(status);
+            }
+
+            if start_time.elapsed() > RISCV_TIMEOUT {
+                return Err(RiscvError::Timeout);
+            }
+        }
+    }
+
     pub(super) fn read_dm_register<R: DebugRegister>(&mut self) -> Result<R, RiscvError> {
         log::debug!("Reading DM register '{}' at {:#010x}", R::NAME, R::ADDRESS);
 
@@ -597,6 +894,7 @@ impl<'probe> RiscvCommunicationInterface {
 
         sbcs.set_sbaccess(V::WIDTH as u32);
         sbcs.set_sbreadonaddr(true);
+        sbcs.clear_errors();
 
         self.write_dm_register(sbcs)?;
 
@@ -606,7 +904,7 @@ impl<'probe> RiscvCommunicationInterface {
         // Check that the read was succesful
         let sbcs = self.read_dm_register::<Sbcs>()?;
 
-        if sbcs.sberror() != 0 {
+        if sbcs.has_error() {
             Err(RiscvError::SystemBusAccess)
         } else {
             Ok(data)
@@ -629,6 +927,7 @@ impl<'probe> RiscvCommunicationInterface {
 
         sbcs.set_sbreadondata(true);
         sbcs.set_sbautoincrement(true);
+        sbcs.clear_errors();
 
         self.schedule_write_dm_register(sbcs)?;
 
@@ -643,6 +942,7 @@ impl<'probe> RiscvCommunicationInterface {
         }
 
         sbcs.set_sbautoincrement(false);
+        sbcs.set_sbreadondata(false);
         self.schedule_write_dm_register(sbcs)?;
 
         // Read last value
@@ -668,46 +968,40 @@ impl<'probe> RiscvCommunicationInterface {
 
         let sbcs = Sbcs(sbcs);
 
-        if sbcs.sberror() != 0 {
+        if sbcs.has_error() {
             Err(RiscvError::SystemBusAccess)
         } else {
             Ok(())
         }
     }
 
-    /// Perform memory read from a single location using the program buffer.
-    /// Only reads up to a width of 32 bits are currently supported.
-    fn perform_memory_read_progbuf<V: RiscvValue32>(
+    /// Run `f`, which uses `registers` as scratch registers, and restore their original
+    /// values afterwards, also when `f` fails.
+    fn with_saved_registers<T>(
         &mut self,
-        address: u32,
-    ) -> Result<V, RiscvError> {
-        // assemble
-        //  lb s1, 0(s0)
-
-        // Backup register s0
-        let s0 = self.abstract_cmd_register_read(&register::S0)?;
+        registers: &[&RegisterDescription],
+        f: impl FnOnce(&mut Self) -> Result<T, RiscvError>,
+    ) -> Result<T, RiscvError> {
+        let mut saved = Vec::with_capacity(registers.len());
+        for register in registers {
+            saved.push(self.abstract_cmd_register_read(*register)?);
+        }
 
-        let lw_command: u32 = assembly::lw(0, 8, V::WIDTH as u8, 8);
+        let result = f(self);
 
-        self.setup_program_buffer(&[lw_command])?;
+        let restored = registers
+            .iter()
+            .zip(saved)
+            .try_for_each(|(register, value)| self.abstract_cmd_register_write(*register, value));
 
-        self.write_dm_register(Data0(address))?;
+        let value = result?;
+        restored?;
 
-        // Write s0, then execute program buffer
-        let mut command = AccessRegisterCommand(0);
-        command.set_cmd_type(0);
-        command.set_transfer(true);
-        command.set_write(true);
-
-        // registers are 32 bit, so we have size 2 here
-        command.set_aarsize(RiscvBusAccess::A32);
-        command.set_postexec(true);
-
-        // register s0, ie. 0x1008
-        command.set_regno((register::S0).id.0 as u32);
-
-        self.write_dm_register(command)?;
+        Ok(value)
+    }
 
+    /// Check if the abstract commands executed since `abstractcs` was cleared failed.
+    fn check_abstract_command_error(&mut self) -> Result<(), RiscvError> {
         let status: Abstractcs = self.read_dm_register()?;
 
         if status.cmderr() != 0 {
@@ -716,85 +1010,186 @@ impl<'probe> RiscvCommunicationInterface {
             ));
         }
 
-        // Read back s0
-        let value = self.abstract_cmd_register_read(&register::S0)?;
-
-        // Restore s0 register
-        self.abstract_cmd_register_write(&register::S0, s0)?;
-
-        Ok(V::from_register_value(value))
+        Ok(())
     }
 
-    fn perform_memory_read_multiple_progbuf<V: RiscvValue32>(
+    /// Perform memory read from a single location using the program buffer.
+    /// Only reads up to a width of 32 bits are currently supported.
+    fn perform_memory_read_progbuf<V: RiscvValue32>(
         &mut self,
         address: u32,
-        data: &mut [V],
-    ) -> Result<(), RiscvError> {
-        // Backup registers s0 and s1
-        let s0 = self.abstract_cmd_register_read(&register::S0)?;
-        let s1 = self.abstract_cmd_register_read(&register::S1)?;
+    ) -> Result<V, RiscvError> {
+        // assemble
+        //  lb s0, 0(s0)
+        self.with_saved_registers(&[&register::S0], |interface| {
+            let lw_command: u32 = assembly::lw(0, 8, V::WIDTH as u8, 8);
+
+            interface.setup_program_buffer(&[lw_command])?;
+
+            interface.write_dm_register(Data0(address))?;
+
+            // Write s0, then execute program buffer
+            let mut command = AccessRegisterCommand(0);
+            command.set_cmd_type(0);
+            command.set_transfer(true);
+            command.set_write(true);
 
-        // Load a word from address in register 8 (S0), with offset 0, into register 9 (S9)
-        let lw_command: u32 = assembly::lw(0, 8, V::WIDTH as u8, 9);
+            // registers are 32 bit, so we have size 2 here
+            command.set_aarsize(RiscvBusAccess::A32);
+            command.set_postexec(true);
 
-        self.setup_program_buffer(&[
-            lw_command,
-            assembly::addi(8, 8, V::WIDTH.byte_width() as u16),
-        ])?;
+            // register s0, ie. 0x1008
+            command.set_regno((register::S0).id.0 as u32);
 
-        self.write_dm_register(Data0(address))?;
+            interface.write_dm_register(command)?;
 
-        // Write s0, then execute program buffer
-        let mut command = AccessRegisterCommand(0);
-        command.set_cmd_type(0);
-        command.set_transfer(true);
-        command.set_write(true);
+            interface.check_abstract_command_error()?;
 
-        // registers are 32 bit, so we have size 2 here
-        command.set_aarsize(RiscvBusAccess::A32);
-        command.set_postexec(true);
+            // Read back s0
+            let value = interface.abstract_cmd_register_read(&register::S0)?;
 
-        // register s0, ie. 0x1008
-        command.set_regno((register::S0).id.0 as u32);
+            Ok(V::from_register_value(value))
+        })
+    }
 
-        self.write_dm_register(command)?;
+    fn perform_memory_read_multiple_progbuf<V: RiscvValue32>(
+        &mut self,
+        address: u32,
+        data: &mut [V],
+    ) -> Result<(), RiscvError> {
+        self.with_saved_registers(&[&register::S0, &register::S1], |interface| {
+            // Load a word from address in register 8 (S0), with offset 0, into register 9 (S9)
+            let lw_command: u32 = assembly::lw(0, 8, V::WIDTH as u8, 9);
 
-        let data_len = data.len();
+            interface.setup_program_buffer(&[
+                lw_command,
+                assembly::addi(8, 8, V::WIDTH.byte_width() as u16),
+            ])?;
 
-        for word in &mut data[..data_len - 1] {
+            interface.write_dm_register(Data0(address))?;
+
+            // Write s0, then execute program buffer
             let mut command = AccessRegisterCommand(0);
             command.set_cmd_type(0);
             command.set_transfer(true);
-            command.set_write(false);
+            command.set_write(true);
 
             // registers are 32 bit, so we have size 2 here
             command.set_aarsize(RiscvBusAccess::A32);
             command.set_postexec(true);
 
-            command.set_regno((register::S1).id.0 as u32);
+            // register s0, ie. 0x1008
+            command.set_regno((register::S0).id.0 as u32);
 
-            self.write_dm_register(command)?;
+            interface.write_dm_register(command)?;
 
-            // Read back s1
-            let value: Data0 = self.read_dm_register()?;
+            let data_len = data.len();
 
-            *word = V::from_register_value(value.0);
-        }
+            if interface.state.supports_autoexec && data_len > 1 {
+                // Move s1 into data0 and load the next value into s1. With autoexec, every
+                // read of data0 repeats this, and the last value is read from s1 afterwards,
+                // so no memory after the end of `data` is accessed.
+                let mut read_command = AccessRegisterCommand(0);
+                read_command.set_cmd_type(0);
+                read_command.set_transfer(true);
+                read_command.set_write(false);
+                read_command.set_aarsize(RiscvBusAccess::A32);
+                read_command.set_postexec(true);
+                read_command.set_regno((register::S1).id.0 as u32);
 
-        let last_value = self.abstract_cmd_register_read(&register::S1)?;
+                interface.write_dm_register(read_command)?;
+                interface.check_abstract_command_error()?;
 
-        data[data.len() - 1] = V::from_register_value(last_value);
+                if interface.read_data0_autoexec(&mut data[..data_len - 1])? {
+                    let last_value = interface.abstract_cmd_register_read(&register::S1)?;
+                    data[data_len - 1] = V::from_register_value(last_value);
 
-        let status: Abstractcs = self.read_dm_register()?;
+                    return Ok(());
+                }
 
-        if status.cmderr() != 0 {
-            return Err(RiscvError::AbstractCommand(
-                AbstractCommandErrorKind::parse(status.cmderr() as u8),
-            ));
+                // Start over, the address was incremented by the program buffer.
+                interface.write_dm_register(Data0(address))?;
+                interface.write_dm_register(command)?;
+            }
+
+            for word in &mut data[..data_len - 1] {
+                let mut command = AccessRegisterCommand(0);
+                command.set_cmd_type(0);
+                command.set_transfer(true);
+                command.set_write(false);
+
+                // registers are 32 bit, so we have size 2 here
+                command.set_aarsize(RiscvBusAccess::A32);
+                command.set_postexec(true);
+
+                command.set_regno((register::S1).id.0 as u32);
+
+                interface.write_dm_register(command)?;
+
+                // Read back s1
+                let value: Data0 = interface.read_dm_register()?;
+
+                *word = V::from_register_value(value.0);
+            }
+
+            // Errors are sticky, so we can just check at the end if everything worked.
+            interface.check_abstract_command_error()?;
+
+            let last_value = interface.abstract_cmd_register_read(&register::S1)?;
+
+            data[data_len - 1] = V::from_register_value(last_value);
+
+            Ok(())
+        })
+    }
+
+    /// Perform a single memory read using the access memory abstract command.
+    fn perform_memory_read_abstract<V: RiscvValue32>(
+        &mut self,
+        address: u32,
+    ) -> Result<V, RiscvError> {
+        let mut command = AccessMemoryCommand(0);
+        command.set_cmd_type(2);
+        command.set_aamsize(V::WIDTH);
+
+        self.write_dm_register(Data1(address))?;
+        self.execute_abstract_command(command.0)?;
+
+        let value: Data0 = self.read_dm_register()?;
+
+        Ok(V::from_register_value(value.0))
+    }
+
+    /// Perform multiple reads from consecutive memory locations using the access memory abstract command.
+    fn perform_memory_read_multiple_abstract<V: RiscvValue32>(
+        &mut self,
+        address: u32,
+        data: &mut [V],
+    ) -> Result<(), RiscvError> {
+        let mut command = AccessMemoryCommand(0);
+        command.set_cmd_type(2);
+        command.set_aamsize(V::WIDTH);
+        command.set_aampostincrement(true);
+
+        if self.state.supports_autoexec {
+            // The first read puts the first value into data0, every read of data0
+            // then reads the next value.
+            self.write_dm_register(Data1(address))?;
+            self.execute_abstract_command(command.0)?;
+
+            if self.read_data0_autoexec(data)? {
+                return Ok(());
+            }
         }
 
-        self.abstract_cmd_register_write(&register::S0, s0)?;
-        self.abstract_cmd_register_write(&register::S1, s1)?;
+        self.write_dm_register(Data1(address))?;
+
+        for word in data.iter_mut() {
+            self.execute_abstract_command(command.0)?;
+
+            let value: Data0 = self.read_dm_register()?;
+            *word = V::from_register_value(value.0);
+        }
 
         Ok(())
     }
@@ -810,6 +1205,7 @@ impl<'probe> RiscvCommunicationInterface {
         // Set correct access width
         sbcs.set_sbaccess(V::WIDTH as u32);
         sbcs.set_sbautoincrement(true);
+        sbcs.clear_errors();
 
         self.schedule_write_dm_register(sbcs)?;
 
@@ -832,7 +1228,7 @@ impl<'probe> RiscvCommunicationInterface {
 
         let sbcs = Sbcs(sbcs);
 
-        if sbcs.sberror() != 0 {
+        if sbcs.has_error() {
             Err(RiscvError::SystemBusAccess)
         } else {
             Ok(())
@@ -852,55 +1248,40 @@ impl<'probe> RiscvCommunicationInterface {
             data
         );
 
-        // Backup registers s0 and s1
-        let s0 = self.abstract_cmd_register_read(&register::S0)?;
-        let s1 = self.abstract_cmd_register_read(&register::S1)?;
-
-        let sw_command = assembly::sw(0, 8, V::WIDTH as u32, 9);
-
-        self.setup_program_buffer(&[sw_command])?;
-
-        // write address into s0
-        self.abstract_cmd_register_write(&register::S0, address)?;
-
-        // write data into data 0
-        self.write_dm_register(Data0(data.into()))?;
-
-        // Write s1, then execute program buffer
-        let mut command = AccessRegisterCommand(0);
-        command.set_cmd_type(0);
-        command.set_transfer(true);
-        command.set_write(true);
-
-        // registers are 32 bit, so we have size 2 here
-        command.set_aarsize(RiscvBusAccess::A32);
-        command.set_postexec(true);
-
-        // register s1, ie. 0x1009
-        command.set_regno((register::S1).id.0 as u32);
+        self.with_saved_registers(&[&register::S0, &register::S1], |interface| {
+            let sw_command = assembly::sw(0, 8, V::WIDTH as u32, 9);
 
-        self.write_dm_register(command)?;
+            interface.setup_program_buffer(&[sw_command])?;
 
-        let status: Abstractcs = self.read_dm_register()?;
+            // write address into s0
+            interface.abstract_cmd_register_write(&register::S0, address)?;
 
-        if status.cmderr() != 0 {
-            let error = AbstractCommandErrorKind::parse(status.cmderr() as u8);
+            // write data into data 0
+            interface.write_dm_register(Data0(data.into()))?;
 
-            log::error!(
-                "Executing the abstract command for perform_memory_write failed: {:?} ({:x?})",
-                error,
-                status,
-            );
+            // Write s1, then execute program buffer
+            let mut command = AccessRegisterCommand(0);
+            command.set_cmd_type(0);
+            command.set_transfer(true);
+            command.set_write(true);
 
-            return Err(RiscvError::AbstractCommand(error));
-        }
+            // registers are 32 bit, so we have size 2 here
+            command.set_aarsize(RiscvBusAccess::A32);
+            command.set_postexec(true);
 
-        // Restore register s0 and s1
+            // register s1, ie. 0x1009
+            command.set_regno((register::S1).id.0 as u32);
 
-        self.abstract_cmd_register_write(&register::S0, s0)?;
-        self.abstract_cmd_register_write(&register::S1, s1)?;
+            interface.write_dm_register(command)?;
 
-        Ok(())
+            interface.check_abstract_command_error().map_err(|error| {
+                log::error!(
+                    "Executing the abstract command for perform_memory_write failed: {:?}",
+                    error,
+                );
+                error
+            })
+        })
     }
 
     /// Perform multiple memory writes to consecutive locations using the program buffer.
@@ -910,27 +1291,21 @@ impl<'probe> RiscvCommunicationInterface {
         address: u32,
         data: &[V],
     ) -> Result<(), RiscvError> {
-        let s0 = self.abstract_cmd_register_read(&register::S0)?;
-        let s1 = self.abstract_cmd_register_read(&register::S1)?;
+        self.with_saved_registers(&[&register::S0, &register::S1], |interface| {
+            // Setup program buffer for multiple writes
+            // Store value from register s9 into memory,
+            // then increase the address for next write.
+            let sw_command = assembly::sw(0, 8, V::WIDTH as u32, 9);
 
-        // Setup program buffer for multiple writes
-        // Store value from register s9 into memory,
-        // then increase the address for next write.
-        let sw_command = assembly::sw(0, 8, V::WIDTH as u32, 9);
+            interface.setup_program_buffer(&[
+                sw_command,
+                assembly::addi(8, 8, V::WIDTH.byte_width() as u16),
+            ])?;
 
-        self.setup_program_buffer(&[
-            sw_command,
-            assembly::addi(8, 8, V::WIDTH.byte_width() as u16),
-        ])?;
+            // write address into s0
+            interface.abstract_cmd_register_write(&register::S0, address)?;
 
-        // write address into s0
-        self.abstract_cmd_register_write(&register::S0, address)?;
-
-        for value in data {
-            // write address into data 0
-            self.write_dm_register(Data0((*value).into()))?;
-
-            // Write s0, then execute program buffer
+            // Write s1, then execute program buffer
             let mut command = AccessRegisterCommand(0);
             command.set_cmd_type(0);
             command.set_transfer(true);
@@ -943,46 +1318,198 @@ impl<'probe> RiscvCommunicationInterface {
             // register s1
             command.set_regno((register::S1).id.0 as u32);
 
-            self.write_dm_register(command)?;
-        }
+            if interface.state.supports_autoexec && data.len() > 1 {
+                interface.write_dm_register(Data0(data[0].into()))?;
+                interface.write_dm_register(command)?;
+                interface.check_abstract_command_error()?;
 
-        // Errors are sticky, so we can just check at the end if everything worked.
-        let status: Abstractcs = self.read_dm_register()?;
+                if interface.write_data0_autoexec(&data[1..])? {
+                    return Ok(());
+                }
 
-        if status.cmderr() != 0 {
-            let error = AbstractCommandErrorKind::parse(status.cmderr() as u8);
+                // Start over, the address was incremented by the program buffer.
+                interface.abstract_cmd_register_write(&register::S0, address)?;
+            }
 
-            log::error!(
-                "Executing the abstract command for write_32 failed: {:?} ({:x?})",
-                error,
-                status,
-            );
+            for value in data {
+                // write address into data 0
+                interface.write_dm_register(Data0((*value).into()))?;
+
+                interface.write_dm_register(command)?;
+            }
+
+            // Errors are sticky, so we can just check at the end if everything worked.
+            interface.check_abstract_command_error().map_err(|error| {
+                log::error!(
+                    "Executing the abstract command for write_32 failed: {:?}",
+                    error,
+                );
+                error
+            })
+        })
+    }
+
+    /// Perform multiple memory writes to consecutive locations using the access memory abstract command.
+    fn perform_memory_write_multiple_abstract<V: RiscvValue32>(
+        &mut self,
+        address: u32,
+        data: &[V],
+    ) -> Result<(), RiscvError> {
+        let mut command = AccessMemoryCommand(0);
+        command.set_cmd_type(2);
+        command.set_aamsize(V::WIDTH);
+        command.set_aampostincrement(true);
+        command.set_write(true);
+
+        if self.state.supports_autoexec && data.len() > 1 {
+            self.write_dm_register(Data1(address))?;
+            self.write_dm_register(Data0(data[0].into()))?;
+            self.execute_abstract_command(command.0)?;
 
-            return Err(DebugProbeError::ArchitectureSpecific(Box::new(
-                RiscvError::AbstractCommand(error),
-            ))
-            .into());
+            if self.write_data0_autoexec(&data[1..])? {
+                return Ok(());
+            }
         }
 
-        // Restore register s0 and s1
+        self.write_dm_register(Data1(address))?;
 
-        self.abstract_cmd_register_write(&register::S0, s0)?;
-        self.abstract_cmd_register_write(&register::S1, s1)?;
+        for value in data {
+            self.write_dm_register(Data0((*value).into()))?;
+            self.execute_abstract_command(command.0)?;
+        }
 
         Ok(())
     }
 
+    /// Read `data` from `data0`, which has to contain the first value already.
+    ///
+    /// Each read of `data0` executes the last abstract command again, which has to place
+    /// the next value into `data0`. This saves a write of `command` for every value.
+    ///
+    /// Returns `false` if the debug module couldn't keep up, in which case the transfer
+    /// has to be repeated without autoexec.
+    fn read_data0_autoexec<V: RiscvValue32>(&mut self, data: &mut [V]) -> Result<bool, RiscvError> {
+        let (last, values) = match data.split_last_mut() {
+            Some(split) => split,
+            None => return Ok(true),
+        };
+
+        let mut read_results = Vec::with_capacity(values.len());
+
+        let mut abstractauto = Abstractauto(0);
+        abstractauto.set_autoexecdata(1);
+        self.schedule_write_dm_register(abstractauto)?;
+
+        for _ in values.iter() {
+            read_results.push(self.schedule_read_dm_register::<Data0>()?);
+        }
+
+        // The last value is read without starting another command.
+        self.schedule_write_dm_register(Abstractauto(0))?;
+        let last_result = self.schedule_read_dm_register::<Data0>()?;
+
+        let status_result = self.schedule_read_dm_register::<Abstractcs>()?;
+
+        let result = self.execute()?;
+
+        let status = match result[status_result] {
+            CommandResult::U32(status) => Abstractcs(status),
+            _ => panic!("Internal error occurred."),
+        };
+
+        if !self.check_autoexec_transfer(status)? {
+            return Ok(false);
+        }
+
+        for (value, &idx) in values.iter_mut().zip(read_results.iter()) {
+            *value = match result[idx] {
+                CommandResult::U32(data) => V::from_register_value(data),
+                _ => panic!("Internal error occurred."),
+            };
+        }
+
+        *last = match result[last_result] {
+            CommandResult::U32(data) => V::from_register_value(data),
+            _ => panic!("Internal error occurred."),
+        };
+
+        Ok(true)
+    }
+
+    /// Write `data` to `data0`, where each write executes the last abstract command again.
+    ///
+    /// Returns `false` if the debug module couldn't keep up, in which case the transfer
+    /// has to be repeated without autoexec.
+    fn write_data0_autoexec<V: RiscvValue32>(&mut self, data: &[V]) -> Result<bool, RiscvError> {
+        let mut abstractauto = Abstractauto(0);
+        abstractauto.set_autoexecdata(1);
+        self.schedule_write_dm_register(abstractauto)?;
+
+        for value in data {
+            self.schedule_write_dm_register(Data0((*value).into()))?;
+        }
+
+        self.schedule_write_dm_register(Abstractauto(0))?;
+
+        let status_result = self.schedule_read_dm_register::<Abstractcs>()?;
+
+        let result = self.execute()?;
+
+        let status = match result[status_result] {
+            CommandResult::U32(status) => Abstractcs(status),
+            _ => panic!("Internal error occurred."),
+        };
+
+        self.check_autoexec_transfer(status)
+    }
+
+    /// Check the status of a transfer using autoexec.
+    ///
+    /// If the debug module was still busy when `data0` was accessed, the error is cleared,
+    /// autoexec is not used anymore and `false` is returned.
+    fn check_autoexec_transfer(&mut self, mut status: Abstractcs) -> Result<bool, RiscvError> {
+        let start_time = Instant::now();
+
+        while status.busy() {
+            if start_time.elapsed() > RISCV_TIMEOUT {
+                return Err(RiscvError::Timeout);
+            }
+
+            status = self.read_dm_register()?;
+        }
+
+        if status.cmderr() == 0 {
+            return Ok(true);
+        }
+
+        // Writes to abstractauto are ignored while a command is executing,
+        // so make sure autoexec is disabled before clearing the error.
+        self.write_dm_register(Abstractauto(0))?;
+
+        let mut abstractcs_clear = Abstractcs(0);
+        abstractcs_clear.set_cmderr(0x7);
+        self.write_dm_register(abstractcs_clear)?;
+
+        match AbstractCommandErrorKind::parse(status.cmderr() as u8) {
+            AbstractCommandErrorKind::Busy => {
+                log::debug!("Debug module is too slow for autoexec transfers, disabling autoexec.");
+                self.state.supports_autoexec = false;
+                Ok(false)
+            }
+            error => Err(RiscvError::AbstractCommand(error)),
+        }
+    }
+
     pub(crate) fn execute_abstract_command(&mut self, command: u32) -> Result<(), RiscvError> {
         // ensure that preconditions are fullfileld
         // haltreq      = 0
         // resumereq    = 0
         // ackhavereset = 0
 
-        let mut dmcontrol = Dmcontrol(0);
+        let mut dmcontrol = self.dmcontrol();
         dmcontrol.set_haltreq(false);
         dmcontrol.set_resumereq(false);
         dmcontrol.set_ackhavereset(true);
-        dmcontrol.set_dmactive(true);
         self.write_dm_register(dmcontrol)?;
 
         // read abstractcs to see its state
@@ -1207,12 +1734,65 @@ impl<'probe> RiscvCommunicationInterface {
         V::write_to_register::<R>(self, value)
     }
 
+    /// Decide if a failed system bus access should be retried using the program buffer.
+    ///
+    /// The system bus of some debug modules can't reach all memories of the chip, e.g. the
+    /// tightly coupled memories of the core. These are still accessible from the hart itself.
+    fn fall_back_to_progbuf<T>(&self, address: u32, result: &Result<T, RiscvError>) -> bool {
+        match result {
+            Err(RiscvError::SystemBusAccess) if self.state.progbuf_size > 0 => {
+                log::debug!(
+                    "System bus access to {:#010x} failed, using the program buffer instead.",
+                    address
+                );
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Decide if a failed access memory abstract command should be retried using the program buffer.
+    ///
+    /// The access memory command is optional, if the debug module doesn't implement it, the
+    /// program buffer is used for all following accesses with the same width.
+    fn fall_back_from_abstract_command<T>(
+        &mut self,
+        access_width: RiscvBusAccess,
+        result: &Result<T, RiscvError>,
+    ) -> bool {
+        match result {
+            Err(RiscvError::AbstractCommand(AbstractCommandErrorKind::NotSupported)) => {
+                log::debug!(
+                    "Memory access with abstract commands is not supported for {:?}, using the program buffer instead.",
+                    access_width
+                );
+                self.state
+                    .memory_access_info
+                    .insert(access_width, MemoryAccessMethod::ProgramBuffer);
+                true
+            }
+            _ => false,
+        }
+    }
+
     fn read_word<V: RiscvValue32>(&mut self, address: u32) -> Result<V, crate::Error> {
         let result = match self.state.memory_access_method(V::WIDTH) {
             MemoryAccessMethod::ProgramBuffer => self.perform_memory_read_progbuf(address)?,
-            MemoryAccessMethod::SystemBus => self.perform_memory_read_sysbus(address)?,
+            MemoryAccessMethod::SystemBus => {
+                let result = self.perform_memory_read_sysbus(address);
+                if self.fall_back_to_progbuf(address, &result) {
+                    self.perform_memory_read_progbuf(address)?
+                } else {
+                    result?
+                }
+            }
             MemoryAccessMethod::AbstractCommand => {
-                unimplemented!("Memory access using abstract commands is not implemted")
+                let result = self.perform_memory_read_abstract(address);
+                if self.fall_back_from_abstract_command(V::WIDTH, &result) {
+                    self.perform_memory_read_progbuf(address)?
+                } else {
+                    result?
+                }
             }
         };
 
@@ -1224,17 +1804,31 @@ impl<'probe> RiscvCommunicationInterface {
         address: u32,
         data: &mut [V],
     ) -> Result<(), crate::Error> {
-        log::debug!("read_32 from {:#08x}", address);
+        log::debug!("read_multiple from {:#08x}", address);
+
+        if data.is_empty() {
+            return Ok(());
+        }
 
-        match self.state.memory_access_method(RiscvBusAccess::A32) {
+        match self.state.memory_access_method(V::WIDTH) {
             MemoryAccessMethod::ProgramBuffer => {
                 self.perform_memory_read_multiple_progbuf(address, data)?;
             }
             MemoryAccessMethod::SystemBus => {
-                self.perform_memory_read_multiple_sysbus(address, data)?;
+                let result = self.perform_memory_read_multiple_sysbus(address, data);
+                if self.fall_back_to_progbuf(address, &result) {
+                    self.perform_memory_read_multiple_progbuf(address, data)?;
+                } else {
+                    result?;
+                }
             }
             MemoryAccessMethod::AbstractCommand => {
-                unimplemented!("Memory access using abstract commands is not implemted")
+                let result = self.perform_memory_read_multiple_abstract(address, data);
+                if self.fall_back_from_abstract_command(V::WIDTH, &result) {
+                    self.perform_memory_read_multiple_progbuf(address, data)?;
+                } else {
+                    result?;
+                }
             }
         };
 
@@ -1246,9 +1840,21 @@ impl<'probe> RiscvCommunicationInterface {
             MemoryAccessMethod::ProgramBuffer => {
                 self.perform_memory_write_progbuf(address, data)?
             }
-            MemoryAccessMethod::SystemBus => self.perform_memory_write_sysbus(address, &[data])?,
+            MemoryAccessMethod::SystemBus => {
+                let result = self.perform_memory_write_sysbus(address, &[data]);
+                if self.fall_back_to_progbuf(address, &result) {
+                    self.perform_memory_write_progbuf(address, data)?;
+                } else {
+                    result?;
+                }
+            }
             MemoryAccessMethod::AbstractCommand => {
-                unimplemented!("Memory access using abstract commands is not implemted")
+                let result = self.perform_memory_write_multiple_abstract(address, &[data]);
+                if self.fall_back_from_abstract_command(V::WIDTH, &result) {
+                    self.perform_memory_write_progbuf(address, data)?;
+                } else {
+                    result?;
+                }
             }
         };
 
@@ -1260,19 +1866,51 @@ impl<'probe> RiscvCommunicationInterface {
         address: u32,
         data: &[V],
     ) -> Result<(), crate::Error> {
+        if data.is_empty() {
+            return Ok(());
+        }
+
         match self.state.memory_access_method(V::WIDTH) {
-            MemoryAccessMethod::SystemBus => self.perform_memory_write_sysbus(address, data)?,
+            MemoryAccessMethod::SystemBus => {
+                let result = self.perform_memory_write_sysbus(address, data);
+                if self.fall_back_to_progbuf(address, &result) {
+                    self.perform_memory_write_multiple_progbuf(address, data)?;
+                } else {
+                    result?;
+                }
+            }
             MemoryAccessMethod::ProgramBuffer => {
                 self.perform_memory_write_multiple_progbuf(address, data)?
             }
             MemoryAccessMethod::AbstractCommand => {
-                unimplemented!("Memory access using abstract commands is not implemted")
+                let result = self.perform_memory_write_multiple_abstract(address, data);
+                if self.fall_back_from_abstract_command(V::WIDTH, &result) {
+                    self.perform_memory_write_multiple_progbuf(address, data)?;
+                } else {
+                    result?;
+                }
             }
         }
 
         Ok(())
     }
 
+    /// Returns `true` if memory accesses of the given width use the system bus of the debug module.
+    ///
+    /// System bus accesses don't need the hart to be halted, so memory can be read and written
+    /// while the hart is running, e.g. to poll RTT buffers.
+    pub fn supports_system_bus_access(&mut self, access_width: RiscvBusAccess) -> bool {
+        matches!(
+            self.state.memory_access_method(access_width),
+            MemoryAccessMethod::SystemBus
+        )
+    }
+
+    /// Get a reference to the underlying debug probe.
+    pub fn probe_mut(&mut self) -> &mut dyn DebugProbe {
+        self.dtm.probe.probe_mut()
+    }
+
     /// Destruct the interface and return the stored probe driver.
     pub fn close(self) -> Probe {
         Probe::from_attached_probe(self.dtm.probe.into_probe())
@@ -1836,10 +2474,7 @@ impl From<RiscvBusAccess> for u8 {
 
 /// Different methods of memory access,
 /// which can be supported by a debug module.
-///
-/// The `AbstractCommand` method for memory access is not implemented.
 #[derive(Debug, Copy, Clone)]
-#[allow(dead_code)]
 enum MemoryAccessMethod {
     /// Memory access using the program buffer is supported
     ProgramBuffer,
@@ -1853,6 +2488,7 @@ bitfield! {
     /// Abstract command register, located at address 0x17
     /// This is not for all commands, only for the ones
     /// from the debug spec.
+    #[derive(Copy, Clone)]
     pub struct AccessRegisterCommand(u32);
     impl Debug;
     /// This is 0 to indicate Access Register Command.
@@ -1908,6 +2544,51 @@ impl From<u32> for AccessRegisterCommand {
     }
 }
 
+bitfield! {
+    /// Abstract command register, located at address 0x17,
+    /// for the access memory command (see 3.6.1.3).
+    pub struct AccessMemoryCommand(u32);
+    impl Debug;
+    /// This is 2 to indicate Access Memory Command.
+    pub _, set_cmd_type: 31, 24;
+    /// An implementation does not have to implement both virtual and physical accesses, but it must
+    /// fail accesses that it doesn’t support.\
+    /// 0: Addresses are physical (to the hart they are performed on).\
+    /// 1: Addresses are virtual, and translated the way they would be from M-mode, with MPRV set.
+    pub _, set_aamvirtual: 23;
+    /// 0: Access the lowest 8 bits of the memory location.\
+    /// 1: Access the lowest 16 bits of the memory location.\
+    /// 2: Access the lowest 32 bits of the memory location.\
+    /// 3: Access the lowest 64 bits of the memory location.\
+    /// 4: Access the lowest 128 bits of the memory location.
+    pub u8, from into RiscvBusAccess, _, set_aamsize: 22, 20;
+    /// After a memory access has completed, if this bit is 1, increment arg1 (which contains the
+    /// address used) by the number of bytes encoded in aamsize.
+    pub _, set_aampostincrement: 19;
+    /// 0: Copy data from the memory location specified in arg1 into arg0 portion of data.\
+    /// 1: Copy data from arg0 portion of data into the memory location specified in arg1.
+    pub _, set_write: 16;
+    /// These bits are reserved for target-specific uses.
+    pub _, set_target_specific: 15, 14;
+}
+
+impl DebugRegister for AccessMemoryCommand {
+    const ADDRESS: u8 = 0x17;
+    const NAME: &'static str = "command";
+}
+
+impl From<AccessMemoryCommand> for u32 {
+    fn from(register: AccessMemoryCommand) -> Self {
+        register.0
+    }
+}
+
+impl From<u32> for AccessMemoryCommand {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
 pub(super) trait DebugRegister: Into<u32> + From<u32> + std::fmt::Debug {
     const ADDRESS: u8;
     const NAME: &'static str;
@@ -1987,6 +2668,19 @@ bitfield! {
     sbaccess8, _: 0;
 }
 
+impl Sbcs {
+    /// Clear the sticky error flags with the next write to the register.
+    fn clear_errors(&mut self) {
+        self.set_sberror(0b111);
+        self.set_sbbusyerror(true);
+    }
+
+    /// Returns `true` if the last system bus access failed.
+    fn has_error(&self) -> bool {
+        self.sberror() != 0 || self.sbbusyerror()
+    }
+}
+
 impl DebugRegister for Sbcs {
     const ADDRESS: u8 = 0x38;
     const NAME: &'static str = "sbcs";
@@ -6,7 +6,8 @@ use crate::core::Architecture;
 use crate::{CoreInterface, CoreType, InstructionSet};
 use anyhow::{anyhow, Result};
 use communication_interface::{
-    AbstractCommandErrorKind, DebugRegister, RiscvCommunicationInterface, RiscvError,
+    AbstractCommandErrorKind, DebugRegister, RiscvBusAccess, RiscvCommunicationInterface,
+    RiscvError,
 };
 
 use crate::core::{CoreInformation, RegisterFile, RegisterValue};
@@ -18,7 +19,7 @@ use register::RISCV_REGISTERS;
 use std::time::{Duration, Instant};
 
 #[macro_use]
-mod register;
+pub(crate) mod register;
 pub(crate) mod assembly;
 mod dtm;
 
@@ -36,6 +37,36 @@ impl<'probe> Riscv32<'probe> {
         Self { interface }
     }
 
+    /// Check the `misa` register for the C extension.
+    fn supports_compressed_instructions(&mut self) -> Result<bool, RiscvError> {
+        let misa = self.read_csr(0x301)?;
+
+        Ok(misa & (1 << 2) != 0)
+    }
+
+    /// The length of the breakpoint instruction at `address`,
+    /// or `None` if there is no breakpoint instruction.
+    fn breakpoint_instruction_length(&mut self, address: u32) -> Result<Option<u64>, Error> {
+        let mut bytes = [0; 4];
+        self.read_8(address.into(), &mut bytes[..2])?;
+
+        let first_halfword = u16::from_le_bytes([bytes[0], bytes[1]]);
+        let length = assembly::instruction_length(first_halfword);
+
+        let is_breakpoint = if length == 2 {
+            first_halfword == assembly::C_EBREAK
+        } else {
+            self.read_8(u64::from(address) + 2, &mut bytes[2..])?;
+            u32::from_le_bytes(bytes) == assembly::EBREAK
+        };
+
+        if is_breakpoint {
+            Ok(Some(length))
+        } else {
+            Ok(None)
+        }
+    }
+
     fn read_csr(&mut self, address: u16) -> Result<u32, RiscvError> {
         // We need to use the "Access Register Command",
         // which has cmdtype 0
@@ -100,19 +131,16 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         let current_dmcontrol: Dmcontrol = self.interface.read_dm_register()?;
         log::debug!("{:?}", current_dmcontrol);
 
-        let mut dmcontrol = Dmcontrol(0);
+        let mut dmcontrol = self.interface.dmcontrol();
 
         dmcontrol.set_haltreq(true);
-        dmcontrol.set_dmactive(true);
 
         self.interface.write_dm_register(dmcontrol)?;
 
         self.wait_for_core_halted(timeout)?;
 
         // clear the halt request
-        let mut dmcontrol = Dmcontrol(0);
-
-        dmcontrol.set_dmactive(true);
+        let dmcontrol = self.interface.dmcontrol();
 
         self.interface.write_dm_register(dmcontrol)?;
 
@@ -125,8 +153,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         // TODO: test if core halted?
 
         // set resume request
-        let mut dmcontrol = Dmcontrol(0);
-        dmcontrol.set_dmactive(true);
+        let mut dmcontrol = self.interface.dmcontrol();
         dmcontrol.set_resumereq(true);
 
         self.interface.write_dm_register(dmcontrol)?;
@@ -139,8 +166,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         };
 
         // clear resume request
-        let mut dmcontrol = Dmcontrol(0);
-        dmcontrol.set_dmactive(true);
+        let dmcontrol = self.interface.dmcontrol();
 
         self.interface.write_dm_register(dmcontrol)?;
 
@@ -150,8 +176,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
     fn reset(&mut self) -> Result<(), crate::Error> {
         log::debug!("Resetting core, setting hartreset bit");
 
-        let mut dmcontrol = Dmcontrol(0);
-        dmcontrol.set_dmactive(true);
+        let mut dmcontrol = self.interface.dmcontrol();
         dmcontrol.set_hartreset(true);
 
         self.interface.write_dm_register(dmcontrol)?;
@@ -162,8 +187,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         if readback.hartreset() {
             log::debug!("Clearing hartreset bit");
             // Reset is performed by setting the bit high, and then low again
-            let mut dmcontrol = Dmcontrol(0);
-            dmcontrol.set_dmactive(true);
+            let mut dmcontrol = self.interface.dmcontrol();
             dmcontrol.set_hartreset(false);
 
             self.interface.write_dm_register(dmcontrol)?;
@@ -172,15 +196,13 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
             //
             // TODO: Cache this
             log::debug!("Hartreset bit not supported, using ndmreset");
-            let mut dmcontrol = Dmcontrol(0);
-            dmcontrol.set_dmactive(true);
+            let mut dmcontrol = self.interface.dmcontrol();
             dmcontrol.set_ndmreset(true);
 
             self.interface.write_dm_register(dmcontrol)?;
 
             log::debug!("Clearing ndmreset bit");
-            let mut dmcontrol = Dmcontrol(0);
-            dmcontrol.set_dmactive(true);
+            let mut dmcontrol = self.interface.dmcontrol();
             dmcontrol.set_ndmreset(false);
 
             self.interface.write_dm_register(dmcontrol)?;
@@ -196,8 +218,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         }
 
         // acknowledge the reset
-        let mut dmcontrol = Dmcontrol(0);
-        dmcontrol.set_dmactive(true);
+        let mut dmcontrol = self.interface.dmcontrol();
         dmcontrol.set_ackhavereset(true);
 
         self.interface.write_dm_register(dmcontrol)?;
@@ -211,8 +232,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
     ) -> Result<crate::core::CoreInformation, crate::Error> {
         log::debug!("Resetting core, setting hartreset bit");
 
-        let mut dmcontrol = Dmcontrol(0);
-        dmcontrol.set_dmactive(true);
+        let mut dmcontrol = self.interface.dmcontrol();
         dmcontrol.set_hartreset(true);
         dmcontrol.set_haltreq(true);
 
@@ -224,8 +244,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         if readback.hartreset() {
             log::debug!("Clearing hartreset bit");
             // Reset is performed by setting the bit high, and then low again
-            let mut dmcontrol = Dmcontrol(0);
-            dmcontrol.set_dmactive(true);
+            let mut dmcontrol = self.interface.dmcontrol();
             dmcontrol.set_haltreq(true);
             dmcontrol.set_hartreset(false);
 
@@ -235,16 +254,14 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
             //
             // TODO: Cache this
             log::debug!("Hartreset bit not supported, using ndmreset");
-            let mut dmcontrol = Dmcontrol(0);
-            dmcontrol.set_dmactive(true);
+            let mut dmcontrol = self.interface.dmcontrol();
             dmcontrol.set_ndmreset(true);
             dmcontrol.set_haltreq(true);
 
             self.interface.write_dm_register(dmcontrol)?;
 
             log::debug!("Clearing ndmreset bit");
-            let mut dmcontrol = Dmcontrol(0);
-            dmcontrol.set_dmactive(true);
+            let mut dmcontrol = self.interface.dmcontrol();
             dmcontrol.set_ndmreset(false);
             dmcontrol.set_haltreq(true);
 
@@ -259,8 +276,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         }
 
         // acknowledge the reset, clear the halt request
-        let mut dmcontrol = Dmcontrol(0);
-        dmcontrol.set_dmactive(true);
+        let mut dmcontrol = self.interface.dmcontrol();
         dmcontrol.set_ackhavereset(true);
 
         self.interface.write_dm_register(dmcontrol)?;
@@ -273,6 +289,20 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
     fn step(&mut self) -> Result<crate::core::CoreInformation, crate::Error> {
         let mut dcsr = Dcsr(self.read_core_reg(RegisterId(0x7b0))?.try_into()?);
 
+        // When halted by an ebreak instruction, the dpc points to the ebreak itself,
+        // so stepping would only hit it again. Skip over it instead, taking into
+        // account that it can be a compressed instruction.
+        if dcsr.cause() == 1 {
+            let pc: u32 = self.read_core_reg(RegisterId(0x7b1))?.try_into()?;
+
+            if let Some(length) = self.breakpoint_instruction_length(pc)? {
+                let next_pc = pc.wrapping_add(length as u32);
+                self.write_csr(0x7b1, next_pc)?;
+
+                return Ok(CoreInformation { pc: next_pc.into() });
+            }
+        }
+
         dcsr.set_step(true);
 
         self.write_csr(0x7b0, dcsr.0)?;
@@ -460,7 +490,11 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
     }
 
     fn instruction_set(&mut self) -> Result<InstructionSet, Error> {
-        Ok(InstructionSet::RV32)
+        if self.supports_compressed_instructions()? {
+            Ok(InstructionSet::RV32C)
+        } else {
+            Ok(InstructionSet::RV32)
+        }
     }
 
     fn status(&mut self) -> Result<crate::core::CoreStatus, crate::Error> {
@@ -545,6 +579,15 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
             "Fpu detection not yet implemented"
         )))
     }
+
+    fn supports_background_memory_access(&mut self) -> bool {
+        // Accesses with the program buffer or abstract commands need a halted hart.
+        self.interface
+            .supports_system_bus_access(RiscvBusAccess::A8)
+            && self
+                .interface
+                .supports_system_bus_access(RiscvBusAccess::A32)
+    }
 }
 
 impl<'probe> MemoryInterface for Riscv32<'probe> {
@@ -778,6 +821,11 @@ data_register! { pub Data9, 0x0D, "data9" }
 data_register! { pub Data10, 0x0E, "data10" }
 data_register! { pub Data11, 0x0f, "data11" }
 
+data_register! { pub Hawindowsel, 0x14, "hawindowsel" }
+data_register! { pub Hawindow, 0x15, "hawindow" }
+
+data_register! { pub Authdata, 0x30, "authdata" }
+
 data_register! { Command, 0x17, "command" }
 
 data_register! { pub Progbuf0, 0x20, "progbuf0" }
@@ -106,7 +106,7 @@ pub static S1: RegisterDescription = RegisterDescription {
     size_in_bits: 32,
 };
 
-pub(super) static RISCV_REGISTERS: RegisterFile = RegisterFile {
+pub(crate) static RISCV_REGISTERS: RegisterFile = RegisterFile {
     platform_registers: &[
         RegisterDescription {
             name: "x0",
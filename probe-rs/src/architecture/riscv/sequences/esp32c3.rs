@@ -16,10 +16,11 @@ impl ESP32C3 {
 }
 
 impl RiscvDebugSequence for ESP32C3 {
-    fn on_connect(
+    fn watchdog_suspend(
         &self,
         interface: &mut crate::architecture::riscv::communication_interface::RiscvCommunicationInterface,
     ) -> Result<(), crate::Error> {
+        // The watchdogs don't stop while the core is halted, so they are disabled.
         log::info!("Disabling esp32c3 watchdogs...");
         // disable super wdt
         interface.write_word_32(0x600080B0, 0x8F1D312Au32)?; // write protection off
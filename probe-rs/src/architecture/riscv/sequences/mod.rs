@@ -13,6 +13,16 @@ pub trait RiscvDebugSequence: Send + Sync {
     fn on_connect(&self, _interface: &mut RiscvCommunicationInterface) -> Result<(), crate::Error> {
         Ok(())
     }
+
+    /// Executed after connecting, to stop the hardware watchdogs of the target while it is
+    /// halted, where the chip supports it. It is skipped if the session was opened with
+    /// [`AttachOptions::keep_watchdogs_running`](crate::AttachOptions::keep_watchdogs_running).
+    fn watchdog_suspend(
+        &self,
+        _interface: &mut RiscvCommunicationInterface,
+    ) -> Result<(), crate::Error> {
+        Ok(())
+    }
 }
 
 /// The default sequences that is used for RISC-V chips that do not specify a specific sequence.
@@ -0,0 +1,185 @@
+use std::ops::Range;
+
+use super::{MemoryRange, MemoryRegion, RawFlashAlgorithm, SectorInfo, Target};
+
+/// A region in the memory map of a target, as returned by [`Session::memory_regions`]
+/// and [`Core::memory_regions`].
+///
+/// [`Session::memory_regions`]: crate::Session::memory_regions
+/// [`Core::memory_regions`]: crate::Core::memory_regions
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryRegionInfo {
+    /// The name of the region, if the target description contains one.
+    pub name: Option<String>,
+    /// The address range of the region.
+    pub range: Range<u64>,
+    /// The kind of memory in the region.
+    pub kind: MemoryRegionKind,
+    /// The names of the cores which can access the region.
+    pub cores: Vec<String>,
+}
+
+impl MemoryRegionInfo {
+    /// Check if the region contains `address`.
+    pub fn contains(&self, address: u64) -> bool {
+        self.range.contains(&address)
+    }
+
+    /// Check if the core with the given name can access the region.
+    pub fn is_accessible_by(&self, core_name: &str) -> bool {
+        // Regions which don't list any cores are accessible by all of them.
+        self.cores.is_empty() || self.cores.iter().any(|core| core == core_name)
+    }
+
+    pub(crate) fn from_region(
+        region: &MemoryRegion,
+        flash_algorithms: &[RawFlashAlgorithm],
+    ) -> Self {
+        match region {
+            MemoryRegion::Ram(region) => MemoryRegionInfo {
+                name: region.name.clone(),
+                range: region.range.clone(),
+                kind: MemoryRegionKind::Ram {
+                    is_boot_memory: region.is_boot_memory,
+                },
+                cores: region.cores.clone(),
+            },
+            MemoryRegion::Nvm(region) => MemoryRegionInfo {
+                name: region.name.clone(),
+                range: region.range.clone(),
+                kind: MemoryRegionKind::Nvm {
+                    is_boot_memory: region.is_boot_memory,
+                    sectors: sectors(&region.range, flash_algorithms),
+                },
+                cores: region.cores.clone(),
+            },
+            MemoryRegion::Generic(region) => MemoryRegionInfo {
+                name: region.name.clone(),
+                range: region.range.clone(),
+                kind: MemoryRegionKind::Generic,
+                cores: region.cores.clone(),
+            },
+        }
+    }
+}
+
+/// The kind of memory in a [`MemoryRegionInfo`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum MemoryRegionKind {
+    /// RAM.
+    Ram {
+        /// True if the chip boots from this memory.
+        is_boot_memory: bool,
+    },
+    /// Flash, EEPROM or other non-volatile memory.
+    Nvm {
+        /// True if the chip boots from this memory.
+        is_boot_memory: bool,
+        /// The sectors of the region, which can be erased individually.
+        ///
+        /// This is empty if there is no flash algorithm for the region.
+        sectors: Vec<SectorInfo>,
+    },
+    /// Any other memory, e.g. peripherals.
+    Generic,
+}
+
+/// The memory regions of `target`.
+pub(crate) fn memory_regions(target: &Target) -> Vec<MemoryRegionInfo> {
+    target
+        .memory_map
+        .iter()
+        .map(|region| MemoryRegionInfo::from_region(region, &target.flash_algorithms))
+        .collect()
+}
+
+/// The sectors in `range`, according to the flash algorithm which covers it.
+fn sectors(range: &Range<u64>, flash_algorithms: &[RawFlashAlgorithm]) -> Vec<SectorInfo> {
+    // Prefer the default algorithm, as the flash loader does.
+    let mut candidates = flash_algorithms
+        .iter()
+        .filter(|algorithm| {
+            algorithm
+                .flash_properties
+                .address_range
+                .contains_range(range)
+        })
+        .collect::<Vec<_>>();
+    candidates.sort_by_key(|algorithm| !algorithm.default);
+
+    let properties = match candidates.first() {
+        Some(algorithm) => &algorithm.flash_properties,
+        None => return vec![],
+    };
+
+    let mut sectors = Vec::new();
+    for (i, description) in properties.sectors.iter().enumerate() {
+        let start = properties.address_range.start + description.address;
+        let end = properties
+            .sectors
+            .get(i + 1)
+            .map(|next| properties.address_range.start + next.address)
+            .unwrap_or(properties.address_range.end);
+
+        let mut address = start;
+        while address < end && description.size > 0 {
+            if range.contains(&address) {
+                sectors.push(SectorInfo {
+                    base_address: address,
+                    size: description.size,
+                });
+            }
+            address += description.size;
+        }
+    }
+
+    sectors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{FlashProperties, NvmRegion, SectorDescription};
+
+    #[test]
+    fn nvm_region_contains_sectors_of_algorithm() {
+        let algorithm = RawFlashAlgorithm {
+            flash_properties: FlashProperties {
+                address_range: 0x0800_0000..0x0804_0000,
+                sectors: vec![
+                    SectorDescription {
+                        size: 0x4000,
+                        address: 0,
+                    },
+                    SectorDescription {
+                        size: 0x10000,
+                        address: 0x10000,
+                    },
+                ],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let region = MemoryRegion::Nvm(NvmRegion {
+            name: None,
+            range: 0x0800_0000..0x0804_0000,
+            is_boot_memory: true,
+            cores: vec!["main".to_owned()],
+        });
+
+        let info = MemoryRegionInfo::from_region(&region, &[algorithm]);
+
+        let sectors = match &info.kind {
+            MemoryRegionKind::Nvm { sectors, .. } => sectors,
+            kind => panic!("Unexpected kind {:?}", kind),
+        };
+        let sizes = sectors.iter().map(|s| s.size).collect::<Vec<_>>();
+        assert_eq!(
+            sizes,
+            [0x4000, 0x4000, 0x4000, 0x4000, 0x10000, 0x10000, 0x10000]
+        );
+        assert_eq!(sectors[4].base_address, 0x0801_0000);
+        assert!(info.is_accessible_by("main"));
+        assert!(!info.is_accessible_by("other"));
+    }
+}
@@ -15,28 +15,45 @@
 //! crate.
 //!
 //! The built-in targets can be disabled by not including the `builtin-targets` feature.
+//! To only include some of the target families, disable the default features and enable
+//! the feature of each family instead, e.g. `target-stm32f4` for the targets in
+//! `targets/STM32F4_Series.yaml`.
 //!
 //! ## Adding targets at runtime
 //!
 //! To add a target at runtime, the [add_target_from_yaml] file can
-//! be used to read targets from a YAML file.
+//! be used to read targets from a YAML file, and [add_targets_from_dir]
+//! to read all target files in a directory. Chip families which are generated
+//! at runtime, e.g. from a CMSIS-Pack, can be added with [add_target_family].
+//!
+//! Targets which are added at runtime take precedence over the built-in targets
+//! and the targets which were added before.
 //!
 
 mod chip_info;
+mod memory_map;
 mod registry;
 mod target;
+mod variant;
 
 pub use probe_rs_target::{
-    Chip, ChipFamily, Core, CoreType, FlashProperties, InstructionSet, MemoryRange, MemoryRegion,
-    NvmRegion, PageInfo, RamRegion, RawFlashAlgorithm, SectorDescription, SectorInfo,
-    TargetDescriptionSource,
+    Chip, ChipFamily, ConnectUnderReset, Core, CoreType, DebugSequenceDescription,
+    DebugSequenceScript, FlashProperties, InstructionSet, MemoryRange, MemoryRegion, NvmRegion,
+    PageInfo, RamRegion, RawFlashAlgorithm, SectorDescription, SectorInfo, SequenceBlock,
+    SequenceControl, SequenceElement, TargetDescriptionSource,
 };
 
+pub use memory_map::{MemoryRegionInfo, MemoryRegionKind};
 pub use registry::{
-    add_target_from_yaml, families, get_target_by_name, search_chips, RegistryError,
+    add_target_family, add_target_from_yaml, add_targets_from_dir, families, get_target_by_name,
+    list_families, register_debug_sequence, search, search_chips, ChipMatch, FamilySummary,
+    MatchQuality, RegistryError,
 };
 pub use target::{DebugSequence, Target, TargetParseError, TargetSelector};
+pub use variant::VariantId;
 
 // Crate-internal API
 pub(crate) use chip_info::ChipInfo;
-pub(crate) use registry::get_target_by_chip_info;
+pub(crate) use memory_map::memory_regions;
+pub(crate) use registry::{get_target_by_chip_info, get_target_by_variant_id};
+pub(crate) use variant::{main_flash_size, read_variant_id, Vendor};
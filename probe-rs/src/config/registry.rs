@@ -1,11 +1,14 @@
 //! Internal target registry
 
-use super::{Chip, ChipFamily, ChipInfo, Core, Target, TargetDescriptionSource};
+use super::variant::{main_flash_size, VariantId};
+use super::{Chip, ChipFamily, ChipInfo, Core, DebugSequence, Target, TargetDescriptionSource};
 use crate::config::CoreType;
+use jep106::JEP106Code;
 use once_cell::sync::Lazy;
-use probe_rs_target::{CoreAccessOptions, RiscvCoreAccessOptions};
+use probe_rs_target::{CoreAccessOptions, MipsCoreAccessOptions, RiscvCoreAccessOptions};
+use std::collections::HashMap;
 use std::fs::File;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
 static REGISTRY: Lazy<Arc<Mutex<Registry>>> =
@@ -37,7 +40,54 @@ pub enum RegistryError {
     Yaml(#[from] serde_yaml::Error),
     /// An invalid [`ChipFamily`] was encountered.
     #[error("Invalid chip family definition ({})", .0.name)]
-    InvalidChipFamilyDefinition(ChipFamily, String),
+    InvalidChipFamilyDefinition(Box<ChipFamily>, String),
+    /// A target description file in a directory could not be loaded.
+    #[error("Failed to load the target description file {}", path.display())]
+    InvalidTargetFile {
+        /// The path of the file.
+        path: PathBuf,
+        /// The reason why the file could not be loaded.
+        #[source]
+        source: Box<RegistryError>,
+    },
+}
+
+/// A chip which matches a search query, see [`search`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChipMatch {
+    /// The name of the family of the chip.
+    pub family: String,
+    /// The name of the chip.
+    pub chip: String,
+    /// How well the name of the chip matches the query.
+    pub quality: MatchQuality,
+}
+
+/// How well the name of a chip matches a search query.
+///
+/// Better matches compare as smaller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MatchQuality {
+    /// The name matches the query, ignoring the case.
+    Exact,
+    /// The name starts with the query.
+    Prefix,
+    /// The name contains the query.
+    Substring,
+    /// The name differs from the query by the given number of edits,
+    /// e.g. because of a typo.
+    Similar(usize),
+}
+
+/// A summary of a chip family, without its flash algorithms.
+#[derive(Debug, Clone)]
+pub struct FamilySummary {
+    /// The name of the family.
+    pub name: String,
+    /// The JEP106 code of the manufacturer.
+    pub manufacturer: Option<JEP106Code>,
+    /// The chips of the family, with their cores and memory maps.
+    pub variants: Vec<Chip>,
 }
 
 fn add_generic_targets(vec: &mut Vec<ChipFamily>) {
@@ -52,6 +102,8 @@ fn add_generic_targets(vec: &mut Vec<ChipFamily>) {
             ],
 
             flash_algorithms: vec![],
+            debug_vars: None,
+            debug_sequences: vec![],
             source: TargetDescriptionSource::Generic,
         },
         ChipFamily {
@@ -59,6 +111,8 @@ fn add_generic_targets(vec: &mut Vec<ChipFamily>) {
             manufacturer: None,
             variants: vec![Chip::generic_arm("Cortex-M3", CoreType::Armv7m)],
             flash_algorithms: vec![],
+            debug_vars: None,
+            debug_sequences: vec![],
             source: TargetDescriptionSource::Generic,
         },
         ChipFamily {
@@ -69,6 +123,8 @@ fn add_generic_targets(vec: &mut Vec<ChipFamily>) {
                 Chip::generic_arm("Cortex-M7", CoreType::Armv7em),
             ],
             flash_algorithms: vec![],
+            debug_vars: None,
+            debug_sequences: vec![],
             source: TargetDescriptionSource::Generic,
         },
         ChipFamily {
@@ -81,6 +137,8 @@ fn add_generic_targets(vec: &mut Vec<ChipFamily>) {
                 Chip::generic_arm("Cortex-M55", CoreType::Armv8m),
             ],
             flash_algorithms: vec![],
+            debug_vars: None,
+            debug_sequences: vec![],
             source: TargetDescriptionSource::Generic,
         },
         ChipFamily {
@@ -92,12 +150,37 @@ fn add_generic_targets(vec: &mut Vec<ChipFamily>) {
                 cores: vec![Core {
                     name: "core".to_owned(),
                     core_type: CoreType::Riscv,
-                    core_access_options: CoreAccessOptions::Riscv(RiscvCoreAccessOptions {}),
+                    core_access_options: CoreAccessOptions::Riscv(RiscvCoreAccessOptions::default()),
                 }],
                 memory_map: vec![],
                 flash_algorithms: vec![],
+                connect_under_reset: None,
+                rtt_scan_ranges: vec![],
             }],
             flash_algorithms: vec![],
+            debug_vars: None,
+            debug_sequences: vec![],
+            source: TargetDescriptionSource::Generic,
+        },
+        ChipFamily {
+            name: "Generic MIPS32".to_owned(),
+            manufacturer: None,
+            variants: vec![Chip {
+                name: "mips32".to_owned(),
+                part: None,
+                cores: vec![Core {
+                    name: "core".to_owned(),
+                    core_type: CoreType::Mips32,
+                    core_access_options: CoreAccessOptions::Mips(MipsCoreAccessOptions::default()),
+                }],
+                memory_map: vec![],
+                flash_algorithms: vec![],
+                connect_under_reset: None,
+                rtt_scan_ranges: vec![],
+            }],
+            flash_algorithms: vec![],
+            debug_vars: None,
+            debug_sequences: vec![],
             source: TargetDescriptionSource::Generic,
         },
     ]);
@@ -107,11 +190,13 @@ fn add_generic_targets(vec: &mut Vec<ChipFamily>) {
 struct Registry {
     /// All the available chips.
     families: Vec<ChipFamily>,
+    /// Debug sequences which were registered for a chip, by the lowercase name of the chip.
+    debug_sequences: HashMap<String, DebugSequence>,
 }
 
 impl Registry {
-    #[cfg(feature = "builtin-targets")]
     fn from_builtin_families() -> Self {
+        // The targets which were selected with the `builtin-targets` or `target-*` features.
         const BUILTIN_TARGETS: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/targets.bincode"));
 
         let mut families: Vec<ChipFamily> = bincode::deserialize(BUILTIN_TARGETS)
@@ -123,19 +208,10 @@ impl Registry {
         // Additionally, validation for existing targets is done in the tests `validate_generic_targets` and
         // `validate_builtin` as well, to ensure we do not ship broken target definitions.
 
-        Self { families }
-    }
-
-    #[cfg(not(feature = "builtin-targets"))]
-    fn from_builtin_families() -> Self {
-        let mut families = vec![];
-        add_generic_targets(&mut families);
-
-        // We skip validating the targets here as this is done at a later stage in `get_target`.
-        // Additionally, validation for existing targets is done in the tests `validate_generic_targets` and
-        // `validate_builtin` as well, to ensure we do not ship broken target definitions.
-
-        Self { families }
+        Self {
+            families,
+            debug_sequences: HashMap::new(),
+        }
     }
 
     fn families(&self) -> &Vec<ChipFamily> {
@@ -219,6 +295,48 @@ impl Registry {
         targets
     }
 
+    fn search(&self, query: &str) -> Vec<ChipMatch> {
+        let mut matches = Vec::new();
+
+        for family in &self.families {
+            for variant in family.variants.iter() {
+                if let Some(quality) = match_quality(query, &variant.name) {
+                    matches.push(ChipMatch {
+                        family: family.name.clone(),
+                        chip: variant.name.clone(),
+                        quality,
+                    });
+                }
+            }
+        }
+
+        matches.sort_by(|a, b| a.quality.cmp(&b.quality).then_with(|| a.chip.cmp(&b.chip)));
+        matches
+    }
+
+    fn get_target_by_variant_id(&self, id: &VariantId) -> Result<Target, RegistryError> {
+        let prefix = id.name_prefix.to_ascii_lowercase();
+
+        let mut candidates = self.families.iter().flat_map(|family| {
+            family
+                .variants
+                .iter()
+                .filter(|variant| variant.name.to_ascii_lowercase().starts_with(&prefix))
+                .filter(|variant| {
+                    id.flash_size.is_none() || main_flash_size(&variant.memory_map) == id.flash_size
+                })
+                .map(move |variant| (family, variant))
+        });
+
+        match (candidates.next(), candidates.next()) {
+            (Some((family, chip)), None) => self.get_target(family, chip),
+            _ => {
+                log::debug!("Found no unique chip for variant {:?}", id);
+                Err(RegistryError::ChipAutodetectFailed)
+            }
+        }
+    }
+
     fn get_target_by_chip_info(&self, chip_info: ChipInfo) -> Result<Target, RegistryError> {
         let (family, chip) = {
             match chip_info {
@@ -263,28 +381,108 @@ impl Registry {
 
     fn get_target(&self, family: &ChipFamily, chip: &Chip) -> Result<Target, RegistryError> {
         // The validity of the given `ChipFamily` is checked in the constructor.
-        Target::new(family, &chip.name)
+        let mut target = Target::new(family, &chip.name)?;
+
+        if let Some(sequence) = self.debug_sequences.get(&chip.name.to_ascii_lowercase()) {
+            log::debug!("Using the registered debug sequence for {}", chip.name);
+            target.debug_sequence = sequence.clone();
+        }
+
+        Ok(target)
+    }
+
+    fn register_debug_sequence(&mut self, chip_name: &str, sequence: DebugSequence) {
+        self.debug_sequences
+            .insert(chip_name.to_ascii_lowercase(), sequence);
     }
 
     fn add_target_from_yaml(&mut self, path_to_yaml: &Path) -> Result<(), RegistryError> {
-        let file = File::open(path_to_yaml)?;
-        let family: ChipFamily = serde_yaml::from_reader(file)?;
+        let family = read_family(path_to_yaml)?;
+        self.add_family(family);
+
+        Ok(())
+    }
 
+    fn add_target_family(&mut self, family: ChipFamily) -> Result<(), RegistryError> {
         family
             .validate()
-            .map_err(|e| RegistryError::InvalidChipFamilyDefinition(family.clone(), e))?;
+            .map_err(|e| RegistryError::InvalidChipFamilyDefinition(Box::new(family.clone()), e))?;
+        self.add_family(family);
 
-        let index = self
-            .families
-            .iter()
-            .position(|old_family| old_family.name == family.name);
-        if let Some(index) = index {
-            self.families.remove(index);
+        Ok(())
+    }
+
+    fn add_targets_from_dir(&mut self, path: &Path) -> Result<(), RegistryError> {
+        let mut files = std::fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        files.retain(|file| {
+            matches!(
+                file.extension().and_then(|extension| extension.to_str()),
+                Some("yaml") | Some("yml")
+            )
+        });
+        files.sort();
+
+        // All files are read first, so nothing is added if one of them is invalid.
+        let families = files
+            .into_iter()
+            .map(|file| {
+                read_family(&file).map_err(|source| RegistryError::InvalidTargetFile {
+                    path: file,
+                    source: Box::new(source),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for family in families {
+            self.add_family(family);
         }
-        self.families.push(family);
 
         Ok(())
     }
+
+    /// Add a validated chip family, which takes precedence over the families which are already in the registry.
+    ///
+    /// A family with the same name is replaced, and chips with the same name are removed from other families.
+    fn add_family(&mut self, family: ChipFamily) {
+        self.families
+            .retain(|old_family| old_family.name != family.name);
+
+        for old_family in &mut self.families {
+            old_family.variants.retain(|old_variant| {
+                let replaced = family
+                    .variants
+                    .iter()
+                    .any(|variant| variant.name.eq_ignore_ascii_case(&old_variant.name));
+                if replaced {
+                    log::debug!(
+                        "Chip {} of family {} is replaced by family {}",
+                        old_variant.name,
+                        old_family.name,
+                        family.name
+                    );
+                }
+                !replaced
+            });
+        }
+        self.families
+            .retain(|old_family| !old_family.variants.is_empty());
+
+        self.families.push(family);
+    }
+}
+
+/// Read and validate the chip family in a target description file.
+fn read_family(path_to_yaml: &Path) -> Result<ChipFamily, RegistryError> {
+    let file = File::open(path_to_yaml)?;
+    let family: ChipFamily = serde_yaml::from_reader(file)?;
+
+    family
+        .validate()
+        .map_err(|e| RegistryError::InvalidChipFamilyDefinition(Box::new(family.clone()), e))?;
+
+    Ok(family)
 }
 
 /// Get a target from the internal registry based on its name.
@@ -302,12 +500,78 @@ pub(crate) fn get_target_by_chip_info(chip_info: ChipInfo) -> Result<Target, Reg
     REGISTRY.lock().unwrap().get_target_by_chip_info(chip_info)
 }
 
+/// Try to retrieve a target based on the [VariantId] read from a target.
+pub(crate) fn get_target_by_variant_id(id: &VariantId) -> Result<Target, RegistryError> {
+    REGISTRY.lock().unwrap().get_target_by_variant_id(id)
+}
+
 /// Parse a target description file and add the contained targets
 /// to the internal target registry.
+///
+/// The added targets take precedence over the targets which are already in the registry:
+/// A family with the same name is replaced, and chips with the same name are removed from other families.
 pub fn add_target_from_yaml(path_to_yaml: &Path) -> Result<(), RegistryError> {
     REGISTRY.lock().unwrap().add_target_from_yaml(path_to_yaml)
 }
 
+/// Parse all target description files (`*.yaml` and `*.yml`) in a directory
+/// and add the contained targets to the internal target registry.
+///
+/// The files are added in the order of their file names, as with [`add_target_from_yaml`].
+/// If any of the files is invalid, no targets are added.
+pub fn add_targets_from_dir(path: &Path) -> Result<(), RegistryError> {
+    REGISTRY.lock().unwrap().add_targets_from_dir(path)
+}
+
+/// Add a chip family to the internal target registry, e.g. one which was generated at runtime.
+///
+/// The family takes precedence over the targets which are already in the registry, as with [`add_target_from_yaml`].
+pub fn add_target_family(family: ChipFamily) -> Result<(), RegistryError> {
+    REGISTRY.lock().unwrap().add_target_family(family)
+}
+
+/// Register a debug sequence for the chip with the given name.
+///
+/// Targets of the chip which are loaded from the registry afterwards use this sequence
+/// instead of the built-in one, or the one from the target description. This allows handling
+/// board specific requirements, like an external reset controller, without changing probe-rs.
+/// The name has to match the name of the chip exactly, ignoring the case.
+///
+/// To use a sequence only for a single session, set [`Target::debug_sequence`] before attaching,
+/// or use [`Session::set_debug_sequence`](crate::Session::set_debug_sequence).
+pub fn register_debug_sequence(chip_name: impl AsRef<str>, sequence: DebugSequence) {
+    REGISTRY
+        .lock()
+        .unwrap()
+        .register_debug_sequence(chip_name.as_ref(), sequence)
+}
+
+/// Search the internal registry for chips whose name matches `query`.
+///
+/// The search ignores the case, treats a lower-case `x` in a chip name as a wildcard, and also
+/// returns chips whose name is similar to the query, so it can be used to suggest the chip
+/// which was meant after a typo. The best matches are returned first.
+pub fn search(query: impl AsRef<str>) -> Vec<ChipMatch> {
+    REGISTRY.lock().unwrap().search(query.as_ref())
+}
+
+/// Get a summary of all families which are contained in the internal registry.
+///
+/// In contrast to [`families`], this does not copy the flash algorithms of the families.
+pub fn list_families() -> Vec<FamilySummary> {
+    REGISTRY
+        .lock()
+        .unwrap()
+        .families()
+        .iter()
+        .map(|family| FamilySummary {
+            name: family.name.clone(),
+            manufacturer: family.manufacturer,
+            variants: family.variants.clone(),
+        })
+        .collect()
+}
+
 /// Get a list of all families which are contained in the internal
 /// registry.
 pub fn families() -> Result<Vec<ChipFamily>, RegistryError> {
@@ -329,6 +593,57 @@ fn match_name_prefix(pattern: &str, name: &str) -> bool {
     true
 }
 
+/// Determine how well the chip `name` matches the search `query`, if at all.
+fn match_quality(query: &str, name: &str) -> Option<MatchQuality> {
+    let query = query.to_ascii_lowercase();
+    let query_len = query.chars().count();
+    let name_len = name.chars().count();
+
+    if query_len <= name_len && match_name_prefix(name, &query) {
+        if query_len == name_len {
+            return Some(MatchQuality::Exact);
+        }
+        return Some(MatchQuality::Prefix);
+    }
+
+    let lower_name = name.to_ascii_lowercase();
+    if lower_name.contains(&query) {
+        return Some(MatchQuality::Substring);
+    }
+
+    // Very short queries would be similar to almost everything.
+    if query_len < 3 {
+        return None;
+    }
+
+    // Compare with the start of the name as well, so a typo in a partial name is found.
+    let start = lower_name.chars().take(query_len).collect::<String>();
+    let distance = edit_distance(&query, &lower_name).min(edit_distance(&query, &start));
+    if distance <= query_len / 4 + 1 {
+        Some(MatchQuality::Similar(distance))
+    } else {
+        None
+    }
+}
+
+/// The Levenshtein distance between `a` and `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, a_char) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, &b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -373,6 +688,121 @@ mod tests {
         assert!(registry.get_target_by_name("nrf51822_Xxaa").is_ok());
     }
 
+    #[test]
+    fn added_targets_take_precedence() {
+        let mut registry = Registry::from_builtin_families();
+        let mut family = registry
+            .families()
+            .iter()
+            .find(|family| family.name == "nRF51 Series")
+            .unwrap()
+            .clone();
+        family.name = "Custom nRF51".to_owned();
+        family
+            .variants
+            .retain(|variant| variant.name == "nRF51822_xxAA");
+
+        let dir = std::env::temp_dir().join(format!("probe-rs-targets-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("custom.yaml"),
+            serde_yaml::to_string(&family).unwrap(),
+        )
+        .unwrap();
+        std::fs::write(dir.join("README.md"), "Not a target description").unwrap();
+
+        registry.add_targets_from_dir(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let target = registry.get_target_by_name("nrf51822_Xxaa").unwrap();
+        assert_eq!(target.source(), &TargetDescriptionSource::External);
+        // The other chips of the built-in family are still available.
+        assert!(registry.get_target_by_name("nRF51422_xxAA").is_ok());
+    }
+
+    #[test]
+    fn search_orders_matches() {
+        let registry = Registry::from_builtin_families();
+
+        let matches = registry.search("nrf51822_xxaa");
+        assert_eq!(matches[0].chip, "nRF51822_xxAA");
+        assert_eq!(matches[0].quality, MatchQuality::Exact);
+
+        let matches = registry.search("nrf51822");
+        assert_eq!(matches[0].quality, MatchQuality::Prefix);
+        assert!(matches[0].chip.starts_with("nRF51822"));
+        assert!(matches
+            .windows(2)
+            .all(|pair| pair[0].quality <= pair[1].quality));
+    }
+
+    #[test]
+    fn search_finds_typos() {
+        let registry = Registry::from_builtin_families();
+
+        let matches = registry.search("nrf51832_xxaa");
+        assert!(matches
+            .iter()
+            .any(|m| m.chip == "nRF51822_xxAA" && m.quality == MatchQuality::Similar(1)));
+        assert!(matches
+            .iter()
+            .all(|m| matches!(m.quality, MatchQuality::Similar(_))));
+    }
+
+    #[test]
+    fn edit_distance_counts_edits() {
+        assert_eq!(edit_distance("stm32", "stm32"), 0);
+        assert_eq!(edit_distance("stm32", "stm23"), 2);
+        assert_eq!(edit_distance("stm32f4", "stm32f"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn registered_debug_sequence_is_used() {
+        use crate::architecture::arm::sequences::DefaultArmSequence;
+
+        let mut registry = Registry::from_builtin_families();
+        let sequence = DefaultArmSequence::create();
+        registry.register_debug_sequence("NRF51822_xxAA", DebugSequence::Arm(sequence.clone()));
+
+        let target = registry.get_target_by_name("nrf51822_Xxaa").unwrap();
+        match target.debug_sequence {
+            DebugSequence::Arm(registered) => assert!(Arc::ptr_eq(&registered, &sequence)),
+            DebugSequence::Riscv(_) | DebugSequence::Mips => {
+                panic!("Expected an ARM debug sequence")
+            }
+        }
+    }
+
+    #[test]
+    fn dual_core_targets_use_an_ap_per_core() {
+        let registry = Registry::from_builtin_families();
+
+        for (chip, second_core) in [("STM32H745ZITx", "cm4"), ("LPC55S69JBD100", "cpu1")] {
+            let target = registry.get_target_by_name(chip).unwrap();
+
+            assert_eq!(target.core_index_by_name("main"), Some(0));
+            assert_eq!(target.core_index_by_name(second_core), Some(1));
+
+            let aps = target
+                .cores
+                .iter()
+                .map(|core| match &core.core_access_options {
+                    CoreAccessOptions::Arm(options) => options.ap,
+                    _ => panic!("Expected ARM cores"),
+                })
+                .collect::<Vec<_>>();
+            assert_ne!(aps[0], aps[1]);
+
+            // The flash is accessible by both cores.
+            for region in &target.memory_map {
+                if let crate::config::MemoryRegion::Nvm(region) = region {
+                    assert!(region.cores.iter().any(|core| core == second_core));
+                }
+            }
+        }
+    }
+
     #[test]
     fn validate_generic_targets() {
         let mut families = vec![];
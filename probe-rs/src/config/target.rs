@@ -1,12 +1,24 @@
-use probe_rs_target::{Architecture, ChipFamily};
+use probe_rs_target::{Architecture, ChipFamily, ConnectUnderReset, DebugSequenceScript};
 
 use super::{Core, MemoryRegion, RawFlashAlgorithm, RegistryError, TargetDescriptionSource};
 use crate::architecture::arm::sequences::{
-    nrf53::Nrf5340, nxp::LPC55S69, stm32::Stm32h7, ArmDebugSequence,
+    atsam::AtSAM,
+    kinetis::Kinetis,
+    nrf52::Nrf52,
+    nrf53::Nrf5340,
+    nrf91::Nrf9160,
+    nxp::LPC55S69,
+    pack::{PackDebugSequence, PackSequenceError},
+    rp2040::Rp2040,
+    silabs::SiliconLabs,
+    stm32::{Stm32, Stm32h7},
+    ti::CC13xxCC26xx,
+    ArmDebugSequence,
 };
 use crate::architecture::riscv::sequences::esp32c3::ESP32C3;
 use crate::architecture::riscv::sequences::{DefaultRiscvSequence, RiscvDebugSequence};
-use crate::flashing::FlashLoader;
+use crate::flashing::{EspChip, FlashLoader};
+use std::ops::Range;
 use std::sync::Arc;
 
 use crate::architecture::arm::sequences::DefaultArmSequence;
@@ -28,6 +40,14 @@ pub struct Target {
 
     /// Debug sequences for the given target.
     pub debug_sequence: DebugSequence,
+
+    /// The steps taken when attaching to the target while it is held in reset.
+    pub connect_under_reset: ConnectUnderReset,
+
+    /// The address ranges in which the RTT control block is searched.
+    ///
+    /// If this is empty, all RAM of the target is searched.
+    pub rtt_scan_ranges: Vec<Range<u64>>,
 }
 
 impl std::fmt::Debug for Target {
@@ -66,7 +86,7 @@ impl Target {
         // Make sure we are given a valid family:
         family
             .validate()
-            .map_err(|e| RegistryError::InvalidChipFamilyDefinition(family.clone(), e))?;
+            .map_err(|e| RegistryError::InvalidChipFamilyDefinition(Box::new(family.clone()), e))?;
 
         let chip = family
             .variants
@@ -87,6 +107,7 @@ impl Target {
         let mut debug_sequence = match chip.cores[0].core_type.architecture() {
             Architecture::Arm => DebugSequence::Arm(DefaultArmSequence::create()),
             Architecture::Riscv => DebugSequence::Riscv(DefaultRiscvSequence::create()),
+            Architecture::Mips => DebugSequence::Mips,
         };
 
         if chip.name.starts_with("LPC55S16") || chip.name.starts_with("LPC55S69") {
@@ -95,12 +116,71 @@ impl Target {
         } else if chip.name.starts_with("esp32c3") {
             log::warn!("Using custom sequence for ESP32c3");
             debug_sequence = DebugSequence::Riscv(ESP32C3::create());
+        } else if chip.name.starts_with("nRF52") {
+            log::warn!("Using custom sequence for nRF52");
+            debug_sequence = DebugSequence::Arm(Nrf52::create());
         } else if chip.name.starts_with("nRF5340") {
             log::warn!("Using custom sequence for nRF5340");
             debug_sequence = DebugSequence::Arm(Nrf5340::create());
+        } else if chip.name.starts_with("nRF91") {
+            log::warn!("Using custom sequence for nRF91");
+            debug_sequence = DebugSequence::Arm(Nrf9160::create());
+        } else if ["ATSAMD", "ATSAME5", "ATSAML"]
+            .iter()
+            .any(|prefix| chip.name.starts_with(prefix))
+        {
+            log::warn!("Using custom sequence for SAM D/E/L");
+            debug_sequence = DebugSequence::Arm(AtSAM::create());
+        } else if chip.name.starts_with("EFM32") || chip.name.starts_with("EFR32") {
+            log::warn!("Using custom sequence for EFM32/EFR32");
+            debug_sequence = DebugSequence::Arm(SiliconLabs::create(&chip.name));
+        } else if chip.name.starts_with("RP2040") {
+            log::warn!("Using custom sequence for RP2040");
+            debug_sequence = DebugSequence::Arm(Rp2040::create());
+        } else if chip.name.starts_with("CC13") || chip.name.starts_with("CC26") {
+            log::warn!("Using custom sequence for CC13xx/CC26xx");
+            debug_sequence = DebugSequence::Arm(CC13xxCC26xx::create());
+        } else if chip.name.starts_with("MK") {
+            log::warn!("Using custom sequence for Kinetis");
+            debug_sequence = DebugSequence::Arm(Kinetis::create());
         } else if chip.name.starts_with("STM32H7") {
             log::warn!("Using custom sequence for STM32H7");
             debug_sequence = DebugSequence::Arm(Stm32h7::create());
+        } else if chip.name.starts_with("STM32") {
+            debug_sequence = DebugSequence::Arm(Stm32::create(&chip.name));
+        }
+
+        // Sequences from the target description replace the built-in ones.
+        if !family.debug_sequences.is_empty() {
+            debug_sequence = match debug_sequence {
+                DebugSequence::Arm(fallback) => DebugSequence::Arm(
+                    PackDebugSequence::create(
+                        family.debug_vars.as_deref(),
+                        &family.debug_sequences,
+                        fallback,
+                    )
+                    .map_err(|e| {
+                        RegistryError::InvalidChipFamilyDefinition(
+                            Box::new(family.clone()),
+                            e.to_string(),
+                        )
+                    })?,
+                ),
+                DebugSequence::Riscv(sequence) => {
+                    log::warn!(
+                        "Ignoring the debug sequences of {}, they are only supported for ARM targets",
+                        family.name
+                    );
+                    DebugSequence::Riscv(sequence)
+                }
+                DebugSequence::Mips => {
+                    log::warn!(
+                        "Ignoring the debug sequences of {}, they are only supported for ARM targets",
+                        family.name
+                    );
+                    DebugSequence::Mips
+                }
+            };
         }
 
         Ok(Target {
@@ -110,9 +190,36 @@ impl Target {
             source: family.source.clone(),
             memory_map: chip.memory_map.clone(),
             debug_sequence,
+            connect_under_reset: chip.connect_under_reset.unwrap_or_default(),
+            rtt_scan_ranges: chip.rtt_scan_ranges.clone(),
         })
     }
 
+    /// Replace debug sequences of the target with the sequences of `script`.
+    ///
+    /// This allows running board specific code, e.g. when connecting to the target or before
+    /// its flash is programmed, without writing a target description.
+    /// Sequences which are not contained in `script` are not changed.
+    pub fn add_sequence_script(
+        &mut self,
+        script: &DebugSequenceScript,
+    ) -> Result<(), PackSequenceError> {
+        let fallback = match &self.debug_sequence {
+            DebugSequence::Arm(sequence) => sequence.clone(),
+            DebugSequence::Riscv(_) | DebugSequence::Mips => {
+                return Err(PackSequenceError::UnsupportedArchitecture)
+            }
+        };
+
+        self.debug_sequence = DebugSequence::Arm(PackDebugSequence::create(
+            script.debug_vars.as_deref(),
+            &script.debug_sequences,
+            fallback,
+        )?);
+
+        Ok(())
+    }
+
     /// Get the architecture of the target
     pub fn architecture(&self) -> Architecture {
         let target_arch = self.cores[0].core_type.architecture();
@@ -137,7 +244,13 @@ impl Target {
     /// Create a [FlashLoader] for this target, which can be used
     /// to program its non-volatile memory.
     pub fn flash_loader(&self) -> FlashLoader {
-        FlashLoader::new(self.memory_map.clone(), self.source.clone())
+        let mut loader = FlashLoader::new(self.memory_map.clone(), self.source.clone());
+
+        if let Some(chip) = EspChip::from_target_name(&self.name) {
+            loader.set_esp_chip(chip);
+        }
+
+        loader
     }
 
     /// Gets a [RawFlashAlgorithm] by name.
@@ -145,8 +258,8 @@ impl Target {
         self.flash_algorithms.iter().find(|a| a.name == name)
     }
 
-    /// Gets the core index from the core name
-    pub(crate) fn core_index_by_name(&self, name: &str) -> Option<usize> {
+    /// Gets the core index from the core name, which is the number used by [`Session::core`](crate::Session::core).
+    pub fn core_index_by_name(&self, name: &str) -> Option<usize> {
         self.cores.iter().position(|c| c.name == name)
     }
 
@@ -216,4 +329,6 @@ pub enum DebugSequence {
     Arm(Arc<dyn ArmDebugSequence>),
     /// A RISC-V debug sequence.
     Riscv(Arc<dyn RiscvDebugSequence>),
+    /// MIPS targets have no debug sequences.
+    Mips,
 }
@@ -0,0 +1,248 @@
+//! Detection of the exact variant of a chip from its ID registers.
+//!
+//! The ROM table of an ARM chip only identifies the manufacturer and often the family of a chip,
+//! but not its exact variant, which e.g. determines the size of the flash. Many vendors have
+//! registers which hold this information, like the `DBGMCU_IDCODE` and flash size registers of
+//! STM32 chips or the FICR of Nordic chips.
+
+use jep106::JEP106Code;
+
+use super::{MemoryRegion, Target};
+use crate::{Error, Memory};
+
+/// Information about the variant of a chip, read from its ID registers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VariantId {
+    /// The start of the names of the matching chips, e.g. `STM32F4` or `nRF52840_xxAA`.
+    pub name_prefix: String,
+    /// The size of the main flash in bytes.
+    pub flash_size: Option<u64>,
+}
+
+impl VariantId {
+    /// Check if `target` is a description of this variant.
+    ///
+    /// Returns the reason if it is not.
+    pub fn mismatch(&self, target: &Target) -> Option<String> {
+        if !target
+            .name
+            .to_ascii_lowercase()
+            .starts_with(&self.name_prefix.to_ascii_lowercase())
+        {
+            return Some(format!(
+                "the chip is a {}, but {} was selected",
+                self.name_prefix, target.name
+            ));
+        }
+
+        match (self.flash_size, main_flash_size(&target.memory_map)) {
+            (Some(detected), Some(described)) if detected != described => Some(format!(
+                "the chip has {} KiB of flash, but {} has {} KiB",
+                detected / 1024,
+                target.name,
+                described / 1024
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// The vendors whose ID registers can be read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Vendor {
+    Nordic,
+    St,
+}
+
+impl Vendor {
+    pub(crate) fn from_chip_name(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        if name.starts_with("nrf51") || name.starts_with("nrf52") {
+            Some(Vendor::Nordic)
+        } else if name.starts_with("stm32") {
+            Some(Vendor::St)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn from_manufacturer(manufacturer: JEP106Code) -> Option<Self> {
+        if manufacturer == JEP106Code::new(0x02, 0x44) {
+            Some(Vendor::Nordic)
+        } else if manufacturer == JEP106Code::new(0x00, 0x20) {
+            Some(Vendor::St)
+        } else {
+            None
+        }
+    }
+}
+
+/// Read the ID registers of a chip of the given vendor.
+///
+/// Returns `Ok(None)` if the registers do not contain a known ID.
+pub(crate) fn read_variant_id(
+    memory: &mut Memory<'_>,
+    vendor: Vendor,
+) -> Result<Option<VariantId>, Error> {
+    match vendor {
+        Vendor::Nordic => read_nordic_id(memory),
+        Vendor::St => read_stm32_id(memory),
+    }
+}
+
+const FICR_CODEPAGESIZE: u64 = 0x1000_0010;
+const FICR_CODESIZE: u64 = 0x1000_0014;
+const FICR_INFO_PART: u64 = 0x1000_0100;
+const FICR_INFO_VARIANT: u64 = 0x1000_0104;
+
+fn read_nordic_id(memory: &mut Memory<'_>) -> Result<Option<VariantId>, Error> {
+    let page_size = memory.read_word_32(FICR_CODEPAGESIZE)? as u64;
+    let page_count = memory.read_word_32(FICR_CODESIZE)? as u64;
+
+    // Only the nRF52 series has the INFO registers, they read as all ones on the nRF51.
+    let part = memory.read_word_32(FICR_INFO_PART)?;
+    let name_prefix = if part >> 12 == 0x52 {
+        let variant = memory.read_word_32(FICR_INFO_VARIANT)?.to_be_bytes();
+        match &variant[..2] {
+            [a, b] if a.is_ascii_uppercase() && b.is_ascii_uppercase() => {
+                format!("nRF{:x}_xx{}{}", part, *a as char, *b as char)
+            }
+            _ => format!("nRF{:x}", part),
+        }
+    } else {
+        "nRF51".to_owned()
+    };
+
+    Ok(Some(VariantId {
+        name_prefix,
+        flash_size: Some(page_size * page_count).filter(|&size| size != 0),
+    }))
+}
+
+/// The addresses of the `DBGMCU_IDCODE` register of the different STM32 series.
+const STM32_IDCODE: [u64; 3] = [0xE004_2000, 0x4001_5800, 0x5C00_1000];
+
+/// The series and the address of the flash size register of STM32 device IDs.
+fn stm32_series(device_id: u32) -> Option<(&'static str, u64)> {
+    Some(match device_id {
+        0x410 | 0x412 | 0x414 | 0x418 | 0x420 | 0x428 | 0x430 => ("STM32F1", 0x1FFF_F7E0),
+        0x440 | 0x442 | 0x444 | 0x445 | 0x448 => ("STM32F0", 0x1FFF_F7CC),
+        0x411 => ("STM32F2", 0x1FFF_7A22),
+        0x422 | 0x432 | 0x438 | 0x439 | 0x446 => ("STM32F3", 0x1FFF_F7CC),
+        0x413 | 0x419 | 0x421 | 0x423 | 0x431 | 0x433 | 0x434 | 0x441 | 0x458 | 0x463 => {
+            ("STM32F4", 0x1FFF_7A22)
+        }
+        0x449 | 0x451 => ("STM32F7", 0x1FF0_F442),
+        0x452 => ("STM32F7", 0x1FF0_7A22),
+        0x456 | 0x460 | 0x466 | 0x467 => ("STM32G0", 0x1FFF_75E0),
+        0x468 | 0x469 | 0x479 => ("STM32G4", 0x1FFF_75E0),
+        0x450 | 0x480 | 0x483 => ("STM32H7", 0x1FF1_E880),
+        0x417 | 0x425 | 0x447 | 0x457 => ("STM32L0", 0x1FF8_007C),
+        0x415 | 0x435 | 0x461 | 0x462 | 0x464 | 0x470 | 0x471 => ("STM32L4", 0x1FFF_75E0),
+        0x495 => ("STM32WB", 0x1FFF_75E0),
+        _ => return None,
+    })
+}
+
+fn read_stm32_id(memory: &mut Memory<'_>) -> Result<Option<VariantId>, Error> {
+    for address in STM32_IDCODE {
+        // The registers of the other series are not mapped, so errors are expected here.
+        let device_id = match memory.read_word_32(address) {
+            Ok(idcode) => idcode & 0xFFF,
+            Err(error) => {
+                log::debug!(
+                    "Failed to read DBGMCU_IDCODE at {:#010x}: {}",
+                    address,
+                    error
+                );
+                continue;
+            }
+        };
+
+        if let Some((series, flash_size_register)) = stm32_series(device_id) {
+            log::debug!("Found STM32 device ID {:#05x} ({})", device_id, series);
+
+            let mut flash_size = [0; 2];
+            memory.read_8(flash_size_register, &mut flash_size)?;
+            let flash_size = u16::from_le_bytes(flash_size) as u64 * 1024;
+
+            return Ok(Some(VariantId {
+                name_prefix: series.to_owned(),
+                // The register is not programmed on some engineering samples.
+                flash_size: Some(flash_size).filter(|&size| size != 0 && size != 0xFFFF * 1024),
+            }));
+        }
+    }
+
+    Ok(None)
+}
+
+/// The size of the flash which the chip boots from, including the flash which directly follows it.
+pub(crate) fn main_flash_size(memory_map: &[MemoryRegion]) -> Option<u64> {
+    let nvm = memory_map
+        .iter()
+        .filter_map(|region| match region {
+            MemoryRegion::Nvm(region) => Some(region),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let boot = nvm.iter().find(|region| region.is_boot_memory)?;
+    let mut end = boot.range.end;
+    while let Some(next) = nvm.iter().find(|region| region.range.start == end) {
+        end = next.range.end;
+    }
+
+    Some(end - boot.range.start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::NvmRegion;
+
+    fn nvm(start: u64, end: u64, is_boot_memory: bool) -> MemoryRegion {
+        MemoryRegion::Nvm(NvmRegion {
+            name: None,
+            range: start..end,
+            is_boot_memory,
+            cores: vec![],
+        })
+    }
+
+    #[test]
+    fn main_flash_includes_following_banks() {
+        let memory_map = [
+            nvm(0x0800_0000, 0x0810_0000, true),
+            nvm(0x0810_0000, 0x0820_0000, false),
+            nvm(0x1FFF_C000, 0x1FFF_C010, false),
+        ];
+
+        assert_eq!(main_flash_size(&memory_map), Some(0x20_0000));
+    }
+
+    // The nRF52840 is only embedded if its target family is.
+    #[cfg(any(feature = "builtin-targets", feature = "target-nrf52"))]
+    #[test]
+    fn mismatching_flash_size_is_detected() {
+        let target = crate::config::get_target_by_name("nRF52840_xxAA").unwrap();
+
+        let id = VariantId {
+            name_prefix: "nRF52840_xxAA".to_owned(),
+            flash_size: Some(1024 * 1024),
+        };
+        assert_eq!(id.mismatch(&target), None);
+
+        let id = VariantId {
+            name_prefix: "nRF52840_xxAA".to_owned(),
+            flash_size: Some(512 * 1024),
+        };
+        assert!(id.mismatch(&target).is_some());
+
+        let id = VariantId {
+            name_prefix: "nRF52832".to_owned(),
+            flash_size: None,
+        };
+        assert!(id.mismatch(&target).is_some());
+    }
+}
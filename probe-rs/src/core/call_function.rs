@@ -0,0 +1,175 @@
+use super::{Core, RegisterId, RegisterValue};
+use crate::{Error, InstructionSet};
+use anyhow::anyhow;
+use std::time::Duration;
+
+/// The stack alignment at function calls, which is 8 bytes for the AAPCS and 16 bytes for RISC-V.
+const STACK_ALIGNMENT: u64 = 16;
+
+impl<'probe> Core<'probe> {
+    /// Calls the function at `address` on the target, and returns its result.
+    ///
+    /// The core has to be halted. The arguments are passed in the argument registers, as defined by
+    /// the AAPCS on ARM and by the RISC-V calling convention. Arguments which would be passed on the
+    /// stack are not supported. The function runs on the current stack, and returns to the current
+    /// program counter, where a hardware breakpoint halts the core again.
+    ///
+    /// Afterwards, all registers are restored, also if the function fails or doesn't return within
+    /// `timeout`, so the program continues as if the function was never called. Memory which the
+    /// function changed is not restored.
+    pub fn call_function(
+        &mut self,
+        address: u64,
+        args: &[u32],
+        timeout: Duration,
+    ) -> Result<u32, Error> {
+        if !self.core_halted()? {
+            return Err(Error::Other(anyhow!(
+                "The core has to be halted to call a function."
+            )));
+        }
+
+        let registers = self.registers();
+        if args.len() > registers.argument_registers.len() {
+            return Err(Error::Other(anyhow!(
+                "A function can be called with at most {} arguments, but {} were given.",
+                registers.argument_registers.len(),
+                args.len()
+            )));
+        }
+
+        let (entry, trampoline) = match self.instruction_set()? {
+            InstructionSet::Thumb2 => (address & !1, 1),
+            InstructionSet::A32 if address & 1 == 0 => (address, 0),
+            InstructionSet::RV32 | InstructionSet::RV32C => (address, 0),
+            instruction_set => {
+                return Err(Error::Other(anyhow!(
+                    "Calling functions is not supported for the {:?} instruction set.",
+                    instruction_set
+                )))
+            }
+        };
+
+        let saved = self.save_registers()?;
+
+        let result = self.call_with_saved_registers(entry, trampoline, args, timeout);
+
+        self.restore_registers(&saved)?;
+
+        result
+    }
+
+    /// Calls the function, with the state of the caller already saved.
+    ///
+    /// `thumb_bit` is added to the return address.
+    fn call_with_saved_registers(
+        &mut self,
+        entry: u64,
+        thumb_bit: u64,
+        args: &[u32],
+        timeout: Duration,
+    ) -> Result<u32, Error> {
+        let registers = self.registers();
+
+        let return_address: u64 = self.read_core_reg(registers.program_counter().id)?;
+        let stack_pointer: u64 = self.read_core_reg(registers.stack_pointer().id)?;
+        let stack_pointer = stack_pointer & !(STACK_ALIGNMENT - 1);
+
+        for (register, &value) in registers.argument_registers.iter().zip(args) {
+            self.write_core_reg(register.id, value)?;
+        }
+
+        self.write_core_reg(registers.stack_pointer().id, stack_pointer as u32)?;
+        self.write_core_reg(
+            registers.return_address().id,
+            (return_address | thumb_bit) as u32,
+        )?;
+        self.write_core_reg(registers.program_counter().id, entry as u32)?;
+
+        let breakpoint_was_set = self.inner.hw_breakpoints()?.contains(&Some(return_address));
+        self.set_hw_breakpoint(return_address)?;
+
+        let result = self.run_until_returned(return_address, stack_pointer, timeout);
+
+        if !breakpoint_was_set {
+            self.clear_hw_breakpoint(return_address)?;
+        }
+
+        result?;
+
+        self.read_core_reg(registers.result_registers[0].id)
+    }
+
+    /// Runs the core until the called function returns to `return_address`.
+    fn run_until_returned(
+        &mut self,
+        return_address: u64,
+        stack_pointer: u64,
+        timeout: Duration,
+    ) -> Result<(), Error> {
+        let registers = self.registers();
+
+        self.run()?;
+
+        loop {
+            if let Err(error) = self.wait_for_core_halted(timeout) {
+                self.halt(Duration::from_millis(100))?;
+                return Err(error);
+            }
+
+            let pc: u64 = self.read_core_reg(registers.program_counter().id)?;
+            let sp: u64 = self.read_core_reg(registers.stack_pointer().id)?;
+
+            if pc != return_address {
+                return Err(Error::Other(anyhow!(
+                    "The called function halted at {:#010x} instead of returning.",
+                    pc
+                )));
+            }
+
+            // The function itself hit the breakpoint, e.g. because it calls the function which
+            // was halted. It returned when the stack pointer is back to the one it was called with.
+            if sp == stack_pointer {
+                return Ok(());
+            }
+
+            self.run()?;
+        }
+    }
+
+    /// Reads all registers which are not preserved by a function call.
+    fn save_registers(&mut self) -> Result<Vec<(RegisterId, RegisterValue)>, Error> {
+        let registers = self.registers();
+
+        // FPU detection is not implemented for all cores, so assume there is no FPU then.
+        let fpu_support = self.fpu_support().unwrap_or(false);
+
+        let mut ids: Vec<RegisterId> = registers.registers().map(|register| register.id).collect();
+        ids.extend(registers.psr.map(|register| register.id));
+        if fpu_support {
+            ids.extend(registers.fp_status.map(|register| register.id));
+            ids.extend(
+                registers
+                    .fp_registers
+                    .iter()
+                    .flat_map(|fp_registers| fp_registers.iter())
+                    .map(|register| register.id),
+            );
+        }
+
+        ids.into_iter()
+            .map(|id| Ok((id, self.inner.read_core_reg(id)?)))
+            .collect()
+    }
+
+    /// Writes back the registers which changed since they were saved.
+    fn restore_registers(&mut self, saved: &[(RegisterId, RegisterValue)]) -> Result<(), Error> {
+        for &(id, value) in saved {
+            if self.inner.read_core_reg(id)? != value {
+                self.inner.write_core_reg(id, value)?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,706 @@
+use crate::architecture::{arm::core::ARM_REGISTER_FILE, riscv::register::RISCV_REGISTERS};
+use crate::core::{Architecture, CoreInformation, RegisterFile, RegisterValue};
+use crate::debug::{DebugInfo, StackFrame, VariableName};
+use crate::{
+    Core, CoreInterface, CoreStatus, CoreType, Error, HaltReason, InstructionSet, MemoryInterface,
+    RegisterId,
+};
+use anyhow::anyhow;
+use object::elf::{
+    FileHeader32, EM_ARM, EM_RISCV, ET_CORE, NT_PRSTATUS, PF_R, PF_W, PF_X, PT_LOAD, PT_NOTE,
+};
+use object::read::elf::{FileHeader, ProgramHeader};
+use object::Endianness;
+use probe_rs_target::{ArmCoreAccessOptions, CoreAccessOptions, RiscvCoreAccessOptions};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::Path;
+use std::time::Duration;
+
+/// The size of the ELF header of a 32-bit ELF file.
+const ELF_HEADER_SIZE: usize = 52;
+
+/// The size of a program header of a 32-bit ELF file.
+const PROGRAM_HEADER_SIZE: usize = 32;
+
+/// The offset of `pr_reg` in the `elf_prstatus` struct of 32-bit Linux targets.
+const PRSTATUS_REGISTERS_OFFSET: usize = 72;
+
+/// The signal which is reported by the `NT_PRSTATUS` note, `SIGTRAP`, as the core was halted by a debugger.
+const SIGTRAP: u16 = 5;
+
+/// The name of the note which stores the probe-rs specific information about the dumped core.
+const PROBE_RS_NOTE_NAME: &[u8] = b"probe-rs";
+
+/// The type of the note which stores the type of the dumped core.
+const NT_PROBE_RS_CORE_TYPE: u32 = 1;
+
+/// The registers in the `pr_reg` field of `NT_PRSTATUS` for ARM: r0-r15 and the xPSR or CPSR.
+/// The last register, `orig_r0`, is always zero.
+const ARM_PRSTATUS_REGISTERS: &[u16] = &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
+
+/// The number of registers in the `pr_reg` field of `NT_PRSTATUS` for ARM.
+const ARM_PRSTATUS_REGISTER_COUNT: usize = 18;
+
+/// The registers in the `pr_reg` field of `NT_PRSTATUS` for RISC-V: the PC and x1-x31.
+const RISCV_PRSTATUS_REGISTERS: &[u16] = &[
+    0x7b1, 0x1001, 0x1002, 0x1003, 0x1004, 0x1005, 0x1006, 0x1007, 0x1008, 0x1009, 0x100a, 0x100b,
+    0x100c, 0x100d, 0x100e, 0x100f, 0x1010, 0x1011, 0x1012, 0x1013, 0x1014, 0x1015, 0x1016, 0x1017,
+    0x1018, 0x1019, 0x101a, 0x101b, 0x101c, 0x101d, 0x101e, 0x101f,
+];
+
+/// The registers and selected memory of a halted core, which can be stored as an ELF core file.
+///
+/// The core file contains the registers in a `NT_PRSTATUS` note and each memory range in a `PT_LOAD`
+/// segment, like the core files of Linux processes, so it can be opened with GDB or LLDB together
+/// with the ELF file of the program which was running. It can also be loaded again with
+/// [`CoreDump::load`], to unwind the stack with [`CoreDump::symbolicate`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoreDump {
+    pub(crate) core_type: CoreType,
+    pub(crate) instruction_set: InstructionSet,
+    pub(crate) registers: HashMap<RegisterId, u32>,
+    pub(crate) memory: Vec<(u64, Vec<u8>)>,
+}
+
+impl CoreDump {
+    /// Captures the registers of a halted core and the memory in `ranges`.
+    ///
+    /// Only 32-bit ARM and RISC-V cores are supported.
+    pub fn capture(core: &mut Core<'_>, ranges: &[Range<u64>]) -> Result<CoreDump, Error> {
+        if !core.core_halted()? {
+            return Err(Error::Other(anyhow!(
+                "The core has to be halted to capture a core dump."
+            )));
+        }
+
+        let core_type = core.core_type();
+        let instruction_set = core.instruction_set()?;
+
+        let register_ids = match (core.architecture(), instruction_set) {
+            (Architecture::Arm, InstructionSet::Thumb2 | InstructionSet::A32) => {
+                ARM_PRSTATUS_REGISTERS
+            }
+            (Architecture::Riscv, _) => RISCV_PRSTATUS_REGISTERS,
+            _ => {
+                return Err(Error::Other(anyhow!(
+                    "Core dumps are not supported for {:?} cores with the {:?} instruction set.",
+                    core_type,
+                    instruction_set
+                )))
+            }
+        };
+
+        let mut registers = HashMap::new();
+        for &id in register_ids {
+            let value: u32 = core.read_core_reg(RegisterId(id))?;
+            registers.insert(RegisterId(id), value);
+        }
+
+        let mut memory = Vec::new();
+        for range in ranges {
+            let length = usize::try_from(range.end.saturating_sub(range.start)).map_err(|_| {
+                Error::Other(anyhow!("The memory range {:#010x?} is too large.", range))
+            })?;
+
+            let mut data = vec![0u8; length];
+            core.read_8(range.start, &mut data)?;
+            memory.push((range.start, data));
+        }
+
+        Ok(CoreDump {
+            core_type,
+            instruction_set,
+            registers,
+            memory,
+        })
+    }
+
+    /// Loads a core dump from an ELF core file, which was stored with [`CoreDump::store`].
+    ///
+    /// Core files of 32-bit ARM and RISC-V targets written by other tools can be loaded as well.
+    pub fn load(path: &Path) -> Result<CoreDump, Error> {
+        let data = std::fs::read(path).map_err(|error| {
+            Error::Other(anyhow!(
+                "Failed to read the core dump {}: {}",
+                path.display(),
+                error
+            ))
+        })?;
+
+        CoreDump::from_elf(&data)
+    }
+
+    /// Stores the core dump as an ELF core file.
+    pub fn store(&self, path: &Path) -> Result<(), Error> {
+        std::fs::write(path, self.to_elf()?).map_err(|error| {
+            Error::Other(anyhow!(
+                "Failed to write the core dump {}: {}",
+                path.display(),
+                error
+            ))
+        })
+    }
+
+    /// Parses an ELF core file.
+    pub fn from_elf(data: &[u8]) -> Result<CoreDump, Error> {
+        let invalid = |error: object::read::Error| {
+            Error::Other(anyhow!("The core dump is not a valid ELF file: {}", error))
+        };
+
+        let header = FileHeader32::<Endianness>::parse(data).map_err(invalid)?;
+        let endian = header.endian().map_err(invalid)?;
+
+        if header.e_type(endian) != ET_CORE {
+            return Err(Error::Other(anyhow!("The ELF file is not a core file.")));
+        }
+
+        let (mut core_type, mut instruction_set, register_ids) = match header.e_machine(endian) {
+            EM_ARM => (
+                CoreType::Armv7m,
+                InstructionSet::Thumb2,
+                ARM_PRSTATUS_REGISTERS,
+            ),
+            EM_RISCV => (
+                CoreType::Riscv,
+                InstructionSet::RV32,
+                RISCV_PRSTATUS_REGISTERS,
+            ),
+            machine => {
+                return Err(Error::Other(anyhow!(
+                    "Core dumps of ELF machine {} are not supported.",
+                    machine
+                )))
+            }
+        };
+
+        let mut registers = HashMap::new();
+        let mut memory = Vec::new();
+
+        for segment in header.program_headers(endian, data).map_err(invalid)? {
+            match segment.p_type(endian) {
+                PT_LOAD => {
+                    let segment_data = segment.data(endian, data).map_err(|_| {
+                        Error::Other(anyhow!("Failed to access the data of a segment."))
+                    })?;
+                    memory.push((segment.p_vaddr(endian).into(), segment_data.to_vec()));
+                }
+                PT_NOTE => {
+                    let mut notes = match segment.notes(endian, data).map_err(invalid)? {
+                        Some(notes) => notes,
+                        None => continue,
+                    };
+
+                    while let Some(note) = notes.next().map_err(invalid)? {
+                        if note.name() == b"CORE" && note.n_type(endian) == NT_PRSTATUS {
+                            let desc = note.desc();
+
+                            for (index, &id) in register_ids.iter().enumerate() {
+                                let offset = PRSTATUS_REGISTERS_OFFSET + 4 * index;
+                                let bytes = desc.get(offset..offset + 4).ok_or_else(|| {
+                                    Error::Other(anyhow!("The NT_PRSTATUS note is too short."))
+                                })?;
+
+                                let value = match endian {
+                                    Endianness::Little => {
+                                        u32::from_le_bytes(bytes.try_into().unwrap())
+                                    }
+                                    Endianness::Big => {
+                                        u32::from_be_bytes(bytes.try_into().unwrap())
+                                    }
+                                };
+                                registers.insert(RegisterId(id), value);
+                            }
+                        } else if note.name() == PROBE_RS_NOTE_NAME
+                            && note.n_type(endian) == NT_PROBE_RS_CORE_TYPE
+                        {
+                            match serde_yaml::from_slice(note.desc()) {
+                                Ok(dumped_core_type) => core_type = dumped_core_type,
+                                Err(error) => {
+                                    log::warn!("Failed to parse the dumped core type: {}", error)
+                                }
+                            }
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        if registers.is_empty() {
+            return Err(Error::Other(anyhow!(
+                "The core dump doesn't contain a NT_PRSTATUS note with the registers."
+            )));
+        }
+
+        // The T bit of the CPSR of A- and R-profile cores selects the Thumb instruction set.
+        if matches!(
+            core_type,
+            CoreType::Armv7a | CoreType::Armv7r | CoreType::Armv8a
+        ) {
+            let cpsr = registers.get(&RegisterId(16)).copied().unwrap_or(0);
+            if cpsr & (1 << 5) == 0 {
+                instruction_set = InstructionSet::A32;
+            }
+        }
+
+        Ok(CoreDump {
+            core_type,
+            instruction_set,
+            registers,
+            memory,
+        })
+    }
+
+    /// Encodes the core dump as an ELF core file.
+    pub fn to_elf(&self) -> Result<Vec<u8>, Error> {
+        let (machine, register_ids, register_count) = match self.core_type.architecture() {
+            Architecture::Arm => (EM_ARM, ARM_PRSTATUS_REGISTERS, ARM_PRSTATUS_REGISTER_COUNT),
+            Architecture::Riscv => (
+                EM_RISCV,
+                RISCV_PRSTATUS_REGISTERS,
+                RISCV_PRSTATUS_REGISTERS.len(),
+            ),
+            architecture => {
+                return Err(Error::Other(anyhow!(
+                    "Core dumps are not supported for the {:?} architecture.",
+                    architecture
+                )))
+            }
+        };
+
+        // The `elf_prstatus` struct, with the registers followed by `pr_fpvalid`.
+        let mut prstatus = vec![0u8; PRSTATUS_REGISTERS_OFFSET + 4 * register_count + 4];
+        prstatus[12..14].copy_from_slice(&SIGTRAP.to_le_bytes());
+        for (index, id) in register_ids.iter().enumerate() {
+            let value = self.register(RegisterId(*id)).unwrap_or(0);
+            let offset = PRSTATUS_REGISTERS_OFFSET + 4 * index;
+            prstatus[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        }
+
+        let core_type = serde_yaml::to_string(&self.core_type)
+            .map_err(|error| Error::Other(anyhow!("Failed to encode the core type: {}", error)))?;
+
+        let mut notes = Vec::new();
+        write_note(&mut notes, b"CORE", NT_PRSTATUS, &prstatus);
+        write_note(
+            &mut notes,
+            PROBE_RS_NOTE_NAME,
+            NT_PROBE_RS_CORE_TYPE,
+            core_type.as_bytes(),
+        );
+
+        let program_header_count = 1 + self.memory.len();
+        let mut offset = ELF_HEADER_SIZE + PROGRAM_HEADER_SIZE * program_header_count;
+
+        let mut elf = Vec::new();
+
+        // The ELF header.
+        elf.extend_from_slice(&[0x7f, b'E', b'L', b'F', 1, 1, 1]);
+        elf.resize(16, 0);
+        push_u16(&mut elf, ET_CORE);
+        push_u16(&mut elf, machine);
+        push_u32(&mut elf, 1); // e_version
+        push_u32(&mut elf, 0); // e_entry
+        push_u32(&mut elf, ELF_HEADER_SIZE as u32); // e_phoff
+        push_u32(&mut elf, 0); // e_shoff
+        push_u32(&mut elf, 0); // e_flags
+        push_u16(&mut elf, ELF_HEADER_SIZE as u16);
+        push_u16(&mut elf, PROGRAM_HEADER_SIZE as u16);
+        push_u16(&mut elf, program_header_count as u16);
+        push_u16(&mut elf, 40); // e_shentsize
+        push_u16(&mut elf, 0); // e_shnum
+        push_u16(&mut elf, 0); // e_shstrndx
+
+        // The program headers.
+        write_program_header(&mut elf, PT_NOTE, offset, 0, notes.len(), 0, 4)?;
+        offset += notes.len();
+
+        for (address, data) in &self.memory {
+            write_program_header(
+                &mut elf,
+                PT_LOAD,
+                offset,
+                *address,
+                data.len(),
+                PF_R | PF_W | PF_X,
+                1,
+            )?;
+            offset += data.len();
+        }
+
+        elf.extend_from_slice(&notes);
+        for (_, data) in &self.memory {
+            elf.extend_from_slice(data);
+        }
+
+        Ok(elf)
+    }
+
+    /// The type of the dumped core.
+    pub fn core_type(&self) -> CoreType {
+        self.core_type
+    }
+
+    /// The instruction set the dumped core was executing.
+    pub fn instruction_set(&self) -> InstructionSet {
+        self.instruction_set
+    }
+
+    /// The value of a register at the time of the dump, if it was dumped.
+    pub fn register(&self, id: RegisterId) -> Option<u32> {
+        self.registers.get(&id).copied()
+    }
+
+    /// The address ranges of the dumped memory.
+    pub fn memory_ranges(&self) -> impl Iterator<Item = Range<u64>> + '_ {
+        self.memory
+            .iter()
+            .map(|(address, data)| *address..*address + data.len() as u64)
+    }
+
+    /// Reads dumped memory, which fails if not all of `data` was dumped.
+    pub fn read(&self, address: u64, data: &mut [u8]) -> Result<(), Error> {
+        let dumped = self.memory.iter().find_map(|(start, dumped)| {
+            let offset = usize::try_from(address.checked_sub(*start)?).ok()?;
+            dumped.get(offset..offset.checked_add(data.len())?)
+        });
+
+        match dumped {
+            Some(dumped) => {
+                data.copy_from_slice(dumped);
+                Ok(())
+            }
+            None => Err(Error::Other(anyhow!(
+                "The memory at {:#010x} is not in the core dump.",
+                address
+            ))),
+        }
+    }
+
+    /// Symbolicates the core dump: unwinds the dumped stack with the debug information of the
+    /// program which was running on the core, and resolves the local variables of each frame.
+    ///
+    /// This uses the same unwinder as for a connected core, so no hardware is needed. Variables in
+    /// memory which is not in the dump show an error instead of their value.
+    pub fn symbolicate(&self, debug_info: &DebugInfo) -> Result<Vec<StackFrame>, Error> {
+        let options = match self.core_type.architecture() {
+            Architecture::Riscv => CoreAccessOptions::Riscv(RiscvCoreAccessOptions::default()),
+            _ => CoreAccessOptions::Arm(ArmCoreAccessOptions::default()),
+        };
+
+        let mut state = Core::create_state(0, options);
+        let mut core = Core::new(DumpCore { dump: self }, &mut state);
+
+        let program_counter = core.registers().program_counter();
+        let pc: u64 = core.read_core_reg(program_counter)?;
+
+        let mut stack_frames = debug_info.unwind(&mut core, pc)?;
+
+        for stack_frame in &mut stack_frames {
+            let local_variables = match stack_frame.local_variables.as_mut() {
+                Some(local_variables) => local_variables,
+                None => continue,
+            };
+
+            // The local variables are only cached when the scope is expanded.
+            if let Some(mut locals) =
+                local_variables.get_variable_by_name_and_parent(&VariableName::LocalScopeRoot, None)
+            {
+                if let Err(error) = debug_info.cache_deferred_variables(
+                    local_variables,
+                    &mut core,
+                    &mut locals,
+                    &stack_frame.registers,
+                ) {
+                    log::warn!(
+                        "Failed to resolve the local variables of {}: {}",
+                        stack_frame.function_name,
+                        error
+                    );
+                }
+            }
+        }
+
+        Ok(stack_frames)
+    }
+}
+
+fn push_u16(elf: &mut Vec<u8>, value: u16) {
+    elf.extend_from_slice(&value.to_le_bytes());
+}
+
+fn push_u32(elf: &mut Vec<u8>, value: u32) {
+    elf.extend_from_slice(&value.to_le_bytes());
+}
+
+/// Appends a note with the 4 byte alignment of 32-bit ELF files.
+fn write_note(notes: &mut Vec<u8>, name: &[u8], note_type: u32, desc: &[u8]) {
+    // The name is terminated with a NUL byte.
+    push_u32(notes, name.len() as u32 + 1);
+    push_u32(notes, desc.len() as u32);
+    push_u32(notes, note_type);
+
+    notes.extend_from_slice(name);
+    notes.push(0);
+    notes.resize((notes.len() + 3) & !3, 0);
+
+    notes.extend_from_slice(desc);
+    notes.resize((notes.len() + 3) & !3, 0);
+}
+
+fn write_program_header(
+    elf: &mut Vec<u8>,
+    segment_type: u32,
+    offset: usize,
+    address: u64,
+    size: usize,
+    flags: u32,
+    align: u32,
+) -> Result<(), Error> {
+    let address = u32::try_from(address).map_err(|_| {
+        Error::Other(anyhow!(
+            "The address {:#x} doesn't fit in a 32-bit core dump.",
+            address
+        ))
+    })?;
+    let offset = u32::try_from(offset)
+        .map_err(|_| Error::Other(anyhow!("The core dump is larger than 4 GiB.")))?;
+
+    push_u32(elf, segment_type);
+    push_u32(elf, offset);
+    push_u32(elf, address); // p_vaddr
+    push_u32(elf, address); // p_paddr
+    push_u32(elf, size as u32); // p_filesz
+    push_u32(elf, size as u32); // p_memsz
+    push_u32(elf, flags);
+    push_u32(elf, align);
+
+    Ok(())
+}
+
+/// A core which was dumped, see [`CoreDump::symbolicate`].
+///
+/// Only its registers and the dumped memory can be read, everything else returns an error.
+struct DumpCore<'dump> {
+    dump: &'dump CoreDump,
+}
+
+fn not_supported() -> Error {
+    Error::Other(anyhow!("This is not supported for a dumped core."))
+}
+
+impl CoreInterface for DumpCore<'_> {
+    fn wait_for_core_halted(&mut self, _timeout: Duration) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn core_halted(&mut self) -> Result<bool, Error> {
+        Ok(true)
+    }
+
+    fn status(&mut self) -> Result<CoreStatus, Error> {
+        Ok(CoreStatus::Halted(HaltReason::Unknown))
+    }
+
+    fn halt(&mut self, _timeout: Duration) -> Result<CoreInformation, Error> {
+        let program_counter = self.registers().program_counter().id;
+        let pc: u64 = self.read_core_reg(program_counter)?;
+
+        Ok(CoreInformation { pc })
+    }
+
+    fn run(&mut self) -> Result<(), Error> {
+        Err(not_supported())
+    }
+
+    fn reset(&mut self) -> Result<(), Error> {
+        Err(not_supported())
+    }
+
+    fn reset_and_halt(&mut self, _timeout: Duration) -> Result<CoreInformation, Error> {
+        Err(not_supported())
+    }
+
+    fn step(&mut self) -> Result<CoreInformation, Error> {
+        Err(not_supported())
+    }
+
+    fn read_core_reg(&mut self, address: RegisterId) -> Result<RegisterValue, Error> {
+        // x0 is hardwired to zero.
+        if self.dump.core_type == CoreType::Riscv && address == RegisterId(0x1000) {
+            return Ok(0u32.into());
+        }
+
+        self.dump
+            .register(address)
+            .map(RegisterValue::from)
+            .ok_or_else(|| Error::Other(anyhow!("The register {:?} is not in the dump.", address)))
+    }
+
+    fn write_core_reg(
+        &mut self,
+        _address: RegisterId,
+        _value: RegisterValue,
+    ) -> anyhow::Result<()> {
+        Err(anyhow!("This is not supported for a dumped core."))
+    }
+
+    fn available_breakpoint_units(&mut self) -> Result<u32, Error> {
+        Ok(0)
+    }
+
+    fn hw_breakpoints(&mut self) -> Result<Vec<Option<u64>>, Error> {
+        Ok(Vec::new())
+    }
+
+    fn enable_breakpoints(&mut self, _state: bool) -> Result<(), Error> {
+        Err(not_supported())
+    }
+
+    fn set_hw_breakpoint(&mut self, _unit_index: usize, _addr: u64) -> Result<(), Error> {
+        Err(not_supported())
+    }
+
+    fn clear_hw_breakpoint(&mut self, _unit_index: usize) -> Result<(), Error> {
+        Err(not_supported())
+    }
+
+    fn registers(&self) -> &'static RegisterFile {
+        match self.dump.core_type.architecture() {
+            Architecture::Riscv => &RISCV_REGISTERS,
+            _ => &ARM_REGISTER_FILE,
+        }
+    }
+
+    fn hw_breakpoints_enabled(&self) -> bool {
+        false
+    }
+
+    fn architecture(&self) -> Architecture {
+        self.dump.core_type.architecture()
+    }
+
+    fn core_type(&self) -> CoreType {
+        self.dump.core_type
+    }
+
+    fn instruction_set(&mut self) -> Result<InstructionSet, Error> {
+        Ok(self.dump.instruction_set)
+    }
+
+    fn fpu_support(&mut self) -> Result<bool, Error> {
+        Ok(false)
+    }
+}
+
+impl MemoryInterface for DumpCore<'_> {
+    fn supports_native_64bit_access(&mut self) -> bool {
+        false
+    }
+
+    fn read_word_64(&mut self, address: u64) -> Result<u64, Error> {
+        let mut data = [0u8; 8];
+        self.read_8(address, &mut data)?;
+        Ok(u64::from_le_bytes(data))
+    }
+
+    fn read_word_32(&mut self, address: u64) -> Result<u32, Error> {
+        let mut data = [0u8; 4];
+        self.read_8(address, &mut data)?;
+        Ok(u32::from_le_bytes(data))
+    }
+
+    fn read_word_8(&mut self, address: u64) -> Result<u8, Error> {
+        let mut data = [0u8; 1];
+        self.read_8(address, &mut data)?;
+        Ok(data[0])
+    }
+
+    fn read_64(&mut self, address: u64, data: &mut [u64]) -> Result<(), Error> {
+        for (address, word) in (address..).step_by(8).zip(data) {
+            *word = self.read_word_64(address)?;
+        }
+        Ok(())
+    }
+
+    fn read_32(&mut self, address: u64, data: &mut [u32]) -> Result<(), Error> {
+        for (address, word) in (address..).step_by(4).zip(data) {
+            *word = self.read_word_32(address)?;
+        }
+        Ok(())
+    }
+
+    fn read_8(&mut self, address: u64, data: &mut [u8]) -> Result<(), Error> {
+        self.dump.read(address, data)
+    }
+
+    fn write_word_64(&mut self, _address: u64, _data: u64) -> Result<(), Error> {
+        Err(not_supported())
+    }
+
+    fn write_word_32(&mut self, _address: u64, _data: u32) -> Result<(), Error> {
+        Err(not_supported())
+    }
+
+    fn write_word_8(&mut self, _address: u64, _data: u8) -> Result<(), Error> {
+        Err(not_supported())
+    }
+
+    fn write_64(&mut self, _address: u64, _data: &[u64]) -> Result<(), Error> {
+        Err(not_supported())
+    }
+
+    fn write_32(&mut self, _address: u64, _data: &[u32]) -> Result<(), Error> {
+        Err(not_supported())
+    }
+
+    fn write_8(&mut self, _address: u64, _data: &[u8]) -> Result<(), Error> {
+        Err(not_supported())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CoreDump, DumpCore};
+    use crate::{CoreType, InstructionSet, MemoryInterface, RegisterId};
+
+    fn dump() -> CoreDump {
+        CoreDump {
+            core_type: CoreType::Armv7em,
+            instruction_set: InstructionSet::Thumb2,
+            registers: (0..=16)
+                .map(|id| (RegisterId(id), 0x100 + id as u32))
+                .collect(),
+            memory: vec![
+                (0x2000_0000, vec![1, 2, 3, 4, 5, 6, 7, 8]),
+                (0x0800_0000, vec![0xaa; 3]),
+            ],
+        }
+    }
+
+    #[test]
+    fn read_dumped_memory() {
+        let dump = dump();
+        let mut core = DumpCore { dump: &dump };
+
+        assert_eq!(core.read_word_32(0x2000_0004).unwrap(), 0x0807_0605);
+        assert_eq!(core.read_word_8(0x0800_0002).unwrap(), 0xaa);
+        assert!(core.read_word_32(0x2000_0006).is_err());
+        assert!(core.read_word_8(0x1fff_ffff).is_err());
+    }
+
+    #[test]
+    fn elf_round_trip() {
+        let dump = dump();
+
+        let elf = dump.to_elf().unwrap();
+        let loaded = CoreDump::from_elf(&elf).unwrap();
+
+        assert_eq!(loaded, dump);
+    }
+}
@@ -1,18 +1,25 @@
+mod call_function;
 pub(crate) mod communication_interface;
+mod dump;
+mod run_loop;
 
 use crate::{CoreType, InstructionSet};
 pub use communication_interface::CommunicationInterface;
+pub use dump::CoreDump;
 pub use probe_rs_target::{Architecture, CoreAccessOptions};
+pub use run_loop::{symbol_address, FaultReport, PanicInfo, RunLoop, RunOutcome};
 
 use crate::architecture::{
     arm::core::CortexAState, arm::core::CortexMState,
+    mips::communication_interface::MipsCommunicationInterface,
     riscv::communication_interface::RiscvCommunicationInterface,
 };
+use crate::config::MemoryRegionInfo;
 use crate::error;
 use crate::Target;
 use crate::{Error, Memory, MemoryInterface};
 use anyhow::{anyhow, Result};
-use std::time::Duration;
+use std::{ops::Range, time::Duration};
 
 /// A memory mapped register, for instance ARM debug registers (DHCSR, etc).
 pub trait MemoryMappedRegister: Clone + From<u32> + Into<u32> + Sized + std::fmt::Debug {
@@ -275,17 +282,20 @@ impl RegisterFile {
         self.psr
     }
 
-    // ARM DDI 0403E.d (ID070218)
-    // C1.6.3 Debug Core Register Selector Register, DCRSR
-    // Bits[31:24] CONTROL.
-    // Bits[23:16] FAULTMASK.
-    // Bits[15:8]  BASEPRI.
-    // Bits[7:0]   PRIMASK.
-    // In each field, the valid bits are packed with leading zeros. For example,
-    // // FAULTMASK is always a single bit, DCRDR[16], and DCRDR[23:17] is 0b0000000.
-    // pub fn extra(&self) -> Option<&RegisterDescription> {
-    //     self.extra
-    // }
+    /// The register which contains the special registers of ARM M-profile cores.
+    ///
+    /// See ARM DDI 0403E.d (ID070218), C1.6.3 Debug Core Register Selector Register, DCRSR:
+    ///
+    /// - Bits[31:24] CONTROL.
+    /// - Bits[23:16] FAULTMASK.
+    /// - Bits[15:8]  BASEPRI.
+    /// - Bits[7:0]   PRIMASK.
+    ///
+    /// In each field, the valid bits are packed with leading zeros. For example,
+    /// FAULTMASK is always a single bit, DCRDR[16], and DCRDR[23:17] is 0b0000000.
+    pub fn extra(&self) -> Option<&RegisterDescription> {
+        self.extra
+    }
 
     /// The fpu status register.
     pub fn fpscr(&self) -> Option<&RegisterDescription> {
@@ -370,6 +380,54 @@ pub trait CoreInterface: MemoryInterface {
     /// Clears the breakpoint configured in unit `unit_index`.
     fn clear_hw_breakpoint(&mut self, unit_index: usize) -> Result<(), error::Error>;
 
+    /// Returns the number of hardware watchpoint units of the core.
+    fn available_watchpoint_units(&mut self) -> Result<u32, error::Error> {
+        Ok(0)
+    }
+
+    /// Reads the watchpoints which are configured in the watchpoint units of the core.
+    /// A value of None in any position of the Vector indicates that the unit is available.
+    fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, error::Error> {
+        Ok(Vec::new())
+    }
+
+    /// Sets a watchpoint in the unit `unit_index`.
+    fn set_hw_watchpoint(
+        &mut self,
+        _unit_index: usize,
+        _watchpoint: Watchpoint,
+    ) -> Result<(), error::Error> {
+        Err(error::Error::Other(anyhow!(
+            "Watchpoints are not supported on this core"
+        )))
+    }
+
+    /// Clears the watchpoint configured in unit `unit_index`.
+    fn clear_hw_watchpoint(&mut self, _unit_index: usize) -> Result<(), error::Error> {
+        Err(error::Error::Other(anyhow!(
+            "Watchpoints are not supported on this core"
+        )))
+    }
+
+    /// Returns the watchpoint which halted the core, if any.
+    ///
+    /// The core may only report a triggered watchpoint once, so this has to be called right after
+    /// the core halted with [`HaltReason::Watchpoint`].
+    fn triggered_watchpoint(&mut self) -> Result<Option<Watchpoint>, error::Error> {
+        Ok(None)
+    }
+
+    /// Enables or disables halting the core on the vector catch `condition`.
+    fn set_vector_catch(
+        &mut self,
+        _condition: VectorCatchCondition,
+        _enabled: bool,
+    ) -> Result<(), error::Error> {
+        Err(error::Error::Other(anyhow!(
+            "Vector catch is not supported on this core"
+        )))
+    }
+
     /// Returns a list of all the registers of this core.
     fn registers(&self) -> &'static RegisterFile;
 
@@ -396,6 +454,14 @@ pub trait CoreInterface: MemoryInterface {
     fn on_session_stop(&mut self) -> Result<(), Error> {
         Ok(())
     }
+
+    /// Returns `true` if memory can be accessed while the core is running, without halting it.
+    ///
+    /// Cores whose memory is accessed by executing instructions on the core itself have to be
+    /// halted for every memory access.
+    fn supports_background_memory_access(&mut self) -> bool {
+        true
+    }
 }
 
 impl<'probe> MemoryInterface for Core<'probe> {
@@ -463,6 +529,15 @@ pub struct CoreState {
 
     /// Information needed to access the core
     core_access_options: CoreAccessOptions,
+
+    /// The memory regions which the core can access.
+    memory_regions: Vec<MemoryRegionInfo>,
+
+    /// The address ranges in which the RTT control block is searched.
+    rtt_scan_ranges: Vec<Range<u64>>,
+
+    /// The address of the RTT control block, once it is known.
+    rtt_control_block: Option<u64>,
 }
 
 impl CoreState {
@@ -471,9 +546,27 @@ impl CoreState {
         Self {
             id,
             core_access_options,
+            memory_regions: Vec::new(),
+            rtt_scan_ranges: Vec::new(),
+            rtt_control_block: None,
         }
     }
 
+    /// Set the memory regions which the core can access.
+    pub(crate) fn set_memory_regions(&mut self, memory_regions: Vec<MemoryRegionInfo>) {
+        self.memory_regions = memory_regions;
+    }
+
+    /// Set the address ranges in which the RTT control block is searched.
+    pub(crate) fn set_rtt_scan_ranges(&mut self, ranges: Vec<Range<u64>>) {
+        self.rtt_scan_ranges = ranges;
+    }
+
+    /// Set the address of the RTT control block, if it is known.
+    pub(crate) fn set_rtt_control_block(&mut self, address: Option<u64>) {
+        self.rtt_control_block = address;
+    }
+
     /// Returns the core ID.
 
     pub fn id(&self) -> usize {
@@ -488,6 +581,8 @@ pub enum SpecificCoreState {
     Armv6m(CortexMState),
     /// The state of an ARMv7-A core.
     Armv7a(CortexAState),
+    /// The state of an ARMv7-R core.
+    Armv7r(CortexAState),
     /// The state of an ARMv7-M core.
     Armv7m(CortexMState),
     /// The state of an ARMv7-EM core.
@@ -498,6 +593,8 @@ pub enum SpecificCoreState {
     Armv8m(CortexMState),
     /// The state of an RISC-V core.
     Riscv,
+    /// The state of a MIPS32 core.
+    Mips32,
 }
 
 impl SpecificCoreState {
@@ -505,11 +602,13 @@ impl SpecificCoreState {
         match typ {
             CoreType::Armv6m => SpecificCoreState::Armv6m(CortexMState::new()),
             CoreType::Armv7a => SpecificCoreState::Armv7a(CortexAState::new()),
+            CoreType::Armv7r => SpecificCoreState::Armv7r(CortexAState::new()),
             CoreType::Armv7m => SpecificCoreState::Armv7m(CortexMState::new()),
             CoreType::Armv7em => SpecificCoreState::Armv7m(CortexMState::new()),
             CoreType::Armv8a => SpecificCoreState::Armv8a(CortexAState::new()),
             CoreType::Armv8m => SpecificCoreState::Armv8m(CortexMState::new()),
             CoreType::Riscv => SpecificCoreState::Riscv,
+            CoreType::Mips32 => SpecificCoreState::Mips32,
         }
     }
 
@@ -517,11 +616,13 @@ impl SpecificCoreState {
         match self {
             SpecificCoreState::Armv6m(_) => CoreType::Armv6m,
             SpecificCoreState::Armv7a(_) => CoreType::Armv7a,
+            SpecificCoreState::Armv7r(_) => CoreType::Armv7r,
             SpecificCoreState::Armv7m(_) => CoreType::Armv7m,
             SpecificCoreState::Armv7em(_) => CoreType::Armv7em,
             SpecificCoreState::Armv8a(_) => CoreType::Armv8a,
             SpecificCoreState::Armv8m(_) => CoreType::Armv8m,
             SpecificCoreState::Riscv => CoreType::Riscv,
+            SpecificCoreState::Mips32 => CoreType::Mips32,
         }
     }
 
@@ -533,7 +634,7 @@ impl SpecificCoreState {
     ) -> Result<Core<'probe>, Error> {
         let debug_sequence = match &target.debug_sequence {
             crate::config::DebugSequence::Arm(sequence) => sequence.clone(),
-            crate::config::DebugSequence::Riscv(_) => {
+            crate::config::DebugSequence::Riscv(_) | crate::config::DebugSequence::Mips => {
                 return Err(Error::UnableToOpenProbe(
                     "Core architecture and Probe mismatch.",
                 ))
@@ -542,7 +643,7 @@ impl SpecificCoreState {
 
         let options = match &state.core_access_options {
             CoreAccessOptions::Arm(options) => options,
-            CoreAccessOptions::Riscv(_) => {
+            CoreAccessOptions::Riscv(_) | CoreAccessOptions::Mips(_) => {
                 return Err(Error::UnableToOpenProbe(
                     "Core architecture and Probe mismatch.",
                 ))
@@ -563,6 +664,15 @@ impl SpecificCoreState {
                 )?,
                 state,
             ),
+            SpecificCoreState::Armv7r(s) => Core::new(
+                crate::architecture::arm::armv7a::Armv7a::new_armv7r(
+                    memory,
+                    s,
+                    options.debug_base.expect("base_address not specified"),
+                    debug_sequence,
+                )?,
+                state,
+            ),
             SpecificCoreState::Armv7m(s) | SpecificCoreState::Armv7em(s) => Core::new(
                 crate::architecture::arm::armv7m::Armv7m::new(memory, s, debug_sequence)?,
                 state,
@@ -589,13 +699,57 @@ impl SpecificCoreState {
         })
     }
 
+    /// Attaches to an ARMv8-A core with a CTI, returning `None` for all other cores.
+    ///
+    /// This gives access to the cross trigger functionality, which is not part of [`CoreInterface`].
+    pub(crate) fn attach_armv8a<'probe, 'target: 'probe>(
+        &'probe mut self,
+        state: &CoreState,
+        memory: Memory<'probe>,
+        target: &'target Target,
+    ) -> Result<Option<crate::architecture::arm::armv8a::Armv8a<'probe>>, Error> {
+        let (s, options) = match (self, &state.core_access_options) {
+            (SpecificCoreState::Armv8a(s), CoreAccessOptions::Arm(options)) => (s, options),
+            _ => return Ok(None),
+        };
+
+        let (debug_base, cti_base) = match (options.debug_base, options.cti_base) {
+            (Some(debug_base), Some(cti_base)) => (debug_base, cti_base),
+            _ => return Ok(None),
+        };
+
+        let debug_sequence = match &target.debug_sequence {
+            crate::config::DebugSequence::Arm(sequence) => sequence.clone(),
+            _ => return Ok(None),
+        };
+
+        crate::architecture::arm::armv8a::Armv8a::new(
+            memory,
+            s,
+            debug_base,
+            cti_base,
+            debug_sequence,
+        )
+        .map(Some)
+    }
+
     pub(crate) fn attach_riscv<'probe>(
         &self,
         state: &'probe mut CoreState,
         interface: &'probe mut RiscvCommunicationInterface,
     ) -> Result<Core<'probe>, Error> {
+        let options = match &state.core_access_options {
+            CoreAccessOptions::Riscv(options) => options,
+            CoreAccessOptions::Arm(_) | CoreAccessOptions::Mips(_) => {
+                return Err(Error::UnableToOpenProbe(
+                    "Core architecture and Probe mismatch.",
+                ))
+            }
+        };
+
         Ok(match self {
             SpecificCoreState::Riscv => {
+                interface.select_hart(options.hart_id)?;
                 Core::new(crate::architecture::riscv::Riscv32::new(interface), state)
             }
             _ => {
@@ -605,6 +759,23 @@ impl SpecificCoreState {
             }
         })
     }
+
+    pub(crate) fn attach_mips<'probe>(
+        &self,
+        state: &'probe mut CoreState,
+        interface: &'probe mut MipsCommunicationInterface,
+    ) -> Result<Core<'probe>, Error> {
+        Ok(match self {
+            SpecificCoreState::Mips32 => {
+                Core::new(crate::architecture::mips::Mips32::new(interface), state)
+            }
+            _ => {
+                return Err(Error::UnableToOpenProbe(
+                    "Core architecture and Probe mismatch.",
+                ))
+            }
+        })
+    }
 }
 
 /// Generic core handle representing a physical core on an MCU.
@@ -632,11 +803,55 @@ impl<'probe> Core<'probe> {
         CoreState::new(id, options)
     }
 
+    /// The memory regions which this core can access.
+    pub fn memory_regions(&self) -> &[MemoryRegionInfo] {
+        &self.state.memory_regions
+    }
+
+    /// The memory region which contains `address`, if this core can access it.
+    pub fn memory_region(&self, address: u64) -> Option<&MemoryRegionInfo> {
+        self.state
+            .memory_regions
+            .iter()
+            .find(|region| region.contains(address))
+    }
+
     /// Returns the ID of this core.
     pub fn id(&self) -> usize {
         self.state.id
     }
 
+    /// Returns `true` if memory can be accessed while this core is running, without halting it.
+    ///
+    /// This is the case for Cortex-M cores, which are accessed through a memory access port, and
+    /// for RISC-V cores whose debug module supports system bus access. Cortex-A and MIPS cores,
+    /// and RISC-V cores without system bus access, have to be halted to access their memory.
+    pub fn supports_background_memory_access(&mut self) -> bool {
+        self.inner.supports_background_memory_access()
+    }
+
+    /// The address ranges in which the RTT control block is searched.
+    ///
+    /// These come from the [`AttachOptions`](crate::AttachOptions) of the session or the target
+    /// description. If they are empty, all RAM which this core can access is searched.
+    pub fn rtt_scan_ranges(&self) -> &[Range<u64>] {
+        &self.state.rtt_scan_ranges
+    }
+
+    /// The address of the RTT control block, if it is known.
+    ///
+    /// The address is remembered for the whole session once the control block was found, so
+    /// attaching to RTT again doesn't need to search for it.
+    pub fn rtt_control_block(&self) -> Option<u64> {
+        self.state.rtt_control_block
+    }
+
+    /// Remembers the address of the RTT control block for this core, or forgets it with `None`,
+    /// e.g. after a different program was flashed.
+    pub fn set_rtt_control_block(&mut self, address: Option<u64>) {
+        self.state.set_rtt_control_block(address);
+    }
+
     /// Wait until the core is halted. If the core does not halt on its own,
     /// a [`DebugProbeError::Timeout`](crate::DebugProbeError::Timeout) error will be returned.
     pub fn wait_for_core_halted(&mut self, timeout: Duration) -> Result<(), error::Error> {
@@ -816,6 +1031,93 @@ impl<'probe> Core<'probe> {
         Ok(())
     }
 
+    /// Returns the number of hardware watchpoint units of the core.
+    pub fn available_watchpoint_units(&mut self) -> Result<u32, error::Error> {
+        self.inner.available_watchpoint_units()
+    }
+
+    /// Set a hardware watchpoint
+    ///
+    /// The core halts with [`HaltReason::Watchpoint`] when the memory range of the watchpoint is
+    /// accessed. Which ranges can be watched is architecture specific, usually the length has to be
+    /// a power of two, and the address has to be aligned to it.
+    pub fn set_hw_watchpoint(&mut self, watchpoint: Watchpoint) -> Result<(), error::Error> {
+        let watchpoints = self.inner.hw_watchpoints()?;
+
+        // If the watchpoint is set already, set it again in the same unit, else find a free one.
+        let unit_index = match watchpoints.iter().position(|&wp| wp == Some(watchpoint)) {
+            Some(unit_index) => unit_index,
+            None => watchpoints
+                .iter()
+                .position(Option::is_none)
+                .ok_or_else(|| error::Error::Other(anyhow!("No available hardware watchpoints")))?,
+        };
+
+        log::debug!(
+            "Trying to set HW watchpoint #{} for {:?}",
+            unit_index,
+            watchpoint
+        );
+
+        self.inner.set_hw_watchpoint(unit_index, watchpoint)
+    }
+
+    /// Clear a hardware watchpoint
+    ///
+    /// This function will clear the unit which is configured with `watchpoint`, if there is one.
+    pub fn clear_hw_watchpoint(&mut self, watchpoint: Watchpoint) -> Result<(), error::Error> {
+        let unit_index = self
+            .inner
+            .hw_watchpoints()?
+            .iter()
+            .position(|&wp| wp == Some(watchpoint));
+
+        match unit_index {
+            Some(unit_index) => self.inner.clear_hw_watchpoint(unit_index),
+            None => Err(error::Error::Other(anyhow!(
+                "No watchpoint found for {:#010x} with a length of {} bytes",
+                watchpoint.address,
+                watchpoint.length
+            ))),
+        }
+    }
+
+    /// Clear all hardware watchpoints
+    ///
+    /// Like [`Core::clear_all_hw_breakpoints`], this also clears watchpoints which were not set by
+    /// probe-rs.
+    pub fn clear_all_hw_watchpoints(&mut self) -> Result<(), error::Error> {
+        let watchpoints = self.inner.hw_watchpoints()?;
+
+        for (unit_index, watchpoint) in watchpoints.into_iter().enumerate() {
+            if watchpoint.is_some() {
+                self.inner.clear_hw_watchpoint(unit_index)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the watchpoint which halted the core, if any.
+    ///
+    /// The core may only report a triggered watchpoint once, so this has to be called right after
+    /// the core halted with [`HaltReason::Watchpoint`], before any other watchpoint function.
+    pub fn triggered_watchpoint(&mut self) -> Result<Option<Watchpoint>, error::Error> {
+        self.inner.triggered_watchpoint()
+    }
+
+    /// Enables or disables halting the core on the vector catch `condition`.
+    ///
+    /// The core halts with [`HaltReason::Exception`] when the condition occurs. Which conditions
+    /// are supported is architecture specific. Resetting the core may disable the
+    /// [`VectorCatchCondition::CoreReset`] condition again.
+    pub fn set_vector_catch(
+        &mut self,
+        condition: VectorCatchCondition,
+        enabled: bool,
+    ) -> Result<(), error::Error> {
+        self.inner.set_vector_catch(condition, enabled)
+    }
+
     /// Returns the architecture of the core.
     pub fn architecture(&self) -> Architecture {
         self.inner.architecture()
@@ -857,6 +1159,46 @@ impl BreakpointId {
     }
 }
 
+/// The memory accesses which trigger a [`Watchpoint`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WatchpointKind {
+    /// The core halts when the memory is read.
+    Read,
+    /// The core halts when the memory is written.
+    Write,
+    /// The core halts when the memory is read or written.
+    Access,
+}
+
+/// A hardware watchpoint, which halts the core when a range of memory is accessed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Watchpoint {
+    /// The start address of the watched range.
+    pub address: u64,
+    /// The length of the watched range in bytes.
+    pub length: u64,
+    /// The accesses which trigger the watchpoint.
+    pub kind: WatchpointKind,
+}
+
+/// A vector catch condition, which halts the core when it takes the exception, before the first
+/// instruction of the exception handler is executed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum VectorCatchCondition {
+    /// A HardFault exception.
+    HardFault,
+    /// A MemManage fault.
+    MemManage,
+    /// A BusFault exception.
+    BusFault,
+    /// A UsageFault exception.
+    UsageFault,
+    /// A SecureFault exception.
+    SecureFault,
+    /// A reset of the core, e.g. by a watchdog.
+    CoreReset,
+}
+
 /// The status of the core.
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub enum CoreStatus {
@@ -0,0 +1,528 @@
+use super::{Core, CoreStatus, HaltReason};
+use crate::architecture::arm::semihosting::{SemihostingEvent, SemihostingHost, SemihostingPolicy};
+use crate::{CoreType, Error, InstructionSet, MemoryInterface};
+use object::{Object, ObjectSymbol};
+use std::time::{Duration, Instant};
+
+/// Configurable Fault Status Register
+const CFSR: u64 = 0xE000_ED28;
+/// HardFault Status Register
+const HFSR: u64 = 0xE000_ED2C;
+/// MemManage Fault Address Register
+const MMFAR: u64 = 0xE000_ED34;
+/// BusFault Address Register
+const BFAR: u64 = 0xE000_ED38;
+
+const CFSR_MMARVALID: u32 = 1 << 7;
+const CFSR_BFARVALID: u32 = 1 << 15;
+
+/// The fault status bits of the CFSR, and their descriptions.
+const CFSR_CAUSES: &[(u32, &str)] = &[
+    (1 << 0, "MemManage: Instruction access violation"),
+    (1 << 1, "MemManage: Data access violation"),
+    (
+        1 << 3,
+        "MemManage: Fault on unstacking for a return from exception",
+    ),
+    (1 << 4, "MemManage: Fault on stacking for exception entry"),
+    (
+        1 << 5,
+        "MemManage: Fault during floating-point lazy state preservation",
+    ),
+    (1 << 8, "BusFault: Instruction bus error"),
+    (1 << 9, "BusFault: Precise data bus error"),
+    (1 << 10, "BusFault: Imprecise data bus error"),
+    (
+        1 << 11,
+        "BusFault: Fault on unstacking for a return from exception",
+    ),
+    (1 << 12, "BusFault: Fault on stacking for exception entry"),
+    (
+        1 << 13,
+        "BusFault: Fault during floating-point lazy state preservation",
+    ),
+    (1 << 16, "UsageFault: Undefined instruction"),
+    (1 << 17, "UsageFault: Invalid state"),
+    (1 << 18, "UsageFault: Invalid PC load by EXC_RETURN"),
+    (1 << 19, "UsageFault: No coprocessor"),
+    (1 << 20, "UsageFault: Stack overflow"),
+    (1 << 24, "UsageFault: Unaligned access"),
+    (1 << 25, "UsageFault: Divide by zero"),
+];
+
+/// The fault status bits of the HFSR, and their descriptions.
+const HFSR_CAUSES: &[(u32, &str)] = &[
+    (1 << 1, "HardFault: Bus error on a vector table read"),
+    (1 << 30, "HardFault: Escalated from a configurable fault"),
+    (1 << 31, "HardFault: Debug event"),
+];
+
+/// The exit code of a target which panicked, the same as for a panicking Rust program on a host.
+const PANIC_EXIT_CODE: i32 = 101;
+
+/// Runs a core until the program on it has finished, e.g. to run tests on the target.
+///
+/// The program is considered finished when it
+///
+/// - exits with a semihosting call, if semihosting is enabled with [`RunLoop::semihosting`],
+/// - executes a breakpoint instruction, where the immediate of `BKPT` is the exit code on ARM,
+///   and `a0` is the exit code for an `EBREAK` on RISC-V,
+/// - reaches the address given with [`RunLoop::done_at`], its panic handler or its fault handler,
+/// - or locks up.
+///
+/// # Example
+///
+/// ```no_run
+/// # use probe_rs::{Core, Error, RunLoop};
+/// # use probe_rs::architecture::arm::semihosting::SemihostingPolicy;
+/// # use std::time::Duration;
+/// # fn run_tests(core: &mut Core, elf: &[u8]) -> Result<(), Error> {
+/// let outcome = RunLoop::from_elf(elf, Duration::from_secs(30))?
+///     .semihosting(SemihostingPolicy::new())
+///     .run(core)?;
+///
+/// std::process::exit(outcome.exit_code());
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct RunLoop {
+    timeout: Duration,
+    poll_interval: Duration,
+    semihosting: Option<SemihostingHost>,
+    done: Option<u64>,
+    panic: Option<u64>,
+    fault: Option<u64>,
+}
+
+impl RunLoop {
+    /// Creates a run loop which stops the program after `timeout`.
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            poll_interval: Duration::from_millis(10),
+            semihosting: None,
+            done: None,
+            panic: None,
+            fault: None,
+        }
+    }
+
+    /// Creates a run loop for the program in the ELF file, which detects panics with a breakpoint on
+    /// `rust_begin_unwind`, and faults of Cortex-M cores with a breakpoint on `HardFault`.
+    pub fn from_elf(elf_data: &[u8], timeout: Duration) -> Result<Self, Error> {
+        let mut run_loop = Self::new(timeout);
+
+        run_loop.panic = symbol_address(elf_data, "rust_begin_unwind")?.map(code_address);
+        run_loop.fault = symbol_address(elf_data, "HardFault")?.map(code_address);
+
+        Ok(run_loop)
+    }
+
+    /// Service the semihosting calls of the program, with the given policy.
+    ///
+    /// The program exits when it calls `SYS_EXIT`, if the policy allows it.
+    #[must_use]
+    pub fn semihosting(self, policy: SemihostingPolicy) -> Self {
+        Self {
+            semihosting: Some(SemihostingHost::new(policy)),
+            ..self
+        }
+    }
+
+    /// The program is done when it reaches `address`, e.g. the address of a symbol
+    /// found with [`symbol_address`].
+    #[must_use]
+    pub fn done_at(self, address: u64) -> Self {
+        Self {
+            done: Some(code_address(address)),
+            ..self
+        }
+    }
+
+    /// The program panicked when it reaches `address`, which is usually its panic handler.
+    #[must_use]
+    pub fn panic_at(self, address: u64) -> Self {
+        Self {
+            panic: Some(code_address(address)),
+            ..self
+        }
+    }
+
+    /// The program faulted when it reaches `address`, which is usually its fault handler.
+    #[must_use]
+    pub fn fault_at(self, address: u64) -> Self {
+        Self {
+            fault: Some(code_address(address)),
+            ..self
+        }
+    }
+
+    /// How often the status of the core is checked, 10 ms by default.
+    #[must_use]
+    pub fn poll_interval(self, poll_interval: Duration) -> Self {
+        Self {
+            poll_interval,
+            ..self
+        }
+    }
+
+    /// Resumes the core and waits until the program has finished.
+    ///
+    /// The core is halted when this returns.
+    pub fn run(&mut self, core: &mut Core<'_>) -> Result<RunOutcome, Error> {
+        self.run_with(core, |_| Ok(()))
+    }
+
+    /// Resumes the core and waits until the program has finished, calling `poll` while the core runs,
+    /// e.g. to read the RTT channels of the program.
+    ///
+    /// The core is halted when this returns.
+    pub fn run_with(
+        &mut self,
+        core: &mut Core<'_>,
+        mut poll: impl FnMut(&mut Core<'_>) -> Result<(), Error>,
+    ) -> Result<RunOutcome, Error> {
+        let breakpoints: Vec<u64> = [self.done, self.panic, self.fault]
+            .into_iter()
+            .flatten()
+            .collect();
+
+        for &address in &breakpoints {
+            core.set_hw_breakpoint(address)?;
+        }
+
+        let outcome = self.run_until_finished(core, &mut poll);
+
+        for &address in &breakpoints {
+            if let Err(error) = core.clear_hw_breakpoint(address) {
+                log::warn!(
+                    "Failed to clear the breakpoint at {:#010x}: {}",
+                    address,
+                    error
+                );
+            }
+        }
+
+        outcome
+    }
+
+    fn run_until_finished(
+        &mut self,
+        core: &mut Core<'_>,
+        poll: &mut impl FnMut(&mut Core<'_>) -> Result<(), Error>,
+    ) -> Result<RunOutcome, Error> {
+        let start = Instant::now();
+
+        if core.core_halted()? {
+            core.run()?;
+        }
+
+        loop {
+            poll(core)?;
+
+            match core.status()? {
+                CoreStatus::Halted(reason) => match self.finished(core, reason)? {
+                    Some(outcome) => return Ok(outcome),
+                    None => core.run()?,
+                },
+                CoreStatus::LockedUp => {
+                    let pc = core.halt(Duration::from_millis(100))?.pc;
+                    return Ok(RunOutcome::Faulted(fault_report(core, pc)?));
+                }
+                _ => (),
+            }
+
+            if start.elapsed() > self.timeout {
+                let pc = core.halt(Duration::from_millis(100))?.pc;
+                return Ok(RunOutcome::TimedOut { pc });
+            }
+
+            std::thread::sleep(self.poll_interval);
+        }
+    }
+
+    /// Checks why the core halted. Returns `None` if the program can continue.
+    fn finished(
+        &mut self,
+        core: &mut Core<'_>,
+        reason: HaltReason,
+    ) -> Result<Option<RunOutcome>, Error> {
+        if let Some(semihosting) = &mut self.semihosting {
+            match semihosting.handle(core)? {
+                Some(SemihostingEvent::Serviced(_)) => return Ok(None),
+                Some(SemihostingEvent::Exit(status)) => {
+                    return Ok(Some(RunOutcome::Exited {
+                        code: status.code(),
+                    }))
+                }
+                None => (),
+            }
+        }
+
+        let pc: u64 = core.read_core_reg(core.registers().program_counter().id)?;
+
+        let outcome = if Some(pc) == self.done {
+            RunOutcome::Done
+        } else if Some(pc) == self.panic {
+            let return_address: u64 = core.read_core_reg(core.registers().return_address().id)?;
+
+            RunOutcome::Panicked(PanicInfo {
+                return_address: code_address(return_address),
+            })
+        } else if Some(pc) == self.fault || reason == HaltReason::Exception {
+            RunOutcome::Faulted(fault_report(core, pc)?)
+        } else if let Some(code) = breakpoint_exit_code(core, pc)? {
+            RunOutcome::Exited { code }
+        } else {
+            RunOutcome::Halted { pc, reason }
+        };
+
+        Ok(Some(outcome))
+    }
+}
+
+/// How the program on a core finished, returned by [`RunLoop::run`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum RunOutcome {
+    /// The program exited with a semihosting call or a breakpoint instruction.
+    Exited {
+        /// The exit code of the program.
+        code: i32,
+    },
+    /// The program reached the address given with [`RunLoop::done_at`].
+    Done,
+    /// The program panicked.
+    Panicked(PanicInfo),
+    /// The program faulted, or the core locked up.
+    Faulted(FaultReport),
+    /// The core halted for another reason, e.g. on a breakpoint which was set before.
+    Halted {
+        /// The program counter where the core halted.
+        pc: u64,
+        /// The reason why the core halted.
+        reason: HaltReason,
+    },
+    /// The program didn't finish in time, and the core was halted.
+    TimedOut {
+        /// The program counter where the core was halted.
+        pc: u64,
+    },
+}
+
+impl RunOutcome {
+    /// Returns `true` if the program exited with exit code 0, or reached its done address.
+    pub fn is_success(&self) -> bool {
+        self.exit_code() == 0
+    }
+
+    /// The exit code to propagate to a test harness.
+    ///
+    /// Panics are reported with 101, like a Rust program on the host, and timeouts with 124,
+    /// like the `timeout` command. All other failures are reported with 1.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            RunOutcome::Exited { code } => *code,
+            RunOutcome::Done => 0,
+            RunOutcome::Panicked(_) => PANIC_EXIT_CODE,
+            RunOutcome::TimedOut { .. } => 124,
+            RunOutcome::Faulted(_) | RunOutcome::Halted { .. } => 1,
+        }
+    }
+}
+
+/// Information about a panic of the program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PanicInfo {
+    /// The return address of the call of the panic handler.
+    ///
+    /// Use [`DebugInfo::get_source_location`](crate::debug::DebugInfo::get_source_location) to find
+    /// the source location of the panic, or [`DebugInfo::unwind`](crate::debug::DebugInfo::unwind) for a backtrace.
+    pub return_address: u64,
+}
+
+/// Information about a fault of the program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FaultReport {
+    /// The program counter where the core halted.
+    pub pc: u64,
+    /// The program counter of the faulting instruction, from the exception frame on Cortex-M cores.
+    pub faulting_pc: Option<u64>,
+    /// The Configurable Fault Status Register of Cortex-M cores, except ARMv6-M.
+    pub cfsr: Option<u32>,
+    /// The HardFault Status Register of Cortex-M cores, except ARMv6-M.
+    pub hfsr: Option<u32>,
+    /// The address of the access which caused a MemManage fault, if it is valid.
+    pub mmfar: Option<u32>,
+    /// The address of the access which caused a BusFault, if it is valid.
+    pub bfar: Option<u32>,
+}
+
+impl FaultReport {
+    /// Reads the fault status of a core which halted at `pc` because of a fault.
+    pub fn read(core: &mut Core<'_>, pc: u64) -> Result<Self, Error> {
+        fault_report(core, pc)
+    }
+
+    /// Returns a description of each fault status bit which is set in the HFSR and CFSR.
+    pub fn causes(&self) -> Vec<&'static str> {
+        let hfsr = self.hfsr.unwrap_or(0);
+        let cfsr = self.cfsr.unwrap_or(0);
+
+        HFSR_CAUSES
+            .iter()
+            .filter(|(bit, _)| hfsr & bit != 0)
+            .chain(CFSR_CAUSES.iter().filter(|(bit, _)| cfsr & bit != 0))
+            .map(|(_, cause)| *cause)
+            .collect()
+    }
+}
+
+/// Returns the address of the symbol `name` in the ELF file, e.g. to use it with [`RunLoop::done_at`].
+pub fn symbol_address(elf_data: &[u8], name: &str) -> Result<Option<u64>, Error> {
+    let elf = object::File::parse(elf_data).map_err(|error| Error::Other(error.into()))?;
+
+    let address = elf
+        .symbols()
+        .find(|symbol| {
+            symbol
+                .name()
+                .map_or(false, |symbol_name| symbol_name == name)
+        })
+        .map(|symbol| symbol.address());
+
+    Ok(address)
+}
+
+/// Clears the Thumb bit of the address of a function.
+fn code_address(address: u64) -> u64 {
+    address & !1
+}
+
+/// Returns the exit code of a breakpoint instruction at `pc`, if there is one.
+fn breakpoint_exit_code(core: &mut Core<'_>, pc: u64) -> Result<Option<i32>, Error> {
+    let code = match core.instruction_set()? {
+        InstructionSet::Thumb2 => {
+            let mut instruction = [0u8; 2];
+            core.read_8(pc, &mut instruction)?;
+            let instruction = u16::from_le_bytes(instruction);
+
+            // BKPT #imm8
+            (instruction & 0xff00 == 0xbe00).then(|| (instruction & 0xff) as i32)
+        }
+        InstructionSet::A32 => {
+            let instruction = core.read_word_32(pc)?;
+
+            // BKPT #imm16, which is split into two fields.
+            (instruction & 0xfff0_00f0 == 0xe120_0070)
+                .then(|| (((instruction >> 4) & 0xfff0) | (instruction & 0xf)) as i32)
+        }
+        InstructionSet::RV32 | InstructionSet::RV32C => {
+            let mut instruction = [0u8; 4];
+            core.read_8(pc, &mut instruction)?;
+
+            // EBREAK and C.EBREAK
+            let is_ebreak = u32::from_le_bytes(instruction) == 0x0010_0073
+                || u16::from_le_bytes([instruction[0], instruction[1]]) == 0x9002;
+
+            if is_ebreak {
+                let a0: u32 = core.read_core_reg(core.registers().argument_register(0).id)?;
+                Some(a0 as i32)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    };
+
+    Ok(code)
+}
+
+/// Reads the fault status of a core which halted at `pc` because of a fault.
+fn fault_report(core: &mut Core<'_>, pc: u64) -> Result<FaultReport, Error> {
+    let mut report = FaultReport {
+        pc,
+        faulting_pc: None,
+        cfsr: None,
+        hfsr: None,
+        mmfar: None,
+        bfar: None,
+    };
+
+    let core_type = core.core_type();
+    if !core_type.is_cortex_m() {
+        return Ok(report);
+    }
+
+    if core_type != CoreType::Armv6m {
+        let cfsr = core.read_word_32(CFSR)?;
+
+        report.cfsr = Some(cfsr);
+        report.hfsr = Some(core.read_word_32(HFSR)?);
+        if cfsr & CFSR_MMARVALID != 0 {
+            report.mmfar = Some(core.read_word_32(MMFAR)?);
+        }
+        if cfsr & CFSR_BFARVALID != 0 {
+            report.bfar = Some(core.read_word_32(BFAR)?);
+        }
+    }
+
+    // At the entry of the fault handler, LR contains EXC_RETURN, which tells which stack
+    // the exception frame is on. The stacked PC is the seventh word of the frame.
+    let exc_return: u32 = core.read_core_reg(core.registers().return_address().id)?;
+    if exc_return >> 24 == 0xff {
+        let stack_pointer = if exc_return & (1 << 2) != 0 {
+            core.registers().psp
+        } else {
+            core.registers().msp
+        };
+
+        if let Some(stack_pointer) = stack_pointer {
+            let frame: u32 = core.read_core_reg(stack_pointer.id)?;
+            report.faulting_pc = Some(core.read_word_32(frame as u64 + 24)?.into());
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn outcome_exit_codes() {
+        assert!(RunOutcome::Done.is_success());
+        assert!(RunOutcome::Exited { code: 0 }.is_success());
+        assert_eq!(RunOutcome::Exited { code: 3 }.exit_code(), 3);
+        assert_eq!(
+            RunOutcome::Panicked(PanicInfo {
+                return_address: 0x1000
+            })
+            .exit_code(),
+            PANIC_EXIT_CODE
+        );
+        assert!(!RunOutcome::TimedOut { pc: 0 }.is_success());
+    }
+
+    #[test]
+    fn fault_report_causes() {
+        let report = FaultReport {
+            pc: 0x1000,
+            faulting_pc: Some(0x2000),
+            cfsr: Some((1 << 25) | (1 << 9) | CFSR_BFARVALID),
+            hfsr: Some(1 << 30),
+            mmfar: None,
+            bfar: Some(0x4000_0000),
+        };
+
+        assert_eq!(
+            report.causes(),
+            vec![
+                "HardFault: Escalated from a configurable fault",
+                "BusFault: Precise data bus error",
+                "UsageFault: Divide by zero",
+            ]
+        );
+    }
+}
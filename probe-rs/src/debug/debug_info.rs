@@ -2,7 +2,8 @@ use super::{
     function_die::FunctionDie, get_sequential_key, unit_info::UnitInfo, unit_info::UnitIter,
     variable::*, DebugError, Registers, SourceLocation, StackFrame, VariableCache,
 };
-use crate::{core::Core, debug::registers, MemoryInterface};
+use crate::architecture::arm::core::cortex_m::ExcReturn;
+use crate::{core::Core, debug::registers, CoreType, MemoryInterface, RegisterId};
 use ::gimli::{FileEntry, LineProgramHeader, UnwindContext};
 use num_traits::Zero;
 use object::read::{Object, ObjectSection};
@@ -20,6 +21,12 @@ pub(crate) type GimliAttribute = gimli::Attribute<GimliReader>;
 
 pub(crate) type DwarfReader = gimli::read::EndianRcSlice<gimli::LittleEndian>;
 
+/// The `DCRSR` register selectors of the banked stack pointers of ARMv8-M cores with the Security Extension.
+const MSP_NS: u16 = 0x18;
+const PSP_NS: u16 = 0x19;
+const MSP_S: u16 = 0x1a;
+const PSP_S: u16 = 0x1b;
+
 /// Program row data that the debugger can use for breakpoints and stepping.
 /// To understand how this struct is used, use the following framework:
 /// - Everything is calculated from a given machine instruction address, usually the current program counter.
@@ -894,18 +901,32 @@ impl DebugInfo {
     /// The unwind loop will continue until we meet one of the following conditions:
     /// - We can no longer unwind a valid PC value to be used for the next frame.
     /// - We encounter a LR register value of 0xFFFFFFFF which is the 'Reset` value for that register.
-    /// - TODO: Catch the situation where the PC value indicates a hard-fault or other non-recoverable exception
     /// - We can not intelligently calculate a valid LR register value from the other registers, or the gimli::RegisterRule result is a value of 0x0. Note: [DWARF](https://dwarfstd.org) 6.4.4 - CIE defines the return register address used in the `gimli::RegisterRule` tables for unwind operations. Theoretically, if we encounter a function that has `Undefined` `gimli::RegisterRule` for the return register address, it means we have reached the bottom of the stack OR the function is a 'no return' type of function. I have found actual examples (e.g. local functions) where we get `Undefined` for register rule when we cannot apply this logic. Example 1: local functions in main.rs will have LR rule as `Undefined`. Example 2: main()-> ! that is called from a trampoline will have a valid LR rule.
     /// - Similarly, certain error conditions encountered in `StackFrameIterator` will also break out of the unwind loop.
+    /// On Cortex-M cores, the unwind continues from an exception handler into the interrupted code, with the registers from the exception frame.
     /// Note: In addition to populating the `StackFrame`s, this function will also populate the `DebugInfo::VariableCache` with `Variable`s for available Registers as well as static and function variables.
     /// TODO: Separate logic for stackframe creation and cache population
     pub fn unwind(&self, core: &mut Core, address: u64) -> Result<Vec<StackFrame>, crate::Error> {
-        let mut stack_frames = Vec::<StackFrame>::new();
         let mut unwind_registers = registers::Registers::from_core(core);
-        // Register state as updated for every iteration (previous function) of the unwind process.
         if unwind_registers.get_program_counter().is_none() {
             unwind_registers.set_program_counter(Some(address));
         }
+
+        self.unwind_registers(core, unwind_registers)
+    }
+
+    /// Performs the logical unwind of the stack, starting with the given register values instead of the
+    /// current registers of the core, see [`DebugInfo::unwind`].
+    ///
+    /// This is used to unwind the stacks of threads which are not running, e.g. the ones
+    /// of an [RTOS](super::rtos), from their saved registers.
+    pub fn unwind_registers(
+        &self,
+        core: &mut Core,
+        // Register state as updated for every iteration (previous function) of the unwind process.
+        mut unwind_registers: registers::Registers,
+    ) -> Result<Vec<StackFrame>, crate::Error> {
+        let mut stack_frames = Vec::<StackFrame>::new();
         let mut unwind_context: Box<UnwindContext<DwarfReader>> =
             Box::new(gimli::UnwindContext::new());
         let unwind_bases = gimli::BaseAddresses::default();
@@ -952,7 +973,7 @@ impl DebugInfo {
             };
 
             // Part 1-b: Check LR values to determine if we can continue unwinding.
-            // TODO: ARM has special ranges of LR addresses to indicate fault conditions. We should check those also.
+            // NOTE: The `EXC_RETURN` values of Cortex-M exception handlers are handled in PART 2-d.
             if let Some(check_return_address) = unwind_registers.get_return_address() {
                 if check_return_address == u32::MAX as u64 {
                     // When we encounter the starting (after reset) return address, we've reached the bottom of the stack, so no more unwinding after this.
@@ -1197,6 +1218,28 @@ impl DebugInfo {
                 }
             };
 
+            // PART 2-d: A Cortex-M exception handler returns with an `EXC_RETURN` value instead of an address, and the registers of the interrupted code were stacked by the core on exception entry.
+            if core.core_type().is_cortex_m() {
+                if let Some(exc_return) = unwind_registers
+                    .get_return_address()
+                    .and_then(ExcReturn::from_return_address)
+                {
+                    log::trace!(
+                        "UNWIND: Unwinding the exception frame of {} with {:?}",
+                        return_frame.function_name,
+                        exc_return
+                    );
+
+                    if let Err(error) =
+                        unwind_exception_frame(core, &mut unwind_registers, exc_return)
+                    {
+                        log::error!("UNWIND: Failed to unwind the exception frame: {}", error);
+                        stack_frames.push(return_frame);
+                        break;
+                    }
+                }
+            }
+
             // PART 3: In order to set the correct value of the previous frame we need to peek one frame deeper in the stack.
             // NOTE: ARM Specific.
             // TODO: Investigate and document why and under which circumstances this extra step is necessary. It was added during PR#895.
@@ -1445,6 +1488,413 @@ impl DebugInfo {
         Some(combined_path)
     }
 
+    /// Returns the address of the static variable with the given name, e.g. `pxCurrentTCB`.
+    ///
+    /// If several variables with this name exist, e.g. in different namespaces, the first one is returned.
+    pub(crate) fn static_variable_address(&self, name: &str) -> Option<u64> {
+        self.static_variables(name)
+            .into_iter()
+            .next()
+            .map(|variable| variable.address)
+    }
+
+    /// Returns all static variables with the given name, with the namespaces they are declared in.
+    pub(crate) fn static_variables(&self, name: &str) -> Vec<StaticVariable> {
+        self.collect_static_variables(Some(name))
+    }
+
+    /// Returns all global and static variables of the program which have a fixed address.
+    ///
+    /// The variables are listed by compilation unit, in the order of the debug information.
+    /// Their values can be read with [`DebugInfo::read_static_variable`], also while the core is running.
+    pub fn all_static_variables(&self) -> Vec<StaticVariable> {
+        self.collect_static_variables(None)
+    }
+
+    /// Returns the static variable with the given path, e.g. `firmware::state::STATE_MACHINE_CURRENT`,
+    /// or with the given name, if it is not qualified by a namespace.
+    ///
+    /// If several variables match, e.g. with the same name in different namespaces, the first one is returned.
+    pub fn find_static_variable(&self, path: &str) -> Option<StaticVariable> {
+        let name = path.rsplit("::").next().unwrap_or(path);
+
+        self.static_variables(name)
+            .into_iter()
+            .find(|variable| !path.contains("::") || variable.path() == path)
+    }
+
+    /// Reads the value of a static variable, with the type information from the debug information.
+    ///
+    /// The variable is the only top level entry of the returned cache, and its children are resolved as
+    /// for the variables of a stack frame. The registers of the core are not needed, so this works
+    /// while the core is running, as long as the memory can be accessed.
+    pub fn read_static_variable(
+        &self,
+        core: &mut Core<'_>,
+        static_variable: &StaticVariable,
+    ) -> Result<(Variable, VariableCache), DebugError> {
+        let unit_header = self
+            .dwarf
+            .debug_info
+            .header_from_offset(static_variable.unit_header_offset)?;
+        let unit_info = UnitInfo {
+            debug_info: self,
+            unit: gimli::Unit::new(&self.dwarf, unit_header)?,
+        };
+
+        let mut tree = unit_info.unit.header.entries_tree(
+            &unit_info.unit.abbreviations,
+            Some(static_variable.entry_offset),
+        )?;
+        let mut variable_node = tree.root()?;
+
+        let registers = Registers::without_values(core);
+        let mut cache = VariableCache::new();
+
+        // A temporary parent, which is removed again, so the variable is at the top level of the cache.
+        let mut parent_variable = Variable::new(
+            static_variable.unit_header_offset,
+            Some(static_variable.entry_offset),
+        );
+        parent_variable.name = VariableName::StaticScopeRoot;
+        parent_variable.memory_location = VariableLocation::Unavailable;
+        parent_variable = cache.cache_variable(None, parent_variable, core)?;
+
+        let variable = cache.cache_variable(
+            Some(parent_variable.variable_key),
+            Variable::new(
+                static_variable.unit_header_offset,
+                Some(static_variable.entry_offset),
+            ),
+            core,
+        )?;
+        let mut variable = unit_info.process_tree_node_attributes(
+            &mut variable_node,
+            &mut parent_variable,
+            variable,
+            core,
+            &registers,
+            &mut cache,
+        )?;
+
+        variable.parent_key = None;
+        variable = cache.cache_variable(None, variable, core)?;
+        cache.remove_cache_entry(parent_variable.variable_key)?;
+
+        Ok((variable, cache))
+    }
+
+    /// Returns the static variables with the given name, or all of them.
+    fn collect_static_variables(&self, name: Option<&str>) -> Vec<StaticVariable> {
+        let mut variables = Vec::new();
+
+        let mut units = self.get_units();
+        while let Some(unit_info) = self.get_next_unit_info(&mut units) {
+            let unit = &unit_info.unit;
+
+            let mut compile_unit = None;
+            // The namespaces enclosing the current entry, with the depth of their entries.
+            let mut namespaces: Vec<(isize, String)> = Vec::new();
+            let mut depth = 0;
+
+            let mut entries_cursor = unit.entries();
+            while let Ok(Some((delta_depth, entry))) = entries_cursor.next_dfs() {
+                depth += delta_depth;
+                namespaces.retain(|(namespace_depth, _)| *namespace_depth < depth);
+
+                match entry.tag() {
+                    gimli::DW_TAG_compile_unit => compile_unit = self.entry_name(unit, entry),
+                    gimli::DW_TAG_namespace => {
+                        if let Some(namespace) = self.entry_name(unit, entry) {
+                            namespaces.push((depth, namespace));
+                        }
+                    }
+                    gimli::DW_TAG_variable => {
+                        let variable_name = match self.entry_name(unit, entry) {
+                            Some(variable_name) => variable_name,
+                            None => continue,
+                        };
+                        if name.map_or(false, |name| name != variable_name) {
+                            continue;
+                        }
+
+                        if let Some(address) = static_address(unit, entry) {
+                            variables.push(StaticVariable {
+                                name: variable_name,
+                                namespaces: namespaces
+                                    .iter()
+                                    .map(|(_, namespace)| namespace.clone())
+                                    .collect(),
+                                compile_unit: compile_unit.clone(),
+                                type_name: self.type_name(unit, entry),
+                                address,
+                                unit_header_offset: unit.header.offset().as_debug_info_offset(),
+                                entry_offset: entry.offset(),
+                            });
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        variables
+    }
+
+    /// Returns the name of the type of the entry, if the type has a name.
+    fn type_name(
+        &self,
+        unit: &gimli::Unit<GimliReader>,
+        entry: &gimli::DebuggingInformationEntry<GimliReader>,
+    ) -> Option<String> {
+        match entry.attr_value(gimli::DW_AT_type) {
+            Ok(Some(gimli::AttributeValue::UnitRef(offset))) => {
+                self.entry_name(unit, &unit.entry(offset).ok()?)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the address of the function with the given path, e.g. `firmware::handlers::on_button`.
+    ///
+    /// The path can omit leading namespaces, e.g. the crate name, so `handlers::on_button` and `on_button`
+    /// find the same function. Methods are qualified by their type, e.g. `Uart::write`. Functions which
+    /// were only inlined have no address. If several functions match, the first one is returned.
+    pub fn function_address(&self, path: &str) -> Option<u64> {
+        let mut units = self.get_units();
+        while let Some(unit_info) = self.get_next_unit_info(&mut units) {
+            let unit = &unit_info.unit;
+
+            // The namespaces and types enclosing the current entry, with the depth of their entries.
+            let mut scopes: Vec<(isize, String)> = Vec::new();
+            let mut depth = 0;
+
+            let mut entries_cursor = unit.entries();
+            while let Ok(Some((delta_depth, entry))) = entries_cursor.next_dfs() {
+                depth += delta_depth;
+                scopes.retain(|(scope_depth, _)| *scope_depth < depth);
+
+                match entry.tag() {
+                    gimli::DW_TAG_namespace
+                    | gimli::DW_TAG_structure_type
+                    | gimli::DW_TAG_enumeration_type
+                    | gimli::DW_TAG_union_type => {
+                        if let Some(scope) = self.entry_name(unit, entry) {
+                            scopes.push((depth, scope));
+                        }
+                    }
+                    gimli::DW_TAG_subprogram => {
+                        let address = match entry.attr_value(gimli::DW_AT_low_pc) {
+                            Ok(Some(gimli::AttributeValue::Addr(address))) if address != 0 => {
+                                address
+                            }
+                            _ => continue,
+                        };
+                        let name = match self.entry_name(unit, entry) {
+                            Some(name) => name,
+                            None => continue,
+                        };
+
+                        let function_path = scopes
+                            .iter()
+                            .map(|(_, scope)| scope.as_str())
+                            .chain(std::iter::once(name.as_str()))
+                            .collect::<Vec<_>>()
+                            .join("::");
+
+                        if function_path == path || function_path.ends_with(&format!("::{}", path))
+                        {
+                            return Some(address);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Returns the offset of `member` in the structure `struct_name`.
+    ///
+    /// Members of anonymous structures and unions inside the structure are found as well,
+    /// which is common in C code, e.g. for the `prio` member of Zephyr's `_thread_base`.
+    pub(crate) fn struct_member_offset(&self, struct_name: &str, member: &str) -> Option<u64> {
+        self.find_struct(struct_name, |unit, entry| {
+            self.member_offset(unit, entry.offset(), member)
+        })
+    }
+
+    /// Returns the size of the structure `struct_name` in bytes.
+    pub(crate) fn struct_size(&self, struct_name: &str) -> Option<u64> {
+        self.find_struct(struct_name, |_, entry| {
+            entry
+                .attr_value(gimli::DW_AT_byte_size)
+                .ok()
+                .flatten()
+                .and_then(|size| size.udata_value())
+        })
+    }
+
+    /// Returns `true` if the debug information contains a top-level namespace with the given name,
+    /// which is the case for every Rust crate the program uses, e.g. `embassy_executor`.
+    pub(crate) fn has_namespace(&self, name: &str) -> bool {
+        let mut units = self.get_units();
+        while let Some(unit_info) = self.get_next_unit_info(&mut units) {
+            let unit = &unit_info.unit;
+
+            let mut tree = match unit.entries_tree(None) {
+                Ok(tree) => tree,
+                Err(_) => continue,
+            };
+            let root = match tree.root() {
+                Ok(root) => root,
+                Err(_) => continue,
+            };
+
+            let mut children = root.children();
+            while let Ok(Some(child)) = children.next() {
+                let entry = child.entry();
+                if entry.tag() == gimli::DW_TAG_namespace
+                    && self.entry_name(unit, entry).as_deref() == Some(name)
+                {
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Calls `f` with the definitions of the structure `struct_name`, until it returns `Some`.
+    fn find_struct<T>(
+        &self,
+        struct_name: &str,
+        mut f: impl FnMut(
+            &gimli::Unit<GimliReader>,
+            &gimli::DebuggingInformationEntry<GimliReader>,
+        ) -> Option<T>,
+    ) -> Option<T> {
+        let mut units = self.get_units();
+        while let Some(unit_info) = self.get_next_unit_info(&mut units) {
+            let unit = &unit_info.unit;
+
+            let mut entries_cursor = unit.entries();
+            while let Ok(Some((_, entry))) = entries_cursor.next_dfs() {
+                if entry.tag() != gimli::DW_TAG_structure_type
+                    || self.entry_name(unit, entry).as_deref() != Some(struct_name)
+                    || matches!(
+                        entry.attr_value(gimli::DW_AT_declaration),
+                        Ok(Some(gimli::AttributeValue::Flag(true)))
+                    )
+                {
+                    continue;
+                }
+
+                if let Some(result) = f(unit, entry) {
+                    return Some(result);
+                }
+            }
+        }
+
+        None
+    }
+
+    fn member_offset(
+        &self,
+        unit: &gimli::Unit<GimliReader>,
+        type_offset: gimli::UnitOffset,
+        member: &str,
+    ) -> Option<u64> {
+        let mut tree = unit.entries_tree(Some(type_offset)).ok()?;
+        let root = tree.root().ok()?;
+
+        let mut anonymous_members = Vec::new();
+
+        let mut children = root.children();
+        while let Ok(Some(child)) = children.next() {
+            let entry = child.entry();
+            if entry.tag() != gimli::DW_TAG_member {
+                continue;
+            }
+
+            // Members of unions have no location.
+            let offset = member_location(unit, entry).unwrap_or(0);
+
+            match self.entry_name(unit, entry) {
+                Some(name) if name == member => return Some(offset),
+                Some(_) => {}
+                None => {
+                    if let Ok(Some(gimli::AttributeValue::UnitRef(member_type))) =
+                        entry.attr_value(gimli::DW_AT_type)
+                    {
+                        anonymous_members.push((offset, member_type));
+                    }
+                }
+            }
+        }
+
+        anonymous_members
+            .into_iter()
+            .find_map(|(offset, member_type)| {
+                self.member_offset(unit, member_type, member)
+                    .map(|inner_offset| offset + inner_offset)
+            })
+    }
+
+    /// Returns the name of the entry, following `DW_AT_specification` for definitions of declarations,
+    /// and `DW_AT_abstract_origin` for concrete instances of inlined functions.
+    ///
+    /// The declaration or abstract instance can be in another unit, which is common after LTO.
+    pub(crate) fn entry_name(
+        &self,
+        unit: &gimli::Unit<GimliReader>,
+        entry: &gimli::DebuggingInformationEntry<GimliReader>,
+    ) -> Option<String> {
+        if let Ok(Some(name)) = entry.attr_value(gimli::DW_AT_name) {
+            return self
+                .dwarf
+                .attr_string(unit, name)
+                .ok()
+                .map(|name| String::from_utf8_lossy(&name).to_string());
+        }
+
+        let reference = [gimli::DW_AT_specification, gimli::DW_AT_abstract_origin]
+            .into_iter()
+            .find_map(|attribute| entry.attr_value(attribute).ok().flatten())?;
+
+        match reference {
+            gimli::AttributeValue::UnitRef(offset) => {
+                let declaration = unit.entry(offset).ok()?;
+                self.entry_name(unit, &declaration)
+            }
+            gimli::AttributeValue::DebugInfoRef(offset) => self.entry_name_in_unit_of(offset),
+            other => {
+                log::debug!(
+                    "Unsupported reference to the declaration of an entry: {:?}",
+                    other
+                );
+                None
+            }
+        }
+    }
+
+    /// Returns the name of the entry at `offset` in `.debug_info`, in whichever unit contains it.
+    fn entry_name_in_unit_of(&self, offset: gimli::DebugInfoOffset) -> Option<String> {
+        let mut headers = self.dwarf.units();
+
+        while let Ok(Some(header)) = headers.next() {
+            if let Some(unit_offset) = offset.to_unit_offset(&header) {
+                let unit = self.dwarf.unit(header).ok()?;
+                let entry = unit.entry(unit_offset).ok()?;
+
+                return self.entry_name(&unit, &entry);
+            }
+        }
+
+        None
+    }
+
     /// The minimum instruction size in bytes.
     pub fn get_instruction_size(&self) -> u8 {
         self.instruction_size
@@ -1468,6 +1918,139 @@ impl DebugInfo {
     }
 }
 
+/// A global or static variable found in the debug information, see [`DebugInfo::all_static_variables`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaticVariable {
+    /// The name of the variable.
+    pub name: String,
+    /// The namespaces the variable is declared in, outermost first. For Rust, this is the module path.
+    pub namespaces: Vec<String>,
+    /// The name of the compilation unit, which is usually the path of its main source file.
+    pub compile_unit: Option<String>,
+    /// The name of the type of the variable, if the type has a name.
+    pub type_name: Option<String>,
+    /// The address of the variable.
+    pub address: u64,
+    pub(crate) unit_header_offset: gimli::DebugInfoOffset,
+    pub(crate) entry_offset: gimli::UnitOffset,
+}
+
+impl StaticVariable {
+    /// The name of the variable, qualified by its namespaces, e.g. `firmware::state::STATE_MACHINE_CURRENT`.
+    pub fn path(&self) -> String {
+        self.namespaces
+            .iter()
+            .chain(std::iter::once(&self.name))
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("::")
+    }
+}
+
+/// Returns the address of a variable with a fixed location, i.e. a `DW_OP_addr` location expression.
+fn static_address(
+    unit: &gimli::Unit<GimliReader>,
+    entry: &gimli::DebuggingInformationEntry<GimliReader>,
+) -> Option<u64> {
+    match entry.attr_value(gimli::DW_AT_location) {
+        Ok(Some(gimli::AttributeValue::Exprloc(expression))) => {
+            let mut operations = expression.operations(unit.encoding());
+
+            match (operations.next(), operations.next()) {
+                (Ok(Some(gimli::Operation::Address { address })), Ok(None)) => Some(address),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Returns the offset of a structure member, from its `DW_AT_data_member_location`.
+fn member_location(
+    unit: &gimli::Unit<GimliReader>,
+    entry: &gimli::DebuggingInformationEntry<GimliReader>,
+) -> Option<u64> {
+    match entry.attr_value(gimli::DW_AT_data_member_location) {
+        Ok(Some(gimli::AttributeValue::Exprloc(expression))) => {
+            match expression.operations(unit.encoding()).next() {
+                Ok(Some(gimli::Operation::PlusConstant { value })) => Some(value),
+                _ => None,
+            }
+        }
+        Ok(Some(value)) => value.udata_value(),
+        _ => None,
+    }
+}
+
+/// Replaces the registers which were unwound from a Cortex-M exception handler with the registers of the
+/// interrupted code, which the core stacked on exception entry, as described by the `EXC_RETURN` value.
+fn unwind_exception_frame(
+    core: &mut Core,
+    registers: &mut Registers,
+    exc_return: ExcReturn,
+) -> Result<(), crate::Error> {
+    let is_armv8m = core.core_type() == CoreType::Armv8m;
+
+    let frame_address = if is_armv8m && exc_return.s() != exc_return.es() {
+        // The frame is on the stack of the other security state, which is only accessible with its banked stack pointer.
+        let stack_pointer = match (exc_return.s(), exc_return.spsel()) {
+            (false, false) => MSP_NS,
+            (false, true) => PSP_NS,
+            (true, false) => MSP_S,
+            (true, true) => PSP_S,
+        };
+        core.read_core_reg(RegisterId(stack_pointer))?
+    } else if exc_return.spsel() {
+        let psp = core
+            .registers()
+            .psp()
+            .ok_or_else(|| crate::Error::Other(anyhow::anyhow!("The core has no PSP register.")))?;
+        core.read_core_reg(psp)?
+    } else {
+        // The handler used the main stack as well, so the frame is right above the frame of the handler.
+        registers.get_stack_pointer().ok_or_else(|| {
+            crate::Error::Other(anyhow::anyhow!(
+                "The stack pointer of the exception handler is unknown."
+            ))
+        })?
+    };
+
+    let mut address = frame_address;
+
+    // ARMv8-M stacks the callee saved registers as well, after an integrity signature and a reserved word.
+    if is_armv8m && !exc_return.dcrs() {
+        let mut additional_state = [0u32; 10];
+        core.read_32(address, &mut additional_state)?;
+
+        for (register, value) in (4..=11).zip(&additional_state[2..]) {
+            registers.set_by_dwarf_register_number(register, Some((*value).into()));
+        }
+
+        address += 40;
+    }
+
+    let mut frame = [0u32; 8];
+    core.read_32(address, &mut frame)?;
+    let [r0, r1, r2, r3, r12, lr, pc, xpsr] = frame;
+
+    for (register, value) in [(0, r0), (1, r1), (2, r2), (3, r3), (12, r12), (14, lr)] {
+        registers.set_by_dwarf_register_number(register, Some(value.into()));
+    }
+    registers.set_program_counter(Some(u64::from(pc) & !0b1));
+
+    // The extended frame contains S0-S15, the FPSCR and a reserved word.
+    let mut frame_size = if exc_return.ftype() { 32 } else { 32 + 72 };
+
+    // The core inserts a padding word to align the frame to 8 bytes, if the stack pointer wasn't aligned.
+    if xpsr & (1 << 9) != 0 {
+        frame_size += 4;
+    }
+
+    registers.set_stack_pointer(Some(address + frame_size));
+
+    Ok(())
+}
+
 /// Helper function to handle adding a signed offset to a u64 address
 /// Wraps, which matches previous behavior of using i64 operations and
 /// casting to u32
@@ -0,0 +1,757 @@
+use super::{
+    variable::parse_integer, DebugError, DebugInfo, StackFrame, Variable, VariableCache,
+    VariableLocation, VariableName, VariableType,
+};
+use crate::{core::Core, MemoryInterface};
+use anyhow::anyhow;
+
+/// The result of evaluating an expression with [`DebugInfo::evaluate_expression`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExpressionValue {
+    /// The expression refers to a variable of the stack frame, which can have children.
+    ///
+    /// The variable is in either the `local_variables` or the `static_variables` of the stack frame.
+    Variable(Variable),
+    /// The expression computed an integer.
+    Integer(i128),
+}
+
+impl ExpressionValue {
+    /// Formats the value for display, using the caches of the stack frame it was evaluated in.
+    pub fn value(&self, stack_frame: &StackFrame) -> String {
+        match self {
+            ExpressionValue::Variable(variable) => {
+                match [&stack_frame.local_variables, &stack_frame.static_variables]
+                    .into_iter()
+                    .flatten()
+                    .find(|cache| cache.get_variable_by_key(variable.variable_key).is_some())
+                {
+                    Some(cache) => variable.get_value(cache),
+                    None => format!("<variable {} is not cached>", variable.name),
+                }
+            }
+            ExpressionValue::Integer(value) => value.to_string(),
+        }
+    }
+}
+
+impl DebugInfo {
+    /// Evaluates a C or Rust like expression, e.g. `config.flags & 0x4`, `buf[i].len` or `*ptr`,
+    /// against the variables of `stack_frame` and the memory of the target.
+    ///
+    /// Identifiers are looked up in the local variables, then in the static variables, and last in
+    /// the registers of the stack frame. Variables which are not resolved yet, e.g. the target of a
+    /// pointer, are added to the caches of the stack frame.
+    ///
+    /// Arithmetic is done on 128 bit integers, so it does not overflow for the integer types of the
+    /// target. Floating point values are not supported.
+    pub fn evaluate_expression(
+        &self,
+        core: &mut Core<'_>,
+        stack_frame: &mut StackFrame,
+        expression: &str,
+    ) -> Result<ExpressionValue, DebugError> {
+        let expression = Parser::parse(expression)?;
+
+        Evaluator {
+            debug_info: self,
+            core,
+            stack_frame,
+        }
+        .evaluate(&expression)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Integer(i128),
+    Identifier(String),
+    Symbol(&'static str),
+}
+
+/// The symbols of the operators and brackets, with the longer ones first.
+const SYMBOLS: [&str; 25] = [
+    "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "+", "-", "*", "/", "%", "&", "|", "^", "!",
+    "~", "<", ">", "(", ")", "[", "]", ".",
+];
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, DebugError> {
+    let mut tokens = Vec::new();
+    let mut remaining = expression.trim_start();
+
+    while let Some(first) = remaining.chars().next() {
+        if first.is_ascii_alphanumeric() || first == '_' {
+            let length = remaining
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(remaining.len());
+            let (word, rest) = remaining.split_at(length);
+
+            tokens.push(if first.is_ascii_digit() {
+                Token::Integer(parse_integer(word).map_err(|error| {
+                    DebugError::Other(anyhow!("Invalid integer `{}`: {}", word, error))
+                })?)
+            } else {
+                Token::Identifier(word.to_string())
+            });
+            remaining = rest;
+        } else if let Some(symbol) = SYMBOLS
+            .iter()
+            .find(|symbol| remaining.starts_with(**symbol))
+        {
+            tokens.push(Token::Symbol(*symbol));
+            remaining = &remaining[symbol.len()..];
+        } else {
+            return Err(DebugError::Other(anyhow!(
+                "Unexpected character `{}` in expression.",
+                first
+            )));
+        }
+
+        remaining = remaining.trim_start();
+    }
+
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum UnaryOperator {
+    Negate,
+    Not,
+    BitNot,
+    Deref,
+    AddressOf,
+}
+
+impl UnaryOperator {
+    fn from_symbol(symbol: &str) -> Option<Self> {
+        Some(match symbol {
+            "-" => UnaryOperator::Negate,
+            "!" => UnaryOperator::Not,
+            "~" => UnaryOperator::BitNot,
+            "*" => UnaryOperator::Deref,
+            "&" => UnaryOperator::AddressOf,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BinaryOperator {
+    Multiply,
+    Divide,
+    Remainder,
+    Add,
+    Subtract,
+    ShiftLeft,
+    ShiftRight,
+    BitAnd,
+    BitXor,
+    BitOr,
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    And,
+    Or,
+}
+
+impl BinaryOperator {
+    fn from_symbol(symbol: &str) -> Option<Self> {
+        Some(match symbol {
+            "*" => BinaryOperator::Multiply,
+            "/" => BinaryOperator::Divide,
+            "%" => BinaryOperator::Remainder,
+            "+" => BinaryOperator::Add,
+            "-" => BinaryOperator::Subtract,
+            "<<" => BinaryOperator::ShiftLeft,
+            ">>" => BinaryOperator::ShiftRight,
+            "&" => BinaryOperator::BitAnd,
+            "^" => BinaryOperator::BitXor,
+            "|" => BinaryOperator::BitOr,
+            "==" => BinaryOperator::Equal,
+            "!=" => BinaryOperator::NotEqual,
+            "<" => BinaryOperator::Less,
+            "<=" => BinaryOperator::LessOrEqual,
+            ">" => BinaryOperator::Greater,
+            ">=" => BinaryOperator::GreaterOrEqual,
+            "&&" => BinaryOperator::And,
+            "||" => BinaryOperator::Or,
+            _ => return None,
+        })
+    }
+
+    /// The precedence of the operator, as in C, where higher values bind more tightly.
+    fn precedence(self) -> u8 {
+        match self {
+            BinaryOperator::Or => 1,
+            BinaryOperator::And => 2,
+            BinaryOperator::BitOr => 3,
+            BinaryOperator::BitXor => 4,
+            BinaryOperator::BitAnd => 5,
+            BinaryOperator::Equal | BinaryOperator::NotEqual => 6,
+            BinaryOperator::Less
+            | BinaryOperator::LessOrEqual
+            | BinaryOperator::Greater
+            | BinaryOperator::GreaterOrEqual => 7,
+            BinaryOperator::ShiftLeft | BinaryOperator::ShiftRight => 8,
+            BinaryOperator::Add | BinaryOperator::Subtract => 9,
+            BinaryOperator::Multiply | BinaryOperator::Divide | BinaryOperator::Remainder => 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expression {
+    Integer(i128),
+    Identifier(String),
+    Field(Box<Expression>, String),
+    Index(Box<Expression>, Box<Expression>),
+    Unary(UnaryOperator, Box<Expression>),
+    Binary(BinaryOperator, Box<Expression>, Box<Expression>),
+}
+
+/// A precedence climbing parser for expressions.
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn parse(expression: &str) -> Result<Expression, DebugError> {
+        let mut parser = Parser {
+            tokens: tokenize(expression)?,
+            position: 0,
+        };
+
+        let expression = parser.parse_binary(1)?;
+
+        match parser.next() {
+            None => Ok(expression),
+            Some(token) => Err(DebugError::Other(anyhow!(
+                "Unexpected {:?} after the end of the expression.",
+                token
+            ))),
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn expect(&mut self, symbol: &'static str) -> Result<(), DebugError> {
+        match self.next() {
+            Some(Token::Symbol(next)) if next == symbol => Ok(()),
+            other => Err(DebugError::Other(anyhow!(
+                "Expected `{}`, but found {:?}.",
+                symbol,
+                other
+            ))),
+        }
+    }
+
+    /// Parses binary operations with operators of at least `min_precedence`.
+    fn parse_binary(&mut self, min_precedence: u8) -> Result<Expression, DebugError> {
+        let mut lhs = self.parse_unary()?;
+
+        while let Some(operator) = match self.peek() {
+            Some(Token::Symbol(symbol)) => BinaryOperator::from_symbol(symbol),
+            _ => None,
+        } {
+            if operator.precedence() < min_precedence {
+                break;
+            }
+            self.next();
+
+            let rhs = self.parse_binary(operator.precedence() + 1)?;
+            lhs = Expression::Binary(operator, Box::new(lhs), Box::new(rhs));
+        }
+
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expression, DebugError> {
+        if let Some(operator) = match self.peek() {
+            Some(Token::Symbol(symbol)) => UnaryOperator::from_symbol(symbol),
+            _ => None,
+        } {
+            self.next();
+            return Ok(Expression::Unary(operator, Box::new(self.parse_unary()?)));
+        }
+
+        let mut expression = match self.next() {
+            Some(Token::Integer(value)) => Expression::Integer(value),
+            Some(Token::Identifier(name)) => Expression::Identifier(name),
+            Some(Token::Symbol("(")) => {
+                let expression = self.parse_binary(1)?;
+                self.expect(")")?;
+                expression
+            }
+            other => {
+                return Err(DebugError::Other(anyhow!(
+                    "Expected a value, but found {:?}.",
+                    other
+                )))
+            }
+        };
+
+        loop {
+            match self.peek() {
+                Some(Token::Symbol(".")) => {
+                    self.next();
+                    let field = match self.next() {
+                        Some(Token::Identifier(name)) => name,
+                        // Tuple fields, e.g. `pair.0`.
+                        Some(Token::Integer(index)) => index.to_string(),
+                        other => {
+                            return Err(DebugError::Other(anyhow!(
+                                "Expected a field name after `.`, but found {:?}.",
+                                other
+                            )))
+                        }
+                    };
+                    expression = Expression::Field(Box::new(expression), field);
+                }
+                Some(Token::Symbol("[")) => {
+                    self.next();
+                    let index = self.parse_binary(1)?;
+                    self.expect("]")?;
+                    expression = Expression::Index(Box::new(expression), Box::new(index));
+                }
+                _ => return Ok(expression),
+            }
+        }
+    }
+}
+
+struct Evaluator<'a, 'probe> {
+    debug_info: &'a DebugInfo,
+    core: &'a mut Core<'probe>,
+    stack_frame: &'a mut StackFrame,
+}
+
+impl Evaluator<'_, '_> {
+    fn evaluate(&mut self, expression: &Expression) -> Result<ExpressionValue, DebugError> {
+        match expression {
+            Expression::Integer(value) => Ok(ExpressionValue::Integer(*value)),
+            Expression::Identifier(name) => self.identifier(name),
+            Expression::Field(base, field) => {
+                let base = self.evaluate_variable(base)?;
+                self.field(base, field)
+            }
+            Expression::Index(base, index) => {
+                let base = self.evaluate_variable(base)?;
+                let index = self.evaluate_integer(index)?;
+                self.index(base, index)
+            }
+            Expression::Unary(operator, operand) => {
+                let operand = self.evaluate(operand)?;
+                self.unary(*operator, operand)
+            }
+            Expression::Binary(operator, lhs, rhs) => {
+                let lhs = self.evaluate_integer(lhs)?;
+
+                // `&&` and `||` only evaluate the right hand side if needed.
+                let value = match operator {
+                    BinaryOperator::And if lhs == 0 => 0,
+                    BinaryOperator::Or if lhs != 0 => 1,
+                    BinaryOperator::And | BinaryOperator::Or => {
+                        (self.evaluate_integer(rhs)? != 0) as i128
+                    }
+                    operator => binary(*operator, lhs, self.evaluate_integer(rhs)?)?,
+                };
+
+                Ok(ExpressionValue::Integer(value))
+            }
+        }
+    }
+
+    fn evaluate_variable(&mut self, expression: &Expression) -> Result<Variable, DebugError> {
+        match self.evaluate(expression)? {
+            ExpressionValue::Variable(variable) => Ok(variable),
+            ExpressionValue::Integer(value) => Err(DebugError::Other(anyhow!(
+                "The integer {} has no fields or elements.",
+                value
+            ))),
+        }
+    }
+
+    fn evaluate_integer(&mut self, expression: &Expression) -> Result<i128, DebugError> {
+        match self.evaluate(expression)? {
+            ExpressionValue::Variable(variable) => self.integer_value(&variable),
+            ExpressionValue::Integer(value) => Ok(value),
+        }
+    }
+
+    fn identifier(&mut self, name: &str) -> Result<ExpressionValue, DebugError> {
+        let variable_name = VariableName::Named(name.to_string());
+
+        for scope in [VariableName::LocalScopeRoot, VariableName::StaticScopeRoot] {
+            let root = match self
+                .cache(&scope)
+                .and_then(|cache| cache.get_variable_by_name_and_parent(&scope, None))
+            {
+                Some(root) => root,
+                None => continue,
+            };
+
+            // The variables of a scope are usually only cached when the scope is expanded.
+            let variables = self.children(root)?;
+            if let Some(variable) = variables
+                .into_iter()
+                .find(|variable| variable.name == variable_name)
+            {
+                return Ok(ExpressionValue::Variable(variable));
+            }
+
+            // Static variables can also be nested in namespaces.
+            if let Some(variable) = self
+                .cache(&scope)
+                .and_then(|cache| cache.get_variable_by_name(&variable_name))
+            {
+                return Ok(ExpressionValue::Variable(variable));
+            }
+        }
+
+        let registers = &self.stack_frame.registers;
+        registers
+            .registers()
+            .find(|(register_number, _)| {
+                registers
+                    .get_name_by_dwarf_register_number(**register_number)
+                    .map_or(false, |register_name| register_name == name)
+            })
+            .map(|(_, &value)| ExpressionValue::Integer(value.into()))
+            .ok_or_else(|| {
+                DebugError::Other(anyhow!("No variable or register named `{}` found.", name))
+            })
+    }
+
+    fn field(&mut self, base: Variable, field: &str) -> Result<ExpressionValue, DebugError> {
+        // Fields are accessed through pointers, as in Rust.
+        let base = if matches!(base.type_name, VariableType::Pointer(_)) {
+            self.deref(base)?
+        } else {
+            base
+        };
+
+        let names = [
+            VariableName::Named(field.to_string()),
+            VariableName::Named(format!("__{}", field)),
+        ];
+
+        let base_name = base.name.clone();
+        self.children(base)?
+            .into_iter()
+            .find(|child| names.contains(&child.name))
+            .map(ExpressionValue::Variable)
+            .ok_or_else(|| {
+                DebugError::Other(anyhow!("`{}` has no field named `{}`.", base_name, field))
+            })
+    }
+
+    fn index(&mut self, base: Variable, index: i128) -> Result<ExpressionValue, DebugError> {
+        if !matches!(base.type_name, VariableType::Array { .. }) {
+            return Err(DebugError::Other(anyhow!(
+                "`{}` of type `{}` is not an array.",
+                base.name,
+                base.type_name
+            )));
+        }
+
+        let element_name = VariableName::Named(format!("__{}", index));
+
+        let base_name = base.name.clone();
+        self.children(base)?
+            .into_iter()
+            .find(|child| child.name == element_name)
+            .map(ExpressionValue::Variable)
+            .ok_or_else(|| {
+                DebugError::Other(anyhow!(
+                    "Index {} is out of bounds of `{}`.",
+                    index,
+                    base_name
+                ))
+            })
+    }
+
+    fn unary(
+        &mut self,
+        operator: UnaryOperator,
+        operand: ExpressionValue,
+    ) -> Result<ExpressionValue, DebugError> {
+        let value = match (operator, operand) {
+            (UnaryOperator::Deref, ExpressionValue::Variable(variable)) => {
+                return self.deref(variable).map(ExpressionValue::Variable)
+            }
+            (UnaryOperator::Deref, ExpressionValue::Integer(address)) => {
+                let address = u64::try_from(address).map_err(|_| {
+                    DebugError::Other(anyhow!("{} is not a valid address.", address))
+                })?;
+                self.core.read_word_32(address)?.into()
+            }
+            (UnaryOperator::AddressOf, ExpressionValue::Variable(variable)) => {
+                variable.memory_location.memory_address()?.into()
+            }
+            (UnaryOperator::AddressOf, ExpressionValue::Integer(value)) => {
+                return Err(DebugError::Other(anyhow!(
+                    "Cannot take the address of the integer {}.",
+                    value
+                )))
+            }
+            (operator, operand) => {
+                let value = match operand {
+                    ExpressionValue::Variable(variable) => self.integer_value(&variable)?,
+                    ExpressionValue::Integer(value) => value,
+                };
+
+                match operator {
+                    UnaryOperator::Negate => value.wrapping_neg(),
+                    UnaryOperator::Not => (value == 0) as i128,
+                    _ => !value,
+                }
+            }
+        };
+
+        Ok(ExpressionValue::Integer(value))
+    }
+
+    fn deref(&mut self, pointer: Variable) -> Result<Variable, DebugError> {
+        if !matches!(pointer.type_name, VariableType::Pointer(_)) {
+            return Err(DebugError::Other(anyhow!(
+                "`{}` of type `{}` is not a pointer.",
+                pointer.name,
+                pointer.type_name
+            )));
+        }
+
+        let pointer_name = pointer.name.clone();
+        // The only child of a pointer is the variable it points to.
+        self.children(pointer)?.into_iter().next().ok_or_else(|| {
+            DebugError::Other(anyhow!(
+                "The target of `{}` could not be resolved.",
+                pointer_name
+            ))
+        })
+    }
+
+    /// Returns the integer value of a variable of a base or pointer type.
+    fn integer_value(&mut self, variable: &Variable) -> Result<i128, DebugError> {
+        let type_name = match &variable.type_name {
+            VariableType::Base(type_name) if !is_float(type_name) => type_name.as_str(),
+            VariableType::Pointer(_) => "",
+            other => {
+                return Err(DebugError::Other(anyhow!(
+                    "`{}` of type `{}` is not an integer.",
+                    variable.name,
+                    other
+                )))
+            }
+        };
+
+        // Read the value from memory if possible, because the cached value can be formatted,
+        // e.g. as a character.
+        if let VariableLocation::Address(address) = variable.memory_location {
+            if (1..=16).contains(&variable.byte_size) {
+                let mut bytes = [0u8; 16];
+                self.core
+                    .read_8(address, &mut bytes[..variable.byte_size as usize])?;
+
+                let value = u128::from_le_bytes(bytes);
+                let unused_bits = 128 - 8 * variable.byte_size as u32;
+
+                return Ok(if is_signed(type_name) {
+                    ((value << unused_bits) as i128) >> unused_bits
+                } else {
+                    value as i128
+                });
+            }
+        }
+
+        let value = self
+            .cache(&VariableName::LocalScopeRoot)
+            .filter(|cache| cache.get_variable_by_key(variable.variable_key).is_some())
+            .or_else(|| self.cache(&VariableName::StaticScopeRoot))
+            .map(|cache| variable.get_value(cache))
+            .unwrap_or_default();
+
+        match value.as_str() {
+            "true" => Ok(1),
+            "false" => Ok(0),
+            value => parse_integer(value).map_err(|_| {
+                DebugError::Other(anyhow!(
+                    "The value `{}` of `{}` is not an integer.",
+                    value,
+                    variable.name
+                ))
+            }),
+        }
+    }
+
+    /// Returns the children of a variable, and resolves them first if they are deferred.
+    fn children(&mut self, mut variable: Variable) -> Result<Vec<Variable>, DebugError> {
+        let StackFrame {
+            local_variables,
+            static_variables,
+            registers,
+            ..
+        } = &mut *self.stack_frame;
+
+        let cache = [local_variables.as_mut(), static_variables.as_mut()]
+            .into_iter()
+            .flatten()
+            .find(|cache| cache.get_variable_by_key(variable.variable_key).is_some())
+            .ok_or_else(|| {
+                DebugError::Other(anyhow!(
+                    "The variable `{}` is not in the caches of the stack frame.",
+                    variable.name
+                ))
+            })?;
+
+        if variable.variable_node_type.is_deferred() && !cache.has_children(&variable)? {
+            self.debug_info
+                .cache_deferred_variables(cache, self.core, &mut variable, registers)?;
+        }
+
+        cache.get_children(Some(variable.variable_key))
+    }
+
+    /// Returns the cache of the local or static variables.
+    fn cache(&self, scope: &VariableName) -> Option<&VariableCache> {
+        match scope {
+            VariableName::LocalScopeRoot => self.stack_frame.local_variables.as_ref(),
+            VariableName::StaticScopeRoot => self.stack_frame.static_variables.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+fn binary(operator: BinaryOperator, lhs: i128, rhs: i128) -> Result<i128, DebugError> {
+    let shift = || {
+        u32::try_from(rhs)
+            .ok()
+            .filter(|shift| *shift < 128)
+            .ok_or_else(|| DebugError::Other(anyhow!("Invalid shift amount {}.", rhs)))
+    };
+    let divisor = || {
+        if rhs == 0 {
+            Err(DebugError::Other(anyhow!("Division by zero.")))
+        } else {
+            Ok(rhs)
+        }
+    };
+
+    Ok(match operator {
+        BinaryOperator::Multiply => lhs.wrapping_mul(rhs),
+        BinaryOperator::Divide => lhs.wrapping_div(divisor()?),
+        BinaryOperator::Remainder => lhs.wrapping_rem(divisor()?),
+        BinaryOperator::Add => lhs.wrapping_add(rhs),
+        BinaryOperator::Subtract => lhs.wrapping_sub(rhs),
+        BinaryOperator::ShiftLeft => lhs << shift()?,
+        BinaryOperator::ShiftRight => lhs >> shift()?,
+        BinaryOperator::BitAnd => lhs & rhs,
+        BinaryOperator::BitXor => lhs ^ rhs,
+        BinaryOperator::BitOr => lhs | rhs,
+        BinaryOperator::Equal => (lhs == rhs) as i128,
+        BinaryOperator::NotEqual => (lhs != rhs) as i128,
+        BinaryOperator::Less => (lhs < rhs) as i128,
+        BinaryOperator::LessOrEqual => (lhs <= rhs) as i128,
+        BinaryOperator::Greater => (lhs > rhs) as i128,
+        BinaryOperator::GreaterOrEqual => (lhs >= rhs) as i128,
+        BinaryOperator::And => (lhs != 0 && rhs != 0) as i128,
+        BinaryOperator::Or => (lhs != 0 || rhs != 0) as i128,
+    })
+}
+
+fn is_float(type_name: &str) -> bool {
+    matches!(
+        type_name,
+        "f32" | "f64" | "float" | "double" | "long double"
+    )
+}
+
+/// Checks if a base type is signed, for the Rust and the C integer types.
+fn is_signed(type_name: &str) -> bool {
+    match type_name.strip_prefix('i') {
+        Some("8" | "16" | "32" | "64" | "128" | "size") => true,
+        _ => {
+            !type_name.starts_with('u')
+                && !type_name.contains("unsigned")
+                && ["char", "short", "int", "long"]
+                    .iter()
+                    .any(|name| type_name.contains(name))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{binary, BinaryOperator, Expression, Parser, UnaryOperator};
+
+    fn identifier(name: &str) -> Box<Expression> {
+        Box::new(Expression::Identifier(name.to_string()))
+    }
+
+    #[test]
+    fn parse_field_and_mask() {
+        assert_eq!(
+            Parser::parse("config.flags & 0x4").unwrap(),
+            Expression::Binary(
+                BinaryOperator::BitAnd,
+                Box::new(Expression::Field(identifier("config"), "flags".to_string())),
+                Box::new(Expression::Integer(4)),
+            )
+        );
+    }
+
+    #[test]
+    fn parse_index_and_deref() {
+        assert_eq!(
+            Parser::parse("buf[i].len").unwrap(),
+            Expression::Field(
+                Box::new(Expression::Index(identifier("buf"), identifier("i"))),
+                "len".to_string()
+            )
+        );
+        assert_eq!(
+            Parser::parse("*ptr").unwrap(),
+            Expression::Unary(UnaryOperator::Deref, identifier("ptr"))
+        );
+    }
+
+    #[test]
+    fn parse_precedence() {
+        match Parser::parse("1 + 2 * 3 == 7").unwrap() {
+            Expression::Binary(BinaryOperator::Equal, lhs, _) => {
+                assert!(matches!(
+                    *lhs,
+                    Expression::Binary(BinaryOperator::Add, _, _)
+                ))
+            }
+            other => panic!("Expected a comparison, but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(Parser::parse("a +").is_err());
+        assert!(Parser::parse("(a").is_err());
+        assert!(Parser::parse("a b").is_err());
+        assert!(Parser::parse("a $ b").is_err());
+    }
+
+    #[test]
+    fn binary_operations() {
+        assert_eq!(binary(BinaryOperator::ShiftLeft, 1, 4).unwrap(), 16);
+        assert_eq!(binary(BinaryOperator::Remainder, 7, 4).unwrap(), 3);
+        assert!(binary(BinaryOperator::Divide, 1, 0).is_err());
+        assert!(binary(BinaryOperator::ShiftRight, 1, -1).is_err());
+    }
+}
@@ -10,8 +10,10 @@ pub(crate) struct FunctionDie<'abbrev, 'unit, 'unit_info, 'debug_info> {
     pub(crate) function_die: FunctionDieType<'abbrev, 'unit>,
 
     /// Only present for inlined functions, where this is a reference
-    /// to the declaration of the function.
+    /// to the declaration of the function, if it is in the same unit.
     pub(crate) abstract_die: Option<FunctionDieType<'abbrev, 'unit>>,
+    /// `true` for inlined functions, also if their declaration is in another unit.
+    pub(crate) inlined: bool,
     /// The address of the first instruction in this function.
     pub(crate) low_pc: u64,
     /// The address of the first instruction after this funciton.
@@ -32,6 +34,7 @@ impl<'debugunit, 'abbrev, 'unit: 'debugunit, 'unit_info, 'debug_info>
                 unit_info,
                 function_die: die,
                 abstract_die: None,
+                inlined: false,
                 low_pc: 0,
                 high_pc: 0,
             }),
@@ -42,9 +45,11 @@ impl<'debugunit, 'abbrev, 'unit: 'debugunit, 'unit_info, 'debug_info>
         }
     }
 
+    /// Create a [`FunctionDie`] for an inlined function. The `abstract_die` is `None` if the
+    /// declaration of the function is in another unit.
     pub(crate) fn new_inlined(
         concrete_die: FunctionDieType<'abbrev, 'unit>,
-        abstract_die: FunctionDieType<'abbrev, 'unit>,
+        abstract_die: Option<FunctionDieType<'abbrev, 'unit>>,
         unit_info: &'unit_info UnitInfo<'debug_info>,
     ) -> Option<Self> {
         let tag = concrete_die.tag();
@@ -53,7 +58,8 @@ impl<'debugunit, 'abbrev, 'unit: 'debugunit, 'unit_info, 'debug_info>
             gimli::DW_TAG_inlined_subroutine => Some(Self {
                 unit_info,
                 function_die: concrete_die,
-                abstract_die: Some(abstract_die),
+                abstract_die,
+                inlined: true,
                 low_pc: 0,
                 high_pc: 0,
             }),
@@ -65,31 +71,22 @@ impl<'debugunit, 'abbrev, 'unit: 'debugunit, 'unit_info, 'debug_info>
     }
 
     pub(crate) fn is_inline(&self) -> bool {
-        self.abstract_die.is_some()
+        self.inlined
     }
 
+    /// The name of the function, from the declaration or the abstract instance of the function
+    /// if the DIE itself has no name.
     pub(crate) fn function_name(&self) -> Option<String> {
-        if let Some(fn_name_attr) = self.get_attribute(gimli::DW_AT_name) {
-            match fn_name_attr.value() {
-                gimli::AttributeValue::DebugStrRef(fn_name_ref) => {
-                    match self.unit_info.debug_info.dwarf.string(fn_name_ref) {
-                        Ok(fn_name_raw) => Some(String::from_utf8_lossy(&fn_name_raw).to_string()),
-                        Err(error) => {
-                            log::debug!("No value for DW_AT_name: {:?}: error", error);
-
-                            None
-                        }
-                    }
-                }
-                value => {
-                    log::debug!("Unexpected attribute value for DW_AT_name: {:?}", value);
-                    None
-                }
-            }
-        } else {
+        let name = self
+            .unit_info
+            .debug_info
+            .entry_name(&self.unit_info.unit, &self.function_die);
+
+        if name.is_none() {
             log::debug!("DW_AT_name attribute not found, unable to retrieve function name");
-            None
         }
+
+        name
     }
 
     /// Get the call site of an inlined function.
@@ -10,10 +10,14 @@
 
 /// Debug information which is parsed from DWARF debugging information.
 pub mod debug_info;
+/// Evaluation of expressions against the variables of a stack frame and the target memory.
+pub mod expression;
 /// References to the DIE (debug information entry) of functions.
 pub mod function_die;
 /// Target Register definitions.
 pub mod registers;
+/// Awareness of the threads of real-time operating systems.
+pub mod rtos;
 /// The stack frame information used while unwinding the stack from a specific program counter.
 pub mod stack_frame;
 /// Stepping granularity for stepping through a program during debug.
@@ -26,7 +30,7 @@ pub mod variable;
 pub mod variable_cache;
 
 pub use self::{
-    debug_info::*, registers::*, stack_frame::StackFrame, variable::*,
+    debug_info::*, expression::ExpressionValue, registers::*, stack_frame::StackFrame, variable::*,
     variable_cache::VariableCache,
 };
 use crate::{core::Core, MemoryInterface};
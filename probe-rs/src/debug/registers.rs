@@ -1,4 +1,7 @@
 use crate::core::Core;
+use crate::Error;
+
+use anyhow::anyhow;
 
 use probe_rs_target::Architecture;
 
@@ -47,6 +50,21 @@ impl Registers {
         registers
     }
 
+    /// Registers without values, except for a program counter of zero, for variables which don't
+    /// depend on the state of the core, e.g. static variables, which are read while the core is running.
+    pub(crate) fn without_values(core: &mut Core) -> Self {
+        let register_file = core.registers();
+
+        let mut registers = Registers {
+            register_description: register_file,
+            values: HashMap::new(),
+            architecture: core.architecture(),
+            address_size: register_file.program_counter().size_in_bytes(),
+        };
+        registers.set_program_counter(Some(0));
+        registers
+    }
+
     /// Gets the address size for this target, in bytes
     pub fn get_address_size_bytes(&self) -> usize {
         self.address_size
@@ -155,8 +173,50 @@ impl Registers {
             .get_platform_register(register_number as usize)
     }
 
+    /// Write a new value to the register with the dwarf register number `register_number`, both to
+    /// the core and to these register values.
+    ///
+    /// This is only correct for the registers of the frame which is currently executing, i.e. the
+    /// first frame of a backtrace. The registers of the other frames are reconstructed by unwinding,
+    /// and are not the registers of the core.
+    pub fn write_register(
+        &mut self,
+        core: &mut Core,
+        register_number: u32,
+        value: u64,
+    ) -> Result<(), Error> {
+        let description = self
+            .get_description_by_dwarf_register_number(register_number)
+            .ok_or_else(|| Error::Other(anyhow!("Unknown register number {}", register_number)))?;
+
+        write_core_register(core, description, value)?;
+        self.values.insert(register_number, value);
+
+        Ok(())
+    }
+
     /// Returns an iterator over all register numbers and their values.
     pub fn registers(&self) -> impl Iterator<Item = (&u32, &u64)> {
         self.values.iter()
     }
 }
+
+/// Write `value` to the register of the core, with the size of the register.
+pub(crate) fn write_core_register(
+    core: &mut Core,
+    description: &RegisterDescription,
+    value: u64,
+) -> Result<(), Error> {
+    if description.size_in_bytes() <= 4 {
+        let value = u32::try_from(value).map_err(|_| {
+            Error::Other(anyhow!(
+                "The value {:#x} is too large for the 32-bit register {}",
+                value,
+                description.name()
+            ))
+        })?;
+        core.write_core_reg(description.id, value)
+    } else {
+        core.write_core_reg(description.id, value)
+    }
+}
@@ -0,0 +1,89 @@
+use super::{Rtos, RtosThread, ThreadState};
+use crate::debug::{DebugError, DebugInfo};
+use crate::{core::Core, MemoryInterface};
+
+// The bits of `TaskHeader.state`.
+const STATE_SPAWNED: u32 = 1 << 0;
+const STATE_RUN_QUEUED: u32 = 1 << 1;
+
+/// Awareness of Embassy tasks.
+///
+/// The `#[embassy_executor::task]` macro places the storage of each task in a static `POOL`,
+/// inside of a namespace named after the task function, so the tasks are found by name.
+/// Only the first instance of tasks with a `pool_size` larger than one is shown.
+///
+/// The tasks are futures, which are polled on the stack of the executor, so they have no
+/// registers of their own, and the task which is currently polled is not known.
+#[derive(Debug)]
+pub struct Embassy {
+    /// The names and addresses of the task pools.
+    tasks: Vec<(String, u64)>,
+    /// The offset of `state` in `TaskHeader`, which is at the start of the pool.
+    state: u64,
+}
+
+impl Embassy {
+    /// Detects Embassy from the `embassy_executor` crate and the task pools in the debug information.
+    pub fn detect(debug_info: &DebugInfo) -> Option<Self> {
+        if !debug_info.has_namespace("embassy_executor") {
+            return None;
+        }
+
+        let tasks: Vec<_> = debug_info
+            .static_variables("POOL")
+            .into_iter()
+            .filter(|pool| !pool.namespaces.is_empty())
+            .map(|pool| (pool.namespaces.join("::"), pool.address))
+            .collect();
+
+        if tasks.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            tasks,
+            state: debug_info
+                .struct_member_offset("TaskHeader", "state")
+                .unwrap_or(0),
+        })
+    }
+}
+
+impl Rtos for Embassy {
+    fn name(&self) -> &'static str {
+        "Embassy"
+    }
+
+    fn threads(
+        &self,
+        core: &mut Core<'_>,
+        _debug_info: &DebugInfo,
+    ) -> Result<Vec<RtosThread>, DebugError> {
+        let mut threads = Vec::with_capacity(self.tasks.len());
+
+        for (name, pool) in &self.tasks {
+            let state = core.read_word_32(pool + self.state)?;
+
+            threads.push(RtosThread {
+                id: *pool,
+                name: name.clone(),
+                state: task_state_from_bits(state),
+                priority: None,
+                registers: None,
+            });
+        }
+
+        Ok(threads)
+    }
+}
+
+fn task_state_from_bits(state: u32) -> ThreadState {
+    if state & STATE_SPAWNED == 0 {
+        ThreadState::Inactive
+    } else if state & STATE_RUN_QUEUED != 0 {
+        ThreadState::Ready
+    } else {
+        // The task waits to be woken, possibly by a timer.
+        ThreadState::Blocked
+    }
+}
@@ -0,0 +1,248 @@
+use super::{
+    cortex_m_thread_registers, has_extended_frame, is_exc_return, read_c_string, Rtos, RtosThread,
+    ThreadState,
+};
+use crate::debug::{DebugError, DebugInfo, Registers};
+use crate::{core::Core, MemoryInterface};
+
+/// The maximum number of tasks read from a single list, in case the list is corrupted.
+const MAX_TASKS_PER_LIST: u32 = 256;
+
+/// The maximum number of priorities read, in case `uxTopUsedPriority` is corrupted.
+const MAX_PRIORITIES: u64 = 256;
+
+/// The length of `pcTaskName` if it is not known from the debug information.
+const DEFAULT_TASK_NAME_LEN: usize = 16;
+
+/// Awareness of FreeRTOS tasks.
+///
+/// The tasks are found in the task lists of the kernel, `pxReadyTasksLists`, the delayed,
+/// pending and suspended lists. The number of priorities is read from `uxTopUsedPriority`,
+/// which recent versions of FreeRTOS always define, and older ones with `configUSE_TRACE_FACILITY`.
+///
+/// The saved registers of the tasks are reconstructed for the Cortex-M ports of FreeRTOS.
+#[derive(Debug)]
+pub struct FreeRtos {
+    /// The address of `pxCurrentTCB`.
+    current_tcb: u64,
+    /// The address of `uxTopUsedPriority`.
+    top_used_priority: u64,
+    /// The address of `pxReadyTasksLists`.
+    ready_lists: u64,
+    /// The addresses of the other task lists, with the state of their tasks.
+    lists: Vec<(u64, ThreadState)>,
+    layout: Layout,
+}
+
+/// The offsets of the members of the FreeRTOS structures which are needed to find the tasks.
+#[derive(Debug)]
+struct Layout {
+    list_size: u64,
+    list_number_of_items: u64,
+    list_end: u64,
+    mini_list_item_next: u64,
+    list_item_next: u64,
+    list_item_owner: u64,
+    tcb_priority: Option<u64>,
+    tcb_name: Option<u64>,
+}
+
+impl Layout {
+    /// Reads the layout from the debug information, with the defaults of a 32-bit target
+    /// without `configUSE_LIST_DATA_INTEGRITY_CHECK_BYTES` where it is not available.
+    fn from_debug_info(debug_info: &DebugInfo) -> Self {
+        let list_end = debug_info
+            .struct_member_offset("xLIST", "xListEnd")
+            .unwrap_or(8);
+
+        Self {
+            list_size: debug_info.struct_size("xLIST").unwrap_or(list_end + 12),
+            list_number_of_items: debug_info
+                .struct_member_offset("xLIST", "uxNumberOfItems")
+                .unwrap_or(0),
+            list_end,
+            mini_list_item_next: debug_info
+                .struct_member_offset("xMINI_LIST_ITEM", "pxNext")
+                .unwrap_or(4),
+            list_item_next: debug_info
+                .struct_member_offset("xLIST_ITEM", "pxNext")
+                .unwrap_or(4),
+            list_item_owner: debug_info
+                .struct_member_offset("xLIST_ITEM", "pvOwner")
+                .unwrap_or(12),
+            tcb_priority: debug_info.struct_member_offset("tskTaskControlBlock", "uxPriority"),
+            tcb_name: debug_info.struct_member_offset("tskTaskControlBlock", "pcTaskName"),
+        }
+    }
+}
+
+impl FreeRtos {
+    /// Detects FreeRTOS from the kernel variables in the debug information.
+    pub fn detect(debug_info: &DebugInfo) -> Option<Self> {
+        let current_tcb = debug_info.static_variable_address("pxCurrentTCB")?;
+        let ready_lists = debug_info.static_variable_address("pxReadyTasksLists")?;
+
+        let top_used_priority = match debug_info.static_variable_address("uxTopUsedPriority") {
+            Some(address) => address,
+            None => {
+                log::warn!(
+                    "FreeRTOS detected, but `uxTopUsedPriority` is missing, so its tasks can't be found. \
+                    Enable `configUSE_TRACE_FACILITY` or update FreeRTOS to get task awareness."
+                );
+                return None;
+            }
+        };
+
+        let lists = [
+            ("xDelayedTaskList1", ThreadState::Blocked),
+            ("xDelayedTaskList2", ThreadState::Blocked),
+            ("xPendingReadyList", ThreadState::Ready),
+            ("xSuspendedTaskList", ThreadState::Suspended),
+            ("xTasksWaitingTermination", ThreadState::Inactive),
+        ]
+        .into_iter()
+        .filter_map(|(name, state)| {
+            debug_info
+                .static_variable_address(name)
+                .map(|address| (address, state))
+        })
+        .collect();
+
+        Some(Self {
+            current_tcb,
+            top_used_priority,
+            ready_lists,
+            lists,
+            layout: Layout::from_debug_info(debug_info),
+        })
+    }
+
+    /// Returns the control blocks of the tasks in the list.
+    fn list_tasks(&self, core: &mut Core<'_>, list: u64) -> Result<Vec<u64>, DebugError> {
+        let number_of_items = core.read_word_32(list + self.layout.list_number_of_items)?;
+
+        let mut tasks = Vec::new();
+
+        let list_end = list + self.layout.list_end;
+        let mut item = core.read_word_32(list_end + self.layout.mini_list_item_next)? as u64;
+
+        for _ in 0..number_of_items.min(MAX_TASKS_PER_LIST) {
+            if item == list_end || item == 0 {
+                break;
+            }
+
+            tasks.push(core.read_word_32(item + self.layout.list_item_owner)? as u64);
+
+            item = core.read_word_32(item + self.layout.list_item_next)? as u64;
+        }
+
+        Ok(tasks)
+    }
+
+    /// Reconstructs the registers which the Cortex-M ports save when switching out a task.
+    fn task_registers(&self, core: &mut Core<'_>, tcb: u64) -> Result<Registers, DebugError> {
+        // `pxTopOfStack` is always the first member of the TCB.
+        let top_of_stack = core.read_word_32(tcb)? as u64;
+
+        let mut saved = [0u32; 10];
+        core.read_32(top_of_stack, &mut saved)?;
+
+        let mut callee_saved = [0u32; 8];
+
+        let (frame, extended_frame) = if is_exc_return(saved[8]) {
+            // ARM_CM4F and ARM_CM7: R4-R11 and EXC_RETURN, with S16-S31 below them.
+            callee_saved.copy_from_slice(&saved[..8]);
+            let fpu_registers = if has_extended_frame(saved[8]) { 64 } else { 0 };
+
+            (
+                top_of_stack + 36 + fpu_registers,
+                has_extended_frame(saved[8]),
+            )
+        } else if is_exc_return(saved[1]) {
+            // ARMv8-M ports: PSPLIM, EXC_RETURN and R4-R11, with S16-S31 below them.
+            callee_saved.copy_from_slice(&saved[2..]);
+            let fpu_registers = if has_extended_frame(saved[1]) { 64 } else { 0 };
+
+            (
+                top_of_stack + 40 + fpu_registers,
+                has_extended_frame(saved[1]),
+            )
+        } else {
+            // ARM_CM0 and ARM_CM3: R4-R11.
+            callee_saved.copy_from_slice(&saved[..8]);
+
+            (top_of_stack + 32, false)
+        };
+
+        cortex_m_thread_registers(core, &callee_saved, frame, extended_frame)
+    }
+}
+
+impl Rtos for FreeRtos {
+    fn name(&self) -> &'static str {
+        "FreeRTOS"
+    }
+
+    fn threads(
+        &self,
+        core: &mut Core<'_>,
+        _debug_info: &DebugInfo,
+    ) -> Result<Vec<RtosThread>, DebugError> {
+        let current_tcb = core.read_word_32(self.current_tcb)? as u64;
+        let top_used_priority =
+            (core.read_word_32(self.top_used_priority)? as u64).min(MAX_PRIORITIES - 1);
+
+        let mut tasks: Vec<(u64, ThreadState)> = Vec::new();
+
+        for priority in 0..=top_used_priority {
+            let list = self.ready_lists + priority * self.layout.list_size;
+            for tcb in self.list_tasks(core, list)? {
+                tasks.push((tcb, ThreadState::Ready));
+            }
+        }
+
+        for &(list, state) in &self.lists {
+            for tcb in self.list_tasks(core, list)? {
+                tasks.push((tcb, state));
+            }
+        }
+
+        let is_cortex_m = core.core_type().is_cortex_m();
+
+        let mut threads: Vec<RtosThread> = Vec::with_capacity(tasks.len());
+
+        for (tcb, state) in tasks {
+            if threads.iter().any(|thread| thread.id == tcb) {
+                continue;
+            }
+
+            let name = match self.layout.tcb_name {
+                Some(offset) => read_c_string(core, tcb + offset, DEFAULT_TASK_NAME_LEN)?,
+                None => format!("{:#010x}", tcb),
+            };
+
+            let priority = match self.layout.tcb_priority {
+                Some(offset) => Some(core.read_word_32(tcb + offset)?.into()),
+                None => None,
+            };
+
+            let (state, registers) = if tcb == current_tcb {
+                (ThreadState::Running, Some(Registers::from_core(core)))
+            } else if is_cortex_m {
+                (state, Some(self.task_registers(core, tcb)?))
+            } else {
+                (state, None)
+            };
+
+            threads.push(RtosThread {
+                id: tcb,
+                name,
+                state,
+                priority,
+                registers,
+            });
+        }
+
+        Ok(threads)
+    }
+}
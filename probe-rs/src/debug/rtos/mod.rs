@@ -0,0 +1,211 @@
+//! Awareness of the threads of real-time operating systems and async executors.
+//!
+//! Without it, only the stack of the code which is currently running can be inspected.
+//! An [`Rtos`] implementation finds the threads in the memory of the target, using the debug
+//! information to locate the kernel structures, and reconstructs the registers which were saved
+//! when a thread was switched out. The stack of each thread can then be unwound with
+//! [`RtosThread::unwind`].
+//!
+//! The supported RTOSes are detected with [`detect`]:
+//! - [FreeRTOS](freertos::FreeRtos)
+//! - [Zephyr](zephyr::Zephyr)
+//! - [RTIC](rtic::Rtic)
+//! - [Embassy](embassy::Embassy)
+//!
+//! Other RTOSes can be supported by implementing the [`Rtos`] trait.
+
+/// Awareness of Embassy tasks.
+pub mod embassy;
+/// Awareness of FreeRTOS tasks.
+pub mod freertos;
+/// Awareness of RTIC tasks.
+pub mod rtic;
+/// Awareness of Zephyr threads.
+pub mod zephyr;
+
+use super::{DebugError, DebugInfo, Registers, StackFrame};
+use crate::{core::Core, MemoryInterface};
+use std::collections::HashMap;
+
+/// The threads of an RTOS running on the target.
+pub trait Rtos {
+    /// The name of the RTOS, e.g. `FreeRTOS`.
+    fn name(&self) -> &'static str;
+
+    /// Reads the threads of the RTOS from the target.
+    ///
+    /// The core has to be halted, otherwise the kernel structures may change while they are read.
+    fn threads(
+        &self,
+        core: &mut Core<'_>,
+        debug_info: &DebugInfo,
+    ) -> Result<Vec<RtosThread>, DebugError>;
+}
+
+/// Detects which of the supported RTOSes the program uses, from its debug information.
+///
+/// Returns `None` if the program uses none of them, or if the debug information lacks
+/// the kernel structures which are needed to find the threads.
+pub fn detect(debug_info: &DebugInfo) -> Option<Box<dyn Rtos>> {
+    if let Some(rtos) = freertos::FreeRtos::detect(debug_info) {
+        return Some(Box::new(rtos));
+    }
+
+    if let Some(rtos) = zephyr::Zephyr::detect(debug_info) {
+        return Some(Box::new(rtos));
+    }
+
+    if let Some(rtos) = rtic::Rtic::detect(debug_info) {
+        return Some(Box::new(rtos));
+    }
+
+    if let Some(rtos) = embassy::Embassy::detect(debug_info) {
+        return Some(Box::new(rtos));
+    }
+
+    None
+}
+
+/// A thread, or task, of an RTOS.
+#[derive(Debug, Clone)]
+pub struct RtosThread {
+    /// The identifier of the thread, usually the address of its control block.
+    pub id: u64,
+    /// The name of the thread.
+    pub name: String,
+    /// The scheduling state of the thread.
+    pub state: ThreadState,
+    /// The priority of the thread, with the meaning the RTOS gives it.
+    pub priority: Option<i64>,
+    /// The registers of the thread.
+    ///
+    /// For the running thread, these are the registers of the core. For the other threads, they
+    /// are the registers saved by the last context switch, which is only supported on Cortex-M cores.
+    /// Tasks of async executors don't have registers of their own, as they are polled on the
+    /// stack of the executor.
+    pub registers: Option<Registers>,
+}
+
+impl RtosThread {
+    /// Returns `true` if the thread is currently running on the core.
+    pub fn is_running(&self) -> bool {
+        self.state == ThreadState::Running
+    }
+
+    /// Unwinds the stack of the thread, starting with its registers.
+    ///
+    /// Returns no frames if the registers of the thread are not known.
+    pub fn unwind(
+        &self,
+        core: &mut Core<'_>,
+        debug_info: &DebugInfo,
+    ) -> Result<Vec<StackFrame>, crate::Error> {
+        match &self.registers {
+            Some(registers) => debug_info.unwind_registers(core, registers.clone()),
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+/// The scheduling state of an [`RtosThread`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThreadState {
+    /// The thread is running on the core.
+    Running,
+    /// The thread is ready to run, or was preempted by a thread with a higher priority.
+    Ready,
+    /// The thread waits for an event, e.g. a timeout, a semaphore or a message.
+    Blocked,
+    /// The thread was suspended explicitly, and will not run until it is resumed.
+    Suspended,
+    /// The thread has not been started yet, or has exited.
+    Inactive,
+    /// The state is not known.
+    Unknown,
+}
+
+/// Returns a register set without any values, for the architecture of the core.
+fn empty_registers(core: &mut Core<'_>) -> Registers {
+    let register_description = core.registers();
+
+    Registers {
+        register_description,
+        values: HashMap::new(),
+        architecture: core.architecture(),
+        address_size: register_description.program_counter().size_in_bytes(),
+    }
+}
+
+/// Returns `true` if the value is an `EXC_RETURN` value of a Cortex-M core.
+fn is_exc_return(value: u32) -> bool {
+    value >> 24 == 0xff
+}
+
+/// Returns `true` if the `EXC_RETURN` value indicates an exception frame with floating point registers.
+fn has_extended_frame(exc_return: u32) -> bool {
+    exc_return & (1 << 4) == 0
+}
+
+/// Reconstructs the registers of a Cortex-M thread which was switched out in an exception handler,
+/// e.g. in PendSV.
+///
+/// `callee_saved` are the values of R4 to R11, as saved by the context switch, and `frame` is the
+/// address of the exception frame which the core pushed on the stack of the thread.
+fn cortex_m_thread_registers(
+    core: &mut Core<'_>,
+    callee_saved: &[u32; 8],
+    frame: u64,
+    extended_frame: bool,
+) -> Result<Registers, DebugError> {
+    let mut stacked = [0u32; 8];
+    core.read_32(frame, &mut stacked)?;
+
+    let [r0, r1, r2, r3, r12, lr, pc, xpsr] = stacked;
+
+    // The extended frame contains S0-S15, FPSCR and a reserved word.
+    let mut frame_size = if extended_frame { 0x68 } else { 0x20 };
+    // The core aligned the stack to 8 bytes before pushing the frame.
+    if xpsr & (1 << 9) != 0 {
+        frame_size += 4;
+    }
+
+    let mut registers = empty_registers(core);
+
+    for (number, value) in [r0, r1, r2, r3].into_iter().enumerate() {
+        registers.set_by_dwarf_register_number(number as u32, Some(value.into()));
+    }
+    for (number, value) in callee_saved.iter().enumerate() {
+        registers.set_by_dwarf_register_number(number as u32 + 4, Some((*value).into()));
+    }
+    registers.set_by_dwarf_register_number(12, Some(r12.into()));
+    registers.set_stack_pointer(Some(frame + frame_size));
+    registers.set_return_address(Some(lr.into()));
+    registers.set_program_counter(Some(pc.into()));
+
+    Ok(registers)
+}
+
+/// Reads a NUL terminated string of at most `max_len` bytes.
+fn read_c_string(core: &mut Core<'_>, address: u64, max_len: usize) -> Result<String, DebugError> {
+    let mut buffer = vec![0u8; max_len];
+    core.read_8(address, &mut buffer)?;
+
+    let len = buffer.iter().position(|&b| b == 0).unwrap_or(max_len);
+
+    Ok(String::from_utf8_lossy(&buffer[..len]).into_owned())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{has_extended_frame, is_exc_return};
+
+    #[test]
+    fn exc_return_values() {
+        assert!(is_exc_return(0xffff_fffd));
+        assert!(is_exc_return(0xffff_ffed));
+        assert!(!is_exc_return(0x2000_1000));
+
+        assert!(!has_extended_frame(0xffff_fffd));
+        assert!(has_extended_frame(0xffff_ffed));
+    }
+}
@@ -0,0 +1,155 @@
+use super::{Rtos, RtosThread, ThreadState};
+use crate::architecture::arm::core::register;
+use crate::debug::{DebugError, DebugInfo, Registers};
+use crate::{core::Core, CoreType, MemoryInterface};
+
+/// Vector Table Offset Register
+const VTOR: u64 = 0xE000_ED08;
+
+/// System Handler Control and State Register
+const SHCSR: u64 = 0xE000_ED24;
+
+/// System Handler Priority Registers, starting with the one of exception 4.
+const SHPR: u64 = 0xE000_ED18;
+
+/// Interrupt Active Bit Registers of the NVIC
+const NVIC_IABR: u64 = 0xE000_E300;
+
+/// Interrupt Priority Registers of the NVIC
+const NVIC_IPR: u64 = 0xE000_E400;
+
+/// The number of `NVIC_IABR` registers, for up to 496 interrupts.
+const NVIC_IABR_COUNT: usize = 16;
+
+/// The exceptions which RTIC tasks can be bound to, with their active bit in the SHCSR.
+const SYSTEM_HANDLERS: [(u32, u32); 3] = [(11, 7), (14, 10), (15, 11)];
+
+/// Awareness of RTIC tasks.
+///
+/// RTIC runs its tasks in interrupt handlers, which preempt each other on the main stack, so
+/// the tasks have no control blocks. Instead, each active exception is shown as a task, named
+/// after its handler, together with the thread mode code (`idle`). Only the task which is
+/// running has registers of its own. The registers of the preempted tasks are on the main
+/// stack, below the exception frames in the backtrace of the running task.
+#[derive(Debug)]
+pub struct Rtic {}
+
+impl Rtic {
+    /// Detects RTIC from the `rtic` crate in the debug information.
+    pub fn detect(debug_info: &DebugInfo) -> Option<Self> {
+        debug_info.has_namespace("rtic").then(|| Self {})
+    }
+
+    /// Returns the numbers of the active exceptions.
+    fn active_exceptions(&self, core: &mut Core<'_>) -> Result<Vec<u32>, DebugError> {
+        let mut active = Vec::new();
+
+        // ARMv6-M has neither the SHCSR active bits nor the NVIC_IABR registers.
+        if core.core_type() == CoreType::Armv6m {
+            return Ok(active);
+        }
+
+        let shcsr = core.read_word_32(SHCSR)?;
+        for (exception, bit) in SYSTEM_HANDLERS {
+            if shcsr & (1 << bit) != 0 {
+                active.push(exception);
+            }
+        }
+
+        let mut iabr = [0u32; NVIC_IABR_COUNT];
+        core.read_32(NVIC_IABR, &mut iabr)?;
+
+        for (index, bits) in iabr.iter().enumerate() {
+            for bit in 0..32 {
+                if bits & (1 << bit) != 0 {
+                    active.push(16 + 32 * index as u32 + bit);
+                }
+            }
+        }
+
+        Ok(active)
+    }
+
+    /// Returns the name of the handler of the exception, from the vector table.
+    fn handler_name(
+        &self,
+        core: &mut Core<'_>,
+        debug_info: &DebugInfo,
+        exception: u32,
+    ) -> Result<String, DebugError> {
+        let vector_table = core.read_word_32(VTOR)? as u64;
+        let handler = core.read_word_32(vector_table + 4 * exception as u64)? & !1;
+
+        Ok(debug_info
+            .function_name(handler.into(), false)?
+            .unwrap_or_else(|| format!("exception {}", exception)))
+    }
+
+    /// Returns the raw priority of the exception, where lower values mean higher priorities.
+    fn priority(&self, core: &mut Core<'_>, exception: u32) -> Result<Option<i64>, DebugError> {
+        let address = match exception {
+            0..=3 => return Ok(None),
+            4..=15 => SHPR + exception as u64 - 4,
+            _ => NVIC_IPR + exception as u64 - 16,
+        };
+
+        // ARMv6-M only supports word accesses to these registers.
+        let priorities = core.read_word_32(address & !0b11)?;
+
+        Ok(Some(((priorities >> (8 * (address & 0b11))) & 0xff).into()))
+    }
+}
+
+impl Rtos for Rtic {
+    fn name(&self) -> &'static str {
+        "RTIC"
+    }
+
+    fn threads(
+        &self,
+        core: &mut Core<'_>,
+        debug_info: &DebugInfo,
+    ) -> Result<Vec<RtosThread>, DebugError> {
+        if !core.core_type().is_cortex_m() {
+            return Ok(Vec::new());
+        }
+
+        let xpsr: u32 = core.read_core_reg(register::XPSR.id)?;
+        let current_exception = xpsr & 0x1ff;
+
+        let mut exceptions = self.active_exceptions(core)?;
+        if current_exception != 0 && !exceptions.contains(&current_exception) {
+            exceptions.push(current_exception);
+        }
+
+        let mut threads = vec![RtosThread {
+            id: 0,
+            name: "idle".to_string(),
+            state: if current_exception == 0 {
+                ThreadState::Running
+            } else {
+                ThreadState::Ready
+            },
+            priority: None,
+            registers: (current_exception == 0).then(|| Registers::from_core(core)),
+        }];
+
+        for exception in exceptions {
+            let running = exception == current_exception;
+
+            threads.push(RtosThread {
+                id: exception.into(),
+                name: self.handler_name(core, debug_info, exception)?,
+                state: if running {
+                    ThreadState::Running
+                } else {
+                    ThreadState::Ready
+                },
+                priority: self.priority(core, exception)?,
+                registers: running.then(|| Registers::from_core(core)),
+            });
+        }
+
+        Ok(threads)
+    }
+}
@@ -0,0 +1,216 @@
+use super::{
+    cortex_m_thread_registers, has_extended_frame, read_c_string, Rtos, RtosThread, ThreadState,
+};
+use crate::debug::{DebugError, DebugInfo, Registers};
+use crate::{core::Core, MemoryInterface};
+
+/// The maximum number of threads read, in case the thread list is corrupted.
+const MAX_THREADS: usize = 256;
+
+/// The default of `CONFIG_THREAD_MAX_NAME_LEN`.
+const THREAD_NAME_LEN: usize = 32;
+
+// The bits of `_thread_base.thread_state`.
+const THREAD_PENDING: u8 = 1 << 1;
+const THREAD_PRESTART: u8 = 1 << 2;
+const THREAD_DEAD: u8 = 1 << 3;
+const THREAD_SUSPENDED: u8 = 1 << 4;
+const THREAD_QUEUED: u8 = 1 << 7;
+
+/// Awareness of Zephyr threads.
+///
+/// The threads are found in the thread list of the kernel, `_kernel.threads`, which requires
+/// `CONFIG_THREAD_MONITOR`. The names of the threads are available with `CONFIG_THREAD_NAME`.
+///
+/// The saved registers of the threads are reconstructed on Cortex-M cores.
+#[derive(Debug)]
+pub struct Zephyr {
+    /// The address of `_kernel`.
+    kernel: u64,
+    layout: Layout,
+}
+
+/// The offsets of the members of the kernel structures which are needed to find the threads.
+#[derive(Debug)]
+struct Layout {
+    /// `_kernel.cpus[0].current`
+    current: u64,
+    /// `_kernel.threads`
+    threads: u64,
+    /// `k_thread.next_thread`
+    next_thread: u64,
+    /// `k_thread.base.thread_state`
+    thread_state: u64,
+    /// `k_thread.base.prio`
+    prio: Option<u64>,
+    /// `k_thread.callee_saved.v1`
+    callee_saved: u64,
+    /// `k_thread.callee_saved.psp`
+    psp: u64,
+    /// `k_thread.name`
+    name: Option<u64>,
+    /// `k_thread.arch.mode_exc_return`
+    mode_exc_return: Option<u64>,
+}
+
+impl Layout {
+    fn from_debug_info(debug_info: &DebugInfo) -> Option<Self> {
+        let member = |struct_name, member| debug_info.struct_member_offset(struct_name, member);
+
+        let threads = match member("z_kernel", "threads") {
+            Some(offset) => offset,
+            None => {
+                log::warn!(
+                    "Zephyr detected, but `_kernel.threads` is missing, so its threads can't be found. \
+                    Enable `CONFIG_THREAD_MONITOR` to get thread awareness."
+                );
+                return None;
+            }
+        };
+
+        let base = member("k_thread", "base")?;
+        let callee_saved = member("k_thread", "callee_saved")?;
+        let arch = member("k_thread", "arch");
+
+        Some(Self {
+            current: member("z_kernel", "cpus")? + member("_cpu", "current")?,
+            threads,
+            next_thread: member("k_thread", "next_thread")?,
+            thread_state: base + member("_thread_base", "thread_state")?,
+            prio: member("_thread_base", "prio").map(|prio| base + prio),
+            callee_saved: callee_saved + member("_callee_saved", "v1")?,
+            psp: callee_saved + member("_callee_saved", "psp")?,
+            name: member("k_thread", "name"),
+            mode_exc_return: arch
+                .zip(member("_thread_arch", "mode_exc_return"))
+                .map(|(arch, mode_exc_return)| arch + mode_exc_return),
+        })
+    }
+}
+
+impl Zephyr {
+    /// Detects Zephyr from the kernel structure `_kernel` in the debug information.
+    pub fn detect(debug_info: &DebugInfo) -> Option<Self> {
+        let kernel = debug_info.static_variable_address("_kernel")?;
+
+        Some(Self {
+            kernel,
+            layout: Layout::from_debug_info(debug_info)?,
+        })
+    }
+
+    /// Reconstructs the registers which the Cortex-M port saves when switching out a thread.
+    fn thread_registers(&self, core: &mut Core<'_>, thread: u64) -> Result<Registers, DebugError> {
+        let mut callee_saved = [0u32; 8];
+        core.read_32(thread + self.layout.callee_saved, &mut callee_saved)?;
+
+        let psp = core.read_word_32(thread + self.layout.psp)? as u64;
+
+        // Only the low byte of EXC_RETURN is saved.
+        let extended_frame = match self.layout.mode_exc_return {
+            Some(offset) => has_extended_frame(core.read_word_8(thread + offset)?.into()),
+            None => false,
+        };
+
+        cortex_m_thread_registers(core, &callee_saved, psp, extended_frame)
+    }
+}
+
+impl Rtos for Zephyr {
+    fn name(&self) -> &'static str {
+        "Zephyr"
+    }
+
+    fn threads(
+        &self,
+        core: &mut Core<'_>,
+        _debug_info: &DebugInfo,
+    ) -> Result<Vec<RtosThread>, DebugError> {
+        let current = core.read_word_32(self.kernel + self.layout.current)? as u64;
+
+        let is_cortex_m = core.core_type().is_cortex_m();
+
+        let mut threads: Vec<RtosThread> = Vec::new();
+
+        let mut thread = core.read_word_32(self.kernel + self.layout.threads)? as u64;
+
+        while thread != 0 && threads.len() < MAX_THREADS {
+            if threads.iter().any(|known| known.id == thread) {
+                log::warn!("The Zephyr thread list contains a loop at {:#010x}", thread);
+                break;
+            }
+
+            let thread_state = core.read_word_8(thread + self.layout.thread_state)?;
+
+            let name = match self.layout.name {
+                Some(offset) => read_c_string(core, thread + offset, THREAD_NAME_LEN)?,
+                None => String::new(),
+            };
+            let name = if name.is_empty() {
+                format!("{:#010x}", thread)
+            } else {
+                name
+            };
+
+            let priority = match self.layout.prio {
+                Some(offset) => Some((core.read_word_8(thread + offset)? as i8).into()),
+                None => None,
+            };
+
+            let (state, registers) = if thread == current {
+                (ThreadState::Running, Some(Registers::from_core(core)))
+            } else if is_cortex_m {
+                (
+                    thread_state_from_bits(thread_state),
+                    Some(self.thread_registers(core, thread)?),
+                )
+            } else {
+                (thread_state_from_bits(thread_state), None)
+            };
+
+            threads.push(RtosThread {
+                id: thread,
+                name,
+                state,
+                priority,
+                registers,
+            });
+
+            thread = core.read_word_32(thread + self.layout.next_thread)? as u64;
+        }
+
+        Ok(threads)
+    }
+}
+
+fn thread_state_from_bits(thread_state: u8) -> ThreadState {
+    if thread_state & (THREAD_DEAD | THREAD_PRESTART) != 0 {
+        ThreadState::Inactive
+    } else if thread_state & THREAD_SUSPENDED != 0 {
+        ThreadState::Suspended
+    } else if thread_state & THREAD_PENDING != 0 {
+        ThreadState::Blocked
+    } else if thread_state & THREAD_QUEUED != 0 {
+        ThreadState::Ready
+    } else {
+        // Sleeping threads are in none of the queues.
+        ThreadState::Blocked
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn thread_states() {
+        assert_eq!(thread_state_from_bits(THREAD_QUEUED), ThreadState::Ready);
+        assert_eq!(thread_state_from_bits(THREAD_PENDING), ThreadState::Blocked);
+        assert_eq!(thread_state_from_bits(0), ThreadState::Blocked);
+        assert_eq!(
+            thread_state_from_bits(THREAD_SUSPENDED | THREAD_QUEUED),
+            ThreadState::Suspended
+        );
+        assert_eq!(thread_state_from_bits(THREAD_DEAD), ThreadState::Inactive);
+    }
+}
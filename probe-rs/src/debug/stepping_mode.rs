@@ -49,13 +49,25 @@ impl SteppingMode {
             }
         };
 
-        // First deal with the two special cases.
+        // First deal with the special cases.
         match self {
             SteppingMode::StepInstruction => {
                 program_counter = core.step()?.pc;
                 core_status = core.status()?;
                 return Ok((core_status, program_counter));
             }
+            SteppingMode::OverStatement | SteppingMode::IntoStatement
+                if debug_info.get_source_location(program_counter).is_none() =>
+            {
+                // Code without debug information has no statements, so the best we can do is to step a single instruction.
+                log::debug!(
+                    "No source location @{:#010X}, stepping a single instruction.",
+                    program_counter
+                );
+                program_counter = core.step()?.pc;
+                core_status = core.status()?;
+                return Ok((core_status, program_counter));
+            }
             SteppingMode::IntoStatement => {
                 // Step a single instruction, then proceed to the next step.
                 program_counter = core.step()?.pc;
@@ -76,6 +76,8 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
 
     /// Check if the function located at the given offset contains inlined functions at the
     /// given address.
+    ///
+    /// The result is ordered from the outermost to the innermost inlined function.
     pub(crate) fn find_inlined_functions(
         &self,
         address: u64,
@@ -91,7 +93,10 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
             while let Ok(Some((depth, current))) = cursor.next_dfs() {
                 current_depth += depth;
 
-                if current_depth < abort_depth {
+                // Stop at the end of the function, or after the last inlined function
+                // which contains the address.
+                if current_depth < abort_depth || (current_depth <= 0 && current.offset() != offset)
+                {
                     break;
                 }
 
@@ -106,34 +111,38 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
                             // they will be children of the current function.
                             abort_depth = current_depth;
 
-                            // Find the abstract definition
-                            if let Ok(Some(abstract_origin)) =
-                                current.attr(gimli::DW_AT_abstract_origin)
-                            {
-                                match abstract_origin.value() {
-                                    gimli::AttributeValue::UnitRef(unit_ref) => {
-                                        if let Ok(abstract_die) = self.unit.entry(unit_ref) {
-                                            if let Some(mut die) = FunctionDie::new_inlined(
-                                                current.clone(),
-                                                abstract_die.clone(),
-                                                self,
-                                            ) {
-                                                die.low_pc = ranges.begin;
-                                                die.high_pc = ranges.end;
-
-                                                functions.push(die);
-                                            }
-                                        }
+                            // Find the abstract definition. If it is in another unit, e.g. after LTO,
+                            // the function name is resolved from there, but the other attributes are
+                            // only taken from the concrete instance.
+                            let abstract_die =
+                                match current.attr_value(gimli::DW_AT_abstract_origin) {
+                                    Ok(Some(gimli::AttributeValue::UnitRef(unit_ref))) => {
+                                        self.unit.entry(unit_ref).ok()
                                     }
-                                    other_value => log::warn!(
-                                        "Unsupported DW_AT_abstract_origin value: {:?}",
-                                        other_value
-                                    ),
-                                }
-                            } else {
-                                log::warn!("No abstract origin for inlined function, skipping.");
-                                return Ok(vec![]);
+                                    Ok(Some(gimli::AttributeValue::DebugInfoRef(_))) => None,
+                                    Ok(Some(other_value)) => {
+                                        log::warn!(
+                                            "Unsupported DW_AT_abstract_origin value: {:?}",
+                                            other_value
+                                        );
+                                        None
+                                    }
+                                    _ => {
+                                        log::warn!("No abstract origin for inlined function.");
+                                        None
+                                    }
+                                };
+
+                            if let Some(mut die) =
+                                FunctionDie::new_inlined(current.clone(), abstract_die, self)
+                            {
+                                die.low_pc = ranges.begin;
+                                die.high_pc = ranges.end;
+
+                                functions.push(die);
                             }
+
+                            break;
                         }
                     }
                 }
@@ -1469,6 +1478,19 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
                 Location::Register { register } => {
                     child_variable.memory_location =
                         VariableLocation::Register(register.0 as usize);
+
+                    // The value is taken from the registers of the frame, which for frames other than the
+                    // first one differ from the registers of the core.
+                    match stack_frame_registers
+                        .get_value_by_dwarf_register_number(register.0 as u32)
+                    {
+                        Some(value) => {
+                            child_variable.set_value(VariableValue::Valid(value.to_string()))
+                        }
+                        None => child_variable.set_value(VariableValue::Error(
+                            "<register value not available>".to_string(),
+                        )),
+                    }
                 }
                 l => {
                     return Err(DebugError::Other(anyhow::anyhow!(
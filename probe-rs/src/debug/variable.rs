@@ -376,19 +376,17 @@ impl Variable {
         }
     }
 
-    /// Call the underlaying [Value::update_value] trait to convert the [String] value into the appropriate memory format and update the target memory with the new value.
-    /// Currently this only works for base data types. There is no provision in the MS DAP API to catch this client side, so we can only respond with a 'gentle' error message if the user attemtps unsupported data types.
+    /// Call the underlaying [Value::update_value] trait to convert the [String] value into the appropriate memory format and update the target memory, or register, with the new value.
+    /// Currently this only works for base data types and pointers, where the new value of a pointer is the address it points to. There is no provision in the MS DAP API to catch this client side, so we can only respond with a 'gentle' error message if the user attemtps unsupported data types.
+    /// Integers can also be given in hexadecimal, binary or octal, with a `0x`, `0b` or `0o` prefix.
+    ///
+    /// Variables which are stored in registers are written to the registers of the core, so they can only be updated in the frame which is currently executing.
     pub fn update_value(
         &self,
         core: &mut Core,
         variable_cache: &mut variable_cache::VariableCache,
         new_value: String,
     ) -> Result<String, DebugError> {
-        let variable_name = if let VariableName::Named(variable_name) = &self.name {
-            variable_name.clone()
-        } else {
-            String::new()
-        };
         let updated_value = if !self.is_valid()
                 // Need a valid type
                 || self.type_name == VariableType::Unknown
@@ -399,9 +397,6 @@ impl Variable {
             return Err(anyhow!(
                 "Cannot update variable: {:?}, with supplied information (value={:?}, type={:?}, memory location={:#010x?}).",
                 self.name, self.value, self.type_name, self.memory_location).into());
-        } else if variable_name.starts_with('*') {
-            // Writing the values of pointers is a bit more complex, and not currently supported.
-            return  Err(anyhow!("Please only update variables with a base data type. Updating pointer variable types is not yet supported.").into());
         } else {
             // We have everything we need to update the variable value.
             let update_result = match &self.type_name {
@@ -427,8 +422,23 @@ impl Variable {
                     other.to_string()
                 ))),
                 },
+                VariableType::Pointer(_) => {
+                    // The value of a pointer is the address it points to.
+                    let address_size = core.registers().program_counter().size_in_bytes();
+                    parse_integer::<u64>(new_value.as_str())
+                        .map_err(|error| {
+                            DebugError::Other(anyhow::anyhow!(
+                                "Invalid address: {:?}. {:?}",
+                                new_value,
+                                error
+                            ))
+                        })
+                        .and_then(|address| {
+                            self.write_bytes(core, &address.to_le_bytes()[..address_size])
+                        })
+                }
                 other => Err(DebugError::Other(anyhow::anyhow!(
-                    "Unsupported variable type {:?}. Only base variables can be updated.",
+                    "Unsupported variable type {:?}. Only base variables and pointers can be updated.",
                     other
                 ))),
             };
@@ -457,6 +467,45 @@ impl Variable {
         Ok(updated_value)
     }
 
+    /// Write the new value of the variable, in its little endian memory representation, to the
+    /// memory or the register where the variable is stored.
+    ///
+    /// Variables in registers are written to the registers of the core, which is only correct for
+    /// the variables of the frame which is currently executing.
+    fn write_bytes(&self, core: &mut Core<'_>, data: &[u8]) -> Result<(), DebugError> {
+        match &self.memory_location {
+            VariableLocation::Address(address) => Ok(core.write_8(*address, data)?),
+            VariableLocation::Register(register_number) => {
+                let description = core
+                    .registers()
+                    .get_platform_register(*register_number)
+                    .ok_or_else(|| {
+                        DebugError::Other(anyhow!("Unknown register number {}", register_number))
+                    })?;
+
+                let mut value = [0u8; 8];
+                if data.len() > value.len() {
+                    return Err(DebugError::Other(anyhow!(
+                        "A value of {} bytes does not fit into the register {}",
+                        data.len(),
+                        description.name()
+                    )));
+                }
+                value[..data.len()].copy_from_slice(data);
+
+                Ok(registers::write_core_register(
+                    core,
+                    description,
+                    u64::from_le_bytes(value),
+                )?)
+            }
+            other => Err(DebugError::Other(anyhow!(
+                "Variable can not be written at location {:?}",
+                other
+            ))),
+        }
+    }
+
     /// Implementing get_value(), because Variable.value has to be private (a requirement of updating the value without overriding earlier values ... see set_value()).
     pub fn get_value(&self, variable_cache: &variable_cache::VariableCache) -> String {
         // Allow for chained `if let` without complaining
@@ -925,6 +974,28 @@ impl Variable {
 }
 
 /// Traits and Impl's to read from, and write to, memory value based on Variable::typ and Variable::location.
+/// Parse an integer in decimal, or in hexadecimal, binary or octal with a `0x`, `0b` or `0o` prefix.
+/// Underscores between the digits are ignored, as in Rust.
+pub(crate) fn parse_integer<T>(value: &str) -> Result<T, T::FromStrRadixErr>
+where
+    T: num_traits::Num,
+{
+    let value = value.trim().replace('_', "");
+    let (sign, digits) = match value.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", value.as_str()),
+    };
+
+    let (radix, digits) = match digits.get(..2) {
+        Some("0x" | "0X") => (16, &digits[2..]),
+        Some("0b" | "0B") => (2, &digits[2..]),
+        Some("0o" | "0O") => (8, &digits[2..]),
+        _ => (10, digits),
+    };
+
+    T::from_str_radix(&format!("{}{}", sign, digits), radix)
+}
+
 trait Value {
     /// The MS DAP protocol passes the value as a string, so this trait is here to provide the memory read logic before returning it as a string.
     fn get_value(
@@ -961,17 +1032,16 @@ impl Value for bool {
         core: &mut Core<'_>,
         new_value: &str,
     ) -> Result<(), DebugError> {
-        core.write_word_8(
-            variable.memory_location.memory_address()?,
-            <bool as FromStr>::from_str(new_value).map_err(|error| {
+        variable.write_bytes(
+            core,
+            &[<bool as FromStr>::from_str(new_value).map_err(|error| {
                 DebugError::Other(anyhow::anyhow!(
                     "Invalid data conversion from value: {:?}. {:?}",
                     new_value,
                     error
                 ))
-            })? as u8,
+            })? as u8],
         )
-        .map_err(|error| DebugError::Other(anyhow::anyhow!("{:?}", error)))
     }
 }
 impl Value for char {
@@ -993,17 +1063,17 @@ impl Value for char {
         core: &mut Core<'_>,
         new_value: &str,
     ) -> Result<(), DebugError> {
-        core.write_word_32(
-            variable.memory_location.memory_address()?,
-            <char as FromStr>::from_str(new_value).map_err(|error| {
+        variable.write_bytes(
+            core,
+            &(<char as FromStr>::from_str(new_value).map_err(|error| {
                 DebugError::Other(anyhow::anyhow!(
                     "Invalid data conversion from value: {:?}. {:?}",
                     new_value,
                     error
                 ))
-            })? as u32,
+            })? as u32)
+                .to_le_bytes(),
         )
-        .map_err(|error| DebugError::Other(anyhow::anyhow!("{:?}", error)))
     }
 }
 impl Value for String {
@@ -1099,17 +1169,16 @@ impl Value for i8 {
         core: &mut Core<'_>,
         new_value: &str,
     ) -> Result<(), DebugError> {
-        core.write_word_8(
-            variable.memory_location.memory_address()?,
-            <i8 as FromStr>::from_str(new_value).map_err(|error| {
+        variable.write_bytes(
+            core,
+            &[parse_integer::<i8>(new_value).map_err(|error| {
                 DebugError::Other(anyhow::anyhow!(
                     "Invalid data conversion from value: {:?}. {:?}",
                     new_value,
                     error
                 ))
-            })? as u8,
+            })? as u8],
         )
-        .map_err(|error| DebugError::Other(anyhow::anyhow!("{:?}", error)))
     }
 }
 impl Value for i16 {
@@ -1129,15 +1198,14 @@ impl Value for i16 {
         core: &mut Core<'_>,
         new_value: &str,
     ) -> Result<(), DebugError> {
-        let buff = i16::to_le_bytes(<i16 as FromStr>::from_str(new_value).map_err(|error| {
+        let buff = i16::to_le_bytes(parse_integer::<i16>(new_value).map_err(|error| {
             DebugError::Other(anyhow::anyhow!(
                 "Invalid data conversion from value: {:?}. {:?}",
                 new_value,
                 error
             ))
         })?);
-        core.write_8(variable.memory_location.memory_address()?, &buff)
-            .map_err(|error| DebugError::Other(anyhow::anyhow!("{:?}", error)))
+        variable.write_bytes(core, &buff)
     }
 }
 impl Value for i32 {
@@ -1157,15 +1225,14 @@ impl Value for i32 {
         core: &mut Core<'_>,
         new_value: &str,
     ) -> Result<(), DebugError> {
-        let buff = i32::to_le_bytes(<i32 as FromStr>::from_str(new_value).map_err(|error| {
+        let buff = i32::to_le_bytes(parse_integer::<i32>(new_value).map_err(|error| {
             DebugError::Other(anyhow::anyhow!(
                 "Invalid data conversion from value: {:?}. {:?}",
                 new_value,
                 error
             ))
         })?);
-        core.write_8(variable.memory_location.memory_address()?, &buff)
-            .map_err(|error| DebugError::Other(anyhow::anyhow!("{:?}", error)))
+        variable.write_bytes(core, &buff)
     }
 }
 impl Value for i64 {
@@ -1185,15 +1252,14 @@ impl Value for i64 {
         core: &mut Core<'_>,
         new_value: &str,
     ) -> Result<(), DebugError> {
-        let buff = i64::to_le_bytes(<i64 as FromStr>::from_str(new_value).map_err(|error| {
+        let buff = i64::to_le_bytes(parse_integer::<i64>(new_value).map_err(|error| {
             DebugError::Other(anyhow::anyhow!(
                 "Invalid data conversion from value: {:?}. {:?}",
                 new_value,
                 error
             ))
         })?);
-        core.write_8(variable.memory_location.memory_address()?, &buff)
-            .map_err(|error| DebugError::Other(anyhow::anyhow!("{:?}", error)))
+        variable.write_bytes(core, &buff)
     }
 }
 impl Value for i128 {
@@ -1213,15 +1279,14 @@ impl Value for i128 {
         core: &mut Core<'_>,
         new_value: &str,
     ) -> Result<(), DebugError> {
-        let buff = i128::to_le_bytes(<i128 as FromStr>::from_str(new_value).map_err(|error| {
+        let buff = i128::to_le_bytes(parse_integer::<i128>(new_value).map_err(|error| {
             DebugError::Other(anyhow::anyhow!(
                 "Invalid data conversion from value: {:?}. {:?}",
                 new_value,
                 error
             ))
         })?);
-        core.write_8(variable.memory_location.memory_address()?, &buff)
-            .map_err(|error| DebugError::Other(anyhow::anyhow!("{:?}", error)))
+        variable.write_bytes(core, &buff)
     }
 }
 impl Value for isize {
@@ -1242,16 +1307,14 @@ impl Value for isize {
         core: &mut Core<'_>,
         new_value: &str,
     ) -> Result<(), DebugError> {
-        let buff =
-            isize::to_le_bytes(<isize as FromStr>::from_str(new_value).map_err(|error| {
-                DebugError::Other(anyhow::anyhow!(
-                    "Invalid data conversion from value: {:?}. {:?}",
-                    new_value,
-                    error
-                ))
-            })?);
-        core.write_8(variable.memory_location.memory_address()?, &buff)
-            .map_err(|error| DebugError::Other(anyhow::anyhow!("{:?}", error)))
+        let buff = isize::to_le_bytes(parse_integer::<isize>(new_value).map_err(|error| {
+            DebugError::Other(anyhow::anyhow!(
+                "Invalid data conversion from value: {:?}. {:?}",
+                new_value,
+                error
+            ))
+        })?);
+        variable.write_bytes(core, &buff)
     }
 }
 impl Value for u8 {
@@ -1271,17 +1334,16 @@ impl Value for u8 {
         core: &mut Core<'_>,
         new_value: &str,
     ) -> Result<(), DebugError> {
-        core.write_word_8(
-            variable.memory_location.memory_address()?,
-            <u8 as FromStr>::from_str(new_value).map_err(|error| {
+        variable.write_bytes(
+            core,
+            &[parse_integer::<u8>(new_value).map_err(|error| {
                 DebugError::Other(anyhow::anyhow!(
                     "Invalid data conversion from value: {:?}. {:?}",
                     new_value,
                     error
                 ))
-            })? as u8,
+            })? as u8],
         )
-        .map_err(|error| DebugError::Other(anyhow::anyhow!("{:?}", error)))
     }
 }
 impl Value for u16 {
@@ -1301,15 +1363,14 @@ impl Value for u16 {
         core: &mut Core<'_>,
         new_value: &str,
     ) -> Result<(), DebugError> {
-        let buff = u16::to_le_bytes(<u16 as FromStr>::from_str(new_value).map_err(|error| {
+        let buff = u16::to_le_bytes(parse_integer::<u16>(new_value).map_err(|error| {
             DebugError::Other(anyhow::anyhow!(
                 "Invalid data conversion from value: {:?}. {:?}",
                 new_value,
                 error
             ))
         })?);
-        core.write_8(variable.memory_location.memory_address()?, &buff)
-            .map_err(|error| DebugError::Other(anyhow::anyhow!("{:?}", error)))
+        variable.write_bytes(core, &buff)
     }
 }
 impl Value for u32 {
@@ -1329,15 +1390,14 @@ impl Value for u32 {
         core: &mut Core<'_>,
         new_value: &str,
     ) -> Result<(), DebugError> {
-        let buff = u32::to_le_bytes(<u32 as FromStr>::from_str(new_value).map_err(|error| {
+        let buff = u32::to_le_bytes(parse_integer::<u32>(new_value).map_err(|error| {
             DebugError::Other(anyhow::anyhow!(
                 "Invalid data conversion from value: {:?}. {:?}",
                 new_value,
                 error
             ))
         })?);
-        core.write_8(variable.memory_location.memory_address()?, &buff)
-            .map_err(|error| DebugError::Other(anyhow::anyhow!("{:?}", error)))
+        variable.write_bytes(core, &buff)
     }
 }
 impl Value for u64 {
@@ -1357,15 +1417,14 @@ impl Value for u64 {
         core: &mut Core<'_>,
         new_value: &str,
     ) -> Result<(), DebugError> {
-        let buff = u64::to_le_bytes(<u64 as FromStr>::from_str(new_value).map_err(|error| {
+        let buff = u64::to_le_bytes(parse_integer::<u64>(new_value).map_err(|error| {
             DebugError::Other(anyhow::anyhow!(
                 "Invalid data conversion from value: {:?}. {:?}",
                 new_value,
                 error
             ))
         })?);
-        core.write_8(variable.memory_location.memory_address()?, &buff)
-            .map_err(|error| DebugError::Other(anyhow::anyhow!("{:?}", error)))
+        variable.write_bytes(core, &buff)
     }
 }
 impl Value for u128 {
@@ -1385,15 +1444,14 @@ impl Value for u128 {
         core: &mut Core<'_>,
         new_value: &str,
     ) -> Result<(), DebugError> {
-        let buff = u128::to_le_bytes(<u128 as FromStr>::from_str(new_value).map_err(|error| {
+        let buff = u128::to_le_bytes(parse_integer::<u128>(new_value).map_err(|error| {
             DebugError::Other(anyhow::anyhow!(
                 "Invalid data conversion from value: {:?}. {:?}",
                 new_value,
                 error
             ))
         })?);
-        core.write_8(variable.memory_location.memory_address()?, &buff)
-            .map_err(|error| DebugError::Other(anyhow::anyhow!("{:?}", error)))
+        variable.write_bytes(core, &buff)
     }
 }
 impl Value for usize {
@@ -1414,16 +1472,14 @@ impl Value for usize {
         core: &mut Core<'_>,
         new_value: &str,
     ) -> Result<(), DebugError> {
-        let buff =
-            usize::to_le_bytes(<usize as FromStr>::from_str(new_value).map_err(|error| {
-                DebugError::Other(anyhow::anyhow!(
-                    "Invalid data conversion from value: {:?}. {:?}",
-                    new_value,
-                    error
-                ))
-            })?);
-        core.write_8(variable.memory_location.memory_address()?, &buff)
-            .map_err(|error| DebugError::Other(anyhow::anyhow!("{:?}", error)))
+        let buff = usize::to_le_bytes(parse_integer::<usize>(new_value).map_err(|error| {
+            DebugError::Other(anyhow::anyhow!(
+                "Invalid data conversion from value: {:?}. {:?}",
+                new_value,
+                error
+            ))
+        })?);
+        variable.write_bytes(core, &buff)
     }
 }
 impl Value for f32 {
@@ -1450,8 +1506,7 @@ impl Value for f32 {
                 error
             ))
         })?);
-        core.write_8(variable.memory_location.memory_address()?, &buff)
-            .map_err(|error| DebugError::Other(anyhow::anyhow!("{:?}", error)))
+        variable.write_bytes(core, &buff)
     }
 }
 impl Value for f64 {
@@ -1478,7 +1533,21 @@ impl Value for f64 {
                 error
             ))
         })?);
-        core.write_8(variable.memory_location.memory_address()?, &buff)
-            .map_err(|error| DebugError::Other(anyhow::anyhow!("{:?}", error)))
+        variable.write_bytes(core, &buff)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_integer;
+
+    #[test]
+    fn parse_integers_with_prefixes() {
+        assert_eq!(parse_integer::<u32>("42"), Ok(42));
+        assert_eq!(parse_integer::<u32>("0x2000_0000"), Ok(0x2000_0000));
+        assert_eq!(parse_integer::<u8>("0b101"), Ok(5));
+        assert_eq!(parse_integer::<i16>("-0x10"), Ok(-16));
+        assert!(parse_integer::<u8>("0x100").is_err());
+        assert!(parse_integer::<u32>("abc").is_err());
     }
 }
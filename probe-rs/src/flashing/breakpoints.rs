@@ -0,0 +1,187 @@
+use probe_rs_target::{MemoryRange, MemoryRegion};
+
+use super::builder::FlashBuilder;
+use super::{EraseMode, FlashError, FlashLoader, FlashProgress, Flasher};
+use crate::memory::MemoryInterface;
+use crate::session::Session;
+use crate::InstructionSet;
+
+impl Session {
+    /// Set a software breakpoint in flash at `address`, for the core with the index `core_index`.
+    ///
+    /// Most cores only have a few hardware breakpoint units, which is not enough to debug a larger
+    /// application comfortably. Instead, this places a breakpoint instruction directly into the flash,
+    /// by re-programming the sectors which contain the breakpoint address. The number of these
+    /// breakpoints is not limited.
+    ///
+    /// This is opt-in, because every breakpoint costs a sector erase cycle when it is set and cleared,
+    /// and takes considerably longer than setting a hardware breakpoint.
+    /// To continue from a breakpoint, clear it first, so the original instruction is executed.
+    ///
+    /// The flash is modified while the core is debugged, so the core is not reset.
+    /// The core registers and the RAM used by the flash algorithm are restored afterwards.
+    /// The original instructions are programmed again when the breakpoints are cleared,
+    /// at the latest when the session is dropped.
+    ///
+    /// Setting a breakpoint which is already set does nothing.
+    pub fn set_flash_breakpoint(
+        &mut self,
+        core_index: usize,
+        address: u64,
+    ) -> Result<(), FlashError> {
+        if self.flash_breakpoints.contains_key(&address) {
+            return Ok(());
+        }
+
+        let mut core = self.core(core_index).map_err(FlashError::Core)?;
+        let instruction_set = core.instruction_set().map_err(FlashError::Core)?;
+
+        let mut first_halfword = [0; 2];
+        core.read(address, &mut first_halfword)
+            .map_err(FlashError::Core)?;
+        let (instruction, alignment) = breakpoint_instruction(instruction_set, first_halfword[0]);
+
+        if address & (alignment - 1) != 0 {
+            return Err(FlashError::InvalidBreakpointAddress { address });
+        }
+
+        let mut original = vec![0; instruction.len()];
+        core.read(address, &mut original)
+            .map_err(FlashError::Core)?;
+        drop(core);
+
+        log::debug!("Setting flash breakpoint at {:#010x}", address);
+        program_in_place(self, address, instruction)?;
+        self.flash_breakpoints.insert(address, original);
+
+        Ok(())
+    }
+
+    /// Clear the flash breakpoint at `address`, by programming the original instruction again.
+    ///
+    /// Clearing a breakpoint which is not set does nothing.
+    pub fn clear_flash_breakpoint(&mut self, address: u64) -> Result<(), FlashError> {
+        let original = match self.flash_breakpoints.get(&address) {
+            Some(original) => original.clone(),
+            None => return Ok(()),
+        };
+
+        log::debug!("Clearing flash breakpoint at {:#010x}", address);
+        program_in_place(self, address, &original)?;
+        self.flash_breakpoints.remove(&address);
+
+        Ok(())
+    }
+
+    /// Clear all flash breakpoints.
+    ///
+    /// This is done when the session is dropped as well.
+    pub fn clear_all_flash_breakpoints(&mut self) -> Result<(), FlashError> {
+        let addresses = self.flash_breakpoints().collect::<Vec<_>>();
+        for address in addresses {
+            self.clear_flash_breakpoint(address)?;
+        }
+
+        Ok(())
+    }
+
+    /// The addresses of all flash breakpoints which are currently set.
+    pub fn flash_breakpoints(&self) -> impl Iterator<Item = u64> + '_ {
+        self.flash_breakpoints.keys().copied()
+    }
+}
+
+/// The breakpoint instruction for `instruction_set` and the required alignment of its address.
+///
+/// With the RISC-V C extension, the length of the replaced instruction is determined from its
+/// first byte, so that a compressed instruction is replaced by a compressed breakpoint and the
+/// following instruction is left intact.
+fn breakpoint_instruction(instruction_set: InstructionSet, first_byte: u8) -> (&'static [u8], u64) {
+    match instruction_set {
+        // BKPT #0
+        InstructionSet::Thumb2 => (&[0x00, 0xbe], 2),
+        // BKPT #0
+        InstructionSet::A32 => (&[0x70, 0x00, 0x20, 0xe1], 4),
+        // BRK #0
+        InstructionSet::A64 => (&[0x00, 0x00, 0x20, 0xd4], 4),
+        // EBREAK
+        InstructionSet::RV32 => (&[0x73, 0x00, 0x10, 0x00], 4),
+        // EBREAK, 32-bit instructions only need to be aligned to 2 bytes with the C extension
+        InstructionSet::RV32C if first_byte & 0b11 == 0b11 => (&[0x73, 0x00, 0x10, 0x00], 2),
+        // C.EBREAK
+        InstructionSet::RV32C => (&[0x02, 0x90], 2),
+        // SDBBP, assuming a little endian core
+        InstructionSet::Mips32 => (&[0x3f, 0x00, 0x00, 0x70], 4),
+    }
+}
+
+/// Program `data` to the flash at `address`, keeping the rest of the affected sectors
+/// and the state of the core intact.
+fn program_in_place(session: &mut Session, address: u64, data: &[u8]) -> Result<(), FlashError> {
+    let range = address..address + data.len() as u64;
+
+    let region = session
+        .target()
+        .memory_map
+        .iter()
+        .find_map(|region| match region {
+            MemoryRegion::Nvm(region) if region.range.contains_range(&range) => {
+                Some(region.clone())
+            }
+            _ => None,
+        })
+        .ok_or_else(|| FlashError::NoSuitableNvm {
+            start: range.start,
+            end: range.end,
+            description_source: session.target().source().clone(),
+        })?;
+
+    let algorithm = FlashLoader::get_flash_algorithm_for_region(&region, session.target())?.clone();
+    let core = region
+        .cores
+        .first()
+        .and_then(|name| session.target().cores.iter().position(|c| &c.name == name))
+        .ok_or_else(|| FlashError::NoNvmCoreAccess(region.clone()))?;
+
+    let mut builder = FlashBuilder::new();
+    builder.add_data(address, data)?;
+
+    let mut flasher = Flasher::new_preserving_state(session, core, &algorithm)?;
+    let result = flasher.program(
+        &region,
+        &builder,
+        true,
+        false,
+        EraseMode::All,
+        &FlashProgress::new(|_| {}),
+    );
+    let restored = flasher.restore_state();
+
+    result.and(restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn riscv_breakpoint_matches_instruction_length() {
+        // addi a0, a0, 1
+        let (instruction, alignment) = breakpoint_instruction(InstructionSet::RV32C, 0x13);
+        assert_eq!(instruction, &[0x73, 0x00, 0x10, 0x00]);
+        assert_eq!(alignment, 2);
+
+        // c.addi a0, 1
+        let (instruction, alignment) = breakpoint_instruction(InstructionSet::RV32C, 0x05);
+        assert_eq!(instruction, &[0x02, 0x90]);
+        assert_eq!(alignment, 2);
+    }
+
+    #[test]
+    fn riscv_breakpoint_without_compressed_instructions() {
+        // addi a0, a0, 1
+        let (instruction, alignment) = breakpoint_instruction(InstructionSet::RV32, 0x13);
+        assert_eq!(instruction, &[0x73, 0x00, 0x10, 0x00]);
+        assert_eq!(alignment, 4);
+    }
+}
@@ -132,6 +132,75 @@ impl FlashLayout {
         &self.data_blocks
     }
 
+    /// Only keep the sectors for which `keep` is `true`, together with the pages and fills in them.
+    ///
+    /// A page which spans multiple sectors can only be programmed if all of them are erased,
+    /// so all sectors of a page are kept if one of them is kept.
+    /// Returns the number of sectors which were kept.
+    pub(super) fn retain_sectors(&mut self, keep: &[bool]) -> usize {
+        let mut keep = keep.to_vec();
+
+        let overlaps = |sector: &FlashSector, page: &FlashPage| {
+            sector.address < page.address + page.size() as u64
+                && page.address < sector.address + sector.size
+        };
+
+        // Propagate the kept state through pages spanning multiple sectors, until nothing changes.
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for page in &self.pages {
+                let sectors = self
+                    .sectors
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, sector)| overlaps(sector, page))
+                    .map(|(index, _)| index)
+                    .collect::<Vec<_>>();
+
+                if sectors.iter().any(|&index| keep[index])
+                    && !sectors.iter().all(|&index| keep[index])
+                {
+                    for index in sectors {
+                        keep[index] = true;
+                    }
+                    changed = true;
+                }
+            }
+        }
+
+        let sectors = std::mem::take(&mut self.sectors)
+            .into_iter()
+            .zip(keep)
+            .filter(|(_, keep)| *keep)
+            .map(|(sector, _)| sector)
+            .collect::<Vec<_>>();
+
+        // Keep the pages in the remaining sectors, and remap the page indices of the fills.
+        let mut page_indices = Vec::with_capacity(self.pages.len());
+        let mut pages = Vec::new();
+        for page in std::mem::take(&mut self.pages) {
+            if sectors.iter().any(|sector| overlaps(sector, &page)) {
+                page_indices.push(Some(pages.len()));
+                pages.push(page);
+            } else {
+                page_indices.push(None);
+            }
+        }
+
+        self.fills = std::mem::take(&mut self.fills)
+            .into_iter()
+            .filter_map(|fill| {
+                page_indices[fill.page_index].map(|page_index| FlashFill { page_index, ..fill })
+            })
+            .collect();
+
+        self.sectors = sectors;
+        self.pages = pages;
+
+        self.sectors.len()
+    }
+
     pub fn visualize(&self) -> FlashVisualizer {
         FlashVisualizer::new(self)
     }
@@ -157,7 +226,7 @@ impl FlashDataBlockSpan {
 }
 
 /// A helper structure to build a flash layout from a set of data blocks.
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub(super) struct FlashBuilder {
     pub(super) data: BTreeMap<u64, Vec<u8>>,
 }
@@ -366,69 +435,12 @@ impl FlashBuilder {
 
 #[cfg(test)]
 mod tests {
-    use probe_rs_target::{FlashProperties, NvmRegion, SectorDescription};
-
     use super::*;
-
-    fn assemble_demo_flash1() -> (NvmRegion, FlashAlgorithm) {
-        let sd = SectorDescription {
-            size: 4096,
-            address: 0,
-        };
-
-        let flash_algorithm = FlashAlgorithm {
-            flash_properties: FlashProperties {
-                address_range: 0..1 << 16,
-                page_size: 1024,
-                erased_byte_value: 255,
-                program_page_timeout: 200,
-                erase_sector_timeout: 200,
-                sectors: vec![sd],
-            },
-            ..Default::default()
-        };
-
-        let region = NvmRegion {
-            name: Some("FLASH".into()),
-            is_boot_memory: true,
-            range: 0..1 << 16,
-            cores: vec!["main".into()],
-        };
-
-        (region, flash_algorithm)
-    }
-
-    fn assemble_demo_flash2() -> (NvmRegion, FlashAlgorithm) {
-        let sd = SectorDescription {
-            size: 128,
-            address: 0,
-        };
-
-        let flash_algorithm = FlashAlgorithm {
-            flash_properties: FlashProperties {
-                address_range: 0..1 << 16,
-                page_size: 1024,
-                erased_byte_value: 255,
-                program_page_timeout: 200,
-                erase_sector_timeout: 200,
-                sectors: vec![sd],
-            },
-            ..Default::default()
-        };
-
-        let region = NvmRegion {
-            name: Some("FLASH".into()),
-            is_boot_memory: true,
-            range: 0..1 << 16,
-            cores: vec!["main".into()],
-        };
-
-        (region, flash_algorithm)
-    }
+    use crate::flashing::test_flash::demo_flash;
 
     #[test]
     fn single_byte_in_single_page() {
-        let (region, flash_algorithm) = assemble_demo_flash1();
+        let (region, flash_algorithm) = demo_flash(1 << 16, 4096);
         let mut flash_builder = FlashBuilder::new();
         flash_builder.add_data(0, &[42]).unwrap();
         let flash_layout = flash_builder
@@ -498,7 +510,7 @@ mod tests {
 
     #[test]
     fn equal_bytes_full_single_page() {
-        let (region, flash_algorithm) = assemble_demo_flash1();
+        let (region, flash_algorithm) = demo_flash(1 << 16, 4096);
         let mut flash_builder = FlashBuilder::new();
         flash_builder.add_data(0, &[42; 1024]).unwrap();
         let flash_layout = flash_builder
@@ -559,7 +571,7 @@ mod tests {
 
     #[test]
     fn equal_bytes_one_full_page_one_page_one_byte() {
-        let (region, flash_algorithm) = assemble_demo_flash1();
+        let (region, flash_algorithm) = demo_flash(1 << 16, 4096);
         let mut flash_builder = FlashBuilder::new();
         flash_builder.add_data(0, &[42; 1025]).unwrap();
         let flash_layout = flash_builder
@@ -624,7 +636,7 @@ mod tests {
 
     #[test]
     fn equal_bytes_one_full_page_one_page_one_byte_skip_fill() {
-        let (region, flash_algorithm) = assemble_demo_flash1();
+        let (region, flash_algorithm) = demo_flash(1 << 16, 4096);
         let mut flash_builder = FlashBuilder::new();
         flash_builder.add_data(0, &[42; 1025]).unwrap();
         let flash_layout = flash_builder
@@ -669,7 +681,7 @@ mod tests {
 
     #[test]
     fn equal_bytes_one_page_from_offset_span_two_pages() {
-        let (region, flash_algorithm) = assemble_demo_flash1();
+        let (region, flash_algorithm) = demo_flash(1 << 16, 4096);
         let mut flash_builder = FlashBuilder::new();
         flash_builder.add_data(42, &[42; 1024]).unwrap();
         let flash_layout = flash_builder
@@ -747,7 +759,7 @@ mod tests {
 
     #[test]
     fn equal_bytes_four_and_a_half_pages_two_sectors() {
-        let (region, flash_algorithm) = assemble_demo_flash1();
+        let (region, flash_algorithm) = demo_flash(1 << 16, 4096);
         let mut flash_builder = FlashBuilder::new();
         flash_builder.add_data(0, &[42; 5024]).unwrap();
         let flash_layout = flash_builder
@@ -841,7 +853,7 @@ mod tests {
 
     #[test]
     fn equal_bytes_in_two_data_chunks_multiple_sectors() {
-        let (region, flash_algorithm) = assemble_demo_flash1();
+        let (region, flash_algorithm) = demo_flash(1 << 16, 4096);
         let mut flash_builder = FlashBuilder::new();
         flash_builder.add_data(0, &[42; 5024]).unwrap();
         flash_builder.add_data(7860, &[42; 5024]).unwrap();
@@ -1014,7 +1026,7 @@ mod tests {
 
     #[test]
     fn two_data_chunks_multiple_sectors_smaller_than_page() {
-        let (region, flash_algorithm) = assemble_demo_flash2();
+        let (region, flash_algorithm) = demo_flash(1 << 16, 128);
         let mut flash_builder = FlashBuilder::new();
         flash_builder.add_data(0, &[42; 5024]).unwrap();
         flash_builder.add_data(7860, &[42; 5024]).unwrap();
@@ -1139,4 +1151,73 @@ mod tests {
             }
         )
     }
+
+    #[test]
+    fn retain_sectors_removes_pages_and_fills() {
+        let (region, flash_algorithm) = demo_flash(1 << 16, 4096);
+        let mut flash_builder = FlashBuilder::new();
+        flash_builder.add_data(0, &[42; 5024]).unwrap();
+        let mut flash_layout = flash_builder
+            .build_sectors_and_pages(&region, &flash_algorithm, true)
+            .unwrap();
+
+        assert_eq!(flash_layout.retain_sectors(&[false, true]), 1);
+
+        assert_eq!(
+            flash_layout.sectors(),
+            &[FlashSector {
+                address: 0x001000,
+                size: 0x001000,
+            }]
+        );
+        assert_eq!(
+            flash_layout
+                .pages()
+                .iter()
+                .map(|page| page.address())
+                .collect::<Vec<_>>(),
+            vec![0x001000, 0x001400, 0x001800, 0x001C00]
+        );
+        assert_eq!(
+            flash_layout.fills(),
+            &[
+                FlashFill {
+                    address: 0x0013A0,
+                    size: 0x000060,
+                    page_index: 0,
+                },
+                FlashFill {
+                    address: 0x001400,
+                    size: 0x000400,
+                    page_index: 1,
+                },
+                FlashFill {
+                    address: 0x001800,
+                    size: 0x000400,
+                    page_index: 2,
+                },
+                FlashFill {
+                    address: 0x001C00,
+                    size: 0x000400,
+                    page_index: 3,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn retain_sectors_keeps_all_sectors_of_a_page() {
+        let (region, flash_algorithm) = demo_flash(1 << 16, 128);
+        let mut flash_builder = FlashBuilder::new();
+        flash_builder.add_data(0, &[42; 1024]).unwrap();
+        let mut flash_layout = flash_builder
+            .build_sectors_and_pages(&region, &flash_algorithm, false)
+            .unwrap();
+
+        let mut keep = vec![false; flash_layout.sectors().len()];
+        keep[3] = true;
+
+        assert_eq!(flash_layout.retain_sectors(&keep), 8);
+        assert_eq!(flash_layout.pages().len(), 1);
+    }
 }
@@ -0,0 +1,157 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::FlashError;
+
+/// A host-side cache of the flash sectors which were programmed by earlier downloads.
+///
+/// The cache stores a CRC of the contents of every programmed sector, separately for every target,
+/// which is identified by a key chosen by the user, e.g. the serial number of the probe or the chip.
+/// When the cache is passed to [`DownloadOptions::sector_cache`](super::DownloadOptions::sector_cache),
+/// sectors whose new contents match the cached CRC are neither erased nor programmed,
+/// without having to read them back from the target first.
+///
+/// The cached contents can become stale if the target is programmed by other means. To detect this,
+/// the data of one skipped sector is read back and compared. If it does not match, the cache of the
+/// target is discarded and all sectors are programmed. A chip erase also discards the cache.
+#[derive(Debug)]
+pub struct SectorCache {
+    path: PathBuf,
+    key: String,
+    file: CacheFile,
+}
+
+/// The contents of a cache file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheFile {
+    /// The CRCs of the sectors per target, by the address of the sector.
+    targets: BTreeMap<String, BTreeMap<u64, u32>>,
+}
+
+impl SectorCache {
+    /// Load the cache for the target identified by `key` from the file at `path`.
+    ///
+    /// A missing or unreadable file results in an empty cache, which is written to `path` on the next download.
+    pub fn load(path: impl Into<PathBuf>, key: impl Into<String>) -> Self {
+        let path = path.into();
+
+        let file = match File::open(&path) {
+            Ok(file) => serde_yaml::from_reader(file).unwrap_or_else(|error| {
+                log::warn!(
+                    "Ignoring the invalid sector cache at {}: {}",
+                    path.display(),
+                    error
+                );
+                CacheFile::default()
+            }),
+            Err(error) => {
+                if error.kind() != io::ErrorKind::NotFound {
+                    log::warn!(
+                        "Ignoring the sector cache at {}, it could not be opened: {}",
+                        path.display(),
+                        error
+                    );
+                }
+                CacheFile::default()
+            }
+        };
+
+        Self {
+            path,
+            key: key.into(),
+            file,
+        }
+    }
+
+    /// The path of the cache file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Discard all cached sectors of the target.
+    pub fn invalidate(&mut self) {
+        self.file.targets.remove(&self.key);
+    }
+
+    /// Write the cache back to its file.
+    pub fn save(&self) -> Result<(), FlashError> {
+        let error = |source| FlashError::SectorCache {
+            path: self.path.clone(),
+            source,
+        };
+
+        let file = File::create(&self.path).map_err(|e| error(Box::new(e)))?;
+        serde_yaml::to_writer(file, &self.file).map_err(|e| error(Box::new(e)))
+    }
+
+    /// The CRCs of the cached sectors of the target, by the address of the sector.
+    pub(super) fn checksums(&self) -> BTreeMap<u64, u32> {
+        self.file
+            .targets
+            .get(&self.key)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Replace the cached sectors of the target.
+    pub(super) fn set_checksums(&mut self, checksums: BTreeMap<u64, u32>) {
+        self.file.targets.insert(self.key.clone(), checksums);
+    }
+}
+
+/// Calculate the CRC-32 (IEEE 802.3) of `data`.
+pub(super) fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn cache_is_stored_per_target() {
+        let path =
+            std::env::temp_dir().join(format!("probe-rs-sector-cache-{}", std::process::id()));
+
+        let mut cache = SectorCache::load(&path, "first");
+        cache.set_checksums(vec![(0x0800_0000, 0x1234_5678)].into_iter().collect());
+        cache.save().unwrap();
+
+        let mut other = SectorCache::load(&path, "second");
+        assert!(other.checksums().is_empty());
+        other.set_checksums(vec![(0x0800_0000, 1)].into_iter().collect());
+        other.save().unwrap();
+
+        let mut cache = SectorCache::load(&path, "first");
+        assert_eq!(cache.checksums().get(&0x0800_0000), Some(&0x1234_5678));
+
+        cache.invalidate();
+        assert!(cache.checksums().is_empty());
+        assert_eq!(
+            SectorCache::load(&path, "second")
+                .checksums()
+                .get(&0x0800_0000),
+            Some(&1)
+        );
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
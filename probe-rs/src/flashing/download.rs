@@ -2,12 +2,18 @@ use object::{
     elf::FileHeader32, elf::PT_LOAD, read::elf::FileHeader, read::elf::ProgramHeader, Endianness,
     Object, ObjectSection,
 };
-use probe_rs_target::MemoryRange;
+use probe_rs_target::{MemoryRange, MemoryRegion};
 
-use std::{fs::File, path::Path, str::FromStr};
+use std::{ops::Range, path::Path, str::FromStr, time::Duration};
 
 use super::*;
 use crate::session::Session;
+#[cfg(feature = "serial")]
+use crate::Target;
+use crate::{Architecture, InstructionSet, MemoryInterface};
+
+/// The address of the Vector Table Offset Register (VTOR) of Cortex-M cores.
+const VTOR: u64 = 0xE000_ED08;
 
 /// Extended options for flashing a binary file.
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
@@ -75,6 +81,25 @@ pub enum FileDownloadError {
     /// This is most likely because of a bad linker script.
     #[error("No loadable ELF sections were found.")]
     NoLoadableSegments,
+    /// A loadable segment of the ELF file can not be loaded, because it does not fit the memory map of the target.
+    #[error("The ELF segment containing the sections {sections} can not be loaded")]
+    InvalidSegment {
+        /// The names of the sections in the segment.
+        sections: String,
+        /// The reason why the segment can not be loaded.
+        source: Box<FlashError>,
+    },
+    /// A loadable segment of the ELF file is not located in RAM, so it can not be run from RAM.
+    #[error("The segment at {address:#010x} ({size} bytes) is not located in RAM.")]
+    SegmentNotInRam {
+        /// The address of the segment.
+        address: u64,
+        /// The size of the segment in bytes.
+        size: usize,
+    },
+    /// An ELF file could not be converted into a flash image for the bootloader of an Espressif chip.
+    #[error("The ELF file can not be converted into a flash image for the ESP bootloader: {0}")]
+    InvalidEspImage(&'static str),
 }
 
 /// Options for downloading a file onto a target chip.
@@ -111,8 +136,29 @@ pub struct DownloadOptions<'progress> {
     /// If the chip was pre-erased with external erasers, this flag can set to true to skip erasing
     /// It may be useful for mass production.
     pub skip_erase: bool,
+    /// Before erasing, read back the contents of all sectors which are going to be programmed,
+    /// and skip the sectors which already contain the new data.
+    ///
+    /// This speeds up flashing considerably if only small parts of an image change between downloads.
+    pub skip_unchanged_sectors: bool,
+    /// Address ranges in flash which must keep their current contents, e.g. a bootloader or
+    /// calibration data.
+    ///
+    /// Preserved bytes in sectors which have to be erased are read before erasing and programmed
+    /// again afterwards. It is an error if the data to be programmed overlaps with a preserved range.
+    pub preserved_ranges: Vec<Range<u64>>,
+    /// A host-side cache of the sectors which were programmed by earlier downloads.
+    ///
+    /// Sectors which are unchanged according to the cache are skipped, and the cache file is
+    /// updated after a successful download. See [`SectorCache`] for details.
+    pub sector_cache: Option<SectorCache>,
     /// After flashing, read back all the flashed data to verify it has been written correctly.
     pub verify: bool,
+    /// Skip data of the file which is outside of the memory map or in a memory region which can not be written,
+    /// instead of aborting. See [`FlashLoader::set_skip_invalid_data`].
+    ///
+    /// This is only used by [`download_file_with_options`], when the file is loaded.
+    pub skip_invalid_data: bool,
     /// Disable double buffering when loading flash.
     pub disable_double_buffering: bool,
 }
@@ -148,24 +194,179 @@ pub fn download_file_with_options<P: AsRef<Path>>(
     format: Format,
     options: DownloadOptions<'_>,
 ) -> Result<(), FileDownloadError> {
-    let mut file = match File::open(path.as_ref()) {
-        Ok(file) => file,
-        Err(e) => return Err(FileDownloadError::IO(e)),
-    };
+    download_files_with_options(session, &[(path, format)], options)
+}
 
+/// Downloads multiple files, each in the given format, to the flash of the target given in `session`.
+///
+/// All files are combined into one image, which is erased and programmed in a single operation.
+/// It is an error if the data of the files overlaps.
+///
+/// On Espressif targets, ELF files are converted into a flash image for the second stage
+/// bootloader, which is programmed at the offset of the first application partition.
+pub fn download_files_with_options<P: AsRef<Path>>(
+    session: &mut Session,
+    files: &[(P, Format)],
+    options: DownloadOptions<'_>,
+) -> Result<(), FileDownloadError> {
     let mut loader = session.target().flash_loader();
+    loader.set_skip_invalid_data(options.skip_invalid_data);
 
-    match format {
-        Format::Bin(options) => loader.load_bin_data(&mut file, options),
-        Format::Elf => loader.load_elf_data(&mut file),
-        Format::Hex => loader.load_hex_data(&mut file),
-    }?;
+    for (path, format) in files {
+        loader.load_image(path, format.clone())?;
+    }
 
     loader
         .commit(session, options)
         .map_err(FileDownloadError::Flash)
 }
 
+/// Downloads multiple files, each in the given format, to the flash of an Espressif `target`,
+/// through the serial bootloader in its ROM instead of a debug probe.
+///
+/// The bootloader is reached through the serial port `port`, which is either a UART of the chip
+/// or its USB-Serial-JTAG peripheral. The chip is reset into the bootloader before programming,
+/// and reset again afterwards to start the new program.
+///
+/// ELF files are converted into a flash image for the second stage bootloader, like by
+/// [`download_files_with_options`].
+#[cfg(feature = "serial")]
+pub fn download_files_with_esp_rom_loader<P: AsRef<Path>>(
+    target: &Target,
+    port: &str,
+    files: &[(P, Format)],
+    options: DownloadOptions<'_>,
+) -> Result<(), FileDownloadError> {
+    let mut loader = target.flash_loader();
+    loader.set_skip_invalid_data(options.skip_invalid_data);
+
+    for (path, format) in files {
+        loader.load_image(path, format.clone())?;
+    }
+
+    let mut rom_loader =
+        EspRomLoader::open(port, EspRomLoader::DEFAULT_BAUD_RATE).map_err(FlashError::EspRom)?;
+
+    loader.commit_esp_rom(&mut rom_loader, options)?;
+
+    rom_loader.hard_reset().map_err(FlashError::EspRom)?;
+
+    Ok(())
+}
+
+/// Loads the ELF file at `path` into RAM and runs it on the first core of the target, without flashing anything.
+///
+/// All loadable segments of the ELF file have to be located in RAM. The core is reset and halted
+/// before the segments are written, and is then started at the entry point of the ELF file.
+///
+/// On Cortex-M cores, the stack pointer is loaded from the vector table of the image, which is
+/// also made the active vector table. The vector table is taken from the `.vector_table` section,
+/// or from the start of the image if there is no such section. If the ELF file has no entry point,
+/// the reset vector is used instead.
+pub fn run_from_ram<P: AsRef<Path>>(
+    session: &mut Session,
+    path: P,
+) -> Result<(), FileDownloadError> {
+    let elf_data = std::fs::read(path)?;
+
+    let mut extracted_data = Vec::new();
+    if extract_from_elf(&mut extracted_data, &elf_data)? == 0 {
+        return Err(FileDownloadError::NoLoadableSegments);
+    }
+
+    check_segments_in_ram(&session.target().memory_map, &extracted_data)?;
+
+    let elf = object::File::parse(&elf_data[..])?;
+    let entry = elf.entry();
+    let vector_table = match elf.section_by_name(".vector_table") {
+        Some(section) => section.address(),
+        // This can't fail, there is at least one segment.
+        None => extracted_data
+            .iter()
+            .map(|segment| segment.address as u64)
+            .min()
+            .unwrap(),
+    };
+
+    let mut core = session.core(0).map_err(FlashError::Core)?;
+
+    core.reset_and_halt(Duration::from_millis(500))
+        .map_err(FlashError::Core)?;
+
+    for segment in &extracted_data {
+        log::debug!(
+            "Loading {:08x}-{:08x} ({} bytes)",
+            segment.address,
+            segment.address as u64 + segment.data.len() as u64,
+            segment.data.len()
+        );
+        core.write_8(segment.address as u64, segment.data)
+            .map_err(FlashError::Core)?;
+    }
+
+    let mut pc = entry;
+
+    if core.architecture() == Architecture::Arm && core.core_type().is_cortex_m() {
+        let stack_pointer = core.read_word_32(vector_table).map_err(FlashError::Core)?;
+        let reset_vector = core
+            .read_word_32(vector_table + 4)
+            .map_err(FlashError::Core)?;
+
+        log::debug!(
+            "Using the vector table at {:#010x}, SP: {:#010x}, reset vector: {:#010x}",
+            vector_table,
+            stack_pointer,
+            reset_vector
+        );
+
+        core.write_word_32(VTOR, vector_table as u32)
+            .map_err(FlashError::Core)?;
+        core.write_core_reg(core.registers().stack_pointer().id, stack_pointer)
+            .map_err(FlashError::Core)?;
+
+        if pc == 0 {
+            pc = reset_vector as u64;
+        }
+    }
+
+    // The lowest bit only selects the Thumb state, it is not part of the address.
+    if core.instruction_set().map_err(FlashError::Core)? == InstructionSet::Thumb2 {
+        pc &= !1;
+    }
+
+    log::info!("Running the image from RAM, starting at {:#010x}", pc);
+
+    core.write_core_reg(core.registers().program_counter().id, pc as u32)
+        .map_err(FlashError::Core)?;
+    core.run().map_err(FlashError::Core)?;
+
+    Ok(())
+}
+
+/// Checks that each of the `segments` is located completely in one RAM region of the `memory_map`.
+fn check_segments_in_ram(
+    memory_map: &[MemoryRegion],
+    segments: &[ExtractedFlashData<'_>],
+) -> Result<(), FileDownloadError> {
+    for segment in segments {
+        let address = segment.address as u64;
+        let range = address..address + segment.data.len() as u64;
+
+        let in_ram = memory_map.iter().any(|region| {
+            matches!(region, MemoryRegion::Ram(region) if region.range.contains_range(&range))
+        });
+
+        if !in_ram {
+            return Err(FileDownloadError::SegmentNotInRam {
+                address,
+                size: segment.data.len(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
 /// Flash data which was extraced from an ELF file.
 pub(super) struct ExtractedFlashData<'data> {
     pub(super) section_names: Vec<String>,
@@ -282,7 +483,34 @@ pub(super) fn extract_from_elf<'data>(
 mod tests {
     use std::str::FromStr;
 
-    use super::{BinOptions, Format};
+    use probe_rs_target::{MemoryRegion, NvmRegion, RamRegion};
+
+    use super::{check_segments_in_ram, BinOptions, ExtractedFlashData, FileDownloadError, Format};
+
+    fn ram_and_flash() -> Vec<MemoryRegion> {
+        vec![
+            MemoryRegion::Nvm(NvmRegion {
+                name: Some("FLASH".into()),
+                is_boot_memory: true,
+                range: 0x0000_0000..0x0001_0000,
+                cores: vec!["main".into()],
+            }),
+            MemoryRegion::Ram(RamRegion {
+                name: Some("RAM".into()),
+                is_boot_memory: false,
+                range: 0x2000_0000..0x2000_4000,
+                cores: vec!["main".into()],
+            }),
+        ]
+    }
+
+    fn segment(address: u32, data: &[u8]) -> ExtractedFlashData<'_> {
+        ExtractedFlashData {
+            section_names: vec![".text".into()],
+            address,
+            data,
+        }
+    }
 
     #[test]
     fn parse_format() {
@@ -338,4 +566,40 @@ mod tests {
             Err("Format 'asdasdf' is unknown.".to_string())
         );
     }
+
+    #[test]
+    fn segments_in_ram_can_be_run() {
+        let data = [0u8; 0x100];
+        let segments = [segment(0x2000_0000, &data), segment(0x2000_3F00, &data)];
+
+        assert!(check_segments_in_ram(&ram_and_flash(), &segments).is_ok());
+    }
+
+    #[test]
+    fn segment_in_flash_is_rejected() {
+        let data = [0u8; 0x100];
+        let segments = [segment(0x2000_0000, &data), segment(0x0000_0400, &data)];
+
+        assert!(matches!(
+            check_segments_in_ram(&ram_and_flash(), &segments),
+            Err(FileDownloadError::SegmentNotInRam {
+                address: 0x0000_0400,
+                size: 0x100
+            })
+        ));
+    }
+
+    #[test]
+    fn segment_exceeding_ram_is_rejected() {
+        let data = [0u8; 0x100];
+        let segments = [segment(0x2000_3F80, &data)];
+
+        assert!(matches!(
+            check_segments_in_ram(&ram_and_flash(), &segments),
+            Err(FileDownloadError::SegmentNotInRam {
+                address: 0x2000_3F80,
+                size: 0x100
+            })
+        ));
+    }
 }
@@ -1,14 +1,33 @@
 use std::collections::HashMap;
+use std::ops::Range;
 
-use probe_rs_target::{MemoryRange, MemoryRegion, NvmRegion};
+use probe_rs_target::{MemoryRange, MemoryRegion, NvmRegion, SectorInfo};
 
-use crate::flashing::{flasher::Flasher, FlashError, FlashLoader};
+use crate::flashing::{flasher::Flasher, FlashAlgorithm, FlashError, FlashLoader, SectorCache};
 use crate::Session;
 
 /// Mass-erase all nonvolatile memory.
+///
+/// This requires the session to be opened with [`Permissions::allow_erase_all`](crate::Permissions::allow_erase_all).
+///
+/// Targets which need a vendor specific procedure to be erased completely, e.g. the nRF5340,
+/// are erased with the procedure of their debug sequence. All other targets are erased with the
+/// chip erase routine of their flash algorithms, or sector by sector if it is not available.
 pub fn erase_all(session: &mut Session) -> Result<(), FlashError> {
+    session
+        .permissions()
+        .erase_all()
+        .map_err(FlashError::Core)?;
+
     log::debug!("Erasing all...");
 
+    session.sequence_flash_start().map_err(FlashError::Core)?;
+
+    if session.sequence_erase_all().map_err(FlashError::Core)? {
+        log::debug!("Erased all memory with the debug sequence of the target.");
+        return Ok(());
+    }
+
     let mut algos: HashMap<(String, String), Vec<NvmRegion>> = HashMap::new();
     log::debug!("Regions:");
     for region in &session.target().memory_map {
@@ -82,3 +101,216 @@ pub fn erase_all(session: &mut Session) -> Result<(), FlashError> {
 
     Ok(())
 }
+
+/// Mass-erase all nonvolatile memory like [`erase_all`], and discard the sectors of the target
+/// in `sector_cache`, which are blank afterwards.
+///
+/// The cache is saved before erasing, so an interrupted erase doesn't leave stale entries behind.
+pub fn erase_all_with_sector_cache(
+    session: &mut Session,
+    sector_cache: &mut SectorCache,
+) -> Result<(), FlashError> {
+    sector_cache.invalidate();
+    sector_cache.save()?;
+
+    erase_all(session)
+}
+
+/// Erase all sectors which overlap with the given address ranges, without programming anything.
+///
+/// Flash can only be erased in whole sectors, so more memory than requested is erased
+/// if a range does not start and end on sector boundaries.
+/// All ranges must be completely contained in the nonvolatile memory of the target.
+pub fn erase(session: &mut Session, ranges: &[Range<u64>]) -> Result<(), FlashError> {
+    for ((algo_name, core_name), regions) in algorithms_for_ranges(session, ranges)? {
+        log::debug!("Erasing with algorithm: {}", algo_name);
+
+        // This can't fail, algo_name comes from the target.
+        let algo = session.target().flash_algorithm_by_name(&algo_name);
+        let algo = algo.unwrap().clone();
+
+        let core_index = session.target().core_index_by_name(&core_name).unwrap();
+        let mut flasher = Flasher::new(session, core_index, &algo)?;
+
+        let sectors = sectors_in_ranges(flasher.flash_algorithm(), &regions, ranges);
+
+        flasher.run_erase(|active| {
+            for info in sectors {
+                log::debug!(
+                    "    sector: {:08x}-{:08x} ({} bytes)",
+                    info.base_address,
+                    info.base_address + info.size,
+                    info.size
+                );
+
+                active
+                    .erase_sector(info.base_address)
+                    .map_err(|e| FlashError::EraseFailed {
+                        sector_address: info.base_address,
+                        source: Box::new(e),
+                    })?;
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Check if all sectors which overlap with `range` are erased, and return the ones which are not.
+///
+/// The sectors are checked on the target by the `BlankCheck()` routine of the flash algorithm,
+/// where available. Flash algorithms without this routine fall back to reading the sectors back
+/// to the host and comparing them to the erased byte value of the flash, which is slower.
+/// The range must be completely contained in the nonvolatile memory of the target.
+pub fn blank_check(
+    session: &mut Session,
+    range: Range<u64>,
+) -> Result<Vec<SectorInfo>, FlashError> {
+    let ranges = [range];
+    let mut not_blank = Vec::new();
+
+    for ((algo_name, core_name), regions) in algorithms_for_ranges(session, &ranges)? {
+        log::debug!("Blank checking with algorithm: {}", algo_name);
+
+        // This can't fail, algo_name comes from the target.
+        let algo = session.target().flash_algorithm_by_name(&algo_name);
+        let algo = algo.unwrap().clone();
+
+        let core_index = session.target().core_index_by_name(&core_name).unwrap();
+        let mut flasher = Flasher::new(session, core_index, &algo)?;
+
+        let sectors = sectors_in_ranges(flasher.flash_algorithm(), &regions, &ranges);
+
+        let pc_blank_check = flasher.flash_algorithm().pc_blank_check;
+        let blank = match pc_blank_check {
+            Some(pc_blank_check) => flasher.run_erase(|active| {
+                sectors
+                    .iter()
+                    .map(|info| active.blank_check(pc_blank_check, info.base_address, info.size))
+                    .collect::<Result<Vec<_>, _>>()
+            })?,
+            None => flasher.run_verify(|active| {
+                sectors
+                    .iter()
+                    .map(|info| active.read_is_erased(info.base_address, info.size))
+                    .collect::<Result<Vec<_>, _>>()
+            })?,
+        };
+
+        for (info, blank) in sectors.into_iter().zip(blank) {
+            if !blank {
+                log::debug!(
+                    "    sector {:08x}-{:08x} is not blank",
+                    info.base_address,
+                    info.base_address + info.size
+                );
+                not_blank.push(info);
+            }
+        }
+    }
+
+    not_blank.sort_by_key(|info| info.base_address);
+
+    Ok(not_blank)
+}
+
+/// Group the NVM regions which overlap with `ranges` by the flash algorithm and core used to access them.
+///
+/// Returns an error if the ranges are not completely covered by NVM.
+fn algorithms_for_ranges(
+    session: &Session,
+    ranges: &[Range<u64>],
+) -> Result<HashMap<(String, String), Vec<NvmRegion>>, FlashError> {
+    let memory_map = &session.target().memory_map;
+
+    // Check that all ranges are covered by NVM, possibly by multiple regions.
+    for range in ranges {
+        let mut address = range.start;
+        while address < range.end {
+            match memory_map.iter().find_map(|region| match region {
+                MemoryRegion::Nvm(region) if region.range.contains(&address) => Some(region),
+                _ => None,
+            }) {
+                Some(region) => address = region.range.end,
+                None => {
+                    return Err(FlashError::NoSuitableNvm {
+                        start: range.start,
+                        end: range.end,
+                        description_source: session.target().source().clone(),
+                    })
+                }
+            }
+        }
+    }
+
+    let mut algos: HashMap<(String, String), Vec<NvmRegion>> = HashMap::new();
+    for region in memory_map {
+        if let MemoryRegion::Nvm(region) = region {
+            if !ranges
+                .iter()
+                .any(|range| range.start < region.range.end && region.range.start < range.end)
+            {
+                continue;
+            }
+
+            let algo = FlashLoader::get_flash_algorithm_for_region(region, session.target())?;
+
+            // Get the first core that can access the region
+            let core_name = region
+                .cores
+                .first()
+                .ok_or_else(|| FlashError::NoNvmCoreAccess(region.clone()))?;
+
+            algos
+                .entry((algo.name.clone(), core_name.clone()))
+                .or_default()
+                .push(region.clone());
+        }
+    }
+
+    Ok(algos)
+}
+
+/// The sectors of `regions` which overlap with any of the `ranges`.
+fn sectors_in_ranges(
+    flash_algorithm: &FlashAlgorithm,
+    regions: &[NvmRegion],
+    ranges: &[Range<u64>],
+) -> Vec<SectorInfo> {
+    flash_algorithm
+        .iter_sectors()
+        .filter(|info| {
+            let sector = info.base_address..info.base_address + info.size;
+            regions.iter().any(|r| r.range.contains_range(&sector))
+                && ranges
+                    .iter()
+                    .any(|range| range.start < sector.end && sector.start < range.end)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flashing::test_flash::demo_flash;
+
+    #[test]
+    fn sectors_in_ranges_includes_partially_covered_sectors() {
+        let (region, flash_algorithm) = demo_flash(0x4000, 0x1000);
+
+        let sectors = sectors_in_ranges(
+            &flash_algorithm,
+            &[region],
+            &[0x0fff..0x1001, 0x3000..0x3004],
+        );
+
+        assert_eq!(
+            sectors
+                .iter()
+                .map(|info| info.base_address)
+                .collect::<Vec<_>>(),
+            vec![0x0000, 0x1000, 0x3000]
+        );
+    }
+}
@@ -1,6 +1,8 @@
+use super::EspRomError;
 use crate::config::{NvmRegion, RamRegion, TargetDescriptionSource};
 use crate::error;
 use std::ops::Range;
+use std::path::PathBuf;
 
 /// Describes any error that happened during the or in preparation for the flashing procedure.
 #[derive(thiserror::Error, Debug)]
@@ -17,6 +19,36 @@ pub enum FlashError {
         /// The source of this target description (was it a built in target or one loaded externally and from what file path?).
         description_source: TargetDescriptionSource,
     },
+    /// Data was added to a range which is not part of the memory map of the target.
+    ///
+    /// This usually means that the image was built for a different chip.
+    #[error("The data at {start:#010x}..{end:#010x} is outside of the memory map of the target. Was the image built for a different chip?")]
+    DataOutsideMemoryMap {
+        /// The start of the range outside of the memory map.
+        start: u64,
+        /// The end of the range outside of the memory map.
+        end: u64,
+        /// The source of this target description (was it a built in target or one loaded externally and from what file path?).
+        description_source: TargetDescriptionSource,
+    },
+    /// Data was added to a memory region which can not be written, e.g. a reserved or peripheral region.
+    #[error("The data at {start:#010x}..{end:#010x} is in the memory region '{region}', which can not be written.")]
+    DataInReservedRegion {
+        /// The start of the range inside the region.
+        start: u64,
+        /// The end of the range inside the region.
+        end: u64,
+        /// The name of the region.
+        region: String,
+    },
+    /// The sector cache could not be written.
+    #[error("Failed to write the sector cache to {}.", path.display())]
+    SectorCache {
+        /// The path of the cache file.
+        path: PathBuf,
+        /// The source of this error.
+        source: Box<dyn std::error::Error + 'static + Send + Sync>,
+    },
     /// Erasing the full chip flash failed.
     #[error("Failed to erase the whole chip.")]
     ChipEraseFailed {
@@ -142,13 +174,47 @@ pub enum FlashError {
         /// The address range that was already present.
         existing_addresses: Range<u64>,
     },
+    /// The data of two images which are flashed together overlaps.
+    #[error("The image {image} overlaps with the image {existing_image} at the addresses {added_addresses:08X?}.")]
+    ImagesOverlap {
+        /// The image which was added.
+        image: String,
+        /// The image which was already present.
+        existing_image: String,
+        /// The address range that was tried to be added.
+        added_addresses: Range<u64>,
+        /// The address range that was already present.
+        existing_addresses: Range<u64>,
+    },
+    /// The data to be programmed overlaps with an address range which should be preserved.
+    #[error("The data to be programmed overlaps with the preserved addresses {preserved_addresses:08X?}.")]
+    PreservedRangeOverwritten {
+        /// The address range which should have been preserved.
+        preserved_addresses: Range<u64>,
+    },
+    /// The data would be programmed through the wrong alias of the flash of a chip with TrustZone
+    /// enabled, according to its secure watermarks.
+    #[error("The data at {addresses:08X?} is programmed through the wrong alias of the flash. With TrustZone enabled, the secure pages can only be programmed through the secure alias, and the non-secure pages through the non-secure alias.")]
+    TrustZoneAliasMismatch {
+        /// The addresses which are programmed through the wrong alias.
+        addresses: Range<u64>,
+    },
     /// No core can access this NVM region.
     #[error("No core can access the NVM region {0:?}.")]
     NoNvmCoreAccess(NvmRegion),
     /// No core can access this RAM region.
     #[error("No core can access the ram region {0:?}.")]
     NoRamCoreAccess(RamRegion),
+    /// A flash breakpoint can not be placed at this address, because it is not aligned to an instruction.
+    #[error("A breakpoint can not be placed at {address:#010x}, because it is not aligned to an instruction.")]
+    InvalidBreakpointAddress {
+        /// The address of the breakpoint.
+        address: u64,
+    },
     /// The register value supplied for this flash algorithm is out of the supported range.
     #[error("The register value {0:08X?} is out of the supported range.")]
     RegisterValueNotSupported(u64),
+    /// Programming the flash through the ROM bootloader of an Espressif chip failed.
+    #[error("Programming the flash through the ROM bootloader failed.")]
+    EspRom(#[from] EspRomError),
 }
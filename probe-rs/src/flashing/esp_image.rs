@@ -0,0 +1,251 @@
+//! Conversion of ELF files into the flash image format which is loaded by the second stage
+//! bootloader of Espressif chips.
+//!
+//! The code and read-only data of a program for an Espressif chip are executed in place from
+//! memory mapped address ranges, which are set up by the bootloader from the segments of the
+//! image. The image is programmed at [`APP_OFFSET`], the offset of the first application
+//! partition in the default partition table of ESP-IDF. The bootloader and the partition
+//! table are not part of the image, and have to be present in the flash already.
+
+use std::ops::Range;
+
+use object::elf::FileHeader32;
+use object::read::elf::FileHeader;
+use object::Endianness;
+
+use super::download::extract_from_elf;
+use super::{EspChip, FileDownloadError};
+
+/// The offset in the flash at which the image is programmed.
+pub(super) const APP_OFFSET: u64 = 0x1_0000;
+
+const IMAGE_MAGIC: u8 = 0xE9;
+/// The magic word at the start of the application description in the first data segment.
+const APP_DESC_MAGIC: u32 = 0xABCD_5432;
+
+/// The initial value of the checksum over the data of all segments.
+const CHECKSUM_MAGIC: u8 = 0xEF;
+
+/// The alignment of the memory mapping of the flash. Memory mapped segments must be
+/// located at the same offset in a page of the flash as in a page of the address space.
+const MMU_PAGE_SIZE: u32 = 0x1_0000;
+
+/// The size of the image header, including the extended header.
+const HEADER_SIZE: usize = 24;
+/// The size of the header in front of each segment.
+const SEGMENT_HEADER_SIZE: u32 = 8;
+/// The bootloader only loads images with at most this many segments.
+const MAX_SEGMENTS: usize = 16;
+
+const FLASH_MODE_DIO: u8 = 2;
+const FLASH_SIZE_4MB: u8 = 2;
+const FLASH_FREQ_40MHZ: u8 = 0;
+
+/// The value of the write protection pin in the extended header, which disables it.
+const WP_PIN_DISABLED: u8 = 0xEE;
+
+/// The parameters of the chip which the image is built for.
+struct ImageChip {
+    /// The chip ID in the extended header, checked by the bootloader.
+    id: u16,
+    /// The address ranges which are mapped to the flash, for code and for data.
+    mapped: [Range<u32>; 2],
+}
+
+impl ImageChip {
+    fn new(chip: EspChip) -> Result<Self, FileDownloadError> {
+        let (id, irom, drom) = match chip {
+            EspChip::Esp32 => (0, 0x400D_0000..0x4040_0000, 0x3F40_0000..0x3F80_0000),
+            EspChip::Esp32s2 => (2, 0x4008_0000..0x4080_0000, 0x3F00_0000..0x3FF8_0000),
+            EspChip::Esp32c3 => (5, 0x4200_0000..0x4280_0000, 0x3C00_0000..0x3C80_0000),
+            EspChip::Esp32s3 => (9, 0x4200_0000..0x4400_0000, 0x3C00_0000..0x3E00_0000),
+            EspChip::Unknown(_) => {
+                return Err(FileDownloadError::InvalidEspImage(
+                    "the image format of the chip is not known",
+                ))
+            }
+        };
+
+        Ok(Self {
+            id,
+            mapped: [irom, drom],
+        })
+    }
+
+    fn is_mapped(&self, address: u32) -> bool {
+        self.mapped.iter().any(|range| range.contains(&address))
+    }
+}
+
+/// Convert the ELF file in `elf_data` into a flash image for `chip`.
+pub(super) fn elf_to_image(elf_data: &[u8], chip: EspChip) -> Result<Vec<u8>, FileDownloadError> {
+    let elf_header = FileHeader32::<Endianness>::parse(elf_data)?;
+    let entry = elf_header.e_entry(elf_header.endian()?);
+
+    let mut extracted_data = Vec::new();
+    if extract_from_elf(&mut extracted_data, elf_data)? == 0 {
+        return Err(FileDownloadError::NoLoadableSegments);
+    }
+
+    let segments = extracted_data
+        .iter()
+        .map(|data| (data.address, data.data))
+        .collect::<Vec<_>>();
+
+    build_image(chip, entry, &segments)
+}
+
+/// Build an image from the `segments` of a program, given by their load address and data.
+///
+/// Like `esptool.py elf2image`, the memory mapped segments come first, in the order of their
+/// addresses, so the application description at the start of the data is the first segment.
+/// A padding segment is inserted in front of a memory mapped segment when it would not be
+/// aligned to the memory mapping. The segments which are copied into RAM by the bootloader
+/// follow at the end.
+fn build_image(
+    chip: EspChip,
+    entry: u32,
+    segments: &[(u32, &[u8])],
+) -> Result<Vec<u8>, FileDownloadError> {
+    let image_chip = ImageChip::new(chip)?;
+
+    let mut mapped = segments
+        .iter()
+        .filter(|(address, _)| image_chip.is_mapped(*address))
+        .collect::<Vec<_>>();
+    mapped.sort_by_key(|(address, _)| *address);
+
+    let mut ram = segments
+        .iter()
+        .filter(|(address, _)| !image_chip.is_mapped(*address))
+        .collect::<Vec<_>>();
+    ram.sort_by_key(|(address, _)| *address);
+
+    match mapped.first() {
+        Some((_, data)) if data.len() >= 4 && data[..4] == APP_DESC_MAGIC.to_le_bytes() => {
+            log::debug!("Found the application description in the first data segment");
+        }
+        _ => log::warn!(
+            "The ELF file has no application description at the start of its data, it might be rejected by the bootloader."
+        ),
+    }
+
+    let mut image = vec![0; HEADER_SIZE];
+    let mut checksum = CHECKSUM_MAGIC;
+    let mut segment_count = 0;
+
+    let mut push_segment = |image: &mut Vec<u8>, address: u32, data: &[u8]| {
+        let padded_len = (data.len() + 3) & !3;
+
+        image.extend_from_slice(&address.to_le_bytes());
+        image.extend_from_slice(&(padded_len as u32).to_le_bytes());
+        image.extend_from_slice(data);
+        image.resize(image.len() + padded_len - data.len(), 0);
+
+        checksum = data.iter().fold(checksum, |checksum, byte| checksum ^ byte);
+        segment_count += 1;
+    };
+
+    for (address, data) in mapped {
+        // The image is programmed at a page boundary of the flash, so offsets in the image
+        // and offsets in the flash are aligned the same way.
+        let data_offset = image.len() as u32 + SEGMENT_HEADER_SIZE;
+        if data_offset % MMU_PAGE_SIZE != address % MMU_PAGE_SIZE {
+            let padding = address.wrapping_sub(data_offset + SEGMENT_HEADER_SIZE) % MMU_PAGE_SIZE;
+            push_segment(&mut image, 0, &vec![0; padding as usize]);
+        }
+
+        push_segment(&mut image, *address, data);
+    }
+
+    for (address, data) in ram {
+        push_segment(&mut image, *address, data);
+    }
+
+    if segment_count > MAX_SEGMENTS {
+        return Err(FileDownloadError::InvalidEspImage(
+            "the bootloader can load at most 16 segments",
+        ));
+    }
+
+    // The checksum is stored in the last byte of a 16 byte block.
+    image.resize(image.len() + 15 - image.len() % 16, 0);
+    image.push(checksum);
+
+    image[0] = IMAGE_MAGIC;
+    image[1] = segment_count as u8;
+    image[2] = FLASH_MODE_DIO;
+    image[3] = (FLASH_SIZE_4MB << 4) | FLASH_FREQ_40MHZ;
+    image[4..8].copy_from_slice(&entry.to_le_bytes());
+    image[8] = WP_PIN_DISABLED;
+    image[12..14].copy_from_slice(&image_chip.id.to_le_bytes());
+    // Any revision of the chip is accepted, and no SHA-256 digest is appended to the image.
+    image[17..19].copy_from_slice(&0xFFFFu16.to_le_bytes());
+
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(image: &[u8], offset: usize) -> (u32, u32) {
+        let word =
+            |offset: usize| u32::from_le_bytes(image[offset..offset + 4].try_into().unwrap());
+        (word(offset), word(offset + 4))
+    }
+
+    #[test]
+    fn mapped_segments_are_aligned() {
+        let mut drom = APP_DESC_MAGIC.to_le_bytes().to_vec();
+        drom.extend_from_slice(&[1; 0x1FE]);
+        let irom = [2; 0x100];
+        let ram = [3; 0x10];
+
+        let image = build_image(
+            EspChip::Esp32c3,
+            0x4200_0000,
+            &[
+                (0x4038_0000, &ram[..]),
+                (0x4200_0020, &irom[..]),
+                (0x3C00_0020, &drom[..]),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(image[..4], [IMAGE_MAGIC, 4, FLASH_MODE_DIO, 0x20]);
+        assert_eq!(image[4..8], 0x4200_0000u32.to_le_bytes());
+        assert_eq!(image[12..14], 5u16.to_le_bytes());
+        assert_eq!(image.len() % 16, 0);
+
+        // The data segment with the application description comes first, at offset 0x20.
+        assert_eq!(segment(&image, 0x18), (0x3C00_0020, 0x204));
+        assert_eq!(image[0x20..0x24], APP_DESC_MAGIC.to_le_bytes());
+
+        // A padding segment aligns the code segment to the next page of the flash.
+        let padding_offset = 0x20 + 0x204;
+        let (padding_address, padding_len) = segment(&image, padding_offset);
+        assert_eq!(padding_address, 0);
+        let irom_offset = padding_offset + 8 + padding_len as usize;
+        assert_eq!(segment(&image, irom_offset), (0x4200_0020, 0x100));
+        assert_eq!((irom_offset + 8) as u32 % MMU_PAGE_SIZE, 0x20);
+
+        // RAM segments come last.
+        assert_eq!(
+            segment(&image, irom_offset + 8 + 0x100),
+            (0x4038_0000, 0x10)
+        );
+
+        let checksum = drom
+            .iter()
+            .chain(&irom)
+            .chain(&ram)
+            .fold(CHECKSUM_MAGIC, |checksum, byte| checksum ^ byte);
+        assert_eq!(image[image.len() - 1], checksum);
+    }
+
+    #[test]
+    fn unknown_chip_is_rejected() {
+        assert!(build_image(EspChip::Unknown(0), 0, &[(0x4038_0000, &[0; 4][..])]).is_err());
+    }
+}
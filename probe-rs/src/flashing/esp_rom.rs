@@ -0,0 +1,632 @@
+//! Programming the SPI flash of Espressif chips through the serial bootloader in their ROM.
+//!
+//! The bootloader is reached through a UART of the chip, or through the USB-Serial-JTAG
+//! peripheral of chips like the ESP32-C3 and ESP32-S3, and does not need a debug probe.
+
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+use std::time::{Duration, Instant};
+
+use probe_rs_target::MemoryRegion;
+#[cfg(feature = "serial")]
+use serialport::{SerialPort, SerialPortType};
+
+/// An error which occurred while talking to the ROM bootloader of an Espressif chip.
+#[derive(thiserror::Error, Debug)]
+pub enum EspRomError {
+    /// The communication with the bootloader failed.
+    #[error("I/O error while communicating with the ROM bootloader")]
+    Io(#[from] io::Error),
+    /// The serial port could not be opened or configured.
+    #[cfg(feature = "serial")]
+    #[error("Failed to open the serial port '{port}'")]
+    Open {
+        /// The name of the serial port.
+        port: String,
+        /// The underlying error.
+        #[source]
+        source: serialport::Error,
+    },
+    /// The bootloader did not answer the synchronization.
+    #[error("The ROM bootloader did not respond. Is the chip in download mode?")]
+    NoResponse,
+    /// The bootloader did not answer a command in time.
+    #[error("Timeout while waiting for the response to the command {0:#04x}")]
+    Timeout(u8),
+    /// The bootloader answered with a malformed packet.
+    #[error("The ROM bootloader sent an invalid response to the command {0:#04x}")]
+    InvalidResponse(u8),
+    /// The bootloader reported an error.
+    #[error("The command {command:#04x} failed with the error code {error:#04x}")]
+    CommandFailed {
+        /// The command which failed.
+        command: u8,
+        /// The error code reported by the bootloader.
+        error: u8,
+    },
+    /// Data which is not located in the flash was passed to the bootloader.
+    #[error("The data at {start:#010x}..{end:#010x} is not located in the flash, which is the only memory that can be programmed through the ROM bootloader.")]
+    NotInFlash {
+        /// The start address of the data.
+        start: u64,
+        /// The end address of the data.
+        end: u64,
+    },
+}
+
+/// The Espressif chips, as identified by the ROM bootloader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EspChip {
+    /// ESP32
+    Esp32,
+    /// ESP32-S2
+    Esp32s2,
+    /// ESP32-S3
+    Esp32s3,
+    /// ESP32-C3
+    Esp32c3,
+    /// A chip which is not known, with the value of its chip detection register.
+    Unknown(u32),
+}
+
+impl EspChip {
+    /// Identify the chip by the value of its chip detection register.
+    fn from_magic(magic: u32) -> Self {
+        match magic {
+            0x00F0_1D83 => EspChip::Esp32,
+            0x0000_07C6 => EspChip::Esp32s2,
+            0x0000_0009 => EspChip::Esp32s3,
+            0x6921_506F | 0x1B31_506F | 0x4881_606F | 0x4361_606F => EspChip::Esp32c3,
+            magic => EspChip::Unknown(magic),
+        }
+    }
+
+    /// Identify the chip of a target by the name of the target.
+    pub(crate) fn from_target_name(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        if name.starts_with("esp32c3") {
+            Some(EspChip::Esp32c3)
+        } else if name.starts_with("esp32s2") {
+            Some(EspChip::Esp32s2)
+        } else if name.starts_with("esp32s3") {
+            Some(EspChip::Esp32s3)
+        } else if name.starts_with("esp32") {
+            Some(EspChip::Esp32)
+        } else {
+            None
+        }
+    }
+
+    /// The bootloaders of all chips except the ESP32 expect an additional parameter,
+    /// which selects encrypted writes, when starting to program the flash.
+    fn supports_encrypted_flash(self) -> bool {
+        self != EspChip::Esp32
+    }
+}
+
+/// A connection to the ROM bootloader of an Espressif chip.
+pub struct EspRomLoader<T> {
+    transport: T,
+    chip: EspChip,
+}
+
+const SLIP_END: u8 = 0xC0;
+const SLIP_ESC: u8 = 0xDB;
+const SLIP_ESC_END: u8 = 0xDC;
+const SLIP_ESC_ESC: u8 = 0xDD;
+
+const FLASH_BEGIN: u8 = 0x02;
+const FLASH_DATA: u8 = 0x03;
+const FLASH_END: u8 = 0x04;
+const SYNC: u8 = 0x08;
+const READ_REG: u8 = 0x0A;
+const SPI_SET_PARAMS: u8 = 0x0B;
+const SPI_ATTACH: u8 = 0x0D;
+
+/// The register which identifies the chip.
+const CHIP_DETECT_MAGIC_REG: u32 = 0x4000_1000;
+
+/// The initial value of the checksum of the data in a `FLASH_DATA` command.
+const CHECKSUM_MAGIC: u8 = 0xEF;
+
+/// The size of the blocks which are programmed with a single `FLASH_DATA` command.
+const FLASH_WRITE_SIZE: usize = 0x400;
+/// The size of the sectors which are erased by the bootloader.
+const FLASH_SECTOR_SIZE: u64 = 0x1000;
+
+/// The size of the flash which the bootloader is configured for. This is the largest flash
+/// which can be addressed by the bootloader, so any data which fits the memory map can be
+/// written.
+const FLASH_SIZE: u32 = 16 * 1024 * 1024;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(3);
+const SYNC_TIMEOUT: Duration = Duration::from_millis(100);
+/// The time it takes at most to erase one MiB of flash.
+const ERASE_TIMEOUT_PER_MB: Duration = Duration::from_secs(30);
+
+/// The USB VID and PID of the USB-Serial-JTAG peripheral.
+#[cfg(feature = "serial")]
+const USB_SERIAL_JTAG: (u16, u16) = (0x303A, 0x1001);
+
+#[cfg(feature = "serial")]
+impl EspRomLoader<Box<dyn SerialPort>> {
+    /// The baud rate which the bootloader uses after a reset.
+    pub const DEFAULT_BAUD_RATE: u32 = 115_200;
+
+    /// Open the serial port `port`, reset the chip into its bootloader and connect to it.
+    ///
+    /// The chip is reset through the DTR and RTS lines of the port, which are connected to the
+    /// boot mode and reset pins on most development boards, and are emulated by the
+    /// USB-Serial-JTAG peripheral.
+    pub fn open(port: &str, baud_rate: u32) -> Result<Self, EspRomError> {
+        let open_error = |source| EspRomError::Open {
+            port: port.to_owned(),
+            source,
+        };
+
+        let usb_serial_jtag = serialport::available_ports()
+            .map_err(open_error)?
+            .into_iter()
+            .any(|info| match info.port_type {
+                SerialPortType::UsbPort(usb) => {
+                    info.port_name == port && (usb.vid, usb.pid) == USB_SERIAL_JTAG
+                }
+                _ => false,
+            });
+
+        let mut transport = serialport::new(port, baud_rate)
+            .timeout(Duration::from_millis(10))
+            .open()
+            .map_err(open_error)?;
+
+        for _ in 0..3 {
+            if usb_serial_jtag {
+                reset_usb_serial_jtag(&mut *transport).map_err(open_error)?;
+            } else {
+                reset_classic(&mut *transport).map_err(open_error)?;
+            }
+
+            match Self::connect(transport) {
+                Ok(loader) => return Ok(loader),
+                Err((returned, EspRomError::NoResponse)) => transport = returned,
+                Err((_, error)) => return Err(error),
+            }
+        }
+
+        Err(EspRomError::NoResponse)
+    }
+
+    /// Reset the chip through the RTS line, which starts the program in the flash.
+    pub fn hard_reset(&mut self) -> Result<(), EspRomError> {
+        let port = self.transport.name().unwrap_or_default();
+        let reset_error = |source| EspRomError::Open {
+            port: port.clone(),
+            source,
+        };
+
+        self.transport
+            .write_request_to_send(true)
+            .map_err(reset_error)?;
+        std::thread::sleep(Duration::from_millis(100));
+        self.transport
+            .write_request_to_send(false)
+            .map_err(reset_error)?;
+
+        Ok(())
+    }
+}
+
+/// Reset a chip into its bootloader, with DTR connected to GPIO0 and RTS connected to EN,
+/// both through inverting transistors.
+#[cfg(feature = "serial")]
+fn reset_classic(port: &mut dyn SerialPort) -> Result<(), serialport::Error> {
+    port.write_data_terminal_ready(false)?;
+    port.write_request_to_send(true)?;
+    std::thread::sleep(Duration::from_millis(100));
+    port.write_data_terminal_ready(true)?;
+    port.write_request_to_send(false)?;
+    std::thread::sleep(Duration::from_millis(50));
+    port.write_data_terminal_ready(false)?;
+
+    Ok(())
+}
+
+/// Reset a chip into its bootloader through the USB-Serial-JTAG peripheral, which decodes
+/// the DTR and RTS lines differently.
+#[cfg(feature = "serial")]
+fn reset_usb_serial_jtag(port: &mut dyn SerialPort) -> Result<(), serialport::Error> {
+    port.write_request_to_send(false)?;
+    port.write_data_terminal_ready(false)?;
+    std::thread::sleep(Duration::from_millis(100));
+    port.write_data_terminal_ready(true)?;
+    port.write_request_to_send(false)?;
+    std::thread::sleep(Duration::from_millis(100));
+    // The reset has to go through the state where both lines are set.
+    port.write_request_to_send(true)?;
+    port.write_data_terminal_ready(false)?;
+    port.write_request_to_send(true)?;
+    std::thread::sleep(Duration::from_millis(100));
+    port.write_data_terminal_ready(false)?;
+    port.write_request_to_send(false)?;
+
+    Ok(())
+}
+
+impl<T: Read + Write> EspRomLoader<T> {
+    /// Connect to a bootloader which is reachable through `transport`.
+    ///
+    /// The chip has to be in download mode already. On failure, the transport is returned.
+    pub fn connect(transport: T) -> Result<Self, (T, EspRomError)> {
+        let mut loader = Self {
+            transport,
+            chip: EspChip::Unknown(0),
+        };
+
+        match loader.initialize() {
+            Ok(()) => Ok(loader),
+            Err(error) => Err((loader.transport, error)),
+        }
+    }
+
+    fn initialize(&mut self) -> Result<(), EspRomError> {
+        self.sync()?;
+
+        self.chip = EspChip::from_magic(self.read_register(CHIP_DETECT_MAGIC_REG)?);
+        log::info!("Connected to the ROM bootloader of an {:?}", self.chip);
+
+        Ok(())
+    }
+
+    /// The chip the bootloader is running on.
+    pub fn chip(&self) -> EspChip {
+        self.chip
+    }
+
+    fn sync(&mut self) -> Result<(), EspRomError> {
+        let mut data = vec![0x07, 0x07, 0x12, 0x20];
+        data.extend_from_slice(&[0x55; 32]);
+
+        for _ in 0..7 {
+            match self.command(SYNC, &data, 0, SYNC_TIMEOUT) {
+                Ok(_) => {
+                    // The bootloader answers each synchronization several times.
+                    while self.read_packet(SYNC_TIMEOUT).is_ok() {}
+                    return Ok(());
+                }
+                Err(EspRomError::Timeout(_)) | Err(EspRomError::InvalidResponse(_)) => continue,
+                Err(error) => return Err(error),
+            }
+        }
+
+        Err(EspRomError::NoResponse)
+    }
+
+    /// Read the register at `address`.
+    pub fn read_register(&mut self, address: u32) -> Result<u32, EspRomError> {
+        self.command(READ_REG, &address.to_le_bytes(), 0, DEFAULT_TIMEOUT)
+    }
+
+    /// Prepare the SPI flash for programming.
+    pub fn attach_flash(&mut self) -> Result<(), EspRomError> {
+        self.command(SPI_ATTACH, &[0; 8], 0, DEFAULT_TIMEOUT)?;
+
+        let params = [0, FLASH_SIZE, 64 * 1024, 4 * 1024, 256, 0xFFFF];
+        self.command(SPI_SET_PARAMS, &words(&params), 0, DEFAULT_TIMEOUT)?;
+
+        Ok(())
+    }
+
+    /// Erase the sectors which contain `offset..offset + data.len()`, and program `data` at `offset`.
+    ///
+    /// `progress` is called with the size of each block after it was programmed.
+    pub fn write_flash(
+        &mut self,
+        offset: u32,
+        data: &[u8],
+        mut progress: impl FnMut(usize),
+    ) -> Result<(), EspRomError> {
+        let size = data.len() as u32;
+        let blocks = data.chunks(FLASH_WRITE_SIZE).count();
+
+        let mut params = vec![size, blocks as u32, FLASH_WRITE_SIZE as u32, offset];
+        if self.chip.supports_encrypted_flash() {
+            params.push(0);
+        }
+
+        // The sectors are erased when the programming is started.
+        let erase_timeout = ERASE_TIMEOUT_PER_MB * (size / (1024 * 1024) + 1);
+        self.command(FLASH_BEGIN, &words(&params), 0, erase_timeout)?;
+
+        for (sequence, block) in data.chunks(FLASH_WRITE_SIZE).enumerate() {
+            let mut block = block.to_vec();
+            block.resize(FLASH_WRITE_SIZE, 0xFF);
+
+            let checksum = block
+                .iter()
+                .fold(CHECKSUM_MAGIC, |checksum, byte| checksum ^ byte);
+
+            let mut command = words(&[block.len() as u32, sequence as u32, 0, 0]);
+            command.extend_from_slice(&block);
+
+            self.command(FLASH_DATA, &command, checksum as u32, DEFAULT_TIMEOUT)?;
+            progress(FLASH_WRITE_SIZE);
+        }
+
+        Ok(())
+    }
+
+    /// Finish the programming, and keep the bootloader running.
+    pub fn finish_flash(&mut self) -> Result<(), EspRomError> {
+        self.command(FLASH_END, &1u32.to_le_bytes(), 0, DEFAULT_TIMEOUT)?;
+        Ok(())
+    }
+
+    /// Send a command and wait for its response. Returns the value of the response.
+    fn command(
+        &mut self,
+        command: u8,
+        data: &[u8],
+        checksum: u32,
+        timeout: Duration,
+    ) -> Result<u32, EspRomError> {
+        let mut packet = vec![0x00, command];
+        packet.extend_from_slice(&(data.len() as u16).to_le_bytes());
+        packet.extend_from_slice(&checksum.to_le_bytes());
+        packet.extend_from_slice(data);
+
+        self.transport.write_all(&slip_encode(&packet))?;
+        self.transport.flush()?;
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let response = match self.read_packet(remaining) {
+                Ok(response) => response,
+                Err(EspRomError::Timeout(_)) => return Err(EspRomError::Timeout(command)),
+                Err(error) => return Err(error),
+            };
+
+            // Responses to earlier commands, e.g. repeated synchronizations, are skipped.
+            if response.len() < 8 || response[0] != 0x01 || response[1] != command {
+                log::debug!("Skipping unexpected response {:02x?}", response);
+                continue;
+            }
+
+            let value = u32::from_le_bytes([response[4], response[5], response[6], response[7]]);
+
+            // The ROM bootloaders of all supported chips end the response with four status bytes.
+            let status = &response[8..];
+            if status.len() < 4 {
+                return Err(EspRomError::InvalidResponse(command));
+            }
+            let status = &status[status.len() - 4..];
+            if status[0] != 0 {
+                return Err(EspRomError::CommandFailed {
+                    command,
+                    error: status[1],
+                });
+            }
+
+            return Ok(value);
+        }
+    }
+
+    /// Read the next SLIP packet.
+    fn read_packet(&mut self, timeout: Duration) -> Result<Vec<u8>, EspRomError> {
+        let deadline = Instant::now() + timeout;
+
+        let mut packet = Vec::new();
+        let mut started = false;
+        let mut escaped = false;
+
+        loop {
+            let mut byte = [0];
+            match self.transport.read(&mut byte) {
+                Ok(1) => (),
+                Ok(_) => {
+                    if Instant::now() > deadline {
+                        return Err(EspRomError::Timeout(0));
+                    }
+                    continue;
+                }
+                Err(error) if error.kind() == io::ErrorKind::TimedOut => {
+                    if Instant::now() > deadline {
+                        return Err(EspRomError::Timeout(0));
+                    }
+                    continue;
+                }
+                Err(error) => return Err(error.into()),
+            }
+
+            match (started, escaped, byte[0]) {
+                (false, _, SLIP_END) => started = true,
+                // Anything outside of a packet, e.g. the boot messages of the ROM, is ignored.
+                (false, _, _) => (),
+                (true, false, SLIP_END) if packet.is_empty() => (),
+                (true, false, SLIP_END) => return Ok(packet),
+                (true, false, SLIP_ESC) => escaped = true,
+                (true, false, byte) => packet.push(byte),
+                (true, true, SLIP_ESC_END) => {
+                    packet.push(SLIP_END);
+                    escaped = false;
+                }
+                (true, true, SLIP_ESC_ESC) => {
+                    packet.push(SLIP_ESC);
+                    escaped = false;
+                }
+                (true, true, _) => return Err(EspRomError::InvalidResponse(0)),
+            }
+        }
+    }
+}
+
+fn words(words: &[u32]) -> Vec<u8> {
+    words.iter().flat_map(|word| word.to_le_bytes()).collect()
+}
+
+fn slip_encode(packet: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(packet.len() + 2);
+    encoded.push(SLIP_END);
+    for &byte in packet {
+        match byte {
+            SLIP_END => encoded.extend_from_slice(&[SLIP_ESC, SLIP_ESC_END]),
+            SLIP_ESC => encoded.extend_from_slice(&[SLIP_ESC, SLIP_ESC_ESC]),
+            byte => encoded.push(byte),
+        }
+    }
+    encoded.push(SLIP_END);
+    encoded
+}
+
+/// Combine the data chunks into segments which are programmed at once, and convert their
+/// addresses to offsets in the flash.
+///
+/// The bootloader erases whole sectors before programming a segment, so chunks which share a
+/// sector have to be programmed together. Gaps between them are filled with the erased value.
+pub(super) fn flash_segments(
+    data: &BTreeMap<u64, Vec<u8>>,
+    memory_map: &[MemoryRegion],
+) -> Result<Vec<(u32, Vec<u8>)>, EspRomError> {
+    let mut segments: Vec<(u64, Vec<u8>)> = Vec::new();
+
+    for (&address, chunk) in data {
+        let end = address + chunk.len() as u64;
+        let region = memory_map
+            .iter()
+            .find_map(|region| match region {
+                MemoryRegion::Nvm(region)
+                    if region.range.start <= address && end <= region.range.end =>
+                {
+                    Some(region)
+                }
+                _ => None,
+            })
+            .ok_or(EspRomError::NotInFlash {
+                start: address,
+                end,
+            })?;
+
+        let offset = address - region.range.start;
+
+        match segments.last_mut() {
+            Some((start, segment))
+                if offset <= align_up(*start + segment.len() as u64, FLASH_SECTOR_SIZE) =>
+            {
+                segment.resize((offset - *start) as usize, 0xFF);
+                segment.extend_from_slice(chunk);
+            }
+            _ => segments.push((offset, chunk.clone())),
+        }
+    }
+
+    Ok(segments
+        .into_iter()
+        .map(|(offset, segment)| (offset as u32, segment))
+        .collect())
+}
+
+fn align_up(value: u64, alignment: u64) -> u64 {
+    value + (alignment - value % alignment) % alignment
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use probe_rs_target::NvmRegion;
+
+    /// A transport which returns the prepared responses to each command, and records the sent data.
+    struct MockTransport {
+        responses: Vec<Vec<u8>>,
+        received: io::Cursor<Vec<u8>>,
+        sent: Vec<u8>,
+    }
+
+    impl Read for MockTransport {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.received.read(buf)
+        }
+    }
+
+    impl Write for MockTransport {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.sent.extend_from_slice(buf);
+            if !self.responses.is_empty() {
+                self.received = io::Cursor::new(self.responses.remove(0));
+            }
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn response(command: u8, value: u32) -> Vec<u8> {
+        let mut packet = vec![0x01, command, 4, 0];
+        packet.extend_from_slice(&value.to_le_bytes());
+        packet.extend_from_slice(&[0, 0, 0, 0]);
+        slip_encode(&packet)
+    }
+
+    #[test]
+    fn slip_escapes_special_bytes() {
+        assert_eq!(
+            slip_encode(&[0x01, SLIP_END, 0x02, SLIP_ESC]),
+            [
+                SLIP_END,
+                0x01,
+                SLIP_ESC,
+                SLIP_ESC_END,
+                0x02,
+                SLIP_ESC,
+                SLIP_ESC_ESC,
+                SLIP_END
+            ]
+        );
+    }
+
+    #[test]
+    fn connect_detects_chip() {
+        let mut sync = b"ESP-ROM:esp32c3-api1-20210207\r\n".to_vec();
+        sync.extend(response(SYNC, 0));
+        sync.extend(response(SYNC, 0));
+
+        let transport = MockTransport {
+            responses: vec![sync, response(READ_REG, 0x1B31_506F)],
+            received: io::Cursor::new(Vec::new()),
+            sent: Vec::new(),
+        };
+
+        let loader = EspRomLoader::connect(transport).map_err(|(_, error)| error);
+        let loader = loader.unwrap();
+        assert_eq!(loader.chip(), EspChip::Esp32c3);
+        assert_eq!(loader.transport.sent[..2], [SLIP_END, 0x00]);
+        assert_eq!(loader.transport.sent[2], SYNC);
+    }
+
+    #[test]
+    fn chunks_in_one_sector_are_combined() {
+        let memory_map = [MemoryRegion::Nvm(NvmRegion {
+            name: None,
+            range: 0..0x40_0000,
+            is_boot_memory: true,
+            cores: vec![],
+        })];
+
+        let mut data = BTreeMap::new();
+        data.insert(0x1000, vec![1; 0x100]);
+        data.insert(0x1200, vec![2; 0x100]);
+        data.insert(0x10000, vec![3; 0x10]);
+
+        let segments = flash_segments(&data, &memory_map).unwrap();
+
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].0, 0x1000);
+        assert_eq!(segments[0].1.len(), 0x300);
+        assert_eq!(segments[0].1[0x150], 0xFF);
+        assert_eq!(segments[1].0, 0x10000);
+
+        data.insert(0x4000_0000, vec![4; 4]);
+        assert!(flash_segments(&data, &memory_map).is_err());
+    }
+}
@@ -2,7 +2,10 @@ use probe_rs_target::{FlashProperties, PageInfo, RamRegion, RawFlashAlgorithm, S
 
 use super::FlashError;
 use crate::core::Architecture;
-use crate::{architecture::riscv, Target};
+use crate::{
+    architecture::{mips, riscv},
+    Target,
+};
 use std::convert::TryInto;
 
 /// A flash algorithm, which has been assembled for a specific
@@ -31,6 +34,8 @@ pub struct FlashAlgorithm {
     pub pc_erase_sector: u64,
     /// Address of the `EraseAll()` entry point. Optional.
     pub pc_erase_all: Option<u64>,
+    /// Address of the `BlankCheck()` entry point. Optional.
+    pub pc_blank_check: Option<u64>,
     /// Initial value of the R9 register for calling flash algo entry points, which
     /// determines where the position-independent data resides.
     pub static_base: u64,
@@ -160,6 +165,9 @@ impl FlashAlgorithm {
     // Header for RISCV Flash Algorithms
     const RISCV_FLASH_BLOB_HEADER: [u32; 2] = [riscv::assembly::EBREAK, riscv::assembly::EBREAK];
 
+    // Header for MIPS Flash Algorithms
+    const MIPS_FLASH_BLOB_HEADER: [u32; 2] = [mips::assembly::SDBBP, mips::assembly::SDBBP];
+
     const ARM_FLASH_BLOB_HEADER: [u32; 8] = [
         0xE00A_BE00,
         0x062D_780D,
@@ -175,6 +183,7 @@ impl FlashAlgorithm {
         match architecture {
             Architecture::Arm => &Self::ARM_FLASH_BLOB_HEADER,
             Architecture::Riscv => &Self::RISCV_FLASH_BLOB_HEADER,
+            Architecture::Mips => &Self::MIPS_FLASH_BLOB_HEADER,
         }
     }
 
@@ -271,6 +280,7 @@ impl FlashAlgorithm {
             pc_program_page: code_start + raw.pc_program_page,
             pc_erase_sector: code_start + raw.pc_erase_sector,
             pc_erase_all: raw.pc_erase_all.map(|v| code_start + v),
+            pc_blank_check: raw.pc_blank_check.map(|v| code_start + v),
             static_base: code_start + raw.data_section_offset,
             begin_stack: addr_stack,
             begin_data: page_buffers[0],
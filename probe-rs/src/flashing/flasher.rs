@@ -1,8 +1,11 @@
 use probe_rs_target::{MemoryRegion, RawFlashAlgorithm};
 
+use super::cache::crc32;
 use super::{
     FlashAlgorithm, FlashBuilder, FlashError, FlashFill, FlashLayout, FlashPage, FlashProgress,
+    FlashSector,
 };
+use crate::architecture::arm::sequences::stm32::SECURE_SRAM;
 use crate::config::NvmRegion;
 use crate::memory::MemoryInterface;
 use crate::{
@@ -10,7 +13,7 @@ use crate::{
     session::Session,
     Core, InstructionSet, RegisterId,
 };
-use std::{fmt::Debug, time::Duration};
+use std::{collections::BTreeMap, fmt::Debug, ops::Range, time::Duration};
 
 pub(super) trait Operation {
     fn operation() -> u32;
@@ -48,6 +51,18 @@ impl Operation for Verify {
     }
 }
 
+/// Selects which sectors are erased before they are programmed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum EraseMode {
+    /// Erase all sectors which are going to be programmed.
+    All,
+    /// Compare the contents of the sectors with the new data first,
+    /// and neither erase nor program sectors which are already up to date.
+    Changed,
+    /// Do not erase any sectors, because they have been erased already.
+    Skip,
+}
+
 /// A structure to control the flash of an attached microchip.
 ///
 /// Once constructed it can be used to program date to the flash.
@@ -55,6 +70,20 @@ pub(super) struct Flasher<'session> {
     session: &'session mut Session,
     core_index: usize,
     flash_algorithm: FlashAlgorithm,
+    /// The CRCs of the sectors as they were programmed by earlier downloads, by their address,
+    /// if a sector cache is used. The CRCs of newly programmed sectors are added to it.
+    pub(super) sector_checksums: Option<BTreeMap<u64, u32>>,
+    /// The state of the core before the flash algorithm was loaded, if the flasher
+    /// was created with [`Flasher::new_preserving_state`].
+    saved_state: Option<SavedState>,
+}
+
+/// The state of a core which is overwritten when a flash algorithm is run.
+struct SavedState {
+    was_halted: bool,
+    registers: Vec<(RegisterId, u64)>,
+    ram_address: u64,
+    ram: Vec<u8>,
 }
 
 impl<'session> Flasher<'session> {
@@ -62,22 +91,66 @@ impl<'session> Flasher<'session> {
         session: &'session mut Session,
         core_index: usize,
         raw_flash_algorithm: &RawFlashAlgorithm,
+    ) -> Result<Self, FlashError> {
+        let mut this = Self::assemble(session, core_index, raw_flash_algorithm)?;
+
+        this.load(true)?;
+
+        Ok(this)
+    }
+
+    /// Create a flasher which does not reset the core, so the flash can be modified while the core is being debugged.
+    ///
+    /// The registers of the core and the RAM which is overwritten by the flash algorithm are saved first,
+    /// and have to be restored with [`Flasher::restore_state`] once flashing has finished.
+    pub(super) fn new_preserving_state(
+        session: &'session mut Session,
+        core_index: usize,
+        raw_flash_algorithm: &RawFlashAlgorithm,
+    ) -> Result<Self, FlashError> {
+        let mut this = Self::assemble(session, core_index, raw_flash_algorithm)?;
+
+        this.saved_state = Some(this.save_state()?);
+
+        if let Err(error) = this.load(false) {
+            if let Err(restore_error) = this.restore_state() {
+                log::warn!("Failed to restore the state of the core: {}", restore_error);
+            }
+            return Err(error);
+        }
+
+        Ok(this)
+    }
+
+    fn assemble(
+        session: &'session mut Session,
+        core_index: usize,
+        raw_flash_algorithm: &RawFlashAlgorithm,
     ) -> Result<Self, FlashError> {
         let target = session.target();
 
         // Find a RAM region from which we can run the algo.
         let mm = &target.memory_map;
         let core_name = &target.cores[core_index].name;
-        let ram = mm
+        let rams: Vec<_> = mm
             .iter()
             .filter_map(|mm| match mm {
                 MemoryRegion::Ram(ram) => Some(ram),
                 _ => None,
             })
-            .find(|ram| {
+            .filter(|ram| {
                 // The RAM must be accessible from the core we're going to run the algo on.
                 ram.cores.contains(core_name)
             })
+            .collect();
+        // With TrustZone enabled, the core is in the secure state after a reset and can not
+        // execute code from the non-secure alias of the RAM.
+        let secure_ram = rams
+            .iter()
+            .find(|ram| SECURE_SRAM.contains(&ram.range.start))
+            .filter(|_| session.trustzone().is_some());
+        let ram = secure_ram
+            .or_else(|| rams.first())
             .ok_or(FlashError::NoRamDefined {
                 name: session.target().name.clone(),
             })?;
@@ -86,15 +159,13 @@ impl<'session> Flasher<'session> {
 
         let flash_algorithm = FlashAlgorithm::assemble_from_raw(raw_flash_algorithm, ram, target)?;
 
-        let mut this = Self {
+        Ok(Self {
             session,
             core_index,
             flash_algorithm,
-        };
-
-        this.load()?;
-
-        Ok(this)
+            sector_checksums: None,
+            saved_state: None,
+        })
     }
 
     pub(super) fn flash_algorithm(&self) -> &FlashAlgorithm {
@@ -105,7 +176,85 @@ impl<'session> Flasher<'session> {
         self.flash_algorithm.page_buffers.len() > 1
     }
 
-    fn load(&mut self) -> Result<(), FlashError> {
+    /// Save the registers of the core and the contents of the RAM used by the flash algorithm.
+    ///
+    /// The core is halted if it is running.
+    fn save_state(&mut self) -> Result<SavedState, FlashError> {
+        let algo = &self.flash_algorithm;
+        let ram_address = algo.load_address;
+        let ram_end = algo
+            .page_buffers
+            .iter()
+            .max()
+            .copied()
+            .unwrap_or(algo.begin_data)
+            + algo.flash_properties.page_size as u64;
+
+        let mut core = self
+            .session
+            .core(self.core_index)
+            .map_err(FlashError::Core)?;
+
+        let was_halted = core.core_halted().map_err(FlashError::Core)?;
+        if !was_halted {
+            core.halt(Duration::from_millis(100))
+                .map_err(FlashError::Core)?;
+        }
+
+        let mut register_ids: Vec<RegisterId> =
+            core.registers().registers().map(RegisterId::from).collect();
+        if core.architecture() == Architecture::Riscv {
+            // The flash algorithm modifies `dcsr` to be able to return to the debugger.
+            register_ids.push(RegisterId::from(0x7b0));
+        }
+
+        let registers = register_ids
+            .into_iter()
+            .map(|id| Ok((id, core.read_core_reg(id)?)))
+            .collect::<Result<Vec<_>, crate::Error>>()
+            .map_err(FlashError::Core)?;
+
+        let mut ram = vec![0; (ram_end - ram_address) as usize];
+        core.read(ram_address, &mut ram).map_err(FlashError::Core)?;
+
+        Ok(SavedState {
+            was_halted,
+            registers,
+            ram_address,
+            ram,
+        })
+    }
+
+    /// Restore the state of the core saved by [`Flasher::new_preserving_state`].
+    ///
+    /// The core is resumed if it was running before.
+    pub(super) fn restore_state(&mut self) -> Result<(), FlashError> {
+        let state = match self.saved_state.take() {
+            Some(state) => state,
+            None => return Ok(()),
+        };
+
+        let mut core = self
+            .session
+            .core(self.core_index)
+            .map_err(FlashError::Core)?;
+
+        core.halt(Duration::from_millis(100))
+            .map_err(FlashError::Core)?;
+        core.write_8(state.ram_address, &state.ram)
+            .map_err(FlashError::Core)?;
+        for (id, value) in state.registers {
+            core.write_core_reg(id, value).map_err(FlashError::Core)?;
+        }
+
+        if !state.was_halted {
+            core.run().map_err(FlashError::Core)?;
+        }
+
+        Ok(())
+    }
+
+    fn load(&mut self, reset: bool) -> Result<(), FlashError> {
         log::debug!("Initializing the flash algorithm.");
         let algo = &mut self.flash_algorithm;
 
@@ -121,9 +270,11 @@ impl<'session> Flasher<'session> {
             .halt(Duration::from_millis(100))
             .map_err(FlashError::Core)?;
         log::debug!("PC = 0x{:08x}", cpu_info.pc);
-        log::debug!("Reset and halt");
-        core.reset_and_halt(Duration::from_millis(500))
-            .map_err(FlashError::Core)?;
+        if reset {
+            log::debug!("Reset and halt");
+            core.reset_and_halt(Duration::from_millis(500))
+                .map_err(FlashError::Core)?;
+        }
 
         // TODO: Possible special preparation of the target such as enabling faster clocks for the flash e.g.
 
@@ -232,7 +383,7 @@ impl<'session> Flasher<'session> {
         flash_builder: &FlashBuilder,
         restore_unwritten_bytes: bool,
         enable_double_buffering: bool,
-        skip_erasing: bool,
+        erase_mode: EraseMode,
         progress: &FlashProgress,
     ) -> Result<(), FlashError> {
         log::debug!("Starting program procedure.");
@@ -243,6 +394,28 @@ impl<'session> Flasher<'session> {
             restore_unwritten_bytes,
         )?;
 
+        // The checksums have to be determined before any sectors are removed from the layout.
+        let erased_byte_value = self.flash_algorithm.flash_properties.erased_byte_value;
+        let checksums = match self.sector_checksums {
+            Some(_) => flash_layout
+                .sectors()
+                .iter()
+                .map(|sector| {
+                    let contents = expected_contents(&flash_layout, sector, erased_byte_value);
+                    (sector.address(), crc32(&contents))
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+
+        if erase_mode != EraseMode::Skip && !checksums.is_empty() {
+            self.remove_cached_sectors(&mut flash_layout, flash_builder, &checksums, progress)?;
+        }
+
+        if erase_mode == EraseMode::Changed {
+            self.remove_unchanged_sectors(&mut flash_layout, restore_unwritten_bytes, progress)?;
+        }
+
         progress.initialized(flash_layout.clone());
 
         log::debug!("Double Buffering enabled: {:?}", enable_double_buffering);
@@ -275,7 +448,7 @@ impl<'session> Flasher<'session> {
         progress.finished_filling();
 
         // Skip erase if necessary
-        if !skip_erasing {
+        if erase_mode != EraseMode::Skip {
             // Erase all necessary sectors
             self.sector_erase(&flash_layout, progress)?;
         }
@@ -287,6 +460,187 @@ impl<'session> Flasher<'session> {
             self.program_simple(&flash_layout, progress)?;
         };
 
+        if let Some(cached) = &mut self.sector_checksums {
+            cached.extend(checksums);
+        }
+
+        Ok(())
+    }
+
+    /// Create a copy of `flash_builder` which additionally contains the current contents of
+    /// the `preserved_ranges` in all sectors of `region` which are going to be erased.
+    pub(super) fn add_preserved_data(
+        &mut self,
+        region: &NvmRegion,
+        flash_builder: &FlashBuilder,
+        preserved_ranges: &[Range<u64>],
+        restore_unwritten_bytes: bool,
+    ) -> Result<FlashBuilder, FlashError> {
+        let flash_layout = flash_builder.build_sectors_and_pages(
+            region,
+            &self.flash_algorithm,
+            restore_unwritten_bytes,
+        )?;
+
+        let preserved = erased_preserved_ranges(&flash_layout, flash_builder, preserved_ranges)?;
+        if preserved.is_empty() {
+            return Ok(flash_builder.clone());
+        }
+
+        let preserved_data = self.run_verify(|active| {
+            preserved
+                .iter()
+                .map(|range| {
+                    let mut data = vec![0; (range.end - range.start) as usize];
+                    active
+                        .core
+                        .read(range.start, &mut data)
+                        .map_err(FlashError::Core)?;
+                    Ok(data)
+                })
+                .collect::<Result<Vec<_>, FlashError>>()
+        })?;
+
+        with_preserved_data(flash_builder, &preserved, preserved_data)
+    }
+
+    /// Remove all sectors from `flash_layout` whose checksum matches the one in the sector cache,
+    /// together with their pages.
+    ///
+    /// To detect a stale cache, the data of the first matching sector is read back from the target.
+    /// If it differs, the cache is discarded and no sectors are removed.
+    fn remove_cached_sectors(
+        &mut self,
+        flash_layout: &mut FlashLayout,
+        flash_builder: &FlashBuilder,
+        checksums: &[(u64, u32)],
+        progress: &FlashProgress,
+    ) -> Result<(), FlashError> {
+        let cached = match &self.sector_checksums {
+            Some(cached) => cached,
+            None => return Ok(()),
+        };
+
+        let changed = checksums
+            .iter()
+            .map(|(address, checksum)| cached.get(address) != Some(checksum))
+            .collect::<Vec<_>>();
+
+        if let Some(index) = changed.iter().position(|&changed| !changed) {
+            let sector = &flash_layout.sectors()[index];
+            let expected = flash_builder
+                .data_in_range(&(sector.address()..sector.address() + sector.size()))
+                .map(|(address, data)| (address, data.to_vec()))
+                .collect::<Vec<_>>();
+
+            let up_to_date = self.run_verify(|active| {
+                for (address, data) in &expected {
+                    let mut current = vec![0; data.len()];
+                    active
+                        .core
+                        .read(*address, &mut current)
+                        .map_err(FlashError::Core)?;
+
+                    if current != *data {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            })?;
+
+            if !up_to_date {
+                log::warn!(
+                    "The sector cache does not match the contents of the target, all sectors will be programmed."
+                );
+                self.sector_checksums = Some(BTreeMap::new());
+                return Ok(());
+            }
+        }
+
+        let sectors = flash_layout.sectors().to_vec();
+        let kept = flash_layout.retain_sectors(&changed);
+        let skipped_size = sectors
+            .iter()
+            .zip(changed.iter())
+            .filter(|(_, &changed)| !changed)
+            .map(|(sector, _)| sector.size())
+            .sum();
+
+        log::info!(
+            "Skipping {} of {} sectors, they are unchanged according to the sector cache.",
+            sectors.len() - kept,
+            sectors.len()
+        );
+        progress.sectors_skipped(sectors.len() - kept, skipped_size);
+
+        Ok(())
+    }
+
+    /// Remove all sectors from `flash_layout` which already contain the data they would
+    /// contain after programming, together with their pages.
+    ///
+    /// Bytes which are restored after erasing, i.e. fills if `restore_unwritten_bytes` is `true`,
+    /// are considered unchanged.
+    fn remove_unchanged_sectors(
+        &mut self,
+        flash_layout: &mut FlashLayout,
+        restore_unwritten_bytes: bool,
+        progress: &FlashProgress,
+    ) -> Result<(), FlashError> {
+        let erased_byte_value = self.flash_algorithm.flash_properties.erased_byte_value;
+
+        let sectors = flash_layout.sectors().to_vec();
+        let current_contents = self.run_verify(|active| {
+            sectors
+                .iter()
+                .map(|sector| {
+                    let mut data = vec![0; sector.size() as usize];
+                    active
+                        .core
+                        .read(sector.address(), &mut data)
+                        .map_err(FlashError::Core)?;
+                    Ok(data)
+                })
+                .collect::<Result<Vec<_>, FlashError>>()
+        })?;
+
+        let changed = sectors
+            .iter()
+            .zip(current_contents.iter())
+            .map(|(sector, current)| {
+                let sector_range = sector.address()..sector.address() + sector.size();
+                let mut expected = expected_contents(flash_layout, sector, erased_byte_value);
+
+                if restore_unwritten_bytes {
+                    for fill in flash_layout.fills() {
+                        let fill_range = fill.address()..fill.address() + fill.size();
+                        if let Some(range) = intersection(&sector_range, &fill_range) {
+                            let range = (range.start - sector_range.start) as usize
+                                ..(range.end - sector_range.start) as usize;
+                            expected[range.clone()].copy_from_slice(&current[range]);
+                        }
+                    }
+                }
+
+                expected != *current
+            })
+            .collect::<Vec<_>>();
+
+        let kept = flash_layout.retain_sectors(&changed);
+        let skipped_size = sectors
+            .iter()
+            .zip(changed.iter())
+            .filter(|(_, &changed)| !changed)
+            .map(|(sector, _)| sector.size())
+            .sum();
+
+        log::info!(
+            "Skipping {} of {} sectors, their contents are already up to date.",
+            sectors.len() - kept,
+            sectors.len()
+        );
+        progress.sectors_skipped(sectors.len() - kept, skipped_size);
+
         Ok(())
     }
 
@@ -477,6 +831,106 @@ impl Debug for Registers {
     }
 }
 
+/// The contents of `sector` after the pages of `flash_layout` which overlap with it have been programmed.
+///
+/// Bytes which are not covered by any page are expected to keep the `erased_byte_value`.
+fn expected_contents(
+    flash_layout: &FlashLayout,
+    sector: &FlashSector,
+    erased_byte_value: u8,
+) -> Vec<u8> {
+    let sector_range = sector.address()..sector.address() + sector.size();
+
+    let mut expected = vec![erased_byte_value; sector.size() as usize];
+    for page in flash_layout.pages() {
+        let page_range = page.address()..page.address() + page.size() as u64;
+        if let Some(range) = intersection(&sector_range, &page_range) {
+            expected[(range.start - sector_range.start) as usize
+                ..(range.end - sector_range.start) as usize]
+                .copy_from_slice(
+                    &page.data()[(range.start - page_range.start) as usize
+                        ..(range.end - page_range.start) as usize],
+                );
+        }
+    }
+
+    expected
+}
+
+/// The parts of the `preserved_ranges` which are located in the sectors of `flash_layout`, and
+/// would therefore be erased.
+///
+/// It is an error if `flash_builder` contains data for one of the `preserved_ranges`.
+///
+/// The returned ranges are sorted and don't overlap, so every preserved byte is read and
+/// programmed again exactly once, even if the `preserved_ranges` overlap.
+fn erased_preserved_ranges(
+    flash_layout: &FlashLayout,
+    flash_builder: &FlashBuilder,
+    preserved_ranges: &[Range<u64>],
+) -> Result<Vec<Range<u64>>, FlashError> {
+    let mut preserved = Vec::new();
+    for range in preserved_ranges {
+        if flash_builder.has_data_in_range(range) {
+            return Err(FlashError::PreservedRangeOverwritten {
+                preserved_addresses: range.clone(),
+            });
+        }
+
+        for sector in flash_layout.sectors() {
+            let sector_range = sector.address()..sector.address() + sector.size();
+            if let Some(range) = intersection(&sector_range, range) {
+                preserved.push(range);
+            }
+        }
+    }
+
+    preserved.sort_by_key(|range| range.start);
+
+    let mut merged: Vec<Range<u64>> = Vec::with_capacity(preserved.len());
+    for range in preserved {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+
+    Ok(merged)
+}
+
+/// Create a copy of `flash_builder` which additionally contains the `preserved_data` which was
+/// read from the `preserved` ranges, so it is programmed again after the erase.
+fn with_preserved_data(
+    flash_builder: &FlashBuilder,
+    preserved: &[Range<u64>],
+    preserved_data: Vec<Vec<u8>>,
+) -> Result<FlashBuilder, FlashError> {
+    let mut flash_builder = flash_builder.clone();
+    for (range, data) in preserved.iter().zip(preserved_data) {
+        log::debug!(
+            "Preserving {:08x}-{:08x} ({} bytes)",
+            range.start,
+            range.end,
+            data.len()
+        );
+        flash_builder.add_data(range.start, &data)?;
+    }
+
+    Ok(flash_builder)
+}
+
+/// The overlap of two address ranges, if there is any.
+fn intersection(a: &Range<u64>, b: &Range<u64>) -> Option<Range<u64>> {
+    let start = a.start.max(b.start);
+    let end = a.end.min(b.end);
+
+    if start < end {
+        Some(start..end)
+    } else {
+        None
+    }
+}
+
 fn into_reg(val: u64) -> Result<u32, FlashError> {
     let reg_value: u32 = val
         .try_into()
@@ -732,6 +1186,52 @@ impl<'probe> ActiveFlasher<'probe, Erase> {
             Ok(())
         }
     }
+
+    /// Check if the `size` bytes at `address` are erased, with the `BlankCheck()` routine of the
+    /// flash algorithm at `pc_blank_check`. The memory is checked by the target, without
+    /// transferring it to the host.
+    pub(super) fn blank_check(
+        &mut self,
+        pc_blank_check: u64,
+        address: u64,
+        size: u64,
+    ) -> Result<bool, FlashError> {
+        let result = self
+            .call_function_and_wait(
+                &Registers {
+                    pc: into_reg(pc_blank_check)?,
+                    r0: Some(into_reg(address)?),
+                    r1: Some(into_reg(size)?),
+                    r2: Some(self.flash_algorithm.flash_properties.erased_byte_value as u32),
+                    r3: None,
+                },
+                false,
+                Duration::from_millis(
+                    self.flash_algorithm.flash_properties.erase_sector_timeout as u64,
+                ),
+            )
+            .map_err(FlashError::Core)?;
+
+        // The routine returns 0 if the memory is blank, and 1 otherwise.
+        Ok(result == 0)
+    }
+}
+
+impl<'probe> ActiveFlasher<'probe, Verify> {
+    /// Check if the `size` bytes at `address` are erased, by reading them back to the host and
+    /// comparing them to the erased byte value.
+    ///
+    /// This is the fallback for flash algorithms without a `BlankCheck()` routine.
+    pub(super) fn read_is_erased(&mut self, address: u64, size: u64) -> Result<bool, FlashError> {
+        let erased_byte_value = self.flash_algorithm.flash_properties.erased_byte_value;
+
+        let mut data = vec![0; size as usize];
+        self.core
+            .read(address, &mut data)
+            .map_err(FlashError::Core)?;
+
+        Ok(data.iter().all(|&byte| byte == erased_byte_value))
+    }
 }
 
 impl<'p> ActiveFlasher<'p, Program> {
@@ -852,3 +1352,92 @@ impl<'p> ActiveFlasher<'p, Program> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{erased_preserved_ranges, with_preserved_data};
+    use crate::flashing::test_flash::demo_flash;
+    use crate::flashing::{FlashBuilder, FlashError};
+
+    #[test]
+    fn preserved_range_keeps_old_contents_after_erase() {
+        let (region, flash_algorithm) = demo_flash(0x4000, 0x1000);
+        let mut flash_builder = FlashBuilder::new();
+        flash_builder.add_data(0x0000, &[0x42; 0x100]).unwrap();
+
+        let flash_layout = flash_builder
+            .build_sectors_and_pages(&region, &flash_algorithm, false)
+            .unwrap();
+
+        // Only the part in the erased sector has to be preserved.
+        let preserved =
+            erased_preserved_ranges(&flash_layout, &flash_builder, &[0x0F80..0x1080]).unwrap();
+        assert_eq!(preserved, vec![0x0F80..0x1000]);
+
+        // The old contents, as read from the target before the erase.
+        let flash_builder =
+            with_preserved_data(&flash_builder, &preserved, vec![vec![0xAA; 0x80]]).unwrap();
+        let flash_layout = flash_builder
+            .build_sectors_and_pages(&region, &flash_algorithm, false)
+            .unwrap();
+
+        assert_eq!(flash_layout.sectors().len(), 1);
+        let page = flash_layout
+            .pages()
+            .iter()
+            .find(|page| page.address() == 0x0C00)
+            .unwrap();
+        assert_eq!(&page.data()[0x380..], &[0xAA; 0x80][..]);
+        assert_eq!(&page.data()[..0x380], &[0xFF; 0x380][..]);
+    }
+
+    #[test]
+    fn overlapping_preserved_ranges_are_merged() {
+        let (region, flash_algorithm) = demo_flash(0x4000, 0x1000);
+        let mut flash_builder = FlashBuilder::new();
+        flash_builder.add_data(0x0000, &[0x42; 0x100]).unwrap();
+
+        let flash_layout = flash_builder
+            .build_sectors_and_pages(&region, &flash_algorithm, false)
+            .unwrap();
+
+        let preserved = erased_preserved_ranges(
+            &flash_layout,
+            &flash_builder,
+            &[0x0E00..0x0F00, 0x0F80..0x1080, 0x0E80..0x0FC0],
+        )
+        .unwrap();
+        assert_eq!(preserved, vec![0x0E00..0x1000]);
+
+        // Each preserved byte is added only once, so this doesn't fail with overlapping data.
+        let flash_builder =
+            with_preserved_data(&flash_builder, &preserved, vec![vec![0xAA; 0x200]]).unwrap();
+        let flash_layout = flash_builder
+            .build_sectors_and_pages(&region, &flash_algorithm, false)
+            .unwrap();
+
+        let page = flash_layout
+            .pages()
+            .iter()
+            .find(|page| page.address() == 0x0C00)
+            .unwrap();
+        assert_eq!(&page.data()[0x200..], &[0xAA; 0x200][..]);
+    }
+
+    #[test]
+    fn preserved_range_with_new_data_is_rejected() {
+        let (region, flash_algorithm) = demo_flash(0x4000, 0x1000);
+        let mut flash_builder = FlashBuilder::new();
+        flash_builder.add_data(0x0800, &[0x42; 0x10]).unwrap();
+
+        let flash_layout = flash_builder
+            .build_sectors_and_pages(&region, &flash_algorithm, false)
+            .unwrap();
+
+        let result = erased_preserved_ranges(&flash_layout, &flash_builder, &[0x0800..0x0900]);
+        assert!(matches!(
+            result,
+            Err(FlashError::PreservedRangeOverwritten { preserved_addresses }) if preserved_addresses == (0x0800..0x0900)
+        ));
+    }
+}
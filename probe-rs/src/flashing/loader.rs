@@ -1,15 +1,20 @@
 use ihex::Record;
 use probe_rs_target::{
-    MemoryRange, MemoryRegion, NvmRegion, RawFlashAlgorithm, TargetDescriptionSource,
+    MemoryRange, MemoryRegion, NvmRegion, RamRegion, RawFlashAlgorithm, TargetDescriptionSource,
 };
 use std::collections::HashMap;
-use std::io::{Read, Seek, SeekFrom};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::ops::Range;
+use std::path::Path;
+use std::time::Instant;
 
 use super::builder::FlashBuilder;
+use super::esp_image::{elf_to_image, APP_OFFSET};
+use super::esp_rom::{flash_segments, EspChip, EspRomLoader};
 use super::{
-    extract_from_elf, BinOptions, DownloadOptions, FileDownloadError, FlashError, FlashProgress,
-    Flasher,
+    extract_from_elf, BinOptions, DownloadOptions, EraseMode, FileDownloadError, FlashError,
+    FlashProgress, Flasher, Format,
 };
 use crate::memory::MemoryInterface;
 use crate::session::Session;
@@ -21,10 +26,28 @@ use crate::Target;
 /// Once you are done adding all your data, use `commit()` to flash the data.
 /// The flash loader will make sure to select the appropriate flash region for the right data chunks.
 /// Region crossing data chunks are allowed as long as the regions are contiguous.
+///
+/// Multiple images can be added to one loader with [load_image()](FlashLoader::load_image),
+/// so they are programmed in a single operation.
+///
+/// All data is checked against the memory map of the target when it is added,
+/// so an image which does not fit the target is rejected before anything is erased.
 pub struct FlashLoader {
     memory_map: Vec<MemoryRegion>,
     builder: FlashBuilder,
 
+    /// Skip data which does not fit the memory map, instead of rejecting it.
+    skip_invalid_data: bool,
+
+    /// The image which is currently being loaded, and the address ranges of all loaded images,
+    /// used to report overlapping images.
+    current_image: Option<String>,
+    images: Vec<(String, Range<u64>)>,
+
+    /// The Espressif chip of the target, if any. ELF files for these chips are converted into
+    /// a flash image for the second stage bootloader.
+    esp_chip: Option<EspChip>,
+
     /// Source of the flash description,
     /// used for diagnostics.
     source: TargetDescriptionSource,
@@ -36,28 +59,90 @@ impl FlashLoader {
         Self {
             memory_map,
             builder: FlashBuilder::new(),
+            skip_invalid_data: false,
+            current_image: None,
+            images: Vec::new(),
+            esp_chip: None,
             source,
         }
     }
 
-    /// Check the given address range is completely covered by the memory map,
-    /// possibly by multiple memory regions.
-    fn check_data_in_memory_map(&mut self, range: Range<u64>) -> Result<(), FlashError> {
+    /// Convert ELF files into flash images for the second stage bootloader of the Espressif `chip`.
+    pub(crate) fn set_esp_chip(&mut self, chip: EspChip) {
+        self.esp_chip = Some(chip);
+    }
+
+    /// The start address of the boot memory, or 0 if the target has no boot memory.
+    fn boot_memory_start(&self) -> u64 {
+        self.memory_map
+            .iter()
+            .find_map(|region| match region {
+                MemoryRegion::Nvm(region) if region.is_boot_memory => Some(region.range.start),
+                _ => None,
+            })
+            .unwrap_or(0)
+    }
+
+    /// Skip data which is outside of the memory map or in a memory region which can not be written,
+    /// with a warning, instead of rejecting it with an error.
+    pub fn set_skip_invalid_data(&mut self, skip: bool) {
+        self.skip_invalid_data = skip;
+    }
+
+    /// Split the given address range into the parts which are covered by writable memory regions,
+    /// possibly by multiple contiguous ones, and the parts which are not.
+    ///
+    /// Returns the writable parts and an error for every part which is not writable.
+    fn check_data_in_memory_map(&self, range: Range<u64>) -> (Vec<Range<u64>>, Vec<FlashError>) {
+        let mut valid: Vec<Range<u64>> = Vec::new();
+        let mut errors = Vec::new();
+
         let mut address = range.start;
         while address < range.end {
             match Self::get_region_for_address(&self.memory_map, address) {
-                Some(MemoryRegion::Nvm(region)) => address = region.range.end,
-                Some(MemoryRegion::Ram(region)) => address = region.range.end,
-                _ => {
-                    return Err(FlashError::NoSuitableNvm {
-                        start: range.start,
-                        end: range.end,
+                Some(MemoryRegion::Nvm(NvmRegion { range: region, .. }))
+                | Some(MemoryRegion::Ram(RamRegion { range: region, .. })) => {
+                    let end = region.end.min(range.end);
+                    match valid.last_mut() {
+                        Some(last) if last.end == address => last.end = end,
+                        _ => valid.push(address..end),
+                    }
+                    address = end;
+                }
+                Some(MemoryRegion::Generic(region)) => {
+                    let end = region.range.end.min(range.end);
+                    errors.push(FlashError::DataInReservedRegion {
+                        start: address,
+                        end,
+                        region: region.name.clone().unwrap_or_else(|| "unnamed".into()),
+                    });
+                    address = end;
+                }
+                None => {
+                    // Skip ahead to the next region.
+                    let end = self
+                        .memory_map
+                        .iter()
+                        .map(|region| match region {
+                            MemoryRegion::Ram(r) => r.range.start,
+                            MemoryRegion::Nvm(r) => r.range.start,
+                            MemoryRegion::Generic(r) => r.range.start,
+                        })
+                        .filter(|&start| start > address)
+                        .min()
+                        .unwrap_or(range.end)
+                        .min(range.end);
+                    errors.push(FlashError::DataOutsideMemoryMap {
+                        start: address,
+                        end,
                         description_source: self.source.clone(),
-                    })
+                    });
+                    address = end;
                 }
             }
         }
-        Ok(())
+
+        (valid, errors)
     }
 
     /// Stages a chunk of data to be programmed.
@@ -70,8 +155,87 @@ impl FlashLoader {
             data.len()
         );
 
-        self.check_data_in_memory_map(address..address + data.len() as u64)?;
-        self.builder.add_data(address, data)
+        let (valid, errors) = self.check_data_in_memory_map(address..address + data.len() as u64);
+
+        if self.skip_invalid_data {
+            for error in errors {
+                log::warn!("Skipping data: {}", error);
+            }
+        } else if let Some(error) = errors.into_iter().next() {
+            return Err(error);
+        }
+
+        for range in valid {
+            let offset = (range.start - address) as usize;
+            let size = (range.end - range.start) as usize;
+            self.builder
+                .add_data(range.start, &data[offset..offset + size])
+                .map_err(|error| self.name_overlapping_image(error))?;
+
+            if let Some(image) = &self.current_image {
+                match self.images.last_mut() {
+                    Some((name, last)) if name == image && last.end == range.start => {
+                        last.end = range.end
+                    }
+                    _ => self.images.push((image.clone(), range)),
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Turn an overlap of data into an overlap of images, if the data belongs to two different images.
+    fn name_overlapping_image(&self, error: FlashError) -> FlashError {
+        if let FlashError::DataOverlaps {
+            added_addresses,
+            existing_addresses,
+        } = &error
+        {
+            let existing_image = self.images.iter().find(|(_, range)| {
+                range.start < existing_addresses.end && existing_addresses.start < range.end
+            });
+
+            if let (Some(image), Some((existing_image, _))) = (&self.current_image, existing_image)
+            {
+                if image != existing_image {
+                    return FlashError::ImagesOverlap {
+                        image: image.clone(),
+                        existing_image: existing_image.clone(),
+                        added_addresses: added_addresses.clone(),
+                        existing_addresses: existing_addresses.clone(),
+                    };
+                }
+            }
+        }
+
+        error
+    }
+
+    /// Reads the image file at `path` in the given `format` and adds its data to the loader.
+    ///
+    /// Multiple images, e.g. a bootloader ELF, an application HEX and a file system BIN at an offset,
+    /// can be loaded into the same loader. They are then erased and programmed in one operation,
+    /// so sectors shared by the images are only erased once. The images must not overlap.
+    pub fn load_image<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        format: Format,
+    ) -> Result<(), FileDownloadError> {
+        let path = path.as_ref();
+        let mut file = File::open(path)?;
+
+        self.current_image = Some(path.display().to_string());
+
+        let result = match format {
+            Format::Bin(options) => self.load_bin_data(&mut file, options),
+            Format::Elf => self.load_elf_data(&mut file),
+            Format::Hex => self.load_hex_data(&mut file),
+        };
+
+        self.current_image = None;
+
+        result
     }
 
     pub(super) fn get_region_for_address(
@@ -92,6 +256,8 @@ impl FlashLoader {
     }
 
     /// Reads the data from the binary file and adds it to the loader without splitting it into flash instructions yet.
+    ///
+    /// If no base address is given in `options`, the data is placed at the start of the boot memory.
     pub fn load_bin_data<T: Read + Seek>(
         &mut self,
         file: &mut T,
@@ -103,16 +269,12 @@ impl FlashLoader {
         let mut buf = Vec::new();
         file.read_to_end(&mut buf)?;
 
-        self.add_data(
-            if let Some(address) = options.base_address {
-                address
-            } else {
-                // If no base address is specified use the start of the boot memory.
-                // TODO: Implement this as soon as we know targets.
-                0
-            },
-            &buf,
-        )?;
+        // If no base address is specified use the start of the boot memory.
+        let base_address = options
+            .base_address
+            .unwrap_or_else(|| self.boot_memory_start());
+
+        self.add_data(base_address, &buf)?;
 
         Ok(())
     }
@@ -149,10 +311,28 @@ impl FlashLoader {
 
     /// Prepares the data sections that have to be loaded into flash from an ELF file.
     /// This will validate the ELF file and transform all its data into sections but no flash loader commands yet.
+    ///
+    /// On Espressif targets, the ELF file is converted into a flash image for the second stage
+    /// bootloader instead, which is added at the offset of the first application partition.
     pub fn load_elf_data<T: Read>(&mut self, file: &mut T) -> Result<(), FileDownloadError> {
         let mut elf_buffer = Vec::new();
         file.read_to_end(&mut elf_buffer)?;
 
+        if let Some(chip) = self.esp_chip {
+            let image = elf_to_image(&elf_buffer, chip)?;
+            let address = self.boot_memory_start() + APP_OFFSET;
+
+            log::info!(
+                "Converted the ELF file into a flash image of {} bytes at {:#010x}",
+                image.len(),
+                address
+            );
+
+            self.add_data(address, &image)?;
+
+            return Ok(());
+        }
+
         let mut extracted_data = Vec::new();
 
         let num_sections = extract_from_elf(&mut extracted_data, &elf_buffer)?;
@@ -183,7 +363,11 @@ impl FlashLoader {
         }
 
         for data in extracted_data {
-            self.add_data(data.address.into(), data.data)?;
+            self.add_data(data.address.into(), data.data)
+                .map_err(|source| FileDownloadError::InvalidSegment {
+                    sections: data.section_names.join(", "),
+                    source: Box::new(source),
+                })?;
         }
 
         Ok(())
@@ -276,6 +460,15 @@ impl FlashLoader {
             }
         }
 
+        if let Some(trustzone) = session.trustzone() {
+            for (address, data) in self.builder.data_in_range(&(0..u64::MAX)) {
+                let range = address..address + data.len() as u64;
+                if let Some(addresses) = trustzone.flash_alias_mismatch(&range) {
+                    return Err(FlashError::TrustZoneAliasMismatch { addresses });
+                }
+            }
+        }
+
         if options.dry_run {
             log::info!("Skipping programming, dry run!");
 
@@ -288,6 +481,20 @@ impl FlashLoader {
             return Ok(());
         }
 
+        if !algos.is_empty() {
+            session.sequence_flash_start().map_err(FlashError::Core)?;
+        }
+
+        // The cache is discarded on disk until the download has finished successfully,
+        // so an interrupted download can not leave stale entries behind.
+        let mut sector_cache = options.sector_cache;
+        let mut sector_checksums = None;
+        if let Some(cache) = &mut sector_cache {
+            sector_checksums = Some(cache.checksums());
+            cache.invalidate();
+            cache.save()?;
+        }
+
         // Iterate all flash algorithms we need to use.
         for ((algo_name, core_name), regions) in algos {
             log::debug!("Flashing ranges for algo: {}", algo_name);
@@ -303,6 +510,7 @@ impl FlashLoader {
                 .position(|c| c.name == core_name)
                 .unwrap();
             let mut flasher = Flasher::new(session, core, &algo)?;
+            flasher.sector_checksums = sector_checksums.take();
 
             let mut do_chip_erase = options.do_chip_erase;
 
@@ -313,13 +521,31 @@ impl FlashLoader {
                 log::warn!("A manual sector erase will be performed.");
             }
 
+            // A chip erase would destroy the preserved ranges before they can be read.
+            if do_chip_erase && !options.preserved_ranges.is_empty() {
+                do_chip_erase = false;
+                log::warn!(
+                    "Chip erase was selected, but some address ranges have to be preserved."
+                );
+                log::warn!("A manual sector erase will be performed.");
+            }
+
             if do_chip_erase {
                 log::debug!("    Doing chip erase...");
-                flasher.run_erase(|active| active.erase_all())?;
+                if let Some(progress) = options.progress {
+                    progress.started_erasing();
+                }
+
+                let result = flasher.run_erase(|active| active.erase_all());
 
                 if let Some(progress) = options.progress {
-                    progress.finished_erasing();
+                    if result.is_ok() {
+                        progress.finished_erasing();
+                    } else {
+                        progress.failed_erasing();
+                    }
                 }
+                result?;
             }
 
             let mut do_use_double_buffering = flasher.double_buffering_supported();
@@ -328,6 +554,21 @@ impl FlashLoader {
                 do_use_double_buffering = false;
             }
 
+            // The previous contents are unknown after a chip erase or an external erase.
+            if options.skip_erase || do_chip_erase {
+                if let Some(checksums) = &mut flasher.sector_checksums {
+                    checksums.clear();
+                }
+            }
+
+            let erase_mode = if options.skip_erase || do_chip_erase {
+                EraseMode::Skip
+            } else if options.skip_unchanged_sectors {
+                EraseMode::Changed
+            } else {
+                EraseMode::All
+            };
+
             for region in regions {
                 log::debug!(
                     "    programming region: {:08x}-{:08x} ({} bytes)",
@@ -336,16 +577,29 @@ impl FlashLoader {
                     region.range.end - region.range.start
                 );
 
+                let preserved_builder = if options.preserved_ranges.is_empty() {
+                    None
+                } else {
+                    Some(flasher.add_preserved_data(
+                        &region,
+                        &self.builder,
+                        &options.preserved_ranges,
+                        options.keep_unwritten_bytes,
+                    )?)
+                };
+
                 // Program the data.
                 flasher.program(
                     &region,
-                    &self.builder,
+                    preserved_builder.as_ref().unwrap_or(&self.builder),
                     options.keep_unwritten_bytes,
                     do_use_double_buffering,
-                    options.skip_erase || do_chip_erase,
+                    erase_mode,
                     options.progress.unwrap_or(&FlashProgress::new(|_| {})),
                 )?;
             }
+
+            sector_checksums = flasher.sector_checksums.take();
         }
 
         log::debug!("committing RAM!");
@@ -394,35 +648,141 @@ impl FlashLoader {
 
         if options.verify {
             log::debug!("Verifying!");
-            for (&address, data) in &self.builder.data {
+            if let Some(progress) = options.progress {
+                progress.started_verifying();
+            }
+
+            let result = self.verify(session, options.progress);
+
+            if let Some(progress) = options.progress {
+                if result.is_ok() {
+                    progress.finished_verifying();
+                } else {
+                    progress.failed_verifying();
+                }
+            }
+            result?;
+        }
+
+        if let (Some(cache), Some(checksums)) = (&mut sector_cache, sector_checksums) {
+            cache.set_checksums(checksums);
+            cache.save()?;
+        }
+
+        if let Some(progress) = options.progress {
+            progress.finished();
+        }
+
+        Ok(())
+    }
+
+    /// Writes all the stored data chunks to the SPI flash of an Espressif chip, through the
+    /// serial bootloader in its ROM instead of a debug probe.
+    ///
+    /// Only data in the flash can be written this way. The bootloader always erases whole
+    /// sectors, and can not read back the flash, so `keep_unwritten_bytes`, `preserved_ranges`,
+    /// `skip_unchanged_sectors` and `verify` are not supported.
+    pub fn commit_esp_rom<T: Read + Write>(
+        &self,
+        loader: &mut EspRomLoader<T>,
+        options: DownloadOptions<'_>,
+    ) -> Result<(), FlashError> {
+        let segments = flash_segments(&self.builder.data, &self.memory_map)?;
+
+        if options.keep_unwritten_bytes
+            || options.skip_unchanged_sectors
+            || options.verify
+            || !options.preserved_ranges.is_empty()
+        {
+            log::warn!("Preserving, skipping or verifying data is not supported by the ROM bootloader, the options are ignored.");
+        }
+
+        if options.dry_run {
+            log::info!("Skipping programming, dry run!");
+
+            if let Some(progress) = options.progress {
+                progress.failed_programming();
+            }
+
+            return Ok(());
+        }
+
+        if let Some(progress) = options.progress {
+            progress.started_programming();
+        }
+
+        let result = (|| {
+            loader.attach_flash()?;
+
+            for (offset, data) in &segments {
                 log::debug!(
-                    "    data: {:08x}-{:08x} ({} bytes)",
-                    address,
-                    address + data.len() as u64,
-                    data.len()
+                    "Programming {} bytes at flash offset {:#08x}",
+                    data.len(),
+                    offset
                 );
 
-                let associated_region = session
-                    .target()
-                    .get_memory_region_by_address(address)
-                    .unwrap();
-                let core_name = match associated_region {
-                    MemoryRegion::Ram(r) => &r.cores,
-                    MemoryRegion::Generic(r) => &r.cores,
-                    MemoryRegion::Nvm(r) => &r.cores,
-                }
-                .first()
+                let mut last = Instant::now();
+                loader.write_flash(*offset, data, |size| {
+                    if let Some(progress) = options.progress {
+                        progress.page_programmed(size as u32, last.elapsed());
+                    }
+                    last = Instant::now();
+                })?;
+            }
+
+            loader.finish_flash()
+        })();
+
+        if let Some(progress) = options.progress {
+            if result.is_ok() {
+                progress.finished_programming();
+                progress.finished();
+            } else {
+                progress.failed_programming();
+            }
+        }
+
+        Ok(result?)
+    }
+
+    /// Read back all data of the loader and compare it to the contents of the memory.
+    fn verify(
+        &self,
+        session: &mut Session,
+        progress: Option<&FlashProgress>,
+    ) -> Result<(), FlashError> {
+        for (&address, data) in &self.builder.data {
+            log::debug!(
+                "    data: {:08x}-{:08x} ({} bytes)",
+                address,
+                address + data.len() as u64,
+                data.len()
+            );
+
+            let associated_region = session
+                .target()
+                .get_memory_region_by_address(address)
                 .unwrap();
-                let core_index = session.target().core_index_by_name(core_name).unwrap();
-                let mut core = session.core(core_index).map_err(FlashError::Core)?;
+            let core_name = match associated_region {
+                MemoryRegion::Ram(r) => &r.cores,
+                MemoryRegion::Generic(r) => &r.cores,
+                MemoryRegion::Nvm(r) => &r.cores,
+            }
+            .first()
+            .unwrap();
+            let core_index = session.target().core_index_by_name(core_name).unwrap();
+            let mut core = session.core(core_index).map_err(FlashError::Core)?;
 
-                let mut written_data = vec![0; data.len()];
-                core.read(address as u64, &mut written_data)
-                    .map_err(FlashError::Core)?;
+            let mut written_data = vec![0; data.len()];
+            core.read(address as u64, &mut written_data)
+                .map_err(FlashError::Core)?;
 
-                if data != &written_data {
-                    return Err(FlashError::Verify);
-                }
+            if data != &written_data {
+                return Err(FlashError::Verify);
+            }
+
+            if let Some(progress) = progress {
+                progress.data_verified(data.len() as u64);
             }
         }
 
@@ -474,3 +834,178 @@ impl FlashLoader {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use probe_rs_target::GenericRegion;
+
+    use super::*;
+
+    fn loader() -> FlashLoader {
+        FlashLoader::new(
+            vec![
+                MemoryRegion::Ram(RamRegion {
+                    name: Some("RAM".into()),
+                    is_boot_memory: false,
+                    range: 0x2000_0000..0x2000_4000,
+                    cores: vec!["main".into()],
+                }),
+                MemoryRegion::Nvm(NvmRegion {
+                    name: Some("FLASH".into()),
+                    is_boot_memory: true,
+                    range: 0x0800_0000..0x0801_0000,
+                    cores: vec!["main".into()],
+                }),
+                MemoryRegion::Generic(GenericRegion {
+                    name: Some("OTP".into()),
+                    range: 0x0801_0000..0x0801_0400,
+                    cores: vec!["main".into()],
+                }),
+            ],
+            TargetDescriptionSource::BuiltIn,
+        )
+    }
+
+    #[test]
+    fn bin_data_defaults_to_boot_memory() {
+        let mut loader = loader();
+        loader
+            .load_bin_data(
+                &mut Cursor::new(vec![1, 2, 3, 4]),
+                BinOptions {
+                    base_address: None,
+                    skip: 1,
+                },
+            )
+            .unwrap();
+
+        assert_eq!(
+            loader.builder.data.get(&0x0800_0000),
+            Some(&vec![2u8, 3, 4])
+        );
+    }
+
+    #[test]
+    fn hex_data_uses_extended_linear_address() {
+        let hex = ":020000040800F2\n:0400100001020304E2\n:00000001FF\n";
+
+        let mut loader = loader();
+        loader.load_hex_data(&mut Cursor::new(hex)).unwrap();
+
+        assert_eq!(
+            loader.builder.data.get(&0x0800_0010),
+            Some(&vec![1u8, 2, 3, 4])
+        );
+    }
+
+    #[test]
+    fn hex_data_outside_memory_map_is_rejected() {
+        let hex = ":020000040900F1\n:0400100001020304E2\n:00000001FF\n";
+
+        let mut loader = loader();
+        assert!(matches!(
+            loader.load_hex_data(&mut Cursor::new(hex)),
+            Err(FileDownloadError::Flash(FlashError::DataOutsideMemoryMap {
+                start: 0x0900_0010,
+                end: 0x0900_0014,
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn data_in_reserved_region_is_rejected() {
+        let mut loader = loader();
+
+        assert!(matches!(
+            loader.add_data(0x0800_fffe, &[0; 4]),
+            Err(FlashError::DataInReservedRegion {
+                start: 0x0801_0000,
+                end: 0x0801_0002,
+                ..
+            })
+        ));
+        assert!(loader.builder.data.is_empty());
+    }
+
+    #[test]
+    fn overlapping_images_are_named() {
+        let directory = std::env::temp_dir();
+        let bootloader = directory.join(format!("probe-rs-bootloader-{}.bin", std::process::id()));
+        let application =
+            directory.join(format!("probe-rs-application-{}.bin", std::process::id()));
+        std::fs::write(&bootloader, [0u8; 0x100]).unwrap();
+        std::fs::write(&application, [1u8; 0x100]).unwrap();
+
+        let bin = |base_address| {
+            Format::Bin(BinOptions {
+                base_address: Some(base_address),
+                skip: 0,
+            })
+        };
+
+        let mut loader = loader();
+        loader.load_image(&bootloader, bin(0x0800_0000)).unwrap();
+        loader.load_image(&application, bin(0x0800_0100)).unwrap();
+        let result = loader.load_image(&application, bin(0x0800_00f0));
+
+        std::fs::remove_file(&bootloader).unwrap();
+        std::fs::remove_file(&application).unwrap();
+
+        match result {
+            Err(FileDownloadError::Flash(FlashError::ImagesOverlap {
+                image,
+                existing_image,
+                ..
+            })) => {
+                assert_eq!(image, application.display().to_string());
+                assert_eq!(existing_image, bootloader.display().to_string());
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn invalid_data_can_be_skipped() {
+        let mut loader = loader();
+        loader.set_skip_invalid_data(true);
+
+        loader.add_data(0x07ff_fffe, &[1, 2, 3, 4]).unwrap();
+        loader.add_data(0x0800_fffe, &[5, 6, 7, 8]).unwrap();
+
+        assert_eq!(loader.builder.data.get(&0x0800_0000), Some(&vec![3u8, 4]));
+        assert_eq!(loader.builder.data.get(&0x0800_fffe), Some(&vec![5u8, 6]));
+        assert_eq!(loader.builder.data.len(), 2);
+    }
+
+    #[test]
+    fn secure_and_non_secure_flash_use_their_algorithms() {
+        let target = crate::config::get_target_by_name("STM32L552ZETxQ").unwrap();
+
+        let algorithms = target
+            .memory_map
+            .iter()
+            .filter_map(|region| match region {
+                MemoryRegion::Nvm(region) => Some(region),
+                _ => None,
+            })
+            .map(|region| {
+                let algorithm = FlashLoader::get_flash_algorithm_for_region(region, &target)
+                    .unwrap()
+                    .name
+                    .clone();
+                (region.range.start, algorithm)
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            algorithms,
+            [
+                (0x0800_0000, "stm32l5x_512_0800".to_owned()),
+                (0x0C00_0000, "stm32l5x_512_0c00".to_owned()),
+            ]
+        );
+    }
+}
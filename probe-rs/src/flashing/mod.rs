@@ -43,22 +43,30 @@
 //!
 //!
 
+mod breakpoints;
 mod builder;
+mod cache;
 mod download;
 mod erase;
 mod error;
+mod esp_image;
+mod esp_rom;
 mod flash_algorithm;
 mod flasher;
 mod loader;
 mod progress;
+#[cfg(test)]
+mod test_flash;
 mod visualizer;
 
 use builder::*;
 use flasher::*;
 
+pub use cache::SectorCache;
 pub use download::*;
 pub use erase::*;
 pub use error::*;
+pub use esp_rom::*;
 pub use flash_algorithm::*;
 pub use loader::*;
 pub use progress::*;
@@ -1,11 +1,15 @@
 use super::FlashLayout;
-use std::time::Duration;
+use std::cell::{Cell, RefCell};
+use std::time::{Duration, Instant};
 
 /// A structure to manage the flashing procedure progress reporting.
 ///
 /// This struct stores a handler closure which will be called everytime an event happens during the flashing process.
 /// Such an event can be start or finish of the flashing procedure or a progress report, as well as some more events.
 ///
+/// Additionally, it collects the statistics of all flashing procedures it was used for,
+/// which are available with [`FlashProgress::summary`].
+///
 /// # Example
 ///
 /// ```
@@ -16,6 +20,8 @@ use std::time::Duration;
 /// ```
 pub struct FlashProgress {
     handler: Box<dyn Fn(ProgressEvent)>,
+    summary: RefCell<FlashSummary>,
+    phase_started: Cell<Option<Instant>>,
 }
 
 impl FlashProgress {
@@ -23,14 +29,41 @@ impl FlashProgress {
     pub fn new(handler: impl Fn(ProgressEvent) + 'static) -> Self {
         Self {
             handler: Box::new(handler),
+            summary: RefCell::new(FlashSummary::default()),
+            phase_started: Cell::new(None),
         }
     }
 
+    /// The statistics of all flashing procedures this `FlashProgress` was used for so far.
+    pub fn summary(&self) -> FlashSummary {
+        self.summary.borrow().clone()
+    }
+
     /// Emit a flashing progress event.
     fn emit(&self, event: ProgressEvent) {
         (self.handler)(event);
     }
 
+    /// Remember the start of a phase, so its duration can be added once it ends.
+    fn start_phase(&self) {
+        self.phase_started.set(Some(Instant::now()));
+    }
+
+    /// Add the duration of the current phase to the statistics selected by `phase`.
+    fn end_phase(&self, phase: impl FnOnce(&mut FlashSummary) -> &mut PhaseStatistics) {
+        if let Some(started) = self.phase_started.take() {
+            phase(&mut self.summary.borrow_mut()).duration += started.elapsed();
+        }
+    }
+
+    /// Add a finished unit of work of `size` bytes to the statistics selected by `phase`.
+    fn add_unit(&self, size: u64, phase: impl FnOnce(&mut FlashSummary) -> &mut PhaseStatistics) {
+        let mut summary = self.summary.borrow_mut();
+        let statistics = phase(&mut summary);
+        statistics.bytes += size;
+        statistics.units += 1;
+    }
+
     /// Signalize that the flashing algorithm was set up and is initialized.
     pub(super) fn initialized(&self, flash_layout: FlashLayout) {
         self.emit(ProgressEvent::Initialized { flash_layout });
@@ -38,63 +71,155 @@ impl FlashProgress {
 
     /// Signalize that the erasing procedure started.
     pub(super) fn started_erasing(&self) {
+        self.start_phase();
         self.emit(ProgressEvent::StartedErasing);
     }
 
     /// Signalize that the filling procedure started.
     pub(super) fn started_filling(&self) {
+        self.start_phase();
         self.emit(ProgressEvent::StartedFilling);
     }
 
     /// Signalize that the programing procedure started.
     pub(super) fn started_programming(&self) {
+        self.start_phase();
         self.emit(ProgressEvent::StartedProgramming);
     }
 
     /// Signalize that the page programming procedure has made progress.
     pub(super) fn page_programmed(&self, size: u32, time: Duration) {
+        self.add_unit(size as u64, |s| &mut s.programming);
         self.emit(ProgressEvent::PageProgrammed { size, time });
     }
 
     /// Signalize that the sector erasing procedure has made progress.
     pub(super) fn sector_erased(&self, size: u64, time: Duration) {
+        self.add_unit(size, |s| &mut s.erasing);
         self.emit(ProgressEvent::SectorErased { size, time });
     }
 
     /// Signalize that the page filling procedure has made progress.
     pub(super) fn page_filled(&self, size: u64, time: Duration) {
+        self.add_unit(size, |s| &mut s.filling);
         self.emit(ProgressEvent::PageFilled { size, time });
     }
 
     /// Signalize that the programming procedure failed.
     pub(super) fn failed_programming(&self) {
+        self.end_phase(|s| &mut s.programming);
         self.emit(ProgressEvent::FailedProgramming);
     }
 
     /// Signalize that the programming procedure completed successfully.
     pub(super) fn finished_programming(&self) {
+        self.end_phase(|s| &mut s.programming);
         self.emit(ProgressEvent::FinishedProgramming);
     }
 
     /// Signalize that the erasing procedure failed.
     pub(super) fn failed_erasing(&self) {
+        self.end_phase(|s| &mut s.erasing);
         self.emit(ProgressEvent::FailedErasing);
     }
 
     /// Signalize that the erasing procedure completed successfully.
     pub(super) fn finished_erasing(&self) {
+        self.end_phase(|s| &mut s.erasing);
         self.emit(ProgressEvent::FinishedErasing);
     }
 
     /// Signalize that the filling procedure failed.
     pub(super) fn failed_filling(&self) {
+        self.end_phase(|s| &mut s.filling);
         self.emit(ProgressEvent::FailedFilling);
     }
 
     /// Signalize that the filling procedure completed successfully.
     pub(super) fn finished_filling(&self) {
+        self.end_phase(|s| &mut s.filling);
         self.emit(ProgressEvent::FinishedFilling);
     }
+
+    /// Signalize that sectors were skipped, because they already contain the new data.
+    pub(super) fn sectors_skipped(&self, count: usize, size: u64) {
+        {
+            let mut summary = self.summary.borrow_mut();
+            summary.skipped_sectors += count;
+            summary.skipped_bytes += size;
+        }
+        self.emit(ProgressEvent::SectorsSkipped { count, size });
+    }
+
+    /// Signalize that the verification procedure started.
+    pub(super) fn started_verifying(&self) {
+        self.start_phase();
+        self.emit(ProgressEvent::StartedVerifying);
+    }
+
+    /// Signalize that a block of data has been verified.
+    pub(super) fn data_verified(&self, size: u64) {
+        self.add_unit(size, |s| &mut s.verifying);
+    }
+
+    /// Signalize that the verification procedure failed.
+    pub(super) fn failed_verifying(&self) {
+        self.end_phase(|s| &mut s.verifying);
+        self.emit(ProgressEvent::FailedVerifying);
+    }
+
+    /// Signalize that the verification procedure completed successfully.
+    pub(super) fn finished_verifying(&self) {
+        self.end_phase(|s| &mut s.verifying);
+        self.emit(ProgressEvent::FinishedVerifying);
+    }
+
+    /// Signalize that the whole flashing procedure completed successfully.
+    pub(super) fn finished(&self) {
+        self.emit(ProgressEvent::Finished {
+            summary: self.summary(),
+        });
+    }
+}
+
+/// The statistics of one phase of the flashing procedure.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PhaseStatistics {
+    /// The number of bytes which were processed.
+    pub bytes: u64,
+    /// The number of units, i.e. sectors or pages, which were processed.
+    pub units: usize,
+    /// The time spent in this phase.
+    pub duration: Duration,
+}
+
+impl PhaseStatistics {
+    /// The achieved throughput in KiB/s, or `None` if no time was spent in this phase.
+    pub fn kibibytes_per_second(&self) -> Option<f64> {
+        let seconds = self.duration.as_secs_f64();
+        if seconds > 0.0 {
+            Some(self.bytes as f64 / 1024.0 / seconds)
+        } else {
+            None
+        }
+    }
+}
+
+/// A machine-readable summary of the flashing procedure.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FlashSummary {
+    /// Reading the flash contents which have to be restored after erasing, counted in fills.
+    pub filling: PhaseStatistics,
+    /// Erasing the flash, counted in sectors.
+    pub erasing: PhaseStatistics,
+    /// Programming the flash, counted in pages.
+    pub programming: PhaseStatistics,
+    /// Verifying the written data, counted in contiguous blocks of data.
+    pub verifying: PhaseStatistics,
+    /// The number of sectors which were skipped, because they already contained the new data.
+    pub skipped_sectors: usize,
+    /// The number of bytes in the skipped sectors.
+    pub skipped_bytes: u64,
 }
 
 /// Possible events during the flashing process.
@@ -112,10 +237,17 @@ impl FlashProgress {
 /// * `StartedProgramming`
 /// * `PageProgrammed` for every page
 /// * `FinishedProgramming`
+/// * `StartedVerifying` and `FinishedVerifying`, if verification is enabled
+/// * `Finished`
+///
+/// `SectorsSkipped` is reported before `Initialized` if unchanged sectors are skipped.
 ///
 /// If an erorr occurs in any stage, one of the `Failed*` event will be returned,
 /// and no further events will be returned.
+///
+/// More events might be added in the future, so matches on this enum need a wildcard arm.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum ProgressEvent {
     /// The flash layout has been built and the flashing procedure was initialized.
     Initialized {
@@ -164,4 +296,61 @@ pub enum ProgressEvent {
     FailedProgramming,
     /// Programming of the flash has finished successfully.
     FinishedProgramming,
+    /// Sectors were skipped, because they already contain the new data.
+    SectorsSkipped {
+        /// The number of skipped sectors.
+        count: usize,
+        /// The total size of the skipped sectors in bytes.
+        size: u64,
+    },
+    /// Verification of the written data has started.
+    StartedVerifying,
+    /// Verification of the written data failed.
+    FailedVerifying,
+    /// Verification of the written data has finished successfully.
+    FinishedVerifying,
+    /// The whole flashing procedure has finished successfully.
+    Finished {
+        /// The statistics of the flashing procedure.
+        summary: FlashSummary,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary_collects_phase_statistics() {
+        let progress = FlashProgress::new(|_| {});
+
+        progress.started_erasing();
+        progress.sector_erased(4096, Duration::from_millis(10));
+        progress.sector_erased(4096, Duration::from_millis(10));
+        progress.finished_erasing();
+        progress.sectors_skipped(3, 3 * 4096);
+        progress.started_programming();
+        progress.page_programmed(256, Duration::from_millis(1));
+        progress.failed_programming();
+
+        let summary = progress.summary();
+        assert_eq!(summary.erasing.bytes, 8192);
+        assert_eq!(summary.erasing.units, 2);
+        assert_eq!(summary.programming.bytes, 256);
+        assert_eq!(summary.programming.units, 1);
+        assert_eq!(summary.verifying, PhaseStatistics::default());
+        assert_eq!(summary.skipped_sectors, 3);
+        assert_eq!(summary.skipped_bytes, 3 * 4096);
+    }
+
+    #[test]
+    fn throughput_requires_elapsed_time() {
+        let statistics = PhaseStatistics {
+            bytes: 2048,
+            units: 1,
+            duration: Duration::from_secs(2),
+        };
+        assert_eq!(statistics.kibibytes_per_second(), Some(1.0));
+        assert_eq!(PhaseStatistics::default().kibibytes_per_second(), None);
+    }
 }
@@ -0,0 +1,32 @@
+//! A flash region and algorithm shared by the tests of the flashing modules.
+
+use probe_rs_target::{FlashProperties, NvmRegion, SectorDescription};
+
+use super::FlashAlgorithm;
+
+/// A flash of `size` bytes at address 0, with sectors of `sector_size` bytes and pages of 1 KiB.
+pub(super) fn demo_flash(size: u64, sector_size: u64) -> (NvmRegion, FlashAlgorithm) {
+    let flash_algorithm = FlashAlgorithm {
+        flash_properties: FlashProperties {
+            address_range: 0..size,
+            page_size: 1024,
+            erased_byte_value: 0xFF,
+            program_page_timeout: 200,
+            erase_sector_timeout: 200,
+            sectors: vec![SectorDescription {
+                size: sector_size,
+                address: 0,
+            }],
+        },
+        ..Default::default()
+    };
+
+    let region = NvmRegion {
+        name: Some("FLASH".into()),
+        is_boot_memory: true,
+        range: 0..size,
+        cores: vec!["main".into()],
+    };
+
+    (region, flash_algorithm)
+}
@@ -83,20 +83,28 @@ mod memory;
 mod probe;
 #[warn(missing_docs)]
 mod session;
+#[warn(missing_docs)]
+pub mod vendor;
 
 pub use crate::config::{CoreType, InstructionSet, Target};
 pub use crate::core::{
-    Architecture, BreakpointId, CommunicationInterface, Core, CoreInformation, CoreInterface,
-    CoreState, CoreStatus, HaltReason, MemoryMappedRegister, RegisterDescription, RegisterFile,
-    RegisterId, RegisterValue, SpecificCoreState,
+    symbol_address, Architecture, BreakpointId, CommunicationInterface, Core, CoreDump,
+    CoreInformation, CoreInterface, CoreState, CoreStatus, FaultReport, HaltReason,
+    MemoryMappedRegister, PanicInfo, RegisterDescription, RegisterFile, RegisterId, RegisterValue,
+    RunLoop, RunOutcome, SpecificCoreState, VectorCatchCondition, Watchpoint, WatchpointKind,
 };
 pub use crate::error::Error;
 pub use crate::memory::{Memory, MemoryInterface};
+#[cfg(feature = "serial")]
+pub use crate::probe::vcom::{VcomError, VirtualComPort};
 pub use crate::probe::{
     AttachMethod, DebugProbe, DebugProbeError, DebugProbeInfo, DebugProbeSelector, DebugProbeType,
-    Probe, ProbeCreationError, WireProtocol,
+    PinRequest, Probe, ProbeCreationError, ProbeSelectionError, WireProtocol,
+};
+pub use crate::session::{
+    AttachOptions, Breakpoint, BreakpointKind, BreakpointLocation, CoreHandle, CoreMultiplexer,
+    CoreOrder, Permissions, Session, Snapshot,
 };
-pub use crate::session::{Permissions, Session};
 
 // TODO: Hide behind feature
 pub use crate::probe::fake_probe::FakeProbe;
@@ -5,6 +5,8 @@ pub(crate) mod fake_probe;
 pub(crate) mod ftdi;
 pub(crate) mod jlink;
 pub(crate) mod stlink;
+#[cfg(feature = "serial")]
+pub(crate) mod vcom;
 
 use crate::error::Error;
 use crate::Session;
@@ -17,16 +19,19 @@ use crate::{
         arm::{
             communication_interface::DapProbe,
             sequences::{ArmDebugSequence, DefaultArmSequence},
-            PortType, SwoAccess,
+            Pins, PortType, SwoAccess,
         },
+        mips::communication_interface::MipsCommunicationInterface,
         riscv::communication_interface::RiscvCommunicationInterface,
     },
-    Permissions,
+    AttachOptions, Permissions,
 };
 use jlink::list_jlink_devices;
-use std::{convert::TryFrom, fmt};
+use std::{convert::TryFrom, fmt, thread, time::Duration};
 
 use self::espusbjtag::list_espjtag_devices;
+#[cfg(feature = "serial")]
+use self::vcom::{VcomError, VirtualComPort};
 
 /// Used to log warnings when the measured target voltage is
 /// lower than 1.4V, if at all measureable.
@@ -217,6 +222,7 @@ pub enum ProbeCreationError {
 pub struct Probe {
     inner: Box<dyn DebugProbe>,
     attached: bool,
+    serial_number: Option<String>,
 }
 
 impl Probe {
@@ -225,6 +231,7 @@ impl Probe {
         Self {
             inner: Box::new(probe),
             attached: false,
+            serial_number: None,
         }
     }
 
@@ -232,6 +239,7 @@ impl Probe {
         Self {
             inner: probe,
             attached: true,
+            serial_number: None,
         }
     }
 
@@ -240,6 +248,7 @@ impl Probe {
         Probe {
             inner: probe,
             attached: false,
+            serial_number: None,
         }
     }
 
@@ -261,10 +270,36 @@ impl Probe {
         list
     }
 
+    /// Get a list of all debug probes which match the given selector.
+    ///
+    /// The serial number of the selector may contain `*` and `?` wildcards.
+    pub fn list_matching(selector: &DebugProbeSelector) -> Vec<DebugProbeInfo> {
+        Self::list_all()
+            .into_iter()
+            .filter(|info| selector.matches(info))
+            .collect()
+    }
+
     /// Create a [`Probe`] from [`DebugProbeInfo`]. Use the
     /// [`Probe::list_all()`] function to get the information
     /// about all probes available.
     pub fn open(selector: impl Into<DebugProbeSelector> + Clone) -> Result<Self, DebugProbeError> {
+        let selector = selector.into();
+
+        // With wildcards, the selector doesn't tell which of the matching probes was opened.
+        let serial_number = selector
+            .serial_number
+            .clone()
+            .filter(|serial_number| !serial_number.contains(|c| c == '*' || c == '?'));
+
+        let mut probe = Self::open_driver(selector)?;
+        probe.serial_number = serial_number;
+
+        Ok(probe)
+    }
+
+    /// Open the probe with the first driver which finds a probe matching the `selector`.
+    fn open_driver(selector: DebugProbeSelector) -> Result<Self, DebugProbeError> {
         match cmsisdap::CmsisDap::new_from_selector(selector.clone()) {
             Ok(link) => return Ok(Probe::from_specific_probe(link)),
             Err(DebugProbeError::ProbeCouldNotBeCreated(ProbeCreationError::NotFound)) => {}
@@ -302,19 +337,38 @@ impl Probe {
         self.inner.get_name().to_string()
     }
 
+    /// The serial number of the probe.
+    ///
+    /// This is only known if the probe was opened with [`Probe::open`], with a selector
+    /// which contains the full serial number, e.g. a [`DebugProbeInfo`] from [`Probe::list_all`].
+    pub fn serial_number(&self) -> Option<&str> {
+        self.serial_number.as_deref()
+    }
+
     /// Attach to the chip.
     ///
     /// This runs all the necessary protocol init routines.
     ///
     /// If this doesn't work, you might want to try [`Probe::attach_under_reset`]
     pub fn attach(
+        self,
+        target: impl Into<TargetSelector>,
+        permissions: Permissions,
+    ) -> Result<Session, Error> {
+        self.attach_with_options(target, permissions, AttachOptions::default())
+    }
+
+    /// Attach to the chip with the given options, e.g. to attach under reset or to keep the
+    /// watchdogs of the chip running while it is halted.
+    pub fn attach_with_options(
         mut self,
         target: impl Into<TargetSelector>,
         permissions: Permissions,
+        options: AttachOptions,
     ) -> Result<Session, Error> {
         self.attached = true;
 
-        Session::new(self, target.into(), AttachMethod::Normal, permissions)
+        Session::new(self, target.into(), options, permissions)
     }
 
     /// Attach to a target without knowing what target you have at hand.
@@ -348,14 +402,12 @@ impl Probe {
     /// This is necessary if the chip is not responding to the SWD reset sequence.
     /// For example this can happen if the chip has the SWDIO pin remapped.
     pub fn attach_under_reset(
-        mut self,
+        self,
         target: impl Into<TargetSelector>,
         permissions: Permissions,
     ) -> Result<Session, Error> {
-        self.attached = true;
-
         // The session will de-assert reset after connecting to the debug interface.
-        Session::new(self, target.into(), AttachMethod::UnderReset, permissions)
+        self.attach_with_options(target, permissions, AttachOptions::new().under_reset())
     }
 
     pub(crate) fn inner_attach(&mut self) -> Result<(), DebugProbeError> {
@@ -464,6 +516,28 @@ impl Probe {
         }
     }
 
+    /// Check if the probe has an interface to
+    /// debug MIPS chips.
+    pub fn has_mips_interface(&self) -> bool {
+        self.inner.has_mips_interface()
+    }
+
+    /// Try to get a [`MipsCommunicationInterface`], which can
+    /// can be used to communicate with MIPS cores over EJTAG.
+    ///
+    /// If an error occurs while trying to connect, the probe is returned.
+    pub fn try_into_mips_interface(
+        self,
+    ) -> Result<MipsCommunicationInterface, (Self, DebugProbeError)> {
+        if !self.attached {
+            Err((self, DebugProbeError::NotAttached))
+        } else {
+            self.inner
+                .try_get_mips_interface()
+                .map_err(|(probe, err)| (Probe::from_attached_probe(probe), err))
+        }
+    }
+
     /// Gets a SWO interface from the debug probe.
     ///
     /// This does not work on all probes.
@@ -487,10 +561,173 @@ impl Probe {
 
     /// Try reading the target voltage of via the connected volgate pin.
     ///
-    /// This does not work on all probes.
+    /// This does not work on all probes. ST-Links and J-Links support it, while the
+    /// CMSIS-DAP specification does not define a command for it, so CMSIS-DAP probes
+    /// always return `Ok(None)`.
+    ///
+    /// Once attached, the voltage can also be read with [`Session::get_target_voltage`].
     pub fn get_target_voltage(&mut self) -> Result<Option<f32>, DebugProbeError> {
         self.inner.get_target_voltage()
     }
+
+    /// Get the firmware version of the probe, if it is known.
+    ///
+    /// The format of the version depends on the probe type:
+    /// - CMSIS-DAP: the product firmware version reported by `DAP_Info`. Probes which implement
+    ///   a CMSIS-DAP version older than 2.1 don't report it, so the version of the CMSIS-DAP
+    ///   protocol they implement is returned instead, e.g. `2.0.0`.
+    /// - ST-Link: the hardware and JTAG versions, e.g. `V2J37`.
+    /// - J-Link: the firmware string of the probe, e.g. `J-Link V11 compiled Mar  2 2021 12:04:58`.
+    ///
+    /// Tools can use this to warn about firmware versions with known issues.
+    pub fn firmware_version(&self) -> Option<String> {
+        self.inner.firmware_version()
+    }
+
+    /// Check if the probe can supply power to the target.
+    pub fn has_target_power_control(&self) -> bool {
+        self.inner.has_target_power_control()
+    }
+
+    /// Enable or disable the power supply the probe provides to the target.
+    ///
+    /// This can be used to power-cycle a target, and is only supported by probes
+    /// for which [`Probe::has_target_power_control`] returns `true`. Currently,
+    /// this is the case for J-Links with the kickstart power capability. ST-Link V3PWR
+    /// and CMSIS-DAP probes are not supported.
+    pub fn set_target_power(&mut self, enabled: bool) -> Result<(), DebugProbeError> {
+        log::debug!(
+            "{} target power",
+            if enabled { "Enabling" } else { "Disabling" }
+        );
+        self.inner.set_target_power(enabled)
+    }
+
+    /// Drive the debug pins selected in `request` and read back the state of all pins.
+    ///
+    /// Returns `Ok(None)` if the probe is able to drive the pins, but cannot read them back.
+    /// See [`PinRequest`] for details on how the pins are selected.
+    pub fn set_pins(&mut self, request: PinRequest) -> Result<Option<Pins>, DebugProbeError> {
+        log::debug!("Setting probe pins: {:?}", request);
+        self.inner.set_pins(request)
+    }
+
+    /// Read the current state of the debug pins without driving any of them.
+    ///
+    /// Returns `Ok(None)` if the probe cannot read the pin state.
+    pub fn read_pins(&mut self) -> Result<Option<Pins>, DebugProbeError> {
+        self.inner.set_pins(PinRequest::new())
+    }
+
+    /// Assert the nRESET line for the given duration and release it afterwards.
+    pub fn pulse_reset(&mut self, duration: Duration) -> Result<(), DebugProbeError> {
+        pulse_reset(self.inner.as_mut(), duration)
+    }
+}
+
+/// Assert nRESET on `probe` for `duration`, then release it again.
+pub(crate) fn pulse_reset(
+    probe: &mut dyn DebugProbe,
+    duration: Duration,
+) -> Result<(), DebugProbeError> {
+    probe.set_pins(PinRequest::new().nreset(false))?;
+    thread::sleep(duration);
+    probe.set_pins(PinRequest::new().nreset(true))?;
+
+    Ok(())
+}
+
+/// A request to drive and sample the debug pins of a probe.
+///
+/// Only the pins which are explicitly set on the request are driven, all other pins keep
+/// their current state. After driving the pins, the probe waits until all selected pins have
+/// reached the requested level, or until the [wait time](PinRequest::wait) has elapsed,
+/// before sampling the state of all pins. This mirrors the `DAP_SWJ_Pins` command of CMSIS-DAP.
+///
+/// Only the debug pins can be controlled. The user GPIOs of probes are not supported yet.
+#[derive(Debug, Clone, Copy)]
+pub struct PinRequest {
+    output: Pins,
+    select: Pins,
+    wait: Duration,
+}
+
+impl Default for PinRequest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PinRequest {
+    /// The longest wait time supported by the `DAP_SWJ_Pins` command.
+    pub const MAX_WAIT: Duration = Duration::from_secs(3);
+
+    /// Create a request which does not drive any pin, and only reads the pin state.
+    pub fn new() -> Self {
+        Self {
+            output: Pins(0),
+            select: Pins(0),
+            wait: Duration::from_micros(0),
+        }
+    }
+
+    /// Drive the active low nRESET pin to the given level.
+    pub fn nreset(mut self, level: bool) -> Self {
+        self.select.set_nreset(true);
+        self.output.set_nreset(level);
+        self
+    }
+
+    /// Drive the active low JTAG nTRST pin to the given level.
+    pub fn ntrst(mut self, level: bool) -> Self {
+        self.select.set_ntrst(true);
+        self.output.set_ntrst(level);
+        self
+    }
+
+    /// Drive the TDI pin to the given level.
+    pub fn tdi(mut self, level: bool) -> Self {
+        self.select.set_tdi(true);
+        self.output.set_tdi(level);
+        self
+    }
+
+    /// Drive the SWDIO or TMS pin to the given level.
+    pub fn swdio_tms(mut self, level: bool) -> Self {
+        self.select.set_swdio_tms(true);
+        self.output.set_swdio_tms(level);
+        self
+    }
+
+    /// Drive the SWCLK or TCK pin to the given level.
+    pub fn swclk_tck(mut self, level: bool) -> Self {
+        self.select.set_swclk_tck(true);
+        self.output.set_swclk_tck(level);
+        self
+    }
+
+    /// Set the maximum time to wait for the selected pins to settle.
+    ///
+    /// The wait time is limited to [`PinRequest::MAX_WAIT`].
+    pub fn wait(mut self, wait: Duration) -> Self {
+        self.wait = wait.min(Self::MAX_WAIT);
+        self
+    }
+
+    /// The levels the selected pins are driven to.
+    pub fn output(&self) -> Pins {
+        self.output
+    }
+
+    /// The pins which are driven by this request.
+    pub fn select(&self) -> Pins {
+        self.select
+    }
+
+    /// The maximum time to wait for the selected pins to settle.
+    pub fn wait_time(&self) -> Duration {
+        self.wait
+    }
 }
 
 /// An abstraction over general debug probe functionality.
@@ -587,6 +824,22 @@ pub trait DebugProbe: Send + fmt::Debug {
         false
     }
 
+    /// Get the dedicated interface to debug MIPS chips. Ensure that the
+    /// probe actually supports this by calling [DebugProbe::has_mips_interface] first.
+    fn try_get_mips_interface(
+        self: Box<Self>,
+    ) -> Result<MipsCommunicationInterface, (Box<dyn DebugProbe>, DebugProbeError)> {
+        Err((
+            self.into_probe(),
+            DebugProbeError::InterfaceNotAvailable("MIPS"),
+        ))
+    }
+
+    /// Check if the probe offers an interface to debug MIPS chips.
+    fn has_mips_interface(&self) -> bool {
+        false
+    }
+
     /// Get a SWO interface from the debug probe.
     ///
     /// This is not available on all debug probes.
@@ -611,11 +864,45 @@ pub trait DebugProbe: Send + fmt::Debug {
         None
     }
 
+    /// Get low-level access to the JTAG protocol of the probe.
+    ///
+    /// This is used by sequences which have to talk to JTAG routers or other TAPs
+    /// in the scan chain, and is not available on all probes.
+    fn try_as_jtag_probe(&mut self) -> Option<&mut dyn JTAGAccess> {
+        None
+    }
+
     /// Reads the target voltage in Volts, if possible. Returns `Ok(None)`
     /// if the probe doesn’t support reading the target voltage.
     fn get_target_voltage(&mut self) -> Result<Option<f32>, DebugProbeError> {
         Ok(None)
     }
+
+    /// Get the firmware version of the probe, if it is known.
+    fn firmware_version(&self) -> Option<String> {
+        None
+    }
+
+    /// Check if the probe can supply power to the target.
+    fn has_target_power_control(&self) -> bool {
+        false
+    }
+
+    /// Enable or disable the power supply the probe provides to the target.
+    ///
+    /// This is only supported if [`DebugProbe::has_target_power_control`] returns `true`.
+    fn set_target_power(&mut self, _enabled: bool) -> Result<(), DebugProbeError> {
+        Err(DebugProbeError::CommandNotSupportedByProbe(
+            "set_target_power",
+        ))
+    }
+
+    /// Drive the debug pins selected in `request` and read back the state of all pins.
+    ///
+    /// Returns `Ok(None)` if the pins were driven, but the probe cannot read their state.
+    fn set_pins(&mut self, _request: PinRequest) -> Result<Option<Pins>, DebugProbeError> {
+        Err(DebugProbeError::CommandNotSupportedByProbe("set_pins"))
+    }
 }
 
 /// Denotes the type of a given [`DebugProbe`].
@@ -650,19 +937,27 @@ pub struct DebugProbeInfo {
     /// The USB HID interface which should be used.
     /// This is necessary for composite HID devices.
     pub hid_interface: Option<u8>,
+
+    /// The firmware version of the debug probe, if it could be read while listing the probes.
+    ///
+    /// This is only filled in for J-Link probes.
+    pub firmware_version: Option<String>,
 }
 
 impl std::fmt::Debug for DebugProbeInfo {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(
             f,
-            "{} (VID: {:04x}, PID: {:04x}, {}{:?})",
+            "{} (VID: {:04x}, PID: {:04x}, {}{}{:?})",
             self.identifier,
             self.vendor_id,
             self.product_id,
             self.serial_number
                 .clone()
                 .map_or("".to_owned(), |v| format!("Serial: {}, ", v)),
+            self.firmware_version
+                .clone()
+                .map_or("".to_owned(), |v| format!("Firmware: {}, ", v)),
             self.probe_type
         )
     }
@@ -685,13 +980,46 @@ impl DebugProbeInfo {
             serial_number,
             probe_type,
             hid_interface: usb_hid_interface,
+            firmware_version: None,
         }
     }
 
+    /// Set the firmware version of the probe.
+    pub(crate) fn with_firmware_version(mut self, firmware_version: Option<String>) -> Self {
+        self.firmware_version = firmware_version;
+        self
+    }
+
+    /// The firmware version of the probe.
+    ///
+    /// This is only available for J-Link probes, which report their version while listing
+    /// the probes. For all other probes, including CMSIS-DAP probes, use
+    /// [`Probe::firmware_version`] after opening the probe.
+    pub fn firmware_version(&self) -> Option<&str> {
+        self.firmware_version.as_deref()
+    }
+
     /// Open the probe described by this `DebugProbeInfo`.
     pub fn open(&self) -> Result<Probe, DebugProbeError> {
         Probe::open(self)
     }
+
+    /// List the names of the virtual COM ports this probe exposes.
+    ///
+    /// Many probes, e.g. most CMSIS-DAP probes, bridge a UART of the target to
+    /// a USB serial port. The ports are sorted by name.
+    #[cfg(feature = "serial")]
+    pub fn vcom_ports(&self) -> Result<Vec<String>, VcomError> {
+        vcom::list_ports(self)
+    }
+
+    /// Open the first virtual COM port of this probe with the given baud rate.
+    ///
+    /// The port can be used independently of the debug interface of the probe.
+    #[cfg(feature = "serial")]
+    pub fn open_vcom(&self, baud_rate: u32) -> Result<VirtualComPort, VcomError> {
+        VirtualComPort::open(self, baud_rate)
+    }
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -702,14 +1030,40 @@ pub enum DebugProbeSelectorParseError {
     Format,
 }
 
+/// An error which occurs when a [`DebugProbeSelector`] does not select exactly one probe.
+#[derive(thiserror::Error, Debug)]
+pub enum ProbeSelectionError {
+    /// No connected probe matches the selector.
+    #[error("No probe matching '{0}' was found.")]
+    NoMatch(DebugProbeSelector),
+    /// More than one connected probe matches the selector.
+    ///
+    /// The matching probes are returned so that the user can be presented with a list of candidates.
+    #[error(
+        "{} probes match '{selector}', please use a more specific selector:{}",
+        candidates.len(),
+        candidates.iter().map(|c| format!("\n    {}", DebugProbeSelector::from(c))).collect::<String>()
+    )]
+    Ambiguous {
+        /// The selector which was used.
+        selector: DebugProbeSelector,
+        /// All probes which matched the selector.
+        candidates: Vec<DebugProbeInfo>,
+    },
+}
+
 /// A struct to describe the way a probe should be selected.
 ///
 /// Construct this from a set of info or from a string.
 ///
+/// The serial number may contain the wildcards `*` (any sequence of characters)
+/// and `?` (any single character), which is useful if many identical probes are connected.
+///
 /// Example:
 /// ```
 /// use std::convert::TryInto;
 /// let selector: probe_rs::DebugProbeSelector = "1337:1337:SERIAL".try_into().unwrap();
+/// let pattern: probe_rs::DebugProbeSelector = "1337:1337:0012*".try_into().unwrap();
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
 // We need this so that serde will first convert from the string `PID:VID:<Serial>` to a struct before deserializing.
@@ -723,6 +1077,82 @@ pub struct DebugProbeSelector {
     pub serial_number: Option<String>,
 }
 
+impl DebugProbeSelector {
+    /// Check if the given probe is matched by this selector.
+    pub fn matches(&self, info: &DebugProbeInfo) -> bool {
+        self.matches_vid_pid(info.vendor_id, info.product_id)
+            && self.matches_serial_number(info.serial_number.as_deref())
+    }
+
+    /// Check if the given USB vendor and product id are matched by this selector.
+    pub(crate) fn matches_vid_pid(&self, vendor_id: u16, product_id: u16) -> bool {
+        self.vendor_id == vendor_id && self.product_id == product_id
+    }
+
+    /// Check if the given serial number is matched by this selector.
+    ///
+    /// If the selector does not contain a serial number, any serial number matches.
+    pub(crate) fn matches_serial_number(&self, serial_number: Option<&str>) -> bool {
+        match (&self.serial_number, serial_number) {
+            (None, _) => true,
+            (Some(pattern), Some(serial_number)) => wildcard_match(pattern, serial_number),
+            (Some(_), None) => false,
+        }
+    }
+
+    /// Select exactly one probe out of the given list.
+    ///
+    /// Returns an error listing all candidates if the selection is ambiguous.
+    pub fn resolve(
+        &self,
+        probes: &[DebugProbeInfo],
+    ) -> Result<DebugProbeInfo, ProbeSelectionError> {
+        let mut candidates: Vec<_> = probes
+            .iter()
+            .filter(|info| self.matches(info))
+            .cloned()
+            .collect();
+
+        match candidates.len() {
+            0 => Err(ProbeSelectionError::NoMatch(self.clone())),
+            1 => Ok(candidates.remove(0)),
+            _ => Err(ProbeSelectionError::Ambiguous {
+                selector: self.clone(),
+                candidates,
+            }),
+        }
+    }
+}
+
+/// Match `text` against a `pattern` which may contain the wildcards `*` and `?`.
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` in the pattern, and the text position it was tried at.
+    let mut backtrack = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, star_t)) = backtrack {
+            // Let the last `*` consume one more character.
+            p = star + 1;
+            t = star_t + 1;
+            backtrack = Some((star, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
 impl TryFrom<&str> for DebugProbeSelector {
     type Error = DebugProbeSelectorParseError;
     fn try_from(value: &str) -> Result<Self, Self::Error> {
@@ -789,6 +1219,22 @@ impl fmt::Display for DebugProbeSelector {
     }
 }
 
+/// The position of the selected TAP in a JTAG scan chain with several TAPs.
+///
+/// All other TAPs in the chain are kept in BYPASS, so each of them adds its
+/// IR length to instruction scans and a single bit to data scans.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScanChainPosition {
+    /// The number of TAPs between the selected TAP and TDO.
+    pub tdo_side_taps: usize,
+    /// The sum of the IR lengths of the TAPs between the selected TAP and TDO.
+    pub tdo_side_ir_len: usize,
+    /// The number of TAPs between TDI and the selected TAP.
+    pub tdi_side_taps: usize,
+    /// The sum of the IR lengths of the TAPs between TDI and the selected TAP.
+    pub tdi_side_ir_len: usize,
+}
+
 /// Low-Level Access to the JTAG protocol
 ///
 /// This trait should be implemented by all probes which offer low-level access to
@@ -808,6 +1254,18 @@ pub trait JTAGAccess: DebugProbe {
     /// Set the IR register length
     fn set_ir_len(&mut self, len: u32);
 
+    /// Select a TAP in a scan chain with several TAPs.
+    ///
+    /// The default position is a chain which only contains the selected TAP.
+    fn set_scan_chain_position(
+        &mut self,
+        _position: ScanChainPosition,
+    ) -> Result<(), DebugProbeError> {
+        Err(DebugProbeError::NotImplemented(
+            "JTAG scan chains with several TAPs",
+        ))
+    }
+
     /// Write to a JTAG register
     ///
     /// This function will perform a write to the IR register, if necessary,
@@ -820,6 +1278,9 @@ pub trait JTAGAccess: DebugProbe {
         len: u32,
     ) -> Result<Vec<u8>, DebugProbeError>;
 
+    /// Get a reference to this interface as a generic [`DebugProbe`].
+    fn probe_mut(&mut self) -> &mut dyn DebugProbe;
+
     fn write_register_batch(
         &mut self,
         writes: &[JtagWriteCommand],
@@ -894,3 +1355,75 @@ pub enum AttachMethod {
     /// This is required on targets that can remap SWD pins or disable the SWD interface in sleep.
     UnderReset,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn probe_info(serial_number: Option<&str>) -> DebugProbeInfo {
+        DebugProbeInfo::new(
+            "Test probe",
+            0x1337,
+            0x0042,
+            serial_number.map(|s| s.to_owned()),
+            DebugProbeType::CmsisDap,
+            None,
+        )
+    }
+
+    #[test]
+    fn wildcard_matching() {
+        assert!(wildcard_match("ABC123", "ABC123"));
+        assert!(!wildcard_match("ABC123", "ABC1234"));
+        assert!(wildcard_match("ABC*", "ABC1234"));
+        assert!(wildcard_match("*1234", "ABC1234"));
+        assert!(wildcard_match("A*C*4", "ABC1234"));
+        assert!(wildcard_match("ABC12?4", "ABC1234"));
+        assert!(!wildcard_match("ABC?", "ABC"));
+        assert!(wildcard_match("*", ""));
+    }
+
+    #[test]
+    fn selector_matches_serial_pattern() {
+        let selector: DebugProbeSelector = "1337:0042:0012*".try_into().unwrap();
+
+        assert!(selector.matches(&probe_info(Some("00123456"))));
+        assert!(!selector.matches(&probe_info(Some("00223456"))));
+        assert!(!selector.matches(&probe_info(None)));
+    }
+
+    #[test]
+    fn resolve_ambiguous_selector() {
+        let probes = vec![probe_info(Some("0012A")), probe_info(Some("0012B"))];
+
+        let selector: DebugProbeSelector = "1337:0042".try_into().unwrap();
+        assert!(matches!(
+            selector.resolve(&probes),
+            Err(ProbeSelectionError::Ambiguous { candidates, .. }) if candidates.len() == 2
+        ));
+
+        let selector: DebugProbeSelector = "1337:0042:*B".try_into().unwrap();
+        assert_eq!(
+            selector.resolve(&probes).unwrap().serial_number.as_deref(),
+            Some("0012B")
+        );
+
+        let selector: DebugProbeSelector = "1337:0043".try_into().unwrap();
+        assert!(matches!(
+            selector.resolve(&probes),
+            Err(ProbeSelectionError::NoMatch(_))
+        ));
+    }
+
+    #[test]
+    fn pin_request_selects_only_set_pins() {
+        let request = PinRequest::new()
+            .nreset(false)
+            .ntrst(true)
+            .wait(Duration::from_secs(10));
+
+        assert_eq!(request.select().0, 0b1010_0000);
+        assert_eq!(request.output().0, 0b0010_0000);
+        assert_eq!(request.wait_time(), PinRequest::MAX_WAIT);
+    }
+}
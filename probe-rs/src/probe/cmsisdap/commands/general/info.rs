@@ -40,6 +40,8 @@ info_command!(0x07, TargetBoardVendorCommand, Option<String>);
 
 info_command!(0x08, TargetBoardNameCommand, Option<String>);
 
+info_command!(0x09, ProductFirmwareVersionCommand, Option<String>);
+
 info_command!(0xF0, CapabilitiesCommand, Capabilities);
 
 #[derive(Copy, Clone, Debug)]
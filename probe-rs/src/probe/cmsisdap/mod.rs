@@ -12,10 +12,13 @@ use crate::{
     },
     probe::{
         cmsisdap::commands::{
-            general::info::{CapabilitiesCommand, PacketCountCommand, SWOTraceBufferSizeCommand},
+            general::info::{
+                CapabilitiesCommand, FirmwareVersionCommand, PacketCountCommand,
+                ProductFirmwareVersionCommand, SWOTraceBufferSizeCommand,
+            },
             CmsisDapError,
         },
-        BatchCommand,
+        BatchCommand, PinRequest,
     },
     DebugProbe, DebugProbeError, DebugProbeSelector, Error as ProbeRsError, WireProtocol,
 };
@@ -49,6 +52,7 @@ pub struct CmsisDap {
     pub device: CmsisDapDevice,
     _hw_version: u8,
     _jtag_version: u8,
+    firmware_version: Option<String>,
     protocol: Option<WireProtocol>,
 
     packet_size: u16,
@@ -80,6 +84,31 @@ impl std::fmt::Debug for CmsisDap {
 }
 
 impl CmsisDap {
+    /// Reads the product firmware version, which is only reported by probes implementing
+    /// CMSIS-DAP 2.1 or later. Older probes only report the version of the CMSIS-DAP protocol,
+    /// which is used instead.
+    ///
+    /// The version is only informational, so a probe which rejects the queries is still usable.
+    ///
+    /// The strings are zero terminated, and the terminator is removed.
+    fn read_firmware_version(device: &mut CmsisDapDevice) -> Option<String> {
+        match commands::send_command(device, ProductFirmwareVersionCommand {}) {
+            Ok(Some(version)) if !version.trim_end_matches('\0').is_empty() => {
+                return Some(version.trim_end_matches('\0').to_owned())
+            }
+            Ok(_) => (),
+            Err(error) => log::debug!("Could not read the product firmware version: {}", error),
+        }
+
+        match commands::send_command(device, FirmwareVersionCommand {}) {
+            Ok(version) => version.map(|version| version.trim_end_matches('\0').to_owned()),
+            Err(error) => {
+                log::debug!("Could not read the CMSIS-DAP protocol version: {}", error);
+                None
+            }
+        }
+    }
+
     pub fn new_from_device(mut device: CmsisDapDevice) -> Result<Self, DebugProbeError> {
         // Discard anything left in buffer, as otherwise
         // we'll get out of sync between requests and responses.
@@ -93,6 +122,8 @@ impl CmsisDap {
         let packet_count = commands::send_command(&mut device, PacketCountCommand {})?;
         let caps: Capabilities = commands::send_command(&mut device, CapabilitiesCommand {})?;
         log::debug!("Detected probe capabilities: {:?}", caps);
+        let firmware_version = Self::read_firmware_version(&mut device);
+        log::debug!("Probe firmware version: {:?}", firmware_version);
         let mut swo_buffer_size = None;
         if caps.swo_uart_implemented || caps.swo_manchester_implemented {
             let swo_size = commands::send_command(&mut device, SWOTraceBufferSizeCommand {})?;
@@ -104,6 +135,7 @@ impl CmsisDap {
             device,
             _hw_version: 0,
             _jtag_version: 0,
+            firmware_version,
             protocol: None,
             packet_count,
             packet_size,
@@ -397,6 +429,10 @@ impl DebugProbe for CmsisDap {
         "CMSIS-DAP"
     }
 
+    fn firmware_version(&self) -> Option<String> {
+        self.firmware_version.clone()
+    }
+
     /// Get the currently set maximum speed.
     ///
     /// CMSIS-DAP offers no possibility to get the actual speed used.
@@ -549,6 +585,19 @@ impl DebugProbe for CmsisDap {
     fn try_as_dap_probe(&mut self) -> Option<&mut dyn DapProbe> {
         Some(self)
     }
+
+    fn set_pins(&mut self, request: PinRequest) -> Result<Option<Pins>, DebugProbeError> {
+        // The wait time of DAP_SWJ_Pins is given in microseconds.
+        let request = SWJPinsRequest::from_raw_values(
+            request.output().0,
+            request.select().0,
+            request.wait_time().as_micros() as u32,
+        );
+
+        let pins: SWJPinsResponse = commands::send_command(&mut self.device, request)?;
+
+        Ok(Some(pins))
+    }
 }
 
 impl RawDapAccess for CmsisDap {
@@ -708,6 +757,10 @@ impl RawDapAccess for CmsisDap {
         self
     }
 
+    fn probe_mut(&mut self) -> &mut dyn DebugProbe {
+        self
+    }
+
     fn swj_sequence(&mut self, bit_len: u8, bits: u64) -> Result<(), DebugProbeError> {
         let data = bits.to_le_bytes();
 
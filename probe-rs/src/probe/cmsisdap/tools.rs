@@ -30,10 +30,11 @@ pub fn list_cmsisdap_devices() -> Vec<DebugProbeInfo> {
     if let Ok(api) = hidapi::HidApi::new() {
         for device in api.device_list() {
             if let Some(info) = get_cmsisdap_hid_info(device) {
-                if !probes
-                    .iter()
-                    .any(|p| p.vendor_id == info.vendor_id && p.product_id == info.product_id)
-                {
+                if !probes.iter().any(|p| {
+                    p.vendor_id == info.vendor_id
+                        && p.product_id == info.product_id
+                        && p.serial_number == info.serial_number
+                }) {
                     log::trace!("Adding new HID-only probe {:?}", info);
                     probes.push(info)
                 } else {
@@ -115,6 +116,7 @@ fn get_cmsisdap_info(device: &Device<rusb::Context>) -> Option<DebugProbeInfo> {
             serial_number: sn_str,
             probe_type: DebugProbeType::CmsisDap,
             hid_interface: cmsis_dap_interface,
+            firmware_version: None,
         })
     } else {
         None
@@ -139,6 +141,7 @@ fn get_cmsisdap_hid_info(device: &hidapi::DeviceInfo) -> Option<DebugProbeInfo>
                 serial_number: device.serial_number().map(|s| s.to_owned()),
                 probe_type: DebugProbeType::CmsisDap,
                 hid_interface: Some(device.interface_number() as u8),
+                firmware_version: None,
             });
         }
     }
@@ -234,17 +237,10 @@ fn device_matches(
     selector: &DebugProbeSelector,
     serial_str: Option<String>,
 ) -> bool {
-    if device_descriptor.vendor_id() == selector.vendor_id
-        && device_descriptor.product_id() == selector.product_id
-    {
-        if selector.serial_number.is_some() {
-            serial_str == selector.serial_number
-        } else {
-            true
-        }
-    } else {
-        false
-    }
+    selector.matches_vid_pid(
+        device_descriptor.vendor_id(),
+        device_descriptor.product_id(),
+    ) && selector.matches_serial_number(serial_str.as_deref())
 }
 
 /// Attempt to open the given DebugProbeInfo in CMSIS-DAP v2 mode if possible,
@@ -325,7 +321,6 @@ pub fn open_device_from_selector(
     // If rusb failed or the device didn't support v2, try using hidapi to open in v1 mode.
     let vid = selector.vendor_id;
     let pid = selector.product_id;
-    let sn = &selector.serial_number;
 
     log::debug!(
         "Attempting to open {:04x}:{:04x} in CMSIS-DAP v1 mode",
@@ -344,11 +339,8 @@ pub fn open_device_from_selector(
     // which is not always what we want.
     let device_info = device_list
         .find(|info| {
-            let mut device_match = info.vendor_id() == vid && info.product_id() == pid;
-
-            if let Some(sn) = sn {
-                device_match &= Some(sn.as_ref()) == info.serial_number();
-            }
+            let mut device_match = selector.matches_vid_pid(info.vendor_id(), info.product_id())
+                && selector.matches_serial_number(info.serial_number());
 
             if let Some(hid_interface) =
                 hid_device_info.as_ref().and_then(|info| info.hid_interface)
@@ -261,6 +261,10 @@ impl EspUsbJtag {
 }
 
 impl JTAGAccess for EspUsbJtag {
+    fn probe_mut(&mut self) -> &mut dyn DebugProbe {
+        self
+    }
+
     fn set_ir_len(&mut self, len: u32) {
         if len != 5 {
             panic!("Only IR Length of 5 is currently supported");
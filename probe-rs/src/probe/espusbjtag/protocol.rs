@@ -74,13 +74,11 @@ impl ProtocolHandler {
             .find_map(|device| {
                 let descriptor = device.device_descriptor().ok()?;
                 // First match the VID & PID.
-                if selector.vendor_id == descriptor.vendor_id()
-                    && selector.product_id == descriptor.product_id()
-                {
+                if selector.matches_vid_pid(descriptor.vendor_id(), descriptor.product_id()) {
                     // If the VID & PID match, match the serial if one was given.
-                    if let Some(serial) = &selector.serial_number {
+                    if selector.serial_number.is_some() {
                         let sn_str = read_serial_number(&device, &descriptor).ok();
-                        if sn_str.as_ref() == Some(serial) {
+                        if selector.matches_serial_number(sn_str.as_deref()) {
                             Some(device)
                         } else {
                             None
@@ -199,6 +199,10 @@ impl RawDapAccess for FakeProbe {
     fn into_probe(self: Box<Self>) -> Box<dyn DebugProbe> {
         self
     }
+
+    fn probe_mut(&mut self) -> &mut dyn DebugProbe {
+        self
+    }
 }
 
 #[derive(Debug)]
@@ -316,6 +320,10 @@ impl ArmProbeInterface for FakeArmInterface<Initialized> {
         Ok(None)
     }
 
+    fn probe_mut(&mut self) -> &mut dyn DebugProbe {
+        self.probe.as_mut()
+    }
+
     fn close(self: Box<Self>) -> Probe {
         Probe::from_attached_probe(self.probe)
     }
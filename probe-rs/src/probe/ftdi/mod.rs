@@ -1,5 +1,6 @@
 use crate::architecture::{
     arm::communication_interface::UninitializedArmProbe,
+    mips::communication_interface::MipsCommunicationInterface,
     riscv::communication_interface::RiscvCommunicationInterface,
 };
 use crate::probe::{JTAGAccess, ProbeCreationError};
@@ -546,6 +547,19 @@ impl DebugProbe for FtdiProbe {
         true
     }
 
+    fn try_get_mips_interface(
+        self: Box<Self>,
+    ) -> Result<MipsCommunicationInterface, (Box<dyn DebugProbe>, DebugProbeError)> {
+        match MipsCommunicationInterface::new(self) {
+            Ok(interface) => Ok(interface),
+            Err((probe, err)) => Err((probe.into_probe(), err)),
+        }
+    }
+
+    fn has_mips_interface(&self) -> bool {
+        true
+    }
+
     fn into_probe(self: Box<Self>) -> Box<dyn DebugProbe> {
         self
     }
@@ -559,6 +573,10 @@ impl DebugProbe for FtdiProbe {
 }
 
 impl JTAGAccess for FtdiProbe {
+    fn probe_mut(&mut self) -> &mut dyn DebugProbe {
+        self
+    }
+
     fn read_register(&mut self, address: u32, len: u32) -> Result<Vec<u8>, DebugProbeError> {
         log::debug!("read_register({:#x}, {})", address, len);
         let r = self
@@ -761,6 +779,7 @@ fn get_device_info(device: &rusb::Device<rusb::Context>) -> Option<DebugProbeInf
         serial_number: sn_str,
         probe_type: DebugProbeType::Ftdi,
         hid_interface: None,
+        firmware_version: None,
     })
 }
 
@@ -1337,6 +1337,10 @@ impl<Probe: DebugProbe + RawProtocolIo + JTAGAccess + 'static> RawDapAccess for
         self
     }
 
+    fn probe_mut(&mut self) -> &mut dyn DebugProbe {
+        self
+    }
+
     fn swj_sequence(&mut self, bit_len: u8, mut bits: u64) -> Result<(), DebugProbeError> {
         let protocol = self.active_protocol().expect("No protocol set");
 
@@ -1581,6 +1585,10 @@ mod test {
     }
 
     impl JTAGAccess for MockJaylink {
+        fn probe_mut(&mut self) -> &mut dyn DebugProbe {
+            self
+        }
+
         fn set_ir_len(&mut self, _len: u32) {
             todo!()
         }
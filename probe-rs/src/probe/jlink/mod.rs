@@ -11,12 +11,14 @@ use crate::{
     architecture::{
         arm::{
             communication_interface::DapProbe, communication_interface::UninitializedArmProbe,
-            swo::SwoConfig, ArmCommunicationInterface, SwoAccess,
+            swo::SwoConfig, ArmCommunicationInterface, Pins, SwoAccess,
         },
+        mips::communication_interface::MipsCommunicationInterface,
         riscv::communication_interface::RiscvCommunicationInterface,
     },
     probe::{
-        DebugProbe, DebugProbeError, DebugProbeInfo, DebugProbeType, JTAGAccess, WireProtocol,
+        DebugProbe, DebugProbeError, DebugProbeInfo, DebugProbeType, JTAGAccess, PinRequest,
+        ScanChainPosition, WireProtocol,
     },
     DebugProbeSelector, Error as ProbeRsError,
 };
@@ -39,6 +41,9 @@ pub(crate) struct JLink {
     // JTAG IR register length
     ir_len: usize,
 
+    /// Position of the selected TAP in the JTAG scan chain
+    scan_chain: ScanChainPosition,
+
     /// Currently selected protocol
     protocol: Option<WireProtocol>,
 
@@ -103,8 +108,12 @@ impl JLink {
 
         let tms_enter_shift = [true, false, false];
 
+        // The other TAPs in the chain are in BYPASS and add one bit each
+        let shift_bits =
+            self.scan_chain.tdo_side_taps + register_bits + self.scan_chain.tdi_side_taps;
+
         // Last bit of data is shifted out when we exi the SHIFT-DR State
-        let tms_shift_out_value = iter::repeat(false).take(register_bits - 1);
+        let tms_shift_out_value = vec![false; shift_bits - 1];
 
         let tms_enter_idle = [true, true, false];
 
@@ -125,6 +134,9 @@ impl JLink {
 
         let _remainder = response.split_off(tms_enter_shift.len());
 
+        // The bits of the TAPs between the selected TAP and TDO are shifted out first
+        let _bypass = response.split_off(self.scan_chain.tdo_side_taps);
+
         let mut remaining_bits = register_bits;
 
         let mut result = Vec::new();
@@ -165,14 +177,18 @@ impl JLink {
 
         let tms_enter_ir_shift = [true, true, false, false];
 
+        // The other TAPs in the chain are put into BYPASS, by shifting ones into their IR
+        let shift_bits = self.scan_chain.tdo_side_ir_len + len + self.scan_chain.tdi_side_ir_len;
+
         // The last bit will be transmitted when exiting the shift state,
         // so we need to stay in the shift stay for one period less than
         // we have bits to transmit
-        let tms_data = iter::repeat(false).take(len - 1);
+        let tms_data = vec![false; shift_bits - 1];
 
         let tms_enter_idle = [true, true, false];
 
-        let mut tms = Vec::with_capacity(tms_enter_ir_shift.len() + len + tms_enter_ir_shift.len());
+        let mut tms =
+            Vec::with_capacity(tms_enter_ir_shift.len() + shift_bits + tms_enter_ir_shift.len());
 
         tms.extend_from_slice(&tms_enter_ir_shift);
         tms.extend(tms_data);
@@ -184,9 +200,11 @@ impl JLink {
         // the last bit is transmitted when exiting the IR shift state
         let tdi_enter_idle = [false, false];
 
-        let mut tdi = Vec::with_capacity(tdi_enter_ir_shift.len() + tdi_enter_idle.len() + len);
+        let mut tdi =
+            Vec::with_capacity(tdi_enter_ir_shift.len() + tdi_enter_idle.len() + shift_bits);
 
         tdi.extend_from_slice(&tdi_enter_ir_shift);
+        tdi.extend(vec![true; self.scan_chain.tdo_side_ir_len]);
 
         let num_bytes = len / 8;
 
@@ -211,6 +229,7 @@ impl JLink {
             }
         }
 
+        tdi.extend(vec![true; self.scan_chain.tdi_side_ir_len]);
         tdi.extend_from_slice(&tdi_enter_idle);
 
         log::trace!("tms: {:?}", tms);
@@ -238,12 +257,16 @@ impl JLink {
 
         let tms_enter_shift = [true, false, false];
 
+        // The other TAPs in the chain are in BYPASS and add one bit each
+        let shift_bits =
+            self.scan_chain.tdo_side_taps + register_bits + self.scan_chain.tdi_side_taps;
+
         // Last bit of data is shifted out when we exi the SHIFT-DR State
-        let tms_shift_out_value = iter::repeat(false).take(register_bits - 1);
+        let tms_shift_out_value = vec![false; shift_bits - 1];
 
         let tms_enter_idle = [true, true, false];
 
-        let mut tms = Vec::with_capacity(register_bits + 7);
+        let mut tms = Vec::with_capacity(shift_bits + 7);
 
         tms.extend_from_slice(&tms_enter_shift);
         tms.extend(tms_shift_out_value);
@@ -254,10 +277,10 @@ impl JLink {
         let tdi_enter_idle = [false, false];
 
         // TODO: TDI data
-        let mut tdi =
-            Vec::with_capacity(tdi_enter_shift.len() + tdi_enter_idle.len() + register_bits);
+        let mut tdi = Vec::with_capacity(tdi_enter_shift.len() + tdi_enter_idle.len() + shift_bits);
 
         tdi.extend_from_slice(&tdi_enter_shift);
+        tdi.extend(vec![false; self.scan_chain.tdo_side_taps]);
 
         let num_bytes = register_bits / 8;
 
@@ -282,6 +305,7 @@ impl JLink {
             }
         }
 
+        tdi.extend(vec![false; self.scan_chain.tdi_side_taps]);
         tdi.extend_from_slice(&tdi_enter_idle);
 
         // We need to stay in the idle cycle a bit
@@ -294,6 +318,9 @@ impl JLink {
 
         let _remainder = response.split_off(tms_enter_shift.len());
 
+        // The bits of the TAPs between the selected TAP and TDO are shifted out first
+        let _bypass = response.split_off(self.scan_chain.tdo_side_taps);
+
         let mut remaining_bits = register_bits;
 
         let mut result = Vec::new();
@@ -322,12 +349,12 @@ impl DebugProbe for JLink {
         let selector = selector.into();
         let mut jlinks = jaylink::scan_usb()?
             .filter_map(|usb_info| {
-                if usb_info.vid() == selector.vendor_id && usb_info.pid() == selector.product_id {
+                if selector.matches_vid_pid(usb_info.vid(), usb_info.pid()) {
                     let device = usb_info.open();
-                    if let Some(serial_number) = selector.serial_number.as_deref() {
+                    if selector.serial_number.is_some() {
                         if device
                             .as_ref()
-                            .map(|d| d.serial_string() == serial_number)
+                            .map(|d| selector.matches_serial_number(Some(d.serial_string())))
                             .unwrap_or(false)
                         {
                             Some(device)
@@ -393,6 +420,7 @@ impl DebugProbe for JLink {
             supported_protocols,
             jtag_idle_cycles: 0,
             ir_len: 0,
+            scan_chain: ScanChainPosition::default(),
             protocol: None,
             current_ir_reg: 1,
             speed_khz: 0,
@@ -423,6 +451,10 @@ impl DebugProbe for JLink {
         "J-Link"
     }
 
+    fn firmware_version(&self) -> Option<String> {
+        self.handle.read_firmware_version().ok()
+    }
+
     fn speed_khz(&self) -> u32 {
         self.speed_khz
     }
@@ -511,6 +543,9 @@ impl DebugProbe for JLink {
                 log::debug!("Resetting JTAG chain using trst");
                 self.handle.reset_trst()?;
 
+                // JTAG routers remove the TAPs they added to the chain on reset
+                self.scan_chain = ScanChainPosition::default();
+
                 log::debug!("Resetting JTAG chain by setting tms high for 32 bits");
 
                 // Reset JTAG chain (5 times TMS high), and enter idle state afterwards
@@ -580,6 +615,22 @@ impl DebugProbe for JLink {
         }
     }
 
+    fn try_get_mips_interface(
+        self: Box<Self>,
+    ) -> Result<MipsCommunicationInterface, (Box<dyn DebugProbe>, DebugProbeError)> {
+        if self.supported_protocols.contains(&WireProtocol::Jtag) {
+            match MipsCommunicationInterface::new(self) {
+                Ok(interface) => Ok(interface),
+                Err((probe, err)) => Err((probe.into_probe(), err)),
+            }
+        } else {
+            Err((
+                RawDapAccess::into_probe(self),
+                DebugProbeError::InterfaceNotAvailable("JTAG"),
+            ))
+        }
+    }
+
     fn get_swo_interface(&self) -> Option<&dyn SwoAccess> {
         Some(self as _)
     }
@@ -596,6 +647,10 @@ impl DebugProbe for JLink {
         self.supported_protocols.contains(&WireProtocol::Jtag)
     }
 
+    fn has_mips_interface(&self) -> bool {
+        self.supported_protocols.contains(&WireProtocol::Jtag)
+    }
+
     fn into_probe(self: Box<Self>) -> Box<dyn DebugProbe> {
         self
     }
@@ -604,6 +659,10 @@ impl DebugProbe for JLink {
         Some(self)
     }
 
+    fn try_as_jtag_probe(&mut self) -> Option<&mut dyn JTAGAccess> {
+        Some(self)
+    }
+
     fn try_get_arm_interface<'probe>(
         self: Box<Self>,
     ) -> Result<Box<dyn UninitializedArmProbe + 'probe>, (Box<dyn DebugProbe>, DebugProbeError)>
@@ -617,21 +676,82 @@ impl DebugProbe for JLink {
         // Convert the integer millivolts value from self.handle to volts as an f32.
         Ok(Some((self.handle.read_target_voltage()? as f32) / 1000f32))
     }
+
+    fn has_target_power_control(&self) -> bool {
+        self.handle.capabilities().contains(Capability::SetKsPower)
+    }
+
+    fn set_target_power(&mut self, enabled: bool) -> Result<(), DebugProbeError> {
+        if !self.has_target_power_control() {
+            return Err(DebugProbeError::CommandNotSupportedByProbe(
+                "set_target_power",
+            ));
+        }
+
+        self.handle.set_kickstart_power(enabled)?;
+
+        Ok(())
+    }
+
+    fn set_pins(&mut self, request: PinRequest) -> Result<Option<Pins>, DebugProbeError> {
+        let select = request.select();
+        let output = request.output();
+
+        // The J-Link can drive all pins except for the clock, and cannot read any of them back.
+        if select.swclk_tck() || select.tdo() {
+            return Err(DebugProbeError::CommandNotSupportedByProbe("set_pins"));
+        }
+
+        if select.nreset() {
+            self.handle.set_reset(output.nreset())?;
+        }
+        if select.ntrst() {
+            self.handle.set_trst(output.ntrst())?;
+        }
+        if select.tdi() {
+            self.handle.set_tdi(output.tdi())?;
+        }
+        if select.swdio_tms() {
+            self.handle.set_tms(output.swdio_tms())?;
+        }
+
+        // The J-Link cannot wait for the pins to settle, so we just wait for the
+        // full time on the host.
+        std::thread::sleep(request.wait_time());
+
+        Ok(None)
+    }
 }
 
 impl JTAGAccess for JLink {
+    fn probe_mut(&mut self) -> &mut dyn DebugProbe {
+        self
+    }
+
     fn set_ir_len(&mut self, len: u32) {
         self.ir_len = len as usize;
     }
 
+    fn set_scan_chain_position(
+        &mut self,
+        position: ScanChainPosition,
+    ) -> Result<(), DebugProbeError> {
+        self.scan_chain = position;
+
+        // The IR has to be written again, to put the other TAPs into BYPASS
+        self.current_ir_reg = u32::MAX;
+
+        Ok(())
+    }
+
     /// Read the data register
     fn read_register(&mut self, address: u32, len: u32) -> Result<Vec<u8>, DebugProbeError> {
         let address_bits = address.to_le_bytes();
 
-        // TODO: This is limited to 5 bit addresses for now
-        if address > 0x1f {
+        // TODO: This is limited to 8 bit addresses for now
+        if address > 0xff {
             return Err(DebugProbeError::NotImplemented(
-                "JTAG Register addresses are fixed to 5 bits",
+                "JTAG Register addresses are fixed to 8 bits",
             ));
         }
 
@@ -653,10 +773,10 @@ impl JTAGAccess for JLink {
     ) -> Result<Vec<u8>, DebugProbeError> {
         let address_bits = address.to_le_bytes();
 
-        // TODO: This is limited to 5 bit addresses for now
-        if address > 0x1f {
+        // TODO: This is limited to 8 bit addresses for now
+        if address > 0xff {
             return Err(DebugProbeError::NotImplemented(
-                "JTAG Register addresses are fixed to 5 bits",
+                "JTAG Register addresses are fixed to 8 bits",
             ));
         }
 
@@ -744,7 +864,7 @@ pub(crate) fn list_jlink_devices() -> Vec<DebugProbeInfo> {
             .map(|device_info| {
                 let vid = device_info.vid();
                 let pid = device_info.pid();
-                let (serial, product) = if let Ok(device) = device_info.open() {
+                let (serial, product, firmware_version) = if let Ok(device) = device_info.open() {
                     let serial = device.serial_string();
                     let serial = if serial.is_empty() {
                         None
@@ -757,9 +877,9 @@ pub(crate) fn list_jlink_devices() -> Vec<DebugProbeInfo> {
                     } else {
                         Some(product.to_owned())
                     };
-                    (serial, product)
+                    (serial, product, device.read_firmware_version().ok())
                 } else {
-                    (None, None)
+                    (None, None, None)
                 };
                 DebugProbeInfo::new(
                     format!(
@@ -772,6 +892,7 @@ pub(crate) fn list_jlink_devices() -> Vec<DebugProbeInfo> {
                     DebugProbeType::JLink,
                     None,
                 )
+                .with_firmware_version(firmware_version)
             })
             .collect(),
         Err(_) => Vec::new(),
@@ -80,6 +80,11 @@ impl DebugProbe for StLink<StLinkUsbDevice> {
         &self.name
     }
 
+    fn firmware_version(&self) -> Option<String> {
+        // Use the naming scheme of ST's firmware updater, e.g. V2J37.
+        Some(format!("V{}J{}", self.hw_version, self.jtag_version))
+    }
+
     fn speed_khz(&self) -> u32 {
         match self.protocol {
             WireProtocol::Swd => self.swd_speed_khz,
@@ -1305,6 +1310,10 @@ impl<'probe> ArmProbeInterface for StlinkArmDebug {
         Ok(self.ap_information.len())
     }
 
+    fn probe_mut(&mut self) -> &mut dyn DebugProbe {
+        self.probe.as_mut()
+    }
+
     fn close(self: Box<Self>) -> Probe {
         Probe::from_attached_probe(self.probe)
     }
@@ -0,0 +1,154 @@
+//! Access to the virtual COM port (VCOM) which many probes, e.g. most CMSIS-DAP probes,
+//! expose as an additional USB CDC interface, bridged to a UART of the target.
+
+use super::{DebugProbeInfo, DebugProbeSelector};
+
+use std::io;
+use std::time::Duration;
+
+use serialport::{SerialPort, SerialPortType};
+
+/// An error which occurred while accessing the virtual COM port of a probe.
+#[derive(thiserror::Error, Debug)]
+pub enum VcomError {
+    /// The probe does not expose a virtual COM port.
+    #[error("No virtual COM port was found for the probe {0}.")]
+    NotFound(DebugProbeSelector),
+    /// The serial ports of the system could not be listed.
+    #[error("The serial ports could not be listed.")]
+    ListPorts(#[source] serialport::Error),
+    /// The virtual COM port could not be opened.
+    #[error("Failed to open the virtual COM port '{port}'.")]
+    Open {
+        /// The name of the port which could not be opened.
+        port: String,
+        /// The underlying error.
+        #[source]
+        source: serialport::Error,
+    },
+    /// The virtual COM port could not be configured.
+    #[error("Failed to configure the virtual COM port '{port}'.")]
+    Configure {
+        /// The name of the port which could not be configured.
+        port: String,
+        /// The underlying error.
+        #[source]
+        source: serialport::Error,
+    },
+}
+
+/// The virtual COM port of a probe.
+///
+/// Data received from the target can be read with the [`io::Read`] implementation,
+/// and data can be sent to the target with the [`io::Write`] implementation.
+pub struct VirtualComPort {
+    name: String,
+    port: Box<dyn SerialPort>,
+}
+
+impl VirtualComPort {
+    /// The default time a read waits for data before it returns an error of kind
+    /// [`io::ErrorKind::TimedOut`].
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_millis(10);
+
+    /// Open the first virtual COM port of the probe described by `info`.
+    pub(crate) fn open(info: &DebugProbeInfo, baud_rate: u32) -> Result<Self, VcomError> {
+        let name = list_ports(info)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| VcomError::NotFound(info.into()))?;
+
+        log::debug!("Opening virtual COM port {} of probe {:?}", name, info);
+
+        let port = serialport::new(&name, baud_rate)
+            .timeout(Self::DEFAULT_TIMEOUT)
+            .open()
+            .map_err(|source| VcomError::Open {
+                port: name.clone(),
+                source,
+            })?;
+
+        Ok(Self { name, port })
+    }
+
+    /// The name of the serial port, e.g. `/dev/ttyACM0` or `COM3`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The baud rate of the port.
+    pub fn baud_rate(&self) -> Result<u32, VcomError> {
+        self.port
+            .baud_rate()
+            .map_err(|source| self.configure_error(source))
+    }
+
+    /// Change the baud rate of the port.
+    pub fn set_baud_rate(&mut self, baud_rate: u32) -> Result<(), VcomError> {
+        self.port
+            .set_baud_rate(baud_rate)
+            .map_err(|source| self.configure_error(source))
+    }
+
+    /// Set the time a read waits for data before it times out.
+    pub fn set_timeout(&mut self, timeout: Duration) -> Result<(), VcomError> {
+        self.port
+            .set_timeout(timeout)
+            .map_err(|source| self.configure_error(source))
+    }
+
+    fn configure_error(&self, source: serialport::Error) -> VcomError {
+        VcomError::Configure {
+            port: self.name.clone(),
+            source,
+        }
+    }
+}
+
+impl std::fmt::Debug for VirtualComPort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VirtualComPort")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+impl io::Read for VirtualComPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.port.read(buf)
+    }
+}
+
+impl io::Write for VirtualComPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.port.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.port.flush()
+    }
+}
+
+/// List the names of all serial ports which belong to the probe described by `info`.
+///
+/// The ports are matched by the USB VID, PID and serial number of the probe.
+pub(crate) fn list_ports(info: &DebugProbeInfo) -> Result<Vec<String>, VcomError> {
+    let mut ports: Vec<_> = serialport::available_ports()
+        .map_err(VcomError::ListPorts)?
+        .into_iter()
+        .filter(|port| match &port.port_type {
+            SerialPortType::UsbPort(usb) => {
+                usb.vid == info.vendor_id
+                    && usb.pid == info.product_id
+                    && (info.serial_number.is_none() || usb.serial_number == info.serial_number)
+            }
+            _ => false,
+        })
+        .map(|port| port.port_name)
+        .collect();
+
+    // Probes with multiple ports should always return them in the same order.
+    ports.sort();
+
+    Ok(ports)
+}
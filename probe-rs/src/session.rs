@@ -1,22 +1,46 @@
-use crate::architecture::arm::sequences::DefaultArmSequence;
+use crate::architecture::arm::armv8a::Armv8a;
+use crate::architecture::arm::sequences::{stm32, ArmDebugSequence, DefaultArmSequence};
 use crate::architecture::arm::{ApAddress, DpAddress};
-use crate::config::{ChipInfo, MemoryRegion, RegistryError, Target, TargetSelector};
+use crate::config::{
+    ChipInfo, ConnectUnderReset, MemoryRegion, MemoryRegionInfo, RegistryError, Target,
+    TargetSelector, VariantId, Vendor,
+};
 use crate::core::{Architecture, CoreState, SpecificCoreState};
+use crate::memory::Memory;
+use crate::probe::{self, PinRequest};
 use crate::{
     architecture::{
         arm::{
             ap::{GenericAp, MemoryAp},
             communication_interface::{ArmProbeInterface, MemoryApInformation},
             memory::{Component, CoresightComponent},
-            ApInformation, SwoConfig, SwoReader,
+            ApInformation, Pins, SwoConfig, SwoReader,
+        },
+        mips::communication_interface::MipsCommunicationInterface,
+        riscv::communication_interface::{
+            RiscvAuthentication, RiscvCommunicationInterface, RiscvError,
         },
-        riscv::communication_interface::RiscvCommunicationInterface,
     },
     config::DebugSequence,
 };
-use crate::{AttachMethod, Core, CoreType, Error, Probe};
+use crate::{AttachMethod, Core, CoreInformation, CoreType, DebugProbe, Error, Probe};
 use anyhow::anyhow;
-use std::{fmt, time::Duration};
+use std::{collections::BTreeMap, fmt, ops::Range, path::Path, sync::Arc, time::Duration};
+
+mod breakpoints;
+mod checksum;
+mod multiplexer;
+mod snapshot;
+
+pub use breakpoints::{Breakpoint, BreakpointKind, BreakpointLocation};
+pub use multiplexer::{CoreHandle, CoreMultiplexer};
+pub use snapshot::Snapshot;
+
+/// The CTI channel on which ARMv8-A cores receive halt requests, see `armv8a_core_start`.
+const CTI_HALT_CHANNEL: usize = 0;
+
+/// The CTI channel on which ARMv8-A cores receive restart requests, see `armv8a_core_start`.
+const CTI_RESTART_CHANNEL: usize = 1;
 
 /// The `Session` struct represents an active debug session.
 ///
@@ -41,11 +65,20 @@ pub struct Session {
     target: Target,
     interface: ArchitectureInterface,
     cores: Vec<(SpecificCoreState, CoreState)>,
+    permissions: Permissions,
+    options: AttachOptions,
+    breakpoints: Vec<Breakpoint>,
+    /// The original instructions of the flash breakpoints, by their address.
+    pub(crate) flash_breakpoints: BTreeMap<u64, Vec<u8>>,
+    probe_serial_number: Option<String>,
+    /// The TrustZone configuration of STM32s with TrustZone enabled.
+    trustzone: Option<stm32::TrustZone>,
 }
 
 enum ArchitectureInterface {
     Arm(Box<dyn ArmProbeInterface + 'static>),
     Riscv(Box<RiscvCommunicationInterface>),
+    Mips(Box<MipsCommunicationInterface>),
 }
 
 impl fmt::Debug for ArchitectureInterface {
@@ -56,6 +89,10 @@ impl fmt::Debug for ArchitectureInterface {
                 .debug_tuple("ArchitectureInterface::Riscv")
                 .field(iface)
                 .finish(),
+            ArchitectureInterface::Mips(iface) => f
+                .debug_tuple("ArchitectureInterface::Mips")
+                .field(iface)
+                .finish(),
         }
     }
 }
@@ -65,6 +102,7 @@ impl From<ArchitectureInterface> for Architecture {
         match value {
             ArchitectureInterface::Arm(_) => Architecture::Arm,
             ArchitectureInterface::Riscv(_) => Architecture::Riscv,
+            ArchitectureInterface::Mips(_) => Architecture::Mips,
         }
     }
 }
@@ -78,54 +116,99 @@ impl ArchitectureInterface {
     ) -> Result<Core<'probe>, Error> {
         match self {
             ArchitectureInterface::Arm(state) => {
-                let config = target
-                    .cores
-                    .get(core_state.id())
-                    .ok_or_else(|| Error::CoreNotFound(core_state.id()))?;
-                let arm_core_access_options = match &config.core_access_options {
-                    probe_rs_target::CoreAccessOptions::Arm(opt) => opt,
-                    probe_rs_target::CoreAccessOptions::Riscv(_) => {
-                        unreachable!("This should never happen. Please file a bug if it does.")
-                    }
-                };
-
-                let dp = match arm_core_access_options.psel {
-                    0 => DpAddress::Default,
-                    x => DpAddress::Multidrop(x),
-                };
-
-                let ap = ApAddress {
-                    dp,
-                    ap: arm_core_access_options.ap,
-                };
-                let memory = state.memory_interface(MemoryAp::new(ap))?;
+                let memory = arm_core_memory(state, core_state, target)?;
 
                 core.attach_arm(core_state, memory, target)
             }
             ArchitectureInterface::Riscv(state) => core.attach_riscv(core_state, state),
+            ArchitectureInterface::Mips(state) => core.attach_mips(core_state, state),
+        }
+    }
+
+    /// Attaches to the core if it is an ARMv8-A core with a CTI, see [`SpecificCoreState::attach_armv8a`].
+    fn attach_armv8a<'probe, 'target: 'probe>(
+        &'probe mut self,
+        core: &'probe mut SpecificCoreState,
+        core_state: &CoreState,
+        target: &'target Target,
+    ) -> Result<Option<Armv8a<'probe>>, Error> {
+        match self {
+            ArchitectureInterface::Arm(state) => {
+                let memory = arm_core_memory(state, core_state, target)?;
+
+                core.attach_armv8a(core_state, memory, target)
+            }
+            ArchitectureInterface::Riscv(_) | ArchitectureInterface::Mips(_) => Ok(None),
         }
     }
 }
 
+/// Returns the memory interface of the access port through which the given ARM core is accessed.
+fn arm_core_memory<'probe>(
+    interface: &'probe mut Box<dyn ArmProbeInterface>,
+    core_state: &CoreState,
+    target: &Target,
+) -> Result<Memory<'probe>, Error> {
+    let config = target
+        .cores
+        .get(core_state.id())
+        .ok_or_else(|| Error::CoreNotFound(core_state.id()))?;
+    let arm_core_access_options = match &config.core_access_options {
+        probe_rs_target::CoreAccessOptions::Arm(opt) => opt,
+        probe_rs_target::CoreAccessOptions::Riscv(_)
+        | probe_rs_target::CoreAccessOptions::Mips(_) => {
+            unreachable!("This should never happen. Please file a bug if it does.")
+        }
+    };
+
+    let dp = match arm_core_access_options.psel {
+        0 => DpAddress::Default,
+        x => DpAddress::Multidrop(x),
+    };
+
+    let ap = ApAddress {
+        dp,
+        ap: arm_core_access_options.ap,
+    };
+
+    interface.memory_interface(MemoryAp::new(ap))
+}
+
 impl Session {
     /// Open a new session with a given debug target.
     pub(crate) fn new(
         probe: Probe,
         target: TargetSelector,
-        attach_method: AttachMethod,
+        options: AttachOptions,
         permissions: Permissions,
     ) -> Result<Self, Error> {
+        let attach_method = options.method;
+        let probe_serial_number = probe.serial_number().map(ToOwned::to_owned);
         let (mut probe, target) = get_target_from_selector(target, attach_method, probe)?;
 
+        let memory_regions = crate::config::memory_regions(&target);
         let cores = target
             .cores
             .iter()
             .enumerate()
             .map(|(id, core)| {
-                (
-                    SpecificCoreState::from_core_type(core.core_type),
-                    Core::create_state(id, core.core_access_options.clone()),
-                )
+                let mut state = Core::create_state(id, core.core_access_options.clone());
+                state.set_memory_regions(
+                    memory_regions
+                        .iter()
+                        .filter(|region| region.is_accessible_by(&core.name))
+                        .cloned()
+                        .collect(),
+                );
+                state.set_rtt_scan_ranges(
+                    options
+                        .rtt_scan_ranges
+                        .clone()
+                        .unwrap_or_else(|| target.rtt_scan_ranges.clone()),
+                );
+                state.set_rtt_control_block(options.rtt_control_block);
+
+                (SpecificCoreState::from_core_type(core.core_type), state)
             })
             .collect();
 
@@ -134,7 +217,8 @@ impl Session {
                 let config = target.cores[0].clone();
                 let arm_core_access_options = match config.core_access_options {
                     probe_rs_target::CoreAccessOptions::Arm(opt) => opt,
-                    probe_rs_target::CoreAccessOptions::Riscv(_) => {
+                    probe_rs_target::CoreAccessOptions::Riscv(_)
+                    | probe_rs_target::CoreAccessOptions::Mips(_) => {
                         unreachable!("This should never happen. Please file a bug if it does.")
                     }
                 };
@@ -149,11 +233,15 @@ impl Session {
 
                 let sequence_handle = match &target.debug_sequence {
                     DebugSequence::Arm(sequence) => sequence.clone(),
-                    DebugSequence::Riscv(_) => {
+                    DebugSequence::Riscv(_) | DebugSequence::Mips => {
                         panic!("Mismatch between architecture and sequence type!")
                     }
                 };
 
+                let connect_under_reset = options
+                    .connect_under_reset
+                    .unwrap_or(target.connect_under_reset);
+
                 if AttachMethod::UnderReset == attach_method {
                     if let Some(dap_probe) = probe.try_as_dap_probe() {
                         sequence_handle.reset_hardware_assert(dap_probe)?;
@@ -165,6 +253,8 @@ impl Session {
                         log::info!("Falling back to standard probe reset.");
                         probe.target_reset_assert()?;
                     }
+
+                    std::thread::sleep(Duration::from_millis(connect_under_reset.assert_delay_ms));
                 }
 
                 probe.inner_attach()?;
@@ -173,12 +263,18 @@ impl Session {
 
                 let mut interface = interface.initialize(sequence_handle.clone())?;
 
-                // Enable debug mode
-                sequence_handle.debug_device_unlock(
-                    &mut interface,
-                    default_memory_ap,
-                    &permissions,
-                )?;
+                // Unlocking the device can erase or reset it, which is never done when the
+                // state of the target has to be preserved.
+                if !options.preserve_state {
+                    sequence_handle.debug_device_unlock(
+                        &mut interface,
+                        default_memory_ap,
+                        &permissions,
+                    )?;
+                }
+
+                check_variant(&target, &mut *interface, default_memory_ap);
+                let trustzone = read_trustzone(&target, &mut *interface, default_memory_ap);
 
                 {
                     // For each core, setup debugging
@@ -186,7 +282,8 @@ impl Session {
                         let config = target.cores[i].clone();
                         let arm_core_access_options = match config.core_access_options {
                             probe_rs_target::CoreAccessOptions::Arm(opt) => opt,
-                            probe_rs_target::CoreAccessOptions::Riscv(_) => {
+                            probe_rs_target::CoreAccessOptions::Riscv(_)
+                            | probe_rs_target::CoreAccessOptions::Mips(_) => {
                                 unreachable!(
                                     "This should never happen. Please file a bug if it does."
                                 )
@@ -201,56 +298,90 @@ impl Session {
                             ap: arm_core_access_options.ap,
                         });
 
-                        let mut memory_interface = interface.memory_interface(mem_ap)?;
-
                         // Enable debug mode
-                        sequence_handle.debug_core_start(
-                            &mut memory_interface,
-                            config.core_type,
-                            arm_core_access_options.debug_base,
-                            arm_core_access_options.cti_base,
-                        )?;
+                        let result =
+                            interface
+                                .memory_interface(mem_ap)
+                                .and_then(|mut memory_interface| {
+                                    sequence_handle.debug_core_start(
+                                        &mut memory_interface,
+                                        config.core_type,
+                                        arm_core_access_options.debug_base,
+                                        arm_core_access_options.cti_base,
+                                    )
+                                });
+
+                        match result {
+                            Ok(()) => {}
+                            // Secondary cores are often held in reset or clock gated until the
+                            // first core starts them, which must not prevent debugging the first core.
+                            Err(error) if i > 0 => log::warn!(
+                                "Could not start debugging core {} ({}), it may not be running yet: {}",
+                                i,
+                                config.name,
+                                error
+                            ),
+                            Err(error) => return Err(error),
+                        }
                     }
                 }
 
+                if options.suspend_watchdogs && !options.preserve_state {
+                    sequence_handle.watchdog_suspend(&mut interface, default_memory_ap)?;
+                }
+
                 let session = if attach_method == AttachMethod::UnderReset {
+                    let halt_timeout = Duration::from_millis(connect_under_reset.halt_timeout_ms);
+
                     {
                         let mut memory_interface = interface.memory_interface(default_memory_ap)?;
-                        // we need to halt the chip here
-                        sequence_handle.reset_catch_set(
-                            &mut memory_interface,
-                            config.core_type,
-                            arm_core_access_options.debug_base,
-                        )?;
+                        if connect_under_reset.halt {
+                            // we need to halt the chip here
+                            sequence_handle.reset_catch_set(
+                                &mut memory_interface,
+                                config.core_type,
+                                arm_core_access_options.debug_base,
+                            )?;
+                        }
                         sequence_handle.reset_hardware_deassert(&mut memory_interface)?;
                     }
 
+                    std::thread::sleep(Duration::from_millis(connect_under_reset.release_delay_ms));
+
                     let mut session = Session {
                         target,
                         interface: ArchitectureInterface::Arm(interface),
                         cores,
+                        permissions,
+                        options,
+                        breakpoints: Vec::new(),
+                        flash_breakpoints: BTreeMap::new(),
+                        probe_serial_number,
+                        trustzone,
                     };
 
-                    {
-                        // Wait for the core to be halted
-                        let mut core = session.core(0)?;
-                        core.wait_for_core_halted(Duration::from_millis(100))?;
-                    }
-
-                    {
-                        let interface = session.get_arm_interface()?;
-                        let mut memory_interface = interface.memory_interface(default_memory_ap)?;
-                        // we need to halt the chip here
-                        sequence_handle.reset_catch_clear(
-                            &mut memory_interface,
-                            config.core_type,
-                            arm_core_access_options.debug_base,
-                        )?;
-                    }
-
-                    {
-                        let mut core = session.core(0)?;
-                        core.wait_for_core_halted(Duration::from_millis(100))?;
+                    if connect_under_reset.halt {
+                        {
+                            // Wait for the core to be halted
+                            let mut core = session.core(0)?;
+                            core.wait_for_core_halted(halt_timeout)?;
+                        }
+
+                        {
+                            let interface = session.get_arm_interface()?;
+                            let mut memory_interface =
+                                interface.memory_interface(default_memory_ap)?;
+                            sequence_handle.reset_catch_clear(
+                                &mut memory_interface,
+                                config.core_type,
+                                arm_core_access_options.debug_base,
+                            )?;
+                        }
+
+                        {
+                            let mut core = session.core(0)?;
+                            core.wait_for_core_halted(halt_timeout)?;
+                        }
                     }
 
                     session
@@ -259,6 +390,12 @@ impl Session {
                         target,
                         interface: ArchitectureInterface::Arm(interface),
                         cores,
+                        permissions,
+                        options,
+                        breakpoints: Vec::new(),
+                        flash_breakpoints: BTreeMap::new(),
+                        probe_serial_number,
+                        trustzone,
                     }
                 };
 
@@ -269,37 +406,113 @@ impl Session {
 
                 let sequence_handle = match &target.debug_sequence {
                     DebugSequence::Riscv(sequence) => sequence.clone(),
-                    DebugSequence::Arm(_) => {
+                    DebugSequence::Arm(_) | DebugSequence::Mips => {
                         panic!("Mismatch between architecture and sequence type!")
                     }
                 };
 
                 probe.inner_attach()?;
 
-                let interface = probe
+                let mut interface = probe
                     .try_into_riscv_interface()
                     .map_err(|(_probe, err)| err)?;
 
+                if !interface.is_authenticated() {
+                    match &options.riscv_authentication {
+                        Some(authentication) => interface.authenticate(authentication)?,
+                        None => return Err(RiscvError::NotAuthenticated.into()),
+                    }
+                }
+
                 let mut session = Session {
                     target,
                     interface: ArchitectureInterface::Riscv(Box::new(interface)),
                     cores,
+                    permissions,
+                    options,
+                    breakpoints: Vec::new(),
+                    flash_breakpoints: BTreeMap::new(),
+                    probe_serial_number,
+                    trustzone: None,
                 };
 
-                {
-                    // Todo: Add multicore support. How to deal with any cores that are not active and won't respond?
+                if !session.options.preserve_state {
+                    // Each core of the target is a hart of the debug module.
+                    for n in 0..session.cores.len() {
+                        let mut core = session.core(n)?;
+
+                        core.halt(Duration::from_millis(100))?;
+                    }
+
+                    sequence_handle.on_connect(session.get_riscv_interface()?)?;
+
+                    if session.options.suspend_watchdogs {
+                        sequence_handle.watchdog_suspend(session.get_riscv_interface()?)?;
+                    }
+                }
+
+                session
+            }
+            Architecture::Mips => {
+                probe.inner_attach()?;
+
+                let interface = probe
+                    .try_into_mips_interface()
+                    .map_err(|(_probe, err)| err)?;
+
+                let mut session = Session {
+                    target,
+                    interface: ArchitectureInterface::Mips(Box::new(interface)),
+                    cores,
+                    permissions,
+                    options,
+                    breakpoints: Vec::new(),
+                    flash_breakpoints: BTreeMap::new(),
+                    probe_serial_number,
+                    trustzone: None,
+                };
+
+                if !session.options.preserve_state {
+                    // Memory and registers can only be accessed in debug mode.
                     let mut core = session.core(0)?;
 
                     core.halt(Duration::from_millis(100))?;
                 }
 
-                sequence_handle.on_connect(session.get_riscv_interface()?)?;
-
                 session
             }
         };
 
-        session.clear_all_hw_breakpoints()?;
+        // Breakpoints which are still set from an earlier session are part of the state
+        // of the target as well.
+        if !session.options.preserve_state {
+            for n in 0..session.cores.len() {
+                let result = session
+                    .core(n)
+                    .and_then(|mut core| core.clear_all_hw_breakpoints());
+
+                match result {
+                    Ok(()) => {}
+                    Err(error) if n > 0 => log::warn!(
+                        "Could not clear the hardware breakpoints of core {}: {}",
+                        n,
+                        error
+                    ),
+                    Err(error) => return Err(error),
+                }
+
+                if let Err(error) = session
+                    .core(n)
+                    .and_then(|mut core| core.clear_all_hw_watchpoints())
+                {
+                    log::warn!(
+                        "Could not clear the hardware watchpoints of core {}: {}",
+                        n,
+                        error
+                    );
+                }
+            }
+        }
 
         Ok(session)
     }
@@ -322,6 +535,32 @@ impl Session {
         probe.attach(target, permissions)
     }
 
+    /// Attach to a running target without changing its state.
+    ///
+    /// This is meant for inspecting a live device, e.g. in the field. The target is neither
+    /// reset nor halted, its watchdogs and vector catch (the `DEMCR` register on Cortex-M)
+    /// are not changed, and hardware breakpoints left by an earlier session are kept. Only
+    /// the debug domain is powered up and debugging is enabled in the core, e.g. by setting
+    /// `C_DEBUGEN` on Cortex-M, which does not affect the execution of the core.
+    ///
+    /// Devices which need an unlock sequence to be debugged can not be attached this way. Some
+    /// operations, like reading the registers of a RISC-V core, still require the core to be
+    /// halted explicitly with [`Core::halt`].
+    ///
+    /// This is a shorthand for [`Probe::attach_with_options`] with [`AttachOptions::preserve_state`].
+    pub fn attach_preserving_state(
+        probe: Probe,
+        target: impl Into<TargetSelector>,
+        permissions: Permissions,
+    ) -> Result<Session, Error> {
+        probe.attach_with_options(target, permissions, AttachOptions::new().preserve_state())
+    }
+
+    /// Returns `true` if the session was opened with [`AttachOptions::preserve_state`].
+    pub fn preserves_state(&self) -> bool {
+        self.options.preserve_state
+    }
+
     /// Lists the available cores with their number and their type.
     pub fn list_cores(&self) -> Vec<(usize, CoreType)> {
         self.cores
@@ -333,6 +572,14 @@ impl Session {
 
     /// Attaches to the core with the given number.
     ///
+    /// ## Core numbering
+    /// The number `n` is the index of the core in the `cores` list of the target description,
+    /// so it is the same for every session with the same target. On the STM32H745, for example,
+    /// core 0 is the Cortex-M7 (`main`) and core 1 the Cortex-M4 (`cm4`).
+    /// [Target::core_index_by_name] returns the number of a core by its name.
+    ///
+    /// Every core can be halted and resumed independently of the other cores.
+    ///
     /// ## Usage
     /// Everytime you want to perform an operation on the chip, you need to get the Core handle with the [Session::core()] method. This [Core] handle is merely a view into the core and provides a convenient API surface.
     ///
@@ -351,6 +598,15 @@ impl Session {
         self.interface.attach(core, core_state, &self.target)
     }
 
+    /// Returns a [`CoreMultiplexer`], which gives access to several cores at the same time.
+    ///
+    /// With [`Session::core`], only a single [`Core`] can exist at a time, because it borrows the
+    /// session exclusively. The handles of the multiplexer can be held for all cores at once, e.g.
+    /// to step one core while polling the RTT buffers of another one.
+    pub fn multiplexer(&mut self) -> CoreMultiplexer<'_> {
+        CoreMultiplexer::new(self)
+    }
+
     /// Read available data from the SWO interface without waiting.
     ///
     /// This method is only supported for ARM-based targets, and will
@@ -373,6 +629,56 @@ impl Session {
         Ok(SwoReader::new(interface))
     }
 
+    /// The serial number of the probe the session was opened with, see [`Probe::serial_number`].
+    pub fn probe_serial_number(&self) -> Option<&str> {
+        self.probe_serial_number.as_deref()
+    }
+
+    /// The TrustZone configuration of the chip, if it is an STM32 with TrustZone enabled.
+    pub(crate) fn trustzone(&self) -> Option<&stm32::TrustZone> {
+        self.trustzone.as_ref()
+    }
+
+    /// Try reading the target voltage via the connected voltage pin of the probe.
+    ///
+    /// This returns `Ok(None)` if the probe does not support measuring the target voltage.
+    /// Tools can use this to detect an unpowered target before attempting any operation on it.
+    pub fn get_target_voltage(&mut self) -> Result<Option<f32>, Error> {
+        Ok(self.probe_mut().get_target_voltage()?)
+    }
+
+    /// Enable or disable the power supply the probe provides to the target.
+    ///
+    /// See [`Probe::set_target_power`] for details. Note that the target state
+    /// is lost when the power is removed, so the session will most likely have to be
+    /// re-established afterwards.
+    pub fn set_target_power(&mut self, enabled: bool) -> Result<(), Error> {
+        Ok(self.probe_mut().set_target_power(enabled)?)
+    }
+
+    /// Drive the debug pins selected in `request` and read back the state of all pins.
+    ///
+    /// See [`Probe::set_pins`] for details.
+    pub fn set_pins(&mut self, request: PinRequest) -> Result<Option<Pins>, Error> {
+        Ok(self.probe_mut().set_pins(request)?)
+    }
+
+    /// Assert the nRESET line for the given duration and release it afterwards.
+    ///
+    /// This resets the target without going through the reset sequence of the core,
+    /// so the cores have to be re-initialized afterwards.
+    pub fn pulse_reset(&mut self, duration: Duration) -> Result<(), Error> {
+        Ok(probe::pulse_reset(self.probe_mut(), duration)?)
+    }
+
+    fn probe_mut(&mut self) -> &mut dyn DebugProbe {
+        match &mut self.interface {
+            ArchitectureInterface::Arm(interface) => interface.probe_mut(),
+            ArchitectureInterface::Riscv(interface) => interface.probe_mut(),
+            ArchitectureInterface::Mips(interface) => interface.probe_mut(),
+        }
+    }
+
     /// Get the Arm probe interface.
     pub fn get_arm_interface(&mut self) -> Result<&mut Box<dyn ArmProbeInterface>, Error> {
         let interface = match &mut self.interface {
@@ -452,6 +758,92 @@ impl Session {
         &self.target
     }
 
+    /// Replace the debug sequence of the target for the rest of this session.
+    ///
+    /// The sequence is used for all following operations, like resetting a core or erasing
+    /// the flash. The sequences which are run while attaching, e.g. `DebugPortStart`, have
+    /// already been executed at this point; to replace them, set [`Target::debug_sequence`]
+    /// before attaching, or use [`register_debug_sequence`](crate::config::register_debug_sequence).
+    pub fn set_debug_sequence(&mut self, sequence: DebugSequence) -> Result<(), Error> {
+        match (&self.target.debug_sequence, &sequence) {
+            (DebugSequence::Arm(_), DebugSequence::Arm(_))
+            | (DebugSequence::Riscv(_), DebugSequence::Riscv(_))
+            | (DebugSequence::Mips, DebugSequence::Mips) => {
+                self.target.debug_sequence = sequence;
+                Ok(())
+            }
+            (_, DebugSequence::Arm(_)) => Err(Error::ArchitectureRequired(&["ARMv7", "ARMv8"])),
+            (_, DebugSequence::Riscv(_)) => Err(Error::ArchitectureRequired(&["Riscv"])),
+            (_, DebugSequence::Mips) => Err(Error::ArchitectureRequired(&["Mips"])),
+        }
+    }
+
+    /// Read the variant of the connected chip from its ID registers.
+    ///
+    /// Returns `Ok(None)` if the ID registers of the target are not known.
+    pub fn variant_id(&mut self) -> Result<Option<VariantId>, Error> {
+        let vendor = match Vendor::from_chip_name(&self.target.name) {
+            Some(vendor) => vendor,
+            None => return Ok(None),
+        };
+        let (_, memory_ap) = match self.arm_sequence() {
+            Some(sequence) => sequence,
+            None => return Ok(None),
+        };
+
+        let mut memory = self.get_arm_interface()?.memory_interface(memory_ap)?;
+        crate::config::read_variant_id(&mut memory, vendor)
+    }
+
+    /// The permissions this session was opened with.
+    pub fn permissions(&self) -> &Permissions {
+        &self.permissions
+    }
+
+    /// Erase all nonvolatile memory with the vendor specific procedure of the debug sequence.
+    ///
+    /// Returns `Ok(false)` if the target does not have such a procedure.
+    pub(crate) fn sequence_erase_all(&mut self) -> Result<bool, Error> {
+        let (sequence, default_memory_ap) = match self.arm_sequence() {
+            Some(sequence) => sequence,
+            None => return Ok(false),
+        };
+
+        let interface = self.get_arm_interface()?;
+        sequence.debug_erase_all(interface, default_memory_ap)
+    }
+
+    /// Execute the part of the debug sequence of the target which prepares flashing.
+    pub(crate) fn sequence_flash_start(&mut self) -> Result<(), Error> {
+        if let Some((sequence, default_memory_ap)) = self.arm_sequence() {
+            sequence.flash_start(self.get_arm_interface()?, default_memory_ap)?;
+        }
+
+        Ok(())
+    }
+
+    /// The ARM debug sequence of the target, and the memory AP of its first core.
+    fn arm_sequence(&self) -> Option<(Arc<dyn ArmDebugSequence>, MemoryAp)> {
+        let sequence = match &self.target.debug_sequence {
+            DebugSequence::Arm(sequence) => sequence.clone(),
+            DebugSequence::Riscv(_) | DebugSequence::Mips => return None,
+        };
+
+        let default_memory_ap = match &self.target.cores[0].core_access_options {
+            probe_rs_target::CoreAccessOptions::Arm(options) => MemoryAp::new(ApAddress {
+                dp: match options.psel {
+                    0 => DpAddress::Default,
+                    x => DpAddress::Multidrop(x),
+                },
+                ap: options.ap,
+            }),
+            probe_rs_target::CoreAccessOptions::Riscv(_)
+            | probe_rs_target::CoreAccessOptions::Mips(_) => return None,
+        };
+
+        Some((sequence, default_memory_ap))
+    }
+
     /// Configure the target and probe for serial wire view (SWV) tracing.
     pub fn setup_swv(&mut self, core_index: usize, config: &SwoConfig) -> Result<(), Error> {
         // Configure SWO on the probe
@@ -469,12 +861,24 @@ impl Session {
         // Configure SWV on the target
         let components = self.get_arm_components()?;
         let interface = self.get_arm_interface()?;
-        crate::architecture::arm::component::setup_swv(interface, &components, config)
+        crate::architecture::arm::component::setup_swv(interface, &components, config)?;
+
+        if let Some((sequence, default_memory_ap)) = self.arm_sequence() {
+            sequence.trace_start(self.get_arm_interface()?, default_memory_ap)?;
+        }
+
+        Ok(())
     }
 
     /// Configure the target to stop emitting SWV trace data.
     pub fn disable_swv(&mut self, core_index: usize) -> Result<(), Error> {
-        crate::architecture::arm::component::disable_swv(&mut self.core(core_index)?)
+        crate::architecture::arm::component::disable_swv(&mut self.core(core_index)?)?;
+
+        if let Some((sequence, default_memory_ap)) = self.arm_sequence() {
+            sequence.trace_stop(self.get_arm_interface()?, default_memory_ap)?;
+        }
+
+        Ok(())
     }
 
     /// Begin tracing a memory address over SWV.
@@ -496,6 +900,56 @@ impl Session {
         crate::architecture::arm::component::remove_swv_data_trace(interface, &components, unit)
     }
 
+    /// Re-read the description of the target from a target description file, and apply it to this session.
+    ///
+    /// This is meant for bringing up a new chip: the memory map and the flash algorithms of the target
+    /// can be changed without restarting the session. The file is added to the registry like with
+    /// [`add_target_from_yaml`](crate::config::add_target_from_yaml), and has to contain the chip of the
+    /// session with the same cores. The debug sequence of the session is not changed.
+    pub fn reload_target(&mut self, path_to_yaml: &Path) -> Result<(), Error> {
+        crate::config::add_target_from_yaml(path_to_yaml)?;
+        let target = crate::config::get_target_by_name(&self.target.name)?;
+
+        let same_cores = target.cores.len() == self.target.cores.len()
+            && target
+                .cores
+                .iter()
+                .zip(&self.target.cores)
+                .all(|(new, old)| new.name == old.name && new.core_type == old.core_type);
+        if !same_cores {
+            return Err(Error::Other(anyhow!(
+                "The cores of {} have changed, the session has to be restarted to use them.",
+                target.name
+            )));
+        }
+
+        log::info!("Reloaded the target description of {}", target.name);
+
+        self.target.memory_map = target.memory_map;
+        self.target.flash_algorithms = target.flash_algorithms;
+        self.target.source = target.source;
+
+        let memory_regions = crate::config::memory_regions(&self.target);
+        for ((_, state), core) in self.cores.iter_mut().zip(&self.target.cores) {
+            state.set_memory_regions(
+                memory_regions
+                    .iter()
+                    .filter(|region| region.is_accessible_by(&core.name))
+                    .cloned()
+                    .collect(),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// The memory regions of the target, including the sectors of its flash.
+    ///
+    /// Use [`Core::memory_regions`] for the regions which a single core can access.
+    pub fn memory_regions(&self) -> Vec<MemoryRegionInfo> {
+        crate::config::memory_regions(&self.target)
+    }
+
     /// Returns the memory map of the target.
     #[deprecated = "Use the Session::target function instead"]
     pub fn memory_map(&self) -> &[MemoryRegion] {
@@ -507,9 +961,46 @@ impl Session {
         match self.interface {
             ArchitectureInterface::Arm(_) => Architecture::Arm,
             ArchitectureInterface::Riscv(_) => Architecture::Riscv,
+            ArchitectureInterface::Mips(_) => Architecture::Mips,
         }
     }
 
+    /// Release the power-up requests of the debug domains, the last step of closing a session
+    /// opened with [`AttachOptions::power_down_on_detach`].
+    fn release_debug_power(&mut self) -> Result<(), Error> {
+        match self.target.debug_sequence.clone() {
+            DebugSequence::Arm(sequence) => {
+                let mut dps = self
+                    .target
+                    .cores
+                    .iter()
+                    .filter_map(|core| match &core.core_access_options {
+                        probe_rs_target::CoreAccessOptions::Arm(options) => Some(options.psel),
+                        probe_rs_target::CoreAccessOptions::Riscv(_)
+                        | probe_rs_target::CoreAccessOptions::Mips(_) => None,
+                    })
+                    .collect::<Vec<_>>();
+                dps.sort_unstable();
+                dps.dedup();
+
+                let interface = self.get_arm_interface()?;
+                for psel in dps {
+                    let dp = match psel {
+                        0 => DpAddress::Default,
+                        x => DpAddress::Multidrop(x),
+                    };
+                    sequence.debug_port_stop(interface, dp)?;
+                }
+            }
+            DebugSequence::Riscv(_) => {
+                self.get_riscv_interface()?.disable_debug_module()?;
+            }
+            DebugSequence::Mips => {}
+        }
+
+        Ok(())
+    }
+
     /// Clears all hardware breakpoints on all cores
     pub fn clear_all_hw_breakpoints(&mut self) -> Result<(), Error> {
         { 0..self.cores.len() }.try_for_each(|n| {
@@ -517,6 +1008,146 @@ impl Session {
                 .and_then(|mut core| core.clear_all_hw_breakpoints())
         })
     }
+
+    /// Halts all cores of the target, visiting them in the given `order`.
+    ///
+    /// If all cores are ARMv8-A cores with a CTI, the halt request of the first core is
+    /// broadcast to the other cores through the cross trigger matrix, so that all cores
+    /// halt at the same time. Otherwise, the cores are halted one after the other.
+    ///
+    /// Returns the number and the [`CoreInformation`] of each core, in the order in which they were visited.
+    pub fn halt_all(
+        &mut self,
+        order: &CoreOrder,
+        timeout: Duration,
+    ) -> Result<Vec<(usize, CoreInformation)>, Error> {
+        let cores = order.core_numbers(self.cores.len())?;
+
+        let synchronized = self.open_cti_gates(&cores, CTI_HALT_CHANNEL)?;
+
+        let result: Result<Vec<_>, Error> = cores
+            .iter()
+            .map(|&n| Ok((n, self.core(n)?.halt(timeout)?)))
+            .collect();
+
+        if synchronized {
+            self.close_cti_gates(&cores);
+        }
+
+        result
+    }
+
+    /// Resumes all cores of the target, visiting them in the given `order`.
+    ///
+    /// If all cores are ARMv8-A cores with a CTI, the restart request of the first core is
+    /// broadcast to the other cores through the cross trigger matrix, so that all cores
+    /// resume at the same time. Otherwise, the cores are resumed one after the other.
+    pub fn resume_all(&mut self, order: &CoreOrder) -> Result<(), Error> {
+        let cores = order.core_numbers(self.cores.len())?;
+
+        let mut synchronized = self.supports_cti_synchronization(&cores);
+
+        if synchronized {
+            // Every core has to be ready to restart before the restart event is broadcast,
+            // otherwise it would lose its modified registers.
+            for &n in &cores {
+                let (core, core_state) = &mut self.cores[n];
+                if let Some(mut core) =
+                    self.interface
+                        .attach_armv8a(core, core_state, &self.target)?
+                {
+                    if core.is_halted() {
+                        core.prepare_for_restart()?;
+                    }
+                }
+            }
+
+            synchronized = self.open_cti_gates(&cores, CTI_RESTART_CHANNEL)?;
+        }
+
+        let result = cores.iter().try_for_each(|&n| self.core(n)?.run());
+
+        if synchronized {
+            self.close_cti_gates(&cores);
+        }
+
+        result
+    }
+
+    /// Resets all cores of the target, visiting them in the given `order`.
+    ///
+    /// Note that on many targets, e.g. all Cortex-M based ones, resetting a core resets the whole
+    /// system, so the other cores are reset as well.
+    pub fn reset_all(&mut self, order: &CoreOrder) -> Result<(), Error> {
+        order
+            .core_numbers(self.cores.len())?
+            .into_iter()
+            .try_for_each(|n| self.core(n)?.reset())
+    }
+
+    /// Returns `true` if the given cores can be halted and resumed synchronously through their CTIs.
+    fn supports_cti_synchronization(&self, cores: &[usize]) -> bool {
+        let has_cti = |n: usize| {
+            let (core, core_state) = &self.cores[n];
+
+            let options = match self.target.cores.get(core_state.id()) {
+                Some(config) => &config.core_access_options,
+                None => return false,
+            };
+
+            match (core, options) {
+                (
+                    SpecificCoreState::Armv8a(_),
+                    probe_rs_target::CoreAccessOptions::Arm(options),
+                ) => options.debug_base.is_some() && options.cti_base.is_some(),
+                _ => false,
+            }
+        };
+
+        cores.len() > 1 && cores.iter().all(|&n| has_cti(n))
+    }
+
+    /// Opens the gate of the CTI channel on all given cores, so an event on the channel
+    /// reaches all of them through the cross trigger matrix.
+    ///
+    /// Returns `false`, without changing any gate, if not all cores support this.
+    fn open_cti_gates(&mut self, cores: &[usize], channel: usize) -> Result<bool, Error> {
+        if !self.supports_cti_synchronization(cores) {
+            return Ok(false);
+        }
+
+        for &n in cores {
+            let (core, core_state) = &mut self.cores[n];
+            if let Some(mut core) = self
+                .interface
+                .attach_armv8a(core, core_state, &self.target)?
+            {
+                core.open_cti_gate(channel)?;
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Closes all CTI channel gates on the given cores, which [`Session::open_cti_gates`] opened.
+    ///
+    /// Errors are only logged, as this is done on error paths as well.
+    fn close_cti_gates(&mut self, cores: &[usize]) {
+        for &n in cores {
+            let (core, core_state) = &mut self.cores[n];
+            let result = self
+                .interface
+                .attach_armv8a(core, core_state, &self.target)
+                .and_then(|core| match core {
+                    Some(mut core) => core.close_cti_gates(),
+                    None => Ok(()),
+                });
+
+            if let Err(err) = result {
+                log::warn!("Could not close the CTI gates of core {}: {:?}", n, err);
+            }
+        }
+    }
 }
 
 // This test ensures that [Session] is fully [Send] + [Sync].
@@ -532,17 +1163,33 @@ impl Drop for Session {
             log::warn!("Could not clear all hardware breakpoints: {:?}", err);
         }
 
+        if let Err(err) = { 0..self.cores.len() }.try_for_each(|i| {
+            self.core(i)
+                .and_then(|mut core| core.clear_all_hw_watchpoints())
+        }) {
+            log::warn!("Could not clear all hardware watchpoints: {:?}", err);
+        }
+
+        if let Err(err) = self.remove_software_breakpoints() {
+            log::warn!("Could not remove all software breakpoints: {:?}", err);
+        }
+
+        if let Err(err) = self.clear_all_flash_breakpoints() {
+            log::warn!("Could not remove all flash breakpoints: {:?}", err);
+        }
+
         if let Err(err) = { 0..self.cores.len() }
             .try_for_each(|i| self.core(i).and_then(|mut core| core.on_session_stop()))
         {
             log::warn!("Error during on_session_stop: {:?}", err);
         }
 
-        // Disable tracing for all Cortex-M cores.
+        // Disable tracing for all Cortex-M cores. This changes the DEMCR register, so it is
+        // left as is if the state of the target is to be preserved.
         if let Err(err) = { 0..self.cores.len() }.try_for_each(|i| {
             let is_cortex_m = self.core(i)?.core_type().is_cortex_m();
 
-            if is_cortex_m {
+            if is_cortex_m && !self.options.preserve_state {
                 self.disable_swv(i)
             } else {
                 Ok(())
@@ -563,6 +1210,12 @@ impl Drop for Session {
                 log::warn!("Failed to deconfigure device during shutdown");
             }
         }
+
+        if self.options.power_down_on_detach {
+            if let Err(err) = self.release_debug_power() {
+                log::warn!("Could not release the debug power domains: {:?}", err);
+            }
+        }
     }
 }
 
@@ -583,6 +1236,7 @@ fn get_target_from_selector(
         TargetSelector::Specified(target) => target,
         TargetSelector::Auto => {
             let mut found_chip = None;
+            let mut found_variant = None;
 
             // At this point we do not know what the target is, so we cannot use the chip specific reset sequence.
             // Thus, we try just using a normal reset for target detection if we want to do so under reset.
@@ -607,6 +1261,14 @@ fn get_target_from_selector(
                                 None
                             });
 
+                        if let Some(vendor) = found_arm_chip
+                            .as_ref()
+                            .and_then(|chip| Vendor::from_manufacturer(chip.manufacturer))
+                        {
+                            let memory_ap = MemoryAp::new(ApAddress { dp, ap: 0 });
+                            found_variant = read_variant_id(&mut *interface, memory_ap, vendor);
+                        }
+
                         found_chip = found_arm_chip.map(ChipInfo::from);
 
                         probe = interface.close();
@@ -641,7 +1303,16 @@ fn get_target_from_selector(
             // Now we can deassert reset in case we asserted it before. This is always okay.
             probe.target_reset_deassert()?;
 
-            if let Some(chip) = found_chip {
+            // The ID registers identify the chip more precisely than the ROM table, if they are known.
+            let target_by_variant = found_variant.and_then(|variant| {
+                crate::config::get_target_by_variant_id(&variant)
+                    .map_err(|e| log::debug!("Unable to select a target for {:?}: {}", variant, e))
+                    .ok()
+            });
+
+            if let Some(target) = target_by_variant {
+                target
+            } else if let Some(chip) = found_chip {
                 crate::config::get_target_by_chip_info(chip)?
             } else {
                 return Err(Error::ChipNotFound(RegistryError::ChipAutodetectFailed));
@@ -652,6 +1323,76 @@ fn get_target_from_selector(
     Ok((probe, target))
 }
 
+/// Read the ID registers of the chip, if the registers of the vendor are known.
+///
+/// Errors are only logged, as the registers are not required to use the chip.
+fn read_variant_id(
+    interface: &mut dyn ArmProbeInterface,
+    memory_ap: MemoryAp,
+    vendor: Vendor,
+) -> Option<VariantId> {
+    let result = interface
+        .memory_interface(memory_ap)
+        .and_then(|mut memory| crate::config::read_variant_id(&mut memory, vendor));
+
+    match result {
+        Ok(id) => id,
+        Err(e) => {
+            log::debug!("Failed to read the ID registers of the chip: {}", e);
+            None
+        }
+    }
+}
+
+/// Warn if the ID registers of the chip show that it is not the selected target,
+/// e.g. because it has a different flash size.
+fn check_variant(target: &Target, interface: &mut dyn ArmProbeInterface, memory_ap: MemoryAp) {
+    let vendor = match Vendor::from_chip_name(&target.name) {
+        Some(vendor) => vendor,
+        None => return,
+    };
+
+    if let Some(reason) =
+        read_variant_id(interface, memory_ap, vendor).and_then(|id| id.mismatch(target))
+    {
+        log::warn!(
+            "The connected chip does not match the selected target: {}. Flashing may fail.",
+            reason
+        );
+    }
+}
+
+/// Read the TrustZone configuration of STM32s, which is `None` if TrustZone is disabled.
+fn read_trustzone(
+    target: &Target,
+    interface: &mut dyn ArmProbeInterface,
+    memory_ap: MemoryAp,
+) -> Option<stm32::TrustZone> {
+    if !target.name.starts_with("STM32") {
+        return None;
+    }
+    let flash_size = crate::config::main_flash_size(&target.memory_map)?;
+
+    let result = interface
+        .memory_interface(memory_ap)
+        .and_then(|mut memory| stm32::read_trustzone(&mut memory, &target.name, flash_size));
+    let trustzone = match result {
+        Ok(Some(trustzone)) => trustzone,
+        Ok(None) => return None,
+        Err(e) => {
+            log::debug!("Failed to read the TrustZone configuration: {}", e);
+            return None;
+        }
+    };
+
+    log::info!("TrustZone is enabled");
+    for range in &trustzone.secure_flash {
+        log::info!("Secure flash: {:#010x}..{:#010x}", range.start, range.end);
+    }
+
+    Some(trustzone)
+}
+
 /// The `Permissions` struct represents what a [Session] is allowed to do with a target.
 /// Some operations can be irreversable, so need to be explicitly allowed by the user.
 ///
@@ -688,6 +1429,11 @@ impl Permissions {
         }
     }
 
+    /// Whether the session may erase all memory of the chip, see [`Permissions::allow_erase_all`].
+    pub fn is_erase_all_allowed(&self) -> bool {
+        self.erase_all
+    }
+
     pub(crate) fn erase_all(&self) -> Result<(), crate::Error> {
         if self.erase_all {
             Ok(())
@@ -696,3 +1442,229 @@ impl Permissions {
         }
     }
 }
+
+/// Options which control how a session attaches to the target, for [`Probe::attach_with_options`].
+///
+/// # Example
+///
+/// ```
+/// use probe_rs::AttachOptions;
+///
+/// let options = AttachOptions::new().under_reset().keep_watchdogs_running();
+/// ```
+#[non_exhaustive]
+#[derive(Debug, Clone)]
+pub struct AttachOptions {
+    method: AttachMethod,
+    suspend_watchdogs: bool,
+    connect_under_reset: Option<ConnectUnderReset>,
+    preserve_state: bool,
+    power_down_on_detach: bool,
+    riscv_authentication: Option<RiscvAuthentication>,
+    rtt_control_block: Option<u64>,
+    rtt_scan_ranges: Option<Vec<Range<u64>>>,
+}
+
+impl Default for AttachOptions {
+    fn default() -> Self {
+        Self {
+            method: AttachMethod::Normal,
+            suspend_watchdogs: true,
+            connect_under_reset: None,
+            preserve_state: false,
+            power_down_on_detach: false,
+            riscv_authentication: None,
+            rtt_control_block: None,
+            rtt_scan_ranges: None,
+        }
+    }
+}
+
+impl AttachOptions {
+    /// Constructs a new options object with the default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Attach to the target while it is held in reset, see [`Probe::attach_under_reset`].
+    #[must_use]
+    pub fn under_reset(self) -> Self {
+        Self {
+            method: AttachMethod::UnderReset,
+            preserve_state: false,
+            ..self
+        }
+    }
+
+    /// Attach to the target while it is held in reset, using `steps` instead of the
+    /// [`ConnectUnderReset`] configuration of the target description.
+    ///
+    /// This is useful for boards where nRESET has to be held for longer, e.g. because of a
+    /// large reset capacitor, or when the core should keep running after the reset is released.
+    #[must_use]
+    pub fn under_reset_with(self, steps: ConnectUnderReset) -> Self {
+        Self {
+            method: AttachMethod::UnderReset,
+            connect_under_reset: Some(steps),
+            preserve_state: false,
+            ..self
+        }
+    }
+
+    /// Don't suspend the hardware watchdogs of the target while its cores are halted.
+    ///
+    /// By default, the debug sequence of the target configures the watchdogs to stop while the
+    /// cores are halted where the chip supports it, so the target is not reset while it is being
+    /// inspected. This leaves the watchdog configuration unchanged, e.g. to test the watchdog itself.
+    #[must_use]
+    pub fn keep_watchdogs_running(self) -> Self {
+        Self {
+            suspend_watchdogs: false,
+            ..self
+        }
+    }
+
+    /// Release the power-up requests of the debug domains when the session is closed.
+    ///
+    /// On ARM targets, `CDBGPWRUPREQ` and `CSYSPWRUPREQ` are cleared with the `DebugPortStop`
+    /// sequence; on RISC-V targets, the debug module is deactivated by clearing `dmactive`.
+    /// Without this, many targets keep their debug domains powered after the session is closed,
+    /// and can't enter their lowest power modes until they are power cycled.
+    #[must_use]
+    pub fn power_down_on_detach(self) -> Self {
+        Self {
+            power_down_on_detach: true,
+            ..self
+        }
+    }
+
+    /// Authenticate with the debug module of a RISC-V target which requires it, using the
+    /// handshake of `authentication`.
+    ///
+    /// Attaching to such a target fails with [`RiscvError::NotAuthenticated`] otherwise.
+    #[must_use]
+    pub fn authenticate_riscv(self, authentication: RiscvAuthentication) -> Self {
+        Self {
+            riscv_authentication: Some(authentication),
+            ..self
+        }
+    }
+
+    /// The address of the RTT control block, if it is known, e.g. from the symbol table of the
+    /// program.
+    ///
+    /// RTT is then attached to without scanning the memory for the control block.
+    #[must_use]
+    pub fn rtt_control_block(self, address: u64) -> Self {
+        Self {
+            rtt_control_block: Some(address),
+            ..self
+        }
+    }
+
+    /// Search the RTT control block only in `ranges`, instead of the ranges of the target
+    /// description or all RAM.
+    #[must_use]
+    pub fn rtt_scan_ranges(self, ranges: Vec<Range<u64>>) -> Self {
+        Self {
+            rtt_scan_ranges: Some(ranges),
+            ..self
+        }
+    }
+
+    /// Attach without resetting or halting the target, and without changing its watchdog,
+    /// vector catch and breakpoint configuration, see [`Session::attach_preserving_state`].
+    ///
+    /// This overrides [`AttachOptions::under_reset`].
+    #[must_use]
+    pub fn preserve_state(self) -> Self {
+        Self {
+            method: AttachMethod::Normal,
+            suspend_watchdogs: false,
+            preserve_state: true,
+            ..self
+        }
+    }
+}
+
+/// The order in which session-wide operations like [`Session::halt_all`] visit the cores.
+///
+/// # Example
+///
+/// ```
+/// use probe_rs::CoreOrder;
+///
+/// // Halt the secondary core before the primary core.
+/// let order = CoreOrder::Custom(vec![1, 0]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CoreOrder {
+    /// Visit the cores by ascending core number.
+    Ascending,
+    /// Visit the cores by descending core number.
+    Descending,
+    /// Visit the listed cores in the given order, followed by all other cores by ascending core number.
+    Custom(Vec<usize>),
+}
+
+impl Default for CoreOrder {
+    fn default() -> Self {
+        CoreOrder::Ascending
+    }
+}
+
+impl CoreOrder {
+    /// Returns the numbers of all `num_cores` cores in this order.
+    ///
+    /// Fails with [`Error::CoreNotFound`] if a custom order lists a core that does not exist.
+    fn core_numbers(&self, num_cores: usize) -> Result<Vec<usize>, Error> {
+        match self {
+            CoreOrder::Ascending => Ok((0..num_cores).collect()),
+            CoreOrder::Descending => Ok((0..num_cores).rev().collect()),
+            CoreOrder::Custom(listed) => {
+                let mut cores = Vec::with_capacity(num_cores);
+
+                for &n in listed {
+                    if n >= num_cores {
+                        return Err(Error::CoreNotFound(n));
+                    }
+                    if !cores.contains(&n) {
+                        cores.push(n);
+                    }
+                }
+
+                for n in 0..num_cores {
+                    if !cores.contains(&n) {
+                        cores.push(n);
+                    }
+                }
+
+                Ok(cores)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::CoreOrder;
+    use crate::Error;
+
+    #[test]
+    fn core_order_visits_every_core_once() {
+        assert_eq!(CoreOrder::Ascending.core_numbers(3).unwrap(), [0, 1, 2]);
+        assert_eq!(CoreOrder::Descending.core_numbers(3).unwrap(), [2, 1, 0]);
+        assert_eq!(
+            CoreOrder::Custom(vec![2, 0, 2]).core_numbers(4).unwrap(),
+            [2, 0, 1, 3]
+        );
+    }
+
+    #[test]
+    fn core_order_rejects_unknown_cores() {
+        assert!(matches!(
+            CoreOrder::Custom(vec![0, 2]).core_numbers(2),
+            Err(Error::CoreNotFound(2))
+        ));
+    }
+}
@@ -0,0 +1,331 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+
+use crate::config::MemoryRegionKind;
+use crate::debug::DebugInfo;
+use crate::{Core, CoreInformation, Error, InstructionSet, MemoryInterface, RegisterId, Session};
+
+/// The `dcsr` CSR of RISC-V cores.
+const RISCV_DCSR: u16 = 0x7b0;
+
+/// The `ebreakm` bit of the `dcsr`, which makes `ebreak` instructions in machine mode enter debug mode.
+const RISCV_DCSR_EBREAKM: u32 = 1 << 15;
+
+/// The location of a breakpoint in the program, which is resolved to an address with the debug information.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BreakpointLocation {
+    /// The first statement of a function, after its prologue, e.g. `firmware::handlers::on_button`.
+    Symbol(String),
+    /// The first statement on a line of a source file.
+    SourceLine {
+        /// The path of the source file, as it is stored in the debug information.
+        path: PathBuf,
+        /// The line in the source file.
+        line: u64,
+        /// The column in the line, to select one of several statements on the line.
+        column: Option<u64>,
+    },
+}
+
+/// How a breakpoint is implemented.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakpointKind {
+    /// A hardware breakpoint unit of the core, for code in flash or other non-volatile memory.
+    Hardware,
+    /// A breakpoint instruction, which replaces the instruction at the address, for code in RAM.
+    Software,
+}
+
+/// A breakpoint set with [`Session::set_breakpoint_at_symbol`] or [`Session::set_breakpoint_at_line`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Breakpoint {
+    /// Where the breakpoint is set in the program.
+    pub location: BreakpointLocation,
+    /// The number of the core the breakpoint is set for.
+    pub core: usize,
+    /// The address the location was resolved to.
+    pub address: u64,
+    /// How the breakpoint is implemented.
+    pub kind: BreakpointKind,
+    /// The instruction which was replaced by the breakpoint instruction, for software breakpoints.
+    original_instruction: Vec<u8>,
+}
+
+impl Session {
+    /// Sets a breakpoint at the first statement of a function, after its prologue.
+    ///
+    /// The function is looked up by its path in `debug_info`, see [`DebugInfo::function_address`].
+    /// For code in RAM a software breakpoint is used, so the hardware breakpoint units are left for
+    /// code in flash. If a breakpoint is already set at the resolved address, it is returned.
+    pub fn set_breakpoint_at_symbol(
+        &mut self,
+        core_index: usize,
+        debug_info: &DebugInfo,
+        symbol: &str,
+    ) -> Result<Breakpoint, Error> {
+        self.set_breakpoint(
+            core_index,
+            debug_info,
+            BreakpointLocation::Symbol(symbol.to_string()),
+        )
+    }
+
+    /// Sets a breakpoint at the first statement on a line of a source file.
+    ///
+    /// See [`Session::set_breakpoint_at_symbol`] for how the kind of the breakpoint is chosen.
+    pub fn set_breakpoint_at_line(
+        &mut self,
+        core_index: usize,
+        debug_info: &DebugInfo,
+        path: &Path,
+        line: u64,
+        column: Option<u64>,
+    ) -> Result<Breakpoint, Error> {
+        self.set_breakpoint(
+            core_index,
+            debug_info,
+            BreakpointLocation::SourceLine {
+                path: path.to_path_buf(),
+                line,
+                column,
+            },
+        )
+    }
+
+    /// The breakpoints which are set with [`Session::set_breakpoint_at_symbol`] or
+    /// [`Session::set_breakpoint_at_line`].
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    /// Clears a breakpoint, and restores the original instruction of a software breakpoint.
+    pub fn clear_breakpoint(&mut self, breakpoint: &Breakpoint) -> Result<(), Error> {
+        let index = self
+            .breakpoints
+            .iter()
+            .position(|set| set.core == breakpoint.core && set.address == breakpoint.address)
+            .ok_or_else(|| {
+                Error::Other(anyhow!(
+                    "No breakpoint is set at {:#010x} on core {}.",
+                    breakpoint.address,
+                    breakpoint.core
+                ))
+            })?;
+
+        let breakpoint = self.breakpoints.remove(index);
+        remove_breakpoint(&mut self.core(breakpoint.core)?, &breakpoint)
+    }
+
+    /// Resolves the locations of all breakpoints again, and moves the breakpoints to their new addresses.
+    ///
+    /// Call this after a new program was flashed, with the debug information of the new program.
+    /// The software breakpoints are assumed to be overwritten by the new program, so their original
+    /// instructions are not restored. Breakpoints whose location doesn't exist anymore are cleared,
+    /// and an error is returned after all other breakpoints were moved.
+    pub fn reresolve_breakpoints(&mut self, debug_info: &DebugInfo) -> Result<(), Error> {
+        let mut unresolved = Vec::new();
+
+        for breakpoint in std::mem::take(&mut self.breakpoints) {
+            if breakpoint.kind == BreakpointKind::Hardware {
+                if let Err(error) = self
+                    .core(breakpoint.core)
+                    .and_then(|mut core| core.clear_hw_breakpoint(breakpoint.address))
+                {
+                    log::warn!("Failed to clear a hardware breakpoint: {}", error);
+                }
+            }
+
+            if let Err(error) =
+                self.set_breakpoint(breakpoint.core, debug_info, breakpoint.location)
+            {
+                log::warn!("Failed to set a breakpoint again: {}", error);
+                unresolved.push(error.to_string());
+            }
+        }
+
+        if unresolved.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Other(anyhow!(
+                "Failed to set {} breakpoints again: {}",
+                unresolved.len(),
+                unresolved.join(", ")
+            )))
+        }
+    }
+
+    /// Steps the core over a breakpoint, which a halted core would otherwise hit again when it is resumed.
+    ///
+    /// If there is a software breakpoint at the program counter, its original instruction is executed.
+    /// Otherwise this is the same as [`Core::step`].
+    pub fn step_over_breakpoint(&mut self, core_index: usize) -> Result<CoreInformation, Error> {
+        let pc: u64 = {
+            let mut core = self.core(core_index)?;
+            let program_counter = core.registers().program_counter();
+            core.read_core_reg(program_counter)?
+        };
+
+        let breakpoint = match self.breakpoints.iter().find(|breakpoint| {
+            breakpoint.core == core_index
+                && breakpoint.address == pc
+                && breakpoint.kind == BreakpointKind::Software
+        }) {
+            Some(breakpoint) => breakpoint.clone(),
+            None => return self.core(core_index)?.step(),
+        };
+
+        let mut core = self.core(core_index)?;
+        core.write_8(breakpoint.address, &breakpoint.original_instruction)?;
+        let information = core.step();
+        insert_software_breakpoint(&mut core, breakpoint.address)?;
+
+        information
+    }
+
+    /// Restores the original instructions of all software breakpoints, when the session ends.
+    pub(crate) fn remove_software_breakpoints(&mut self) -> Result<(), Error> {
+        for breakpoint in std::mem::take(&mut self.breakpoints) {
+            if breakpoint.kind == BreakpointKind::Software {
+                remove_breakpoint(&mut self.core(breakpoint.core)?, &breakpoint)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn set_breakpoint(
+        &mut self,
+        core_index: usize,
+        debug_info: &DebugInfo,
+        location: BreakpointLocation,
+    ) -> Result<Breakpoint, Error> {
+        let address = resolve_location(debug_info, &location)?;
+
+        if let Some(breakpoint) = self
+            .breakpoints
+            .iter()
+            .find(|breakpoint| breakpoint.core == core_index && breakpoint.address == address)
+        {
+            return Ok(breakpoint.clone());
+        }
+
+        let breakpoint = {
+            let mut core = self.core(core_index)?;
+
+            let in_ram = matches!(
+                core.memory_region(address).map(|region| &region.kind),
+                Some(MemoryRegionKind::Ram { .. })
+            );
+
+            if in_ram {
+                Breakpoint {
+                    location,
+                    core: core_index,
+                    address,
+                    kind: BreakpointKind::Software,
+                    original_instruction: insert_software_breakpoint(&mut core, address)?,
+                }
+            } else {
+                core.set_hw_breakpoint(address)?;
+
+                Breakpoint {
+                    location,
+                    core: core_index,
+                    address,
+                    kind: BreakpointKind::Hardware,
+                    original_instruction: Vec::new(),
+                }
+            }
+        };
+
+        log::debug!(
+            "Set a {:?} breakpoint at {:#010x} for {:?}",
+            breakpoint.kind,
+            address,
+            breakpoint.location
+        );
+
+        self.breakpoints.push(breakpoint.clone());
+
+        Ok(breakpoint)
+    }
+}
+
+/// Resolves the location of a breakpoint to the address of its first statement.
+fn resolve_location(debug_info: &DebugInfo, location: &BreakpointLocation) -> Result<u64, Error> {
+    let address = match location {
+        BreakpointLocation::Symbol(symbol) => {
+            let function_address = debug_info.function_address(symbol).ok_or_else(|| {
+                Error::Other(anyhow!("No function named `{}` was found.", symbol))
+            })?;
+
+            // Halt after the prologue, so the arguments of the function can be inspected.
+            debug_info
+                .get_halt_locations(function_address, None)
+                .ok()
+                .and_then(|locations| locations.first_halt_address)
+                .unwrap_or(function_address)
+        }
+        BreakpointLocation::SourceLine { path, line, column } => debug_info
+            .get_breakpoint_location(path, *line, *column)
+            .map_err(|error| Error::Other(error.into()))?
+            .first_halt_address
+            .ok_or_else(|| {
+                Error::Other(anyhow!(
+                    "No statement was found at {}:{}.",
+                    path.display(),
+                    line
+                ))
+            })?,
+    };
+
+    Ok(address)
+}
+
+/// Writes a breakpoint instruction to `address`, and returns the instruction it replaced.
+fn insert_software_breakpoint(core: &mut Core<'_>, address: u64) -> Result<Vec<u8>, Error> {
+    let instruction: Vec<u8> = match core.instruction_set()? {
+        // BKPT #0
+        InstructionSet::Thumb2 => 0xbe00u16.to_le_bytes().to_vec(),
+        InstructionSet::A32 => 0xe120_0070u32.to_le_bytes().to_vec(),
+        InstructionSet::RV32 | InstructionSet::RV32C => {
+            let dcsr: u32 = core.read_core_reg(RegisterId(RISCV_DCSR))?;
+            if dcsr & RISCV_DCSR_EBREAKM == 0 {
+                core.write_core_reg(RegisterId(RISCV_DCSR), dcsr | RISCV_DCSR_EBREAKM)?;
+            }
+
+            // The breakpoint has to have the same length as the instruction it replaces.
+            let mut low_half = [0u8; 2];
+            core.read_8(address, &mut low_half)?;
+            if low_half[0] & 0b11 == 0b11 {
+                // EBREAK
+                0x0010_0073u32.to_le_bytes().to_vec()
+            } else {
+                // C.EBREAK
+                0x9002u16.to_le_bytes().to_vec()
+            }
+        }
+        instruction_set => {
+            return Err(Error::Other(anyhow!(
+                "Software breakpoints are not supported for the {:?} instruction set.",
+                instruction_set
+            )))
+        }
+    };
+
+    let mut original_instruction = vec![0u8; instruction.len()];
+    core.read_8(address, &mut original_instruction)?;
+    core.write_8(address, &instruction)?;
+
+    Ok(original_instruction)
+}
+
+fn remove_breakpoint(core: &mut Core<'_>, breakpoint: &Breakpoint) -> Result<(), Error> {
+    match breakpoint.kind {
+        BreakpointKind::Hardware => core.clear_hw_breakpoint(breakpoint.address),
+        BreakpointKind::Software => {
+            core.write_8(breakpoint.address, &breakpoint.original_instruction)
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use std::ops::Range;
+use std::time::Duration;
+
+use crate::config::MemoryRegion;
+use crate::{Core, CoreType, Error, MemoryInterface, Session};
+
+/// A Thumb routine which calculates the CRC-32/MPEG-2 of a memory range, for ARMv6-M and later.
+///
+/// It is called as `u32 crc(const u8 *address, u32 length, u32 crc)`, and processes one bit at a
+/// time, so it is small and needs no table.
+const THUMB_CRC32: [u16; 22] = [
+    0xb430, //       push  {r4, r5}
+    0x4b09, //       ldr   r3, =0x04c11db7
+    0x2900, // byte: cmp   r1, #0
+    0xd00b, //       beq   done
+    0x7804, //       ldrb  r4, [r0]
+    0x3001, //       adds  r0, #1
+    0x0624, //       lsls  r4, r4, #24
+    0x4062, //       eors  r2, r4
+    0x2508, //       movs  r5, #8
+    0x0052, // bit:  lsls  r2, r2, #1
+    0xd300, //       bcc   next
+    0x405a, //       eors  r2, r3
+    0x3d01, // next: subs  r5, #1
+    0xd1fa, //       bne   bit
+    0x3901, //       subs  r1, #1
+    0xe7f1, //       b     byte
+    0x0010, // done: movs  r0, r2
+    0xbc30, //       pop   {r4, r5}
+    0x4770, //       bx    lr
+    0xbf00, //       nop
+    0x1db7, //       .word 0x04c11db7
+    0x04c1,
+];
+
+/// The polynomial of CRC-32/MPEG-2, which is not reflected.
+const POLYNOMIAL: u32 = 0x04c1_1db7;
+
+/// The size of the chunks in which memory is read to calculate the CRC on the host.
+const CHUNK_SIZE: usize = 0x1000;
+
+impl Session {
+    /// Calculates the CRC-32/MPEG-2 of the memory in `range`, with the initial value `crc`.
+    ///
+    /// This is the CRC gdb uses to verify memory with `compare-sections`, which passes
+    /// `0xffff_ffff` as initial value. On ARM M-profile cores, the CRC is calculated by a routine
+    /// running on the core, in the RAM which the core can access, so the memory doesn't have to
+    /// be read through the probe. The RAM used by the routine and the registers of the core are
+    /// restored afterwards. On other cores, or if the routine fails, the memory is read and the
+    /// CRC is calculated on the host.
+    ///
+    /// The core has to be halted.
+    pub fn memory_crc32(
+        &mut self,
+        core_index: usize,
+        range: Range<u64>,
+        crc: u32,
+    ) -> Result<u32, Error> {
+        if let Some(scratch) = self.crc_scratch_address(core_index, &range) {
+            let mut core = self.core(core_index)?;
+
+            match crc_on_target(&mut core, scratch, range.clone(), crc) {
+                Ok(crc) => return Ok(crc),
+                Err(error) => log::warn!(
+                    "Calculating the CRC on the target failed, reading the memory instead: {}",
+                    error
+                ),
+            }
+        }
+
+        let mut core = self.core(core_index)?;
+        crc_on_host(&mut core, range, crc)
+    }
+
+    /// Returns the address where the CRC routine can be placed, if the core can run it.
+    fn crc_scratch_address(&self, core_index: usize, range: &Range<u64>) -> Option<u64> {
+        let core = &self.target().cores[core_index];
+        if !matches!(
+            core.core_type,
+            CoreType::Armv6m | CoreType::Armv7m | CoreType::Armv7em | CoreType::Armv8m
+        ) {
+            return None;
+        }
+
+        let size = (THUMB_CRC32.len() * 2) as u64;
+
+        self.target()
+            .memory_map
+            .iter()
+            .filter_map(|region| match region {
+                MemoryRegion::Ram(region) if region.cores.contains(&core.name) => Some(region),
+                _ => None,
+            })
+            .map(|region| (region.range.start + 3) & !3..region.range.end)
+            .find(|ram| {
+                let code = ram.start..ram.start + size;
+                code.end <= ram.end && (code.end <= range.start || range.end <= code.start)
+            })
+            .map(|ram| ram.start)
+    }
+}
+
+/// Runs the CRC routine at `scratch`, and restores the memory it was placed in.
+fn crc_on_target(core: &mut Core, scratch: u64, range: Range<u64>, crc: u32) -> Result<u32, Error> {
+    let mut code = Vec::with_capacity(THUMB_CRC32.len() * 2);
+    for halfword in THUMB_CRC32 {
+        code.extend_from_slice(&halfword.to_le_bytes());
+    }
+
+    let mut saved = vec![0u8; code.len()];
+    core.read_8(scratch, &mut saved)?;
+    core.write_8(scratch, &code)?;
+
+    let length = range.end - range.start;
+    // The routine needs about 50 cycles per byte, so this is generous even for slow clocks.
+    let timeout = Duration::from_millis(1000 + length / 256);
+
+    let result = core.call_function(
+        scratch | 1,
+        &[range.start as u32, length as u32, crc],
+        timeout,
+    );
+
+    core.write_8(scratch, &saved)?;
+
+    result
+}
+
+fn crc_on_host(core: &mut Core, range: Range<u64>, mut crc: u32) -> Result<u32, Error> {
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    let mut address = range.start;
+
+    while address < range.end {
+        let len = ((range.end - address) as usize).min(CHUNK_SIZE);
+        core.read_8(address, &mut buffer[..len])?;
+
+        crc = crc32_update(crc, &buffer[..len]);
+        address += len as u64;
+    }
+
+    Ok(crc)
+}
+
+/// Updates the CRC-32/MPEG-2 `crc` with `data`, in the same way as the routine on the target.
+fn crc32_update(mut crc: u32, data: &[u8]) -> u32 {
+    for &byte in data {
+        crc ^= (byte as u32) << 24;
+        for _ in 0..8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ POLYNOMIAL
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod test {
+    use super::crc32_update;
+
+    #[test]
+    fn crc32_mpeg2_check_value() {
+        assert_eq!(crc32_update(0xffff_ffff, b"123456789"), 0x0376_e6e7);
+    }
+}
@@ -0,0 +1,206 @@
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+use anyhow::anyhow;
+
+use crate::{Core, CoreInformation, CoreStatus, Error, MemoryInterface, Session};
+
+/// Gives shared access to all cores of a [`Session`], see [`Session::multiplexer`].
+///
+/// [`Session::core`] borrows the session exclusively, so only one [`Core`] can exist at a time.
+/// The multiplexer instead hands out a [`CoreHandle`] for each core, and any number of them
+/// can be held at the same time. Every operation on a handle attaches to its core for the
+/// duration of that operation only, so operations on different cores are interleaved.
+///
+/// The multiplexer is [`Sync`], so the handles can also be used from different threads,
+/// e.g. with [`std::thread::scope`]. The probe is then locked for each operation.
+///
+/// # Example
+///
+/// ```no_run
+/// # use probe_rs::{Error, MemoryInterface, Permissions, Session};
+/// # use std::time::Duration;
+/// # fn main() -> Result<(), Error> {
+/// let mut session = Session::auto_attach("STM32H745ZITx", Permissions::default())?;
+/// let multiplexer = session.multiplexer();
+///
+/// let cm7 = multiplexer.core(0)?;
+/// let mut cm4 = multiplexer.core(1)?;
+///
+/// cm7.halt(Duration::from_millis(100))?;
+/// // The Cortex-M4 keeps running and can be inspected while the Cortex-M7 is halted.
+/// let counter = cm4.read_word_32(0x3000_0000)?;
+/// cm7.step()?;
+/// println!("Counter of the Cortex-M4: {}", counter);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct CoreMultiplexer<'session> {
+    session: Mutex<&'session mut Session>,
+    num_cores: usize,
+}
+
+impl<'session> CoreMultiplexer<'session> {
+    pub(crate) fn new(session: &'session mut Session) -> Self {
+        let num_cores = session.list_cores().len();
+
+        Self {
+            session: Mutex::new(session),
+            num_cores,
+        }
+    }
+
+    /// Returns the number of cores of the session.
+    pub fn num_cores(&self) -> usize {
+        self.num_cores
+    }
+
+    /// Returns a handle for the core with the given number, see [`Session::core`] for the numbering.
+    pub fn core(&self, n: usize) -> Result<CoreHandle<'_, 'session>, Error> {
+        if n >= self.num_cores {
+            return Err(Error::CoreNotFound(n));
+        }
+
+        Ok(CoreHandle {
+            multiplexer: self,
+            id: n,
+        })
+    }
+
+    /// Returns a handle for each core of the session.
+    pub fn cores(&self) -> Vec<CoreHandle<'_, 'session>> {
+        (0..self.num_cores)
+            .map(|id| CoreHandle {
+                multiplexer: self,
+                id,
+            })
+            .collect()
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, &'session mut Session>, Error> {
+        self.session
+            .lock()
+            .map_err(|_| anyhow!("A previous operation on another core panicked.").into())
+    }
+}
+
+/// A handle for a single core of a [`CoreMultiplexer`].
+///
+/// Memory accesses through the [`MemoryInterface`] implementation and the methods of the handle
+/// each attach to the core on their own. Use [`CoreHandle::with_core`] to perform several
+/// operations at once, or to pass the [`Core`] to functions which need one.
+#[derive(Debug, Clone, Copy)]
+pub struct CoreHandle<'multiplexer, 'session> {
+    multiplexer: &'multiplexer CoreMultiplexer<'session>,
+    id: usize,
+}
+
+impl<'multiplexer, 'session> CoreHandle<'multiplexer, 'session> {
+    /// Returns the number of the core.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Attaches to the core and runs `f` with it.
+    ///
+    /// The probe stays locked until `f` returns, so `f` must not use other handles of the
+    /// same multiplexer. Doing so blocks forever.
+    pub fn with_core<T>(
+        &self,
+        f: impl FnOnce(&mut Core<'_>) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let mut session = self.multiplexer.lock()?;
+        let mut core = session.core(self.id)?;
+
+        f(&mut core)
+    }
+
+    /// Halts the core, see [`Core::halt`].
+    pub fn halt(&self, timeout: Duration) -> Result<CoreInformation, Error> {
+        self.with_core(|core| core.halt(timeout))
+    }
+
+    /// Resumes the core, see [`Core::run`].
+    pub fn run(&self) -> Result<(), Error> {
+        self.with_core(|core| core.run())
+    }
+
+    /// Steps the core by a single instruction, see [`Core::step`].
+    pub fn step(&self) -> Result<CoreInformation, Error> {
+        self.with_core(|core| core.step())
+    }
+
+    /// Returns the current status of the core, see [`Core::status`].
+    pub fn status(&self) -> Result<CoreStatus, Error> {
+        self.with_core(|core| core.status())
+    }
+
+    /// Checks if the core is halted, see [`Core::core_halted`].
+    pub fn core_halted(&self) -> Result<bool, Error> {
+        self.with_core(|core| core.core_halted())
+    }
+}
+
+impl<'multiplexer, 'session> MemoryInterface for CoreHandle<'multiplexer, 'session> {
+    fn supports_native_64bit_access(&mut self) -> bool {
+        self.with_core(|core| Ok(core.supports_native_64bit_access()))
+            .unwrap_or(false)
+    }
+
+    fn read_word_64(&mut self, address: u64) -> Result<u64, Error> {
+        self.with_core(|core| core.read_word_64(address))
+    }
+
+    fn read_word_32(&mut self, address: u64) -> Result<u32, Error> {
+        self.with_core(|core| core.read_word_32(address))
+    }
+
+    fn read_word_8(&mut self, address: u64) -> Result<u8, Error> {
+        self.with_core(|core| core.read_word_8(address))
+    }
+
+    fn read_64(&mut self, address: u64, data: &mut [u64]) -> Result<(), Error> {
+        self.with_core(|core| core.read_64(address, data))
+    }
+
+    fn read_32(&mut self, address: u64, data: &mut [u32]) -> Result<(), Error> {
+        self.with_core(|core| core.read_32(address, data))
+    }
+
+    fn read_8(&mut self, address: u64, data: &mut [u8]) -> Result<(), Error> {
+        self.with_core(|core| core.read_8(address, data))
+    }
+
+    fn write_word_64(&mut self, addr: u64, data: u64) -> Result<(), Error> {
+        self.with_core(|core| core.write_word_64(addr, data))
+    }
+
+    fn write_word_32(&mut self, addr: u64, data: u32) -> Result<(), Error> {
+        self.with_core(|core| core.write_word_32(addr, data))
+    }
+
+    fn write_word_8(&mut self, addr: u64, data: u8) -> Result<(), Error> {
+        self.with_core(|core| core.write_word_8(addr, data))
+    }
+
+    fn write_64(&mut self, addr: u64, data: &[u64]) -> Result<(), Error> {
+        self.with_core(|core| core.write_64(addr, data))
+    }
+
+    fn write_32(&mut self, addr: u64, data: &[u32]) -> Result<(), Error> {
+        self.with_core(|core| core.write_32(addr, data))
+    }
+
+    fn write_8(&mut self, addr: u64, data: &[u8]) -> Result<(), Error> {
+        self.with_core(|core| core.write_8(addr, data))
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.with_core(|core| core.flush())
+    }
+}
+
+// The handles are meant to be shared between threads.
+static_assertions::assert_impl_all!(CoreMultiplexer<'static>: Send, Sync);
+static_assertions::assert_impl_all!(CoreHandle<'static, 'static>: Send, Sync);
@@ -0,0 +1,130 @@
+use std::ops::Range;
+use std::time::Duration;
+
+use anyhow::anyhow;
+
+use crate::{
+    Core, Error, MemoryInterface, RegisterDescription, RegisterId, RegisterValue, Session,
+};
+
+/// The state of a halted core, which was captured with [`Session::snapshot`] and can be restored
+/// with [`Session::restore`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    /// The number of the core the snapshot was taken of.
+    pub core: usize,
+    registers: Vec<(RegisterId, RegisterValue)>,
+    memory: Vec<(u64, Vec<u8>)>,
+}
+
+impl Snapshot {
+    /// The address ranges of the memory in the snapshot.
+    pub fn memory_ranges(&self) -> impl Iterator<Item = Range<u64>> + '_ {
+        self.memory
+            .iter()
+            .map(|(address, data)| *address..*address + data.len() as u64)
+    }
+}
+
+impl Session {
+    /// Takes a snapshot of the registers of a core and the memory in `ranges`, e.g. the RAM of
+    /// the program, which can be restored later with [`Session::restore`].
+    ///
+    /// The core is halted to take the snapshot, and stays halted afterwards. The floating point
+    /// registers and the state of peripherals are not part of the snapshot.
+    pub fn snapshot(
+        &mut self,
+        core_index: usize,
+        ranges: &[Range<u64>],
+    ) -> Result<Snapshot, Error> {
+        let mut core = self.core(core_index)?;
+        core.halt(Duration::from_millis(100))?;
+
+        let mut registers = Vec::new();
+        for description in snapshot_registers(&core) {
+            let value = if description.size_in_bits() > 32 {
+                core.read_core_reg::<u64>(description)
+                    .map(RegisterValue::from)
+            } else {
+                core.read_core_reg::<u32>(description)
+                    .map(RegisterValue::from)
+            };
+
+            // Not all registers of a register file exist on every core, e.g. the MSP on A-profile cores.
+            match value {
+                Ok(value) => registers.push((description.id, value)),
+                Err(error) => log::debug!(
+                    "Skipping register {} in the snapshot: {}",
+                    description.name(),
+                    error
+                ),
+            }
+        }
+
+        let mut memory = Vec::new();
+        for range in ranges {
+            let length = usize::try_from(range.end.saturating_sub(range.start)).map_err(|_| {
+                Error::Other(anyhow!("The memory range {:#010x?} is too large.", range))
+            })?;
+
+            let mut data = vec![0u8; length];
+            core.read_8(range.start, &mut data)?;
+            memory.push((range.start, data));
+        }
+
+        Ok(Snapshot {
+            core: core_index,
+            registers,
+            memory,
+        })
+    }
+
+    /// Restores a snapshot taken with [`Session::snapshot`]: writes back its memory and registers.
+    ///
+    /// The core is left halted at the program counter of the snapshot, so it can be resumed with
+    /// [`Core::run`] to run the program again from that point, without flashing it again.
+    pub fn restore(&mut self, snapshot: &Snapshot) -> Result<(), Error> {
+        let mut core = self.core(snapshot.core)?;
+        core.halt(Duration::from_millis(100))?;
+
+        for (address, data) in &snapshot.memory {
+            core.write_8(*address, data)?;
+        }
+        core.flush()?;
+
+        for (id, value) in &snapshot.registers {
+            core.write_core_reg(*id, *value)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The registers which are part of a snapshot, in the order in which they are restored.
+///
+/// The special registers come first, so e.g. the stack pointer registers are restored after
+/// `CONTROL` selected which of them is the current stack pointer.
+fn snapshot_registers(core: &Core<'_>) -> Vec<&'static RegisterDescription> {
+    let register_file = core.registers();
+
+    let mut registers: Vec<&'static RegisterDescription> = Vec::new();
+    let descriptions = register_file
+        .extra
+        .into_iter()
+        .chain(register_file.registers())
+        .chain(Some(register_file.program_counter()))
+        .chain(register_file.msp())
+        .chain(register_file.psp())
+        .chain(register_file.psr());
+
+    for description in descriptions {
+        if !registers
+            .iter()
+            .any(|register| register.id == description.id)
+        {
+            registers.push(description);
+        }
+    }
+
+    registers
+}
@@ -0,0 +1,55 @@
+use probe_rs::{
+    config::{MemoryRegion, MemoryRegionKind},
+    FakeProbe, Permissions, Probe,
+};
+
+#[test]
+fn reload_target_applies_memory_map() {
+    let probe = Probe::from_specific_probe(Box::new(FakeProbe::new()));
+
+    let mut session = probe
+        .attach("stm32wb55ccux", Permissions::default())
+        .expect("Failed to attach with 'fake' probe.");
+
+    // Write the family of the chip with a smaller RAM to a new target description file.
+    let mut family = probe_rs::config::families()
+        .unwrap()
+        .into_iter()
+        .find(|family| {
+            family
+                .variants
+                .iter()
+                .any(|chip| chip.name == "STM32WB55CCUx")
+        })
+        .unwrap();
+    family.name = "Reloaded STM32WB".to_owned();
+    let chip = family
+        .variants
+        .iter_mut()
+        .find(|chip| chip.name == "STM32WB55CCUx")
+        .unwrap();
+    for region in &mut chip.memory_map {
+        if let MemoryRegion::Ram(ram) = region {
+            ram.range.end = ram.range.start + 0x1000;
+        }
+    }
+
+    let path = std::env::temp_dir().join(format!("reload_target_{}.yaml", std::process::id()));
+    std::fs::write(&path, serde_yaml::to_string(&family).unwrap()).unwrap();
+
+    session
+        .reload_target(&path)
+        .expect("Failed to reload the target.");
+    std::fs::remove_file(&path).unwrap();
+
+    let core = session.core(0).unwrap();
+    let ram = core
+        .memory_regions()
+        .iter()
+        .filter(|region| matches!(region.kind, MemoryRegionKind::Ram { .. }))
+        .collect::<Vec<_>>();
+    assert!(!ram.is_empty());
+    assert!(ram
+        .iter()
+        .all(|region| region.range.end - region.range.start == 0x1000));
+}
@@ -0,0 +1,15 @@
+use probe_rs::debug::debug_info::DebugInfo;
+
+#[test]
+fn find_static_variable_by_name_and_path() {
+    let di = DebugInfo::from_file("tests/probe-rs-debugger-test").unwrap();
+
+    let variable = di
+        .find_static_variable("GLOBAL_STATIC")
+        .expect("Failed to find static variable.");
+
+    assert_eq!(variable.name, "GLOBAL_STATIC");
+    assert_ne!(variable.address, 0);
+    assert_eq!(di.find_static_variable(&variable.path()), Some(variable.clone()));
+    assert!(di.all_static_variables().contains(&variable));
+}
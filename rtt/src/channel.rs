@@ -1,7 +1,9 @@
+use crate::stats::{ChannelStatistics, StatisticsCounter};
 use crate::Error;
 use probe_rs::{config::MemoryRegion, Core, MemoryInterface};
 use scroll::{Pread, LE};
 use std::cmp::min;
+use std::time::Instant;
 
 /// Trait for channel information shared between up and down channels.
 pub trait RttChannel {
@@ -24,6 +26,8 @@ pub(crate) struct Channel {
     name: Option<String>,
     buffer_ptr: u32,
     size: u32,
+    // Only collected for up channels.
+    stats: StatisticsCounter,
 }
 
 // Chanels must follow this data layout when reading/writing memory in order to be compatible with
@@ -47,8 +51,8 @@ impl Channel {
     const O_NAME: usize = 0;
     const O_BUFFER_PTR: usize = 4;
     const O_SIZE: usize = 8;
-    const O_WRITE: usize = 12;
-    const O_READ: usize = 16;
+    pub(crate) const O_WRITE: usize = 12;
+    pub(crate) const O_READ: usize = 16;
     const O_FLAGS: usize = 20;
 
     pub(crate) fn from(
@@ -86,6 +90,7 @@ impl Channel {
             name,
             buffer_ptr,
             size: mem.pread_with(Self::O_SIZE, LE).unwrap(),
+            stats: StatisticsCounter::default(),
         }))
     }
 
@@ -106,14 +111,27 @@ impl Channel {
         self.size as usize
     }
 
+    /// The address of the channel structure in the control block.
+    pub(crate) fn ptr(&self) -> u32 {
+        self.ptr
+    }
+
     fn read_pointers(&self, core: &mut Core, dir: &'static str) -> Result<(u32, u32), Error> {
         self.validate_core_id(core)?;
+        check_memory_access(core)?;
         let mut block = [0u32; 2];
         core.read_32((self.ptr + Self::O_WRITE as u32).into(), block.as_mut())?;
 
-        let write: u32 = block[0];
-        let read: u32 = block[1];
+        self.validate_pointers(block[0], block[1], dir)
+    }
 
+    /// Checks the write and read pointers of the channel, which were read from the target.
+    pub(crate) fn validate_pointers(
+        &self,
+        write: u32,
+        read: u32,
+        dir: &'static str,
+    ) -> Result<(u32, u32), Error> {
         let validate = |which, value| {
             if value >= self.size {
                 Err(Error::ControlBlockCorrupted(format!(
@@ -163,6 +181,7 @@ impl UpChannel {
     /// See [`ChannelMode`] for more information on what the modes mean.
     pub fn mode(&self, core: &mut Core) -> Result<ChannelMode, Error> {
         self.0.validate_core_id(core)?;
+        check_memory_access(core)?;
 
         let flags = core.read_word_32((self.0.ptr + Channel::O_FLAGS as u32).into())?;
 
@@ -181,6 +200,7 @@ impl UpChannel {
     /// See [`ChannelMode`] for more information on what the modes mean.
     pub fn set_mode(&self, core: &mut Core, mode: ChannelMode) -> Result<(), Error> {
         self.0.validate_core_id(core)?;
+        check_memory_access(core)?;
         let flags = core.read_word_32((self.0.ptr + Channel::O_FLAGS as u32).into())?;
 
         let new_flags = (flags & !3) | (mode as u32);
@@ -189,10 +209,22 @@ impl UpChannel {
         Ok(())
     }
 
-    fn read_core(&self, core: &mut Core, mut buf: &mut [u8]) -> Result<(u32, usize), Error> {
+    fn read_core(&self, core: &mut Core, buf: &mut [u8]) -> Result<(u32, usize), Error> {
         self.0.validate_core_id(core)?;
-        let (write, mut read) = self.0.read_pointers(core, "up")?;
+        let (write, read) = self.0.read_pointers(core, "up")?;
 
+        self.read_buffer(core, write, read, buf)
+    }
+
+    /// Reads the data between the `read` and `write` pointers, which were already read from the
+    /// target, and returns the new read pointer and how many bytes were read.
+    pub(crate) fn read_buffer(
+        &self,
+        core: &mut Core,
+        write: u32,
+        mut read: u32,
+        mut buf: &mut [u8],
+    ) -> Result<(u32, usize), Error> {
         let mut total = 0;
 
         // Read while buffer contains data and output buffer has space (maximum of two iterations)
@@ -225,16 +257,48 @@ impl UpChannel {
     /// than would fit in `buf`.
     pub fn read(&self, core: &mut Core, buf: &mut [u8]) -> Result<usize, Error> {
         self.0.validate_core_id(core)?;
-        let (read, total) = self.read_core(core, buf)?;
+        let started = Instant::now();
+        let (write, read) = self.0.read_pointers(core, "up")?;
+        let (new_read, total) = self.read_buffer(core, write, read, buf)?;
 
         if total > 0 {
             // Write read pointer back to target if something was read
-            core.write_word_32((self.0.ptr + Channel::O_READ as u32).into(), read)?;
+            core.write_word_32((self.0.ptr + Channel::O_READ as u32).into(), new_read)?;
         }
 
+        self.record_read(write, read, total, started);
+
         Ok(total)
     }
 
+    /// Returns the statistics of the reads of this channel, which tell whether data was lost.
+    pub fn statistics(&self) -> ChannelStatistics {
+        self.0.stats.get()
+    }
+
+    /// Resets the statistics of this channel.
+    pub fn reset_statistics(&self) {
+        self.0.stats.reset()
+    }
+
+    /// Records in the statistics of this channel that the host dropped `bytes` bytes after they
+    /// were read, e.g. because an output couldn't keep up.
+    pub fn report_dropped(&self, bytes: usize) {
+        self.0.stats.record_dropped(bytes)
+    }
+
+    /// Records a read of `count` bytes, which started at `started`, in the statistics of this
+    /// channel. `write` and `read` are the pointers before the read.
+    pub(crate) fn record_read(&self, write: u32, read: u32, count: usize, started: Instant) {
+        // One byte of the buffer always stays free, so the write pointer doesn't reach the read
+        // pointer.
+        let buffer_full = (write + 1) % self.0.size == read;
+
+        self.0
+            .stats
+            .record_read(count, buffer_full, started.elapsed());
+    }
+
     /// Peeks at the current data in the channel buffer, copies data into the specified buffer and
     /// returns how many bytes were read.
     ///
@@ -357,6 +421,18 @@ impl RttChannel for DownChannel {
     }
 }
 
+/// Makes sure that the memory of the core can be accessed without halting it.
+///
+/// The core is only asked whether it is halted if it can't be accessed while running, so this
+/// doesn't cost a memory access on cores which can.
+pub(crate) fn check_memory_access(core: &mut Core) -> Result<(), Error> {
+    if core.supports_background_memory_access() || core.core_halted()? {
+        Ok(())
+    } else {
+        Err(Error::CoreRunning(core.id()))
+    }
+}
+
 /// Reads a null-terminated string from target memory. Lossy UTF-8 decoding is used.
 fn read_c_string(
     core: &mut Core,
@@ -411,3 +487,19 @@ pub enum ChannelMode {
     /// is not read by the host.
     BlockIfFull = 2,
 }
+
+impl std::str::FromStr for ChannelMode {
+    type Err = &'static str;
+
+    /// Parses the mode from `skip`, `trim` or `block`, as in the names of the modes of SEGGER's RTT
+    /// implementation, `SEGGER_RTT_MODE_NO_BLOCK_SKIP`, `SEGGER_RTT_MODE_NO_BLOCK_TRIM` and
+    /// `SEGGER_RTT_MODE_BLOCK_IF_FIFO_FULL`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "skip" => Ok(ChannelMode::NoBlockSkip),
+            "trim" => Ok(ChannelMode::NoBlockTrim),
+            "block" => Ok(ChannelMode::BlockIfFull),
+            _ => Err("Invalid channel mode, expected 'skip', 'trim' or 'block'."),
+        }
+    }
+}
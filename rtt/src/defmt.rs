@@ -0,0 +1,198 @@
+//! Decoding of [defmt](https://defmt.ferrous-systems.com/) encoded channels.
+//!
+//! defmt only sends the indices of its format strings, so the data has to be decoded with the
+//! table in the ELF file of the program which runs on the target. A [`DefmtDecoder`] is created
+//! from that file, and turns the data of a channel into [`DefmtRecord`]s.
+//!
+//! The decoder isn't tied to RTT: data from other transports, e.g. an ITM stimulus port, can be
+//! decoded with [`DefmtDecoder::push`].
+
+use crate::{Error, UpChannel};
+use defmt_decoder::{DecodeError, Encoding, Frame, Locations, Table};
+use log::{Level, LevelFilter};
+use probe_rs::Core;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::SystemTime;
+
+/// A log message which was decoded from a defmt channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DefmtRecord {
+    /// The host time at which the message was read from the target.
+    pub host_timestamp: SystemTime,
+    /// The timestamp of the target, if the program defines one with `defmt::timestamp!`.
+    pub timestamp: Option<String>,
+    /// The level of the message, or `None` for messages of `defmt::println!`.
+    pub level: Option<Level>,
+    /// The formatted message.
+    pub message: String,
+    /// The source file which logged the message, if the program has debug information.
+    pub file: Option<PathBuf>,
+    /// The line in the source file which logged the message.
+    pub line: Option<u64>,
+    /// The module path of the code which logged the message.
+    pub module: Option<String>,
+}
+
+impl fmt::Display for DefmtRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(timestamp) = &self.timestamp {
+            write!(f, "{} ", timestamp)?;
+        }
+
+        if let Some(level) = self.level {
+            write!(f, "{:<5} ", level)?;
+        }
+
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Decodes the data of a defmt encoded channel.
+///
+/// Messages may be split across several reads of the channel, so the same decoder has to be used
+/// for all data of a channel.
+#[derive(Debug)]
+pub struct DefmtDecoder {
+    table: Table,
+    locations: Option<Locations>,
+    level: LevelFilter,
+    pending: Vec<u8>,
+}
+
+impl DefmtDecoder {
+    /// Creates a decoder with the defmt table of a program, or returns `None` if the program
+    /// doesn't use defmt.
+    ///
+    /// All messages are decoded until a level filter is set with [`DefmtDecoder::set_level`].
+    pub fn from_elf(elf: &[u8]) -> Result<Option<Self>, Error> {
+        let table = match Table::parse(elf).map_err(|error| Error::Defmt(error.to_string()))? {
+            Some(table) => table,
+            None => return Ok(None),
+        };
+
+        let locations = table
+            .get_locations(elf)
+            .map_err(|error| Error::Defmt(error.to_string()))?;
+
+        let locations = if !table.is_empty() && locations.is_empty() {
+            log::warn!("Insufficient DWARF info; compile your program with `debug = 2` to enable location info.");
+            None
+        } else if table
+            .indices()
+            .all(|index| locations.contains_key(&(index as u64)))
+        {
+            Some(locations)
+        } else {
+            log::warn!("Location info is incomplete; it will be omitted from the output.");
+            None
+        };
+
+        Ok(Some(Self {
+            table,
+            locations,
+            level: LevelFilter::Trace,
+            pending: Vec::new(),
+        }))
+    }
+
+    /// Only decode messages with at least the level `level`.
+    ///
+    /// Messages without a level, from `defmt::println!`, are always decoded.
+    pub fn set_level(&mut self, level: LevelFilter) {
+        self.level = level;
+    }
+
+    /// Reads the available data from the channel and returns the messages which were completed by
+    /// it.
+    pub fn poll(
+        &mut self,
+        channel: &UpChannel,
+        core: &mut Core,
+    ) -> Result<Vec<DefmtRecord>, Error> {
+        let mut buffer = vec![0u8; channel.buffer_size()];
+        let count = channel.read(core, &mut buffer)?;
+
+        Ok(self.push(&buffer[..count]))
+    }
+
+    /// Decodes data which was read from the channel, and returns the messages which were completed
+    /// by it.
+    pub fn push(&mut self, data: &[u8]) -> Vec<DefmtRecord> {
+        self.pending.extend_from_slice(data);
+
+        let host_timestamp = SystemTime::now();
+        let mut records = Vec::new();
+
+        match self.table.encoding() {
+            Encoding::Raw => loop {
+                match self.table.decode(&self.pending) {
+                    Ok((frame, consumed)) => {
+                        records.extend(self.record(&frame, host_timestamp));
+                        self.pending.drain(..consumed);
+                    }
+                    Err(DecodeError::UnexpectedEof) => break,
+                    Err(DecodeError::Malformed) => {
+                        // The start of the next message can't be found in raw encoded data.
+                        log::warn!(
+                            "Malformed defmt data, discarding {} bytes.",
+                            self.pending.len()
+                        );
+                        self.pending.clear();
+                        break;
+                    }
+                }
+            },
+            Encoding::Rzcobs => {
+                // Each message is terminated by a zero byte, so the complete messages end at the
+                // last one.
+                if let Some(end) = self.pending.iter().rposition(|&byte| byte == 0) {
+                    let mut decoder = self.table.new_stream_decoder();
+                    decoder.received(&self.pending[..=end]);
+
+                    loop {
+                        match decoder.decode() {
+                            Ok(frame) => records.extend(self.record(&frame, host_timestamp)),
+                            Err(DecodeError::UnexpectedEof) => break,
+                            // The decoder skips to the next message.
+                            Err(DecodeError::Malformed) => log::warn!("Malformed defmt message."),
+                        }
+                    }
+
+                    drop(decoder);
+                    self.pending.drain(..=end);
+                }
+            }
+        }
+
+        records
+    }
+
+    fn record(&self, frame: &Frame<'_>, host_timestamp: SystemTime) -> Option<DefmtRecord> {
+        let level = frame
+            .level()
+            .and_then(|level| Level::from_str(level.as_str()).ok());
+
+        if matches!(level, Some(level) if level > self.level) {
+            return None;
+        }
+
+        let location = self
+            .locations
+            .as_ref()
+            .and_then(|locations| locations.get(&frame.index()));
+
+        Some(DefmtRecord {
+            host_timestamp,
+            timestamp: frame
+                .display_timestamp()
+                .map(|timestamp| timestamp.to_string()),
+            level,
+            message: frame.display_message().to_string(),
+            file: location.map(|location| location.file.clone()),
+            line: location.map(|location| location.line),
+            module: location.map(|location| location.module.clone()),
+        })
+    }
+}
@@ -40,6 +40,15 @@
 //!
 //! # Ok::<(), Box<dyn std::error::Error>>(())
 //! ```
+//!
+//! ## Running targets
+//!
+//! Attaching to RTT and accessing its channels never halts the core, so it doesn't disturb the
+//! timing of the application. This needs a core whose memory can be accessed while it is running,
+//! see [`Core::supports_background_memory_access`](probe_rs::Core::supports_background_memory_access),
+//! e.g. a Cortex-M core, or a RISC-V core whose debug module supports system bus access. On other
+//! cores, all operations fail with [`Error::CoreRunning`] while the core is running, instead of
+//! halting it.
 
 use thiserror::Error;
 
@@ -52,6 +61,23 @@ pub use channels::Channels;
 mod rtt;
 pub use rtt::*;
 
+#[cfg(feature = "defmt")]
+pub mod defmt;
+#[cfg(feature = "defmt")]
+pub use crate::defmt::{DefmtDecoder, DefmtRecord};
+
+pub mod poll;
+pub use poll::{read_up_channels, PollInterval};
+
+pub mod stats;
+pub use stats::ChannelStatistics;
+
+pub mod sink;
+pub use sink::ChannelLogger;
+
+pub mod terminal;
+pub use terminal::VirtualTerminals;
+
 /// Error type for RTT operations.
 #[derive(Error, Debug)]
 pub enum Error {
@@ -81,7 +107,20 @@ pub enum Error {
     #[error("Error communicating with probe: {0}")]
     Probe(#[from] probe_rs::Error),
 
+    /// The memory of the core can't be accessed while it is running, and RTT doesn't halt it.
+    #[error("The memory of core {0} can't be accessed without halting it. RTT can only be used while this core is halted.")]
+    CoreRunning(usize),
+
     /// Wraps errors propagated up from reading memory on the target.
     #[error("Unexpected error while reading {0} from target memory. Please report this as a bug.")]
     MemoryRead(String),
+
+    /// Wraps errors while writing or reading logged channel data.
+    #[error("Error accessing the channel log: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// The defmt table of the program could not be read.
+    #[cfg(feature = "defmt")]
+    #[error("Error reading the defmt table: {0}")]
+    Defmt(String),
 }
@@ -0,0 +1,175 @@
+//! Efficient polling of RTT up channels.
+//!
+//! Every memory access is a round trip to the probe, so polling each channel separately in a tight
+//! loop can saturate slow probes and starve other debug traffic. [`read_up_channels`] reads the
+//! pointers of all channels at once, and only accesses the buffers of channels which have data, and
+//! [`PollInterval`] backs off the polling while the target doesn't send anything.
+
+use crate::channel::{check_memory_access, Channel};
+use crate::{Error, UpChannel};
+use probe_rs::{Core, MemoryInterface};
+use scroll::{Pread, LE};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// The largest range of channel structures which is read at once, the channel arrays of a
+/// control block with the maximum number of channels.
+const MAX_HEADER_SPAN: u32 = 2 * 255 * Channel::SIZE as u32;
+
+/// Reads the available data of all `channels`, and returns it by channel number.
+///
+/// The channel structures are next to each other in the control block, so their pointers are read
+/// with a single memory access. After that, only the buffers of channels which have data are read,
+/// and only their read pointers are written back.
+pub fn read_up_channels<'a>(
+    core: &mut Core,
+    channels: impl IntoIterator<Item = &'a UpChannel>,
+) -> Result<BTreeMap<usize, Vec<u8>>, Error> {
+    let started = Instant::now();
+    let channels: Vec<&UpChannel> = channels.into_iter().collect();
+
+    for channel in &channels {
+        channel.0.validate_core_id(core)?;
+    }
+
+    let start = match channels.iter().map(|channel| channel.0.ptr()).min() {
+        Some(start) => start,
+        None => return Ok(BTreeMap::new()),
+    };
+    check_memory_access(core)?;
+
+    let end = channels
+        .iter()
+        .map(|channel| channel.0.ptr() + Channel::SIZE as u32)
+        .max()
+        .unwrap_or(start);
+
+    let pointers = if end - start <= MAX_HEADER_SPAN {
+        let mut headers = vec![0u8; (end - start) as usize];
+        core.read(start.into(), &mut headers)?;
+
+        channels
+            .iter()
+            .map(|channel| {
+                let offset = (channel.0.ptr() - start) as usize;
+
+                (
+                    headers.pread_with(offset + Channel::O_WRITE, LE).unwrap(),
+                    headers.pread_with(offset + Channel::O_READ, LE).unwrap(),
+                )
+            })
+            .collect::<Vec<(u32, u32)>>()
+    } else {
+        // The channels are from different control blocks, which are too far apart to read them
+        // at once.
+        let mut pointers = Vec::with_capacity(channels.len());
+        for channel in &channels {
+            let mut block = [0u32; 2];
+            core.read_32(
+                (channel.0.ptr() + Channel::O_WRITE as u32).into(),
+                &mut block,
+            )?;
+            pointers.push((block[0], block[1]));
+        }
+        pointers
+    };
+
+    let mut data = BTreeMap::new();
+
+    for (channel, (write, read)) in channels.into_iter().zip(pointers) {
+        let (write, read) = channel.0.validate_pointers(write, read, "up")?;
+        if write == read {
+            channel.record_read(write, read, 0, started);
+            continue;
+        }
+
+        let mut buffer = vec![0u8; channel.buffer_size()];
+        let (new_read, count) = channel.read_buffer(core, write, read, &mut buffer)?;
+
+        core.write_word_32((channel.0.ptr() + Channel::O_READ as u32).into(), new_read)?;
+        channel.record_read(write, read, count, started);
+
+        buffer.truncate(count);
+        data.insert(channel.number(), buffer);
+    }
+
+    Ok(data)
+}
+
+/// The interval between polls of RTT channels, which backs off while the channels are idle.
+///
+/// After each poll, [`PollInterval::update`] returns how long to wait before the next one: the
+/// minimum interval if the poll found data, and otherwise twice the previous interval, up to the
+/// maximum interval.
+#[derive(Debug, Clone)]
+pub struct PollInterval {
+    min: Duration,
+    max: Duration,
+    current: Duration,
+}
+
+impl PollInterval {
+    /// Creates an interval which backs off from `min` to `max`.
+    pub fn new(min: Duration, max: Duration) -> Self {
+        Self {
+            min,
+            max,
+            current: min,
+        }
+    }
+
+    /// Returns the current interval.
+    pub fn current(&self) -> Duration {
+        self.current
+    }
+
+    /// Updates the interval after a poll, which found data if `active` is `true`, and returns the
+    /// time to wait before the next poll.
+    pub fn update(&mut self, active: bool) -> Duration {
+        self.current = if active {
+            self.min
+        } else {
+            // Starting from a minimum of zero, the interval would never grow by doubling it.
+            (self.current * 2)
+                .max(Duration::from_millis(1))
+                .min(self.max)
+        };
+
+        self.current
+    }
+
+    /// Updates the interval after a poll, and waits until the next poll.
+    pub fn wait(&mut self, active: bool) {
+        let interval = self.update(active);
+
+        if !interval.is_zero() {
+            std::thread::sleep(interval);
+        }
+    }
+}
+
+impl Default for PollInterval {
+    /// Polls again immediately while there is data, and backs off to 100 ms while there is none.
+    fn default() -> Self {
+        Self::new(Duration::ZERO, Duration::from_millis(100))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PollInterval;
+    use std::time::Duration;
+
+    #[test]
+    fn back_off_while_idle() {
+        let mut interval = PollInterval::new(Duration::ZERO, Duration::from_millis(5));
+
+        assert_eq!(interval.update(true), Duration::ZERO);
+        assert_eq!(interval.update(false), Duration::from_millis(1));
+        assert_eq!(interval.update(false), Duration::from_millis(2));
+        assert_eq!(interval.update(false), Duration::from_millis(4));
+        assert_eq!(interval.update(false), Duration::from_millis(5));
+        assert_eq!(interval.update(false), Duration::from_millis(5));
+        assert_eq!(interval.update(true), Duration::ZERO);
+    }
+}
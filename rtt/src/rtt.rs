@@ -10,6 +10,8 @@ use std::ops::Range;
 ///
 /// Use [`Rtt::attach`] or [`Rtt::attach_region`] to attach to a probe-rs [`Core`] and detect the channels, as they were
 ///     configured on the target.
+/// Attaching never halts the core; if the core's memory can't be accessed while it is running, attaching fails with
+/// [`Error::CoreRunning`](crate::Error::CoreRunning) instead.
 /// The timing of when this is called is really important, or else unexpected results can be expected.
 ///
 /// ## Examples of how timing between host and target effects the results
@@ -171,11 +173,46 @@ impl Rtt {
     /// Attempts to detect an RTT control block in the specified RAM region(s) and returns an
     /// instance if a valid control block was found.
     ///
+    /// The address of the control block is remembered by the core for the rest of the session
+    /// (see [`Core::rtt_control_block`]), and tried first the next time RTT is attached to, before
+    /// the region is scanned again.
+    ///
     /// `core` can be e.g. an owned `Core` or a shared `Rc<Core>`.
     pub fn attach_region(
         core: &mut Core,
         memory_map: &[MemoryRegion],
         region: &ScanRegion,
+    ) -> Result<Rtt, Error> {
+        check_memory_access(core)?;
+
+        let known_address = match region {
+            ScanRegion::Exact(_) => None,
+            _ => core.rtt_control_block(),
+        };
+
+        if let Some(address) = known_address {
+            log::debug!("Trying the known control block address: 0x{:X}", address);
+
+            match Rtt::from(core, memory_map, address as u32, None) {
+                Ok(Some(rtt)) => return Ok(rtt),
+                Ok(None) | Err(Error::ControlBlockNotFound) => {
+                    log::debug!("No control block at the known address anymore, scanning again.");
+                    core.set_rtt_control_block(None);
+                }
+                Err(error) => return Err(error),
+            }
+        }
+
+        let rtt = Self::scan(core, memory_map, region)?;
+        core.set_rtt_control_block(Some(rtt.ptr.into()));
+
+        Ok(rtt)
+    }
+
+    fn scan(
+        core: &mut Core,
+        memory_map: &[MemoryRegion],
+        region: &ScanRegion,
     ) -> Result<Rtt, Error> {
         let ranges: Vec<Range<u32>> = match region {
             ScanRegion::Exact(addr) => {
@@ -184,6 +221,17 @@ impl Rtt {
                 return Rtt::from(core, memory_map, *addr, None)?
                     .ok_or(Error::ControlBlockNotFound);
             }
+            ScanRegion::Ram if !core.rtt_scan_ranges().is_empty() => {
+                log::debug!(
+                    "Scanning the configured ranges: {:?}",
+                    core.rtt_scan_ranges()
+                );
+
+                core.rtt_scan_ranges()
+                    .iter()
+                    .map(|range| range.start as u32..range.end as u32)
+                    .collect()
+            }
             ScanRegion::Ram => {
                 log::debug!("Scanning RAM");
 
@@ -257,6 +305,13 @@ impl Rtt {
         &mut self.up_channels
     }
 
+    /// Reads the available data of all up channels which are still in
+    /// [`up_channels`](Rtt::up_channels), with as few memory accesses as possible, see
+    /// [`read_up_channels`](crate::read_up_channels).
+    pub fn read_up_channels(&mut self, core: &mut Core) -> Result<BTreeMap<usize, Vec<u8>>, Error> {
+        crate::read_up_channels(core, self.up_channels.iter())
+    }
+
     /// Gets the detected down channels.
     pub fn down_channels(&mut self) -> &mut Channels<DownChannel> {
         &mut self.down_channels
@@ -268,6 +323,9 @@ impl Rtt {
 pub enum ScanRegion {
     /// Scans all RAM regions known to probe-rs. This is the default and should always work, however
     /// if your device has a lot of RAM, scanning all of it is slow.
+    ///
+    /// If the target description or the [`AttachOptions`](probe_rs::AttachOptions) of the session
+    /// specify ranges to scan for the control block, only these ranges are scanned.
     Ram,
 
     /// Limit scanning to these memory addresses in target memory. It is up to the user to ensure
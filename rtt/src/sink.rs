@@ -0,0 +1,253 @@
+//! Logging of binary RTT channel data.
+//!
+//! Channels which carry binary telemetry instead of text are logged with a [`ChannelLogger`],
+//! which records the host time at which each chunk of data was read from the target. Framed logs
+//! can be read back with a [`RecordReader`].
+//!
+//! A framed log is a sequence of records, each of which consists of
+//!
+//! - the host timestamp, as microseconds since the UNIX epoch (`u64`, little endian),
+//! - the length of the data (`u32`, little endian),
+//! - the data which was read from the channel.
+
+use crate::{Error, UpChannel};
+use probe_rs::Core;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The size of the header of a record in a framed log.
+const HEADER_SIZE: usize = 12;
+
+/// How the data of a channel is written by a [`ChannelLogger`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framing {
+    /// Each chunk of data is written as a record with its timestamp and length.
+    LengthPrefixed,
+    /// The data is written as it was read, without timestamps, e.g. for channels whose data has
+    /// its own framing.
+    None,
+}
+
+impl Default for Framing {
+    fn default() -> Self {
+        Framing::LengthPrefixed
+    }
+}
+
+/// Writes the data of an RTT up channel to a file or another writer.
+///
+/// # Example
+///
+/// ```no_run
+/// use probe_rs_rtt::sink::{ChannelLogger, Framing};
+/// # use probe_rs::{Permissions, Probe};
+/// # use probe_rs_rtt::Rtt;
+/// # let probe = Probe::list_all()[0].open()?;
+/// # let mut session = probe.attach("somechip", Permissions::default())?;
+/// # let memory_map = session.target().memory_map.clone();
+/// # let mut core = session.core(0)?;
+/// # let mut rtt = Rtt::attach(&mut core, &memory_map)?;
+/// let channel = rtt.up_channels().take(1).unwrap();
+/// let mut logger = ChannelLogger::create("telemetry.bin", Framing::LengthPrefixed)?;
+///
+/// loop {
+///     logger.poll(&channel, &mut core)?;
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug)]
+pub struct ChannelLogger<W: Write> {
+    writer: W,
+    framing: Framing,
+    buffer: Vec<u8>,
+}
+
+impl ChannelLogger<BufWriter<File>> {
+    /// Creates the file at `path`, or truncates it if it exists, and logs to it.
+    pub fn create(path: impl AsRef<Path>, framing: Framing) -> Result<Self, Error> {
+        let file = File::create(path)?;
+
+        Ok(Self::new(BufWriter::new(file), framing))
+    }
+}
+
+impl<W: Write> ChannelLogger<W> {
+    /// Creates a logger which writes to `writer`.
+    pub fn new(writer: W, framing: Framing) -> Self {
+        Self {
+            writer,
+            framing,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Reads the available data from the channel, logs it with the current time, and returns how
+    /// many bytes were read.
+    ///
+    /// Nothing is logged if the channel has no data.
+    pub fn poll(&mut self, channel: &UpChannel, core: &mut Core) -> Result<usize, Error> {
+        self.buffer.resize(channel.buffer_size(), 0);
+        let count = channel.read(core, &mut self.buffer)?;
+
+        if count > 0 {
+            let timestamp = SystemTime::now();
+            write_chunk(
+                &mut self.writer,
+                self.framing,
+                timestamp,
+                &self.buffer[..count],
+            )?;
+        }
+
+        Ok(count)
+    }
+
+    /// Logs data which was read from the channel at `timestamp`.
+    pub fn write(&mut self, timestamp: SystemTime, data: &[u8]) -> Result<(), Error> {
+        write_chunk(&mut self.writer, self.framing, timestamp, data)?;
+
+        Ok(())
+    }
+
+    /// Flushes the data which is buffered by the writer.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Returns the writer of the logger.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+fn write_chunk(
+    writer: &mut impl Write,
+    framing: Framing,
+    timestamp: SystemTime,
+    data: &[u8],
+) -> io::Result<()> {
+    if framing == Framing::LengthPrefixed {
+        // Timestamps before the UNIX epoch can only come from a badly set clock.
+        let micros = timestamp
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_micros() as u64;
+        let length = u32::try_from(data.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "The data is too large."))?;
+
+        writer.write_all(&micros.to_le_bytes())?;
+        writer.write_all(&length.to_le_bytes())?;
+    }
+
+    writer.write_all(data)
+}
+
+/// A chunk of channel data in a framed log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    /// The host time at which the data was read from the target.
+    pub timestamp: SystemTime,
+    /// The data which was read from the channel.
+    pub data: Vec<u8>,
+}
+
+/// Reads the records of a log which was written with [`Framing::LengthPrefixed`].
+#[derive(Debug)]
+pub struct RecordReader<R: Read> {
+    reader: R,
+}
+
+impl<R: Read> RecordReader<R> {
+    /// Creates a reader which reads the records from `reader`.
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+
+    /// Reads the next record, or returns `None` at the end of the log.
+    pub fn read_record(&mut self) -> Result<Option<Record>, Error> {
+        let mut header = [0u8; HEADER_SIZE];
+
+        // The log may end after any complete record, but not inside of one.
+        let mut filled = 0;
+        while filled < HEADER_SIZE {
+            match self.reader.read(&mut header[filled..])? {
+                0 if filled == 0 => return Ok(None),
+                0 => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+                count => filled += count,
+            }
+        }
+
+        let micros = u64::from_le_bytes(header[..8].try_into().unwrap());
+        let length = u32::from_le_bytes(header[8..].try_into().unwrap());
+
+        let mut data = vec![0u8; length as usize];
+        self.reader.read_exact(&mut data)?;
+
+        Ok(Some(Record {
+            timestamp: UNIX_EPOCH + Duration::from_micros(micros),
+            data,
+        }))
+    }
+}
+
+impl<R: Read> Iterator for RecordReader<R> {
+    type Item = Result<Record, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ChannelLogger, Framing, Record, RecordReader};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn read_framed_log() {
+        let first = UNIX_EPOCH + Duration::from_micros(1_650_000_000_000_001);
+        let second = first + Duration::from_millis(3);
+
+        let mut logger = ChannelLogger::new(Vec::new(), Framing::LengthPrefixed);
+        logger.write(first, &[1, 2, 3]).unwrap();
+        logger.write(second, &[0xff]).unwrap();
+        let log = logger.into_inner();
+
+        assert_eq!(log.len(), 2 * 12 + 4);
+
+        let records = RecordReader::new(log.as_slice())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            records,
+            vec![
+                Record {
+                    timestamp: first,
+                    data: vec![1, 2, 3],
+                },
+                Record {
+                    timestamp: second,
+                    data: vec![0xff],
+                },
+            ]
+        );
+
+        assert!(RecordReader::new(&log[..log.len() - 1])
+            .nth(1)
+            .unwrap()
+            .is_err());
+    }
+
+    #[test]
+    fn raw_log() {
+        let mut logger = ChannelLogger::new(Vec::new(), Framing::None);
+        logger.write(UNIX_EPOCH, b"ab").unwrap();
+        logger.write(UNIX_EPOCH, b"c").unwrap();
+
+        assert_eq!(logger.into_inner(), b"abc");
+    }
+}
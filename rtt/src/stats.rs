@@ -0,0 +1,132 @@
+//! Statistics of RTT up channels.
+//!
+//! The target can't tell the host that it dropped data, because a channel's buffer was full, so a
+//! gap in the output looks just like a program which didn't log anything. The statistics of a
+//! channel record each read which found the buffer full, so such gaps can be detected.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Statistics of an RTT up channel, which tell whether the data which was read from it is
+/// complete.
+///
+/// They are returned by [`UpChannel::statistics`](crate::UpChannel::statistics).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChannelStatistics {
+    /// The number of bytes which were read from the channel.
+    pub bytes_read: u64,
+    /// The number of times the channel was read.
+    pub reads: u64,
+    /// The number of reads which found the buffer of the channel full.
+    ///
+    /// The target drops data which doesn't fit into a full buffer in the
+    /// [`NoBlockSkip`](crate::ChannelMode::NoBlockSkip) and
+    /// [`NoBlockTrim`](crate::ChannelMode::NoBlockTrim) modes, and waits for the host in the
+    /// [`BlockIfFull`](crate::ChannelMode::BlockIfFull) mode.
+    pub buffer_full: u64,
+    /// The number of bytes which were dropped by the host after they were read, as reported with
+    /// [`UpChannel::report_dropped`](crate::UpChannel::report_dropped).
+    pub host_dropped: u64,
+    /// How long the last read of the channel took.
+    pub last_read_latency: Duration,
+    /// How long the slowest read of the channel took.
+    pub max_read_latency: Duration,
+}
+
+impl ChannelStatistics {
+    /// Returns `true` if data of the channel may have been lost, on the target or on the host.
+    pub fn data_may_be_lost(&self) -> bool {
+        self.buffer_full > 0 || self.host_dropped > 0
+    }
+}
+
+/// Collects the [`ChannelStatistics`] of a channel.
+///
+/// Channels are read through a shared reference, so the counters are atomic.
+#[derive(Debug, Default)]
+pub(crate) struct StatisticsCounter {
+    bytes_read: AtomicU64,
+    reads: AtomicU64,
+    buffer_full: AtomicU64,
+    host_dropped: AtomicU64,
+    last_read_latency: AtomicU64,
+    max_read_latency: AtomicU64,
+}
+
+impl StatisticsCounter {
+    /// Records a read of `bytes` bytes, which took `latency`, and found the buffer full if
+    /// `buffer_full` is `true`.
+    pub(crate) fn record_read(&self, bytes: usize, buffer_full: bool, latency: Duration) {
+        let latency = latency.as_nanos() as u64;
+
+        self.bytes_read.fetch_add(bytes as u64, Ordering::Relaxed);
+        self.reads.fetch_add(1, Ordering::Relaxed);
+        if buffer_full {
+            self.buffer_full.fetch_add(1, Ordering::Relaxed);
+        }
+        self.last_read_latency.store(latency, Ordering::Relaxed);
+        self.max_read_latency.fetch_max(latency, Ordering::Relaxed);
+    }
+
+    /// Records that the host dropped `bytes` bytes after reading them.
+    pub(crate) fn record_dropped(&self, bytes: usize) {
+        self.host_dropped.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn get(&self) -> ChannelStatistics {
+        ChannelStatistics {
+            bytes_read: self.bytes_read.load(Ordering::Relaxed),
+            reads: self.reads.load(Ordering::Relaxed),
+            buffer_full: self.buffer_full.load(Ordering::Relaxed),
+            host_dropped: self.host_dropped.load(Ordering::Relaxed),
+            last_read_latency: Duration::from_nanos(self.last_read_latency.load(Ordering::Relaxed)),
+            max_read_latency: Duration::from_nanos(self.max_read_latency.load(Ordering::Relaxed)),
+        }
+    }
+
+    pub(crate) fn reset(&self) {
+        for counter in [
+            &self.bytes_read,
+            &self.reads,
+            &self.buffer_full,
+            &self.host_dropped,
+            &self.last_read_latency,
+            &self.max_read_latency,
+        ] {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ChannelStatistics, StatisticsCounter};
+    use std::time::Duration;
+
+    #[test]
+    fn collect_statistics() {
+        let counter = StatisticsCounter::default();
+
+        counter.record_read(16, false, Duration::from_micros(300));
+        counter.record_read(1023, true, Duration::from_micros(900));
+        counter.record_read(0, false, Duration::from_micros(200));
+        counter.record_dropped(5);
+
+        let statistics = counter.get();
+        assert_eq!(
+            statistics,
+            ChannelStatistics {
+                bytes_read: 1039,
+                reads: 3,
+                buffer_full: 1,
+                host_dropped: 5,
+                last_read_latency: Duration::from_micros(200),
+                max_read_latency: Duration::from_micros(900),
+            }
+        );
+        assert!(statistics.data_may_be_lost());
+
+        counter.reset();
+        assert_eq!(counter.get(), ChannelStatistics::default());
+    }
+}
@@ -0,0 +1,124 @@
+//! Virtual terminals on an RTT up channel.
+//!
+//! SEGGER's RTT implementation multiplexes up to 16 virtual terminals on up channel 0. The target
+//! switches to another terminal with an escape sequence, the byte `0xFF` followed by the number of
+//! the terminal, `'0'`-`'9'` or `'A'`-`'F'`, and all following output belongs to that terminal.
+
+use crate::{Error, UpChannel};
+use probe_rs::Core;
+use std::collections::BTreeMap;
+
+/// The byte which starts a terminal switch escape sequence.
+const ESCAPE: u8 = 0xFF;
+
+/// Splits the output of an RTT up channel into its virtual terminals.
+///
+/// The output starts on terminal 0. Data which is read from the channel is passed to
+/// [`push`](VirtualTerminals::push), or read with [`poll`](VirtualTerminals::poll), and the output
+/// of each terminal can then be taken separately with [`take`](VirtualTerminals::take).
+#[derive(Debug, Default)]
+pub struct VirtualTerminals {
+    current: usize,
+    escape_pending: bool,
+    output: BTreeMap<usize, Vec<u8>>,
+}
+
+impl VirtualTerminals {
+    /// Creates the virtual terminals of a channel, with terminal 0 selected.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of the terminal which the target currently writes to.
+    pub fn current_terminal(&self) -> usize {
+        self.current
+    }
+
+    /// Splits data which was read from the channel into the output of the terminals.
+    ///
+    /// An escape sequence may be split across several calls.
+    pub fn push(&mut self, data: &[u8]) {
+        for &byte in data {
+            if self.escape_pending {
+                self.escape_pending = false;
+
+                match terminal_number(byte) {
+                    Some(terminal) => self.current = terminal,
+                    // Not an escape sequence, so the bytes are passed through unchanged.
+                    None => self.output(&[ESCAPE, byte]),
+                }
+            } else if byte == ESCAPE {
+                self.escape_pending = true;
+            } else {
+                self.output(&[byte]);
+            }
+        }
+    }
+
+    /// Reads the available data from the channel, splits it into the output of the terminals, and
+    /// returns how many bytes were read.
+    pub fn poll(&mut self, channel: &UpChannel, core: &mut Core) -> Result<usize, Error> {
+        let mut buffer = vec![0u8; channel.buffer_size()];
+        let count = channel.read(core, &mut buffer)?;
+
+        self.push(&buffer[..count]);
+
+        Ok(count)
+    }
+
+    /// Takes the output of a terminal, which was written since it was last taken.
+    pub fn take(&mut self, terminal: usize) -> Vec<u8> {
+        self.output.remove(&terminal).unwrap_or_default()
+    }
+
+    /// Takes the output of all terminals which have output, sorted by the number of the terminal.
+    pub fn take_all(&mut self) -> impl Iterator<Item = (usize, Vec<u8>)> {
+        std::mem::take(&mut self.output).into_iter()
+    }
+
+    /// Returns the numbers of the terminals which have output which wasn't taken yet.
+    pub fn terminals_with_output(&self) -> impl Iterator<Item = usize> + '_ {
+        self.output.keys().copied()
+    }
+
+    fn output(&mut self, data: &[u8]) {
+        self.output
+            .entry(self.current)
+            .or_default()
+            .extend_from_slice(data);
+    }
+}
+
+/// Returns the terminal number which follows the escape byte in a terminal switch escape sequence.
+fn terminal_number(byte: u8) -> Option<usize> {
+    let number = match byte {
+        b'0'..=b'9' => byte - b'0',
+        b'A'..=b'F' => byte - b'A' + 10,
+        _ => return None,
+    };
+
+    Some(number as usize)
+}
+
+#[cfg(test)]
+mod test {
+    use super::VirtualTerminals;
+
+    #[test]
+    fn split_terminals() {
+        let mut terminals = VirtualTerminals::new();
+
+        terminals.push(b"boot\n\xff1error\n\xff");
+        terminals.push(b"0ok\n\xffFx\xffzy");
+
+        assert_eq!(terminals.current_terminal(), 15);
+        assert_eq!(
+            terminals.terminals_with_output().collect::<Vec<_>>(),
+            vec![0, 1, 15]
+        );
+        assert_eq!(terminals.take(0), b"boot\nok\n");
+        assert_eq!(terminals.take(1), b"error\n");
+        assert_eq!(terminals.take(15), b"x\xffzy");
+        assert!(terminals.take(0).is_empty());
+    }
+}
@@ -1,6 +1,7 @@
 use probe_rs::Permissions;
 use probe_rs::{config::TargetSelector, DebugProbeInfo, Probe};
-use probe_rs_rtt::{Channels, Rtt, RttChannel, ScanRegion};
+use probe_rs_rtt::sink::{ChannelLogger, Framing};
+use probe_rs_rtt::{ChannelMode, Channels, PollInterval, Rtt, RttChannel, ScanRegion, UpChannel};
 
 use clap::Parser;
 use std::io::prelude::*;
@@ -92,6 +93,27 @@ struct Opts {
     )]
     down: Option<usize>,
 
+    #[clap(
+        short,
+        long,
+        help = "Mode of the up channel when its buffer is full: 'skip' or 'trim' the data, or 'block' the target until the data was read."
+    )]
+    mode: Option<ChannelMode>,
+
+    #[clap(
+        short,
+        long,
+        help = "Log the up channel to this file instead of printing it, with the host time of each read."
+    )]
+    output: Option<std::path::PathBuf>,
+
+    #[clap(
+        long,
+        requires = "output",
+        help = "Log the data of the up channel as it is, without timestamps and lengths."
+    )]
+    raw: bool,
+
     #[clap(
         long,
         default_value="",
@@ -181,7 +203,7 @@ fn run() -> i32 {
 
     if opts.list {
         println!("Up channels:");
-        list_channels(rtt.up_channels());
+        list_up_channels(&mut core, rtt.up_channels());
 
         println!("Down channels:");
         list_channels(rtt.down_channels());
@@ -202,6 +224,17 @@ fn run() -> i32 {
         rtt.up_channels().take(0)
     };
 
+    if let (Some(up_channel), Some(mode)) = (up_channel.as_ref(), opts.mode) {
+        if let Err(err) = up_channel.set_mode(&mut core, mode) {
+            eprintln!(
+                "Error setting the mode of up channel {}: {}",
+                up_channel.number(),
+                err
+            );
+            return 1;
+        }
+    }
+
     let down_channel = if let Some(down) = opts.down {
         let chan = rtt.down_channels().take(down);
 
@@ -217,13 +250,47 @@ fn run() -> i32 {
 
     let stdin = down_channel.as_ref().map(|_| stdin_channel());
 
+    let mut logger = match &opts.output {
+        Some(path) => {
+            let framing = if opts.raw {
+                Framing::None
+            } else {
+                Framing::LengthPrefixed
+            };
+
+            match ChannelLogger::create(path, framing) {
+                Ok(logger) => Some(logger),
+                Err(err) => {
+                    eprintln!("Error creating {}: {}", path.display(), err);
+                    return 1;
+                }
+            }
+        }
+        None => None,
+    };
+
     eprintln!("Found control block at 0x{:08x}", rtt.ptr());
 
     let mut up_buf = [0u8; 1024];
     let mut down_buf = vec![];
+    let mut poll_interval = PollInterval::default();
 
     loop {
-        if let Some(up_channel) = up_channel.as_ref() {
+        let mut active = false;
+
+        if let (Some(up_channel), Some(logger)) = (up_channel.as_ref(), logger.as_mut()) {
+            let result = logger
+                .poll(up_channel, &mut core)
+                .and_then(|count| logger.flush().map(|_| count));
+
+            match result {
+                Ok(count) => active |= count > 0,
+                Err(err) => {
+                    eprintln!("\nError logging RTT: {}", err);
+                    return 1;
+                }
+            }
+        } else if let Some(up_channel) = up_channel.as_ref() {
             let count = match up_channel.read(&mut core, up_buf.as_mut()) {
                 Ok(count) => count,
                 Err(err) => {
@@ -231,6 +298,7 @@ fn run() -> i32 {
                     return 1;
                 }
             };
+            active |= count > 0;
 
             match stdout().write_all(&up_buf[..count]) {
                 Ok(_) => {
@@ -259,9 +327,12 @@ fn run() -> i32 {
 
                 if count > 0 {
                     down_buf.drain(..count);
+                    active = true;
                 }
             }
         }
+
+        poll_interval.wait(active);
     }
 }
 
@@ -299,6 +370,28 @@ fn list_channels(channels: &Channels<impl RttChannel>) {
     }
 }
 
+fn list_up_channels(core: &mut probe_rs::Core, channels: &Channels<UpChannel>) {
+    if channels.is_empty() {
+        println!("  (none)");
+        return;
+    }
+
+    for chan in channels.iter() {
+        let mode = match chan.mode(core) {
+            Ok(mode) => format!("{:?}", mode),
+            Err(err) => format!("unknown mode: {}", err),
+        };
+
+        println!(
+            "  {}: {} (buffer size {}, {})",
+            chan.number(),
+            chan.name().unwrap_or("(no name)"),
+            chan.buffer_size(),
+            mode,
+        );
+    }
+}
+
 fn stdin_channel() -> Receiver<Vec<u8>> {
     let (tx, rx) = channel();
 
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::fs::{self};
 use std::io::Read;
 use std::path::Path;
@@ -10,9 +11,13 @@ use probe_rs::config::{
     Chip, ChipFamily, Core as ProbeCore, MemoryRegion, NvmRegion, RamRegion, RawFlashAlgorithm,
 };
 use probe_rs::{Architecture, CoreType};
-use probe_rs_target::{ArmCoreAccessOptions, CoreAccessOptions, RiscvCoreAccessOptions};
+use probe_rs_target::{
+    ArmCoreAccessOptions, CoreAccessOptions, MipsCoreAccessOptions, RiscvCoreAccessOptions,
+};
 use tokio::runtime::Builder;
 
+use crate::sequences::{debug_descriptions, DebugDescription};
+
 pub(crate) enum Kind<'a, T>
 where
     T: std::io::Seek + std::io::Read,
@@ -23,6 +28,7 @@ where
 
 pub(crate) fn handle_package<T>(
     pdsc: Package,
+    debug_descriptions: &HashMap<String, DebugDescription>,
     mut kind: Kind<T>,
     families: &mut Vec<ChipFamily>,
 ) -> Result<()>
@@ -42,11 +48,18 @@ where
         let family = if let Some(ref mut family) = potential_family {
             family
         } else {
+            let description = debug_descriptions
+                .get(&device.family)
+                .cloned()
+                .unwrap_or_default();
+
             families.push(ChipFamily {
                 name: device.family.clone(),
                 manufacturer: None,
                 variants: Vec::new(),
                 flash_algorithms: Vec::new(),
+                debug_vars: description.debug_vars,
+                debug_sequences: description.sequences,
                 source: probe_rs::config::TargetDescriptionSource::BuiltIn,
             });
             // This unwrap is always safe as we insert at least one item previously.
@@ -101,7 +114,7 @@ where
         let flash = get_flash(&device);
 
         if device.processors.len() > 1 {
-            println!("{:#?}", device.processors);
+            log::debug!("{:#?}", device.processors);
         }
 
         let flash_algorithm_names: Vec<_> = variant_flash_algorithms
@@ -138,6 +151,8 @@ where
             cores,
             memory_map,
             flash_algorithms: flash_algorithm_names,
+            connect_under_reset: None,
+            rtt_scan_ranges: vec![],
         });
     }
 
@@ -160,7 +175,8 @@ fn create_core(processor: &Processor) -> Result<ProbeCore> {
                 debug_base: None,
                 cti_base: None,
             }),
-            Architecture::Riscv => CoreAccessOptions::Riscv(RiscvCoreAccessOptions {}),
+            Architecture::Riscv => CoreAccessOptions::Riscv(RiscvCoreAccessOptions::default()),
+            Architecture::Mips => CoreAccessOptions::Mips(MipsCoreAccessOptions::default()),
         },
     })
 }
@@ -193,8 +209,10 @@ pub(crate) fn visit_dirs(path: &Path, families: &mut Vec<ChipFamily>) -> Result<
             if extension == "pdsc" {
                 log::info!("Found .pdsc file: {}", path.display());
 
+                let pdsc = fs::read_to_string(&entry_path)?;
                 handle_package::<std::fs::File>(
-                    Package::from_path(&entry.path())?,
+                    Package::from_string(&pdsc)?,
+                    &debug_descriptions(&pdsc)?,
                     Kind::Directory(path),
                     families,
                 )
@@ -209,6 +227,19 @@ pub(crate) fn visit_dirs(path: &Path, families: &mut Vec<ChipFamily>) -> Result<
     Ok(())
 }
 
+/// Add the devices of a single .pdsc file, with the flash algorithms relative to its directory.
+pub(crate) fn visit_pdsc(path: &Path, families: &mut Vec<ChipFamily>) -> Result<()> {
+    let directory = path.parent().unwrap_or_else(|| Path::new("."));
+
+    let pdsc = fs::read_to_string(path)?;
+    handle_package::<std::fs::File>(
+        Package::from_string(&pdsc)?,
+        &debug_descriptions(&pdsc)?,
+        Kind::Directory(directory),
+        families,
+    )
+}
+
 pub(crate) fn visit_file(path: &Path, families: &mut Vec<ChipFamily>) -> Result<()> {
     log::info!("Trying to open pack file: {}.", path.display());
     // If we get a file, try to unpack it.
@@ -233,10 +264,16 @@ pub(crate) fn visit_file(path: &Path, families: &mut Vec<ChipFamily>) -> Result<
 
     drop(pdsc_file);
 
-    handle_package(package, Kind::Archive(&mut archive), families)
+    let debug_descriptions = debug_descriptions(&pdsc)?;
+    handle_package(
+        package,
+        &debug_descriptions,
+        Kind::Archive(&mut archive),
+        families,
+    )
 }
 
-pub(crate) fn visit_arm_files(families: &mut Vec<ChipFamily>) -> Result<()> {
+pub fn visit_arm_files(families: &mut Vec<ChipFamily>) -> Result<()> {
     let packs = crate::fetch::get_vidx()?;
     Builder::new_multi_thread()
         .enable_all()
@@ -336,9 +373,23 @@ pub(crate) async fn visit_arm_file(pack: &PdscRef) -> Vec<ChipFamily> {
 
     drop(pdsc_file);
 
+    let debug_descriptions = debug_descriptions(&pdsc).unwrap_or_else(|error| {
+        log::warn!(
+            "Ignoring the debug sequences of CMSIS Pack {}: {:?}",
+            &url,
+            error
+        );
+        HashMap::new()
+    });
+
     let mut families = vec![];
 
-    match handle_package(package, Kind::Archive(&mut archive), &mut families) {
+    match handle_package(
+        package,
+        &debug_descriptions,
+        Kind::Archive(&mut archive),
+        &mut families,
+    ) {
         Ok(_) => {}
         Err(err) => log::error!("Something went wrong while handling pack {}: {}", url, err),
     };
@@ -0,0 +1,50 @@
+//! Generation of probe-rs target descriptions from CMSIS-Packs and from ELF files containing a flash algorithm.
+
+pub mod algorithm_binary;
+pub mod fetch;
+pub mod flash_device;
+pub mod generate;
+pub mod parser;
+mod sequences;
+
+use std::{ffi::OsStr, path::Path};
+
+use anyhow::{ensure, Context, Result};
+use probe_rs::config::{ChipFamily, TargetDescriptionSource};
+
+/// Generate the target descriptions of all devices in a CMSIS-Pack.
+///
+/// `input` is either a pack file, a `.pdsc` file, or a directory containing an unzipped pack.
+pub fn families_from_pack(input: &Path) -> Result<Vec<ChipFamily>> {
+    ensure!(
+        input.exists(),
+        "No such file or directory: {}",
+        input.display()
+    );
+
+    let mut families = Vec::<ChipFamily>::new();
+
+    if input.is_dir() {
+        // Look for the .pdsc file in the given dir and it's child directories.
+        generate::visit_dirs(input, &mut families)
+            .context("Failed to generate target configuration.")?;
+
+        // Check that we found at least a single .pdsc file
+        ensure!(
+            !families.is_empty(),
+            "Unable to find any .pdsc files in the provided input directory."
+        );
+    } else if input.extension() == Some(OsStr::new("pdsc")) {
+        generate::visit_pdsc(input, &mut families)
+            .context(format!("Failed to process file {}.", input.display()))?;
+    } else {
+        generate::visit_file(input, &mut families)
+            .context(format!("Failed to process file {}.", input.display()))?;
+    }
+
+    for family in &mut families {
+        family.source = TargetDescriptionSource::External;
+    }
+
+    Ok(families)
+}
@@ -1,16 +1,10 @@
-pub mod algorithm_binary;
-pub mod fetch;
-pub mod flash_device;
-pub mod generate;
-pub mod parser;
-
 use std::{
     fs::{create_dir, File, OpenOptions},
     io::Write,
     path::{Path, PathBuf},
 };
 
-use anyhow::{bail, ensure, Context, Result};
+use anyhow::{bail, Context, Result};
 use clap::Parser;
 use probe_rs::{
     config::{
@@ -22,7 +16,7 @@ use probe_rs::{
 use probe_rs_target::{ArmCoreAccessOptions, CoreAccessOptions};
 use simplelog::*;
 
-use parser::extract_flash_algo;
+use target_gen::{families_from_pack, generate, parser::extract_flash_algo};
 
 #[derive(clap::Parser)]
 enum TargetGen {
@@ -31,7 +25,7 @@ enum TargetGen {
         #[clap(
             name = "INPUT",
             parse(from_os_str),
-            help = "A Pack file or the unziped Pack directory."
+            help = "A Pack file, a .pdsc file or the unziped Pack directory."
         )]
         input: PathBuf,
         #[clap(
@@ -187,8 +181,12 @@ fn cmd_elf(
                     }),
                 ],
                 flash_algorithms: vec![algorithm_name],
+                connect_under_reset: None,
+                rtt_scan_ranges: vec![],
             }],
             flash_algorithms: vec![algorithm],
+            debug_vars: None,
+            debug_sequences: vec![],
             source: BuiltIn,
         };
 
@@ -216,15 +214,11 @@ fn cmd_elf(
 }
 
 /// Handle the pack subcommand. `input` is either the path
-/// to a CMSIS-Pack file, or a directory containing at least one .pdsc file.
+/// to a CMSIS-Pack file or a .pdsc file, or a directory containing at least one .pdsc file.
 ///
 /// The generated target description will be placed in `out_dir`.
 fn cmd_pack(input: &Path, out_dir: &Path) -> Result<()> {
-    ensure!(
-        input.exists(),
-        "No such file or directory: {}",
-        input.display()
-    );
+    let families = families_from_pack(input)?;
 
     if !out_dir.exists() {
         create_dir(&out_dir).context(format!(
@@ -233,23 +227,6 @@ fn cmd_pack(input: &Path, out_dir: &Path) -> Result<()> {
         ))?;
     }
 
-    let mut families = Vec::<ChipFamily>::new();
-
-    if input.is_file() {
-        generate::visit_file(input, &mut families)
-            .context(format!("Failed to process file {}.", input.display()))?;
-    } else {
-        // Look for the .pdsc file in the given dir and it's child directories.
-        generate::visit_dirs(input, &mut families)
-            .context("Failed to generate target configuration.")?;
-
-        // Check that we found at least a single .pdsc file
-        ensure!(
-            !families.is_empty(),
-            "Unable to find any .pdsc files in the provided input directory."
-        );
-    }
-
     let mut generated_files = Vec::with_capacity(families.len());
 
     for family in &families {
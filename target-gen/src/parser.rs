@@ -89,6 +89,7 @@ pub fn extract_flash_algo(
             "Init" => algo.pc_init = Some(sym.st_value - code_section_offset as u64),
             "UnInit" => algo.pc_uninit = Some(sym.st_value - code_section_offset as u64),
             "EraseChip" => algo.pc_erase_all = Some(sym.st_value - code_section_offset as u64),
+            "BlankCheck" => algo.pc_blank_check = Some(sym.st_value - code_section_offset as u64),
             "EraseSector" => algo.pc_erase_sector = sym.st_value - code_section_offset as u64,
             "ProgramPage" => algo.pc_program_page = sym.st_value - code_section_offset as u64,
             _ => {}
@@ -0,0 +1,170 @@
+//! Extraction of the debug sequences from the debug description of a .pdsc file.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Context, Result};
+use minidom::Element;
+use probe_rs_target::{DebugSequenceDescription, SequenceBlock, SequenceControl, SequenceElement};
+
+/// The debug description of a device family.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct DebugDescription {
+    /// The contents of the `<debugvars>` element.
+    pub(crate) debug_vars: Option<String>,
+    /// The enabled sequences of the `<sequences>` element.
+    pub(crate) sequences: Vec<DebugSequenceDescription>,
+}
+
+/// Extract the debug descriptions of all device families in a .pdsc file, by the name of the family.
+///
+/// Debug descriptions of sub-families and devices are not supported, they are ignored.
+pub(crate) fn debug_descriptions(pdsc: &str) -> Result<HashMap<String, DebugDescription>> {
+    let root: Element = pdsc
+        .parse()
+        .map_err(|e| anyhow!("Failed to parse the .pdsc file: {}", e))?;
+
+    let mut descriptions = HashMap::new();
+
+    let families = root
+        .children()
+        .filter(|element| element.name() == "devices")
+        .flat_map(|devices| devices.children())
+        .filter(|element| element.name() == "family");
+
+    for family in families {
+        let name = match family.attr("Dfamily") {
+            Some(name) => name,
+            None => continue,
+        };
+
+        let mut description = DebugDescription::default();
+        for element in family.children() {
+            match element.name() {
+                "debugvars" => description.debug_vars = Some(element.text()),
+                "sequences" => {
+                    for sequence in element.children().filter(|e| e.name() == "sequence") {
+                        if let Some(sequence) = parse_sequence(sequence)
+                            .with_context(|| format!("Invalid debug sequence in family {}", name))?
+                        {
+                            description.sequences.push(sequence);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if description != DebugDescription::default() {
+            descriptions.insert(name.to_string(), description);
+        }
+    }
+
+    Ok(descriptions)
+}
+
+/// Parse a `<sequence>` element, returns `None` if it is disabled or specific to a processor.
+fn parse_sequence(sequence: &Element) -> Result<Option<DebugSequenceDescription>> {
+    let name = sequence
+        .attr("name")
+        .ok_or_else(|| anyhow!("A sequence has no name"))?;
+
+    if sequence.attr("disable").map(parse_number).transpose()? == Some(1) {
+        return Ok(None);
+    }
+    if sequence.attr("Pname").is_some() {
+        log::warn!(
+            "Ignoring the processor specific debug sequence {}, this is not supported",
+            name
+        );
+        return Ok(None);
+    }
+
+    Ok(Some(DebugSequenceDescription {
+        name: name.to_string(),
+        body: parse_elements(sequence)?,
+    }))
+}
+
+fn parse_elements(parent: &Element) -> Result<Vec<SequenceElement>> {
+    parent
+        .children()
+        .filter_map(|element| match element.name() {
+            "block" => Some(Ok(SequenceElement::Block(SequenceBlock {
+                atomic: element.attr("atomic") == Some("1"),
+                code: element.text(),
+            }))),
+            "control" => Some(parse_control(element).map(SequenceElement::Control)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn parse_control(control: &Element) -> Result<SequenceControl> {
+    Ok(SequenceControl {
+        condition: control.attr("if").map(str::to_string),
+        repeat_while: control.attr("while").map(str::to_string),
+        timeout: control.attr("timeout").map(parse_number).transpose()?,
+        body: parse_elements(control)?,
+    })
+}
+
+fn parse_number(value: &str) -> Result<u64> {
+    let value = value.trim();
+    match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => value.parse(),
+    }
+    .with_context(|| format!("Invalid number {}", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_family_sequences() {
+        let pdsc = r#"<?xml version="1.0" encoding="UTF-8"?>
+<package schemaVersion="1.4">
+  <devices>
+    <family Dfamily="Example Series" Dvendor="Example:0">
+      <debugvars configfile="example.dbgconf">
+        __var Unlock = 1;
+      </debugvars>
+      <sequences>
+        <sequence name="ResetSystem">
+          <block>
+            Write32(0xE000ED0C, 0x05FA0004);
+          </block>
+          <control while="(Read32(0xE000EDF0) &amp; 0x02000000) == 0" timeout="500000"/>
+        </sequence>
+        <sequence name="TraceStart" disable="1"/>
+      </sequences>
+    </family>
+  </devices>
+</package>"#;
+
+        let descriptions = debug_descriptions(pdsc).unwrap();
+        let description = &descriptions["Example Series"];
+
+        assert_eq!(
+            description.debug_vars.as_deref().map(str::trim),
+            Some("__var Unlock = 1;")
+        );
+        assert_eq!(description.sequences.len(), 1);
+
+        let sequence = &description.sequences[0];
+        assert_eq!(sequence.name, "ResetSystem");
+        assert_eq!(
+            sequence.body[1],
+            SequenceElement::Control(SequenceControl {
+                condition: None,
+                repeat_while: Some("(Read32(0xE000EDF0) & 0x02000000) == 0".to_string()),
+                timeout: Some(500_000),
+                body: vec![],
+            })
+        );
+    }
+}
@@ -29,3 +29,14 @@ fn extract_target_specs() {
         "Generated 4 target definition(s):",
     ));
 }
+
+#[test]
+fn generate_families_in_memory() {
+    let families =
+        target_gen::families_from_pack(std::path::Path::new(NORDIC_SAMPLE_PACK)).unwrap();
+
+    assert_eq!(families.len(), 4);
+    assert!(families
+        .iter()
+        .all(|family| family.source == probe_rs::config::TargetDescriptionSource::External));
+}